@@ -726,6 +726,12 @@ export function usePageEdit() {
         sessionStorage.setItem(tokenKey, nextToken);
       }
     } catch (err: unknown) {
+      if (isRequestError(err) && err.status === 409) {
+        clearLockToken();
+        errorMessage.value =
+          '管理者によりロックが強制解除されました。編集内容は保存できますが、他の利用者の更新を上書きする可能性があります。';
+        return;
+      }
       reportError(err);
     }
   }
@@ -130,12 +130,17 @@ export function resolveAssetUrl(pagePath: string, rawSpec: string): string | nul
     return null;
   }
 
+  const fileName = decodeIfEncoded(parsed.file);
+  if (parsed.path === '') {
+    const params = new URLSearchParams({ file: fileName });
+    return `/api/assets?${params.toString()}`;
+  }
+
   const resolvedPath = resolvePagePath(pagePath, parsed.path);
   if (!resolvedPath) {
     return null;
   }
 
-  const fileName = decodeIfEncoded(parsed.file);
   const params = new URLSearchParams({
     path: resolvedPath,
     file: fileName,
@@ -0,0 +1,293 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 画像アセットの寸法取得と縮小版生成を提供するモジュール
+//!
+//! デコード・エンコードは pure Rust 実装のみを使用し、ネイティブライブラリ
+//! を持たない環境でも動作する。
+//!
+
+use std::fs::File;
+use std::io::{BufReader, Cursor};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{ImageFormat, ImageReader};
+
+/// 縮小版として生成する幅(ピクセル)の候補
+///
+/// 要求された幅はこの候補のうち要求値以上で最小のものへ切り上げる。キャッ
+/// シュの組み合わせ数を抑えるため、任意の幅では生成しない。
+pub(crate) const VARIANT_WIDTHS: [u32; 7] = [64, 160, 320, 640, 960, 1280, 1920];
+
+/// JPEG で出力する縮小版の品質
+const JPEG_QUALITY: u8 = 85;
+
+///
+/// 縮小版の出力形式
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum VariantFormat {
+    /// JPEG 形式
+    Jpeg,
+
+    /// PNG 形式
+    Png,
+}
+
+impl VariantFormat {
+    ///
+    /// 元画像のMIME種別からの出力形式の決定
+    ///
+    /// # 引数
+    /// * `mime` - 元画像のMIME種別
+    ///
+    /// # 戻り値
+    /// JPEG 画像の場合は JPEG、それ以外は透過を保持できる PNG を返す。
+    ///
+    pub(crate) fn for_mime(mime: &str) -> Self {
+        if mime == "image/jpeg" {
+            Self::Jpeg
+        } else {
+            Self::Png
+        }
+    }
+
+    ///
+    /// MIME種別へのアクセサ
+    ///
+    /// # 戻り値
+    /// 出力形式のMIME種別を返す。
+    ///
+    pub(crate) fn mime(&self) -> &'static str {
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Png => "image/png",
+        }
+    }
+
+    ///
+    /// 拡張子へのアクセサ
+    ///
+    /// # 戻り値
+    /// キャッシュファイルに付与する拡張子を返す。
+    ///
+    pub(crate) fn extension(&self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+        }
+    }
+}
+
+///
+/// 縮小版を生成可能なMIME種別かの判定
+///
+/// # 引数
+/// * `mime` - 判定対象のMIME種別
+///
+/// # 戻り値
+/// 縮小版を生成可能な場合は`true`を返す。
+///
+pub(crate) fn is_resizable_mime(mime: &str) -> bool {
+    image_format(mime).is_some()
+}
+
+///
+/// 要求幅の切り上げ
+///
+/// # 引数
+/// * `width` - 要求された幅
+///
+/// # 戻り値
+/// `VARIANT_WIDTHS`のうち要求値以上で最小の幅を返す。要求値が最大候補を
+/// 超える場合は最大候補を返す。
+///
+pub(crate) fn snap_variant_width(width: u32) -> u32 {
+    VARIANT_WIDTHS
+        .iter()
+        .copied()
+        .find(|candidate| *candidate >= width)
+        .unwrap_or(VARIANT_WIDTHS[VARIANT_WIDTHS.len() - 1])
+}
+
+///
+/// 画像寸法の取得
+///
+/// # 引数
+/// * `mime` - アセットのMIME種別
+/// * `data` - アセットデータ
+///
+/// # 戻り値
+/// 対応する画像の場合は`(幅, 高さ)`を返す。画像でない場合や解析できない
+/// 場合は`None`を返す。
+///
+/// # 注記
+/// ヘッダ部分のみを解析し、画素データのデコードは行わない。
+///
+pub(crate) fn probe_dimensions(mime: &str, data: &[u8]) -> Option<(u32, u32)> {
+    let format = image_format(mime)?;
+    ImageReader::with_format(Cursor::new(data), format)
+        .into_dimensions()
+        .ok()
+}
+
+///
+/// ファイルからの画像寸法の取得
+///
+/// # 引数
+/// * `mime` - アセットのMIME種別
+/// * `path` - アセットデータを格納したファイルのパス
+///
+/// # 戻り値
+/// 対応する画像の場合は`(幅, 高さ)`を返す。画像でない場合や解析できない
+/// 場合は`None`を返す。
+///
+/// # 注記
+/// ファイル全体は読み込まず、ヘッダ部分のみを解析する。
+///
+pub(crate) fn probe_file_dimensions(
+    mime: &str,
+    path: &Path,
+) -> Option<(u32, u32)> {
+    let format = image_format(mime)?;
+    let file = File::open(path).ok()?;
+    ImageReader::with_format(BufReader::new(file), format)
+        .into_dimensions()
+        .ok()
+}
+
+///
+/// 縮小版の生成
+///
+/// # 引数
+/// * `mime` - 元画像のMIME種別
+/// * `data` - 元画像のデータ
+/// * `width` - 縮小後の幅
+///
+/// # 戻り値
+/// 縮小版のデータを返す。元画像の幅が`width`以下の場合は縮小の必要が無い
+/// ため`None`を返す。
+///
+pub(crate) fn render_variant(
+    mime: &str,
+    data: &[u8],
+    width: u32,
+) -> Result<Option<Vec<u8>>> {
+    let format = image_format(mime)
+        .with_context(|| format!("unsupported image type: {}", mime))?;
+
+    /*
+     * デコード
+     */
+    let image = ImageReader::with_format(Cursor::new(data), format)
+        .decode()
+        .context("decode image failed")?;
+
+    if image.width() <= width {
+        return Ok(None);
+    }
+
+    /*
+     * アスペクト比を保持した縮小
+     */
+    let height = ((image.height() as u64 * width as u64)
+        / image.width() as u64)
+        .max(1) as u32;
+    let resized = image.resize_exact(width, height, FilterType::Triangle);
+
+    /*
+     * エンコード
+     */
+    let mut buf = Vec::new();
+    match VariantFormat::for_mime(mime) {
+        VariantFormat::Jpeg => {
+            let rgb = resized.to_rgb8();
+            JpegEncoder::new_with_quality(&mut buf, JPEG_QUALITY)
+                .encode_image(&rgb)
+                .context("encode jpeg failed")?;
+        }
+
+        VariantFormat::Png => {
+            resized
+                .write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)
+                .context("encode png failed")?;
+        }
+    }
+
+    Ok(Some(buf))
+}
+
+///
+/// MIME種別から画像形式への変換
+///
+/// # 引数
+/// * `mime` - MIME種別
+///
+/// # 戻り値
+/// 対応する画像形式を返す。未対応の場合は`None`を返す。
+///
+fn image_format(mime: &str) -> Option<ImageFormat> {
+    match mime {
+        "image/png" => Some(ImageFormat::Png),
+        "image/jpeg" => Some(ImageFormat::Jpeg),
+        "image/gif" => Some(ImageFormat::Gif),
+        "image/webp" => Some(ImageFormat::WebP),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use image::{DynamicImage, RgbImage};
+
+    ///
+    /// 指定寸法の PNG データを生成する。
+    ///
+    fn build_png(width: u32, height: u32) -> Vec<u8> {
+        let image = DynamicImage::ImageRgb8(RgbImage::new(width, height));
+        let mut buf = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)
+            .expect("encode png failed");
+        buf
+    }
+
+    ///
+    /// 寸法取得と縮小版生成がアスペクト比を保持することを確認する。
+    ///
+    #[test]
+    fn render_variant_keeps_aspect_ratio() {
+        let data = build_png(800, 400);
+        assert_eq!(probe_dimensions("image/png", &data), Some((800, 400)));
+        assert_eq!(probe_dimensions("text/plain", &data), None);
+
+        let variant = render_variant("image/png", &data, 320)
+            .expect("render variant failed")
+            .expect("variant missing");
+        assert_eq!(probe_dimensions("image/png", &variant), Some((320, 160)));
+
+        assert!(render_variant("image/png", &data, 960)
+            .expect("render variant failed")
+            .is_none());
+    }
+
+    ///
+    /// 要求幅が候補幅へ切り上げられることを確認する。
+    ///
+    #[test]
+    fn snap_variant_width_rounds_up_to_candidate() {
+        assert_eq!(snap_variant_width(1), 64);
+        assert_eq!(snap_variant_width(320), 320);
+        assert_eq!(snap_variant_width(321), 640);
+        assert_eq!(snap_variant_width(10000), 1920);
+    }
+}
@@ -322,8 +322,10 @@ mod tests {
     ) -> AuditRecord {
         AuditRecord::new(
             AuditOperation::Append,
-            UserId::from_string("01J00000000000000000000000")
-                .expect("user id"),
+            Some(
+                UserId::from_string("01J00000000000000000000000")
+                    .expect("user id"),
+            ),
             Some(
                 TokenId::from_string("01J00000000000000000000001")
                     .expect("token id"),
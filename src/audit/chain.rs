@@ -0,0 +1,673 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 監査ログのハッシュチェーンを定義するモジュール
+//!
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::model::AuditRecord;
+use super::query::list_audit_log_files;
+
+/// チェーン先頭情報を保持するファイル名
+const CHAIN_HEAD_FILE_NAME: &str = "audit.head.json";
+
+/// チェーン先頭情報の一時ファイル名
+const CHAIN_HEAD_TEMP_FILE_NAME: &str = "audit.head.json.tmp";
+
+/// チェーン起点の直前ハッシュ
+pub(crate) const GENESIS_HASH: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+///
+/// ハッシュチェーンの先頭(最後に書き込んだ行)の情報
+///
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct ChainHead {
+    /// 最後に書き込んだ行の通番
+    pub(crate) seq: u64,
+
+    /// 最後に書き込んだ行の SHA-256
+    pub(crate) hash: String,
+}
+
+impl ChainHead {
+    ///
+    /// チェーン起点の先頭情報を返す
+    ///
+    /// # 戻り値
+    /// 通番 0 、直前ハッシュが起点値の先頭情報を返す。
+    ///
+    pub(crate) fn genesis() -> Self {
+        Self {
+            seq: 0,
+            hash: GENESIS_HASH.to_string(),
+        }
+    }
+}
+
+///
+/// チェーン管理用ファイルであるかを判定する
+///
+/// # 引数
+/// * `file_name` - 判定対象ファイル名
+///
+/// # 戻り値
+/// チェーン先頭情報(一時ファイルを含む)の場合は`true`を返す。
+///
+pub(crate) fn is_chain_file_name(file_name: &str) -> bool {
+    file_name == CHAIN_HEAD_FILE_NAME || file_name == CHAIN_HEAD_TEMP_FILE_NAME
+}
+
+///
+/// JSONL 1 行のハッシュ値を計算する
+///
+/// # 引数
+/// * `line` - 末尾 LF を含まない行データ
+///
+/// # 戻り値
+/// SHA-256 の16進文字列を返す。
+///
+pub(crate) fn line_hash(line: &[u8]) -> String {
+    Sha256::digest(line)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+///
+/// チェーン先頭情報を読み込む
+///
+/// # 引数
+/// * `output_dir` - 監査ログ出力ディレクトリ
+///
+/// # 戻り値
+/// 先頭情報ファイルが存在する場合は内容を返す。
+///
+pub(crate) fn load_chain_head(output_dir: &Path) -> Result<Option<ChainHead>> {
+    let path = output_dir.join(CHAIN_HEAD_FILE_NAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let text = fs::read_to_string(&path).with_context(|| {
+        format!("read audit chain head failed: {}", path.display())
+    })?;
+    let head = serde_json::from_str(&text).with_context(|| {
+        format!("parse audit chain head failed: {}", path.display())
+    })?;
+
+    Ok(Some(head))
+}
+
+///
+/// チェーン先頭情報を保存する
+///
+/// # 引数
+/// * `output_dir` - 監査ログ出力ディレクトリ
+/// * `head` - 保存する先頭情報
+///
+/// # 戻り値
+/// 保存に成功した場合は`Ok(())`を返す。
+///
+/// # 注記
+/// 一時ファイルへ書き出してから置き換えることで、書込途中の内容が
+/// 残らないようにする。
+///
+pub(crate) fn store_chain_head(output_dir: &Path, head: &ChainHead) -> Result<()> {
+    let temp_path = output_dir.join(CHAIN_HEAD_TEMP_FILE_NAME);
+    let path = output_dir.join(CHAIN_HEAD_FILE_NAME);
+    let text = serde_json::to_string(head)
+        .context("audit chain head encode failed")?;
+
+    fs::write(&temp_path, text).with_context(|| {
+        format!("write audit chain head failed: {}", temp_path.display())
+    })?;
+    fs::rename(&temp_path, &path).with_context(|| {
+        format!("replace audit chain head failed: {}", path.display())
+    })?;
+
+    Ok(())
+}
+
+///
+/// 書込再開時のチェーン先頭を復元する
+///
+/// # 引数
+/// * `output_dir` - 監査ログ出力ディレクトリ
+///
+/// # 戻り値
+/// 続けて書き込む行が参照すべきチェーン先頭を返す。
+///
+/// # 注記
+/// ログファイル末尾の行と先頭情報ファイルのうち、通番が進んでいる方を
+/// 採用する。ログ末尾が切り詰められていても先頭情報ファイルの値から
+/// 連結するため、切り詰めは検証時に欠落として検出される。
+///
+pub(crate) fn recover_chain_head(output_dir: &Path) -> Result<ChainHead> {
+    /*
+     * ログファイル末尾の行の取得
+     */
+    let mut disk_head = None;
+    for path in list_audit_log_files(output_dir)?.iter().rev() {
+        if let Some(line) = last_line(path)? {
+            let seq = serde_json::from_str::<AuditRecord>(&line)
+                .ok()
+                .and_then(|record| record.seq)
+                .unwrap_or(0);
+            disk_head = Some(ChainHead {
+                seq,
+                hash: line_hash(line.as_bytes()),
+            });
+            break;
+        }
+    }
+
+    /*
+     * 先頭情報ファイルとの比較
+     */
+    let head = match (disk_head, load_chain_head(output_dir)?) {
+        (Some(disk), Some(stored)) if stored.seq > disk.seq => stored,
+        (Some(disk), _) => disk,
+        (None, Some(stored)) => stored,
+        (None, None) => ChainHead::genesis(),
+    };
+
+    Ok(head)
+}
+
+///
+/// ファイル末尾の行のハッシュ値を計算する
+///
+/// # 引数
+/// * `path` - 対象ファイル
+///
+/// # 戻り値
+/// 空でない行が存在する場合は最後の行のハッシュ値を返す。
+///
+pub(crate) fn last_line_hash(path: &Path) -> Result<Option<String>> {
+    Ok(last_line(path)?.map(|line| line_hash(line.as_bytes())))
+}
+
+///
+/// ファイル末尾の空でない行を取得する
+///
+/// # 引数
+/// * `path` - 対象ファイル
+///
+/// # 戻り値
+/// 空でない行が存在する場合は最後の行を返す。
+///
+fn last_line(path: &Path) -> Result<Option<String>> {
+    let file = File::open(path).with_context(|| {
+        format!("open audit log failed: {}", path.display())
+    })?;
+    let mut last = None;
+
+    for line in BufReader::new(file).lines() {
+        let line = line.with_context(|| {
+            format!("read audit log failed: {}", path.display())
+        })?;
+        if !line.trim().is_empty() {
+            last = Some(line);
+        }
+    }
+
+    Ok(last)
+}
+
+///
+/// チェーン検証で検出した問題の分類
+///
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ChainIssueKind {
+    /// JSON として解析できない行
+    Malformed,
+
+    /// 直前の行が書き換えられている
+    Modified,
+
+    /// 行が欠落している
+    Gap,
+
+    /// チェーン開始後にチェーン情報を持たない行がある
+    Unchained,
+
+    /// ログ末尾が切り詰められている
+    Truncated,
+
+    /// 先頭情報ファイルとログ末尾が一致しない
+    HeadMismatch,
+}
+
+impl ChainIssueKind {
+    ///
+    /// 表示用の分類名を返す
+    ///
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Malformed => "malformed",
+            Self::Modified => "modified",
+            Self::Gap => "gap",
+            Self::Unchained => "unchained",
+            Self::Truncated => "truncated",
+            Self::HeadMismatch => "head_mismatch",
+        }
+    }
+}
+
+///
+/// チェーン検証で検出した問題
+///
+#[derive(Clone, Debug)]
+pub(crate) struct ChainIssue {
+    /// 問題の分類
+    pub(crate) kind: ChainIssueKind,
+
+    /// 問題を検出したファイル
+    pub(crate) path: Option<PathBuf>,
+
+    /// 問題を検出した行番号(1起点)
+    pub(crate) line: Option<usize>,
+
+    /// 詳細
+    pub(crate) detail: String,
+}
+
+///
+/// チェーン検証の結果
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ChainVerifyReport {
+    /// 検証したファイル数
+    pub(crate) files: usize,
+
+    /// 検証したチェーン付きレコード数
+    pub(crate) records: usize,
+
+    /// チェーン導入前のレコード数
+    pub(crate) legacy_records: usize,
+
+    /// 検出した問題
+    pub(crate) issues: Vec<ChainIssue>,
+}
+
+impl ChainVerifyReport {
+    ///
+    /// 問題が検出されなかったかを返す
+    ///
+    pub(crate) fn is_intact(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+///
+/// 検証中の行の情報
+///
+struct ChainLine {
+    /// 行を含むファイル
+    path: PathBuf,
+
+    /// 行番号(1起点)
+    line: usize,
+
+    /// 行の内容
+    text: String,
+}
+
+///
+/// 監査ログのハッシュチェーンを検証する
+///
+/// # 引数
+/// * `output_dir` - 監査ログ出力ディレクトリ
+///
+/// # 戻り値
+/// 検証結果を返す。
+///
+/// # 注記
+/// ローテーション済みファイルを古い順に、最後にアクティブファイルを連結した
+/// 1本のチェーンとして検証する。最初の行の直前ハッシュは起点値か、
+/// `retention` レコードが記録した削除済み末尾のハッシュの何れかでなければ
+/// ならない。ログ末尾は先頭情報ファイルと照合して切り詰めを検出する。
+///
+pub(crate) fn verify_audit_chain(output_dir: &Path) -> Result<ChainVerifyReport> {
+    let mut report = ChainVerifyReport::default();
+    let files = list_audit_log_files(output_dir)?;
+    report.files = files.len();
+
+    /*
+     * 全行の読み込みと保持削除アンカーの収集
+     */
+    let mut lines = Vec::new();
+    for path in &files {
+        let file = File::open(path).with_context(|| {
+            format!("open audit log failed: {}", path.display())
+        })?;
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let text = line.with_context(|| {
+                format!("read audit log failed: {}", path.display())
+            })?;
+            if text.trim().is_empty() {
+                continue;
+            }
+            lines.push(ChainLine {
+                path: path.clone(),
+                line: index + 1,
+                text,
+            });
+        }
+    }
+
+    let anchors: HashSet<String> = lines
+        .iter()
+        .filter_map(|line| serde_json::from_str::<AuditRecord>(&line.text).ok())
+        .filter_map(|record| record.chain_anchor)
+        .collect();
+
+    /*
+     * 各行の直前ハッシュと通番の照合
+     */
+    let mut prev_hash: Option<String> = None;
+    let mut prev_seq: Option<u64> = None;
+    let mut chained = false;
+
+    for line in &lines {
+        let hash = line_hash(line.text.as_bytes());
+        let record = match serde_json::from_str::<AuditRecord>(&line.text) {
+            Ok(record) => record,
+            Err(err) => {
+                report.issues.push(issue(
+                    ChainIssueKind::Malformed,
+                    line,
+                    format!("invalid audit record: {}", err),
+                ));
+                prev_hash = Some(hash);
+                prev_seq = None;
+                continue;
+            }
+        };
+
+        let Some(record_prev_hash) = record.prev_hash.as_deref() else {
+            if chained {
+                report.issues.push(issue(
+                    ChainIssueKind::Unchained,
+                    line,
+                    "record without hash chain after chain start".to_string(),
+                ));
+            } else {
+                report.legacy_records += 1;
+            }
+            prev_hash = Some(hash);
+            prev_seq = None;
+            continue;
+        };
+
+        let linked = match prev_hash.as_deref() {
+            Some(expected) => record_prev_hash == expected,
+            None => {
+                record_prev_hash == GENESIS_HASH
+                    || anchors.contains(record_prev_hash)
+            }
+        };
+
+        if !linked {
+            let missing = match (prev_seq, record.seq) {
+                (Some(prev), Some(seq)) if seq > prev + 1 => Some(seq - prev - 1),
+                _ => None,
+            };
+            let (kind, detail) = match (prev_hash.is_some(), missing) {
+                (false, _) => (
+                    ChainIssueKind::Gap,
+                    "first record does not continue from the chain start or \
+                     a retention anchor"
+                        .to_string(),
+                ),
+                (true, Some(count)) => (
+                    ChainIssueKind::Gap,
+                    format!("{} record(s) missing before this line", count),
+                ),
+                (true, None) => (
+                    ChainIssueKind::Modified,
+                    "previous record does not match the hash chain".to_string(),
+                ),
+            };
+            report.issues.push(issue(kind, line, detail));
+        }
+
+        chained = true;
+        report.records += 1;
+        prev_hash = Some(hash);
+        prev_seq = record.seq;
+    }
+
+    /*
+     * 先頭情報ファイルとの照合
+     */
+    let head = load_chain_head(output_dir)?;
+    let last = lines.last().map(|line| (line.path.clone(), line.line));
+    let head_issue = match head {
+        None if chained => Some((
+            ChainIssueKind::HeadMismatch,
+            "chain head file is missing".to_string(),
+        )),
+        None => None,
+        Some(head) if !chained => (head.seq > 0).then(|| {
+            (
+                ChainIssueKind::Truncated,
+                format!("{} chained record(s) are missing", head.seq),
+            )
+        }),
+        Some(head) => match prev_seq {
+            Some(seq) if head.seq > seq => Some((
+                ChainIssueKind::Truncated,
+                format!("{} record(s) missing at the end", head.seq - seq),
+            )),
+            Some(seq) if head.seq < seq => Some((
+                ChainIssueKind::HeadMismatch,
+                "chain head file is behind the log".to_string(),
+            )),
+            _ if prev_hash.as_deref() != Some(head.hash.as_str()) => Some((
+                ChainIssueKind::Modified,
+                "last record does not match the chain head".to_string(),
+            )),
+            _ => None,
+        },
+    };
+
+    if let Some((kind, detail)) = head_issue {
+        report.issues.push(ChainIssue {
+            kind,
+            path: last.as_ref().map(|(path, _)| path.clone()),
+            line: last.map(|(_, line)| line),
+            detail,
+        });
+    }
+
+    Ok(report)
+}
+
+///
+/// 行位置付きの問題情報を生成する
+///
+fn issue(kind: ChainIssueKind, line: &ChainLine, detail: String) -> ChainIssue {
+    ChainIssue {
+        kind,
+        path: Some(line.path.clone()),
+        line: Some(line.line),
+        detail,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use chrono::{Duration, Utc};
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::audit::model::{AuditOperation, AuditResult};
+    use crate::audit::retention::AuditRetentionPolicy;
+    use crate::audit::rotation::{AuditRotationPolicy, active_log_path};
+    use crate::audit::writer::{AuditWriter, AuditWriterConfig};
+
+    ///
+    /// 指定件数のレコードを書き込んだ writer を返す。
+    ///
+    fn write_records(dir: &Path, count: usize, rotate_size: u64) -> AuditWriter {
+        let mut writer = AuditWriter::new(AuditWriterConfig {
+            output_dir: dir.to_path_buf(),
+            rotation_policy: AuditRotationPolicy::new(rotate_size),
+        });
+
+        for index in 0..count {
+            writer
+                .write_record(&AuditRecord::new(
+                    AuditOperation::Update,
+                    None,
+                    None,
+                    None,
+                    Some(format!("/page{}", index)),
+                    AuditResult::Success,
+                    Utc::now(),
+                    None,
+                    None,
+                ))
+                .expect("write record failed");
+        }
+        writer.flush().expect("flush failed");
+        writer
+    }
+
+    ///
+    /// アクティブファイルの行を書き換える。
+    ///
+    fn edit_active_lines<F>(dir: &Path, edit: F)
+    where
+        F: FnOnce(&mut Vec<String>),
+    {
+        let path = active_log_path(dir);
+        let text = fs::read_to_string(&path).expect("read active failed");
+        let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+        edit(&mut lines);
+        let mut text = lines.join("\n");
+        text.push('\n');
+        fs::write(&path, text).expect("write active failed");
+    }
+
+    ///
+    /// ローテーションを跨いで書き込んだチェーンが検証に成功し、
+    /// 別の writer から再開しても連続することを確認する。
+    ///
+    #[test]
+    fn verify_audit_chain_accepts_chain_across_rotation() {
+        let dir = tempdir().expect("tempdir failed");
+        write_records(dir.path(), 5, 400);
+        write_records(dir.path(), 3, 400);
+
+        let report = verify_audit_chain(dir.path()).expect("verify failed");
+        assert!(report.is_intact(), "unexpected issues: {:?}", report.issues);
+        assert_eq!(report.records, 8);
+        assert!(report.files > 1);
+    }
+
+    ///
+    /// 行の書き換え・削除・末尾の切り詰めが検出されることを確認する。
+    ///
+    #[test]
+    fn verify_audit_chain_detects_tampering() {
+        /*
+         * 書き換え
+         */
+        let dir = tempdir().expect("tempdir failed");
+        write_records(dir.path(), 4, 1024 * 1024);
+        edit_active_lines(dir.path(), |lines| {
+            lines[1] = lines[1].replace("/page1", "/forged");
+        });
+        let report = verify_audit_chain(dir.path()).expect("verify failed");
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].kind, ChainIssueKind::Modified);
+        assert_eq!(report.issues[0].line, Some(3));
+
+        /*
+         * 途中の行の削除
+         */
+        let dir = tempdir().expect("tempdir failed");
+        write_records(dir.path(), 4, 1024 * 1024);
+        edit_active_lines(dir.path(), |lines| {
+            lines.remove(1);
+        });
+        let report = verify_audit_chain(dir.path()).expect("verify failed");
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].kind, ChainIssueKind::Gap);
+
+        /*
+         * 末尾の切り詰めと、その後の書込
+         */
+        let dir = tempdir().expect("tempdir failed");
+        write_records(dir.path(), 4, 1024 * 1024);
+        edit_active_lines(dir.path(), |lines| {
+            lines.truncate(2);
+        });
+        let report = verify_audit_chain(dir.path()).expect("verify failed");
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].kind, ChainIssueKind::Truncated);
+
+        write_records(dir.path(), 1, 1024 * 1024);
+        let report = verify_audit_chain(dir.path()).expect("verify failed");
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].kind, ChainIssueKind::Gap);
+    }
+
+    ///
+    /// 保持削除で失われた先頭部分はアンカーにより許容され、
+    /// 保持削除を経ずに削除された先頭ファイルは検出されることを確認する。
+    ///
+    #[test]
+    fn verify_audit_chain_follows_retention_anchor() {
+        let dir = tempdir().expect("tempdir failed");
+        let mut writer = write_records(dir.path(), 6, 400);
+        let rotated = list_audit_log_files(dir.path()).expect("list failed");
+        assert!(rotated.len() > 2);
+
+        /*
+         * 保持削除を経ない先頭ファイルの削除
+         */
+        let copy = tempdir().expect("tempdir failed");
+        for entry in fs::read_dir(dir.path()).expect("read dir failed") {
+            let path = entry.expect("entry failed").path();
+            fs::copy(&path, copy.path().join(path.file_name().unwrap()))
+                .expect("copy failed");
+        }
+        fs::remove_file(copy.path().join(rotated[0].file_name().unwrap()))
+            .expect("remove failed");
+        let report = verify_audit_chain(copy.path()).expect("verify failed");
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].kind, ChainIssueKind::Gap);
+
+        /*
+         * 保持削除による削除
+         */
+        let result = writer
+            .apply_retention(
+                &AuditRetentionPolicy::new(Duration::days(30)),
+                Utc::now() + Duration::days(31),
+            )
+            .expect("retention failed");
+        assert!(!result.deleted_files.is_empty());
+
+        let report = verify_audit_chain(dir.path()).expect("verify failed");
+        assert!(report.is_intact(), "unexpected issues: {:?}", report.issues);
+    }
+}
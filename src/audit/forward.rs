@@ -0,0 +1,129 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 監査ログの外部転送先を定義するモジュール
+//!
+
+use std::fmt::Debug;
+
+use anyhow::{anyhow, Result};
+
+use super::model::AuditRecord;
+use super::syslog::{SyslogConfig, SyslogForwarder};
+use super::webhook::{WebhookConfig, WebhookForwarder};
+
+///
+/// 監査レコードの外部転送先が実装するトレイト
+///
+/// # 注記
+/// 転送はローカルファイルへの書込を補うものであり、転送失敗は呼び出し側で
+/// 警告ログの出力にとどめる。
+///
+pub(crate) trait AuditForwarder: Debug + Send + Sync {
+    ///
+    /// ログ出力用の転送先表記を返す
+    ///
+    fn describe(&self) -> String;
+
+    ///
+    /// 監査レコードを転送する
+    ///
+    /// # 引数
+    /// * `record` - 転送する監査レコード
+    ///
+    /// # 戻り値
+    /// 転送(または転送待ちへの投入)に成功した場合は`Ok(())`を返す。
+    ///
+    fn forward(&mut self, record: &AuditRecord) -> Result<()>;
+
+    ///
+    /// 転送待ちのレコードを送出する
+    ///
+    /// # 戻り値
+    /// 送出の要求に成功した場合は`Ok(())`を返す。
+    ///
+    fn flush(&mut self) -> Result<()>;
+}
+
+///
+/// 監査ログの出力先設定
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct AuditOutputConfig {
+    /// ローカルファイルへの出力の有無
+    pub(crate) file: bool,
+
+    /// syslog 転送先
+    pub(crate) syslog: Vec<SyslogConfig>,
+
+    /// webhook 転送先
+    pub(crate) webhook: Vec<WebhookConfig>,
+}
+
+impl AuditOutputConfig {
+    ///
+    /// 出力先設定の検証
+    ///
+    /// # 戻り値
+    /// 設定が妥当な場合は`Ok(())`を返す。
+    ///
+    pub(crate) fn validate(&self) -> Result<()> {
+        if !self.file && self.syslog.is_empty() && self.webhook.is_empty() {
+            return Err(anyhow!(
+                "audit_output must enable at least one output"
+            ));
+        }
+
+        for syslog in &self.syslog {
+            syslog.validate()?;
+        }
+
+        for webhook in &self.webhook {
+            webhook.validate()?;
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// 外部転送先の生成
+    ///
+    /// # 戻り値
+    /// 設定された syslog / webhook 転送先を生成して返す。
+    ///
+    pub(crate) fn build_forwarders(
+        &self,
+    ) -> Result<Vec<Box<dyn AuditForwarder>>> {
+        let mut forwarders: Vec<Box<dyn AuditForwarder>> = Vec::new();
+
+        for syslog in &self.syslog {
+            forwarders.push(Box::new(SyslogForwarder::new(syslog.clone())?));
+        }
+
+        for webhook in &self.webhook {
+            forwarders.push(Box::new(WebhookForwarder::new(webhook.clone())?));
+        }
+
+        Ok(forwarders)
+    }
+}
+
+impl Default for AuditOutputConfig {
+    ///
+    /// 出力先設定の既定値を生成
+    ///
+    /// # 戻り値
+    /// ローカルファイルのみへ出力する設定を返す。
+    ///
+    fn default() -> Self {
+        Self {
+            file: true,
+            syslog: Vec::new(),
+            webhook: Vec::new(),
+        }
+    }
+}
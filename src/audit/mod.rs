@@ -11,10 +11,15 @@
 #![allow(unused_imports)]
 
 pub(crate) mod buffer;
+pub(crate) mod chain;
+pub(crate) mod forward;
 pub(crate) mod model;
+pub(crate) mod query;
 pub(crate) mod retention;
 pub(crate) mod rotation;
 pub(crate) mod sink;
+pub(crate) mod syslog;
+pub(crate) mod webhook;
 pub(crate) mod writer;
 
 pub(crate) use sink::AuditSink;
@@ -9,7 +9,9 @@
 //!
 
 use std::net::IpAddr;
+use std::str::FromStr;
 
+use anyhow::{anyhow, Error};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -64,6 +66,66 @@ pub(crate) enum AuditOperation {
 
     /// ページリネーム
     Rename,
+
+    /// Bearerトークン発行
+    TokenCreate,
+
+    /// Bearerトークン設定変更
+    TokenUpdate,
+
+    /// Bearerトークン失効
+    TokenRevoke,
+
+    /// Bearerトークンのローテーション
+    TokenRotate,
+
+    /// 管理者によるユーザ作成
+    UserCreate,
+
+    /// 管理者によるユーザ情報変更
+    UserUpdate,
+
+    /// 管理者によるオンラインバックアップ
+    Backup,
+
+    /// ページ削除
+    Delete,
+
+    /// 削除済みページの復帰
+    Undelete,
+
+    /// ページのロールバック
+    Rollback,
+
+    /// ページ履歴のコンパクション
+    Compact,
+
+    /// アセットのアップロード(新規リビジョンの追加を含む)
+    AssetUpload,
+
+    /// アセット削除
+    AssetDelete,
+
+    /// ページロックの取得
+    LockAcquire,
+
+    /// ロック保持者によるページロックの解除
+    LockRelease,
+
+    /// ページロックの強制解除
+    LockBreak,
+
+    /// 共同編集セッションへの参加
+    CollabJoin,
+
+    /// ログイン(Basic認証)
+    Login,
+
+    /// 管理者によるユーザ削除
+    UserDelete,
+
+    /// 保持期間超過による監査ログファイルの削除
+    Retention,
 }
 
 impl AuditOperation {
@@ -87,10 +149,49 @@ impl AuditOperation {
             Self::Update => "update",
             Self::Append => "append",
             Self::Rename => "rename",
+            Self::TokenCreate => "token_create",
+            Self::TokenUpdate => "token_update",
+            Self::TokenRevoke => "token_revoke",
+            Self::TokenRotate => "token_rotate",
+            Self::UserCreate => "user_create",
+            Self::UserUpdate => "user_update",
+            Self::Backup => "backup",
+            Self::Delete => "delete",
+            Self::Undelete => "undelete",
+            Self::Rollback => "rollback",
+            Self::Compact => "compact",
+            Self::AssetUpload => "asset_upload",
+            Self::AssetDelete => "asset_delete",
+            Self::LockAcquire => "lock_acquire",
+            Self::LockRelease => "lock_release",
+            Self::LockBreak => "lock_break",
+            Self::CollabJoin => "collab_join",
+            Self::Login => "login",
+            Self::UserDelete => "user_delete",
+            Self::Retention => "retention",
         }
     }
 }
 
+// FromStrトレイトの実装
+impl FromStr for AuditOperation {
+    type Err = Error;
+
+    ///
+    /// 永続化時の操作名からの変換
+    ///
+    /// # 引数
+    /// * `s` - 小文字スネークケースの操作名
+    ///
+    /// # 戻り値
+    /// 変換に成功した場合は操作種別を`Ok()`でラップして返す。
+    ///
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(s.to_string()))
+            .map_err(|_| anyhow!("unknown audit operation: {}", s))
+    }
+}
+
 ///
 /// 監査ログ向け結果分類
 ///
@@ -131,6 +232,9 @@ pub(crate) enum AuditResult {
 
     /// 内部失敗
     InternalError,
+
+    /// 認証失敗
+    AuthenticationFailed,
 }
 
 impl AuditResult {
@@ -151,10 +255,30 @@ impl AuditResult {
             Self::InvalidInput => "invalid_input",
             Self::Unsupported => "unsupported",
             Self::InternalError => "internal_error",
+            Self::AuthenticationFailed => "authentication_failed",
         }
     }
 }
 
+// FromStrトレイトの実装
+impl FromStr for AuditResult {
+    type Err = Error;
+
+    ///
+    /// 永続化時の結果名からの変換
+    ///
+    /// # 引数
+    /// * `s` - 小文字スネークケースの結果名
+    ///
+    /// # 戻り値
+    /// 変換に成功した場合は結果分類を`Ok()`でラップして返す。
+    ///
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(s.to_string()))
+            .map_err(|_| anyhow!("unknown audit result: {}", s))
+    }
+}
+
 ///
 /// 監査ログ1件分のレコード骨格
 ///
@@ -163,8 +287,9 @@ pub(crate) struct AuditRecord {
     /// 操作種別
     pub(crate) operation: AuditOperation,
 
-    /// 操作主体のユーザID
-    pub(crate) user_id: UserId,
+    /// 操作主体のユーザID (CLI からの操作や未知のユーザ名でのログイン失敗
+    /// では`None`)
+    pub(crate) user_id: Option<UserId>,
 
     /// Bearer トークンID
     pub(crate) token_id: Option<TokenId>,
@@ -186,6 +311,18 @@ pub(crate) struct AuditRecord {
 
     /// 対象 revision
     pub(crate) revision: Option<u64>,
+
+    /// ハッシュチェーン上の通番 (writer が書込時に設定)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) seq: Option<u64>,
+
+    /// 直前の行の SHA-256 (writer が書込時に設定)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) prev_hash: Option<String>,
+
+    /// 保持削除で失われたチェーン末尾のハッシュ (`retention` のみ)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) chain_anchor: Option<String>,
 }
 
 impl AuditRecord {
@@ -208,7 +345,7 @@ impl AuditRecord {
     ///
     pub(crate) fn new(
         operation: AuditOperation,
-        user_id: UserId,
+        user_id: Option<UserId>,
         token_id: Option<TokenId>,
         address: Option<IpAddr>,
         target_path: Option<String>,
@@ -227,6 +364,9 @@ impl AuditRecord {
             timestamp,
             summary,
             revision,
+            seq: None,
+            prev_hash: None,
+            chain_anchor: None,
         }
     }
 
@@ -244,7 +384,7 @@ impl AuditRecord {
         }
 
         Some(AppendAuditKey {
-            user_id: self.user_id.clone(),
+            user_id: self.user_id.clone()?,
             token_id: self.token_id.clone()?,
             target_path: self.target_path.clone()?,
         })
@@ -340,7 +480,7 @@ mod tests {
         let token_id = TokenId::new();
         let success = AuditRecord::new(
             AuditOperation::Append,
-            user_id.clone(),
+            Some(user_id.clone()),
             Some(token_id.clone()),
             None,
             Some("/audit/page".to_string()),
@@ -351,7 +491,7 @@ mod tests {
         );
         let failure = AuditRecord::new(
             AuditOperation::Append,
-            user_id,
+            Some(user_id),
             Some(token_id),
             None,
             Some("/audit/page".to_string()),
@@ -0,0 +1,621 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 監査ログの検索処理を定義するモジュール
+//!
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{
+    DateTime, Duration, Local, NaiveDate, TimeZone, Utc,
+};
+use tracing::warn;
+
+use super::model::{AuditOperation, AuditRecord, AuditResult};
+use super::retention::parse_rotated_timestamp;
+use super::rotation::active_log_path;
+use crate::database::types::{TokenId, UserId};
+
+///
+/// 監査ログの検索条件
+///
+/// # 注記
+/// 未指定(`None`)の条件は絞り込みに利用しない。指定された条件はすべて
+/// AND で評価する。
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AuditQuery {
+    /// 操作主体のユーザID
+    pub(crate) user_id: Option<UserId>,
+
+    /// Bearer トークンID
+    pub(crate) token_id: Option<TokenId>,
+
+    /// 対象 path の prefix
+    pub(crate) path_prefix: Option<String>,
+
+    /// 操作種別
+    pub(crate) operation: Option<AuditOperation>,
+
+    /// 操作結果分類
+    pub(crate) result: Option<AuditResult>,
+
+    /// 検索範囲の開始時刻(この時刻を含む)
+    pub(crate) since: Option<DateTime<Utc>>,
+
+    /// 検索範囲の終了時刻(この時刻を含まない)
+    pub(crate) until: Option<DateTime<Utc>>,
+}
+
+impl AuditQuery {
+    ///
+    /// 監査レコードが検索条件に一致するかを判定する
+    ///
+    /// # 引数
+    /// * `record` - 判定対象の監査レコード
+    ///
+    /// # 戻り値
+    /// すべての条件に一致する場合は`true`を返す。
+    ///
+    pub(crate) fn matches(&self, record: &AuditRecord) -> bool {
+        let user_matched = self
+            .user_id
+            .as_ref()
+            .is_none_or(|user_id| record.user_id.as_ref() == Some(user_id));
+        let token_matched = self
+            .token_id
+            .as_ref()
+            .is_none_or(|token_id| record.token_id.as_ref() == Some(token_id));
+        let path_matched = self.path_prefix.as_deref().is_none_or(|prefix| {
+            record
+                .target_path
+                .as_deref()
+                .is_some_and(|path| is_path_under(path, prefix))
+        });
+        let operation_matched = self
+            .operation
+            .is_none_or(|operation| record.operation == operation);
+        let result_matched =
+            self.result.is_none_or(|result| record.result == result);
+        let since_matched =
+            self.since.is_none_or(|since| record.timestamp >= since);
+        let until_matched =
+            self.until.is_none_or(|until| record.timestamp < until);
+
+        user_matched
+            && token_matched
+            && path_matched
+            && operation_matched
+            && result_matched
+            && since_matched
+            && until_matched
+    }
+}
+
+///
+/// 監査ログの検索結果
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AuditQueryResult {
+    /// 条件に一致したレコード(記録時刻の昇順)
+    pub(crate) records: Vec<AuditRecord>,
+
+    /// 解析できずに読み飛ばした行数
+    pub(crate) skipped_lines: usize,
+}
+
+///
+/// 監査ログの検索結果(1ページ分)
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AuditQueryPage {
+    /// 返却範囲に含まれる条件一致レコード(記録順)
+    pub(crate) records: Vec<AuditRecord>,
+
+    /// 返却範囲より後ろに条件一致レコードが存在するか否か
+    pub(crate) has_more: bool,
+
+    /// 走査した範囲で解析できずに読み飛ばした行数
+    pub(crate) skipped_lines: usize,
+}
+
+///
+/// 検索対象となる監査ログファイルを列挙する
+///
+/// # 引数
+/// * `output_dir` - 監査ログ出力ディレクトリ
+///
+/// # 戻り値
+/// ローテーション済みファイルを古い順に並べ、最後にアクティブファイルを
+/// 加えた一覧を返す。ディレクトリが存在しない場合は空の一覧を返す。
+///
+/// # 注記
+/// ローテーション済みファイルの命名規則に一致しないファイルは対象外とする。
+///
+pub(crate) fn list_audit_log_files(output_dir: &Path) -> Result<Vec<PathBuf>> {
+    if !output_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    /*
+     * ローテーション済みファイルの収集
+     */
+    let entries = fs::read_dir(output_dir).with_context(|| {
+        format!("read audit output dir failed: {}", output_dir.display())
+    })?;
+    let mut rotated = Vec::new();
+
+    for entry in entries {
+        let entry = entry.context("read audit dir entry failed")?;
+        let path = entry.path();
+        if !entry.file_type().map(|kind| kind.is_file()).unwrap_or(false) {
+            continue;
+        }
+
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str())
+        else {
+            continue;
+        };
+
+        if parse_rotated_timestamp(file_name).is_some() {
+            rotated.push(path);
+        }
+    }
+
+    /*
+     * ファイル名(時刻+連番)順に整列し、アクティブファイルを末尾へ追加
+     */
+    rotated.sort();
+
+    let active = active_log_path(output_dir);
+    if active.is_file() {
+        rotated.push(active);
+    }
+
+    Ok(rotated)
+}
+
+///
+/// 監査ログを検索する
+///
+/// # 引数
+/// * `output_dir` - 監査ログ出力ディレクトリ
+/// * `query` - 検索条件
+///
+/// # 戻り値
+/// 条件に一致したレコードを記録順(記録時刻の昇順)で返す。
+///
+/// # 注記
+/// アクティブファイルとローテーション済みファイルの双方を走査する。
+/// 書込途中などで JSON として解析できない行は読み飛ばし、件数のみを
+/// 結果に含める。
+///
+pub(crate) fn query_audit_records(
+    output_dir: &Path,
+    query: &AuditQuery,
+) -> Result<AuditQueryResult> {
+    let mut records = Vec::new();
+    let skipped_lines = scan_audit_records(output_dir, query, |record| {
+        records.push(record);
+        ControlFlow::Continue(())
+    })?;
+
+    Ok(AuditQueryResult {
+        records,
+        skipped_lines,
+    })
+}
+
+///
+/// 監査ログを範囲指定で検索する
+///
+/// # 引数
+/// * `output_dir` - 監査ログ出力ディレクトリ
+/// * `query` - 検索条件
+/// * `offset` - 条件に一致したレコードの先頭から読み飛ばす件数
+/// * `limit` - 返却する最大件数
+///
+/// # 戻り値
+/// 返却範囲の条件一致レコードを記録順で返す。
+///
+/// # 注記
+/// 返却範囲の直後の条件一致レコードを見つけた時点で走査を打ち切るため、
+/// 保持するレコードは`limit`件以内に収まる。
+///
+pub(crate) fn query_audit_page(
+    output_dir: &Path,
+    query: &AuditQuery,
+    offset: usize,
+    limit: usize,
+) -> Result<AuditQueryPage> {
+    let mut page = AuditQueryPage::default();
+    let mut matched = 0usize;
+
+    page.skipped_lines = scan_audit_records(output_dir, query, |record| {
+        if matched >= offset.saturating_add(limit) {
+            page.has_more = true;
+            return ControlFlow::Break(());
+        }
+
+        if matched >= offset {
+            page.records.push(record);
+        }
+        matched += 1;
+        ControlFlow::Continue(())
+    })?;
+
+    Ok(page)
+}
+
+///
+/// 条件に一致する監査レコードを数える
+///
+/// # 引数
+/// * `output_dir` - 監査ログ出力ディレクトリ
+/// * `query` - 検索条件
+///
+/// # 戻り値
+/// 条件に一致したレコードの件数を返す。
+///
+/// # 注記
+/// レコードは保持せずに数えるが、すべてのファイルを走査する。
+///
+pub(crate) fn count_audit_records(
+    output_dir: &Path,
+    query: &AuditQuery,
+) -> Result<usize> {
+    let mut count = 0usize;
+    scan_audit_records(output_dir, query, |_| {
+        count += 1;
+        ControlFlow::Continue(())
+    })?;
+
+    Ok(count)
+}
+
+///
+/// 監査ログを走査して条件に一致したレコードを順に渡す
+///
+/// # 引数
+/// * `output_dir` - 監査ログ出力ディレクトリ
+/// * `query` - 検索条件
+/// * `visit` - 条件に一致したレコードを受け取る関数(`Break`で走査を終了)
+///
+/// # 戻り値
+/// 走査した範囲で解析できずに読み飛ばした行数を返す。
+///
+/// # 注記
+/// ローテーション済みファイルを古い順に、最後にアクティブファイルを
+/// 走査するため、レコードは記録順に渡される。
+///
+fn scan_audit_records<F>(
+    output_dir: &Path,
+    query: &AuditQuery,
+    mut visit: F,
+) -> Result<usize>
+where
+    F: FnMut(AuditRecord) -> ControlFlow<()>,
+{
+    let mut skipped_lines = 0usize;
+
+    for path in list_audit_log_files(output_dir)? {
+        /*
+         * ファイル単位での読み込み
+         */
+        let file = File::open(&path).with_context(|| {
+            format!("open audit log failed: {}", path.display())
+        })?;
+
+        for line in BufReader::new(file).lines() {
+            let line = line.with_context(|| {
+                format!("read audit log failed: {}", path.display())
+            })?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            /*
+             * レコードの解析と条件判定
+             */
+            match serde_json::from_str::<AuditRecord>(&line) {
+                Ok(record) => {
+                    if query.matches(&record) && visit(record).is_break() {
+                        return Ok(skipped_lines);
+                    }
+                }
+                Err(err) => {
+                    warn!(
+                        path = %path.display(),
+                        error = %err,
+                        "skip malformed audit log line"
+                    );
+                    skipped_lines += 1;
+                }
+            }
+        }
+    }
+
+    Ok(skipped_lines)
+}
+
+///
+/// 検索範囲指定の時刻表現を解析する
+///
+/// # 引数
+/// * `raw` - 時刻表現
+/// * `now` - 相対指定の基準時刻
+///
+/// # 戻り値
+/// 解析した UTC 時刻を返す。
+///
+/// # 注記
+/// 以下の表現を受け付ける。
+/// - RFC3339 形式の日時 (例: `2026-01-02T03:04:05Z`)
+/// - `YYYY-MM-DD` 形式の日付 (ローカルタイムゾーンでの 0 時)
+/// - `Nd` / `Nh` / `Nm` 形式の相対指定 (基準時刻から遡った時刻)
+///
+pub(crate) fn parse_query_time(
+    raw: &str,
+    now: DateTime<Utc>,
+) -> Result<DateTime<Utc>> {
+    let value = raw.trim();
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let midnight = date
+            .and_hms_opt(0, 0, 0)
+            .ok_or_else(|| anyhow!("invalid audit query time: {}", raw))?;
+        return Local
+            .from_local_datetime(&midnight)
+            .earliest()
+            .map(|time| time.with_timezone(&Utc))
+            .ok_or_else(|| anyhow!("invalid audit query time: {}", raw));
+    }
+
+    parse_relative_duration(value)
+        .map(|duration| now - duration)
+        .ok_or_else(|| anyhow!("invalid audit query time: {}", raw))
+}
+
+///
+/// `Nd` / `Nh` / `Nm` 形式の相対指定を解析する
+///
+/// # 引数
+/// * `value` - 相対指定文字列
+///
+/// # 戻り値
+/// 解析に成功した場合は期間を返す。
+///
+fn parse_relative_duration(value: &str) -> Option<Duration> {
+    let unit = value.chars().last()?;
+    let amount = value[..value.len() - unit.len_utf8()].parse::<i64>().ok()?;
+    if amount < 0 {
+        return None;
+    }
+
+    match unit {
+        'd' => Duration::try_days(amount),
+        'h' => Duration::try_hours(amount),
+        'm' => Duration::try_minutes(amount),
+        _ => None,
+    }
+}
+
+///
+/// path が prefix 配下にあるかを判定する
+///
+/// # 引数
+/// * `path` - 判定対象 path
+/// * `prefix` - path prefix
+///
+/// # 戻り値
+/// `path` が `prefix` 自身またはその配下である場合は`true`を返す。
+///
+fn is_path_under(path: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    if prefix.is_empty() {
+        return path.starts_with('/');
+    }
+
+    path == prefix
+        || path
+            .strip_prefix(prefix)
+            .map(|rest| rest.starts_with('/'))
+            .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use chrono::{TimeZone, Utc};
+    use tempfile::tempdir;
+
+    use super::*;
+
+    ///
+    /// テスト用の監査レコードを生成する。
+    ///
+    fn test_record(
+        operation: AuditOperation,
+        path: &str,
+        result: AuditResult,
+        hour: u32,
+    ) -> AuditRecord {
+        AuditRecord::new(
+            operation,
+            None,
+            None,
+            None,
+            Some(path.to_string()),
+            result,
+            Utc.with_ymd_and_hms(2026, 3, 1, hour, 0, 0)
+                .single()
+                .expect("timestamp failed"),
+            None,
+            None,
+        )
+    }
+
+    ///
+    /// JSONL 形式でレコードを書き出す。
+    ///
+    fn write_records(path: &Path, records: &[AuditRecord]) {
+        let mut text = String::new();
+        for record in records {
+            text.push_str(
+                &serde_json::to_string(record).expect("serialize failed"),
+            );
+            text.push('\n');
+        }
+        fs::write(path, text).expect("write audit log failed");
+    }
+
+    ///
+    /// ローテーション済みファイルとアクティブファイルの双方を走査し、
+    /// 記録時刻順に条件一致レコードを返すことを確認する。
+    ///
+    /// 注記:
+    /// 解析できない行と規則外ファイルは結果に含めない。
+    ///
+    #[test]
+    fn query_audit_records_scans_rotated_and_active_files() {
+        let dir = tempdir().expect("tempdir failed");
+        write_records(
+            &dir.path().join("audit-20260301T020000Z-000001.jsonl"),
+            &[
+                test_record(AuditOperation::Delete, "/a/b", AuditResult::Success, 1),
+                test_record(AuditOperation::Update, "/a", AuditResult::Success, 2),
+            ],
+        );
+        write_records(
+            &dir.path().join("audit.current.jsonl"),
+            &[
+                test_record(AuditOperation::Delete, "/ab", AuditResult::Success, 3),
+                test_record(AuditOperation::Delete, "/a", AuditResult::NotFound, 4),
+            ],
+        );
+        fs::write(
+            dir.path().join("audit-20260301T030000Z-000001.jsonl"),
+            b"{broken\n",
+        )
+        .expect("write broken failed");
+        write_records(
+            &dir.path().join("notes.jsonl"),
+            &[test_record(AuditOperation::Delete, "/a", AuditResult::Success, 5)],
+        );
+
+        let query = AuditQuery {
+            path_prefix: Some("/a".to_string()),
+            operation: Some(AuditOperation::Delete),
+            ..Default::default()
+        };
+        let result = query_audit_records(dir.path(), &query)
+            .expect("query failed");
+
+        let paths: Vec<_> = result
+            .records
+            .iter()
+            .map(|record| record.target_path.clone().unwrap_or_default())
+            .collect();
+        assert_eq!(paths, vec!["/a/b".to_string(), "/a".to_string()]);
+        assert_eq!(result.skipped_lines, 1);
+
+        let query = AuditQuery {
+            result: Some(AuditResult::Success),
+            since: parse_query_time("2026-03-01T02:00:00Z", Utc::now()).ok(),
+            until: parse_query_time("2026-03-01T04:00:00Z", Utc::now()).ok(),
+            ..Default::default()
+        };
+        let result = query_audit_records(dir.path(), &query)
+            .expect("query failed");
+        assert_eq!(result.records.len(), 2);
+        assert_eq!(result.records[0].operation, AuditOperation::Update);
+    }
+
+    ///
+    /// 範囲指定の検索で返却範囲のレコードと後続の有無を返し、件数を
+    /// 別途数えられることを確認する。
+    ///
+    #[test]
+    fn query_audit_page_returns_window_and_has_more() {
+        let dir = tempdir().expect("tempdir failed");
+        write_records(
+            &dir.path().join("audit-20260301T020000Z-000001.jsonl"),
+            &[
+                test_record(AuditOperation::Delete, "/a", AuditResult::Success, 1),
+                test_record(AuditOperation::Delete, "/b", AuditResult::Success, 2),
+            ],
+        );
+        write_records(
+            &dir.path().join("audit.current.jsonl"),
+            &[
+                test_record(AuditOperation::Update, "/c", AuditResult::Success, 3),
+                test_record(AuditOperation::Delete, "/d", AuditResult::Success, 4),
+            ],
+        );
+
+        let query = AuditQuery {
+            operation: Some(AuditOperation::Delete),
+            ..Default::default()
+        };
+        let paths = |page: &AuditQueryPage| -> Vec<String> {
+            page.records
+                .iter()
+                .map(|record| record.target_path.clone().unwrap_or_default())
+                .collect()
+        };
+
+        let page = query_audit_page(dir.path(), &query, 1, 1)
+            .expect("query failed");
+        assert_eq!(paths(&page), vec!["/b".to_string()]);
+        assert!(page.has_more);
+
+        let page = query_audit_page(dir.path(), &query, 1, 2)
+            .expect("query failed");
+        assert_eq!(paths(&page), vec!["/b".to_string(), "/d".to_string()]);
+        assert!(!page.has_more);
+
+        let page = query_audit_page(dir.path(), &query, 5, 2)
+            .expect("query failed");
+        assert!(page.records.is_empty());
+        assert!(!page.has_more);
+
+        assert_eq!(
+            count_audit_records(dir.path(), &query).expect("count failed"),
+            3
+        );
+    }
+
+    ///
+    /// 相対指定と不正な時刻表現の解析結果を確認する。
+    ///
+    #[test]
+    fn parse_query_time_accepts_relative_form() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 0, 0, 0)
+            .single()
+            .expect("timestamp failed");
+
+        assert_eq!(
+            parse_query_time("2d", now).expect("parse failed"),
+            now - Duration::days(2)
+        );
+        assert_eq!(
+            parse_query_time("30m", now).expect("parse failed"),
+            now - Duration::minutes(30)
+        );
+        assert!(parse_query_time("yesterday", now).is_err());
+        assert!(parse_query_time("5w", now).is_err());
+    }
+}
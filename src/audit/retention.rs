@@ -15,6 +15,8 @@ use anyhow::{Context, Result};
 use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 use tracing::{debug, warn};
 
+use super::chain::is_chain_file_name;
+
 const ACTIVE_LOG_FILE_NAME: &str = "audit.current.jsonl";
 
 ///
@@ -133,7 +135,10 @@ pub(crate) fn build_retention_plan(
             continue;
         };
 
-        if file_name == ACTIVE_LOG_FILE_NAME || !file_type.is_file() {
+        if file_name == ACTIVE_LOG_FILE_NAME
+            || is_chain_file_name(file_name)
+            || !file_type.is_file()
+        {
             continue;
         }
 
@@ -205,7 +210,7 @@ pub(crate) fn execute_retention_plan(
 /// # 戻り値
 /// 命名規則に一致する場合はローテーション時刻を返す。
 ///
-fn parse_rotated_timestamp(file_name: &str) -> Option<DateTime<Utc>> {
+pub(crate) fn parse_rotated_timestamp(file_name: &str) -> Option<DateTime<Utc>> {
     let body = file_name
         .strip_prefix("audit-")?
         .strip_suffix(".jsonl")?;
@@ -8,17 +8,22 @@
 //! 監査イベント投入入口の骨格を定義するモジュール
 //!
 
+use std::path::Path;
+
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use log::warn;
 
 use super::buffer::AppendAuditBuffer;
+use super::forward::{AuditForwarder, AuditOutputConfig};
 use super::model::{
     AppendAuditOutcome,
     AuditOperation,
     AuditRecord,
     AuditResult,
 };
-use super::writer::AuditWriter;
+use super::retention::{AuditRetentionPolicy, RetentionSweepResult};
+use super::writer::{AuditWriter, AuditWriterConfig};
 
 ///
 /// 監査イベント投入入口の骨格
@@ -28,8 +33,11 @@ pub(crate) struct AuditSink {
     /// `append` 集約バッファ
     buffer: AppendAuditBuffer,
 
-    /// JSONL writer
-    writer: AuditWriter,
+    /// JSONL writer (ローカルファイルへの出力を行わない場合は`None`)
+    writer: Option<AuditWriter>,
+
+    /// 外部転送先
+    forwarders: Vec<Box<dyn AuditForwarder>>,
 }
 
 impl AuditSink {
@@ -47,7 +55,66 @@ impl AuditSink {
         buffer: AppendAuditBuffer,
         writer: AuditWriter,
     ) -> Self {
-        Self { buffer, writer }
+        Self {
+            buffer,
+            writer: Some(writer),
+            forwarders: Vec::new(),
+        }
+    }
+
+    ///
+    /// 出力先設定に従った監査イベント投入入口の生成
+    ///
+    /// # 引数
+    /// * `writer_config` - ローカルファイル出力の writer 設定
+    /// * `outputs` - 出力先設定
+    ///
+    /// # 戻り値
+    /// ローカルファイル出力と外部転送先を設定に従って組み合わせた
+    /// 監査イベント投入入口を返す。
+    ///
+    pub(crate) fn open(
+        writer_config: AuditWriterConfig,
+        outputs: &AuditOutputConfig,
+    ) -> Result<Self> {
+        outputs.validate()?;
+
+        Ok(Self {
+            buffer: AppendAuditBuffer::new(),
+            writer: outputs.file.then(|| AuditWriter::new(writer_config)),
+            forwarders: outputs.build_forwarders()?,
+        })
+    }
+
+    ///
+    /// 監査ログ出力ディレクトリへのアクセサ
+    ///
+    /// # 戻り値
+    /// ローカルファイルへ出力する場合は writer の出力先ディレクトリを返す。
+    ///
+    pub(crate) fn output_dir(&self) -> Option<&Path> {
+        self.writer.as_ref().map(AuditWriter::output_dir)
+    }
+
+    ///
+    /// 保持期間超過ログの削除
+    ///
+    /// # 引数
+    /// * `policy` - 保持期間ポリシー
+    /// * `now` - 判定基準時刻
+    ///
+    /// # 戻り値
+    /// 削除結果を返す。ローカルファイルへ出力しない場合は何もしない。
+    ///
+    pub(crate) fn apply_retention(
+        &mut self,
+        policy: &AuditRetentionPolicy,
+        now: DateTime<Utc>,
+    ) -> Result<RetentionSweepResult> {
+        match self.writer.as_mut() {
+            Some(writer) => writer.apply_retention(policy, now),
+            None => Ok(RetentionSweepResult::default()),
+        }
     }
 
     ///
@@ -109,10 +176,26 @@ impl AuditSink {
         let flushed = self.buffer.flush_all(Utc::now());
         self.write_records(&flushed)?;
 
+        /*
+         * 外部転送先の送出要求
+         */
+        for forwarder in &mut self.forwarders {
+            if let Err(err) = forwarder.flush() {
+                warn!(
+                    "audit forward flush failed: {}: {}",
+                    forwarder.describe(),
+                    err
+                );
+            }
+        }
+
         /*
          * writer 自身の flush
          */
-        self.writer.flush()
+        match self.writer.as_mut() {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
     }
 
     ///
@@ -134,15 +217,40 @@ impl AuditSink {
     /// # 戻り値
     /// すべての書込が成功した場合は `Ok(())` を返す。
     ///
+    /// # 注記
+    /// サーバプロセスが強制終了された場合でも確定済みレコードが失われない
+    /// よう、1 件以上書き込んだ場合は writer を flush する。外部転送先への
+    /// 転送失敗は書込結果に含めず、警告ログの出力にとどめる。
+    ///
     fn write_records(
         &mut self,
         records: &[AuditRecord],
     ) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        for record in records {
+            for forwarder in &mut self.forwarders {
+                if let Err(err) = forwarder.forward(record) {
+                    warn!(
+                        "audit forward failed: {}: {}",
+                        forwarder.describe(),
+                        err
+                    );
+                }
+            }
+        }
+
+        let Some(writer) = self.writer.as_mut() else {
+            return Ok(());
+        };
+
         for record in records {
-            self.writer.write_record(record)?;
+            writer.write_record(record)?;
         }
 
-        Ok(())
+        writer.flush()
     }
 }
 
@@ -185,7 +293,7 @@ mod tests {
     fn test_append_record(summary: &str) -> AuditRecord {
         AuditRecord::new(
             AuditOperation::Append,
-            UserId::new(),
+            Some(UserId::new()),
             Some(TokenId::new()),
             None,
             Some("/audit/page".to_string()),
@@ -0,0 +1,454 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 監査レコードを RFC 5424 形式の syslog として転送するモジュール
+//!
+
+use std::fs;
+use std::io::Write;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::process;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::SecondsFormat;
+use serde::{Deserialize, Serialize};
+
+use super::forward::AuditForwarder;
+use super::model::{AuditRecord, AuditResult};
+
+/// syslog 上のアプリケーション名
+const SYSLOG_APP_NAME: &str = "luwiki";
+
+/// TCP 接続・書込のタイムアウト
+const SYSLOG_TCP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// facility の既定値
+pub(crate) const DEFAULT_SYSLOG_FACILITY: &str = "local0";
+
+///
+/// syslog の転送プロトコル
+///
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SyslogProtocol {
+    /// UDP (RFC 5426)
+    #[default]
+    Udp,
+
+    /// TCP (RFC 6587 octet counting)
+    Tcp,
+}
+
+///
+/// syslog 転送先の設定
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct SyslogConfig {
+    /// 転送先アドレス ("host:port")
+    pub(crate) address: String,
+
+    /// 転送プロトコル
+    pub(crate) protocol: SyslogProtocol,
+
+    /// facility 番号
+    pub(crate) facility: u8,
+
+    /// HOSTNAME 欄に記録するホスト名 (未指定時は自動取得)
+    pub(crate) hostname: Option<String>,
+}
+
+impl SyslogConfig {
+    ///
+    /// 転送先設定の検証
+    ///
+    /// # 戻り値
+    /// 設定が妥当な場合は`Ok(())`を返す。
+    ///
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.address.trim().is_empty() {
+            return Err(anyhow!("audit_output.syslog.address is empty"));
+        }
+
+        Ok(())
+    }
+}
+
+///
+/// facility 名を facility 番号へ変換する
+///
+/// # 引数
+/// * `name` - facility 名 (例: `auth`, `local0`)
+///
+/// # 戻り値
+/// 対応する facility 番号を返す。未知の名前の場合はエラーを返す。
+///
+pub(crate) fn parse_syslog_facility(name: &str) -> Result<u8> {
+    let facility = match name.trim().to_ascii_lowercase().as_str() {
+        "user" => 1,
+        "daemon" => 3,
+        "auth" => 4,
+        "authpriv" => 10,
+        "local0" => 16,
+        "local1" => 17,
+        "local2" => 18,
+        "local3" => 19,
+        "local4" => 20,
+        "local5" => 21,
+        "local6" => 22,
+        "local7" => 23,
+        _ => return Err(anyhow!("unknown syslog facility: {}", name)),
+    };
+
+    Ok(facility)
+}
+
+///
+/// 監査レコードを syslog へ転送する転送先
+///
+#[derive(Debug)]
+pub(crate) struct SyslogForwarder {
+    /// 転送先設定
+    config: SyslogConfig,
+
+    /// HOSTNAME 欄に記録するホスト名
+    hostname: String,
+
+    /// UDP 送信用ソケットと送信先
+    udp: Option<(UdpSocket, SocketAddr)>,
+
+    /// TCP 接続
+    tcp: Option<TcpStream>,
+}
+
+impl SyslogForwarder {
+    ///
+    /// 転送先の生成
+    ///
+    /// # 引数
+    /// * `config` - 転送先設定
+    ///
+    /// # 戻り値
+    /// 生成した転送先を返す。
+    ///
+    /// # 注記
+    /// 接続は最初の転送時に確立する。
+    ///
+    pub(crate) fn new(config: SyslogConfig) -> Result<Self> {
+        config.validate()?;
+
+        let hostname = config
+            .hostname
+            .clone()
+            .unwrap_or_else(local_hostname);
+
+        Ok(Self {
+            config,
+            hostname,
+            udp: None,
+            tcp: None,
+        })
+    }
+
+    ///
+    /// 転送先アドレスの解決
+    ///
+    fn resolve_address(&self) -> Result<SocketAddr> {
+        self.config
+            .address
+            .to_socket_addrs()
+            .with_context(|| {
+                format!("resolve syslog address failed: {}", self.config.address)
+            })?
+            .next()
+            .ok_or_else(|| {
+                anyhow!("syslog address not resolved: {}", self.config.address)
+            })
+    }
+
+    ///
+    /// UDP でメッセージを送信する
+    ///
+    fn send_udp(&mut self, message: &[u8]) -> Result<()> {
+        if self.udp.is_none() {
+            let target = self.resolve_address()?;
+            let bind_addr = if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+            let socket = UdpSocket::bind(bind_addr)
+                .context("bind syslog socket failed")?;
+            self.udp = Some((socket, target));
+        }
+
+        let (socket, target) = self.udp.as_ref().expect("udp must be opened");
+        socket
+            .send_to(message, target)
+            .context("send syslog message failed")?;
+
+        Ok(())
+    }
+
+    ///
+    /// TCP でメッセージを送信する
+    ///
+    /// # 注記
+    /// 既存接続への書込に失敗した場合は、接続し直して1回だけ再送する。
+    ///
+    fn send_tcp(&mut self, message: &[u8]) -> Result<()> {
+        let mut frame = format!("{} ", message.len()).into_bytes();
+        frame.extend_from_slice(message);
+
+        if let Some(stream) = self.tcp.as_mut() {
+            if stream.write_all(&frame).is_ok() {
+                return Ok(());
+            }
+            self.tcp = None;
+        }
+
+        let target = self.resolve_address()?;
+        let mut stream = TcpStream::connect_timeout(&target, SYSLOG_TCP_TIMEOUT)
+            .with_context(|| {
+                format!("connect syslog server failed: {}", self.config.address)
+            })?;
+        stream.set_write_timeout(Some(SYSLOG_TCP_TIMEOUT))?;
+        stream
+            .write_all(&frame)
+            .context("send syslog message failed")?;
+        self.tcp = Some(stream);
+
+        Ok(())
+    }
+}
+
+impl AuditForwarder for SyslogForwarder {
+    fn describe(&self) -> String {
+        let protocol = match self.config.protocol {
+            SyslogProtocol::Udp => "udp",
+            SyslogProtocol::Tcp => "tcp",
+        };
+        format!("syslog+{}://{}", protocol, self.config.address)
+    }
+
+    fn forward(&mut self, record: &AuditRecord) -> Result<()> {
+        let message = format_syslog_message(
+            record,
+            self.config.facility,
+            &self.hostname,
+            process::id(),
+        )?;
+
+        match self.config.protocol {
+            SyslogProtocol::Udp => self.send_udp(message.as_bytes()),
+            SyslogProtocol::Tcp => self.send_tcp(message.as_bytes()),
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if let Some(stream) = self.tcp.as_mut() {
+            stream.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+///
+/// 監査レコードを RFC 5424 形式のメッセージへ整形する
+///
+/// # 引数
+/// * `record` - 監査レコード
+/// * `facility` - facility 番号
+/// * `hostname` - HOSTNAME 欄の値
+/// * `pid` - PROCID 欄の値
+///
+/// # 戻り値
+/// `<PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID - MSG` 形式の文字列を
+/// 返す。MSGID は操作種別、MSG は監査ログと同じ JSON 表現とする。
+///
+pub(crate) fn format_syslog_message(
+    record: &AuditRecord,
+    facility: u8,
+    hostname: &str,
+    pid: u32,
+) -> Result<String> {
+    let priority = u16::from(facility) * 8 + u16::from(severity(record.result));
+
+    Ok(format!(
+        "<{}>1 {} {} {} {} {} - {}",
+        priority,
+        record.timestamp.to_rfc3339_opts(SecondsFormat::Micros, true),
+        sanitize_header_field(hostname, 255),
+        SYSLOG_APP_NAME,
+        pid,
+        sanitize_header_field(record.operation.as_str(), 32),
+        serde_json::to_string(record)?,
+    ))
+}
+
+///
+/// 操作結果分類に対応する severity を返す
+///
+fn severity(result: AuditResult) -> u8 {
+    match result {
+        AuditResult::Success => 6,
+        AuditResult::InternalError => 3,
+        AuditResult::AuthenticationFailed
+        | AuditResult::ScopeDenied
+        | AuditResult::PathPrefixDenied
+        | AuditResult::ReadOnlyDenied => 4,
+        AuditResult::NotFound
+        | AuditResult::Conflict
+        | AuditResult::InvalidInput
+        | AuditResult::Unsupported => 5,
+    }
+}
+
+///
+/// ヘッダ欄に使えない文字を置換し、長さを制限する
+///
+/// # 注記
+/// RFC 5424 のヘッダ欄は空白を含まない印字可能 ASCII に限られるため、
+/// それ以外の文字は `_` に置換する。空文字列は NILVALUE (`-`) とする。
+///
+fn sanitize_header_field(value: &str, max_len: usize) -> String {
+    let value: String = value
+        .chars()
+        .map(|ch| if ch.is_ascii_graphic() { ch } else { '_' })
+        .take(max_len)
+        .collect();
+
+    if value.is_empty() {
+        "-".to_string()
+    } else {
+        value
+    }
+}
+
+///
+/// 自ホスト名の取得
+///
+/// # 戻り値
+/// 環境変数または `/etc/hostname` から取得したホスト名を返す。取得できない
+/// 場合は NILVALUE (`-`) を返す。
+///
+fn local_hostname() -> String {
+    ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok())
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::audit::model::AuditOperation;
+
+    fn test_record(result: AuditResult) -> AuditRecord {
+        AuditRecord::new(
+            AuditOperation::Delete,
+            None,
+            None,
+            None,
+            Some("/docs/a".to_string()),
+            result,
+            Utc.with_ymd_and_hms(2026, 3, 1, 2, 3, 4).unwrap(),
+            None,
+            Some(3),
+        )
+    }
+
+    ///
+    /// RFC 5424 形式のヘッダと JSON 本文が生成されることを確認する。
+    ///
+    #[test]
+    fn format_syslog_message_builds_rfc5424_line() {
+        let message = format_syslog_message(
+            &test_record(AuditResult::ScopeDenied),
+            16,
+            "wiki host",
+            42,
+        )
+        .expect("format failed");
+
+        assert!(
+            message.starts_with(
+                "<132>1 2026-03-01T02:03:04.000000Z wiki_host luwiki 42 \
+                 delete - {"
+            ),
+            "unexpected message: {}",
+            message
+        );
+        assert!(message.contains("\"target_path\":\"/docs/a\""));
+        assert_eq!(parse_syslog_facility("LOCAL0").unwrap(), 16);
+        assert!(parse_syslog_facility("unknown").is_err());
+    }
+
+    ///
+    /// UDP および TCP の受信側へメッセージが届くことを確認する。
+    ///
+    #[test]
+    fn syslog_forwarder_sends_over_udp_and_tcp() {
+        /*
+         * UDP
+         */
+        let receiver = UdpSocket::bind("127.0.0.1:0").expect("bind failed");
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .expect("set timeout failed");
+        let mut forwarder = SyslogForwarder::new(SyslogConfig {
+            address: receiver.local_addr().unwrap().to_string(),
+            protocol: SyslogProtocol::Udp,
+            facility: 4,
+            hostname: Some("wiki01".to_string()),
+        })
+        .expect("create forwarder failed");
+        forwarder
+            .forward(&test_record(AuditResult::Success))
+            .expect("forward failed");
+
+        let mut buf = [0u8; 4096];
+        let size = receiver.recv(&mut buf).expect("recv failed");
+        let message = String::from_utf8_lossy(&buf[..size]);
+        assert!(message.starts_with("<38>1 "), "unexpected: {}", message);
+        assert!(message.contains(" wiki01 luwiki "));
+
+        /*
+         * TCP (octet counting)
+         */
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind failed");
+        let mut forwarder = SyslogForwarder::new(SyslogConfig {
+            address: listener.local_addr().unwrap().to_string(),
+            protocol: SyslogProtocol::Tcp,
+            facility: 16,
+            hostname: Some("wiki01".to_string()),
+        })
+        .expect("create forwarder failed");
+        forwarder
+            .forward(&test_record(AuditResult::Success))
+            .expect("forward failed");
+        forwarder
+            .forward(&test_record(AuditResult::InternalError))
+            .expect("forward failed");
+        drop(forwarder);
+
+        let (mut stream, _) = listener.accept().expect("accept failed");
+        let mut body = String::new();
+        stream.read_to_string(&mut body).expect("read failed");
+
+        let (len, rest) = body.split_once(' ').expect("frame missing");
+        let len: usize = len.parse().expect("invalid frame length");
+        assert!(rest[..len].starts_with("<134>1 "));
+        let (_, second) = rest[len..].split_once(' ').expect("frame missing");
+        assert!(second.starts_with("<131>1 "));
+    }
+}
@@ -0,0 +1,588 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 監査レコードを JSON over HTTP で外部収集基盤へ転送するモジュール
+//!
+
+use std::sync::Mutex;
+use std::sync::mpsc::{
+    self,
+    Receiver,
+    RecvTimeoutError,
+    SyncSender,
+    TrySendError,
+};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use log::warn;
+use reqwest::blocking::Client;
+use reqwest::header::CONTENT_TYPE;
+use serde_json::Value;
+
+use super::forward::AuditForwarder;
+use super::model::AuditRecord;
+
+/// 1回の送信にまとめるレコード数の既定値
+pub(crate) const DEFAULT_WEBHOOK_BATCH_SIZE: usize = 50;
+
+/// 送信待ちレコードを送出するまでの最大待ち時間の既定値(秒)
+pub(crate) const DEFAULT_WEBHOOK_FLUSH_INTERVAL_SECS: u64 = 5;
+
+/// 送信失敗時の再送回数の既定値
+pub(crate) const DEFAULT_WEBHOOK_MAX_RETRIES: u32 = 3;
+
+/// 最初の再送までの待ち時間の既定値(秒)
+pub(crate) const DEFAULT_WEBHOOK_RETRY_INTERVAL_SECS: u64 = 1;
+
+/// 1回の HTTP リクエストのタイムアウト
+const WEBHOOK_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 送信スレッドへ渡す送信待ちメッセージ数の上限
+const WEBHOOK_QUEUE_CAPACITY: usize = 4096;
+
+/// 破棄時に送信スレッドの終了を待つ最大時間
+const WEBHOOK_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(15);
+
+///
+/// webhook 転送先の設定
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct WebhookConfig {
+    /// 送信先 URL
+    pub(crate) url: String,
+
+    /// 1回の送信にまとめるレコード数の上限
+    pub(crate) batch_size: usize,
+
+    /// 送信待ちレコードを送出するまでの最大待ち時間
+    pub(crate) flush_interval: Duration,
+
+    /// 送信失敗時の再送回数
+    pub(crate) max_retries: u32,
+
+    /// 最初の再送までの待ち時間 (以降は倍々に延長する)
+    pub(crate) retry_interval: Duration,
+
+    /// 追加するリクエストヘッダ
+    pub(crate) headers: Vec<(String, String)>,
+}
+
+impl WebhookConfig {
+    ///
+    /// 転送先設定の検証
+    ///
+    /// # 戻り値
+    /// 設定が妥当な場合は`Ok(())`を返す。
+    ///
+    pub(crate) fn validate(&self) -> Result<()> {
+        if !self.url.starts_with("http://") && !self.url.starts_with("https://")
+        {
+            return Err(anyhow!(
+                "audit_output.webhook.url must be http or https URL: {}",
+                self.url
+            ));
+        }
+
+        if self.batch_size == 0 {
+            return Err(anyhow!(
+                "audit_output.webhook.batch_size must be greater than zero"
+            ));
+        }
+
+        if self.flush_interval.is_zero() {
+            return Err(anyhow!(
+                "audit_output.webhook.flush_interval_secs must be greater \
+                 than zero"
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+///
+/// 送信スレッドへ渡すメッセージ
+///
+#[derive(Debug)]
+enum WebhookMessage {
+    /// 送信対象レコード (JSON 表現)
+    Record(Value),
+
+    /// 送信待ちレコードの即時送出要求
+    Flush,
+}
+
+///
+/// 送信失敗の分類
+///
+enum DeliveryFailure {
+    /// 再送で回復しうる失敗 (通信エラー、5xx、429)
+    Retryable(String),
+
+    /// 再送しても回復しない失敗 (429 以外の 4xx など)
+    Permanent(String),
+}
+
+///
+/// 監査レコードを webhook へ転送する転送先
+///
+/// # 注記
+/// 送信は専用スレッドで行い、監査レコードの投入側を HTTP 通信で待たせない。
+/// 送信待ちが`WEBHOOK_QUEUE_CAPACITY`件に達している間に投入されたレコードは
+/// 警告ログを出力して破棄する。破棄時は送信待ちレコードの送出を促した上で、
+/// `WEBHOOK_SHUTDOWN_TIMEOUT`(既定値)を上限にスレッドの終了を待つ。
+///
+#[derive(Debug)]
+pub(crate) struct WebhookForwarder {
+    /// 送信先 URL
+    url: String,
+
+    /// 送信スレッドへの送信口
+    sender: Option<SyncSender<WebhookMessage>>,
+
+    /// 送信スレッド
+    worker: Option<JoinHandle<()>>,
+
+    /// 送信スレッドの終了通知の受信口 (転送先を`Sync`とするため排他で包む)
+    finished: Mutex<Receiver<()>>,
+
+    /// 破棄時に送信スレッドの終了を待つ最大時間
+    shutdown_timeout: Duration,
+}
+
+impl WebhookForwarder {
+    ///
+    /// 転送先の生成
+    ///
+    /// # 引数
+    /// * `config` - 転送先設定
+    ///
+    /// # 戻り値
+    /// 送信スレッドを起動した転送先を返す。
+    ///
+    pub(crate) fn new(config: WebhookConfig) -> Result<Self> {
+        Self::spawn(config, WEBHOOK_QUEUE_CAPACITY, WEBHOOK_SHUTDOWN_TIMEOUT)
+    }
+
+    ///
+    /// 送信待ちの上限と終了待ち時間を指定した転送先の生成
+    ///
+    /// # 引数
+    /// * `config` - 転送先設定
+    /// * `queue_capacity` - 送信待ちメッセージ数の上限
+    /// * `shutdown_timeout` - 破棄時に送信スレッドの終了を待つ最大時間
+    ///
+    /// # 戻り値
+    /// 送信スレッドを起動した転送先を返す。
+    ///
+    fn spawn(
+        config: WebhookConfig,
+        queue_capacity: usize,
+        shutdown_timeout: Duration,
+    ) -> Result<Self> {
+        config.validate()?;
+
+        let url = config.url.clone();
+        let (sender, receiver) = mpsc::sync_channel(queue_capacity);
+        let (finished_sender, finished) = mpsc::channel();
+        let worker = thread::Builder::new()
+            .name("audit-webhook".to_string())
+            .spawn(move || {
+                run_worker(config, receiver);
+                let _ = finished_sender.send(());
+            })
+            .context("spawn audit webhook worker failed")?;
+
+        Ok(Self {
+            url,
+            sender: Some(sender),
+            worker: Some(worker),
+            finished: Mutex::new(finished),
+            shutdown_timeout,
+        })
+    }
+
+    ///
+    /// 送信スレッドへメッセージを渡す
+    ///
+    /// # 戻り値
+    /// 送信待ちへの投入に成功した場合は`Ok(true)`、送信待ちが上限に達して
+    /// いた場合は`Ok(false)`を返す。
+    ///
+    fn send(&self, message: WebhookMessage) -> Result<bool> {
+        let sender = self
+            .sender
+            .as_ref()
+            .ok_or_else(|| anyhow!("audit webhook worker stopped: {}", self.url))?;
+
+        match sender.try_send(message) {
+            Ok(()) => Ok(true),
+            Err(TrySendError::Full(_)) => Ok(false),
+            Err(TrySendError::Disconnected(_)) => {
+                Err(anyhow!("audit webhook worker stopped: {}", self.url))
+            }
+        }
+    }
+}
+
+impl AuditForwarder for WebhookForwarder {
+    fn describe(&self) -> String {
+        self.url.clone()
+    }
+
+    fn forward(&mut self, record: &AuditRecord) -> Result<()> {
+        let record = serde_json::to_value(record)?;
+        if !self.send(WebhookMessage::Record(record))? {
+            warn!("audit webhook queue full, record dropped: {}", self.url);
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        /*
+         * 送信待ちが上限に達している場合は送信スレッドが送出中のため、
+         * 送出要求は省略する
+         */
+        self.send(WebhookMessage::Flush).map(|_| ())
+    }
+}
+
+impl Drop for WebhookForwarder {
+    fn drop(&mut self) {
+        /*
+         * 送信口を閉じて送信スレッドに残りの送出と終了を促す
+         */
+        self.sender = None;
+
+        /*
+         * 終了通知を上限時間まで待ち、時間内に終了しない場合はスレッドを
+         * 切り離す(送出できなかったレコードは失われる)
+         */
+        let finished = match self.finished.get_mut() {
+            Ok(finished) => finished,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        match finished.recv_timeout(self.shutdown_timeout) {
+            Err(RecvTimeoutError::Timeout) => {
+                warn!(
+                    "audit webhook worker did not finish in {:?}, detached: {}",
+                    self.shutdown_timeout, self.url
+                );
+            }
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => {
+                if let Some(Err(_)) = self.worker.take().map(JoinHandle::join)
+                {
+                    warn!("audit webhook worker panicked: {}", self.url);
+                }
+            }
+        }
+    }
+}
+
+///
+/// 送信スレッドの本体
+///
+/// # 注記
+/// レコード数が上限に達した時点、最初のレコードの投入から
+/// `flush_interval` が経過した時点、明示的な送出要求を受けた時点、
+/// および送信口が閉じられた時点で送信待ちレコードを送出する。
+///
+fn run_worker(config: WebhookConfig, receiver: Receiver<WebhookMessage>) {
+    let client = match Client::builder().timeout(WEBHOOK_REQUEST_TIMEOUT).build()
+    {
+        Ok(client) => client,
+        Err(err) => {
+            warn!("audit webhook client build failed: {}", err);
+            return;
+        }
+    };
+
+    let mut batch: Vec<Value> = Vec::new();
+    let mut deadline: Option<Instant> = None;
+
+    loop {
+        let message = match deadline {
+            Some(deadline) => receiver.recv_timeout(
+                deadline.saturating_duration_since(Instant::now()),
+            ),
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        match message {
+            Ok(WebhookMessage::Record(record)) => {
+                if batch.is_empty() {
+                    deadline = Some(Instant::now() + config.flush_interval);
+                }
+                batch.push(record);
+                if batch.len() < config.batch_size {
+                    continue;
+                }
+            }
+            Ok(WebhookMessage::Flush) | Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                deliver_batch(&client, &config, &mut batch);
+                break;
+            }
+        }
+
+        deliver_batch(&client, &config, &mut batch);
+        deadline = None;
+    }
+}
+
+///
+/// 送信待ちレコードを JSON 配列として送出する
+///
+/// # 注記
+/// 再送で回復しうる失敗は `retry_interval` から倍々に待ち時間を延ばしながら
+/// `max_retries` 回まで再送する。最終的に送出できなかったレコードは
+/// 警告ログを出力して破棄する。
+///
+fn deliver_batch(client: &Client, config: &WebhookConfig, batch: &mut Vec<Value>) {
+    if batch.is_empty() {
+        return;
+    }
+
+    let count = batch.len();
+    let body = Value::Array(std::mem::take(batch)).to_string();
+    let mut wait = config.retry_interval;
+    let mut attempt = 0;
+
+    let reason = loop {
+        match post_batch(client, config, &body) {
+            Ok(()) => return,
+            Err(DeliveryFailure::Permanent(reason)) => break reason,
+            Err(DeliveryFailure::Retryable(reason)) => {
+                if attempt >= config.max_retries {
+                    break reason;
+                }
+            }
+        }
+
+        attempt += 1;
+        thread::sleep(wait);
+        wait = wait.saturating_mul(2);
+    };
+
+    warn!(
+        "audit webhook delivery failed, {} record(s) dropped: {}: {}",
+        count, config.url, reason
+    );
+}
+
+///
+/// 1回分の送信を行う
+///
+fn post_batch(
+    client: &Client,
+    config: &WebhookConfig,
+    body: &str,
+) -> std::result::Result<(), DeliveryFailure> {
+    let mut request = client
+        .post(&config.url)
+        .header(CONTENT_TYPE, "application/json")
+        .body(body.to_string());
+    for (name, value) in &config.headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+
+    let response = request
+        .send()
+        .map_err(|err| DeliveryFailure::Retryable(err.to_string()))?;
+    let status = response.status();
+
+    if status.is_success() {
+        Ok(())
+    } else if status.is_server_error() || status.as_u16() == 429 {
+        Err(DeliveryFailure::Retryable(format!("HTTP {}", status)))
+    } else {
+        Err(DeliveryFailure::Permanent(format!("HTTP {}", status)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    use chrono::Utc;
+
+    use super::*;
+    use crate::audit::model::{AuditOperation, AuditResult};
+
+    ///
+    /// 指定ステータスを順に返す HTTP 受信側を起動し、受信本文を返す
+    ///
+    fn spawn_collector(statuses: Vec<u16>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind failed");
+        let url = format!("http://{}/audit", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let mut bodies = Vec::new();
+            for status in statuses {
+                let (stream, _) = listener.accept().expect("accept failed");
+                let mut reader = BufReader::new(stream);
+                let mut length = 0usize;
+                let mut auth = String::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).expect("read header failed");
+                    let line = line.trim_end().to_ascii_lowercase();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.strip_prefix("content-length:") {
+                        length = value.trim().parse().expect("invalid length");
+                    }
+                    if let Some(value) = line.strip_prefix("authorization:") {
+                        auth = value.trim().to_string();
+                    }
+                }
+                let mut body = vec![0u8; length];
+                reader.read_exact(&mut body).expect("read body failed");
+                assert_eq!(auth, "bearer secret");
+                bodies.push(String::from_utf8(body).expect("invalid body"));
+
+                let mut stream = reader.into_inner();
+                write!(
+                    stream,
+                    "HTTP/1.1 {} X\r\nContent-Length: 0\r\n\
+                     Connection: close\r\n\r\n",
+                    status
+                )
+                .expect("write response failed");
+            }
+            bodies
+        });
+
+        (url, handle)
+    }
+
+    fn test_record(path: &str) -> AuditRecord {
+        AuditRecord::new(
+            AuditOperation::Update,
+            None,
+            None,
+            None,
+            Some(path.to_string()),
+            AuditResult::Success,
+            Utc::now(),
+            None,
+            None,
+        )
+    }
+
+    ///
+    /// レコードが上限件数ごとにまとめて送信され、5xx 応答の後に再送される
+    /// ことを確認する。
+    ///
+    #[test]
+    fn webhook_forwarder_batches_and_retries() {
+        let (url, collector) = spawn_collector(vec![503, 200, 200]);
+        let mut forwarder = WebhookForwarder::new(WebhookConfig {
+            url,
+            batch_size: 2,
+            flush_interval: Duration::from_secs(60),
+            max_retries: 2,
+            retry_interval: Duration::from_millis(10),
+            headers: vec![(
+                "Authorization".to_string(),
+                "Bearer secret".to_string(),
+            )],
+        })
+        .expect("create forwarder failed");
+
+        for path in ["/a", "/b", "/c"] {
+            forwarder.forward(&test_record(path)).expect("forward failed");
+        }
+        drop(forwarder);
+
+        let bodies = collector.join().expect("collector failed");
+        let batches: Vec<Vec<String>> = bodies
+            .iter()
+            .map(|body| {
+                serde_json::from_str::<Vec<Value>>(body)
+                    .expect("parse body failed")
+                    .iter()
+                    .map(|record| record["target_path"].as_str().unwrap().to_string())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(batches, vec![vec!["/a", "/b"], vec!["/a", "/b"], vec!["/c"]]);
+    }
+
+    ///
+    /// 送信先が応答しない間も投入側が待たされず、送信待ちが上限に達した
+    /// レコードは破棄され、破棄時の終了待ちが上限時間で打ち切られることを
+    /// 確認する。
+    ///
+    #[test]
+    fn webhook_forwarder_drops_records_when_queue_full() {
+        /*
+         * 接続を受け付けるだけで応答しない送信先
+         */
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind failed");
+        let url = format!("http://{}/audit", listener.local_addr().unwrap());
+        let (release, released) = mpsc::channel::<()>();
+        let stalled = thread::spawn(move || {
+            let (_stream, _) = listener.accept().expect("accept failed");
+            let _ = released.recv();
+        });
+
+        let mut forwarder = WebhookForwarder::spawn(
+            WebhookConfig {
+                url,
+                batch_size: 1,
+                flush_interval: Duration::from_secs(60),
+                max_retries: 0,
+                retry_interval: Duration::from_millis(10),
+                headers: Vec::new(),
+            },
+            1,
+            Duration::from_millis(100),
+        )
+        .expect("create forwarder failed");
+
+        let started = Instant::now();
+        for index in 0..32 {
+            forwarder
+                .forward(&test_record(&format!("/page{}", index)))
+                .expect("forward failed");
+        }
+        forwarder.flush().expect("flush failed");
+        drop(forwarder);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let _ = release.send(());
+        stalled.join().expect("stalled collector failed");
+    }
+
+    ///
+    /// 不正な設定が拒否されることを確認する。
+    ///
+    #[test]
+    fn webhook_config_validate_rejects_invalid_values() {
+        let config = WebhookConfig {
+            url: "ftp://example.com/audit".to_string(),
+            batch_size: 1,
+            flush_interval: Duration::from_secs(1),
+            max_retries: 0,
+            retry_interval: Duration::from_secs(1),
+            headers: Vec::new(),
+        };
+        assert!(config.validate().is_err());
+
+        let config = WebhookConfig {
+            url: "http://example.com/audit".to_string(),
+            batch_size: 0,
+            ..config
+        };
+        assert!(config.validate().is_err());
+    }
+}
@@ -8,15 +8,30 @@
 //! 監査ログ JSONL writer の骨格を定義するモジュール
 //!
 
+use std::collections::HashMap;
 use std::fs::{self, File, OpenOptions};
 use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use tracing::{debug, warn};
 
-use super::model::AuditRecord;
+use super::chain::{
+    ChainHead,
+    last_line_hash,
+    line_hash,
+    load_chain_head,
+    recover_chain_head,
+    store_chain_head,
+};
+use super::model::{AuditOperation, AuditRecord, AuditResult};
+use super::retention::{
+    AuditRetentionPolicy,
+    RetentionSweepResult,
+    build_retention_plan,
+    execute_retention_plan,
+};
 use super::rotation::{
     AuditRotationPolicy,
     active_log_path,
@@ -48,6 +63,12 @@ pub(crate) struct AuditWriter {
 
     /// 現在のアクティブファイルサイズ
     current_size: u64,
+
+    /// ハッシュチェーンの先頭(未読込の場合は`None`)
+    chain_head: Option<ChainHead>,
+
+    /// チェーン先頭情報ファイルへの未反映の有無
+    head_dirty: bool,
 }
 
 impl std::fmt::Debug for AuditWriter {
@@ -64,6 +85,7 @@ impl std::fmt::Debug for AuditWriter {
         f.debug_struct("AuditWriter")
             .field("config", &self.config)
             .field("current_size", &self.current_size)
+            .field("chain_head", &self.chain_head)
             .finish()
     }
 }
@@ -83,6 +105,8 @@ impl AuditWriter {
             config,
             file: None,
             current_size: 0,
+            chain_head: None,
+            head_dirty: false,
         }
     }
 
@@ -103,22 +127,36 @@ impl AuditWriter {
     /// * `record` - 書き込む監査レコード
     ///
     /// # 戻り値
-    /// 現時点では骨格のため常に成功を返す。
+    /// 書込に成功した場合は`Ok(())`を返す。
+    ///
+    /// # 注記
+    /// レコードにはチェーン上の通番と直前の行の SHA-256 を付与して書き込む。
+    /// チェーンはローテーションを跨いで連続する。
     ///
     pub(crate) fn write_record(
         &mut self,
         record: &AuditRecord,
     ) -> Result<()> {
+        self.ensure_output_dir()?;
+        self.ensure_chain_head()?;
+
         /*
-         * JSONL 1 行の生成
+         * チェーン情報を付与した JSONL 1 行の生成
          */
-        let line = encode_jsonl_line(record)?;
+        let head = self
+            .chain_head
+            .clone()
+            .context("audit chain head is not loaded")?;
+        let mut chained = record.clone();
+        chained.seq = Some(head.seq + 1);
+        chained.prev_hash = Some(head.hash);
+
+        let line = encode_jsonl_line(&chained)?;
         let line_len = line.len() as u64;
 
         /*
          * アクティブファイル準備とローテーション
          */
-        self.ensure_output_dir()?;
         self.ensure_current_size()?;
         self.rotate_if_needed(line_len)?;
         self.ensure_file_opened()?;
@@ -135,6 +173,15 @@ impl AuditWriter {
             .context("audit jsonl write failed")?;
         self.current_size = self.current_size.saturating_add(line_len);
 
+        /*
+         * チェーン先頭の更新
+         */
+        self.chain_head = Some(ChainHead {
+            seq: head.seq + 1,
+            hash: line_hash(&line[..line.len() - 1]),
+        });
+        self.head_dirty = true;
+
         Ok(())
     }
 
@@ -142,13 +189,117 @@ impl AuditWriter {
     /// writer の flush
     ///
     /// # 戻り値
-    /// 現時点では骨格のため常に成功を返す。
+    /// flush に成功した場合は`Ok(())`を返す。
+    ///
+    /// # 注記
+    /// 書込済みの行を確定させた後に、チェーン先頭情報ファイルを更新する。
     ///
     pub(crate) fn flush(&mut self) -> Result<()> {
         if let Some(writer) = self.file.as_mut() {
             writer.flush().context("audit writer flush failed")?;
         }
 
+        if self.head_dirty {
+            if let Some(head) = self.chain_head.as_ref() {
+                store_chain_head(self.output_dir(), head)?;
+            }
+            self.head_dirty = false;
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// 保持期間を超過したログファイルを削除する
+    ///
+    /// # 引数
+    /// * `policy` - 保持削除設定
+    /// * `now` - 基準時刻
+    ///
+    /// # 戻り値
+    /// 削除結果を返す。
+    ///
+    /// # 注記
+    /// ファイルを削除した場合は、削除したファイルの末尾の行のハッシュを
+    /// `chain_anchor` として持つ `retention` レコードをチェーンへ書き込む。
+    /// 検証時はこのアンカーを残存する最古の行の接続先として扱う。
+    ///
+    pub(crate) fn apply_retention(
+        &mut self,
+        policy: &AuditRetentionPolicy,
+        now: DateTime<Utc>,
+    ) -> Result<RetentionSweepResult> {
+        if !self.output_dir().exists() {
+            return Ok(RetentionSweepResult::default());
+        }
+
+        /*
+         * 削除前にチェーン先頭とアンカー候補を確定
+         */
+        self.ensure_chain_head()?;
+
+        let plan = build_retention_plan(self.output_dir(), policy, now)?;
+        let mut anchors = HashMap::new();
+        for path in &plan.delete_candidates {
+            if let Some(hash) = last_line_hash(path)? {
+                anchors.insert(path.clone(), hash);
+            }
+        }
+
+        /*
+         * 削除の実行とアンカーの記録
+         */
+        let result = execute_retention_plan(plan);
+        if result.deleted_files.is_empty() {
+            return Ok(result);
+        }
+
+        let anchor = result
+            .deleted_files
+            .iter()
+            .filter(|path| anchors.contains_key(*path))
+            .max()
+            .and_then(|path| anchors.get(path).cloned());
+
+        let mut record = AuditRecord::new(
+            AuditOperation::Retention,
+            None,
+            None,
+            None,
+            None,
+            AuditResult::Success,
+            now,
+            Some(format!("deleted {} file(s)", result.deleted_files.len())),
+            None,
+        );
+        record.chain_anchor = anchor;
+
+        self.write_record(&record)?;
+        self.flush()?;
+
+        Ok(result)
+    }
+
+    ///
+    /// チェーン先頭を用意する
+    ///
+    /// # 戻り値
+    /// 準備に成功した場合は`Ok(())`を返す。
+    ///
+    /// # 注記
+    /// 未読込の場合はログファイルと先頭情報ファイルから復元する。読込済みでも
+    /// 他プロセス(CLI とサーバ)が先頭情報を進めている場合はそちらへ追従する。
+    ///
+    fn ensure_chain_head(&mut self) -> Result<()> {
+        let head = match self.chain_head.take() {
+            None => recover_chain_head(self.output_dir())?,
+            Some(cached) => match load_chain_head(self.output_dir())? {
+                Some(stored) if stored.seq > cached.seq => stored,
+                _ => cached,
+            },
+        };
+        self.chain_head = Some(head);
+
         Ok(())
     }
 
@@ -296,7 +447,7 @@ mod tests {
     fn test_record(path: &str) -> AuditRecord {
         AuditRecord::new(
             AuditOperation::Create,
-            UserId::new(),
+            Some(UserId::new()),
             Some(TokenId::new()),
             None,
             Some(path.to_string()),
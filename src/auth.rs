@@ -8,18 +8,36 @@
 //! REST API / MCP で共有する認証コアを提供するモジュール
 //!
 
-use anyhow::Result;
-use chrono::{DateTime, Local};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Duration, Local};
+use rand_core::{OsRng, RngCore};
 
 use crate::database::{DatabaseManager, VerifyBearerTokenFailureReason};
 use crate::database::types::{
     BearerScopeSet,
     BearerTokenPlaintext,
+    LockoutPolicy,
     PathPrefixSet,
     TokenId,
     UserAttributeSet,
 };
 
+/// 二要素認証済みセッションの有効期間(時間)
+const MFA_SESSION_TTL_HOURS: i64 = 12;
+
+/// 二要素認証済みセッションIDの乱数バイト長
+const MFA_SESSION_ID_BYTES: usize = 32;
+
+/// 接続元アドレス単位のログイン失敗記録を保持する期間(時間)
+const LOGIN_FAILURE_RETENTION_HOURS: i64 = 24;
+
+/// パスワードポリシーで判定する文字種の数
+pub(crate) const PASSWORD_CHAR_CLASS_COUNT: u32 = 4;
+
 ///
 /// 認証済みユーザ情報
 ///
@@ -51,6 +69,76 @@ pub(crate) struct BearerAuthSuccess {
     updated_expire_at: Option<DateTime<Local>>,
 }
 
+///
+/// 二要素認証済みセッション情報
+///
+#[derive(Clone, Debug)]
+struct MfaSession {
+    /// セッションを確立したユーザ名
+    user_name: String,
+
+    /// 発行時刻
+    issued_at: DateTime<Local>,
+
+    /// 有効期限
+    expire_at: DateTime<Local>,
+}
+
+///
+/// 二要素認証済みセッションの管理
+///
+/// # 注記
+/// Basic認証はリクエスト毎に資格情報を送るため、TOTP コードを毎回要求すると
+/// コードの有効期間が切れた時点で再入力が必要になる。コード照合に成功した
+/// 時点でセッションIDを発行し、以降は Cookie で提示されたセッションIDにより
+/// 二要素目の照合を省略する。セッションはプロセス内にのみ保持し、サーバ再起動
+/// で失効する。
+///
+#[derive(Debug, Default)]
+pub(crate) struct MfaSessionStore {
+    sessions: Mutex<HashMap<String, MfaSession>>,
+}
+
+///
+/// パスワードの強度要件
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct PasswordPolicy {
+    /// 最小文字数
+    min_length: usize,
+
+    /// 含めるべき文字種(英小文字・英大文字・数字・記号)の最小数
+    min_char_classes: u32,
+}
+
+///
+/// 接続元アドレス単位のログイン失敗記録
+///
+#[derive(Clone, Debug)]
+struct LoginFailureEntry {
+    /// 連続失敗回数
+    failures: u32,
+
+    /// 最終失敗日時
+    last_failed_at: DateTime<Local>,
+
+    /// ロックアウト解除日時
+    locked_until: Option<DateTime<Local>>,
+}
+
+///
+/// 接続元アドレス単位のログイン失敗の管理
+///
+/// # 注記
+/// 存在しないユーザ名を総当たりされた場合はユーザ単位の失敗記録が残らない
+/// ため、接続元アドレス単位でも失敗回数を数える。記録はプロセス内にのみ保持
+/// し、サーバ再起動で消去される。
+///
+#[derive(Debug, Default)]
+pub(crate) struct LoginFailureTracker {
+    entries: Mutex<HashMap<IpAddr, LoginFailureEntry>>,
+}
+
 impl AuthUser {
     ///
     /// 認証済みユーザ情報の生成
@@ -251,6 +339,245 @@ impl BearerAuthSuccess {
     }
 }
 
+impl MfaSessionStore {
+    ///
+    /// セッション管理オブジェクトの生成
+    ///
+    /// # 戻り値
+    /// セッションを持たない管理オブジェクトを返す。
+    ///
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// セッションの発行
+    ///
+    /// # 引数
+    /// * `user_name` - セッションを確立したユーザ名
+    /// * `now` - 発行時刻
+    ///
+    /// # 戻り値
+    /// 発行したセッションIDを返す。
+    ///
+    /// # 注記
+    /// 発行時に期限切れセッションを併せて破棄する。
+    ///
+    pub(crate) fn issue(&self, user_name: &str, now: DateTime<Local>) -> String {
+        let mut raw = [0u8; MFA_SESSION_ID_BYTES];
+        OsRng.fill_bytes(&mut raw);
+        let session_id =
+            raw.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        let mut sessions = self
+            .sessions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        sessions.retain(|_, session| session.expire_at > now);
+        sessions.insert(
+            session_id.clone(),
+            MfaSession {
+                user_name: user_name.to_string(),
+                issued_at: now,
+                expire_at: now + Duration::hours(MFA_SESSION_TTL_HOURS),
+            },
+        );
+
+        session_id
+    }
+
+    ///
+    /// セッションの有効性判定
+    ///
+    /// # 引数
+    /// * `session_id` - 判定対象のセッションID
+    /// * `user_name` - リクエストのユーザ名
+    /// * `not_before` - 有効とみなす発行時刻の下限(二要素認証の登録時刻)
+    /// * `now` - 判定基準時刻
+    ///
+    /// # 戻り値
+    /// 同一ユーザ向けの有効期限内セッションである場合は `true` を返す。
+    ///
+    /// # 注記
+    /// 秘密鍵を再発行した場合は、それ以前に発行したセッションを無効とする。
+    ///
+    pub(crate) fn is_valid(
+        &self,
+        session_id: &str,
+        user_name: &str,
+        not_before: DateTime<Local>,
+        now: DateTime<Local>,
+    ) -> bool {
+        let sessions = self
+            .sessions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        sessions.get(session_id).is_some_and(|session| {
+            session.user_name == user_name
+                && session.issued_at >= not_before
+                && session.expire_at > now
+        })
+    }
+
+    ///
+    /// 指定ユーザのセッションを全て破棄する
+    ///
+    /// # 引数
+    /// * `user_name` - 対象のユーザ名
+    ///
+    pub(crate) fn revoke_user(&self, user_name: &str) {
+        let mut sessions = self
+            .sessions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        sessions.retain(|_, session| session.user_name != user_name);
+    }
+}
+
+impl PasswordPolicy {
+    ///
+    /// パスワードの強度要件の生成
+    ///
+    /// # 引数
+    /// * `min_length` - 最小文字数
+    /// * `min_char_classes` - 含めるべき文字種の最小数
+    ///
+    /// # 戻り値
+    /// 生成した強度要件を返す。
+    ///
+    pub(crate) fn new(min_length: usize, min_char_classes: u32) -> Self {
+        Self {
+            min_length,
+            min_char_classes,
+        }
+    }
+
+    ///
+    /// パスワードが強度要件を満たすかを検証する
+    ///
+    /// # 引数
+    /// * `password` - 検証対象のパスワード
+    ///
+    /// # 戻り値
+    /// 要件を満たす場合は`Ok(())`を返す。満たさない場合は理由を示すエラーを
+    /// 返す。
+    ///
+    /// # 注記
+    /// 文字数は Unicode スカラー値の個数で数え、英字・数字以外の文字は全て
+    /// 記号として扱う。
+    ///
+    pub(crate) fn validate(&self, password: &str) -> Result<()> {
+        if password.chars().count() < self.min_length {
+            return Err(anyhow!(
+                "password must be at least {} characters",
+                self.min_length
+            ));
+        }
+
+        let classes = [
+            password.chars().any(|c| c.is_ascii_lowercase()),
+            password.chars().any(|c| c.is_ascii_uppercase()),
+            password.chars().any(|c| c.is_ascii_digit()),
+            password.chars().any(|c| !c.is_ascii_alphanumeric()),
+        ];
+        let count = classes.iter().filter(|present| **present).count() as u32;
+        if count < self.min_char_classes {
+            return Err(anyhow!(
+                "password must contain at least {} of lowercase, \
+                 uppercase, digit and symbol characters",
+                self.min_char_classes
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl LoginFailureTracker {
+    ///
+    /// ログイン失敗管理オブジェクトの生成
+    ///
+    /// # 戻り値
+    /// 失敗記録を持たない管理オブジェクトを返す。
+    ///
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// ロックアウト解除日時の取得
+    ///
+    /// # 引数
+    /// * `addr` - 接続元アドレス
+    /// * `now` - 判定基準時刻
+    ///
+    /// # 戻り値
+    /// ロックアウト中の場合は解除日時を返す。
+    ///
+    pub(crate) fn locked_until(
+        &self,
+        addr: IpAddr,
+        now: DateTime<Local>,
+    ) -> Option<DateTime<Local>> {
+        let entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        entries
+            .get(&addr)
+            .and_then(|entry| entry.locked_until)
+            .filter(|until| now < *until)
+    }
+
+    ///
+    /// ログイン失敗の記録
+    ///
+    /// # 引数
+    /// * `addr` - 接続元アドレス
+    /// * `now` - 失敗日時
+    /// * `policy` - ロックアウト方針
+    ///
+    /// # 戻り値
+    /// 今回の失敗でロックアウトされた場合は解除日時を返す。
+    ///
+    /// # 注記
+    /// 記録時に保持期間を過ぎた記録を併せて破棄する。
+    ///
+    pub(crate) fn record_failure(
+        &self,
+        addr: IpAddr,
+        now: DateTime<Local>,
+        policy: &LockoutPolicy,
+    ) -> Option<DateTime<Local>> {
+        let mut entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let expire_before = now - Duration::hours(LOGIN_FAILURE_RETENTION_HOURS);
+
+        entries.retain(|_, entry| {
+            entry.last_failed_at > expire_before
+                || entry.locked_until.is_some_and(|until| now < until)
+        });
+
+        let entry = entries.entry(addr).or_insert(LoginFailureEntry {
+            failures: 0,
+            last_failed_at: now,
+            locked_until: None,
+        });
+        entry.failures = entry.failures.saturating_add(1);
+        entry.last_failed_at = now;
+
+        let until = now + policy.lockout_duration(entry.failures)?;
+        entry.locked_until = Some(until);
+        Some(until)
+    }
+
+}
+
 ///
 /// 共通 Bearer認証コア
 ///
@@ -280,6 +607,7 @@ pub(crate) fn authenticate_bearer_token(
     let token_id = token_info.token_id();
     let updated_expire_at =
         db.extend_bearer_token_ttl_if_needed(&token_id, now)?;
+    db.record_user_activity(&user_info.username(), now)?;
     let auth = AuthContext::new_with_attributes(
         AuthUser::new(user_info.username()),
         token_info.scopes(),
@@ -358,6 +686,92 @@ mod tests {
         fs::remove_dir_all(base_dir).expect("cleanup failed");
     }
 
+    ///
+    /// パスワードの強度要件が文字数と文字種の数を判定することを確認する。
+    ///
+    /// # 注記
+    /// `cargo test password_policy_checks_length_and_classes -- --exact`
+    /// で実行する。
+    ///
+    #[test]
+    fn password_policy_checks_length_and_classes() {
+        let policy = PasswordPolicy::new(8, 3);
+
+        assert!(policy.validate("Abc12345").is_ok());
+        assert!(policy.validate("abc-1234").is_ok());
+        assert!(policy.validate("Ab1-").is_err());
+        assert!(policy.validate("password123").is_err());
+
+        let policy = PasswordPolicy::new(4, 1);
+        assert!(policy.validate("ぱすわーど").is_ok());
+        assert!(policy.validate("abc").is_err());
+    }
+
+    ///
+    /// 接続元アドレス単位の失敗記録が閾値到達でロックし期限で解除されることを
+    /// 確認する。
+    ///
+    /// # 注記
+    /// `cargo test login_failure_tracker_locks_address -- --exact`
+    /// で実行する。
+    ///
+    #[test]
+    fn login_failure_tracker_locks_address() {
+        let tracker = LoginFailureTracker::new();
+        let policy = LockoutPolicy::new(
+            2,
+            chrono::Duration::seconds(10),
+            chrono::Duration::seconds(60),
+        );
+        let addr: IpAddr = "192.0.2.1".parse().expect("parse addr failed");
+        let other: IpAddr = "192.0.2.2".parse().expect("parse addr failed");
+        let now = Local::now();
+
+        assert_eq!(tracker.record_failure(addr, now, &policy), None);
+        assert_eq!(tracker.locked_until(addr, now), None);
+
+        let until = now + chrono::Duration::seconds(10);
+        assert_eq!(tracker.record_failure(addr, now, &policy), Some(until));
+        assert_eq!(tracker.locked_until(addr, now), Some(until));
+        assert_eq!(tracker.locked_until(addr, until), None);
+        assert_eq!(tracker.locked_until(other, now), None);
+    }
+
+    ///
+    /// 二要素認証済みセッションがユーザと有効期限に束縛されることを確認する。
+    ///
+    /// # 注記
+    /// `cargo test mfa_session_store_binds_user_and_expiry -- --exact`
+    /// で実行する。
+    ///
+    #[test]
+    fn mfa_session_store_binds_user_and_expiry() {
+        let store = MfaSessionStore::new();
+        let now = Local::now();
+        let session_id = store.issue("alice", now);
+
+        let enrolled_at = now - chrono::Duration::minutes(1);
+
+        assert!(store.is_valid(&session_id, "alice", enrolled_at, now));
+        assert!(!store.is_valid(&session_id, "bob", enrolled_at, now));
+        assert!(!store.is_valid("unknown", "alice", enrolled_at, now));
+        assert!(!store.is_valid(
+            &session_id,
+            "alice",
+            now + chrono::Duration::minutes(1),
+            now,
+        ));
+        assert!(!store.is_valid(
+            &session_id,
+            "alice",
+            enrolled_at,
+            now + chrono::Duration::hours(MFA_SESSION_TTL_HOURS),
+        ));
+
+        store.revoke_user("alice");
+        assert!(!store.is_valid(&session_id, "alice", enrolled_at, now));
+    }
+
     fn prepare_test_dirs() -> (PathBuf, PathBuf) {
         let base = Path::new("tests").join("tmp").join(unique_suffix());
         fs::create_dir_all(&base).expect("create test dir failed");
@@ -51,6 +51,14 @@ pub(crate) enum AssetSubCommand {
     /// アセットの移動
     #[command(name = "move_to", alias = "m", alias = "mv")]
     MoveTo(AssetMoveToOpts),
+
+    /// アセット実体の整合性検査
+    #[command(name = "fsck")]
+    Fsck(AssetFsckOpts),
+
+    /// 不要なアセットファイルの回収
+    #[command(name = "gc")]
+    Gc(AssetGcOpts),
 }
 
 ///
@@ -575,3 +583,98 @@ impl ShowOptions for AssetMoveToOpts {
         println!("   dst_target: {}", self.dst_target());
     }
 }
+
+///
+/// サブコマンドasset_fsckのオプション
+///
+#[derive(Clone, Args, Debug)]
+pub(crate) struct AssetFsckOpts {
+    /// 修復可能な不整合を修復する
+    #[arg(long = "fix")]
+    fix: bool,
+}
+
+impl AssetFsckOpts {
+    ///
+    /// 修復指定へのアクセサ
+    ///
+    /// # 戻り値
+    /// 修復を行う場合は`true`を返す
+    ///
+    pub(crate) fn is_fix(&self) -> bool {
+        self.fix
+    }
+}
+
+// Validateトレイトの実装
+impl Validate for AssetFsckOpts {
+    fn validate(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+// ShowOptionsトレイトの実装
+impl ShowOptions for AssetFsckOpts {
+    fn show_options(&self) {
+        println!("asset fsck command options");
+        println!("   fix: {:?}", self.is_fix());
+    }
+}
+
+///
+/// サブコマンドasset_gcのオプション
+///
+#[derive(Clone, Args, Debug)]
+pub(crate) struct AssetGcOpts {
+    /// 回収対象の表示のみを行う
+    #[arg(short = 'n', long = "dry-run")]
+    dry_run: bool,
+
+    /// 参照されていないアセットを削除状態にする
+    #[arg(long = "delete-unreferenced")]
+    delete_unreferenced: bool,
+}
+
+impl AssetGcOpts {
+    ///
+    /// 表示のみ指定へのアクセサ
+    ///
+    /// # 戻り値
+    /// ファイルの削除を行わない場合は`true`を返す
+    ///
+    pub(crate) fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    ///
+    /// 参照されていないアセットの削除指定へのアクセサ
+    ///
+    /// # 戻り値
+    /// 参照されていないアセットを削除状態にする場合は`true`を返す
+    ///
+    pub(crate) fn is_delete_unreferenced(&self) -> bool {
+        self.delete_unreferenced
+    }
+}
+
+// Validateトレイトの実装
+impl Validate for AssetGcOpts {
+    fn validate(&mut self) -> Result<()> {
+        if self.dry_run && self.delete_unreferenced {
+            return Err(anyhow!(
+                "--dry-run and --delete-unreferenced are exclusive"
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+// ShowOptionsトレイトの実装
+impl ShowOptions for AssetGcOpts {
+    fn show_options(&self) {
+        println!("asset gc command options");
+        println!("   dry_run:             {:?}", self.is_dry_run());
+        println!("   delete_unreferenced: {:?}", self.is_delete_unreferenced());
+    }
+}
@@ -0,0 +1,204 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! サブコマンド"audit"のコマンドライン定義
+//!
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use clap::{Args, Subcommand};
+
+use super::{ApplyConfig, ShowOptions, Validate};
+use crate::audit::query::{parse_query_time, AuditQuery};
+use crate::cmd_args::config::Config;
+use crate::database::types::TokenId;
+use crate::rest_api::validate_page_path;
+
+#[derive(Clone, Args, Debug)]
+pub(crate) struct AuditCommand {
+    #[command(subcommand)]
+    pub(crate) subcommand: AuditSubCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub(crate) enum AuditSubCommand {
+    /// 監査ログの検索
+    #[command(name = "query", alias = "q")]
+    Query(AuditQueryOpts),
+
+    /// 監査ログのハッシュチェーンの検証
+    #[command(name = "verify", alias = "v")]
+    Verify,
+}
+
+///
+/// サブコマンドaudit_queryのオプション
+///
+#[derive(Clone, Args, Debug)]
+pub(crate) struct AuditQueryOpts {
+    /// 操作主体のユーザ名
+    #[arg(short = 'u', long = "user", value_name = "USER-NAME")]
+    user_name: Option<String>,
+
+    /// Bearer トークンID
+    #[arg(short = 't', long = "token", value_name = "TOKEN-ID")]
+    token_id: Option<String>,
+
+    /// 対象 path の prefix
+    #[arg(short = 'p', long = "path-prefix", value_name = "PATH")]
+    path_prefix: Option<String>,
+
+    /// 操作種別 (例: delete, rename, login)
+    #[arg(short = 'o', long = "operation", value_name = "OPERATION")]
+    operation: Option<String>,
+
+    /// 検索範囲の開始時刻 (RFC3339、YYYY-MM-DD または Nd/Nh/Nm)
+    #[arg(long = "since", value_name = "TIME")]
+    since: Option<String>,
+
+    /// 検索範囲の終了時刻 (RFC3339、YYYY-MM-DD または Nd/Nh/Nm)
+    #[arg(long = "until", value_name = "TIME")]
+    until: Option<String>,
+
+    /// 操作結果分類 (例: success, scope_denied)
+    #[arg(short = 'r', long = "result", value_name = "RESULT")]
+    result: Option<String>,
+
+    /// 詳細情報で表示
+    #[arg(short = 'l', long = "long-info")]
+    long_info: bool,
+
+    /// JSON Lines 形式で出力
+    #[arg(long = "json")]
+    json: bool,
+
+    /// 検証済みの検索条件 (ユーザ条件を除く)
+    #[arg(skip)]
+    query: Box<AuditQuery>,
+}
+
+impl AuditQueryOpts {
+    ///
+    /// ユーザ名指定へのアクセサ
+    ///
+    /// # 戻り値
+    /// `--user` で指定されたユーザ名を返す。
+    ///
+    pub(crate) fn user_name(&self) -> Option<String> {
+        self.user_name.clone()
+    }
+
+    ///
+    /// 検索条件へのアクセサ
+    ///
+    /// # 戻り値
+    /// 検証時に解析した検索条件を返す。
+    ///
+    /// # 注記
+    /// ユーザ条件はデータベースでの名前解決が必要なため含まない。
+    ///
+    pub(crate) fn query(&self) -> AuditQuery {
+        (*self.query).clone()
+    }
+
+    ///
+    /// 詳細表示指定へのアクセサ
+    ///
+    /// # 戻り値
+    /// 詳細表示が指定されている場合はtrue
+    ///
+    pub(crate) fn is_long_info(&self) -> bool {
+        self.long_info
+    }
+
+    ///
+    /// JSON出力指定へのアクセサ
+    ///
+    /// # 戻り値
+    /// `--json` が指定されている場合はtrue
+    ///
+    pub(crate) fn is_json(&self) -> bool {
+        self.json
+    }
+}
+
+// Validateトレイトの実装
+impl Validate for AuditQueryOpts {
+    fn validate(&mut self) -> Result<()> {
+        let now = Utc::now();
+        let mut query = AuditQuery::default();
+
+        /*
+         * 識別子・分類指定の解析
+         */
+        if let Some(token_id) = &self.token_id {
+            query.token_id = Some(
+                TokenId::from_string(token_id.trim())
+                    .map_err(|_| anyhow!("invalid token id: {}", token_id))?,
+            );
+        }
+
+        if let Some(prefix) = &self.path_prefix {
+            let prefix = prefix.trim();
+            if let Err(message) = validate_page_path(prefix) {
+                return Err(anyhow!("invalid path prefix: {}", message));
+            }
+            query.path_prefix = Some(prefix.to_string());
+        }
+
+        if let Some(operation) = &self.operation {
+            query.operation = Some(operation.trim().parse()?);
+        }
+
+        if let Some(result) = &self.result {
+            query.result = Some(result.trim().parse()?);
+        }
+
+        /*
+         * 検索範囲の解析
+         */
+        if let Some(since) = &self.since {
+            query.since = Some(parse_query_time(since, now)?);
+        }
+
+        if let Some(until) = &self.until {
+            query.until = Some(parse_query_time(until, now)?);
+        }
+
+        let reversed = query
+            .since
+            .zip(query.until)
+            .is_some_and(|(since, until)| since >= until);
+        if reversed {
+            return Err(anyhow!("--since must be earlier than --until"));
+        }
+
+        *self.query = query;
+        Ok(())
+    }
+}
+
+// ApplyConfigトレイトの実装
+impl ApplyConfig for AuditQueryOpts {
+    fn apply_config(&mut self, _config: &Config) {}
+}
+
+// ShowOptionsトレイトの実装
+impl ShowOptions for AuditQueryOpts {
+    fn show_options(&self) {
+        println!("audit query command options");
+        println!("   user_name:   {:?}", self.user_name());
+        println!("   token_id:    {:?}", self.token_id);
+        println!("   path_prefix: {:?}", self.path_prefix);
+        println!("   operation:   {:?}", self.operation);
+        println!("   since:       {:?}", self.since);
+        println!("   until:       {:?}", self.until);
+        println!("   result:      {:?}", self.result);
+        println!("   long_info:   {:?}", self.is_long_info());
+        println!("   json:        {:?}", self.is_json());
+    }
+}
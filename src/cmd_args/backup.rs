@@ -0,0 +1,245 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! サブコマンド"backup"のコマンドライン定義
+//!
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use chrono::DateTime;
+use clap::Args;
+
+use super::{ApplyConfig, ShowOptions, Validate};
+use crate::cmd_args::config::Config;
+use crate::cmd_args::default_cert_path;
+use crate::database::types::Id;
+
+/// 接続先サーバのデフォルトポート
+const DEFAULT_SERVER_PORT: u16 = 8080;
+
+///
+/// サブコマンドbackupのオプション
+///
+#[derive(Clone, Args, Debug)]
+pub(crate) struct BackupOpts {
+    /// 稼働中のサーバ経由でバックアップを取得
+    #[arg(short = 'r', long = "via-server")]
+    via_server: bool,
+
+    /// 接続先サーバのURL
+    #[arg(long = "url", value_name = "URL")]
+    url: Option<String>,
+
+    /// サーバへの認証に用いるユーザ名
+    #[arg(short = 'u', long = "user", value_name = "USER")]
+    user_name: Option<String>,
+
+    /// TLS用のサーバ証明書ファイルのパス
+    #[arg(short = 'C', long = "cert", value_name = "FILE")]
+    cert_path: Option<PathBuf>,
+
+    /// サーバ証明書の検証を省略
+    #[arg(short = 'k', long = "insecure")]
+    insecure: bool,
+
+    /// 差分 backup の起点 (RFC3339 日時またはエクスポートID)
+    #[arg(long = "since", value_name = "SINCE")]
+    since: Option<String>,
+
+    /// ZIP パスワード
+    #[arg(short = 'p', long = "password", value_name = "PASSWORD")]
+    password: Option<String>,
+
+    /// 出力先 ZIP パス、"-" は標準出力
+    #[arg()]
+    output: String,
+
+    /// 設定ファイルから導出した接続先サーバのURL
+    #[arg(skip)]
+    config_url: Option<String>,
+
+    /// 設定ファイルで指定されたサーバ証明書ファイルのパス
+    #[arg(skip)]
+    config_cert_path: Option<PathBuf>,
+}
+
+impl BackupOpts {
+    ///
+    /// サーバ経由指定へのアクセサ
+    ///
+    pub(crate) fn is_via_server(&self) -> bool {
+        self.via_server
+    }
+
+    ///
+    /// 接続先サーバのURLへのアクセサ
+    ///
+    /// # 戻り値
+    /// 明示指定、設定ファイル由来、デフォルトの順に解決したURLを返す。
+    ///
+    pub(crate) fn url(&self) -> String {
+        self.url
+            .clone()
+            .or_else(|| self.config_url.clone())
+            .unwrap_or_else(|| {
+                format!("http://localhost:{}", DEFAULT_SERVER_PORT)
+            })
+    }
+
+    ///
+    /// 認証ユーザ名へのアクセサ
+    ///
+    pub(crate) fn user_name(&self) -> Option<String> {
+        self.user_name.clone()
+    }
+
+    ///
+    /// サーバ証明書ファイルのパスへのアクセサ
+    ///
+    pub(crate) fn cert_path(&self) -> PathBuf {
+        self.cert_path
+            .clone()
+            .or_else(|| self.config_cert_path.clone())
+            .unwrap_or_else(default_cert_path)
+    }
+
+    ///
+    /// 証明書検証省略指定へのアクセサ
+    ///
+    pub(crate) fn is_insecure(&self) -> bool {
+        self.insecure
+    }
+
+    ///
+    /// 差分起点指定へのアクセサ
+    ///
+    pub(crate) fn since(&self) -> Option<String> {
+        self.since.clone()
+    }
+
+    ///
+    /// パスワード指定へのアクセサ
+    ///
+    pub(crate) fn password(&self) -> Option<String> {
+        self.password.clone()
+    }
+
+    ///
+    /// 出力先へのアクセサ
+    ///
+    pub(crate) fn output(&self) -> String {
+        self.output.clone()
+    }
+}
+
+impl ShowOptions for BackupOpts {
+    fn show_options(&self) {
+        println!("backup command options");
+        println!("   via_server: {}", self.is_via_server());
+        println!("   url:        {}", self.url());
+        println!(
+            "   user:       {}",
+            self.user_name.as_deref().unwrap_or("(none)")
+        );
+        println!("   cert_path:  {}", self.cert_path().display());
+        println!("   insecure:   {}", self.is_insecure());
+        println!(
+            "   since:      {}",
+            self.since.as_deref().unwrap_or("(none)")
+        );
+        println!("   password:   {}", self.password.is_some());
+        println!("   output:     {}", self.output());
+    }
+}
+
+// Validateトレイトの実装
+impl Validate for BackupOpts {
+    fn validate(&mut self) -> Result<()> {
+        if self.output.trim().is_empty() {
+            return Err(anyhow!("output path is empty"));
+        }
+
+        /*
+         * サーバ接続関連オプションの整合性を確認
+         */
+        if self.via_server {
+            if self.user_name.is_none() {
+                return Err(anyhow!("--via-server requires --user"));
+            }
+
+            let url = self.url();
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(anyhow!("invalid server url: {}", url));
+            }
+        } else if self.url.is_some()
+            || self.user_name.is_some()
+            || self.cert_path.is_some()
+            || self.insecure
+        {
+            return Err(anyhow!(
+                "--url, --user, --cert and --insecure require --via-server"
+            ));
+        }
+
+        /*
+         * 差分起点の書式を確認
+         */
+        if let Some(since) = self.since.as_deref() {
+            let valid = DateTime::parse_from_rfc3339(since).is_ok()
+                || Id::from_string(since).is_ok();
+            if !valid {
+                return Err(anyhow!("invalid since value: {}", since));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// ApplyConfigトレイトの実装
+impl ApplyConfig for BackupOpts {
+    ///
+    /// backup サブコマンドへ設定ファイルの値を反映
+    ///
+    /// # 引数
+    /// * `config` - 読み込み済み設定
+    ///
+    /// # 注記
+    /// 接続先URLとサーバ証明書は run サブコマンドの設定から導出する。
+    ///
+    fn apply_config(&mut self, config: &Config) {
+        let scheme = if config.use_tls().unwrap_or(false) {
+            "https"
+        } else {
+            "http"
+        };
+        let host = match config.run_bind_addr() {
+            Some(addr) if is_wildcard_addr(&addr) => "localhost".to_string(),
+            Some(addr) if addr.contains(':') => format!("[{}]", addr),
+            Some(addr) => addr,
+            None => "localhost".to_string(),
+        };
+        let port = config.run_bind_port().unwrap_or(DEFAULT_SERVER_PORT);
+        self.config_url = Some(format!("{}://{}:{}", scheme, host, port));
+
+        self.config_cert_path = config.server_cert();
+    }
+}
+
+///
+/// 全アドレス待ち受けを表すバインドアドレスか判定する
+///
+/// # 引数
+/// * `addr` - バインドアドレス
+///
+/// # 戻り値
+/// `0.0.0.0` や `::` の場合は true を返す。
+///
+fn is_wildcard_addr(addr: &str) -> bool {
+    matches!(addr, "0.0.0.0" | "::")
+}
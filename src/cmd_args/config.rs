@@ -8,11 +8,14 @@
 //! コンフィギュレーション情報の定義
 //!
 
+use std::collections::BTreeMap;
 use std::default::Default;
 use std::ffi::OsString;
 use std::path::{Component, Path, PathBuf};
+use std::time::Duration as StdDuration;
 
 use anyhow::{anyhow, Result};
+use chrono::Duration;
 use serde::{Deserialize, Serialize};
 
 use super::{
@@ -25,18 +28,43 @@ use super::{
     default_assets_path,
     default_db_path,
     default_fts_index_path,
+    default_git_sync_repository_path,
     default_log_path,
     DEFAULT_MCP_AUTHORITY,
     DEFAULT_AUDIT_RETENTION_TEXT,
     DEFAULT_AUDIT_ROTATE_SIZE_TEXT,
     LogLevel,
 };
+use crate::audit::forward::AuditOutputConfig;
+use crate::audit::syslog::{
+    DEFAULT_SYSLOG_FACILITY,
+    SyslogConfig,
+    SyslogProtocol,
+    parse_syslog_facility,
+};
+use crate::audit::webhook::{
+    DEFAULT_WEBHOOK_BATCH_SIZE,
+    DEFAULT_WEBHOOK_FLUSH_INTERVAL_SECS,
+    DEFAULT_WEBHOOK_MAX_RETRIES,
+    DEFAULT_WEBHOOK_RETRY_INTERVAL_SECS,
+    WebhookConfig,
+};
+use crate::auth::{PASSWORD_CHAR_CLASS_COUNT, PasswordPolicy};
+use crate::database::types::LockoutPolicy;
 
 const DEFAULT_FRONTEND_UI_FONT: &str = "sans-serif";
 const DEFAULT_FRONTEND_MD_FONT_SANS: &str = "sans-serif";
 const DEFAULT_FRONTEND_MD_FONT_SERIF: &str = "serif";
 const DEFAULT_FRONTEND_MD_FONT_MONO: &str = "monospace";
 const DEFAULT_FRONTEND_MD_CODE_FONT: &str = "monospace";
+// パスワードの強度要件は既定では課さない (config.toml の [auth] で有効化する)
+const DEFAULT_AUTH_PASSWORD_MIN_LENGTH: usize = 0;
+const DEFAULT_AUTH_PASSWORD_MIN_CLASSES: u32 = 0;
+const DEFAULT_AUTH_LOCKOUT_THRESHOLD: u32 = 5;
+const DEFAULT_AUTH_LOCKOUT_BASE_SECS: i64 = 30;
+const DEFAULT_AUTH_LOCKOUT_MAX_SECS: i64 = 3600;
+const DEFAULT_AUTH_IP_LOCKOUT_THRESHOLD: u32 = 20;
+const DEFAULT_AUTH_TOKEN_ROTATION_GRACE_SECS: i64 = 86400;
 
 ///
 /// コンフィギュレーションデータを集約する構造体
@@ -67,8 +95,17 @@ pub(super) struct Config {
     /// ftsサブコマンド用の設定
     fts: Option<FtsSection>,
 
+    /// git-syncサブコマンド用の設定
+    git_sync: Option<GitSyncSection>,
+
     /// frontend設定
     frontend: Option<FrontendSection>,
+
+    /// 認証設定
+    auth: Option<AuthSection>,
+
+    /// 監査ログ出力先設定
+    audit_output: Option<AuditOutputSection>,
 }
 
 impl Config {
@@ -192,6 +229,22 @@ impl Config {
         run.server_cert = Some(path);
     }
 
+    ///
+    /// runサブコマンドのアセット定期回収間隔を更新
+    ///
+    pub(super) fn set_run_asset_gc_interval(&mut self, interval: String) {
+        let run = self.ensure_run();
+        run.asset_gc_interval = Some(interval);
+    }
+
+    ///
+    /// runサブコマンドのGit定期同期間隔を更新
+    ///
+    pub(super) fn set_run_git_sync_interval(&mut self, interval: String) {
+        let run = self.ensure_run();
+        run.git_sync_interval = Some(interval);
+    }
+
     ///
     /// runサブコマンドのMCP有効化フラグを更新
     ///
@@ -200,6 +253,14 @@ impl Config {
         run.use_mcp = Some(use_mcp);
     }
 
+    ///
+    /// runサブコマンドの共同編集有効化フラグを更新
+    ///
+    pub(super) fn set_run_use_collab(&mut self, use_collab: bool) {
+        let run = self.ensure_run();
+        run.use_collab = Some(use_collab);
+    }
+
     ///
     /// runサブコマンドのMCP authorityを更新
     ///
@@ -346,6 +407,24 @@ impl Config {
         let search = self.ensure_fts_search();
         search.all_revision = Some(value);
     }
+
+    ///
+    /// git-syncサブコマンドの同期リポジトリパスを更新
+    ///
+    pub(super) fn set_git_sync_repository(&mut self, path: PathBuf) {
+        if self.git_sync.is_none() {
+            self.git_sync = Some(GitSyncSection {
+                repository: None,
+                user: None,
+            });
+        }
+
+        let git_sync = self
+            .git_sync
+            .as_mut()
+            .expect("git_sync must be initialized");
+        git_sync.repository = Some(path);
+    }
     ///
     /// データベースファイルへのパスへのアクセサ
     ///
@@ -539,6 +618,33 @@ impl Config {
             .and_then(|run| run.mcp_authority.clone())
     }
 
+    ///
+    /// runサブコマンドの共同編集有効化フラグへのアクセサ
+    ///
+    pub(super) fn run_use_collab(&self) -> Option<bool> {
+        self.run
+            .as_ref()
+            .and_then(|run| run.use_collab)
+    }
+
+    ///
+    /// runサブコマンドのアセット定期回収間隔へのアクセサ
+    ///
+    pub(super) fn run_asset_gc_interval(&self) -> Option<String> {
+        self.run
+            .as_ref()
+            .and_then(|run| run.asset_gc_interval.clone())
+    }
+
+    ///
+    /// runサブコマンドのGit定期同期間隔へのアクセサ
+    ///
+    pub(super) fn run_git_sync_interval(&self) -> Option<String> {
+        self.run
+            .as_ref()
+            .and_then(|run| run.git_sync_interval.clone())
+    }
+
     ///
     /// user listサブコマンドのソートモードへのアクセサ
     ///
@@ -679,6 +785,25 @@ impl Config {
             .and_then(|list| list.long_info)
     }
 
+    ///
+    /// git-syncサブコマンドの同期リポジトリパスへのアクセサ
+    ///
+    pub(super) fn git_sync_repository(&self) -> Option<PathBuf> {
+        self.git_sync
+            .as_ref()
+            .and_then(|git_sync| git_sync.repository.as_ref())
+            .map(|path| self.resolve_path(path))
+    }
+
+    ///
+    /// git-syncサブコマンドの代替ユーザ名へのアクセサ
+    ///
+    pub(super) fn git_sync_user(&self) -> Option<String> {
+        self.git_sync
+            .as_ref()
+            .and_then(|git_sync| git_sync.user.clone())
+    }
+
     ///
     /// fts searchサブコマンドの検索対象へのアクセサ
     ///
@@ -750,6 +875,172 @@ impl Config {
         }
     }
 
+    ///
+    /// 認証設定へのアクセサ
+    ///
+    /// # 戻り値
+    /// 解決済みの認証設定を返す。設定値が不正な場合はエラーを返す。
+    ///
+    pub(super) fn auth_config(&self) -> Result<AuthConfig> {
+        /*
+         * 認証設定の既定値とユーザ設定を取得
+         */
+        let auth = self.auth.as_ref();
+
+        let password_min_length = auth
+            .and_then(|section| section.password_min_length)
+            .unwrap_or(DEFAULT_AUTH_PASSWORD_MIN_LENGTH);
+        let password_min_classes = auth
+            .and_then(|section| section.password_min_classes)
+            .unwrap_or(DEFAULT_AUTH_PASSWORD_MIN_CLASSES);
+        let lockout_threshold = auth
+            .and_then(|section| section.lockout_threshold)
+            .unwrap_or(DEFAULT_AUTH_LOCKOUT_THRESHOLD);
+        let lockout_base_secs = auth
+            .and_then(|section| section.lockout_base_secs)
+            .unwrap_or(DEFAULT_AUTH_LOCKOUT_BASE_SECS);
+        let lockout_max_secs = auth
+            .and_then(|section| section.lockout_max_secs)
+            .unwrap_or(DEFAULT_AUTH_LOCKOUT_MAX_SECS);
+        let ip_lockout_threshold = auth
+            .and_then(|section| section.ip_lockout_threshold)
+            .unwrap_or(DEFAULT_AUTH_IP_LOCKOUT_THRESHOLD);
+        let token_rotation_grace_secs = auth
+            .and_then(|section| section.token_rotation_grace_secs)
+            .unwrap_or(DEFAULT_AUTH_TOKEN_ROTATION_GRACE_SECS);
+
+        /*
+         * 設定値の検証
+         */
+        if password_min_classes > PASSWORD_CHAR_CLASS_COUNT {
+            return Err(anyhow!(
+                "auth.password_min_classes must be {} or less",
+                PASSWORD_CHAR_CLASS_COUNT
+            ));
+        }
+
+        if lockout_base_secs <= 0 {
+            return Err(anyhow!(
+                "auth.lockout_base_secs must be greater than zero"
+            ));
+        }
+
+        if lockout_max_secs < lockout_base_secs {
+            return Err(anyhow!(
+                "auth.lockout_max_secs must not be less than \
+                 auth.lockout_base_secs"
+            ));
+        }
+
+        if token_rotation_grace_secs < 0 {
+            return Err(anyhow!(
+                "auth.token_rotation_grace_secs must not be negative"
+            ));
+        }
+
+        /*
+         * 解決済み設定を返却
+         */
+        let base_duration = Duration::seconds(lockout_base_secs);
+        let max_duration = Duration::seconds(lockout_max_secs);
+
+        Ok(AuthConfig {
+            password_policy: PasswordPolicy::new(
+                password_min_length,
+                password_min_classes,
+            ),
+            user_lockout: LockoutPolicy::new(
+                lockout_threshold,
+                base_duration,
+                max_duration,
+            ),
+            ip_lockout: LockoutPolicy::new(
+                ip_lockout_threshold,
+                base_duration,
+                max_duration,
+            ),
+            token_rotation_grace: Duration::seconds(
+                token_rotation_grace_secs,
+            ),
+        })
+    }
+
+    ///
+    /// 監査ログ出力先設定へのアクセサ
+    ///
+    /// # 戻り値
+    /// 解決済みの出力先設定を返す。設定値が不正な場合はエラーを返す。
+    ///
+    pub(super) fn audit_output_config(&self) -> Result<AuditOutputConfig> {
+        let section = self.audit_output.as_ref();
+
+        /*
+         * syslog 転送先の解決
+         */
+        let syslog_infos = section
+            .and_then(|section| section.syslog.as_deref())
+            .unwrap_or_default();
+        let mut syslog = Vec::new();
+        for info in syslog_infos {
+            let facility = info
+                .facility
+                .as_deref()
+                .unwrap_or(DEFAULT_SYSLOG_FACILITY);
+
+            syslog.push(SyslogConfig {
+                address: info.address.clone(),
+                protocol: info.protocol.unwrap_or_default(),
+                facility: parse_syslog_facility(facility)?,
+                hostname: info.hostname.clone(),
+            });
+        }
+
+        /*
+         * webhook 転送先の解決
+         */
+        let webhook_infos = section
+            .and_then(|section| section.webhook.as_deref())
+            .unwrap_or_default();
+        let mut webhook = Vec::new();
+        for info in webhook_infos {
+            webhook.push(WebhookConfig {
+                url: info.url.clone(),
+                batch_size: info
+                    .batch_size
+                    .unwrap_or(DEFAULT_WEBHOOK_BATCH_SIZE),
+                flush_interval: StdDuration::from_secs(
+                    info.flush_interval_secs
+                        .unwrap_or(DEFAULT_WEBHOOK_FLUSH_INTERVAL_SECS),
+                ),
+                max_retries: info
+                    .max_retries
+                    .unwrap_or(DEFAULT_WEBHOOK_MAX_RETRIES),
+                retry_interval: StdDuration::from_secs(
+                    info.retry_interval_secs
+                        .unwrap_or(DEFAULT_WEBHOOK_RETRY_INTERVAL_SECS),
+                ),
+                headers: info
+                    .headers
+                    .clone()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .collect(),
+            });
+        }
+
+        /*
+         * 設定値の検証と返却
+         */
+        let config = AuditOutputConfig {
+            file: section.and_then(|section| section.file).unwrap_or(true),
+            syslog,
+            webhook,
+        };
+        config.validate()?;
+
+        Ok(config)
+    }
+
     ///
     /// コンフィギュレーション情報の保存
     ///
@@ -881,8 +1172,11 @@ impl Config {
                 bind_port: None,
                 use_mcp: None,
                 mcp_authority: None,
+                use_collab: None,
                 use_tls: None,
                 server_cert: None,
+                asset_gc_interval: None,
+                git_sync_interval: None,
             });
         }
 
@@ -1121,8 +1415,11 @@ impl Default for Config {
                 bind_port: Some(8080),
                 use_mcp: Some(false),
                 mcp_authority: Some(DEFAULT_MCP_AUTHORITY.to_string()),
+                use_collab: Some(false),
                 use_tls: Some(false),
                 server_cert: None,
+                asset_gc_interval: None,
+                git_sync_interval: None,
             }),
 
             user: Some(UserSection {
@@ -1169,6 +1466,11 @@ impl Default for Config {
                 }),
             }),
 
+            git_sync: Some(GitSyncSection {
+                repository: Some(default_git_sync_repository_path()),
+                user: None,
+            }),
+
             frontend: Some(FrontendSection {
                 ui_font: Some(DEFAULT_FRONTEND_UI_FONT.to_string()),
                 md_font_sans: Some(DEFAULT_FRONTEND_MD_FONT_SANS.to_string()),
@@ -1176,6 +1478,24 @@ impl Default for Config {
                 md_font_mono: Some(DEFAULT_FRONTEND_MD_FONT_MONO.to_string()),
                 md_code_font: Some(DEFAULT_FRONTEND_MD_CODE_FONT.to_string()),
             }),
+
+            auth: Some(AuthSection {
+                password_min_length: Some(DEFAULT_AUTH_PASSWORD_MIN_LENGTH),
+                password_min_classes: Some(DEFAULT_AUTH_PASSWORD_MIN_CLASSES),
+                lockout_threshold: Some(DEFAULT_AUTH_LOCKOUT_THRESHOLD),
+                lockout_base_secs: Some(DEFAULT_AUTH_LOCKOUT_BASE_SECS),
+                lockout_max_secs: Some(DEFAULT_AUTH_LOCKOUT_MAX_SECS),
+                ip_lockout_threshold: Some(DEFAULT_AUTH_IP_LOCKOUT_THRESHOLD),
+                token_rotation_grace_secs: Some(
+                    DEFAULT_AUTH_TOKEN_ROTATION_GRACE_SECS,
+                ),
+            }),
+
+            audit_output: Some(AuditOutputSection {
+                file: Some(true),
+                syslog: None,
+                webhook: None,
+            }),
         }
     }
 }
@@ -1258,11 +1578,20 @@ struct RunInfo {
     /// MCP resource URI authority
     mcp_authority: Option<String>,
 
+    /// 共同編集の有効化
+    use_collab: Option<bool>,
+
     /// TLSの使用
     use_tls: Option<bool>,
 
     /// サーバ証明書ファイルのパス
     server_cert: Option<PathBuf>,
+
+    /// 不要なアセットファイルの定期回収間隔
+    asset_gc_interval: Option<String>,
+
+    /// Gitリポジトリへの定期同期間隔
+    git_sync_interval: Option<String>,
 }
 
 ///
@@ -1319,6 +1648,18 @@ struct FtsSection {
     search: Option<FtsSearchInfo>,
 }
 
+///
+/// git-syncサブコマンドの設定情報
+///
+#[derive(Debug, Deserialize, Serialize)]
+struct GitSyncSection {
+    /// 同期リポジトリのパス
+    repository: Option<PathBuf>,
+
+    /// 未登録のコミット作成者に代わって記録するユーザ名
+    user: Option<String>,
+}
+
 ///
 /// frontend設定の情報
 ///
@@ -1422,6 +1763,176 @@ impl Default for FrontendConfig {
     }
 }
 
+///
+/// 認証設定の情報
+///
+#[derive(Debug, Deserialize, Serialize)]
+struct AuthSection {
+    /// パスワードの最小文字数
+    password_min_length: Option<usize>,
+
+    /// パスワードに含めるべき文字種の最小数
+    password_min_classes: Option<u32>,
+
+    /// ユーザ単位でロックアウトを開始する連続失敗回数
+    lockout_threshold: Option<u32>,
+
+    /// 初回ロックアウト期間(秒)
+    lockout_base_secs: Option<i64>,
+
+    /// ロックアウト期間の上限(秒)
+    lockout_max_secs: Option<i64>,
+
+    /// 接続元アドレス単位でロックアウトを開始する連続失敗回数
+    ip_lockout_threshold: Option<u32>,
+
+    /// トークンローテーション後に旧平文を受け付ける猶予期間(秒)
+    token_rotation_grace_secs: Option<i64>,
+}
+
+///
+/// 監査ログ出力先設定の情報
+///
+#[derive(Debug, Deserialize, Serialize)]
+struct AuditOutputSection {
+    /// ローカルファイルへの出力の有無
+    file: Option<bool>,
+
+    /// syslog 転送先
+    syslog: Option<Vec<AuditSyslogInfo>>,
+
+    /// webhook 転送先
+    webhook: Option<Vec<AuditWebhookInfo>>,
+}
+
+///
+/// syslog 転送先の設定情報
+///
+#[derive(Debug, Deserialize, Serialize)]
+struct AuditSyslogInfo {
+    /// 転送先アドレス ("host:port")
+    address: String,
+
+    /// 転送プロトコル (udp / tcp)
+    protocol: Option<SyslogProtocol>,
+
+    /// facility 名
+    facility: Option<String>,
+
+    /// HOSTNAME 欄に記録するホスト名
+    hostname: Option<String>,
+}
+
+///
+/// webhook 転送先の設定情報
+///
+#[derive(Debug, Deserialize, Serialize)]
+struct AuditWebhookInfo {
+    /// 送信先 URL
+    url: String,
+
+    /// 1回の送信にまとめるレコード数の上限
+    batch_size: Option<usize>,
+
+    /// 送信待ちレコードを送出するまでの最大待ち時間(秒)
+    flush_interval_secs: Option<u64>,
+
+    /// 送信失敗時の再送回数
+    max_retries: Option<u32>,
+
+    /// 最初の再送までの待ち時間(秒)
+    retry_interval_secs: Option<u64>,
+
+    /// 追加するリクエストヘッダ
+    headers: Option<BTreeMap<String, String>>,
+}
+
+///
+/// 認証設定の解決済みデータ
+///
+#[derive(Clone, Debug)]
+pub(crate) struct AuthConfig {
+    password_policy: PasswordPolicy,
+    user_lockout: LockoutPolicy,
+    ip_lockout: LockoutPolicy,
+    token_rotation_grace: Duration,
+}
+
+impl AuthConfig {
+    ///
+    /// パスワードの強度要件を返す
+    ///
+    /// # 戻り値
+    /// パスワード設定時に適用する強度要件
+    ///
+    pub(crate) fn password_policy(&self) -> &PasswordPolicy {
+        &self.password_policy
+    }
+
+    ///
+    /// ユーザ単位のロックアウト方針を返す
+    ///
+    /// # 戻り値
+    /// ユーザ単位のログイン失敗に適用するロックアウト方針
+    ///
+    pub(crate) fn user_lockout(&self) -> &LockoutPolicy {
+        &self.user_lockout
+    }
+
+    ///
+    /// 接続元アドレス単位のロックアウト方針を返す
+    ///
+    /// # 戻り値
+    /// 接続元アドレス単位のログイン失敗に適用するロックアウト方針
+    ///
+    pub(crate) fn ip_lockout(&self) -> &LockoutPolicy {
+        &self.ip_lockout
+    }
+
+    ///
+    /// トークンローテーションの猶予期間を返す
+    ///
+    /// # 戻り値
+    /// ローテーション後に旧平文を受け付ける期間
+    ///
+    pub(crate) fn token_rotation_grace(&self) -> Duration {
+        self.token_rotation_grace
+    }
+}
+
+impl Default for AuthConfig {
+    ///
+    /// 認証設定の既定値を生成
+    ///
+    /// # 戻り値
+    /// 既定の強度要件とロックアウト方針を格納した構造体
+    ///
+    fn default() -> Self {
+        let base_duration = Duration::seconds(DEFAULT_AUTH_LOCKOUT_BASE_SECS);
+        let max_duration = Duration::seconds(DEFAULT_AUTH_LOCKOUT_MAX_SECS);
+
+        Self {
+            password_policy: PasswordPolicy::new(
+                DEFAULT_AUTH_PASSWORD_MIN_LENGTH,
+                DEFAULT_AUTH_PASSWORD_MIN_CLASSES,
+            ),
+            user_lockout: LockoutPolicy::new(
+                DEFAULT_AUTH_LOCKOUT_THRESHOLD,
+                base_duration,
+                max_duration,
+            ),
+            ip_lockout: LockoutPolicy::new(
+                DEFAULT_AUTH_IP_LOCKOUT_THRESHOLD,
+                base_duration,
+                max_duration,
+            ),
+            token_rotation_grace: Duration::seconds(
+                DEFAULT_AUTH_TOKEN_ROTATION_GRACE_SECS,
+            ),
+        }
+    }
+}
+
 ///
 /// user listサブコマンドの設定情報
 ///
@@ -1564,6 +2075,65 @@ mod tests {
         assert_eq!(config.user_list_reverse_sort(), Some(true));
     }
 
+    #[test]
+    fn load_auth_section_from_toml() {
+        let toml_str = r#"
+            [auth]
+            password_min_length = 12
+            password_min_classes = 3
+            lockout_threshold = 3
+            lockout_base_secs = 10
+            lockout_max_secs = 60
+            token_rotation_grace_secs = 0
+        "#;
+
+        let config: Config = toml::from_str(toml_str).expect("parse failed");
+        let auth = config.auth_config().expect("resolve failed");
+        assert_eq!(auth.token_rotation_grace(), Duration::zero());
+        assert_eq!(auth.password_policy(), &PasswordPolicy::new(12, 3));
+        assert_eq!(
+            auth.user_lockout(),
+            &LockoutPolicy::new(
+                3,
+                Duration::seconds(10),
+                Duration::seconds(60),
+            )
+        );
+        assert_eq!(
+            auth.ip_lockout(),
+            &LockoutPolicy::new(
+                DEFAULT_AUTH_IP_LOCKOUT_THRESHOLD,
+                Duration::seconds(10),
+                Duration::seconds(60),
+            )
+        );
+    }
+
+    #[test]
+    fn reject_invalid_auth_section() {
+        let toml_str = r#"
+            [auth]
+            password_min_classes = 5
+        "#;
+        let config: Config = toml::from_str(toml_str).expect("parse failed");
+        assert!(config.auth_config().is_err());
+
+        let toml_str = r#"
+            [auth]
+            lockout_base_secs = 120
+            lockout_max_secs = 60
+        "#;
+        let config: Config = toml::from_str(toml_str).expect("parse failed");
+        assert!(config.auth_config().is_err());
+
+        let toml_str = r#"
+            [auth]
+            token_rotation_grace_secs = -1
+        "#;
+        let config: Config = toml::from_str(toml_str).expect("parse failed");
+        assert!(config.auth_config().is_err());
+    }
+
     #[test]
     fn default_user_list_values_are_present() {
         let config = Config::default();
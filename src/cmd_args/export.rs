@@ -9,20 +9,52 @@
 //!
 
 use anyhow::{anyhow, Result};
-use clap::Args;
+use chrono::DateTime;
+use clap::{Args, ValueEnum};
 
 use super::{ShowOptions, Validate};
+use crate::database::types::Id;
 use crate::rest_api::validate_page_path;
 
+///
+/// export の出力形式
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub(crate) enum ExportFormat {
+    /// ZIP アーカイブ
+    #[default]
+    Zip,
+
+    /// 静的 HTML サイト
+    Html,
+
+    /// Markdown ディレクトリツリー
+    Markdown,
+}
+
 ///
 /// サブコマンドexportのオプション
 ///
 #[derive(Clone, Args, Debug)]
 pub(crate) struct ExportOpts {
+    /// 出力形式
+    #[arg(
+        short = 'f',
+        long = "format",
+        value_name = "FORMAT",
+        value_enum,
+        default_value_t = ExportFormat::Zip
+    )]
+    format: ExportFormat,
+
     /// migrate export 対象のサブツリー
     #[arg(short = 's', long = "subtree", value_name = "PREFIX")]
     subtree: Option<String>,
 
+    /// 差分 backup の起点 (RFC3339 日時またはエクスポートID)
+    #[arg(long = "since", value_name = "SINCE")]
+    since: Option<String>,
+
     /// リハーサルモード
     #[arg(short = 'd', long = "dry-run")]
     dry_run: bool,
@@ -39,12 +71,20 @@ pub(crate) struct ExportOpts {
     #[arg(short = 'S', long = "strict-mode")]
     strict_mode: bool,
 
-    /// 出力先 ZIP パス、"-" は標準出力
+    /// 出力先 ZIP パス("-" は標準出力)、html/markdown 形式の場合は出力先
+    /// ディレクトリ
     #[arg()]
     output: String,
 }
 
 impl ExportOpts {
+    ///
+    /// 出力形式へのアクセサ
+    ///
+    pub(crate) fn format(&self) -> ExportFormat {
+        self.format
+    }
+
     ///
     /// サブツリー指定へのアクセサ
     ///
@@ -52,6 +92,13 @@ impl ExportOpts {
         self.subtree.clone()
     }
 
+    ///
+    /// 差分起点指定へのアクセサ
+    ///
+    pub(crate) fn since(&self) -> Option<String> {
+        self.since.clone()
+    }
+
     ///
     /// dry-run 指定へのアクセサ
     ///
@@ -91,10 +138,15 @@ impl ExportOpts {
 impl ShowOptions for ExportOpts {
     fn show_options(&self) {
         println!("export command options");
+        println!("   format:      {:?}", self.format());
         println!(
             "   subtree:     {}",
             self.subtree.as_deref().unwrap_or("(none)")
         );
+        println!(
+            "   since:       {}",
+            self.since.as_deref().unwrap_or("(none)")
+        );
         println!("   dry_run:     {}", self.is_dry_run());
         println!("   password:    {}", self.password.is_some());
         println!("   yes:         {}", self.is_yes());
@@ -110,6 +162,38 @@ impl Validate for ExportOpts {
             return Err(anyhow!("output path is empty"));
         }
 
+        /*
+         * html/markdown 形式は起点の指定のみ受け付ける
+         */
+        if self.format != ExportFormat::Zip {
+            let format = self
+                .format
+                .to_possible_value()
+                .map(|value| value.get_name().to_string())
+                .unwrap_or_default();
+
+            if self.output == "-" {
+                return Err(anyhow!(
+                    "--format {} can not write to stdout",
+                    format
+                ));
+            }
+
+            if self.since.is_some() || self.password.is_some() {
+                return Err(anyhow!(
+                    "--since and --password can not be used with --format {}",
+                    format
+                ));
+            }
+
+            let checked = self.subtree.as_deref().map(validate_page_path);
+            if let Some(Err(message)) = checked {
+                return Err(anyhow!("invalid page path: {}", message));
+            }
+
+            return Ok(());
+        }
+
         if let Some(subtree) = self.subtree.as_deref() {
             if let Err(message) = validate_page_path(subtree) {
                 return Err(anyhow!("invalid page path: {}", message));
@@ -120,6 +204,20 @@ impl Validate for ExportOpts {
             }
         }
 
+        if let Some(since) = self.since.as_deref() {
+            if self.subtree.is_some() {
+                return Err(anyhow!(
+                    "--since can not be used with --subtree"
+                ));
+            }
+
+            let valid = DateTime::parse_from_rfc3339(since).is_ok()
+                || Id::from_string(since).is_ok();
+            if !valid {
+                return Err(anyhow!("invalid since value: {}", since));
+            }
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,217 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! サブコマンド"git-sync"のコマンドライン定義
+//!
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use clap::{Args, Subcommand};
+
+use super::{ApplyConfig, ShowOptions, Validate};
+use crate::cmd_args::config::Config;
+use crate::cmd_args::default_git_sync_repository_path;
+
+#[derive(Clone, Args, Debug)]
+pub(crate) struct GitSyncCommand {
+    #[command(subcommand)]
+    pub(crate) subcommand: GitSyncSubCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub(crate) enum GitSyncSubCommand {
+    /// ページ履歴のGitリポジトリへの複製
+    #[command(name = "push")]
+    Push(GitSyncPushOpts),
+
+    /// Gitリポジトリ側のコミットの取り込み
+    #[command(name = "pull")]
+    Pull(GitSyncPullOpts),
+}
+
+///
+/// サブコマンドgit-sync pushのオプション
+///
+#[derive(Clone, Args, Debug)]
+pub(crate) struct GitSyncPushOpts {
+    /// 同期先リポジトリのパス
+    #[arg(short = 'r', long = "repository", value_name = "DIR")]
+    repository: Option<PathBuf>,
+
+    /// 作成するコミット数の表示のみ行う
+    #[arg(short = 'd', long = "dry-run")]
+    dry_run: bool,
+}
+
+impl GitSyncPushOpts {
+    ///
+    /// 同期先リポジトリのパスへのアクセサ
+    ///
+    /// # 戻り値
+    /// 明示指定、設定ファイル、デフォルトの順に解決したパスを返す。
+    ///
+    pub(crate) fn repository(&self) -> PathBuf {
+        self.repository
+            .clone()
+            .unwrap_or_else(default_git_sync_repository_path)
+    }
+
+    ///
+    /// リハーサル指定へのアクセサ
+    ///
+    pub(crate) fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+}
+
+// ShowOptionsトレイトの実装
+impl ShowOptions for GitSyncPushOpts {
+    fn show_options(&self) {
+        println!("git-sync push command options");
+        println!("   repository: {}", self.repository().display());
+        println!("   dry_run:    {}", self.is_dry_run());
+    }
+}
+
+// Validateトレイトの実装
+impl Validate for GitSyncPushOpts {
+    fn validate(&mut self) -> Result<()> {
+        validate_repository(&self.repository())
+    }
+}
+
+// ApplyConfigトレイトの実装
+impl ApplyConfig for GitSyncPushOpts {
+    fn apply_config(&mut self, config: &Config) {
+        if self.repository.is_none() {
+            self.repository = config.git_sync_repository();
+        }
+    }
+}
+
+///
+/// サブコマンドgit-sync pullのオプション
+///
+#[derive(Clone, Args, Debug)]
+pub(crate) struct GitSyncPullOpts {
+    /// 同期元リポジトリのパス
+    #[arg(short = 'r', long = "repository", value_name = "DIR")]
+    repository: Option<PathBuf>,
+
+    /// 未登録のコミット作成者に代わって記録するユーザ名
+    #[arg(short = 'u', long = "user", value_name = "USER-NAME")]
+    user_name: Option<String>,
+
+    /// Wiki側でも更新されたページへの取り込みを強行する
+    #[arg(long = "force")]
+    force: bool,
+
+    /// 取り込み内容の検証のみ行う
+    #[arg(short = 'd', long = "dry-run")]
+    dry_run: bool,
+}
+
+impl GitSyncPullOpts {
+    ///
+    /// 同期元リポジトリのパスへのアクセサ
+    ///
+    /// # 戻り値
+    /// 明示指定、設定ファイル、デフォルトの順に解決したパスを返す。
+    ///
+    pub(crate) fn repository(&self) -> PathBuf {
+        self.repository
+            .clone()
+            .unwrap_or_else(default_git_sync_repository_path)
+    }
+
+    ///
+    /// 代替ユーザ名へのアクセサ
+    ///
+    pub(crate) fn user_name(&self) -> Option<String> {
+        self.user_name.clone()
+    }
+
+    ///
+    /// 強行指定へのアクセサ
+    ///
+    pub(crate) fn is_force(&self) -> bool {
+        self.force
+    }
+
+    ///
+    /// リハーサル指定へのアクセサ
+    ///
+    pub(crate) fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+}
+
+// ShowOptionsトレイトの実装
+impl ShowOptions for GitSyncPullOpts {
+    fn show_options(&self) {
+        println!("git-sync pull command options");
+        println!("   repository: {}", self.repository().display());
+        println!(
+            "   user:       {}",
+            self.user_name.as_deref().unwrap_or("(none)")
+        );
+        println!("   force:      {}", self.is_force());
+        println!("   dry_run:    {}", self.is_dry_run());
+    }
+}
+
+// Validateトレイトの実装
+impl Validate for GitSyncPullOpts {
+    fn validate(&mut self) -> Result<()> {
+        let empty_user = self
+            .user_name
+            .as_deref()
+            .is_some_and(|user_name| user_name.trim().is_empty());
+        if empty_user {
+            return Err(anyhow!("user name is empty"));
+        }
+
+        validate_repository(&self.repository())
+    }
+}
+
+// ApplyConfigトレイトの実装
+impl ApplyConfig for GitSyncPullOpts {
+    fn apply_config(&mut self, config: &Config) {
+        if self.repository.is_none() {
+            self.repository = config.git_sync_repository();
+        }
+        if self.user_name.is_none() {
+            self.user_name = config.git_sync_user();
+        }
+    }
+}
+
+///
+/// リポジトリパスの検証
+///
+/// # 引数
+/// * `path` - リポジトリのパス
+///
+/// # 戻り値
+/// 未作成、またはディレクトリの場合は`Ok(())`を返す。
+///
+fn validate_repository(path: &Path) -> Result<()> {
+    if path.as_os_str().is_empty() {
+        return Err(anyhow!("repository path is empty"));
+    }
+
+    if path.exists() && !path.is_dir() {
+        return Err(anyhow!(
+            "repository path is not a directory: {}",
+            path.display()
+        ));
+    }
+
+    Ok(())
+}
@@ -9,16 +9,62 @@
 //!
 
 use anyhow::{anyhow, Result};
-use clap::Args;
+use clap::{Args, ValueEnum};
 
 use super::{ShowOptions, Validate};
 use crate::rest_api::validate_page_path;
 
+///
+/// import の入力形式
+///
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub(crate) enum ImportFormat {
+    /// ZIP アーカイブ
+    #[default]
+    Zip,
+
+    /// Markdown ディレクトリツリー
+    Markdown,
+
+    /// Obsidian の vault
+    Obsidian,
+
+    /// MediaWiki の XML ダンプ
+    Mediawiki,
+
+    /// DokuWiki のデータディレクトリ
+    Dokuwiki,
+}
+
+impl ImportFormat {
+    ///
+    /// コマンドライン上の形式名の取得
+    ///
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::Markdown => "markdown",
+            Self::Obsidian => "obsidian",
+            Self::Mediawiki => "mediawiki",
+            Self::Dokuwiki => "dokuwiki",
+        }
+    }
+}
+
 ///
 /// サブコマンドimportのオプション
 ///
 #[derive(Clone, Args, Debug)]
 pub(crate) struct ImportOpts {
+    /// 入力形式
+    #[arg(
+        long = "format",
+        value_name = "FORMAT",
+        value_enum,
+        default_value_t = ImportFormat::Zip
+    )]
+    format: ImportFormat,
+
     /// migrate import 先のプレフィクス
     #[arg(short = 'm', long = "migrate", value_name = "PREFIX")]
     migrate: Option<String>,
@@ -27,6 +73,11 @@ pub(crate) struct ImportOpts {
     #[arg(short = 'u', long = "user-map", value_name = "MAPPING")]
     user_map: Vec<String>,
 
+    /// 編集者として記録するユーザ名 (mediawiki/dokuwiki 形式では未対応付けの
+    /// 編集者の割り当て先)
+    #[arg(long = "user", value_name = "USER-NAME")]
+    user: Option<String>,
+
     /// 編集者一覧のみを表示
     #[arg(short = 'l', long = "user-list")]
     user_list: bool,
@@ -51,12 +102,20 @@ pub(crate) struct ImportOpts {
     #[arg(short = 'S', long = "strict-mode")]
     strict_mode: bool,
 
-    /// 入力 ZIP パス、"-" は標準入力
-    #[arg()]
-    input: String,
+    /// 入力 ZIP パス、"-" は標準入力 (複数指定時は指定順に取り込む)、
+    /// zip 以外の形式の場合は入力ディレクトリまたはダンプファイル
+    #[arg(required = true)]
+    inputs: Vec<String>,
 }
 
 impl ImportOpts {
+    ///
+    /// 入力形式へのアクセサ
+    ///
+    pub(crate) fn format(&self) -> ImportFormat {
+        self.format
+    }
+
     ///
     /// migrate import 先へのアクセサ
     ///
@@ -71,6 +130,13 @@ impl ImportOpts {
         self.user_map.clone()
     }
 
+    ///
+    /// 編集者ユーザ名へのアクセサ
+    ///
+    pub(crate) fn user(&self) -> Option<String> {
+        self.user.clone()
+    }
+
     ///
     /// ユーザ一覧表示指定へのアクセサ
     ///
@@ -114,21 +180,26 @@ impl ImportOpts {
     }
 
     ///
-    /// 入力元へのアクセサ
+    /// 入力元一覧へのアクセサ
     ///
-    pub(crate) fn input(&self) -> String {
-        self.input.clone()
+    pub(crate) fn inputs(&self) -> Vec<String> {
+        self.inputs.clone()
     }
 }
 
 impl ShowOptions for ImportOpts {
     fn show_options(&self) {
         println!("import command options");
+        println!("   format:          {:?}", self.format());
         println!(
             "   migrate:         {}",
             self.migrate.as_deref().unwrap_or("(none)")
         );
         println!("   user_map:        {:?}", self.user_map());
+        println!(
+            "   user:            {}",
+            self.user.as_deref().unwrap_or("(none)")
+        );
         println!("   user_list:       {}", self.is_user_list());
         println!("   dry_run:         {}", self.is_dry_run());
         println!(
@@ -138,17 +209,91 @@ impl ShowOptions for ImportOpts {
         println!("   yes:             {}", self.is_yes());
         println!("   password:        {}", self.password.is_some());
         println!("   strict_mode:     {}", self.is_strict_mode());
-        println!("   input:           {}", self.input());
+        println!("   inputs:          {:?}", self.inputs());
     }
 }
 
 // Validateトレイトの実装
 impl Validate for ImportOpts {
     fn validate(&mut self) -> Result<()> {
-        if self.input.trim().is_empty() {
+        if self.inputs.iter().any(|input| input.trim().is_empty()) {
             return Err(anyhow!("input path is empty"));
         }
 
+        /*
+         * zip 以外の形式は単一の入力を要する
+         */
+        let format = self.format.name();
+        if self.format != ImportFormat::Zip
+            && (self.inputs.len() > 1 || self.inputs[0] == "-")
+        {
+            return Err(anyhow!(
+                "--format {} requires a single input",
+                format
+            ));
+        }
+
+        match self.format {
+            /*
+             * markdown/obsidian 形式は編集者の指定を要する
+             */
+            ImportFormat::Markdown | ImportFormat::Obsidian => {
+                if !self.user_map.is_empty()
+                    || self.user_list
+                    || self.password.is_some()
+                {
+                    return Err(anyhow!(
+                        "--user-map, --user-list and --password can not be used with --format {}",
+                        format
+                    ));
+                }
+
+                if self.user.as_deref().is_none_or(|user| user.trim().is_empty()) {
+                    return Err(anyhow!("--format {} requires --user", format));
+                }
+            }
+
+            /*
+             * mediawiki/dokuwiki 形式は取り込み先の指定を要する
+             */
+            ImportFormat::Mediawiki | ImportFormat::Dokuwiki => {
+                if self.password.is_some() {
+                    return Err(anyhow!(
+                        "--password can not be used with --format {}",
+                        format
+                    ));
+                }
+
+                if !self.user_list && self.migrate.is_none() {
+                    return Err(anyhow!("--format {} requires --migrate", format));
+                }
+
+                if self.user.as_deref().is_some_and(|user| user.trim().is_empty()) {
+                    return Err(anyhow!("user name is empty"));
+                }
+            }
+
+            ImportFormat::Zip => {
+                if self.user.is_some() {
+                    return Err(anyhow!("--user can not be used with --format zip"));
+                }
+            }
+        }
+
+        if self.inputs.len() > 1 {
+            if self.inputs.iter().any(|input| input == "-") {
+                return Err(anyhow!(
+                    "standard input can not be used with multiple inputs"
+                ));
+            }
+
+            if self.migrate.is_some() {
+                return Err(anyhow!(
+                    "--migrate can not be used with multiple inputs"
+                ));
+            }
+        }
+
         if let Some(prefix) = self.migrate.as_deref() {
             if let Err(message) = validate_page_path(prefix) {
                 return Err(anyhow!("invalid page path: {}", message));
@@ -8,10 +8,13 @@
 //! コマンドライン引数を取り扱うモジュール
 //!
 
+mod audit;
+mod backup;
 mod config;
 mod derived;
 mod export;
 mod fts;
+mod git_sync;
 mod asset;
 mod import;
 mod lock;
@@ -31,15 +34,20 @@ use clap::{Parser, Subcommand, ValueEnum};
 use directories::BaseDirs;
 use serde::{Deserialize, Serialize};
 
+use crate::audit::forward::AuditOutputConfig;
 use crate::command::{
-    asset_add, asset_delete, asset_list, asset_move_to, asset_purge,
-    asset_undelete, commands, derived_rebuild, export as export_command, fts_merge,
-    fts_rebuild, fts_search, help_all, import as import_command,
+    asset_add, asset_delete, asset_fsck, asset_gc, asset_list, asset_move_to,
+    asset_purge, asset_undelete, audit_query, audit_verify,
+    backup as backup_command, commands,
+    derived_rebuild, export as export_command, fts_merge,
+    fts_rebuild, fts_search, git_sync_pull, git_sync_push, help_all,
+    import as import_command,
     lock_delete, lock_list, page_add, page_delete, page_list, page_move_to,
     page_undelete, page_unlock, run as run_command, token_add_path,
     token_create, token_info, token_list, token_purge, token_remove_path,
-    token_revoke,
+    token_revoke, token_rotate,
     user_add, user_delete, user_edit, user_info, user_list,
+    user_mfa_disable, user_mfa_enroll, user_unlock,
     CommandContext,
 };
 use crate::database::DatabaseManager;
@@ -48,6 +56,8 @@ pub(crate) use asset::{
     AssetAddOpts,
     AssetCommand,
     AssetDeleteOpts,
+    AssetFsckOpts,
+    AssetGcOpts,
     AssetListOpts,
     AssetListSortMode,
     AssetMoveToOpts,
@@ -55,21 +65,29 @@ pub(crate) use asset::{
     AssetSubCommand,
     AssetUndeleteOpts,
 };
-pub(crate) use config::FrontendConfig;
+pub(crate) use audit::{AuditCommand, AuditQueryOpts, AuditSubCommand};
+pub(crate) use backup::BackupOpts;
+pub(crate) use config::{AuthConfig, FrontendConfig};
 pub(crate) use derived::{
     DerivedCommand,
     DerivedRebuildOpts,
     DerivedRebuildTarget,
     DerivedSubCommand,
 };
-pub(crate) use export::ExportOpts;
+pub(crate) use export::{ExportFormat, ExportOpts};
 pub(crate) use fts::{
     FtsCommand,
     FtsSearchOpts,
     FtsSearchTarget,
     FtsSubCommand,
 };
-pub(crate) use import::ImportOpts;
+pub(crate) use git_sync::{
+    GitSyncCommand,
+    GitSyncPullOpts,
+    GitSyncPushOpts,
+    GitSyncSubCommand,
+};
+pub(crate) use import::{ImportFormat, ImportOpts};
 pub(crate) use lock::{
     LockCommand,
     LockDeleteOpts,
@@ -90,6 +108,7 @@ pub(crate) use page::{
 };
 pub(crate) use run::RunOpts;
 pub(crate) use token::{
+    DEFAULT_TOKEN_CREATE_TTL,
     TokenCommand,
     TokenCreateOpts,
     TokenInfoOpts,
@@ -97,7 +116,11 @@ pub(crate) use token::{
     TokenPathUpdateOpts,
     TokenPurgeOpts,
     TokenRevokeOpts,
+    TokenRotateOpts,
     TokenSubCommand,
+    parse_path_prefixes,
+    parse_token_ttl,
+    validate_token_name,
 };
 pub(crate) use user::{
     UserAddOpts,
@@ -107,7 +130,11 @@ pub(crate) use user::{
     UserInfoOpts,
     UserListOpts,
     UserListSortMode,
+    UserMfaDisableOpts,
+    UserMfaEnrollOpts,
+    UserMfaSubCommand,
     UserSubCommand,
+    UserUnlockOpts,
 };
 
 /// デフォルトのコンフィギュレーションパス
@@ -198,6 +225,16 @@ fn default_cert_path() -> PathBuf {
     DEFAULT_DATA_PATH.join("server.pem")
 }
 
+///
+/// デフォルトのGit同期リポジトリのパス情報を生成
+///
+/// # 戻り値
+/// Git同期リポジトリ(bareリポジトリ)のパス情報
+///
+fn default_git_sync_repository_path() -> PathBuf {
+    DEFAULT_DATA_PATH.join("git")
+}
+
 /// デフォルトのWikiタイトル
 const DEFAULT_WIKI_TITLE: &str = "LUWIKI";
 
@@ -521,6 +558,75 @@ impl Options {
         Ok(config.frontend_config())
     }
 
+    ///
+    /// 認証設定情報へのアクセサ
+    ///
+    /// # 戻り値
+    /// 解決済みの認証設定を返す。
+    ///
+    pub(crate) fn auth_config(&self) -> Result<AuthConfig> {
+        /*
+         * 設定ファイルパスの決定
+         */
+        let path = if let Some(path) = &self.config_path {
+            path.clone()
+        } else {
+            default_config_path()
+        };
+
+        /*
+         * 設定ファイルの存在確認と読込
+         */
+        if !path.exists() {
+            return Ok(AuthConfig::default());
+        }
+
+        if !path.is_file() {
+            return Err(anyhow!("{} is not file", path.display()));
+        }
+
+        /*
+         * 認証設定の返却
+         */
+        let config = config::load(&path)?;
+        config.auth_config()
+    }
+
+    ///
+    /// 監査ログ出力先設定へのアクセサ
+    ///
+    /// # 戻り値
+    /// 解決済みの監査ログ出力先設定を返す。設定ファイルが存在しない場合は
+    /// ローカルファイルのみへ出力する設定を返す。
+    ///
+    pub(crate) fn audit_output_config(&self) -> Result<AuditOutputConfig> {
+        /*
+         * 設定ファイルパスの決定
+         */
+        let path = if let Some(path) = &self.config_path {
+            path.clone()
+        } else {
+            default_config_path()
+        };
+
+        /*
+         * 設定ファイルの存在確認と読込
+         */
+        if !path.exists() {
+            return Ok(AuditOutputConfig::default());
+        }
+
+        if !path.is_file() {
+            return Err(anyhow!("{} is not file", path.display()));
+        }
+
+        /*
+         * 出力先設定の返却
+         */
+        let config = config::load(&path)?;
+        config.audit_output_config()
+    }
+
     ///
     /// データベースのオープン
     ///
@@ -923,6 +1029,14 @@ enum Command {
     #[command(name = "token", alias = "t")]
     Token(TokenCommand),
 
+    /// 監査ログ管理コマンド一覧の表示
+    #[command(name = "audit")]
+    Audit(AuditCommand),
+
+    /// Gitリポジトリとの同期コマンド一覧の表示
+    #[command(name = "git-sync", alias = "g")]
+    GitSync(GitSyncCommand),
+
     /// バックアップ／マイグレート用データのエクスポート
     #[command(name = "export", alias = "e")]
     Export(export::ExportOpts),
@@ -931,6 +1045,10 @@ enum Command {
     #[command(name = "import", alias = "i")]
     Import(import::ImportOpts),
 
+    /// 稼働中サーバを含むバックアップの取得
+    #[command(name = "backup", alias = "b")]
+    Backup(backup::BackupOpts),
+
     /// サブコマンド一覧の表示
     #[command(name = "commands")]
     Commands,
@@ -978,12 +1096,22 @@ impl Command {
                 TokenSubCommand::AddPath(opts) => Some(opts),
                 TokenSubCommand::RemovePath(opts) => Some(opts),
                 TokenSubCommand::Revoke(opts) => Some(opts),
+                TokenSubCommand::Rotate(opts) => Some(opts),
                 TokenSubCommand::Purge(opts) => Some(opts),
                 TokenSubCommand::List(opts) => Some(opts),
                 TokenSubCommand::Info(opts) => Some(opts),
             },
+            Self::Audit(audit) => match &mut audit.subcommand {
+                AuditSubCommand::Query(opts) => Some(opts),
+                AuditSubCommand::Verify => None,
+            },
+            Self::GitSync(git_sync) => match &mut git_sync.subcommand {
+                GitSyncSubCommand::Push(opts) => Some(opts),
+                GitSyncSubCommand::Pull(opts) => Some(opts),
+            },
             Self::Export(_) => None,
             Self::Import(_) => None,
+            Self::Backup(opts) => Some(opts),
             Self::Commands => None,
             Self::HelpAll => None,
         }
@@ -1001,6 +1129,11 @@ impl Command {
                 UserSubCommand::Edit(opts) => Some(opts),
                 UserSubCommand::List(opts) => Some(opts),
                 UserSubCommand::Info(opts) => Some(opts),
+                UserSubCommand::Unlock(opts) => Some(opts),
+                UserSubCommand::Mfa(mfa) => match &mut mfa.subcommand {
+                    UserMfaSubCommand::Enroll(opts) => Some(opts),
+                    UserMfaSubCommand::Disable(opts) => Some(opts),
+                },
             },
             Self::Page(page) => match &mut page.subcommand {
                 PageSubCommand::Add(opts) => Some(opts),
@@ -1021,6 +1154,8 @@ impl Command {
                 AssetSubCommand::Purge(opts) => Some(opts),
                 AssetSubCommand::Undelete(opts) => Some(opts),
                 AssetSubCommand::MoveTo(opts) => Some(opts),
+                AssetSubCommand::Fsck(opts) => Some(opts),
+                AssetSubCommand::Gc(opts) => Some(opts),
             },
             Self::Fts(fts) => match &mut fts.subcommand {
                 FtsSubCommand::Search(opts) => Some(opts),
@@ -1034,12 +1169,22 @@ impl Command {
                 TokenSubCommand::AddPath(opts) => Some(opts),
                 TokenSubCommand::RemovePath(opts) => Some(opts),
                 TokenSubCommand::Revoke(_) => None,
+                TokenSubCommand::Rotate(_) => None,
                 TokenSubCommand::Purge(_) => None,
                 TokenSubCommand::List(_) => None,
                 TokenSubCommand::Info(opts) => Some(opts),
             },
+            Self::Audit(audit) => match &mut audit.subcommand {
+                AuditSubCommand::Query(opts) => Some(opts),
+                AuditSubCommand::Verify => None,
+            },
+            Self::GitSync(git_sync) => match &mut git_sync.subcommand {
+                GitSyncSubCommand::Push(opts) => Some(opts),
+                GitSyncSubCommand::Pull(opts) => Some(opts),
+            },
             Self::Export(opts) => Some(opts),
             Self::Import(opts) => Some(opts),
+            Self::Backup(opts) => Some(opts),
             Self::Commands => None,
             Self::HelpAll => None,
         }
@@ -1057,6 +1202,11 @@ impl Command {
                 UserSubCommand::Edit(opts) => Some(opts),
                 UserSubCommand::List(opts) => Some(opts),
                 UserSubCommand::Info(opts) => Some(opts),
+                UserSubCommand::Unlock(opts) => Some(opts),
+                UserSubCommand::Mfa(mfa) => match &mfa.subcommand {
+                    UserMfaSubCommand::Enroll(opts) => Some(opts),
+                    UserMfaSubCommand::Disable(opts) => Some(opts),
+                },
             },
             Self::Page(page) => match &page.subcommand {
                 PageSubCommand::Add(opts) => Some(opts),
@@ -1077,6 +1227,8 @@ impl Command {
                 AssetSubCommand::Purge(opts) => Some(opts),
                 AssetSubCommand::Undelete(opts) => Some(opts),
                 AssetSubCommand::MoveTo(opts) => Some(opts),
+                AssetSubCommand::Fsck(opts) => Some(opts),
+                AssetSubCommand::Gc(opts) => Some(opts),
             },
             Self::Fts(fts) => match &fts.subcommand {
                 FtsSubCommand::Search(opts) => Some(opts),
@@ -1090,12 +1242,22 @@ impl Command {
                 TokenSubCommand::AddPath(opts) => Some(opts),
                 TokenSubCommand::RemovePath(opts) => Some(opts),
                 TokenSubCommand::Revoke(opts) => Some(opts),
+                TokenSubCommand::Rotate(opts) => Some(opts),
                 TokenSubCommand::Purge(opts) => Some(opts),
                 TokenSubCommand::List(opts) => Some(opts),
                 TokenSubCommand::Info(opts) => Some(opts),
             },
+            Self::Audit(audit) => match &audit.subcommand {
+                AuditSubCommand::Query(opts) => Some(opts),
+                AuditSubCommand::Verify => None,
+            },
+            Self::GitSync(git_sync) => match &git_sync.subcommand {
+                GitSyncSubCommand::Push(opts) => Some(opts),
+                GitSyncSubCommand::Pull(opts) => Some(opts),
+            },
             Self::Export(opts) => Some(opts),
             Self::Import(opts) => Some(opts),
+            Self::Backup(opts) => Some(opts),
             Self::Commands => None,
             Self::HelpAll => None,
         }
@@ -1123,6 +1285,17 @@ impl Command {
                 UserSubCommand::Info(sub_opts) => {
                     user_info::build_context(opts, sub_opts)
                 }
+                UserSubCommand::Unlock(sub_opts) => {
+                    user_unlock::build_context(opts, sub_opts)
+                }
+                UserSubCommand::Mfa(mfa) => match &mfa.subcommand {
+                    UserMfaSubCommand::Enroll(sub_opts) => {
+                        user_mfa_enroll::build_context(opts, sub_opts)
+                    }
+                    UserMfaSubCommand::Disable(sub_opts) => {
+                        user_mfa_disable::build_context(opts, sub_opts)
+                    }
+                },
             },
             Self::Page(page) => match &page.subcommand {
                 PageSubCommand::Add(sub_opts) => {
@@ -1171,6 +1344,12 @@ impl Command {
                 AssetSubCommand::MoveTo(sub_opts) => {
                     asset_move_to::build_context(opts, sub_opts)
                 }
+                AssetSubCommand::Fsck(sub_opts) => {
+                    asset_fsck::build_context(opts, sub_opts)
+                }
+                AssetSubCommand::Gc(sub_opts) => {
+                    asset_gc::build_context(opts, sub_opts)
+                }
             },
             Self::Fts(fts) => match &fts.subcommand {
                 FtsSubCommand::Rebuild => fts_rebuild::build_context(opts),
@@ -1197,6 +1376,9 @@ impl Command {
                 TokenSubCommand::Revoke(sub_opts) => {
                     token_revoke::build_context(opts, sub_opts)
                 }
+                TokenSubCommand::Rotate(sub_opts) => {
+                    token_rotate::build_context(opts, sub_opts)
+                }
                 TokenSubCommand::Purge(sub_opts) => {
                     token_purge::build_context(opts, sub_opts)
                 }
@@ -1207,12 +1389,29 @@ impl Command {
                     token_info::build_context(opts, sub_opts)
                 }
             },
+            Self::Audit(audit) => match &audit.subcommand {
+                AuditSubCommand::Query(sub_opts) => {
+                    audit_query::build_context(opts, sub_opts)
+                }
+                AuditSubCommand::Verify => audit_verify::build_context(opts),
+            },
+            Self::GitSync(git_sync) => match &git_sync.subcommand {
+                GitSyncSubCommand::Push(sub_opts) => {
+                    git_sync_push::build_context(opts, sub_opts)
+                }
+                GitSyncSubCommand::Pull(sub_opts) => {
+                    git_sync_pull::build_context(opts, sub_opts)
+                }
+            },
             Self::Export(sub_opts) => {
                 export_command::build_context(opts, sub_opts)
             }
             Self::Import(sub_opts) => {
                 import_command::build_context(opts, sub_opts)
             }
+            Self::Backup(sub_opts) => {
+                backup_command::build_context(opts, sub_opts)
+            }
             Self::Commands => commands::build_context(opts),
             Self::HelpAll => help_all::build_context(opts),
         }
@@ -1228,8 +1427,15 @@ impl Command {
                 config.set_run_bind_port(opts.bind_port());
                 config.set_run_use_mcp(opts.use_mcp());
                 config.set_run_mcp_authority(opts.mcp_authority());
+                config.set_run_use_collab(opts.use_collab());
                 config.set_run_use_tls(opts.use_tls());
                 config.set_run_server_cert(opts.cert_path());
+                if let Some(interval) = opts.raw_asset_gc_interval() {
+                    config.set_run_asset_gc_interval(interval);
+                }
+                if let Some(interval) = opts.raw_git_sync_interval() {
+                    config.set_run_git_sync_interval(interval);
+                }
             }
             Self::User(user) => {
                 if let UserSubCommand::List(opts) = &user.subcommand {
@@ -1282,8 +1488,17 @@ impl Command {
             }
             Self::Derived(_) => {}
             Self::Token(_) => {}
+            Self::Audit(_) => {}
+            Self::GitSync(git_sync) => {
+                let repository = match &git_sync.subcommand {
+                    GitSyncSubCommand::Push(opts) => opts.repository(),
+                    GitSyncSubCommand::Pull(opts) => opts.repository(),
+                };
+                config.set_git_sync_repository(repository);
+            }
             Self::Export(_) => {}
             Self::Import(_) => {}
+            Self::Backup(_) => {}
             Self::Commands => {}
             Self::HelpAll => {}
         }
@@ -1688,6 +1903,7 @@ fn parse_audit_log_rotate_size(raw: &str) -> Result<u64> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::audit::syslog::SyslogProtocol;
     use clap::Parser;
     use tempfile::TempDir;
 
@@ -1745,6 +1961,118 @@ mod tests {
         assert!(run_opts.use_mcp());
     }
 
+    #[test]
+    fn parse_run_asset_gc_interval_option() {
+        let opts = Options::try_parse_from([
+            "luwiki",
+            "run",
+            "--asset-gc-interval",
+            "6h",
+        ])
+        .expect("parse failed");
+        let mut run_opts = match opts.command {
+            Some(Command::Run(run_opts)) => run_opts,
+            _ => panic!("run options missing"),
+        };
+
+        run_opts.validate().expect("validate failed");
+        assert_eq!(
+            run_opts.asset_gc_interval(),
+            Some(std::time::Duration::from_secs(6 * 60 * 60))
+        );
+
+        let opts = Options::try_parse_from([
+            "luwiki",
+            "run",
+            "--asset-gc-interval",
+            "6x",
+        ])
+        .expect("parse failed");
+        let mut run_opts = match opts.command {
+            Some(Command::Run(run_opts)) => run_opts,
+            _ => panic!("run options missing"),
+        };
+
+        assert!(run_opts.validate().is_err());
+    }
+
+    #[test]
+    fn apply_config_sets_git_sync_repository_when_configured() {
+        let dir = TempDir::new().expect("temp dir");
+        let config_path = dir.path().join("config.toml");
+        std::fs::write(
+            &config_path,
+            "[run]\ngit_sync_interval = \"30m\"\n\n\
+             [git_sync]\nrepository = \"/srv/wiki.git\"\nuser = \"bot\"\n",
+        )
+        .expect("write config failed");
+        let config_arg = config_path.to_string_lossy().to_string();
+
+        let mut opts = Options::try_parse_from([
+            "luwiki",
+            "--config-path",
+            &config_arg,
+            "run",
+        ])
+        .expect("parse failed");
+        opts.apply_config().expect("apply config failed");
+        opts.validate().expect("validate failed");
+        let run_opts = match opts.command {
+            Some(Command::Run(run_opts)) => run_opts,
+            _ => panic!("run options missing"),
+        };
+        assert_eq!(
+            run_opts.git_sync_interval(),
+            Some(std::time::Duration::from_secs(30 * 60))
+        );
+        assert_eq!(
+            run_opts.git_sync_repository(),
+            PathBuf::from("/srv/wiki.git")
+        );
+        assert_eq!(run_opts.git_sync_user().as_deref(), Some("bot"));
+
+        let mut opts = Options::try_parse_from([
+            "luwiki",
+            "--config-path",
+            &config_arg,
+            "git-sync",
+            "pull",
+            "--user",
+            "alice",
+            "--force",
+        ])
+        .expect("parse failed");
+        opts.apply_config().expect("apply config failed");
+        opts.validate().expect("validate failed");
+        let pull_opts = match opts.command {
+            Some(Command::GitSync(GitSyncCommand {
+                subcommand: GitSyncSubCommand::Pull(pull_opts),
+            })) => pull_opts,
+            _ => panic!("git-sync pull options missing"),
+        };
+        assert_eq!(pull_opts.repository(), PathBuf::from("/srv/wiki.git"));
+        assert_eq!(pull_opts.user_name().as_deref(), Some("alice"));
+        assert!(pull_opts.is_force());
+
+        let opts = Options::try_parse_from([
+            "luwiki",
+            "git-sync",
+            "push",
+            "-r",
+            "/tmp/other.git",
+            "-d",
+        ])
+        .expect("parse failed");
+        let push_opts = match opts.command {
+            Some(Command::GitSync(GitSyncCommand {
+                subcommand: GitSyncSubCommand::Push(push_opts),
+            })) => push_opts,
+            _ => panic!("git-sync push options missing"),
+        };
+        assert_eq!(push_opts.repository(), PathBuf::from("/tmp/other.git"));
+        assert!(push_opts.is_dry_run());
+    }
+
     #[test]
     fn apply_config_sets_run_mcp_authority_when_configured() {
         let dir = TempDir::new().expect("temp dir");
@@ -1773,6 +2101,68 @@ mod tests {
         assert_eq!(run_opts.mcp_authority(), "mcp.example.test");
     }
 
+    #[test]
+    fn audit_output_config_resolves_syslog_and_webhook_outputs() {
+        let dir = TempDir::new().expect("temp dir");
+        let config_path = dir.path().join("config.toml");
+        let config_arg = config_path.to_string_lossy().to_string();
+        let opts = Options::try_parse_from([
+            "luwiki",
+            "--config-path",
+            &config_arg,
+            "run",
+        ])
+        .expect("parse failed");
+
+        /*
+         * 設定ファイルが無い場合はローカルファイルのみ
+         */
+        let config = opts.audit_output_config().expect("resolve failed");
+        assert_eq!(config, AuditOutputConfig::default());
+
+        /*
+         * syslog / webhook の併用
+         */
+        std::fs::write(
+            &config_path,
+            "[audit_output]\nfile = false\n\n\
+             [[audit_output.syslog]]\naddress = \"127.0.0.1:514\"\n\
+             protocol = \"tcp\"\n\n\
+             [[audit_output.webhook]]\nurl = \"https://collector.test/in\"\n\
+             batch_size = 20\n\
+             [audit_output.webhook.headers]\nAuthorization = \"Bearer x\"\n",
+        )
+        .expect("write config failed");
+
+        let config = opts.audit_output_config().expect("resolve failed");
+        assert!(!config.file);
+        assert_eq!(config.syslog.len(), 1);
+        assert_eq!(config.syslog[0].protocol, SyslogProtocol::Tcp);
+        assert_eq!(config.syslog[0].facility, 16);
+        assert_eq!(config.webhook.len(), 1);
+        assert_eq!(config.webhook[0].batch_size, 20);
+        assert_eq!(config.webhook[0].max_retries, 3);
+        assert_eq!(
+            config.webhook[0].headers,
+            vec![("Authorization".to_string(), "Bearer x".to_string())]
+        );
+
+        /*
+         * 不正な設定
+         */
+        std::fs::write(&config_path, "[audit_output]\nfile = false\n")
+            .expect("write config failed");
+        assert!(opts.audit_output_config().is_err());
+
+        std::fs::write(
+            &config_path,
+            "[[audit_output.syslog]]\naddress = \"127.0.0.1:514\"\n\
+             facility = \"unknown\"\n",
+        )
+        .expect("write config failed");
+        assert!(opts.audit_output_config().is_err());
+    }
+
     #[test]
     fn validate_rejects_invalid_run_mcp_authority() {
         let dir = TempDir::new().expect("temp dir");
@@ -2788,6 +3178,53 @@ mod tests {
         assert!(err.to_string().contains("--subtree /"));
     }
 
+    #[test]
+    fn export_html_format_accepts_root_subtree_and_rejects_archive_options() {
+        let mut opts = Options::try_parse_from([
+            "luwiki",
+            "export",
+            "--format",
+            "html",
+            "--subtree",
+            "/",
+            "site",
+        ])
+        .expect("parse failed");
+
+        opts.validate().expect("validate failed");
+        let export_opts = match opts.command {
+            Some(Command::Export(export_opts)) => export_opts,
+            _ => panic!("export options missing"),
+        };
+        assert_eq!(export_opts.format(), ExportFormat::Html);
+
+        let mut opts = Options::try_parse_from([
+            "luwiki",
+            "export",
+            "--format",
+            "html",
+            "--password",
+            "secret",
+            "site",
+        ])
+        .expect("parse failed");
+
+        let err = opts.validate().expect_err("password must be rejected");
+        assert!(err.to_string().contains("--format html"));
+
+        let mut opts = Options::try_parse_from([
+            "luwiki",
+            "export",
+            "--format",
+            "markdown",
+            "-",
+        ])
+        .expect("parse failed");
+
+        let err = opts.validate().expect_err("stdout must be rejected");
+        assert!(err.to_string().contains("--format markdown"));
+    }
+
     #[test]
     fn parse_import_command_options() {
         let mut opts = Options::try_parse_from([
@@ -2822,7 +3259,7 @@ mod tests {
         assert!(import_opts.is_yes());
         assert_eq!(import_opts.password(), Some("secret".to_string()));
         assert!(import_opts.is_strict_mode());
-        assert_eq!(import_opts.input(), "in.zip".to_string());
+        assert_eq!(import_opts.inputs(), vec!["in.zip".to_string()]);
     }
 
     #[test]
@@ -2841,6 +3278,57 @@ mod tests {
         assert!(err.to_string().contains("--fix-broken-link requires --migrate"));
     }
 
+    #[test]
+    fn backup_options_are_parsed() {
+        let mut opts = Options::try_parse_from([
+            "luwiki",
+            "backup",
+            "--via-server",
+            "--url",
+            "https://wiki.example:8443",
+            "--user",
+            "admin",
+            "--insecure",
+            "--since",
+            "2026-01-01T00:00:00Z",
+            "out.zip",
+        ])
+        .expect("parse failed");
+
+        opts.validate().expect("validate failed");
+        let backup_opts = match opts.command {
+            Some(Command::Backup(backup_opts)) => backup_opts,
+            _ => panic!("backup options missing"),
+        };
+
+        assert!(backup_opts.is_via_server());
+        assert_eq!(backup_opts.url(), "https://wiki.example:8443");
+        assert_eq!(backup_opts.user_name(), Some("admin".to_string()));
+        assert!(backup_opts.is_insecure());
+        assert_eq!(
+            backup_opts.since(),
+            Some("2026-01-01T00:00:00Z".to_string())
+        );
+        assert_eq!(backup_opts.output(), "out.zip");
+    }
+
+    #[test]
+    fn backup_validate_rejects_server_options_without_via_server() {
+        let mut opts = Options::try_parse_from([
+            "luwiki",
+            "backup",
+            "--user",
+            "admin",
+            "out.zip",
+        ])
+        .expect("parse failed");
+
+        let err = opts.validate().expect_err(
+            "server options without via-server must be rejected",
+        );
+        assert!(err.to_string().contains("require --via-server"));
+    }
+
     #[test]
     fn import_validate_rejects_relative_migrate_prefix() {
         let mut opts = Options::try_parse_from([
@@ -2857,4 +3345,116 @@ mod tests {
             .expect_err("relative migrate prefix must be rejected");
         assert!(err.to_string().contains("invalid page path"));
     }
+
+    #[test]
+    fn import_markdown_format_requires_user_and_single_directory() {
+        let mut opts = Options::try_parse_from([
+            "luwiki",
+            "import",
+            "--format",
+            "markdown",
+            "--user",
+            "alice",
+            "--migrate",
+            "/dst",
+            "notes",
+        ])
+        .expect("parse failed");
+
+        opts.validate().expect("validate failed");
+        let import_opts = match opts.command {
+            Some(Command::Import(import_opts)) => import_opts,
+            _ => panic!("import options missing"),
+        };
+        assert_eq!(import_opts.format(), ImportFormat::Markdown);
+        assert_eq!(import_opts.user().as_deref(), Some("alice"));
+
+        let mut opts = Options::try_parse_from([
+            "luwiki",
+            "import",
+            "--format",
+            "markdown",
+            "notes",
+        ])
+        .expect("parse failed");
+
+        let err = opts.validate().expect_err("user must be required");
+        assert!(err.to_string().contains("requires --user"));
+
+        let mut opts = Options::try_parse_from([
+            "luwiki",
+            "import",
+            "--user",
+            "alice",
+            "in.zip",
+        ])
+        .expect("parse failed");
+
+        let err = opts
+            .validate()
+            .expect_err("user without markdown format must be rejected");
+        assert!(err.to_string().contains("--user can not be used with --format zip"));
+    }
+
+    #[test]
+    fn import_foreign_wiki_formats_require_migrate_and_single_input() {
+        let mut opts = Options::try_parse_from([
+            "luwiki",
+            "import",
+            "--format",
+            "mediawiki",
+            "--migrate",
+            "/wiki",
+            "--user",
+            "alice",
+            "--user-map",
+            "Bob=bob",
+            "dump.xml",
+        ])
+        .expect("parse failed");
+
+        opts.validate().expect("validate failed");
+        let import_opts = match opts.command {
+            Some(Command::Import(import_opts)) => import_opts,
+            _ => panic!("import options missing"),
+        };
+        assert_eq!(import_opts.format(), ImportFormat::Mediawiki);
+        assert_eq!(import_opts.user().as_deref(), Some("alice"));
+
+        let mut opts = Options::try_parse_from([
+            "luwiki",
+            "import",
+            "--format",
+            "dokuwiki",
+            "--user-list",
+            "data",
+        ])
+        .expect("parse failed");
+        opts.validate().expect("user list without migrate must be accepted");
+
+        let mut opts = Options::try_parse_from([
+            "luwiki",
+            "import",
+            "--format",
+            "dokuwiki",
+            "data",
+        ])
+        .expect("parse failed");
+        let err = opts.validate().expect_err("migrate must be required");
+        assert!(err.to_string().contains("--format dokuwiki requires --migrate"));
+
+        let mut opts = Options::try_parse_from([
+            "luwiki",
+            "import",
+            "--format",
+            "obsidian",
+            "--user",
+            "alice",
+            "vault1",
+            "vault2",
+        ])
+        .expect("parse failed");
+        let err = opts.validate().expect_err("multiple inputs must be rejected");
+        assert!(err.to_string().contains("--format obsidian requires a single input"));
+    }
 }
@@ -9,6 +9,7 @@
 //!
 
 use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
 use clap::Args;
@@ -21,7 +22,7 @@ use super::{
     validate_mcp_authority,
 };
 use crate::cmd_args::config::Config;
-use crate::cmd_args::default_cert_path;
+use crate::cmd_args::{default_cert_path, default_git_sync_repository_path};
 
 ///
 /// サブコマンドrunのオプション
@@ -45,6 +46,15 @@ pub(crate) struct RunOpts {
     #[arg(skip)]
     mcp_authority: Option<String>,
 
+    /// 共同編集の有効化指定
+    #[arg(
+        long = "collab",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true"
+    )]
+    use_collab: Option<bool>,
+
     /// TLSでの通信を有効にする
     #[arg(short = 'T', long = "tls")]
     use_tls: bool,
@@ -53,6 +63,22 @@ pub(crate) struct RunOpts {
     #[arg(short = 'C', long = "cert", value_name = "FILE")]
     cert_path: Option<PathBuf>,
 
+    /// 不要なアセットファイルの定期回収間隔
+    #[arg(long = "asset-gc-interval", value_name = "INTERVAL")]
+    asset_gc_interval: Option<String>,
+
+    /// Gitリポジトリへの定期同期間隔
+    #[arg(long = "git-sync-interval", value_name = "INTERVAL")]
+    git_sync_interval: Option<String>,
+
+    /// Git同期リポジトリのパス
+    #[arg(skip)]
+    git_sync_repository: Option<PathBuf>,
+
+    /// Git同期で未登録のコミット作成者に代わって記録するユーザ名
+    #[arg(skip)]
+    git_sync_user: Option<String>,
+
     /// Windowsサービスとして起動する
     #[cfg(windows)]
     #[arg(long = "win-service")]
@@ -100,6 +126,16 @@ impl RunOpts {
             .unwrap_or_else(|| DEFAULT_MCP_AUTHORITY.to_string())
     }
 
+    ///
+    /// 共同編集有効フラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// 共同編集が有効ならtrueを返す。
+    ///
+    pub(crate) fn use_collab(&self) -> bool {
+        self.use_collab.unwrap_or(false)
+    }
+
     ///
     /// TLS有効フラグへのアクセサ
     ///
@@ -135,6 +171,79 @@ impl RunOpts {
         self.cert_path.is_some()
     }
 
+    ///
+    /// 不要なアセットファイルの定期回収間隔へのアクセサ
+    ///
+    /// # 戻り値
+    /// 定期回収を行う場合は回収間隔を返す。未指定または`0`が指定された
+    /// 場合は`None`を返す。
+    ///
+    pub(crate) fn asset_gc_interval(&self) -> Option<Duration> {
+        self.asset_gc_interval
+            .as_deref()
+            .and_then(|raw| {
+                parse_interval(raw, "asset gc interval").ok().flatten()
+            })
+    }
+
+    ///
+    /// 設定前の定期回収間隔へのアクセサ
+    ///
+    /// # 戻り値
+    /// 指定された回収間隔の文字列を返す。
+    ///
+    pub(crate) fn raw_asset_gc_interval(&self) -> Option<String> {
+        self.asset_gc_interval.clone()
+    }
+
+    ///
+    /// Gitリポジトリへの定期同期間隔へのアクセサ
+    ///
+    /// # 戻り値
+    /// 定期同期を行う場合は同期間隔を返す。未指定または`0`が指定された
+    /// 場合は`None`を返す。
+    ///
+    pub(crate) fn git_sync_interval(&self) -> Option<Duration> {
+        self.git_sync_interval
+            .as_deref()
+            .and_then(|raw| {
+                parse_interval(raw, "git sync interval").ok().flatten()
+            })
+    }
+
+    ///
+    /// 設定前の定期同期間隔へのアクセサ
+    ///
+    /// # 戻り値
+    /// 指定された同期間隔の文字列を返す。
+    ///
+    pub(crate) fn raw_git_sync_interval(&self) -> Option<String> {
+        self.git_sync_interval.clone()
+    }
+
+    ///
+    /// Git同期リポジトリのパスへのアクセサ
+    ///
+    /// # 戻り値
+    /// 設定ファイルで指定されたパスを返す。未指定の場合はデフォルトの
+    /// パスを返す。
+    ///
+    pub(crate) fn git_sync_repository(&self) -> PathBuf {
+        self.git_sync_repository
+            .clone()
+            .unwrap_or_else(default_git_sync_repository_path)
+    }
+
+    ///
+    /// Git同期の代替ユーザ名へのアクセサ
+    ///
+    /// # 戻り値
+    /// 設定ファイルで指定されたユーザ名を返す。
+    ///
+    pub(crate) fn git_sync_user(&self) -> Option<String> {
+        self.git_sync_user.clone()
+    }
+
     ///
     /// Windowsサービスモード有効フラグへのアクセサ
     ///
@@ -207,6 +316,20 @@ impl Validate for RunOpts {
          */
         validate_mcp_authority(&self.mcp_authority())?;
 
+        /*
+         * アセット定期回収間隔を検証
+         */
+        if let Some(raw) = &self.asset_gc_interval {
+            parse_interval(raw, "asset gc interval")?;
+        }
+
+        /*
+         * Git定期同期間隔を検証
+         */
+        if let Some(raw) = &self.git_sync_interval {
+            parse_interval(raw, "git sync interval")?;
+        }
+
         /*
          * 検証結果を返却
          */
@@ -253,6 +376,13 @@ impl ApplyConfig for RunOpts {
             self.mcp_authority = config.run_mcp_authority();
         }
 
+        /*
+         * 共同編集有効化設定を補完
+         */
+        if self.use_collab.is_none() {
+            self.use_collab = config.run_use_collab();
+        }
+
         /*
          * TLS関連の設定を補完
          */
@@ -267,7 +397,64 @@ impl ApplyConfig for RunOpts {
                 self.cert_path = Some(path);
             }
         }
+
+        /*
+         * アセット定期回収間隔を補完
+         */
+        if self.asset_gc_interval.is_none() {
+            self.asset_gc_interval = config.run_asset_gc_interval();
+        }
+
+        /*
+         * Git定期同期の設定を補完
+         */
+        if self.git_sync_interval.is_none() {
+            self.git_sync_interval = config.run_git_sync_interval();
+        }
+        if self.git_sync_repository.is_none() {
+            self.git_sync_repository = config.git_sync_repository();
+        }
+        if self.git_sync_user.is_none() {
+            self.git_sync_user = config.git_sync_user();
+        }
+    }
+}
+
+///
+/// 定期処理の実行間隔を解析する
+///
+/// # 引数
+/// * `raw` - 解析対象文字列(`{数値}{d|h|m}`形式または`0`)
+/// * `name` - エラーメッセージに用いる項目名
+///
+/// # 戻り値
+/// 解決済みの実行間隔を返す。`0`が指定された場合は`None`を返す。
+///
+fn parse_interval(raw: &str, name: &str) -> Result<Option<Duration>> {
+    let value = raw.trim();
+    if value == "0" {
+        return Ok(None);
+    }
+
+    let unit = value
+        .chars()
+        .last()
+        .ok_or_else(|| anyhow!("{} is empty", name))?;
+    let number = value[..value.len() - unit.len_utf8()]
+        .parse::<u64>()
+        .map_err(|_| anyhow!("{} format is invalid", name))?;
+    if number == 0 {
+        return Err(anyhow!("{} must be greater than zero", name));
     }
+
+    let secs = match unit {
+        'd' => number * 24 * 60 * 60,
+        'h' => number * 60 * 60,
+        'm' => number * 60,
+        _ => return Err(anyhow!("{} unit is invalid", name)),
+    };
+
+    Ok(Some(Duration::from_secs(secs)))
 }
 
 ///
@@ -355,8 +542,19 @@ impl ShowOptions for RunOpts {
         println!("   browser_open:   {:?}", self.is_browser_open());
         println!("   mcp enabled:    {}", self.use_mcp());
         println!("   mcp authority:  {}", self.mcp_authority());
+        println!("   collab enabled: {}", self.use_collab());
         println!("   tls enabled:    {}", self.use_tls());
         println!("   cert path:      {}", self.cert_path().display());
+        println!("   asset gc:       {:?}", self.asset_gc_interval());
+        println!("   git sync:       {:?}", self.git_sync_interval());
+        println!(
+            "   git repository: {}",
+            self.git_sync_repository().display()
+        );
+        println!(
+            "   git user:       {}",
+            self.git_sync_user.as_deref().unwrap_or("(none)")
+        );
         #[cfg(windows)]
         println!("   win service:    {}", self.is_win_service());
         println!("   bind:  {}:{}", self.bind_addr(), self.bind_port());
@@ -26,7 +26,7 @@ use crate::rest_api::validate_page_path;
 const DEFAULT_TOKEN_CREATE_SCOPE: &str = "write";
 
 /// `token create` のデフォルトTTL文字列
-const DEFAULT_TOKEN_CREATE_TTL: &str = "30d";
+pub(crate) const DEFAULT_TOKEN_CREATE_TTL: &str = "30d";
 
 #[derive(Clone, Args, Debug)]
 pub(crate) struct TokenCommand {
@@ -52,6 +52,10 @@ pub(crate) enum TokenSubCommand {
     #[command(name = "revoke", alias = "r")]
     Revoke(TokenRevokeOpts),
 
+    /// トークンのローテーション
+    #[command(name = "rotate")]
+    Rotate(TokenRotateOpts),
+
     /// トークンの削除
     #[command(name = "purge", alias = "p")]
     Purge(TokenPurgeOpts),
@@ -181,12 +185,7 @@ impl Validate for TokenCreateOpts {
          * 任意名の妥当性を検証する
          */
         if let Some(name) = self.normalized_name() {
-            if name.is_empty() {
-                return Err(anyhow!("token name must not be empty"));
-            }
-            if TokenId::from_string(&name).is_ok() {
-                return Err(anyhow!("token name must not be ULID-formatted"));
-            }
+            validate_token_name(&name)?;
         }
 
         Ok(())
@@ -374,6 +373,65 @@ impl ShowOptions for TokenRevokeOpts {
     }
 }
 
+///
+/// サブコマンドtoken_rotateのオプション
+///
+#[derive(Clone, Args, Debug)]
+pub(crate) struct TokenRotateOpts {
+    /// 旧トークンを受け付ける猶予期間の指定
+    #[arg(short = 'g', long = "grace", value_name = "DURATION")]
+    grace: Option<String>,
+
+    /// ローテーション対象のトークンID
+    #[arg()]
+    token_id: String,
+}
+
+impl TokenRotateOpts {
+    ///
+    /// トークンIDへのアクセサ
+    ///
+    /// # 戻り値
+    /// 指定されたトークンIDを返す。
+    ///
+    pub(crate) fn token_id(&self) -> String {
+        self.token_id.clone()
+    }
+
+    ///
+    /// 猶予期間へのアクセサ
+    ///
+    /// # 戻り値
+    /// 猶予期間が指定されている場合は解析済みの期間を返す。未指定時は
+    /// `None` を返す。
+    ///
+    pub(crate) fn grace_duration(&self) -> Result<Option<Duration>> {
+        self.grace.as_deref().map(parse_token_ttl).transpose()
+    }
+}
+
+// Validateトレイトの実装
+impl Validate for TokenRotateOpts {
+    fn validate(&mut self) -> Result<()> {
+        self.grace_duration()?;
+        Ok(())
+    }
+}
+
+// ApplyConfigトレイトの実装
+impl ApplyConfig for TokenRotateOpts {
+    fn apply_config(&mut self, _config: &Config) {}
+}
+
+// ShowOptionsトレイトの実装
+impl ShowOptions for TokenRotateOpts {
+    fn show_options(&self) {
+        println!("token rotate command options");
+        println!("   token_id: {:?}", self.token_id());
+        println!("   grace:    {:?}", self.grace);
+    }
+}
+
 ///
 /// サブコマンドtoken_purgeのオプション
 ///
@@ -659,7 +717,9 @@ fn parse_token_scopes(raw: &str) -> Result<BearerScopeSet> {
 /// # 戻り値
 /// 解析済みの path prefix 集合を返す。
 ///
-fn parse_path_prefixes(raw_prefixes: &[String]) -> Result<PathPrefixSet> {
+pub(crate) fn parse_path_prefixes(
+    raw_prefixes: &[String],
+) -> Result<PathPrefixSet> {
     let mut prefixes = PathPrefixSet::new();
 
     /*
@@ -683,7 +743,7 @@ fn parse_path_prefixes(raw_prefixes: &[String]) -> Result<PathPrefixSet> {
 /// # 戻り値
 /// 解析済みの `chrono::Duration` を返す。
 ///
-fn parse_token_ttl(raw: &str) -> Result<Duration> {
+pub(crate) fn parse_token_ttl(raw: &str) -> Result<Duration> {
     let raw = raw.trim();
     if raw.len() < 2 {
         return Err(anyhow!("ttl format is invalid"));
@@ -715,6 +775,30 @@ fn parse_token_ttl(raw: &str) -> Result<Duration> {
     Ok(duration)
 }
 
+///
+/// トークン名の妥当性を検証する
+///
+/// # 引数
+/// * `name` - 前後空白除去済みのトークン名
+///
+/// # 戻り値
+/// 検証に成功した場合は `Ok(())` を返す。
+///
+/// # 注記
+/// トークンIDとの取り違えを防ぐため、ULID 形式の名前は受け付けない。
+///
+pub(crate) fn validate_token_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        return Err(anyhow!("token name must not be empty"));
+    }
+
+    if TokenId::from_string(name).is_ok() {
+        return Err(anyhow!("token name must not be ULID-formatted"));
+    }
+
+    Ok(())
+}
+
 ///
 /// path prefix の妥当性を検証する
 ///
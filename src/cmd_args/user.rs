@@ -43,6 +43,31 @@ pub(crate) enum UserSubCommand {
     /// ユーザ情報の詳細表示
     #[command(name = "info")]
     Info(UserInfoOpts),
+
+    /// ログイン失敗によるロックアウトの解除
+    #[command(name = "unlock")]
+    Unlock(UserUnlockOpts),
+
+    /// 二要素認証の管理
+    #[command(name = "mfa")]
+    Mfa(UserMfaCommand),
+}
+
+#[derive(Clone, Args, Debug)]
+pub(crate) struct UserMfaCommand {
+    #[command(subcommand)]
+    pub(crate) subcommand: UserMfaSubCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub(crate) enum UserMfaSubCommand {
+    /// 二要素認証の登録
+    #[command(name = "enroll")]
+    Enroll(UserMfaEnrollOpts),
+
+    /// 二要素認証の解除
+    #[command(name = "disable")]
+    Disable(UserMfaDisableOpts),
 }
 
 ///
@@ -185,6 +210,14 @@ pub(crate) struct UserEditOpts {
     #[arg(long = "clear-attributes")]
     clear_attributes: bool,
 
+    /// ユーザを無効化する
+    #[arg(long = "disable", conflicts_with = "enable")]
+    disable: bool,
+
+    /// 無効化されたユーザを有効化する
+    #[arg(long = "enable")]
+    enable: bool,
+
     /// 変更対象のユーザ名
     #[arg()]
     user_name: String,
@@ -251,6 +284,23 @@ impl UserEditOpts {
         self.clear_attributes
     }
 
+    ///
+    /// 無効化状態の変更指定へのアクセサ
+    ///
+    /// # 戻り値
+    /// `--disable` 指定時は `Some(true)`、`--enable` 指定時は `Some(false)`、
+    /// いずれも無い場合は `None` を返す。
+    ///
+    pub(crate) fn disabled(&self) -> Option<bool> {
+        if self.disable {
+            Some(true)
+        } else if self.enable {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
     ///
     /// 属性更新指定の有無を返す
     ///
@@ -273,6 +323,7 @@ impl Validate for UserEditOpts {
         if self.display_name.is_none()
             && !self.password
             && !self.has_attribute_changes()
+            && self.disabled().is_none()
         {
             return Err(anyhow!("no update options specified"));
         }
@@ -291,6 +342,7 @@ impl ShowOptions for UserEditOpts {
         println!("   add_attrs:    {:?}", self.add_attributes);
         println!("   remove_attrs: {:?}", self.remove_attributes);
         println!("   clear_attrs:  {:?}", self.clear_attributes());
+        println!("   disabled:     {:?}", self.disabled());
     }
 }
 
@@ -336,6 +388,43 @@ impl ShowOptions for UserInfoOpts {
     }
 }
 
+///
+/// サブコマンドuser_unlockのオプション
+///
+#[derive(Clone, Args, Debug)]
+pub(crate) struct UserUnlockOpts {
+    /// ロックアウトを解除するユーザ名
+    #[arg()]
+    user_name: String,
+}
+
+impl UserUnlockOpts {
+    ///
+    /// ユーザ名へのアクセサ
+    ///
+    /// # 戻り値
+    /// 指定されたユーザ名を返す。
+    ///
+    pub(crate) fn user_name(&self) -> String {
+        self.user_name.clone()
+    }
+}
+
+// Validateトレイトの実装
+impl Validate for UserUnlockOpts {
+    fn validate(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+// ShowOptionsトレイトの実装
+impl ShowOptions for UserUnlockOpts {
+    fn show_options(&self) {
+        println!("user unlock command options");
+        println!("   user_name: {}", self.user_name());
+    }
+}
+
 ///
 /// user_listサブコマンドのソート順
 ///
@@ -434,6 +523,95 @@ impl ShowOptions for UserListOpts {
     }
 }
 
+///
+/// サブコマンドuser_mfa_enrollのオプション
+///
+#[derive(Clone, Args, Debug)]
+pub(crate) struct UserMfaEnrollOpts {
+    /// 登録済みの場合に秘密鍵とリカバリコードを再発行する
+    #[arg(short = 'f', long = "force")]
+    force: bool,
+
+    /// 対象ユーザ名
+    #[arg()]
+    user_name: String,
+}
+
+impl UserMfaEnrollOpts {
+    ///
+    /// ユーザ名へのアクセサ
+    ///
+    /// # 戻り値
+    /// ユーザ名を返す
+    ///
+    pub(crate) fn user_name(&self) -> String {
+        self.user_name.clone()
+    }
+
+    ///
+    /// 再発行指定へのアクセサ
+    ///
+    /// # 戻り値
+    /// 再発行が指定されている場合はtrue
+    ///
+    pub(crate) fn is_force(&self) -> bool {
+        self.force
+    }
+}
+
+// Validateトレイトの実装
+impl Validate for UserMfaEnrollOpts {
+    fn validate(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+// ShowOptionsトレイトの実装
+impl ShowOptions for UserMfaEnrollOpts {
+    fn show_options(&self) {
+        println!("user mfa enroll command options");
+        println!("   user_name: {}", self.user_name());
+        println!("   force:     {:?}", self.is_force());
+    }
+}
+
+///
+/// サブコマンドuser_mfa_disableのオプション
+///
+#[derive(Clone, Args, Debug)]
+pub(crate) struct UserMfaDisableOpts {
+    /// 対象ユーザ名
+    #[arg()]
+    user_name: String,
+}
+
+impl UserMfaDisableOpts {
+    ///
+    /// ユーザ名へのアクセサ
+    ///
+    /// # 戻り値
+    /// ユーザ名を返す
+    ///
+    pub(crate) fn user_name(&self) -> String {
+        self.user_name.clone()
+    }
+}
+
+// Validateトレイトの実装
+impl Validate for UserMfaDisableOpts {
+    fn validate(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+// ShowOptionsトレイトの実装
+impl ShowOptions for UserMfaDisableOpts {
+    fn show_options(&self) {
+        println!("user mfa disable command options");
+        println!("   user_name: {}", self.user_name());
+    }
+}
+
 ///
 /// ユーザ属性指定群の解析
 ///
@@ -470,6 +648,8 @@ fn parse_user_attribute(raw_attribute: &str) -> Result<UserAttribute> {
     match raw_attribute {
         "no_basic_auth" => Ok(UserAttribute::NoBasicAuth),
         "read_only" => Ok(UserAttribute::ReadOnly),
+        "require_mfa" => Ok(UserAttribute::RequireMfa),
+        "admin" => Ok(UserAttribute::Admin),
         "" => Err(anyhow!("attribute must not be empty")),
         _ => Err(anyhow!("invalid user attribute: {}", raw_attribute)),
     }
@@ -539,10 +719,37 @@ mod tests {
             add_attributes: vec!["no_basic_auth".to_string()],
             remove_attributes: Vec::new(),
             clear_attributes: false,
+            disable: false,
+            enable: false,
+            user_name: "alice".to_string(),
+        };
+
+        opts.validate().expect("validate must pass");
+    }
+
+    #[test]
+    ///
+    /// `require_mfa` 属性指定が `RequireMfa` へ変換されることを確認
+    ///
+    /// # 注記
+    /// `user edit` の属性追加指定を解析して検証する。
+    ///
+    fn user_edit_attributes_parse_require_mfa() {
+        let mut opts = UserEditOpts {
+            display_name: None,
+            password: false,
+            add_attributes: vec!["require_mfa".to_string()],
+            remove_attributes: Vec::new(),
+            clear_attributes: false,
+            disable: false,
+            enable: false,
             user_name: "alice".to_string(),
         };
 
         opts.validate().expect("validate must pass");
+        let attribute = parse_user_attribute("require_mfa")
+            .expect("attribute parse failed");
+        assert_eq!(attribute, UserAttribute::RequireMfa);
     }
 
     #[test]
@@ -564,10 +771,39 @@ mod tests {
             add_attributes: vec!["unknown".to_string()],
             remove_attributes: Vec::new(),
             clear_attributes: false,
+            disable: false,
+            enable: false,
             user_name: "alice".to_string(),
         };
 
         assert!(add_opts.validate().is_err());
         assert!(edit_opts.validate().is_err());
     }
+
+    #[test]
+    ///
+    /// 無効化指定のみの `user edit` が検証を通ることを確認
+    ///
+    /// # 注記
+    /// `admin` 属性指定が `Admin` へ変換されることも併せて検証する。
+    ///
+    fn user_edit_validate_accepts_disable_only_update() {
+        let mut opts = UserEditOpts {
+            display_name: None,
+            password: false,
+            add_attributes: Vec::new(),
+            remove_attributes: Vec::new(),
+            clear_attributes: false,
+            disable: true,
+            enable: false,
+            user_name: "alice".to_string(),
+        };
+
+        opts.validate().expect("validate must pass");
+        assert_eq!(opts.disabled(), Some(true));
+        assert_eq!(
+            parse_user_attribute("admin").expect("attribute parse failed"),
+            UserAttribute::Admin,
+        );
+    }
 }
@@ -0,0 +1,310 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 共同編集セッションを集約管理するモジュール
+//!
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use log::warn;
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::session::{ClientMessage, CollabEvent, CollabSession, ServerMessage};
+use crate::database::types::{LockToken, PageId};
+use crate::http_server::app_state::AppState;
+
+/// ページIDをキーとしたセッション表
+type SessionTable = HashMap<PageId, Arc<Mutex<CollabSession>>>;
+
+/// 定期保存間隔の既定値
+pub(crate) const DEFAULT_CHECKPOINT_INTERVAL: Duration = Duration::from_secs(30);
+
+///
+/// 共同編集セッションの管理
+///
+/// # 注記
+/// セッションはページ単位に1つ生成し、最初の参加者の権限でページロックを
+/// 取得する。最後の参加者が離脱した時点で未保存の編集を保存し、ロックを
+/// 解除してセッションを破棄する。
+///
+#[derive(Debug)]
+pub(crate) struct CollabHub {
+    /// ページIDをキーとしたセッション
+    sessions: Mutex<SessionTable>,
+
+    /// 次に割り当てる接続ID
+    next_client_id: AtomicU64,
+
+    /// 定期保存間隔
+    checkpoint_interval: Duration,
+}
+
+impl CollabHub {
+    ///
+    /// オブジェクトの生成
+    ///
+    /// # 引数
+    /// * `checkpoint_interval` - 未保存の編集を保存する間隔
+    ///
+    /// # 戻り値
+    /// セッションを持たないオブジェクトを返す。
+    ///
+    pub(crate) fn new(checkpoint_interval: Duration) -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+            next_client_id: AtomicU64::new(1),
+            checkpoint_interval,
+        }
+    }
+
+    ///
+    /// セッションへの参加
+    ///
+    /// # 引数
+    /// * `state` - 共有状態
+    /// * `page_id` - 対象ページ
+    /// * `user` - 参加するユーザ名
+    /// * `sender` - イベントの送出先
+    ///
+    /// # 戻り値
+    /// 割り当てた接続IDを返す。セッションを新規に開始する際にページロックを
+    /// 取得できない場合は`DbError::PageLocked`をエラーとして返す。
+    ///
+    pub(crate) fn join(
+        &self,
+        state: &AppState,
+        page_id: &PageId,
+        user: &str,
+        sender: UnboundedSender<CollabEvent>,
+    ) -> Result<u64> {
+        let mut sessions = self.lock_sessions()?;
+
+        /*
+         * セッションの取得(未開始の場合は開始)
+         */
+        let session = match sessions.get(page_id) {
+            Some(session) => session.clone(),
+            None => {
+                let db = state.db();
+                let index = db
+                    .get_page_index_by_id(page_id)?
+                    .ok_or_else(|| anyhow!("page not found"))?;
+                let source = db
+                    .get_page_source(page_id, index.latest())?
+                    .ok_or_else(|| anyhow!("page source not found"))?
+                    .source();
+                let lock_info = db.acquire_page_lock(page_id, user)?;
+
+                let session = Arc::new(Mutex::new(CollabSession::new(
+                    page_id.clone(),
+                    source,
+                    user.to_string(),
+                    lock_info.token(),
+                )));
+                sessions.insert(page_id.clone(), session.clone());
+                session
+            }
+        };
+
+        /*
+         * 参加者の登録
+         */
+        let client_id = self.next_client_id.fetch_add(1, Ordering::Relaxed);
+        lock_session(&session)?.join(client_id, user, sender);
+
+        Ok(client_id)
+    }
+
+    ///
+    /// セッションからの離脱
+    ///
+    /// # 引数
+    /// * `state` - 共有状態
+    /// * `page_id` - 対象ページ
+    /// * `client_id` - 離脱する接続ID
+    ///
+    /// # 注記
+    /// 最後の参加者が離脱した場合はセッションを終了する。
+    ///
+    pub(crate) fn leave(
+        &self,
+        state: &AppState,
+        page_id: &PageId,
+        client_id: u64,
+    ) -> Result<()> {
+        let mut sessions = self.lock_sessions()?;
+        let Some(session) = sessions.get(page_id).cloned() else {
+            return Ok(());
+        };
+
+        let mut session = lock_session(&session)?;
+        session.leave(client_id);
+        if !session.is_empty() {
+            return Ok(());
+        }
+
+        /*
+         * セッションの終了
+         */
+        sessions.remove(page_id);
+        if let Err(err) = session.checkpoint(state) {
+            warn!("collab checkpoint failed: {:?}", err);
+        }
+        session.release_lock(state)
+    }
+
+    ///
+    /// 受信したテキストメッセージの処理
+    ///
+    /// # 引数
+    /// * `page_id` - 対象ページ
+    /// * `client_id` - 送信元の接続ID
+    /// * `text` - 受信したテキスト
+    ///
+    /// # 戻り値
+    /// 処理に成功した場合は`Ok(())`を返す。
+    ///
+    pub(crate) fn handle_text(
+        &self,
+        page_id: &PageId,
+        client_id: u64,
+        text: &str,
+    ) -> Result<()> {
+        let Some(session) = self.lock_sessions()?.get(page_id).cloned() else {
+            return Err(anyhow!("collab session not found"));
+        };
+        let mut session = lock_session(&session)?;
+
+        match serde_json::from_str::<ClientMessage>(text) {
+            Ok(message) => session.handle_message(client_id, message),
+            Err(err) => session.send(
+                client_id,
+                &ServerMessage::Error {
+                    reason: format!("invalid message: {}", err),
+                },
+            ),
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// 定期処理
+    ///
+    /// # 引数
+    /// * `state` - 共有状態
+    ///
+    /// # 注記
+    /// 各セッションのページロックを延長し、前回の保存から定期保存間隔を
+    /// 経過した未保存の編集を保存する。ロックを延長できないセッション
+    /// (管理者によるロックの強制解除など)は保存したうえで終了する。
+    ///
+    pub(crate) fn tick(&self, state: &AppState) -> Result<()> {
+        let entries: Vec<(PageId, Arc<Mutex<CollabSession>>)> = self
+            .lock_sessions()?
+            .iter()
+            .map(|(page_id, session)| (page_id.clone(), session.clone()))
+            .collect();
+
+        for (page_id, session) in entries {
+            let mut session = lock_session(&session)?;
+
+            if let Err(err) = session.renew_lock(state) {
+                warn!("collab lock renew failed: {:?}", err);
+                if let Err(err) = session.checkpoint(state) {
+                    warn!("collab checkpoint failed: {:?}", err);
+                }
+                session.close_all("page lock lost");
+                drop(session);
+                self.lock_sessions()?.remove(&page_id);
+                continue;
+            }
+
+            if !session.is_dirty()
+                || session.since_checkpoint() < self.checkpoint_interval
+            {
+                continue;
+            }
+
+            if let Err(err) = session.checkpoint(state) {
+                warn!("collab checkpoint failed: {:?}", err);
+                session.broadcast(
+                    &ServerMessage::Error {
+                        reason: "checkpoint failed".to_string(),
+                    },
+                    None,
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// ページロックの強制解除に伴うセッションの終了
+    ///
+    /// # 引数
+    /// * `state` - 共有状態
+    /// * `page_id` - 対象ページ
+    /// * `token` - 強制解除するロック解除トークン
+    ///
+    /// # 戻り値
+    /// 未保存の編集を保存した場合は保存したリビジョン番号を`Some()`で
+    /// ラップして返す。
+    ///
+    /// # 注記
+    /// 指定されたロックを保持するセッションが存在する場合、未保存の編集を
+    /// 保存してから全参加者へ通知して切断する。保存に失敗した場合は
+    /// セッションを維持したままエラーを返す。ロック自体は解除しない。
+    ///
+    pub(crate) fn break_lock(
+        &self,
+        state: &AppState,
+        page_id: &PageId,
+        token: &LockToken,
+    ) -> Result<Option<u64>> {
+        let mut sessions = self.lock_sessions()?;
+        let Some(session) = sessions.get(page_id).cloned() else {
+            return Ok(None);
+        };
+
+        let mut session = lock_session(&session)?;
+        if !session.holds_lock(token) {
+            return Ok(None);
+        }
+
+        let revision = session.checkpoint(state)?;
+        session.close_all("page lock broken by administrator");
+        sessions.remove(page_id);
+
+        Ok(revision)
+    }
+
+    ///
+    /// セッション表のロック取得
+    ///
+    fn lock_sessions(&self) -> Result<MutexGuard<'_, SessionTable>> {
+        self.sessions
+            .lock()
+            .map_err(|_| anyhow!("collab session table lock failed"))
+    }
+}
+
+///
+/// セッションのロック取得
+///
+fn lock_session(
+    session: &Mutex<CollabSession>,
+) -> Result<MutexGuard<'_, CollabSession>> {
+    session
+        .lock()
+        .map_err(|_| anyhow!("collab session lock failed"))
+}
@@ -0,0 +1,16 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! ページの共同編集機能を集約するモジュール
+//!
+
+pub(crate) mod hub;
+pub(crate) mod ot;
+pub(crate) mod session;
+
+pub(crate) use hub::{CollabHub, DEFAULT_CHECKPOINT_INTERVAL};
+pub(crate) use session::CollabEvent;
@@ -0,0 +1,501 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 共同編集で用いるテキスト操作(Operational Transformation)の実装
+//!
+
+use anyhow::{anyhow, Error, Result};
+use serde::{Deserialize, Serialize};
+
+///
+/// テキスト操作の構成要素
+///
+/// # 注記
+/// 長さはすべて文字(Unicodeスカラ値)単位で扱う。
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum OpComponent {
+    /// 指定文字数をそのまま残す
+    Retain(usize),
+
+    /// 文字列を挿入する
+    Insert(String),
+
+    /// 指定文字数を削除する
+    Delete(usize),
+}
+
+///
+/// JSON表現上のテキスト操作の構成要素
+///
+/// # 注記
+/// 正数は保持、負数は削除、文字列は挿入を表す(ot.js互換の表現)。
+///
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum RawComponent {
+    /// 保持または削除の文字数
+    Count(i64),
+
+    /// 挿入する文字列
+    Text(String),
+}
+
+///
+/// 文書全体に対するテキスト操作
+///
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(try_from = "Vec<RawComponent>", into = "Vec<RawComponent>")]
+pub(crate) struct TextOperation {
+    /// 正規化済みの構成要素
+    components: Vec<OpComponent>,
+
+    /// 適用前の文書長
+    base_len: usize,
+
+    /// 適用後の文書長
+    target_len: usize,
+}
+
+impl TextOperation {
+    ///
+    /// 空の操作の生成
+    ///
+    /// # 戻り値
+    /// 構成要素を持たない操作を返す。
+    ///
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// 文書を変更しない操作か否かの判定
+    ///
+    /// # 戻り値
+    /// 保持のみで構成される場合は`true`を返す。
+    ///
+    pub(crate) fn is_noop(&self) -> bool {
+        self.components
+            .iter()
+            .all(|component| matches!(component, OpComponent::Retain(_)))
+    }
+
+    ///
+    /// 保持要素の追加
+    ///
+    /// # 引数
+    /// * `count` - 保持する文字数
+    ///
+    /// # 戻り値
+    /// 自身への参照を返す。
+    ///
+    pub(crate) fn retain(&mut self, count: usize) -> &mut Self {
+        if count == 0 {
+            return self;
+        }
+
+        self.base_len += count;
+        self.target_len += count;
+
+        match self.components.last_mut() {
+            Some(OpComponent::Retain(last)) => *last += count,
+            _ => self.components.push(OpComponent::Retain(count)),
+        }
+
+        self
+    }
+
+    ///
+    /// 挿入要素の追加
+    ///
+    /// # 引数
+    /// * `text` - 挿入する文字列
+    ///
+    /// # 戻り値
+    /// 自身への参照を返す。
+    ///
+    /// # 注記
+    /// 削除の直後に挿入する場合は挿入を削除の前へ置き、表現を正規化する。
+    ///
+    pub(crate) fn insert(&mut self, text: &str) -> &mut Self {
+        if text.is_empty() {
+            return self;
+        }
+
+        self.target_len += text.chars().count();
+
+        let len = self.components.len();
+        match self.components.as_mut_slice() {
+            [.., OpComponent::Insert(last)] => last.push_str(text),
+            [.., OpComponent::Insert(prev), OpComponent::Delete(_)] => {
+                prev.push_str(text)
+            }
+            [.., OpComponent::Delete(_)] => self
+                .components
+                .insert(len - 1, OpComponent::Insert(text.to_string())),
+            _ => self.components.push(OpComponent::Insert(text.to_string())),
+        }
+
+        self
+    }
+
+    ///
+    /// 削除要素の追加
+    ///
+    /// # 引数
+    /// * `count` - 削除する文字数
+    ///
+    /// # 戻り値
+    /// 自身への参照を返す。
+    ///
+    pub(crate) fn delete(&mut self, count: usize) -> &mut Self {
+        if count == 0 {
+            return self;
+        }
+
+        self.base_len += count;
+
+        match self.components.last_mut() {
+            Some(OpComponent::Delete(last)) => *last += count,
+            _ => self.components.push(OpComponent::Delete(count)),
+        }
+
+        self
+    }
+
+    ///
+    /// 文書への操作の適用
+    ///
+    /// # 引数
+    /// * `document` - 適用対象の文書
+    ///
+    /// # 戻り値
+    /// 適用後の文書を返す。文書長が操作と一致しない場合はエラーを返す。
+    ///
+    pub(crate) fn apply(&self, document: &str) -> Result<String> {
+        let chars: Vec<char> = document.chars().collect();
+        if chars.len() != self.base_len {
+            return Err(anyhow!(
+                "operation base length {} does not match document length {}",
+                self.base_len,
+                chars.len()
+            ));
+        }
+
+        let mut result = String::with_capacity(document.len());
+        let mut pos = 0;
+
+        for component in &self.components {
+            match component {
+                OpComponent::Retain(count) => {
+                    result.extend(&chars[pos..pos + count]);
+                    pos += count;
+                }
+                OpComponent::Insert(text) => result.push_str(text),
+                OpComponent::Delete(count) => pos += count,
+            }
+        }
+
+        Ok(result)
+    }
+
+    ///
+    /// 文書上の位置の変換
+    ///
+    /// # 引数
+    /// * `index` - 操作適用前の文書上の位置(文字単位)
+    ///
+    /// # 戻り値
+    /// 操作適用後の文書上で対応する位置を返す。
+    ///
+    /// # 注記
+    /// カーソル位置の追従に用いる。挿入位置と同じ位置は後方へずらす。
+    ///
+    pub(crate) fn transform_index(&self, index: usize) -> usize {
+        let mut remain = index as i64;
+        let mut result = index as i64;
+
+        for component in &self.components {
+            match component {
+                OpComponent::Retain(count) => remain -= *count as i64,
+                OpComponent::Insert(text) => {
+                    result += text.chars().count() as i64
+                }
+                OpComponent::Delete(count) => {
+                    result -= remain.min(*count as i64);
+                    remain -= *count as i64;
+                }
+            }
+
+            if remain < 0 {
+                break;
+            }
+        }
+
+        result.max(0) as usize
+    }
+
+    ///
+    /// 並行する2つの操作の変換
+    ///
+    /// # 引数
+    /// * `a` - 変換する操作
+    /// * `b` - `a`と同じ文書に対して並行に行われた操作
+    ///
+    /// # 戻り値
+    /// `(a', b')`を返す。`b`適用後の文書へ`a'`を適用した結果と、`a`適用後の
+    /// 文書へ`b'`を適用した結果は一致する。
+    ///
+    /// # 注記
+    /// 同じ位置への挿入は`a`の挿入を前に置く。
+    ///
+    pub(crate) fn transform(
+        a: &TextOperation,
+        b: &TextOperation,
+    ) -> Result<(TextOperation, TextOperation)> {
+        if a.base_len != b.base_len {
+            return Err(anyhow!(
+                "concurrent operations have different base lengths"
+            ));
+        }
+
+        let mut a_prime = TextOperation::new();
+        let mut b_prime = TextOperation::new();
+        let mut iter_a = a.components.iter().cloned();
+        let mut iter_b = b.components.iter().cloned();
+        let mut op_a = iter_a.next();
+        let mut op_b = iter_b.next();
+
+        loop {
+            match (op_a.take(), op_b.take()) {
+                (None, None) => break,
+
+                /*
+                 * 挿入は相手の操作を進めずに処理する
+                 */
+                (Some(OpComponent::Insert(text)), rest) => {
+                    b_prime.retain(text.chars().count());
+                    a_prime.insert(&text);
+                    op_a = iter_a.next();
+                    op_b = rest;
+                }
+                (rest, Some(OpComponent::Insert(text))) => {
+                    a_prime.retain(text.chars().count());
+                    b_prime.insert(&text);
+                    op_a = rest;
+                    op_b = iter_b.next();
+                }
+
+                (None, _) | (_, None) => {
+                    return Err(anyhow!(
+                        "concurrent operations have different lengths"
+                    ));
+                }
+
+                /*
+                 * 保持と削除は短い方に合わせて消費する
+                 */
+                (Some(left), Some(right)) => {
+                    let (left_len, right_len) =
+                        (component_len(&left), component_len(&right));
+                    let count = left_len.min(right_len);
+
+                    match (&left, &right) {
+                        (OpComponent::Retain(_), OpComponent::Retain(_)) => {
+                            a_prime.retain(count);
+                            b_prime.retain(count);
+                        }
+                        (OpComponent::Delete(_), OpComponent::Retain(_)) => {
+                            a_prime.delete(count);
+                        }
+                        (OpComponent::Retain(_), OpComponent::Delete(_)) => {
+                            b_prime.delete(count);
+                        }
+                        _ => {}
+                    }
+
+                    op_a = match left_len > count {
+                        true => Some(shrink_component(left, count)),
+                        false => iter_a.next(),
+                    };
+                    op_b = match right_len > count {
+                        true => Some(shrink_component(right, count)),
+                        false => iter_b.next(),
+                    };
+                }
+            }
+        }
+
+        Ok((a_prime, b_prime))
+    }
+}
+
+///
+/// 保持・削除要素の文字数
+///
+fn component_len(component: &OpComponent) -> usize {
+    match component {
+        OpComponent::Retain(count) | OpComponent::Delete(count) => *count,
+        OpComponent::Insert(text) => text.chars().count(),
+    }
+}
+
+///
+/// 保持・削除要素の先頭からの消費
+///
+fn shrink_component(component: OpComponent, count: usize) -> OpComponent {
+    match component {
+        OpComponent::Retain(len) => OpComponent::Retain(len - count),
+        OpComponent::Delete(len) => OpComponent::Delete(len - count),
+        insert => insert,
+    }
+}
+
+// TryFromトレイトの実装
+impl TryFrom<Vec<RawComponent>> for TextOperation {
+    type Error = Error;
+
+    fn try_from(raw: Vec<RawComponent>) -> Result<Self> {
+        let mut operation = TextOperation::new();
+
+        for component in raw {
+            match component {
+                RawComponent::Count(0) => {
+                    return Err(anyhow!("operation contains zero length"));
+                }
+                RawComponent::Count(count) if count > 0 => {
+                    operation.retain(count as usize);
+                }
+                RawComponent::Count(count) => {
+                    operation.delete(count.unsigned_abs() as usize);
+                }
+                RawComponent::Text(text) if text.is_empty() => {
+                    return Err(anyhow!("operation contains empty insert"));
+                }
+                RawComponent::Text(text) => {
+                    operation.insert(&text);
+                }
+            }
+        }
+
+        Ok(operation)
+    }
+}
+
+// Fromトレイトの実装
+impl From<TextOperation> for Vec<RawComponent> {
+    fn from(operation: TextOperation) -> Self {
+        operation
+            .components
+            .into_iter()
+            .map(|component| match component {
+                OpComponent::Retain(count) => RawComponent::Count(count as i64),
+                OpComponent::Insert(text) => RawComponent::Text(text),
+                OpComponent::Delete(count) => {
+                    RawComponent::Count(-(count as i64))
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///
+    /// JSON表現からの操作生成と文書への適用を確認する。
+    ///
+    #[test]
+    fn text_operation_parses_json_and_applies() {
+        let operation: TextOperation =
+            serde_json::from_str(r#"[2, "あい", -1, 2]"#).expect("parse");
+
+        assert_eq!(operation.base_len, 5);
+        assert_eq!(operation.target_len, 6);
+        assert_eq!(operation.apply("abXcd").expect("apply"), "abあいcd");
+        assert!(operation.apply("abc").is_err());
+        assert_eq!(
+            serde_json::to_string(&operation).expect("serialize"),
+            r#"[2,"あい",-1,2]"#
+        );
+
+        assert!(serde_json::from_str::<TextOperation>("[0]").is_err());
+        assert!(serde_json::from_str::<TextOperation>(r#"[""]"#).is_err());
+    }
+
+    ///
+    /// 削除の直後の挿入が削除の前へ正規化されることを確認する。
+    ///
+    #[test]
+    fn text_operation_normalizes_insert_after_delete() {
+        let mut operation = TextOperation::new();
+        operation.retain(1).delete(2).insert("x").insert("y").retain(1);
+
+        let mut expected = TextOperation::new();
+        expected.retain(1).insert("xy").delete(2).retain(1);
+
+        assert_eq!(operation, expected);
+        assert_eq!(operation.apply("abcd").expect("apply"), "axyd");
+    }
+
+    ///
+    /// 並行操作の変換結果がどちらの順で適用しても収束することを確認する。
+    ///
+    #[test]
+    fn transform_converges_concurrent_operations() {
+        let document = "meeting notes";
+        let cases = [
+            (r#"[7, " minutes", 6]"#, r#"[-8, 5]"#),
+            (r#"[13, "!"]"#, r#"[13, "?"]"#),
+            (r#"[2, -5, 6]"#, r#"[4, -6, 3]"#),
+            (r##"["# ", 13]"##, r#"[8, -5, "memo"]"#),
+        ];
+
+        for (left, right) in cases {
+            let a: TextOperation = serde_json::from_str(left).expect("a");
+            let b: TextOperation = serde_json::from_str(right).expect("b");
+            let (a_prime, b_prime) =
+                TextOperation::transform(&a, &b).expect("transform");
+
+            let via_a = b_prime
+                .apply(&a.apply(document).expect("apply a"))
+                .expect("apply b'");
+            let via_b = a_prime
+                .apply(&b.apply(document).expect("apply b"))
+                .expect("apply a'");
+            assert_eq!(via_a, via_b, "diverged: {} / {}", left, right);
+        }
+
+        let a: TextOperation = serde_json::from_str(r#"[13, "!"]"#).unwrap();
+        let b: TextOperation = serde_json::from_str(r#"[13, "?"]"#).unwrap();
+        let (a_prime, _) = TextOperation::transform(&a, &b).unwrap();
+        assert_eq!(
+            a_prime.apply(&b.apply(document).unwrap()).unwrap(),
+            "meeting notes!?"
+        );
+
+        let short: TextOperation = serde_json::from_str("[3]").unwrap();
+        assert!(TextOperation::transform(&a, &short).is_err());
+    }
+
+    ///
+    /// カーソル位置が挿入・削除に追従することを確認する。
+    ///
+    #[test]
+    fn transform_index_follows_edits() {
+        let operation: TextOperation =
+            serde_json::from_str(r#"[2, "xyz", 3, -4, 1]"#).expect("parse");
+
+        assert_eq!(operation.transform_index(0), 0);
+        assert_eq!(operation.transform_index(2), 5);
+        assert_eq!(operation.transform_index(4), 7);
+        assert_eq!(operation.transform_index(7), 8);
+        assert_eq!(operation.transform_index(9), 8);
+        assert_eq!(operation.transform_index(10), 9);
+    }
+}
@@ -0,0 +1,769 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! ページ単位の共同編集セッションの実装
+//!
+
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Instant;
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::ot::TextOperation;
+use crate::audit::model::{AuditOperation, AuditRecord, AuditResult};
+use crate::database::types::{LockToken, PageId};
+use crate::fts;
+use crate::http_server::app_state::AppState;
+
+/// 変換用に保持する操作履歴の上限
+const HISTORY_LIMIT: usize = 1000;
+
+///
+/// 接続へ送出するイベント
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum CollabEvent {
+    /// テキストメッセージの送出
+    Text(String),
+
+    /// 接続の終了
+    Close,
+}
+
+///
+/// クライアントから受信するメッセージ
+///
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum ClientMessage {
+    /// 文書への編集操作
+    Op {
+        /// 操作の基準とした文書リビジョン
+        revision: u64,
+
+        /// 編集操作
+        operation: TextOperation,
+    },
+
+    /// カーソル位置の通知
+    Cursor {
+        /// カーソル位置(文字単位)
+        position: Option<usize>,
+    },
+}
+
+///
+/// クライアントへ送信するメッセージ
+///
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum ServerMessage {
+    /// 参加直後の文書状態
+    Init {
+        /// 自身に割り当てられた接続ID
+        client_id: u64,
+
+        /// 文書リビジョン
+        revision: u64,
+
+        /// 文書本文
+        document: String,
+
+        /// 参加者一覧
+        participants: Vec<ParticipantInfo>,
+    },
+
+    /// 自身の操作の受理
+    Ack {
+        /// 操作適用後の文書リビジョン
+        revision: u64,
+    },
+
+    /// 他の参加者の操作
+    Op {
+        /// 操作適用後の文書リビジョン
+        revision: u64,
+
+        /// 操作を行った接続ID
+        client_id: u64,
+
+        /// 操作を行ったユーザ名
+        user: String,
+
+        /// 変換済みの編集操作
+        operation: TextOperation,
+    },
+
+    /// 参加者一覧の更新
+    Presence {
+        /// 参加者一覧
+        participants: Vec<ParticipantInfo>,
+    },
+
+    /// ページリビジョンへの保存
+    Checkpoint {
+        /// 保存したページのリビジョン
+        page_revision: u64,
+
+        /// 保存時の記録ユーザ
+        user: String,
+    },
+
+    /// エラー通知
+    Error {
+        /// エラーの理由
+        reason: String,
+    },
+}
+
+///
+/// 参加者の公開情報
+///
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub(crate) struct ParticipantInfo {
+    /// 接続ID
+    pub(crate) client_id: u64,
+
+    /// ユーザ名
+    pub(crate) user: String,
+
+    /// カーソル位置(文字単位)
+    pub(crate) cursor: Option<usize>,
+}
+
+///
+/// セッションへの参加者
+///
+#[derive(Debug)]
+struct Participant {
+    /// ユーザ名
+    user: String,
+
+    /// カーソル位置(文字単位)
+    cursor: Option<usize>,
+
+    /// イベントの送出先
+    sender: UnboundedSender<CollabEvent>,
+}
+
+///
+/// ページ単位の共同編集セッション
+///
+/// # 注記
+/// セッションはページロックを保持し、編集内容を定期的にページの
+/// リビジョンとして保存する。
+///
+#[derive(Debug)]
+pub(crate) struct CollabSession {
+    /// 対象ページ
+    page_id: PageId,
+
+    /// 現在の文書本文
+    document: String,
+
+    /// 文書リビジョン(セッション開始からの操作数)
+    revision: u64,
+
+    /// 直近の操作履歴
+    history: VecDeque<TextOperation>,
+
+    /// 参加者(接続ID順)
+    participants: BTreeMap<u64, Participant>,
+
+    /// ページロックを取得したユーザ名
+    lock_user: String,
+
+    /// ページロックのトークン
+    lock_token: LockToken,
+
+    /// 未保存の編集を最後に行ったユーザ名
+    dirty_by: Option<String>,
+
+    /// 最後に保存を行った時刻
+    last_checkpoint: Instant,
+}
+
+impl CollabSession {
+    ///
+    /// セッションの生成
+    ///
+    /// # 引数
+    /// * `page_id` - 対象ページ
+    /// * `document` - ページの最新ソース
+    /// * `lock_user` - ページロックを取得したユーザ名
+    /// * `lock_token` - ページロックのトークン
+    ///
+    /// # 戻り値
+    /// 参加者を持たないセッションを返す。
+    ///
+    pub(crate) fn new(
+        page_id: PageId,
+        document: String,
+        lock_user: String,
+        lock_token: LockToken,
+    ) -> Self {
+        Self {
+            page_id,
+            document,
+            revision: 0,
+            history: VecDeque::new(),
+            participants: BTreeMap::new(),
+            lock_user,
+            lock_token,
+            dirty_by: None,
+            last_checkpoint: Instant::now(),
+        }
+    }
+
+    ///
+    /// 参加者が残っているか否かの判定
+    ///
+    pub(crate) fn is_empty(&self) -> bool {
+        self.participants.is_empty()
+    }
+
+    ///
+    /// 未保存の編集の有無の判定
+    ///
+    pub(crate) fn is_dirty(&self) -> bool {
+        self.dirty_by.is_some()
+    }
+
+    ///
+    /// 最後の保存からの経過時間
+    ///
+    pub(crate) fn since_checkpoint(&self) -> std::time::Duration {
+        self.last_checkpoint.elapsed()
+    }
+
+    ///
+    /// 参加者の追加
+    ///
+    /// # 引数
+    /// * `client_id` - 接続ID
+    /// * `user` - ユーザ名
+    /// * `sender` - イベントの送出先
+    ///
+    /// # 注記
+    /// 追加した参加者へ文書状態を送信し、他の参加者へ参加者一覧を通知する。
+    ///
+    pub(crate) fn join(
+        &mut self,
+        client_id: u64,
+        user: &str,
+        sender: UnboundedSender<CollabEvent>,
+    ) {
+        self.participants.insert(
+            client_id,
+            Participant {
+                user: user.to_string(),
+                cursor: None,
+                sender,
+            },
+        );
+
+        self.send(
+            client_id,
+            &ServerMessage::Init {
+                client_id,
+                revision: self.revision,
+                document: self.document.clone(),
+                participants: self.participants(),
+            },
+        );
+        self.broadcast(&self.presence(), Some(client_id));
+    }
+
+    ///
+    /// 参加者の削除
+    ///
+    /// # 引数
+    /// * `client_id` - 接続ID
+    ///
+    /// # 注記
+    /// 残った参加者へ参加者一覧を通知する。
+    ///
+    pub(crate) fn leave(&mut self, client_id: u64) {
+        if self.participants.remove(&client_id).is_some() {
+            self.broadcast(&self.presence(), None);
+        }
+    }
+
+    ///
+    /// 受信メッセージの処理
+    ///
+    /// # 引数
+    /// * `client_id` - 送信元の接続ID
+    /// * `message` - 受信したメッセージ
+    ///
+    /// # 注記
+    /// 処理できないメッセージは送信元へエラーとして通知する。
+    ///
+    pub(crate) fn handle_message(
+        &mut self,
+        client_id: u64,
+        message: ClientMessage,
+    ) {
+        let result = match message {
+            ClientMessage::Op {
+                revision,
+                operation,
+            } => self.apply_operation(client_id, revision, operation),
+            ClientMessage::Cursor { position } => {
+                self.update_cursor(client_id, position)
+            }
+        };
+
+        if let Err(err) = result {
+            self.send(
+                client_id,
+                &ServerMessage::Error {
+                    reason: err.to_string(),
+                },
+            );
+        }
+    }
+
+    ///
+    /// 編集操作の適用
+    ///
+    /// # 引数
+    /// * `client_id` - 送信元の接続ID
+    /// * `revision` - 操作の基準とした文書リビジョン
+    /// * `operation` - 編集操作
+    ///
+    /// # 戻り値
+    /// 適用に成功した場合は`Ok(())`を返す。
+    ///
+    /// # 注記
+    /// 基準リビジョン以降に適用済みの操作に対して変換してから適用し、
+    /// 送信元へ受理を、他の参加者へ変換済みの操作を送信する。
+    ///
+    pub(crate) fn apply_operation(
+        &mut self,
+        client_id: u64,
+        revision: u64,
+        operation: TextOperation,
+    ) -> Result<()> {
+        let user = match self.participants.get(&client_id) {
+            Some(participant) => participant.user.clone(),
+            None => return Err(anyhow!("unknown client")),
+        };
+
+        /*
+         * 並行操作に対する変換
+         */
+        let oldest = self.revision - self.history.len() as u64;
+        if revision > self.revision {
+            return Err(anyhow!("unknown revision: {}", revision));
+        }
+        if revision < oldest {
+            return Err(anyhow!("revision too old: {}", revision));
+        }
+
+        let mut operation = operation;
+        for concurrent in self.history.iter().skip((revision - oldest) as usize)
+        {
+            operation = TextOperation::transform(&operation, concurrent)?.0;
+        }
+
+        /*
+         * 文書への適用
+         */
+        self.document = operation.apply(&self.document)?;
+        self.revision += 1;
+        self.history.push_back(operation.clone());
+        if self.history.len() > HISTORY_LIMIT {
+            self.history.pop_front();
+        }
+
+        if !operation.is_noop() {
+            self.dirty_by = Some(user.clone());
+        }
+
+        for participant in self.participants.values_mut() {
+            participant.cursor = participant
+                .cursor
+                .map(|cursor| operation.transform_index(cursor));
+        }
+
+        /*
+         * 参加者への通知
+         */
+        self.send(
+            client_id,
+            &ServerMessage::Ack {
+                revision: self.revision,
+            },
+        );
+        self.broadcast(
+            &ServerMessage::Op {
+                revision: self.revision,
+                client_id,
+                user,
+                operation,
+            },
+            Some(client_id),
+        );
+
+        Ok(())
+    }
+
+    ///
+    /// カーソル位置の更新
+    ///
+    /// # 引数
+    /// * `client_id` - 送信元の接続ID
+    /// * `position` - 現在の文書上のカーソル位置
+    ///
+    /// # 戻り値
+    /// 更新に成功した場合は`Ok(())`を返す。
+    ///
+    pub(crate) fn update_cursor(
+        &mut self,
+        client_id: u64,
+        position: Option<usize>,
+    ) -> Result<()> {
+        let length = self.document.chars().count();
+        if position.is_some_and(|position| position > length) {
+            return Err(anyhow!("cursor out of range"));
+        }
+
+        match self.participants.get_mut(&client_id) {
+            Some(participant) => participant.cursor = position,
+            None => return Err(anyhow!("unknown client")),
+        }
+
+        self.broadcast(&self.presence(), Some(client_id));
+
+        Ok(())
+    }
+
+    ///
+    /// 未保存の編集のページリビジョンへの保存
+    ///
+    /// # 引数
+    /// * `state` - 共有状態
+    ///
+    /// # 戻り値
+    /// 保存した場合は`Ok(Some(ページリビジョン))`、未保存の編集がない場合は
+    /// `Ok(None)`を返す。
+    ///
+    /// # 注記
+    /// リビジョンの記録ユーザは最後に編集を行った参加者とする。保存後に
+    /// 全文検索インデックスを更新し、監査ログへ記録して参加者へ通知する。
+    ///
+    pub(crate) fn checkpoint(&mut self, state: &AppState) -> Result<Option<u64>> {
+        let Some(user) = self.dirty_by.clone() else {
+            return Ok(None);
+        };
+
+        /*
+         * ページの更新
+         */
+        let db = state.db();
+        db.put_page(&self.page_id, &user, self.document.clone(), false)?;
+        self.dirty_by = None;
+        self.last_checkpoint = Instant::now();
+
+        let index = db
+            .get_page_index_by_id(&self.page_id)?
+            .ok_or_else(|| anyhow!("page not found"))?;
+        let page_revision = index.latest();
+
+        if let Err(err) =
+            fts::reindex_page(state.fts_config(), db, &self.page_id, false)
+        {
+            warn!("collab checkpoint fts update failed: {:?}", err);
+        }
+
+        /*
+         * 監査ログへの記録と参加者への通知
+         */
+        state.record_audit(AuditRecord::new(
+            AuditOperation::Update,
+            db.get_user_id_by_name(&user).ok().flatten(),
+            None,
+            None,
+            index.current_path().map(str::to_string),
+            AuditResult::Success,
+            Utc::now(),
+            Some("collab checkpoint".to_string()),
+            Some(page_revision),
+        ));
+
+        self.broadcast(
+            &ServerMessage::Checkpoint {
+                page_revision,
+                user,
+            },
+            None,
+        );
+
+        Ok(Some(page_revision))
+    }
+
+    ///
+    /// ページロックの延長
+    ///
+    /// # 引数
+    /// * `state` - 共有状態
+    ///
+    /// # 戻り値
+    /// 延長に成功した場合は`Ok(())`を返す。
+    ///
+    pub(crate) fn renew_lock(&mut self, state: &AppState) -> Result<()> {
+        let info = state.db().renew_page_lock(
+            &self.page_id,
+            &self.lock_user,
+            &self.lock_token,
+        )?;
+        self.lock_token = info.token();
+
+        Ok(())
+    }
+
+    ///
+    /// ページロックの保持の確認
+    ///
+    /// # 引数
+    /// * `token` - 確認するロック解除トークン
+    ///
+    /// # 戻り値
+    /// セッションが指定されたロックを保持している場合は`true`を返す。
+    ///
+    pub(crate) fn holds_lock(&self, token: &LockToken) -> bool {
+        self.lock_token == *token
+    }
+
+    ///
+    /// ページロックの解除
+    ///
+    /// # 引数
+    /// * `state` - 共有状態
+    ///
+    /// # 戻り値
+    /// 解除に成功した場合は`Ok(())`を返す。
+    ///
+    pub(crate) fn release_lock(&self, state: &AppState) -> Result<()> {
+        state.db().release_page_lock(
+            &self.page_id,
+            &self.lock_user,
+            &self.lock_token,
+        )
+    }
+
+    ///
+    /// 全参加者の切断
+    ///
+    /// # 引数
+    /// * `reason` - 切断理由
+    ///
+    pub(crate) fn close_all(&mut self, reason: &str) {
+        self.broadcast(
+            &ServerMessage::Error {
+                reason: reason.to_string(),
+            },
+            None,
+        );
+
+        for participant in self.participants.values() {
+            let _ = participant.sender.send(CollabEvent::Close);
+        }
+        self.participants.clear();
+    }
+
+    ///
+    /// 参加者一覧の生成
+    ///
+    fn participants(&self) -> Vec<ParticipantInfo> {
+        self.participants
+            .iter()
+            .map(|(client_id, participant)| ParticipantInfo {
+                client_id: *client_id,
+                user: participant.user.clone(),
+                cursor: participant.cursor,
+            })
+            .collect()
+    }
+
+    ///
+    /// 参加者一覧メッセージの生成
+    ///
+    fn presence(&self) -> ServerMessage {
+        ServerMessage::Presence {
+            participants: self.participants(),
+        }
+    }
+
+    ///
+    /// 指定参加者へのメッセージ送信
+    ///
+    pub(crate) fn send(&self, client_id: u64, message: &ServerMessage) {
+        if let Some(participant) = self.participants.get(&client_id) {
+            send_message(&participant.sender, message);
+        }
+    }
+
+    ///
+    /// 参加者全体へのメッセージ送信
+    ///
+    /// # 引数
+    /// * `message` - 送信するメッセージ
+    /// * `except` - 送信対象から除く接続ID
+    ///
+    pub(crate) fn broadcast(&self, message: &ServerMessage, except: Option<u64>) {
+        for (client_id, participant) in &self.participants {
+            if Some(*client_id) != except {
+                send_message(&participant.sender, message);
+            }
+        }
+    }
+}
+
+///
+/// メッセージの直列化と送出
+///
+/// # 注記
+/// 切断済みの接続への送出失敗は無視する(切断処理は受信側で行う)。
+///
+fn send_message(sender: &UnboundedSender<CollabEvent>, message: &ServerMessage) {
+    match serde_json::to_string(message) {
+        Ok(text) => {
+            let _ = sender.send(CollabEvent::Text(text));
+        }
+        Err(err) => warn!("collab message serialize failed: {:?}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::Value;
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+    ///
+    /// 受信済みメッセージをJSONとして取り出す
+    ///
+    fn drain(receiver: &mut UnboundedReceiver<CollabEvent>) -> Vec<Value> {
+        let mut messages = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            if let CollabEvent::Text(text) = event {
+                messages.push(serde_json::from_str(&text).expect("json"));
+            }
+        }
+        messages
+    }
+
+    ///
+    /// テスト用セッションの生成
+    ///
+    fn new_session(document: &str) -> CollabSession {
+        CollabSession::new(
+            PageId::new(),
+            document.to_string(),
+            "alice".to_string(),
+            LockToken::new(),
+        )
+    }
+
+    ///
+    /// 参加時に文書状態が送られ、他の参加者へ参加者一覧が通知されることを
+    /// 確認する。
+    ///
+    #[test]
+    fn join_sends_init_and_presence() {
+        let mut session = new_session("notes");
+        let (tx1, mut rx1) = unbounded_channel();
+        let (tx2, mut rx2) = unbounded_channel();
+
+        session.join(1, "alice", tx1);
+        session.join(2, "bob", tx2);
+
+        let first = drain(&mut rx1);
+        assert_eq!(first[0]["type"], "init");
+        assert_eq!(first[0]["document"], "notes");
+        assert_eq!(first[1]["type"], "presence");
+        assert_eq!(first[1]["participants"][1]["user"], "bob");
+
+        let second = drain(&mut rx2);
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0]["client_id"], 2);
+        assert_eq!(second[0]["participants"].as_array().unwrap().len(), 2);
+
+        session.leave(2);
+        let first = drain(&mut rx1);
+        assert_eq!(first[0]["participants"].as_array().unwrap().len(), 1);
+    }
+
+    ///
+    /// 同じリビジョンを基準にした並行操作が変換されて収束することを確認する。
+    ///
+    #[test]
+    fn concurrent_operations_are_transformed() {
+        let mut session = new_session("agenda\n");
+        let (tx1, mut rx1) = unbounded_channel();
+        let (tx2, mut rx2) = unbounded_channel();
+        session.join(1, "alice", tx1);
+        session.join(2, "bob", tx2);
+        session.update_cursor(2, Some(7)).expect("cursor");
+        drain(&mut rx1);
+        drain(&mut rx2);
+
+        let alice: TextOperation =
+            serde_json::from_str(r##"["# ", 7]"##).unwrap();
+        let bob: TextOperation =
+            serde_json::from_str(r#"[7, "- item\n"]"#).unwrap();
+        session.handle_message(
+            1,
+            ClientMessage::Op {
+                revision: 0,
+                operation: alice,
+            },
+        );
+        session.handle_message(
+            2,
+            ClientMessage::Op {
+                revision: 0,
+                operation: bob,
+            },
+        );
+
+        assert_eq!(session.document, "# agenda\n- item\n");
+        assert_eq!(session.revision, 2);
+        assert!(session.is_dirty());
+        assert_eq!(session.participants()[1].cursor, Some(16));
+
+        let to_alice = drain(&mut rx1);
+        assert_eq!(to_alice[0]["type"], "ack");
+        assert_eq!(to_alice[1]["type"], "op");
+        assert_eq!(to_alice[1]["user"], "bob");
+        assert_eq!(
+            to_alice[1]["operation"],
+            serde_json::json!([9, "- item\n"])
+        );
+
+        let to_bob = drain(&mut rx2);
+        assert_eq!(to_bob[0]["type"], "op");
+        assert_eq!(to_bob[1], serde_json::json!({"type": "ack", "revision": 2}));
+
+        session.handle_message(
+            1,
+            ClientMessage::Op {
+                revision: 5,
+                operation: TextOperation::new(),
+            },
+        );
+        let to_alice = drain(&mut rx1);
+        assert_eq!(to_alice[0]["type"], "error");
+    }
+}
@@ -14,9 +14,12 @@ use anyhow::{anyhow, Result};
 use mime_guess::MimeGuess;
 
 use super::CommandContext;
+use super::common::CliAuditLog;
+use crate::audit::model::AuditOperation;
 use crate::cmd_args::{AssetAddOpts, Options};
 use crate::database::types::PageId;
 use crate::database::{DatabaseManager, DbError};
+use crate::fts::{self, FtsIndexConfig};
 use crate::rest_api::{validate_asset_file_name, validate_page_path};
 
 ///
@@ -29,6 +32,8 @@ struct AssetAddCommandContext {
     file_path: std::path::PathBuf,
     target: String,
     asset_limit_size: u64,
+    index_path: std::path::PathBuf,
+    audit_log: CliAuditLog,
 }
 
 impl AssetAddCommandContext {
@@ -43,6 +48,8 @@ impl AssetAddCommandContext {
             file_path: sub_opts.file_path(),
             target: sub_opts.target(),
             asset_limit_size: opts.asset_limit_size()?,
+            index_path: opts.fts_index_path(),
+            audit_log: CliAuditLog::new(opts)?,
         })
     }
 }
@@ -107,6 +114,25 @@ impl CommandContext for AssetAddCommandContext {
             &data,
         )?;
 
+        /*
+         * インデックスの更新
+         */
+        let config = FtsIndexConfig::new(self.index_path.clone());
+        fts::reindex_asset(&config, &self.manager, &asset_id)?;
+
+        /*
+         * 監査ログへの記録
+         */
+        let page_path = self
+            .manager
+            .get_page_index_by_id(&page_id)?
+            .map(|index| index.path());
+        self.audit_log.record(
+            AuditOperation::AssetUpload,
+            page_path,
+            Some(format!("asset {}", file_name)),
+        )?;
+
         /*
          * 実行結果の出力
          */
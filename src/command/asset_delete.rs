@@ -8,12 +8,17 @@
 //! サブコマンド"asset delete"の実装
 //!
 
+use std::path::PathBuf;
+
 use anyhow::{anyhow, Result};
 
 use super::CommandContext;
+use super::common::CliAuditLog;
+use crate::audit::model::AuditOperation;
 use crate::cmd_args::{AssetDeleteOpts, Options};
 use crate::database::types::{AssetId, PageId};
 use crate::database::{DatabaseManager, DbError};
+use crate::fts::{self, FtsIndexConfig};
 use crate::rest_api::{validate_asset_file_name, validate_page_path};
 
 ///
@@ -23,6 +28,8 @@ struct AssetDeleteCommandContext {
     manager: DatabaseManager,
     target: String,
     hard_delete: bool,
+    index_path: PathBuf,
+    audit_log: CliAuditLog,
 }
 
 impl AssetDeleteCommandContext {
@@ -34,6 +41,8 @@ impl AssetDeleteCommandContext {
             manager: opts.open_database()?,
             target: sub_opts.target(),
             hard_delete: sub_opts.is_hard_delete(),
+            index_path: opts.fts_index_path(),
+            audit_log: CliAuditLog::new(opts)?,
         })
     }
 
@@ -141,7 +150,7 @@ impl AssetDeleteCommandContext {
     fn delete_page_assets(
         &self,
         page_id: &crate::database::types::PageId,
-    ) -> Result<()> {
+    ) -> Result<Vec<AssetId>> {
         /*
          * 削除対象アセットの検証
          */
@@ -157,15 +166,61 @@ impl AssetDeleteCommandContext {
         /*
          * アセット削除の実行
          */
+        let mut asset_ids = Vec::with_capacity(assets.len());
         for asset in assets {
             if self.hard_delete {
                 self.manager.delete_asset_hard(&asset.id())?;
             } else {
                 self.manager.delete_asset(&asset.id())?;
             }
+            asset_ids.push(asset.id());
         }
 
-        Ok(())
+        Ok(asset_ids)
+    }
+
+    ///
+    /// 監査ログへ記録する削除対象の収集
+    ///
+    /// # 引数
+    /// * `target` - 削除対象
+    ///
+    /// # 戻り値
+    /// 削除対象アセット毎の所属ページのパスとファイル名を返す。
+    ///
+    /// # 注記
+    /// 完全削除ではアセット情報が失われるため、削除前に呼び出すこと。
+    ///
+    fn audit_targets(
+        &self,
+        target: &AssetDeleteTarget,
+    ) -> Result<Vec<(Option<String>, String)>> {
+        let asset_ids = match target {
+            AssetDeleteTarget::Asset(asset_id) => vec![asset_id.clone()],
+            AssetDeleteTarget::Page(page_id) => self
+                .manager
+                .list_page_assets(page_id)?
+                .iter()
+                .map(|asset| asset.id())
+                .collect(),
+        };
+
+        let mut targets = Vec::with_capacity(asset_ids.len());
+        for asset_id in asset_ids {
+            let Some(info) = self.manager.get_asset_info_by_id(&asset_id)? else {
+                continue;
+            };
+            let page_path = match info.page_id() {
+                Some(page_id) => self
+                    .manager
+                    .get_page_index_by_id(&page_id)?
+                    .map(|index| index.path()),
+                None => None,
+            };
+            targets.push((page_path, info.file_name()));
+        }
+
+        Ok(targets)
     }
 }
 
@@ -180,17 +235,40 @@ impl CommandContext for AssetDeleteCommandContext {
         /*
          * 削除対象の解決と削除実行
          */
-        match self.resolve_target()? {
+        let target = self.resolve_target()?;
+        let audit_targets = self.audit_targets(&target)?;
+        let asset_ids = match target {
             AssetDeleteTarget::Asset(asset_id) => {
                 if self.hard_delete {
                     self.manager.delete_asset_hard(&asset_id)?;
                 } else {
                     self.manager.delete_asset(&asset_id)?;
                 }
+                vec![asset_id]
             }
             AssetDeleteTarget::Page(page_id) => {
-                self.delete_page_assets(&page_id)?;
+                self.delete_page_assets(&page_id)?
             }
+        };
+
+        /*
+         * インデックスの更新
+         */
+        let config = FtsIndexConfig::new(self.index_path.clone());
+        for asset_id in &asset_ids {
+            fts::reindex_asset(&config, &self.manager, asset_id)?;
+        }
+
+        /*
+         * 監査ログへの記録
+         */
+        let mode = if self.hard_delete { "hard" } else { "soft" };
+        for (page_path, file_name) in audit_targets {
+            self.audit_log.record(
+                AuditOperation::AssetDelete,
+                page_path,
+                Some(format!("asset {} mode={}", file_name, mode)),
+            )?;
         }
 
         Ok(())
@@ -0,0 +1,86 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! サブコマンド"asset fsck"の実装
+//!
+
+use anyhow::{anyhow, Result};
+
+use super::CommandContext;
+use crate::cmd_args::{AssetFsckOpts, Options};
+use crate::database::DatabaseManager;
+
+///
+/// "asset fsck"サブコマンドのコンテキスト情報をパックした構造体
+///
+struct AssetFsckCommandContext {
+    manager: DatabaseManager,
+    fix: bool,
+}
+
+impl AssetFsckCommandContext {
+    ///
+    /// オブジェクトの生成
+    ///
+    fn new(opts: &Options, sub_opts: &AssetFsckOpts) -> Result<Self> {
+        Ok(Self {
+            manager: opts.open_database()?,
+            fix: sub_opts.is_fix(),
+        })
+    }
+}
+
+impl CommandContext for AssetFsckCommandContext {
+    ///
+    /// サブコマンドを実行
+    ///
+    /// # 戻り値
+    /// 未解決の不整合が無い場合は`Ok(())`を返す。
+    ///
+    fn exec(&self) -> Result<()> {
+        /*
+         * 整合性検査(修復指定時は修復も実施)
+         */
+        let issues = self.manager.check_asset_blobs(self.fix)?;
+
+        /*
+         * 検査結果の表示
+         */
+        let mut unresolved = 0;
+        for issue in &issues {
+            if self.fix && issue.is_repairable() {
+                println!("fixed: {}", issue);
+            } else {
+                println!("{}", issue);
+                unresolved += 1;
+            }
+        }
+
+        if unresolved > 0 {
+            return Err(anyhow!(
+                "asset fsck found {} unresolved issue(s)",
+                unresolved
+            ));
+        }
+
+        if issues.is_empty() {
+            println!("no issues found");
+        }
+
+        Ok(())
+    }
+}
+
+///
+/// コマンドコンテキストの生成
+///
+pub(crate) fn build_context(
+    opts: &Options,
+    sub_opts: &AssetFsckOpts,
+) -> Result<Box<dyn CommandContext>> {
+    Ok(Box::new(AssetFsckCommandContext::new(opts, sub_opts)?))
+}
@@ -0,0 +1,92 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! サブコマンド"asset gc"の実装
+//!
+
+use anyhow::Result;
+
+use super::CommandContext;
+use crate::cmd_args::{AssetGcOpts, Options};
+use crate::database::{AssetGcItem, DatabaseManager};
+
+///
+/// "asset gc"サブコマンドのコンテキスト情報をパックした構造体
+///
+struct AssetGcCommandContext {
+    manager: DatabaseManager,
+    dry_run: bool,
+    delete_unreferenced: bool,
+}
+
+impl AssetGcCommandContext {
+    ///
+    /// オブジェクトの生成
+    ///
+    fn new(opts: &Options, sub_opts: &AssetGcOpts) -> Result<Self> {
+        Ok(Self {
+            manager: opts.open_database()?,
+            dry_run: sub_opts.is_dry_run(),
+            delete_unreferenced: sub_opts.is_delete_unreferenced(),
+        })
+    }
+}
+
+impl CommandContext for AssetGcCommandContext {
+    ///
+    /// サブコマンドを実行
+    ///
+    /// # 戻り値
+    /// 回収処理に成功した場合は`Ok(())`を返す。
+    ///
+    fn exec(&self) -> Result<()> {
+        /*
+         * 回収対象の検出(表示のみ指定でない場合は回収も実施)
+         */
+        let items = self
+            .manager
+            .collect_asset_garbage(!self.dry_run, self.delete_unreferenced)?;
+
+        /*
+         * 検出結果の表示
+         */
+        for item in &items {
+            let removed = match item {
+                AssetGcItem::OrphanBlob { .. }
+                | AssetGcItem::StrayFile { .. } => !self.dry_run,
+                AssetGcItem::Unreferenced { .. } => self.delete_unreferenced,
+                AssetGcItem::MissingBlob { .. } => false,
+            };
+
+            if removed {
+                println!("removed: {}", item);
+            } else {
+                println!("{}", item);
+            }
+        }
+
+        let reclaimable: u64 =
+            items.iter().map(AssetGcItem::reclaimable_size).sum();
+        if self.dry_run {
+            println!("{} bytes reclaimable", reclaimable);
+        } else {
+            println!("{} bytes reclaimed", reclaimable);
+        }
+
+        Ok(())
+    }
+}
+
+///
+/// コマンドコンテキストの生成
+///
+pub(crate) fn build_context(
+    opts: &Options,
+    sub_opts: &AssetGcOpts,
+) -> Result<Box<dyn CommandContext>> {
+    Ok(Box::new(AssetGcCommandContext::new(opts, sub_opts)?))
+}
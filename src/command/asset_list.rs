@@ -312,8 +312,15 @@ fn asset_mark(asset: &AssetListEntry) -> String {
 /// # 戻り値
 /// 表示用アセットパス文字列を返す。
 ///
+/// # 注記
+/// 共有アセットは`asset:`記法と同じ`:{ファイル名}`の形式で表示する。
+///
 fn asset_path_display(asset: &AssetListEntry) -> String {
     let file_name = asset.file_name();
+    if asset.is_shared() {
+        return format!(":{}", file_name);
+    }
+
     let path = match asset.page_path() {
         Some(path) => path,
         None => "?????".to_string(),
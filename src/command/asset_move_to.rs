@@ -8,12 +8,15 @@
 //! サブコマンド"asset move_to"の実装
 //!
 
+use std::path::PathBuf;
+
 use anyhow::{anyhow, Result};
 
 use super::CommandContext;
 use crate::cmd_args::{AssetMoveToOpts, Options};
 use crate::database::types::{AssetId, PageId};
 use crate::database::{AssetMoveResult, DatabaseManager, DbError};
+use crate::fts::{self, FtsIndexConfig};
 use crate::rest_api::validate_page_path;
 
 ///
@@ -24,6 +27,7 @@ struct AssetMoveToCommandContext {
     asset_id: AssetId,
     dst_target: String,
     force: bool,
+    index_path: PathBuf,
 }
 
 impl AssetMoveToCommandContext {
@@ -37,6 +41,7 @@ impl AssetMoveToCommandContext {
             asset_id,
             dst_target: sub_opts.dst_target(),
             force: sub_opts.is_force(),
+            index_path: opts.fts_index_path(),
         })
     }
 
@@ -115,7 +120,19 @@ impl CommandContext for AssetMoveToCommandContext {
             .manager
             .move_asset(&self.asset_id, &dst_page_id, self.force)?
         {
-            AssetMoveResult::Moved => Ok(()),
+            AssetMoveResult::Moved => {
+                /*
+                 * インデックスの更新(上書きされたアセットを含む)
+                 */
+                let config = FtsIndexConfig::new(self.index_path.clone());
+                fts::reindex_asset(&config, &self.manager, &self.asset_id)?;
+                if let Some(conflict_asset) =
+                    conflict_asset.filter(|id| id != &self.asset_id)
+                {
+                    fts::reindex_asset(&config, &self.manager, &conflict_asset)?;
+                }
+                Ok(())
+            }
             AssetMoveResult::PageNotFound => {
                 Err(anyhow!("destination page not found"))
             }
@@ -12,7 +12,7 @@ use anyhow::{anyhow, Result};
 
 use super::CommandContext;
 use crate::cmd_args::{AssetPurgeOpts, Options};
-use crate::database::types::PageId;
+use crate::database::types::{AssetId, PageId};
 use crate::database::{DatabaseManager, DbError};
 use crate::rest_api::validate_page_path;
 
@@ -86,7 +86,7 @@ impl AssetPurgeCommandContext {
         }
 
         for asset_id in deleted_assets {
-            self.manager.delete_asset_hard(&asset_id)?;
+            self.purge_asset(&asset_id)?;
         }
 
         Ok(())
@@ -102,12 +102,38 @@ impl AssetPurgeCommandContext {
         let assets = self.manager.list_assets()?;
         for asset in assets {
             if asset.deleted() {
-                self.manager.delete_asset_hard(&asset.id())?;
+                self.purge_asset(&asset.id())?;
             }
         }
 
         Ok(())
     }
+
+    ///
+    /// 削除済みアセットのパージ
+    ///
+    /// # 引数
+    /// * `asset_id` - 対象アセットID
+    ///
+    /// # 戻り値
+    /// パージに成功した場合は`Ok(())`を返す。
+    ///
+    /// # 注記
+    /// ページから参照されているアセットはパージせず、その旨を表示して
+    /// 処理を継続する。
+    ///
+    fn purge_asset(&self, asset_id: &AssetId) -> Result<()> {
+        match self.manager.delete_asset_hard(asset_id) {
+            Ok(()) => Ok(()),
+            Err(err) => match err.downcast_ref::<DbError>() {
+                Some(DbError::AssetReferenced) => {
+                    println!("skip referenced asset: {}", asset_id);
+                    Ok(())
+                }
+                _ => Err(err),
+            },
+        }
+    }
 }
 
 impl CommandContext for AssetPurgeCommandContext {
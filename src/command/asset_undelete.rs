@@ -8,12 +8,15 @@
 //! サブコマンド"asset undelete"の実装
 //!
 
+use std::path::PathBuf;
+
 use anyhow::{anyhow, Result};
 
 use super::CommandContext;
 use crate::cmd_args::{AssetUndeleteOpts, Options};
 use crate::database::types::AssetId;
 use crate::database::{DatabaseManager, DbError};
+use crate::fts::{self, FtsIndexConfig};
 
 ///
 /// "asset undelete"サブコマンドのコンテキスト情報をパックした構造体
@@ -22,6 +25,7 @@ struct AssetUndeleteCommandContext {
     manager: DatabaseManager,
     asset_id: AssetId,
     rename_to: Option<String>,
+    index_path: PathBuf,
 }
 
 impl AssetUndeleteCommandContext {
@@ -34,6 +38,7 @@ impl AssetUndeleteCommandContext {
             manager: opts.open_database()?,
             asset_id,
             rename_to: sub_opts.rename_to(),
+            index_path: opts.fts_index_path(),
         })
     }
 }
@@ -62,6 +67,12 @@ impl CommandContext for AssetUndeleteCommandContext {
          */
         self.manager
             .undelete_asset(&self.asset_id, self.rename_to.as_deref())?;
+
+        /*
+         * インデックスの更新
+         */
+        let config = FtsIndexConfig::new(self.index_path.clone());
+        fts::reindex_asset(&config, &self.manager, &self.asset_id)?;
         Ok(())
     }
 }
@@ -0,0 +1,234 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! サブコマンド"audit query"の実装
+//!
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use chrono::Local;
+
+use super::CommandContext;
+use super::common::format_cli_timestamp;
+use crate::audit::model::AuditRecord;
+use crate::audit::query::{AuditQuery, query_audit_records};
+use crate::cmd_args::{AuditQueryOpts, Options};
+use crate::database::types::UserId;
+use crate::database::{DatabaseManager, DbError};
+
+///
+/// "audit query"サブコマンドのコンテキスト情報をパックした構造体
+///
+struct AuditQueryCommandContext {
+    manager: DatabaseManager,
+    audit_log_dir: PathBuf,
+    user_name: Option<String>,
+    query: AuditQuery,
+    long_info: bool,
+    json: bool,
+}
+
+impl AuditQueryCommandContext {
+    ///
+    /// オブジェクトの生成
+    ///
+    fn new(opts: &Options, sub_opts: &AuditQueryOpts) -> Result<Self> {
+        Ok(Self {
+            manager: opts.open_database()?,
+            audit_log_dir: opts.audit_log_dir(),
+            user_name: sub_opts.user_name(),
+            query: sub_opts.query(),
+            long_info: sub_opts.is_long_info(),
+            json: sub_opts.is_json(),
+        })
+    }
+
+    ///
+    /// ユーザ名フィルタをユーザIDへ解決する
+    ///
+    fn resolve_user_id(&self) -> Result<Option<UserId>> {
+        let Some(user_name) = &self.user_name else {
+            return Ok(None);
+        };
+
+        let user_id = self
+            .manager
+            .get_user_id_by_name(user_name)?
+            .ok_or_else(|| anyhow!(DbError::UserNotFound))?;
+        Ok(Some(user_id))
+    }
+}
+
+impl CommandContext for AuditQueryCommandContext {
+    ///
+    /// サブコマンドを実行
+    ///
+    /// # 戻り値
+    /// 監査ログの検索結果の出力に成功した場合は`Ok(())`を返す。
+    ///
+    fn exec(&self) -> Result<()> {
+        /*
+         * 検索条件の確定と検索
+         */
+        let mut query = self.query.clone();
+        query.user_id = self.resolve_user_id()?;
+
+        let result = query_audit_records(&self.audit_log_dir, &query)?;
+        if result.skipped_lines > 0 {
+            eprintln!(
+                "warning: {} malformed audit log line(s) skipped",
+                result.skipped_lines
+            );
+        }
+
+        /*
+         * 結果の出力
+         */
+        if self.json {
+            for record in &result.records {
+                println!("{}", serde_json::to_string(record)?);
+            }
+            return Ok(());
+        }
+
+        let user_names: HashMap<UserId, String> = self
+            .manager
+            .list_users()?
+            .into_iter()
+            .map(|user| (user.id(), user.username()))
+            .collect();
+
+        println!(
+            "{}",
+            format_audit_table(&result.records, &user_names, self.long_info)
+        );
+        Ok(())
+    }
+}
+
+///
+/// 監査ログ一覧のテーブル生成
+///
+/// # 引数
+/// * `records` - 監査レコード一覧
+/// * `user_names` - ユーザIDからユーザ名への対応表
+/// * `long_info` - 詳細表示の有無
+///
+/// # 戻り値
+/// テーブル整形済み文字列を返す。
+///
+fn format_audit_table(
+    records: &[AuditRecord],
+    user_names: &HashMap<UserId, String>,
+    long_info: bool,
+) -> String {
+    /*
+     * ヘッダとデータ行の構築
+     */
+    let mut lines: Vec<Vec<String>> = Vec::with_capacity(records.len() + 1);
+    let header: &[&str] = if long_info {
+        &[
+            "TIMESTAMP", "OPERATION", "RESULT", "USER", "TOKEN_ID", "ADDRESS",
+            "PATH", "REVISION", "SUMMARY",
+        ]
+    } else {
+        &["TIMESTAMP", "OPERATION", "RESULT", "USER", "PATH", "SUMMARY"]
+    };
+    lines.push(header.iter().map(|value| value.to_string()).collect());
+
+    for record in records {
+        let user = match &record.user_id {
+            Some(user_id) => user_names
+                .get(user_id)
+                .cloned()
+                .unwrap_or_else(|| user_id.to_string()),
+            None => "-".to_string(),
+        };
+        let mut row = vec![
+            format_cli_timestamp(record.timestamp.with_timezone(&Local)),
+            record.operation.as_str().to_string(),
+            record.result.as_str().to_string(),
+            user,
+        ];
+
+        if long_info {
+            row.push(
+                record
+                    .token_id
+                    .as_ref()
+                    .map(|token_id| token_id.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+            );
+            row.push(
+                record
+                    .address
+                    .map(|address| address.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+            );
+        }
+
+        row.push(record.target_path.clone().unwrap_or_else(|| "-".to_string()));
+
+        if long_info {
+            row.push(
+                record
+                    .revision
+                    .map(|revision| revision.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+            );
+        }
+
+        row.push(record.summary.clone().unwrap_or_else(|| "-".to_string()));
+        lines.push(row);
+    }
+
+    /*
+     * 列幅の計算
+     */
+    let mut widths = vec![0usize; lines[0].len()];
+    for row in &lines {
+        for (idx, value) in row.iter().enumerate() {
+            widths[idx] = widths[idx].max(value.len());
+        }
+    }
+
+    /*
+     * 出力文字列の生成
+     */
+    let mut output = String::new();
+    for (row_index, row) in lines.iter().enumerate() {
+        let mut line = String::new();
+        for (idx, value) in row.iter().enumerate() {
+            let _ = write!(
+                &mut line,
+                "{:width$}{}",
+                value,
+                if idx + 1 == row.len() { "" } else { "  " },
+                width = widths[idx]
+            );
+        }
+        output.push_str(&line);
+        if row_index + 1 < lines.len() {
+            output.push('\n');
+        }
+    }
+
+    output
+}
+
+///
+/// コマンドコンテキストの生成
+///
+pub(crate) fn build_context(
+    opts: &Options,
+    sub_opts: &AuditQueryOpts,
+) -> Result<Box<dyn CommandContext>> {
+    Ok(Box::new(AuditQueryCommandContext::new(opts, sub_opts)?))
+}
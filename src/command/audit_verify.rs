@@ -0,0 +1,101 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! サブコマンド"audit verify"の実装
+//!
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+
+use super::CommandContext;
+use crate::audit::chain::verify_audit_chain;
+use crate::cmd_args::Options;
+
+///
+/// "audit verify"コマンド実行コンテキスト
+///
+struct AuditVerifyCommandContext {
+    audit_log_dir: PathBuf,
+}
+
+impl AuditVerifyCommandContext {
+    ///
+    /// コンテキストの生成
+    ///
+    /// # 引数
+    /// * `opts` - コマンドラインオプション
+    ///
+    /// # 戻り値
+    /// 生成したコンテキスト
+    ///
+    fn new(opts: &Options) -> Result<Self> {
+        Ok(Self {
+            audit_log_dir: opts.audit_log_dir(),
+        })
+    }
+}
+
+impl CommandContext for AuditVerifyCommandContext {
+    ///
+    /// コマンドの実行
+    ///
+    /// # 概要
+    /// 監査ログのハッシュチェーンを検証し、検出した問題を出力する。
+    ///
+    /// # 戻り値
+    /// 問題が検出されなかった場合は`Ok(())`、検出された場合はエラーを返す。
+    ///
+    fn exec(&self) -> Result<()> {
+        let report = verify_audit_chain(&self.audit_log_dir)?;
+
+        /*
+         * 検出した問題の出力
+         */
+        for issue in &report.issues {
+            let location = match (&issue.path, issue.line) {
+                (Some(path), Some(line)) => {
+                    format!("{}:{}", path.display(), line)
+                }
+                (Some(path), None) => path.display().to_string(),
+                _ => self.audit_log_dir.display().to_string(),
+            };
+            println!("{}: {}: {}", issue.kind.as_str(), location, issue.detail);
+        }
+
+        /*
+         * 集計結果の出力
+         */
+        println!(
+            "checked {} record(s) in {} file(s)",
+            report.records, report.files
+        );
+        if report.legacy_records > 0 {
+            println!(
+                "{} record(s) written before the hash chain were not verified",
+                report.legacy_records
+            );
+        }
+
+        if !report.is_intact() {
+            return Err(anyhow!(
+                "audit log verification failed: {} issue(s)",
+                report.issues.len()
+            ));
+        }
+
+        println!("audit log hash chain is intact");
+        Ok(())
+    }
+}
+
+///
+/// コマンドコンテキストの生成
+///
+pub(crate) fn build_context(opts: &Options) -> Result<Box<dyn CommandContext>> {
+    Ok(Box::new(AuditVerifyCommandContext::new(opts)?))
+}
@@ -0,0 +1,271 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! サブコマンド"backup"の実装
+//!
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use reqwest::blocking::{Client, Response};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Certificate, StatusCode};
+use serde::Serialize;
+
+use super::CommandContext;
+use super::common::read_password;
+use crate::cmd_args::{BackupOpts, Options};
+use crate::export_import::{
+    self, ExportImportPolicy, ExportRequest, write_archive_stream_to_output,
+};
+use crate::rest_api::EXPORT_ID_HEADER;
+
+///
+/// サーバ経由バックアップの接続情報
+///
+struct ServerTarget {
+    /// 接続先サーバのURL
+    url: String,
+
+    /// 認証ユーザ名
+    user_name: String,
+
+    /// サーバ証明書ファイルのパス
+    cert_path: PathBuf,
+
+    /// 証明書検証省略指定
+    insecure: bool,
+}
+
+///
+/// POST /api/admin/backup へ送出するリクエストボディ
+///
+#[derive(Serialize)]
+struct BackupRequestBody {
+    /// 差分 backup の起点
+    #[serde(skip_serializing_if = "Option::is_none")]
+    since: Option<String>,
+
+    /// ZIP パスワード
+    #[serde(skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+}
+
+///
+/// "backup"サブコマンドのコンテキスト情報をパックした構造体
+///
+struct BackupCommandContext {
+    opts: Options,
+    server: Option<ServerTarget>,
+    since: Option<String>,
+    password: Option<String>,
+    output_path: String,
+}
+
+impl BackupCommandContext {
+    ///
+    /// オブジェクトの生成
+    ///
+    /// # 注記
+    /// サーバ経由の場合は稼働中サーバがDBをロックしているため、ここでは
+    /// DBを開かない。
+    ///
+    fn new(opts: &Options, sub_opts: &BackupOpts) -> Result<Self> {
+        let server = if sub_opts.is_via_server() {
+            Some(ServerTarget {
+                url: sub_opts.url(),
+                user_name: sub_opts
+                    .user_name()
+                    .ok_or_else(|| anyhow!("--via-server requires --user"))?,
+                cert_path: sub_opts.cert_path(),
+                insecure: sub_opts.is_insecure(),
+            })
+        } else {
+            None
+        };
+
+        Ok(Self {
+            opts: opts.clone(),
+            server,
+            since: sub_opts.since(),
+            password: sub_opts.password(),
+            output_path: sub_opts.output(),
+        })
+    }
+
+    ///
+    /// DBを直接開いてバックアップを取得
+    ///
+    fn exec_local(&self) -> Result<()> {
+        let manager = self.opts.open_database()?;
+        let request = ExportRequest {
+            policy: ExportImportPolicy::backup(),
+            dry_run: false,
+            output_path: self.output_path.clone(),
+            password: self.password.clone(),
+            since: self.since.clone(),
+        };
+        let result = export_import::export(&manager, request)?;
+
+        let manifest = &result.bundle.manifest;
+        eprintln!(
+            "backup completed: via_server=false export_id={} incremental={} pages={} revisions={} assets={}",
+            manifest
+                .export_id
+                .as_ref()
+                .map(|export_id| export_id.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            manifest.is_incremental(),
+            manifest.page_count,
+            manifest.revision_count,
+            manifest.asset_count,
+        );
+        Ok(())
+    }
+
+    ///
+    /// 稼働中サーバからバックアップを取得
+    ///
+    /// # 引数
+    /// * `server` - 接続情報
+    ///
+    fn exec_via_server(&self, server: &ServerTarget) -> Result<()> {
+        /*
+         * 認証情報の入力
+         */
+        let login_password = read_password(&format!(
+            "Password for {}: ",
+            server.user_name
+        ))?;
+
+        /*
+         * リクエストの送出
+         */
+        let client = build_client(server)?;
+        let endpoint =
+            format!("{}/api/admin/backup", server.url.trim_end_matches('/'));
+        let body = serde_json::to_vec(&BackupRequestBody {
+            since: self.since.clone(),
+            password: self.password.clone(),
+        })?;
+        let mut response = client
+            .post(&endpoint)
+            .basic_auth(&server.user_name, Some(login_password))
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .with_context(|| format!("request failed: {}", endpoint))?;
+
+        if response.status() != StatusCode::OK {
+            return Err(error_from_response(response));
+        }
+
+        /*
+         * アーカイブの保存
+         */
+        let export_id = response
+            .headers()
+            .get(EXPORT_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| !value.is_empty())
+            .unwrap_or("-")
+            .to_string();
+        let size =
+            write_archive_stream_to_output(&mut response, &self.output_path)?;
+
+        eprintln!(
+            "backup completed: via_server=true export_id={} bytes={}",
+            export_id, size,
+        );
+        Ok(())
+    }
+}
+
+impl CommandContext for BackupCommandContext {
+    ///
+    /// サブコマンドを実行
+    ///
+    /// # 戻り値
+    /// backup に成功した場合は`Ok(())`を返す。
+    ///
+    /// # 注記
+    /// 出力先に標準出力を指定できるため、完了報告は標準エラー出力へ行う。
+    ///
+    fn exec(&self) -> Result<()> {
+        match self.server.as_ref() {
+            Some(server) => self.exec_via_server(server),
+            None => self.exec_local(),
+        }
+    }
+}
+
+///
+/// HTTPクライアントの生成
+///
+/// # 引数
+/// * `server` - 接続情報
+///
+/// # 戻り値
+/// 証明書設定を反映したクライアントを返す。
+///
+/// # 注記
+/// アーカイブの作成と転送には時間を要するため、タイムアウトは設定しない。
+///
+fn build_client(server: &ServerTarget) -> Result<Client> {
+    let mut builder = Client::builder().timeout(None);
+
+    if server.insecure {
+        builder = builder.danger_accept_invalid_certs(true);
+    } else if server.url.starts_with("https://") && server.cert_path.exists()
+    {
+        let pem = fs::read(&server.cert_path).with_context(|| {
+            format!("read cert failed: {}", server.cert_path.display())
+        })?;
+        builder = builder.add_root_certificate(Certificate::from_pem(&pem)?);
+    }
+
+    Ok(builder.build()?)
+}
+
+///
+/// エラーレスポンスからエラー値を生成
+///
+/// # 引数
+/// * `response` - 200以外のレスポンス
+///
+/// # 戻り値
+/// レスポンスボディの`reason`を含めたエラーを返す。
+///
+fn error_from_response(response: Response) -> anyhow::Error {
+    let status = response.status();
+    let reason = response
+        .bytes()
+        .ok()
+        .and_then(|body| serde_json::from_slice::<serde_json::Value>(&body).ok())
+        .and_then(|value| {
+            value
+                .get("reason")
+                .and_then(|reason| reason.as_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| {
+            status.canonical_reason().unwrap_or("unknown").to_string()
+        });
+
+    anyhow!("backup request failed: {} {}", status.as_u16(), reason)
+}
+
+///
+/// コマンドコンテキストの生成
+///
+pub(crate) fn build_context(
+    opts: &Options,
+    sub_opts: &BackupOpts,
+) -> Result<Box<dyn CommandContext>> {
+    Ok(Box::new(BackupCommandContext::new(opts, sub_opts)?))
+}
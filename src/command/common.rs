@@ -8,10 +8,11 @@
 //! サブコマンド共通処理を提供するモジュール
 //!
 
+use std::cell::RefCell;
 use std::io::{self, BufRead, IsTerminal, Write};
 
 use anyhow::{anyhow, Result};
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, Utc};
 #[cfg(not(target_family = "windows"))]
 use rpassword::prompt_password;
 
@@ -26,6 +27,13 @@ use windows_sys::Win32::System::Console::{
     SetConsoleMode,
 };
 
+use crate::audit::model::{AuditOperation, AuditRecord, AuditResult};
+use crate::audit::retention::AuditRetentionPolicy;
+use crate::audit::rotation::AuditRotationPolicy;
+use crate::audit::sink::AuditSink;
+use crate::audit::writer::AuditWriterConfig;
+use crate::cmd_args::Options;
+
 /// パスワードの最小長
 const MIN_PASSWORD_LENGTH: usize = 8;
 
@@ -45,6 +53,43 @@ pub(crate) fn read_password_with_confirm() -> Result<String> {
     read_password_with_confirm_from(&mut input, &mut output, use_terminal_input)
 }
 
+///
+/// 確認無しのパスワード入力
+///
+/// # 引数
+/// * `prompt` - 表示するプロンプト文字列
+///
+/// # 戻り値
+/// 入力されたパスワードを返す。
+///
+/// # 注記
+/// 標準出力をデータ出力に用いるコマンドのため、プロンプトは標準エラー
+/// 出力へ表示する。
+///
+pub(crate) fn read_password(prompt: &str) -> Result<String> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let stderr = io::stderr();
+    let mut output = stderr.lock();
+
+    let use_terminal_input = stdin.is_terminal();
+    let password = read_password_prompt(
+        &mut input,
+        &mut output,
+        prompt,
+        use_terminal_input,
+    )?;
+    if !use_terminal_input {
+        writeln!(output)?;
+    }
+
+    if password.is_empty() {
+        return Err(anyhow!("password is empty"));
+    }
+
+    Ok(password)
+}
+
 ///
 /// 確認プロンプトを表示して実行可否を問い合わせる
 ///
@@ -109,6 +154,94 @@ pub(crate) fn format_cli_duration(duration: chrono::Duration) -> String {
     format!("{}s", seconds)
 }
 
+///
+/// 管理CLIの操作を監査ログへ記録するための書込口
+///
+/// # 注記
+/// サーバと同じ出力先設定(ローカルファイルおよび外部転送先)を用い、記録前に
+/// 保持期間を超過したログファイルを削除する。CLI からの操作は操作ユーザを
+/// 持たないため、ユーザIDとトークンIDは記録しない。
+///
+pub(crate) struct CliAuditLog {
+    /// 監査ログの投入入口
+    sink: RefCell<AuditSink>,
+
+    /// 監査ログ保持期間
+    retention: chrono::Duration,
+}
+
+impl CliAuditLog {
+    ///
+    /// オブジェクトの生成
+    ///
+    /// # 引数
+    /// * `opts` - グローバルオプション
+    ///
+    /// # 戻り値
+    /// 生成した書込口を返す。
+    ///
+    pub(crate) fn new(opts: &Options) -> Result<Self> {
+        let sink = AuditSink::open(
+            AuditWriterConfig {
+                output_dir: opts.audit_log_dir(),
+                rotation_policy: AuditRotationPolicy::new(
+                    opts.audit_log_rotate_size()?,
+                ),
+            },
+            &opts.audit_output_config()?,
+        )?;
+
+        Ok(Self {
+            sink: RefCell::new(sink),
+            retention: opts.audit_log_retention()?,
+        })
+    }
+
+    ///
+    /// 成功した操作の記録
+    ///
+    /// # 引数
+    /// * `operation` - 操作種別
+    /// * `target_path` - 対象ページのパス
+    /// * `summary` - 補足要約
+    ///
+    /// # 戻り値
+    /// 記録に成功した場合は`Ok(())`を返す。
+    ///
+    pub(crate) fn record(
+        &self,
+        operation: AuditOperation,
+        target_path: Option<String>,
+        summary: Option<String>,
+    ) -> Result<()> {
+        let mut sink = self.sink.borrow_mut();
+
+        /*
+         * 保持期間超過ログの削除
+         */
+        sink.apply_retention(
+            &AuditRetentionPolicy::new(self.retention),
+            Utc::now(),
+        )?;
+
+        /*
+         * 監査レコードの書き込み
+         */
+        sink.record(AuditRecord::new(
+            operation,
+            None,
+            None,
+            None,
+            target_path,
+            AuditResult::Success,
+            Utc::now(),
+            summary,
+            None,
+        ))?;
+        sink.flush()
+    }
+}
+
 ///
 /// パスワード入力とバリデーション
 ///
@@ -8,21 +8,38 @@
 //! サブコマンド"export"の実装
 //!
 
+use std::path::PathBuf;
+
 use anyhow::Result;
 
 use super::CommandContext;
-use crate::cmd_args::{ExportOpts, Options};
+use crate::cmd_args::{ExportFormat, ExportOpts, Options};
 use crate::database::DatabaseManager;
 use crate::export_import::{
-    self, ExportImportPolicy, ExportRequest,
+    self, ExportImportPolicy, ExportRequest, MarkdownExportRequest,
+    StaticSiteRequest,
 };
 
+///
+/// export の実行内容
+///
+enum ExportTask {
+    /// ZIP アーカイブの出力
+    Archive(ExportRequest),
+
+    /// 静的 HTML サイトの出力
+    StaticSite(StaticSiteRequest),
+
+    /// Markdown ディレクトリツリーの出力
+    MarkdownTree(MarkdownExportRequest),
+}
+
 ///
 /// "export"サブコマンドのコンテキスト情報をパックした構造体
 ///
 struct ExportCommandContext {
     manager: DatabaseManager,
-    request: ExportRequest,
+    task: ExportTask,
     _yes: bool,
     _strict_mode: bool,
 }
@@ -32,45 +49,124 @@ impl ExportCommandContext {
     /// オブジェクトの生成
     ///
     fn new(opts: &Options, sub_opts: &ExportOpts) -> Result<Self> {
-        let policy = match sub_opts.subtree() {
-            Some(subtree) => ExportImportPolicy::migrate(&subtree)?,
-            None => ExportImportPolicy::backup(),
+        let task = match sub_opts.format() {
+            ExportFormat::Zip => {
+                let policy = match sub_opts.subtree() {
+                    Some(subtree) => ExportImportPolicy::migrate(&subtree)?,
+                    None => ExportImportPolicy::backup(),
+                };
+
+                ExportTask::Archive(ExportRequest {
+                    policy,
+                    dry_run: sub_opts.is_dry_run(),
+                    output_path: sub_opts.output(),
+                    password: sub_opts.password(),
+                    since: sub_opts.since(),
+                })
+            }
+            ExportFormat::Html => ExportTask::StaticSite(StaticSiteRequest {
+                root: sub_opts.subtree().unwrap_or_else(|| "/".to_string()),
+                output_dir: PathBuf::from(sub_opts.output()),
+                dry_run: sub_opts.is_dry_run(),
+                strict_mode: sub_opts.is_strict_mode(),
+            }),
+            ExportFormat::Markdown => {
+                ExportTask::MarkdownTree(MarkdownExportRequest {
+                    root: sub_opts.subtree().unwrap_or_else(|| "/".to_string()),
+                    output_dir: PathBuf::from(sub_opts.output()),
+                    dry_run: sub_opts.is_dry_run(),
+                })
+            }
         };
 
         Ok(Self {
             manager: opts.open_database()?,
-            request: ExportRequest {
-                policy,
-                dry_run: sub_opts.is_dry_run(),
-                output_path: sub_opts.output(),
-                password: sub_opts.password(),
-            },
+            task,
             _yes: sub_opts.is_yes(),
             _strict_mode: sub_opts.is_strict_mode(),
         })
     }
-}
 
-impl CommandContext for ExportCommandContext {
     ///
-    /// サブコマンドを実行
-    ///
-    /// # 戻り値
-    /// export に成功した場合は`Ok(())`を返す。
+    /// ZIP アーカイブの出力
     ///
-    fn exec(&self) -> Result<()> {
-        let result = export_import::export(&self.manager, self.request.clone())?;
+    fn exec_archive(&self, request: &ExportRequest) -> Result<()> {
+        let result = export_import::export(&self.manager, request.clone())?;
 
+        let manifest = &result.bundle.manifest;
         println!(
-            "export completed: type={} dry_run={} pages={} revisions={} assets={}",
+            "export completed: type={} export_id={} incremental={} dry_run={} pages={} revisions={} assets={}",
             result.export_type.as_str(),
-            self.request.dry_run,
+            manifest
+                .export_id
+                .as_ref()
+                .map(|export_id| export_id.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            manifest.is_incremental(),
+            request.dry_run,
             result.bundle.manifest.page_count,
             result.bundle.manifest.revision_count,
             result.bundle.manifest.asset_count,
         );
         Ok(())
     }
+
+    ///
+    /// 静的 HTML サイトの出力
+    ///
+    /// # 注記
+    /// 解決できなかった参照は警告として標準エラー出力へ表示する。
+    ///
+    fn exec_static_site(&self, request: &StaticSiteRequest) -> Result<()> {
+        let result =
+            export_import::export_static_site(&self.manager, request.clone())?;
+
+        for issue in &result.issues {
+            eprintln!("warning: {}", issue);
+        }
+
+        println!(
+            "export completed: type=html dry_run={} pages={} assets={}",
+            request.dry_run,
+            result.page_count,
+            result.asset_count,
+        );
+        Ok(())
+    }
+
+    ///
+    /// Markdown ディレクトリツリーの出力
+    ///
+    fn exec_markdown_tree(&self, request: &MarkdownExportRequest) -> Result<()> {
+        let result =
+            export_import::export_markdown_tree(&self.manager, request.clone())?;
+
+        println!(
+            "export completed: type=markdown dry_run={} pages={} assets={}",
+            request.dry_run,
+            result.page_count,
+            result.asset_count,
+        );
+        Ok(())
+    }
+}
+
+impl CommandContext for ExportCommandContext {
+    ///
+    /// サブコマンドを実行
+    ///
+    /// # 戻り値
+    /// export に成功した場合は`Ok(())`を返す。
+    ///
+    fn exec(&self) -> Result<()> {
+        match &self.task {
+            ExportTask::Archive(request) => self.exec_archive(request),
+            ExportTask::StaticSite(request) => self.exec_static_site(request),
+            ExportTask::MarkdownTree(request) => {
+                self.exec_markdown_tree(request)
+            }
+        }
+    }
 }
 
 ///
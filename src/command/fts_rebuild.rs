@@ -16,7 +16,11 @@ use anyhow::Result;
 use super::CommandContext;
 use crate::cmd_args::Options;
 use crate::database::DatabaseManager;
-use crate::fts::{build_document_from_source, FtsIndexConfig};
+use crate::fts::{
+    build_asset_documents_for_page,
+    build_document_from_source,
+    FtsIndexConfig,
+};
 
 ///
 /// "fts rebuild"コマンド実行コンテキスト
@@ -49,7 +53,8 @@ impl CommandContext for FtsRebuildCommandContext {
     /// コマンドの実行
     ///
     /// # 概要
-    /// 全ページの本文から索引文書を生成し、インデックスを再構築する。
+    /// 全ページの本文とテキスト形式のアセットから索引文書を生成し、
+    /// インデックスを再構築する。
     ///
     /// # 戻り値
     /// 処理に成功した場合は`Ok(())`
@@ -89,6 +94,15 @@ impl CommandContext for FtsRebuildCommandContext {
             )?);
         }
 
+        for (page_id, (deleted, latest)) in &index_map {
+            docs.extend(build_asset_documents_for_page(
+                &self.manager,
+                page_id,
+                *latest,
+                *deleted,
+            )?);
+        }
+
         /*
          * 再構築の実行
          */
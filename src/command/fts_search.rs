@@ -111,13 +111,18 @@ impl CommandContext for FtsSearchCommandContext {
             } else {
                 ""
             };
+            let asset_mark = match result.file_name() {
+                Some(file_name) => format!(" (asset: {})", file_name),
+                None => String::new(),
+            };
             let path = self.display_path(&result);
             println!(
-                "- {} {} {:.3} {}{}",
+                "- {} {} {:.3} {}{}{}",
                 result.page_id(),
                 result.revision(),
                 result.score(),
                 path,
+                asset_mark,
                 deleted_mark
             );
 
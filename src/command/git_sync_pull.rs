@@ -0,0 +1,105 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! サブコマンド"git-sync pull"の実装
+//!
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use super::CommandContext;
+use crate::cmd_args::{GitSyncPullOpts, Options};
+use crate::database::DatabaseManager;
+use crate::fts::{self, FtsIndexConfig};
+use crate::git_sync::{self, GitPullRequest};
+
+///
+/// "git-sync pull"サブコマンドのコンテキスト情報をパックした構造体
+///
+struct GitSyncPullCommandContext {
+    manager: DatabaseManager,
+    index_path: PathBuf,
+    repository: PathBuf,
+    user_name: Option<String>,
+    force: bool,
+    dry_run: bool,
+}
+
+impl GitSyncPullCommandContext {
+    ///
+    /// オブジェクトの生成
+    ///
+    fn new(opts: &Options, sub_opts: &GitSyncPullOpts) -> Result<Self> {
+        Ok(Self {
+            manager: opts.open_database()?,
+            index_path: opts.fts_index_path(),
+            repository: sub_opts.repository(),
+            user_name: sub_opts.user_name(),
+            force: sub_opts.is_force(),
+            dry_run: sub_opts.is_dry_run(),
+        })
+    }
+}
+
+impl CommandContext for GitSyncPullCommandContext {
+    ///
+    /// サブコマンドを実行
+    ///
+    /// # 戻り値
+    /// 取り込みに成功した場合は`Ok(())`を返す。
+    ///
+    /// # 注記
+    /// 反映したページは全文検索インデックスへ即時反映する。
+    ///
+    fn exec(&self) -> Result<()> {
+        let request = GitPullRequest {
+            repository: self.repository.clone(),
+            fallback_user: self.user_name.clone(),
+            force: self.force,
+            dry_run: self.dry_run,
+        };
+        let result = git_sync::pull(&self.manager, &request)?;
+
+        for warning in &result.warnings {
+            eprintln!("warning: {}", warning);
+        }
+        for path in &result.conflicts {
+            eprintln!("warning: conflict overwritten: {}", path);
+        }
+
+        let config = FtsIndexConfig::new(self.index_path.clone());
+        for page_id in result.created_pages.iter().chain(&result.updated_pages) {
+            fts::reindex_page(&config, &self.manager, page_id, false)?;
+        }
+        for page_id in &result.deleted_pages {
+            fts::reindex_page(&config, &self.manager, page_id, true)?;
+        }
+
+        println!(
+            "git-sync pull completed: dry_run={} commits={} changes={} created={} updated={} deleted={} unchanged={}",
+            self.dry_run,
+            result.commits,
+            result.changes,
+            result.created_pages.len(),
+            result.updated_pages.len(),
+            result.deleted_pages.len(),
+            result.unchanged,
+        );
+        Ok(())
+    }
+}
+
+///
+/// コマンドコンテキストの生成
+///
+pub(crate) fn build_context(
+    opts: &Options,
+    sub_opts: &GitSyncPullOpts,
+) -> Result<Box<dyn CommandContext>> {
+    Ok(Box::new(GitSyncPullCommandContext::new(opts, sub_opts)?))
+}
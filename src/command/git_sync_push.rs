@@ -0,0 +1,79 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! サブコマンド"git-sync push"の実装
+//!
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use super::CommandContext;
+use crate::cmd_args::{GitSyncPushOpts, Options};
+use crate::database::DatabaseManager;
+use crate::git_sync::{self, GitPushRequest};
+
+///
+/// "git-sync push"サブコマンドのコンテキスト情報をパックした構造体
+///
+struct GitSyncPushCommandContext {
+    manager: DatabaseManager,
+    repository: PathBuf,
+    dry_run: bool,
+}
+
+impl GitSyncPushCommandContext {
+    ///
+    /// オブジェクトの生成
+    ///
+    fn new(opts: &Options, sub_opts: &GitSyncPushOpts) -> Result<Self> {
+        Ok(Self {
+            manager: opts.open_database()?,
+            repository: sub_opts.repository(),
+            dry_run: sub_opts.is_dry_run(),
+        })
+    }
+}
+
+impl CommandContext for GitSyncPushCommandContext {
+    ///
+    /// サブコマンドを実行
+    ///
+    /// # 戻り値
+    /// 同期に成功した場合は`Ok(())`を返す。
+    ///
+    fn exec(&self) -> Result<()> {
+        let request = GitPushRequest {
+            repository: self.repository.clone(),
+            dry_run: self.dry_run,
+        };
+        let result = git_sync::push(&self.manager, &request)?;
+
+        for warning in &result.warnings {
+            eprintln!("warning: {}", warning);
+        }
+
+        println!(
+            "git-sync push completed: dry_run={} revisions={} commits={} head={}",
+            self.dry_run,
+            result.revisions,
+            result.commits,
+            result.head.as_deref().unwrap_or("(none)"),
+        );
+        Ok(())
+    }
+}
+
+///
+/// コマンドコンテキストの生成
+///
+pub(crate) fn build_context(
+    opts: &Options,
+    sub_opts: &GitSyncPushOpts,
+) -> Result<Box<dyn CommandContext>> {
+    Ok(Box::new(GitSyncPushCommandContext::new(opts, sub_opts)?))
+}
@@ -9,21 +9,27 @@
 //!
 
 use std::collections::BTreeMap;
+use std::path::PathBuf;
 
 use anyhow::{Result, anyhow};
 
 use super::CommandContext;
-use crate::cmd_args::{ImportOpts, Options};
+use crate::cmd_args::{ImportFormat, ImportOpts, Options};
 use crate::database::DatabaseManager;
 use crate::export_import::{
-    self, ExportBundle, ExportImportPolicy, ExportType,
+    self, ExportBundle, ExportImportPolicy, ExportManifest, ExportType,
+    ForeignImportRequest, MarkdownImportRequest,
 };
+use crate::fts::{self, FtsIndexConfig};
 
 ///
 /// "import"サブコマンドのコンテキスト情報をパックした構造体
 ///
 struct ImportCommandContext {
     manager: DatabaseManager,
+    index_path: PathBuf,
+    format: ImportFormat,
+    user: Option<String>,
     migrate_prefix: Option<String>,
     user_map: Vec<(String, String)>,
     user_list: bool,
@@ -32,7 +38,7 @@ struct ImportCommandContext {
     _yes: bool,
     password: Option<String>,
     strict_mode: bool,
-    input_path: String,
+    input_paths: Vec<String>,
 }
 
 impl ImportCommandContext {
@@ -42,6 +48,9 @@ impl ImportCommandContext {
     fn new(opts: &Options, sub_opts: &ImportOpts) -> Result<Self> {
         Ok(Self {
             manager: opts.open_database()?,
+            index_path: opts.fts_index_path(),
+            format: sub_opts.format(),
+            user: sub_opts.user(),
             migrate_prefix: sub_opts.migrate(),
             user_map: parse_user_map(&sub_opts.user_map())?,
             user_list: sub_opts.is_user_list(),
@@ -50,9 +59,123 @@ impl ImportCommandContext {
             _yes: sub_opts.is_yes(),
             password: sub_opts.password(),
             strict_mode: sub_opts.is_strict_mode(),
-            input_path: sub_opts.input(),
+            input_paths: sub_opts.inputs(),
         })
     }
+
+    ///
+    /// Markdown ディレクトリツリー(Obsidian の vault を含む)の取り込み
+    ///
+    /// # 注記
+    /// 反映したページとアセットは全文検索インデックスへ即時反映する。
+    ///
+    fn exec_markdown_tree(&self) -> Result<()> {
+        let format = self.format.name();
+        let request = MarkdownImportRequest {
+            input_dir: PathBuf::from(&self.input_paths[0]),
+            prefix: self
+                .migrate_prefix
+                .clone()
+                .unwrap_or_else(|| "/".to_string()),
+            user_name: self
+                .user
+                .clone()
+                .ok_or_else(|| anyhow!("--format {} requires --user", format))?,
+            dry_run: self.dry_run,
+            strict_mode: self.strict_mode,
+            fix_broken_link: self.fix_broken_link,
+        };
+        let result = if self.format == ImportFormat::Obsidian {
+            export_import::import_obsidian_vault(&self.manager, request)?
+        } else {
+            export_import::import_markdown_tree(&self.manager, request)?
+        };
+
+        for warning in &result.warnings {
+            eprintln!("warning: {}", warning.message);
+        }
+
+        let config = FtsIndexConfig::new(self.index_path.clone());
+        for page_id in result.created_pages.iter().chain(&result.updated_pages) {
+            fts::reindex_page(&config, &self.manager, page_id, false)?;
+        }
+        for asset_id in &result.assets {
+            fts::reindex_asset(&config, &self.manager, asset_id)?;
+        }
+
+        println!(
+            "import completed: type={} dry_run={} created={} updated={} unchanged={} assets={}",
+            format,
+            self.dry_run,
+            result.created_pages.len(),
+            result.updated_pages.len(),
+            result.unchanged_pages,
+            result.assets.len(),
+        );
+        Ok(())
+    }
+
+    ///
+    /// 他の Wiki システムのデータの取り込み
+    ///
+    /// # 注記
+    /// `--user-list`指定時は編集者一覧の表示のみを行う。反映したページと
+    /// アセットは全文検索インデックスへ即時反映する。
+    ///
+    fn exec_foreign_wiki(&self) -> Result<()> {
+        let format = self.format.name();
+        let input_path = PathBuf::from(&self.input_paths[0]);
+        let wiki = if self.format == ImportFormat::Mediawiki {
+            export_import::read_mediawiki_dump(&input_path)?
+        } else {
+            export_import::read_dokuwiki_data(&input_path)?
+        };
+
+        if self.user_list {
+            for user in wiki.contributors() {
+                println!("{}", user);
+            }
+            return Ok(());
+        }
+
+        let request = ForeignImportRequest {
+            input_path,
+            prefix: self.migrate_prefix.clone().ok_or_else(|| {
+                anyhow!("--format {} requires --migrate", format)
+            })?,
+            user_map: self.user_map.clone(),
+            fallback_user: self.user.clone(),
+            dry_run: self.dry_run,
+            strict_mode: self.strict_mode,
+            fix_broken_link: self.fix_broken_link,
+        };
+        let result =
+            export_import::import_foreign_wiki(&self.manager, wiki, &request)?;
+
+        for warning in &result.warnings {
+            eprintln!("warning: {}", warning.message);
+        }
+
+        if !self.dry_run {
+            let config = FtsIndexConfig::new(self.index_path.clone());
+            for page_id in &result.pages {
+                fts::reindex_page(&config, &self.manager, page_id, false)?;
+            }
+            for asset_id in &result.assets {
+                fts::reindex_asset(&config, &self.manager, asset_id)?;
+            }
+        }
+
+        println!(
+            "import completed: type={} dry_run={} pages={} revisions={} assets={}",
+            format,
+            self.dry_run,
+            result.pages.len(),
+            result.revisions,
+            result.assets.len(),
+        );
+        Ok(())
+    }
 }
 
 impl CommandContext for ImportCommandContext {
@@ -63,41 +186,76 @@ impl CommandContext for ImportCommandContext {
     /// import に成功した場合は`Ok(())`を返す。
     ///
     fn exec(&self) -> Result<()> {
-        let bundle = export_import::read_bundle_from_input(
-            &self.input_path,
-            self.password.as_deref(),
-        )?;
+        match self.format {
+            ImportFormat::Markdown | ImportFormat::Obsidian => {
+                return self.exec_markdown_tree();
+            }
+            ImportFormat::Mediawiki | ImportFormat::Dokuwiki => {
+                return self.exec_foreign_wiki();
+            }
+            ImportFormat::Zip => {}
+        }
+
+        /*
+         * 全アーカイブを読み込み、取り込み順の連続性を確認する
+         */
+        let mut bundles = Vec::new();
+        for input_path in &self.input_paths {
+            bundles.push(export_import::read_bundle_from_input(
+                input_path,
+                self.password.as_deref(),
+            )?);
+        }
 
         if self.user_list {
-            print_users(&bundle);
+            print_users(&bundles);
             return Ok(());
         }
 
-        let export_type = bundle.manifest.export_type;
-        let policy = build_policy(&bundle, self.migrate_prefix.clone())?;
-        let validated = export_import::validate_import(
-            &self.manager,
-            &policy,
-            &self.user_map,
-            self.strict_mode,
-            self.fix_broken_link,
-            bundle,
-        )?;
+        let manifests: Vec<&ExportManifest> =
+            bundles.iter().map(|bundle| &bundle.manifest).collect();
+        export_import::validate_import_chain(&manifests)?;
 
-        if !self.dry_run {
-            export_import::apply_import(
-                &self.manager,
-                &policy,
-                &self.user_map,
-                validated,
-            )?;
+        /*
+         * 指定順に検証と反映を行う
+         */
+        for (index, bundle) in bundles.into_iter().enumerate() {
+            let export_type = bundle.manifest.export_type;
+            let incremental = bundle.manifest.is_incremental();
+            let policy = build_policy(&bundle, self.migrate_prefix.clone())?;
+
+            /*
+             * dry-run では先行アーカイブを反映しないため、後続アーカイブの
+             * DB との照合は行えない
+             */
+            if !self.dry_run || index == 0 {
+                let validated = export_import::validate_import(
+                    &self.manager,
+                    &policy,
+                    &self.user_map,
+                    self.strict_mode,
+                    self.fix_broken_link,
+                    bundle,
+                )?;
+
+                if !self.dry_run {
+                    export_import::apply_import(
+                        &self.manager,
+                        &policy,
+                        &self.user_map,
+                        validated,
+                    )?;
+                }
+            }
+
+            println!(
+                "import completed: type={} incremental={} dry_run={}",
+                export_type.as_str(),
+                incremental,
+                self.dry_run,
+            );
         }
 
-        println!(
-            "import completed: type={} dry_run={}",
-            export_type.as_str(),
-            self.dry_run,
-        );
         Ok(())
     }
 }
@@ -116,7 +274,11 @@ fn build_policy(
                     "backup import does not accept --migrate"
                 ));
             }
-            Ok(ExportImportPolicy::backup())
+            if bundle.manifest.is_incremental() {
+                Ok(ExportImportPolicy::incremental())
+            } else {
+                Ok(ExportImportPolicy::backup())
+            }
         }
         ExportType::Migrate => {
             let prefix = migrate_prefix.ok_or_else(|| {
@@ -147,12 +309,12 @@ fn parse_user_map(entries: &[String]) -> Result<Vec<(String, String)>> {
 }
 
 ///
-/// アーカイブ内の編集者一覧を表示する
+/// アーカイブ群内の編集者一覧を表示する
 ///
-fn print_users(bundle: &ExportBundle) {
-    let users: BTreeMap<&str, &str> = bundle
-        .users
+fn print_users(bundles: &[ExportBundle]) {
+    let users: BTreeMap<&str, &str> = bundles
         .iter()
+        .flat_map(|bundle| bundle.users.iter())
         .map(|user| (user.username.as_str(), user.display_name.as_str()))
         .collect();
 
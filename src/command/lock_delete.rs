@@ -11,6 +11,8 @@
 use anyhow::{anyhow, Result};
 
 use super::CommandContext;
+use super::common::CliAuditLog;
+use crate::audit::model::AuditOperation;
 use crate::cmd_args::{LockDeleteOpts, Options};
 use crate::database::types::LockToken;
 use crate::database::DatabaseManager;
@@ -21,6 +23,7 @@ use crate::database::DatabaseManager;
 struct LockDeleteCommandContext {
     manager: DatabaseManager,
     lock_id: LockToken,
+    audit_log: CliAuditLog,
 }
 
 impl LockDeleteCommandContext {
@@ -32,6 +35,7 @@ impl LockDeleteCommandContext {
         Ok(Self {
             manager: opts.open_database()?,
             lock_id,
+            audit_log: CliAuditLog::new(opts)?,
         })
     }
 }
@@ -44,11 +48,18 @@ impl CommandContext for LockDeleteCommandContext {
     /// ロック削除に成功した場合は`Ok(())`を返す。
     ///
     fn exec(&self) -> Result<()> {
-        if self.manager.delete_lock(&self.lock_id)? {
-            Ok(())
-        } else {
-            Err(anyhow!("lock not found"))
+        if !self.manager.delete_lock(&self.lock_id)? {
+            return Err(anyhow!("lock not found"));
         }
+
+        /*
+         * 監査ログへの記録
+         */
+        self.audit_log.record(
+            AuditOperation::LockBreak,
+            None,
+            Some(format!("lock {}", self.lock_id)),
+        )
     }
 }
 
@@ -10,10 +10,15 @@
 
 pub(crate) mod asset_add;
 pub(crate) mod asset_delete;
+pub(crate) mod asset_fsck;
+pub(crate) mod asset_gc;
 pub(crate) mod asset_list;
 pub(crate) mod asset_move_to;
 pub(crate) mod asset_purge;
 pub(crate) mod asset_undelete;
+pub(crate) mod audit_query;
+pub(crate) mod audit_verify;
+pub(crate) mod backup;
 pub(crate) mod commands;
 pub(crate) mod common;
 pub(crate) mod derived_rebuild;
@@ -21,6 +26,8 @@ pub(crate) mod export;
 pub(crate) mod fts_merge;
 pub(crate) mod fts_rebuild;
 pub(crate) mod fts_search;
+pub(crate) mod git_sync_pull;
+pub(crate) mod git_sync_push;
 pub(crate) mod help_all;
 pub(crate) mod import;
 pub(crate) mod lock_delete;
@@ -39,11 +46,15 @@ pub(crate) mod token_list;
 pub(crate) mod token_purge;
 pub(crate) mod token_remove_path;
 pub(crate) mod token_revoke;
+pub(crate) mod token_rotate;
 pub(crate) mod user_add;
 pub(crate) mod user_delete;
 pub(crate) mod user_edit;
 pub(crate) mod user_info;
 pub(crate) mod user_list;
+pub(crate) mod user_mfa_disable;
+pub(crate) mod user_mfa_enroll;
+pub(crate) mod user_unlock;
 #[cfg(windows)]
 pub(crate) mod windows_service;
 
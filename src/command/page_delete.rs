@@ -13,6 +13,8 @@ use std::path::PathBuf;
 use anyhow::{anyhow, Result};
 
 use super::CommandContext;
+use super::common::CliAuditLog;
+use crate::audit::model::AuditOperation;
 use crate::cmd_args::{Options, PageDeleteOpts};
 use crate::database::types::PageId;
 use crate::database::{DatabaseManager, DbError};
@@ -29,6 +31,7 @@ struct PageDeleteCommandContext {
     hard_delete: bool,
     recursive: bool,
     force: bool,
+    audit_log: CliAuditLog,
 }
 
 impl PageDeleteCommandContext {
@@ -43,6 +46,7 @@ impl PageDeleteCommandContext {
             hard_delete: sub_opts.is_hard_delete(),
             recursive: sub_opts.is_recursive(),
             force: sub_opts.is_force(),
+            audit_log: CliAuditLog::new(opts)?,
         })
     }
 
@@ -72,6 +76,23 @@ impl PageDeleteCommandContext {
 
         Ok(())
     }
+
+    ///
+    /// 監査ログ向け補足要約の生成
+    ///
+    /// # 引数
+    /// * `recursive` - 再帰削除の場合は`true`
+    ///
+    /// # 戻り値
+    /// 削除方法を示す補足要約を返す。
+    ///
+    fn audit_summary(&self, recursive: bool) -> String {
+        format!(
+            "mode={} recursive={}",
+            if self.hard_delete { "hard" } else { "soft" },
+            recursive,
+        )
+    }
 }
 
 impl CommandContext for PageDeleteCommandContext {
@@ -130,7 +151,15 @@ impl CommandContext for PageDeleteCommandContext {
              * インデックスの更新
              */
             self.update_fts_for_pages(&deleted_ids)?;
-            return Ok(());
+
+            /*
+             * 監査ログへの記録
+             */
+            return self.audit_log.record(
+                AuditOperation::Delete,
+                Some(index.path()),
+                Some(self.audit_summary(true)),
+            );
         }
 
         /*
@@ -157,7 +186,14 @@ impl CommandContext for PageDeleteCommandContext {
          */
         self.update_fts_for_pages(&[page_id])?;
 
-        Ok(())
+        /*
+         * 監査ログへの記録
+         */
+        self.audit_log.record(
+            AuditOperation::Delete,
+            Some(index.path()),
+            Some(self.audit_summary(false)),
+        )
     }
 }
 
@@ -11,6 +11,8 @@
 use anyhow::{anyhow, Result};
 
 use super::CommandContext;
+use super::common::CliAuditLog;
+use crate::audit::model::AuditOperation;
 use crate::cmd_args::{Options, PageMoveToOpts};
 use crate::database::types::PageId;
 use crate::database::{DatabaseManager, DbError};
@@ -24,6 +26,7 @@ struct PageMoveToCommandContext {
     dst_path: String,
     force: bool,
     recursive: bool,
+    audit_log: CliAuditLog,
 }
 
 impl PageMoveToCommandContext {
@@ -37,6 +40,7 @@ impl PageMoveToCommandContext {
             dst_path: sub_opts.dst_path(),
             force: sub_opts.is_force(),
             recursive: sub_opts.is_recursive(),
+            audit_log: CliAuditLog::new(opts)?,
         })
     }
 }
@@ -88,7 +92,18 @@ impl CommandContext for PageMoveToCommandContext {
         } else {
             self.manager.rename_page(&src_path, &self.dst_path)?;
         }
-        Ok(())
+
+        /*
+         * 監査ログへの記録
+         */
+        self.audit_log.record(
+            AuditOperation::Rename,
+            Some(src_path),
+            Some(format!(
+                "rename to {} recursive={}",
+                self.dst_path, self.recursive
+            )),
+        )
     }
 }
 
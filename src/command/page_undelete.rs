@@ -13,6 +13,8 @@ use std::path::PathBuf;
 use anyhow::{anyhow, Result};
 
 use super::CommandContext;
+use super::common::CliAuditLog;
+use crate::audit::model::AuditOperation;
 use crate::cmd_args::{Options, PageUndeleteOpts};
 use crate::database::types::PageId;
 use crate::database::DatabaseManager;
@@ -28,6 +30,7 @@ struct PageUndeleteCommandContext {
     restore_to: String,
     recursive: bool,
     with_assets: bool,
+    audit_log: CliAuditLog,
 }
 
 impl PageUndeleteCommandContext {
@@ -50,6 +53,7 @@ impl PageUndeleteCommandContext {
             restore_to: sub_opts.restore_to(),
             recursive: sub_opts.is_recursive(),
             with_assets: !sub_opts.is_without_assets(),
+            audit_log: CliAuditLog::new(opts)?,
         })
     }
 
@@ -116,7 +120,14 @@ impl CommandContext for PageUndeleteCommandContext {
              */
             self.update_fts_for_pages(&target_ids)?;
 
-            Ok(())
+            /*
+             * 監査ログへの記録
+             */
+            self.audit_log.record(
+                AuditOperation::Undelete,
+                Some(base_path),
+                Some("recursive".to_string()),
+            )
         } else {
             /*
              * 単体復帰の実行
@@ -133,7 +144,14 @@ impl CommandContext for PageUndeleteCommandContext {
              */
             self.update_fts_for_pages(&[self.page_id.clone()])?;
 
-            Ok(())
+            /*
+             * 監査ログへの記録
+             */
+            self.audit_log.record(
+                AuditOperation::Undelete,
+                Some(self.restore_to.clone()),
+                None,
+            )
         }
     }
 }
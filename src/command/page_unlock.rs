@@ -11,6 +11,8 @@
 use anyhow::{anyhow, Result};
 
 use super::CommandContext;
+use super::common::CliAuditLog;
+use crate::audit::model::AuditOperation;
 use crate::cmd_args::{Options, PageUnlockOpts};
 use crate::database::types::PageId;
 use crate::database::{DatabaseManager, DbError};
@@ -22,6 +24,7 @@ use crate::rest_api::validate_page_path;
 struct PageUnlockCommandContext {
     manager: DatabaseManager,
     target: String,
+    audit_log: CliAuditLog,
 }
 
 impl PageUnlockCommandContext {
@@ -32,6 +35,7 @@ impl PageUnlockCommandContext {
         Ok(Self {
             manager: opts.open_database()?,
             target: sub_opts.target(),
+            audit_log: CliAuditLog::new(opts)?,
         })
     }
 }
@@ -80,7 +84,14 @@ impl CommandContext for PageUnlockCommandContext {
             return Err(anyhow!(DbError::LockNotFound));
         }
 
-        Ok(())
+        /*
+         * 監査ログへの記録
+         */
+        self.audit_log.record(
+            AuditOperation::LockBreak,
+            Some(index.path()),
+            None,
+        )
     }
 }
 
@@ -11,14 +11,16 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
 use mime_guess::MimeGuess;
 
 use super::CommandContext;
-use crate::cmd_args::{FrontendConfig, Options, RunOpts};
+use crate::cmd_args::{AuthConfig, FrontendConfig, Options, RunOpts};
 use crate::database::DatabaseManager;
 use crate::fts::FtsIndexConfig;
+use crate::git_sync::GitSyncConfig;
 use crate::http_server;
 use crate::mcp;
 use crate::rest_api::validate_page_path;
@@ -43,6 +45,9 @@ pub(crate) struct RunCommandContext {
     /// frontend設定
     frontend_config: FrontendConfig,
 
+    /// 認証設定
+    auth_config: AuthConfig,
+
     /// FTSインデックス格納ディレクトリへのパス
     fts_index_path: PathBuf,
 
@@ -61,6 +66,9 @@ pub(crate) struct RunCommandContext {
     /// MCP resource URI authority
     mcp_authority: String,
 
+    /// 共同編集有効フラグ
+    collab_enabled: bool,
+
     /// 起動時にブラウザを開くか否かのフラグ
     #[allow(dead_code)]
     open_browser: bool,
@@ -81,6 +89,12 @@ pub(crate) struct RunCommandContext {
     /// アセットサイズ上限
     asset_limit_size: u64,
 
+    /// 不要なアセットファイルの定期回収間隔
+    asset_gc_interval: Option<Duration>,
+
+    /// Gitリポジトリへの定期同期設定
+    git_sync_config: Option<GitSyncConfig>,
+
     /// 監査ログ設定
     audit_log_config: http_server::AuditLogConfig,
 }
@@ -116,12 +130,14 @@ impl RunCommandContext {
             bind_addr: sub_opts.bind_addr(),
             bind_port: sub_opts.bind_port(),
             frontend_config: opts.frontend_config()?,
+            auth_config: opts.auth_config()?,
             fts_index_path: opts.fts_index_path(),
             use_tls: sub_opts.use_tls(),
             cert_path: sub_opts.cert_path(),
             cert_is_explicit: sub_opts.is_cert_path_explicit(),
             mcp_enabled: sub_opts.use_mcp(),
             mcp_authority: sub_opts.mcp_authority(),
+            collab_enabled: sub_opts.use_collab(),
             open_browser: sub_opts.is_browser_open(),
             #[cfg(windows)]
             win_service: sub_opts.is_win_service(),
@@ -129,10 +145,19 @@ impl RunCommandContext {
             wiki_title: opts.wiki_title(),
             wiki_icon,
             asset_limit_size: opts.asset_limit_size()?,
+            asset_gc_interval: sub_opts.asset_gc_interval(),
+            git_sync_config: sub_opts.git_sync_interval().map(|interval| {
+                GitSyncConfig {
+                    repository: sub_opts.git_sync_repository(),
+                    fallback_user: sub_opts.git_sync_user(),
+                    interval,
+                }
+            }),
             audit_log_config: http_server::AuditLogConfig::new(
                 opts.audit_log_dir(),
                 opts.audit_log_retention()?,
                 opts.audit_log_rotate_size()?,
+                opts.audit_output_config()?,
             ),
         })
     }
@@ -205,19 +230,19 @@ impl RunCommandContext {
             self.bind_port,
             manager,
             self.frontend_config.clone(),
+            self.auth_config.clone(),
             fts_config,
             self.template_root.clone(),
             self.wiki_title.clone(),
             self.wiki_icon.clone(),
             self.asset_limit_size,
+            self.asset_gc_interval,
+            self.git_sync_config.clone(),
+            self.collab_enabled,
             self.use_tls,
             self.cert_path.clone(),
             self.cert_is_explicit,
-            if self.mcp_enabled {
-                Some(self.audit_log_config.clone())
-            } else {
-                None
-            },
+            Some(self.audit_log_config.clone()),
             mcp_endpoint,
             shutdown_signal,
             on_started,
@@ -71,11 +71,15 @@ impl CommandContext for TokenInfoCommandContext {
         print_field("PERMISSIONS", &format_effective_permission_list(info.scopes()));
         print_path_prefixes(info.path_prefixes());
         print_field("TTL", &format_ttl(info.ttl()));
-        print_timestamps(&[
+        let mut timestamps = vec![
             ("create", info.created_at()),
             ("update", info.updated_at()),
-            ("expire", info.expire_at()),
-        ]);
+        ];
+        if let Some(rotated_at) = info.rotated_at() {
+            timestamps.push(("rotate", rotated_at));
+        }
+        timestamps.push(("expire", info.expire_at()));
+        print_timestamps(&timestamps);
         Ok(())
     }
 }
@@ -0,0 +1,129 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! token rotate コマンドの実装
+//!
+
+use std::cell::RefCell;
+
+use anyhow::{anyhow, Result};
+use chrono::{Duration, Local, Utc};
+
+use super::CommandContext;
+use super::common::format_cli_timestamp;
+use crate::audit::model::{AuditOperation, AuditRecord, AuditResult};
+use crate::audit::rotation::AuditRotationPolicy;
+use crate::audit::writer::{AuditWriter, AuditWriterConfig};
+use crate::cmd_args::{Options, TokenRotateOpts};
+use crate::database::DatabaseManager;
+use crate::database::types::TokenId;
+
+///
+/// "token rotate"サブコマンドのコンテキスト情報をパックした構造体
+///
+struct TokenRotateCommandContext {
+    /// データベースマネージャオブジェクト
+    manager: RefCell<DatabaseManager>,
+
+    /// ローテーション対象のトークンID
+    token_id: TokenId,
+
+    /// 旧トークンを受け付ける猶予期間
+    grace: Duration,
+
+    /// 監査ログの書込先
+    audit_writer: RefCell<AuditWriter>,
+}
+
+impl TokenRotateCommandContext {
+    ///
+    /// オブジェクトの生成
+    ///
+    fn new(opts: &Options, sub_opts: &TokenRotateOpts) -> Result<Self> {
+        let token_id = sub_opts.token_id();
+        let token_id = TokenId::from_string(&token_id)
+            .map_err(|_| anyhow!("invalid token id: {}", token_id))?;
+        let grace = match sub_opts.grace_duration()? {
+            Some(grace) => grace,
+            None => opts.auth_config()?.token_rotation_grace(),
+        };
+        let audit_writer = AuditWriter::new(AuditWriterConfig {
+            output_dir: opts.audit_log_dir(),
+            rotation_policy: AuditRotationPolicy::new(
+                opts.audit_log_rotate_size()?,
+            ),
+        });
+
+        Ok(Self {
+            manager: RefCell::new(opts.open_database()?),
+            token_id,
+            grace,
+            audit_writer: RefCell::new(audit_writer),
+        })
+    }
+}
+
+// CommandContextの実装
+impl CommandContext for TokenRotateCommandContext {
+    fn exec(&self) -> Result<()> {
+        let manager = self.manager.borrow_mut();
+        let result = manager.rotate_bearer_token(
+            &self.token_id,
+            self.grace,
+            Local::now(),
+        )?;
+        let info = result.token_info();
+
+        /*
+         * 監査ログへの記録
+         */
+        let mut writer = self.audit_writer.borrow_mut();
+        writer.write_record(&AuditRecord::new(
+            AuditOperation::TokenRotate,
+            Some(info.user_id()),
+            Some(info.token_id()),
+            None,
+            None,
+            AuditResult::Success,
+            Utc::now(),
+            Some(result.audit_summary()),
+            None,
+        ))?;
+        writer.flush()?;
+
+        /*
+         * 新しいトークン平文の出力
+         */
+        println!("{:<13} {}", "TOKEN ID:", info.token_id());
+        println!("TIMESTAMPS:");
+        if let Some(rotated_at) = info.rotated_at() {
+            println!("    rotate: {}", format_cli_timestamp(rotated_at));
+        }
+        println!("    expire: {}", format_cli_timestamp(info.expire_at()));
+        match result.grace_until() {
+            Some(grace_until) => {
+                println!("    grace:  {}", format_cli_timestamp(grace_until));
+            }
+            None => println!("    grace:  -"),
+        }
+        println!();
+        println!("TOKEN VALUE:");
+        println!("    {}", result.plaintext().expose());
+
+        Ok(())
+    }
+}
+
+///
+/// コマンドコンテキストの生成
+///
+pub(crate) fn build_context(
+    opts: &Options,
+    sub_opts: &TokenRotateOpts,
+) -> Result<Box<dyn CommandContext>> {
+    Ok(Box::new(TokenRotateCommandContext::new(opts, sub_opts)?))
+}
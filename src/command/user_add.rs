@@ -13,7 +13,9 @@ use std::cell::RefCell;
 use anyhow::Result;
 
 use super::CommandContext;
-use super::common::read_password_with_confirm;
+use super::common::{CliAuditLog, read_password_with_confirm};
+use crate::audit::model::AuditOperation;
+use crate::auth::PasswordPolicy;
 use crate::cmd_args::{Options, UserAddOpts};
 use crate::database::types::UserAttributeSet;
 use crate::database::DatabaseManager;
@@ -36,6 +38,12 @@ struct UserAddCommandContext {
 
     /// パスワード入力要否
     requires_password: bool,
+
+    /// パスワードの強度要件
+    password_policy: PasswordPolicy,
+
+    /// 監査ログの書込口
+    audit_log: CliAuditLog,
 }
 
 impl UserAddCommandContext {
@@ -49,6 +57,8 @@ impl UserAddCommandContext {
             display_name: sub_opts.display_name(),
             attributes: sub_opts.attributes()?,
             requires_password: sub_opts.requires_password()?,
+            password_policy: opts.auth_config()?.password_policy().clone(),
+            audit_log: CliAuditLog::new(opts)?,
         })
     }
 }
@@ -60,7 +70,9 @@ impl CommandContext for UserAddCommandContext {
          * 必要時のみパスワード入力を取得する
          */
         let password = if self.requires_password {
-            Some(read_password_with_confirm()?)
+            let password = read_password_with_confirm()?;
+            self.password_policy.validate(&password)?;
+            Some(password)
         } else {
             None
         };
@@ -75,7 +87,16 @@ impl CommandContext for UserAddCommandContext {
             self.display_name.clone(),
             self.attributes.clone(),
         )?;
-        manager.ensure_default_root(&self.username)
+        manager.ensure_default_root(&self.username)?;
+
+        /*
+         * 監査ログへ記録する
+         */
+        self.audit_log.record(
+            AuditOperation::UserCreate,
+            None,
+            Some(format!("user={}", self.username)),
+        )
     }
 }
 
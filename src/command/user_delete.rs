@@ -11,6 +11,8 @@
 use anyhow::Result;
 
 use super::CommandContext;
+use super::common::CliAuditLog;
+use crate::audit::model::AuditOperation;
 use crate::cmd_args::{Options, UserDeleteOpts};
 use crate::database::DatabaseManager;
 
@@ -20,6 +22,7 @@ use crate::database::DatabaseManager;
 struct UserDeleteCommandContext {
     manager: DatabaseManager,
     username: String,
+    audit_log: CliAuditLog,
 }
 
 impl UserDeleteCommandContext {
@@ -30,6 +33,7 @@ impl UserDeleteCommandContext {
         Ok(Self {
             manager: opts.open_database()?,
             username: sub_opts.user_name(),
+            audit_log: CliAuditLog::new(opts)?,
         })
     }
 }
@@ -42,7 +46,16 @@ impl CommandContext for UserDeleteCommandContext {
     /// ユーザ削除に成功した場合は`Ok(())`を返す。
     ///
     fn exec(&self) -> Result<()> {
-        self.manager.delete_user(&self.username)
+        self.manager.delete_user(&self.username)?;
+
+        /*
+         * 監査ログへの記録
+         */
+        self.audit_log.record(
+            AuditOperation::UserDelete,
+            None,
+            Some(format!("user={}", self.username)),
+        )
     }
 }
 
@@ -11,7 +11,9 @@
 use anyhow::{anyhow, Result};
 
 use super::CommandContext;
-use super::common::read_password_with_confirm;
+use super::common::{CliAuditLog, read_password_with_confirm};
+use crate::audit::model::AuditOperation;
+use crate::auth::PasswordPolicy;
 use crate::cmd_args::{Options, UserEditOpts};
 use crate::database::types::{UserAttribute, UserAttributeSet};
 use crate::database::DatabaseManager;
@@ -27,6 +29,9 @@ struct UserEditCommandContext {
     add_attributes: UserAttributeSet,
     remove_attributes: UserAttributeSet,
     clear_attributes: bool,
+    disabled: Option<bool>,
+    password_policy: PasswordPolicy,
+    audit_log: CliAuditLog,
 }
 
 impl UserEditCommandContext {
@@ -42,6 +47,9 @@ impl UserEditCommandContext {
             add_attributes: sub_opts.add_attributes()?,
             remove_attributes: sub_opts.remove_attributes()?,
             clear_attributes: sub_opts.clear_attributes(),
+            disabled: sub_opts.disabled(),
+            password_policy: opts.auth_config()?.password_policy().clone(),
+            audit_log: CliAuditLog::new(opts)?,
         })
     }
 
@@ -56,6 +64,33 @@ impl UserEditCommandContext {
             || !self.add_attributes.is_empty()
             || !self.remove_attributes.is_empty()
     }
+
+    ///
+    /// 監査ログ向け補足要約の生成
+    ///
+    /// # 戻り値
+    /// 更新対象のユーザ名と変更項目名を列挙した要約を返す。
+    ///
+    /// # 注記
+    /// 変更後の値(特にパスワード)は記録しない。
+    ///
+    fn audit_summary(&self) -> String {
+        let mut changes = Vec::new();
+        if self.display_name.is_some() {
+            changes.push("display_name");
+        }
+        if self.change_password {
+            changes.push("password");
+        }
+        if self.has_attribute_changes() {
+            changes.push("attributes");
+        }
+        if self.disabled.is_some() {
+            changes.push("disabled");
+        }
+
+        format!("user={} changes={}", self.username, changes.join(","))
+    }
 }
 
 impl CommandContext for UserEditCommandContext {
@@ -72,6 +107,7 @@ impl CommandContext for UserEditCommandContext {
         if self.display_name.is_none()
             && !self.change_password
             && !self.has_attribute_changes()
+            && self.disabled.is_none()
         {
             return Err(anyhow!("no update options specified"));
         }
@@ -105,23 +141,42 @@ impl CommandContext for UserEditCommandContext {
          * 必要時のみパスワード変更入力を取得する
          */
         let password = if self.change_password {
-            Some(read_password_with_confirm()?)
+            let password = read_password_with_confirm()?;
+            self.password_policy.validate(&password)?;
+            Some(password)
         } else {
             None
         };
 
         /*
-         * ユーザ情報の更新
+         * ユーザ情報と無効化状態の更新
+         */
+        if self.display_name.is_some()
+            || password.is_some()
+            || self.has_attribute_changes()
+            || self.disabled.is_some()
+        {
+            self.manager.edit_user(
+                &self.username,
+                self.display_name.as_deref(),
+                password.as_deref(),
+                if self.has_attribute_changes() {
+                    Some(target_attributes)
+                } else {
+                    None
+                },
+                self.disabled,
+                false,
+            )?;
+        }
+
+        /*
+         * 監査ログへの記録
          */
-        self.manager.update_user_with_attributes(
-            &self.username,
-            self.display_name.as_deref(),
-            password.as_deref(),
-            if self.has_attribute_changes() {
-                Some(target_attributes)
-            } else {
-                None
-            },
+        self.audit_log.record(
+            AuditOperation::UserUpdate,
+            None,
+            Some(self.audit_summary()),
         )
     }
 }
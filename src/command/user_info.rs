@@ -9,11 +9,12 @@
 //!
 
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
 
 use super::CommandContext;
 use super::common::format_cli_timestamp;
 use crate::cmd_args::{Options, UserInfoOpts};
-use crate::database::types::UserAttributeSet;
+use crate::database::types::{UserAttributeSet, UserInfo};
 use crate::database::DatabaseManager;
 
 ///
@@ -73,8 +74,28 @@ impl CommandContext for UserInfoCommandContext {
                 "denied"
             },
         );
+        print_field("MFA", &format_mfa_status(&user));
+        print_field(
+            "STATUS",
+            if user.is_disabled() { "disabled" } else { "active" },
+        );
+        print_field("LOCKOUT", &format_lockout_status(&user, Local::now()));
         print_attributes(&user.attributes());
-        print_timestamps(&[("update", user.timestamp())]);
+
+        let mut timestamps = vec![("update", user.timestamp())];
+        if let Some(mfa) = user.mfa() {
+            timestamps.push(("mfa enroll", mfa.enrolled_at()));
+        }
+        if let Some(disabled_at) = user.disabled_at() {
+            timestamps.push(("disable", disabled_at));
+        }
+        if let Some(last_activity) = user.last_activity() {
+            timestamps.push(("last activity", last_activity));
+        }
+        if let Some(locked_until) = user.locked_until() {
+            timestamps.push(("locked until", locked_until));
+        }
+        print_timestamps(&timestamps);
 
         Ok(())
     }
@@ -102,6 +123,45 @@ fn format_attributes(attributes: &UserAttributeSet) -> String {
         .join(", ")
 }
 
+///
+/// 二要素認証状態の表示文字列を生成する
+///
+/// # 引数
+/// * `user` - 表示対象のユーザ情報
+///
+/// # 戻り値
+/// 登録状態と残りリカバリコード数を含む表示文字列を返す。
+///
+fn format_mfa_status(user: &UserInfo) -> String {
+    match user.mfa() {
+        Some(mfa) => format!(
+            "enrolled (recovery codes left: {})",
+            mfa.recovery_code_count()
+        ),
+        None if user.requires_mfa() => "required (not enrolled)".to_string(),
+        None => "not enrolled".to_string(),
+    }
+}
+
+///
+/// ロックアウト状態の表示文字列を生成する
+///
+/// # 引数
+/// * `user` - 表示対象のユーザ情報
+/// * `now` - 判定基準時刻
+///
+/// # 戻り値
+/// ロックアウト中か否かと連続失敗回数を含む表示文字列を返す。
+///
+fn format_lockout_status(user: &UserInfo, now: DateTime<Local>) -> String {
+    let state = if user.is_locked(now) { "locked" } else { "none" };
+
+    match user.failed_logins() {
+        0 => state.to_string(),
+        count => format!("{} (failed logins: {})", state, count),
+    }
+}
+
 ///
 /// 単一値フィールドを整形出力する
 ///
@@ -146,7 +206,7 @@ fn print_attributes(attributes: &UserAttributeSet) {
 /// # 戻り値
 /// なし
 ///
-fn print_timestamps(timestamps: &[(&str, chrono::DateTime<chrono::Local>)]) {
+fn print_timestamps(timestamps: &[(&str, DateTime<Local>)]) {
     println!("TIMESTAMPS:");
     for (label, timestamp) in timestamps {
         println!("    {}: {}", label, format_cli_timestamp(*timestamp));
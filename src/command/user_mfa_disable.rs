@@ -0,0 +1,60 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! サブコマンド"user mfa disable"の実装
+//!
+
+use anyhow::Result;
+
+use super::CommandContext;
+use crate::cmd_args::{Options, UserMfaDisableOpts};
+use crate::database::DatabaseManager;
+
+///
+/// "user mfa disable"サブコマンドのコンテキスト情報をパックした構造体
+///
+struct UserMfaDisableCommandContext {
+    manager: DatabaseManager,
+    username: String,
+}
+
+impl UserMfaDisableCommandContext {
+    ///
+    /// オブジェクトの生成
+    ///
+    fn new(opts: &Options, sub_opts: &UserMfaDisableOpts) -> Result<Self> {
+        Ok(Self {
+            manager: opts.open_database()?,
+            username: sub_opts.user_name(),
+        })
+    }
+}
+
+impl CommandContext for UserMfaDisableCommandContext {
+    ///
+    /// サブコマンドを実行
+    ///
+    /// # 戻り値
+    /// 解除に成功した場合は`Ok(())`を返す。
+    ///
+    /// # 注記
+    /// `RequireMfa` 属性を持つユーザは再登録するまでBasic認証できなくなる。
+    ///
+    fn exec(&self) -> Result<()> {
+        self.manager.disable_user_mfa(&self.username)
+    }
+}
+
+///
+/// コマンドコンテキストの生成
+///
+pub(crate) fn build_context(
+    opts: &Options,
+    sub_opts: &UserMfaDisableOpts,
+) -> Result<Box<dyn CommandContext>> {
+    Ok(Box::new(UserMfaDisableCommandContext::new(opts, sub_opts)?))
+}
@@ -0,0 +1,85 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! サブコマンド"user mfa enroll"の実装
+//!
+
+use anyhow::Result;
+
+use super::CommandContext;
+use crate::cmd_args::{Options, UserMfaEnrollOpts};
+use crate::database::DatabaseManager;
+
+/// otpauth URI に埋め込む発行者名
+const MFA_ISSUER: &str = "LuWiki";
+
+///
+/// "user mfa enroll"サブコマンドのコンテキスト情報をパックした構造体
+///
+struct UserMfaEnrollCommandContext {
+    manager: DatabaseManager,
+    username: String,
+    force: bool,
+}
+
+impl UserMfaEnrollCommandContext {
+    ///
+    /// オブジェクトの生成
+    ///
+    /// # 引数
+    /// * `opts` - グローバルオプション
+    /// * `sub_opts` - user mfa enroll オプション
+    ///
+    /// # 戻り値
+    /// 生成したコマンドコンテキストを返す。
+    ///
+    fn new(opts: &Options, sub_opts: &UserMfaEnrollOpts) -> Result<Self> {
+        Ok(Self {
+            manager: opts.open_database()?,
+            username: sub_opts.user_name(),
+            force: sub_opts.is_force(),
+        })
+    }
+}
+
+impl CommandContext for UserMfaEnrollCommandContext {
+    ///
+    /// サブコマンドを実行
+    ///
+    /// # 戻り値
+    /// 登録に成功した場合は `Ok(())` を返す。
+    ///
+    /// # 注記
+    /// 秘密鍵とリカバリコードの平文はこの出力でのみ確認できる。
+    ///
+    fn exec(&self) -> Result<()> {
+        let enrollment =
+            self.manager.enroll_user_mfa(&self.username, self.force)?;
+
+        println!(
+            "otpauth uri: {}",
+            enrollment.otpauth_uri(MFA_ISSUER, &self.username)
+        );
+        println!("secret:      {}", enrollment.secret());
+        println!("recovery codes:");
+        for code in enrollment.recovery_codes() {
+            println!("    {}", code);
+        }
+
+        Ok(())
+    }
+}
+
+///
+/// コマンドコンテキストの生成
+///
+pub(crate) fn build_context(
+    opts: &Options,
+    sub_opts: &UserMfaEnrollOpts,
+) -> Result<Box<dyn CommandContext>> {
+    Ok(Box::new(UserMfaEnrollCommandContext::new(opts, sub_opts)?))
+}
@@ -0,0 +1,61 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! サブコマンド"user unlock"の実装
+//!
+
+use anyhow::Result;
+
+use super::CommandContext;
+use crate::cmd_args::{Options, UserUnlockOpts};
+use crate::database::DatabaseManager;
+
+///
+/// "user unlock"サブコマンドのコンテキスト情報をパックした構造体
+///
+struct UserUnlockCommandContext {
+    manager: DatabaseManager,
+    username: String,
+}
+
+impl UserUnlockCommandContext {
+    ///
+    /// オブジェクトの生成
+    ///
+    fn new(opts: &Options, sub_opts: &UserUnlockOpts) -> Result<Self> {
+        Ok(Self {
+            manager: opts.open_database()?,
+            username: sub_opts.user_name(),
+        })
+    }
+}
+
+impl CommandContext for UserUnlockCommandContext {
+    ///
+    /// サブコマンドを実行
+    ///
+    /// # 戻り値
+    /// ロックアウト解除に成功した場合は`Ok(())`を返す。
+    ///
+    /// # 注記
+    /// ロックアウトされていない場合も失敗回数を消去して成功とする。
+    ///
+    fn exec(&self) -> Result<()> {
+        self.manager.unlock_user(&self.username)?;
+        Ok(())
+    }
+}
+
+///
+/// コマンドコンテキストの生成
+///
+pub(crate) fn build_context(
+    opts: &Options,
+    sub_opts: &UserUnlockOpts,
+) -> Result<Box<dyn CommandContext>> {
+    Ok(Box::new(UserUnlockCommandContext::new(opts, sub_opts)?))
+}
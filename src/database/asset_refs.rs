@@ -0,0 +1,426 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! アセット参照逆引き索引のtransaction内操作
+//!
+
+use std::collections::BTreeSet;
+
+use anyhow::{Result, anyhow};
+use redb::{
+    ReadableMultimapTable,
+    ReadableTable,
+    WriteTransaction,
+};
+
+use crate::database::link_refs::normalize_page_path;
+use crate::database::schema::{
+    ASSET_REF_STATE_TABLE,
+    ASSET_REF_TABLE,
+    PAGE_ASSET_REF_TABLE,
+    PAGE_INDEX_TABLE,
+    PAGE_SOURCE_TABLE,
+};
+use crate::database::types::{AssetInfo, PageId, PageIndex};
+
+/// アセット参照記法のプレフィクス
+const ASSET_PREFIX: &str = "asset:";
+
+/// アセット参照逆引き索引の構築状態キー
+const ASSET_REF_STATE_KEY: u8 = 0;
+
+/// アセット参照逆引き索引の現行構築状態version
+const ASSET_REF_STATE_VERSION: u8 = 1;
+
+///
+/// アセット参照の参照先キーを生成する
+///
+/// # 引数
+/// * `page_path` - 所属ページのパス(共有アセットの場合は`None`)
+/// * `file_name` - ファイル名
+///
+/// # 戻り値
+/// 参照先キーを返す。
+///
+pub(in crate::database) fn asset_ref_key(
+    page_path: Option<&str>,
+    file_name: &str,
+) -> String {
+    match page_path {
+        Some(path) => format!("{}:{}", path, file_name),
+        None => format!(":{}", file_name),
+    }
+}
+
+///
+/// アセット情報から参照先キーを解決する
+///
+/// # 引数
+/// * `index_table` - ページインデックステーブル
+/// * `asset_info` - 対象アセット情報
+///
+/// # 戻り値
+/// 参照先キーを返す。所属ページを解決できない場合は`None`を返す。
+///
+/// # 注記
+/// 削除済みページに所属するアセットは削除時のパスでキーを生成する。
+///
+pub(in crate::database) fn asset_ref_key_for_info<T>(
+    index_table: &T,
+    asset_info: &AssetInfo,
+) -> Result<Option<String>>
+where
+    T: ReadableTable<PageId, PageIndex>,
+{
+    if asset_info.is_shared() {
+        return Ok(Some(asset_ref_key(None, &asset_info.file_name())));
+    }
+
+    let Some(page_id) = asset_info.page_id() else {
+        return Ok(None);
+    };
+
+    Ok(index_table.get(page_id)?.map(|index| {
+        asset_ref_key(Some(&index.value().path()), &asset_info.file_name())
+    }))
+}
+
+///
+/// 参照先キーを参照している有効なページを収集する
+///
+/// # 引数
+/// * `ref_table` - アセット参照逆引きテーブル
+/// * `index_table` - ページインデックステーブル
+/// * `key` - 参照先キー
+///
+/// # 戻り値
+/// 参照元ページのIDとパスの一覧を返す。
+///
+/// # 注記
+/// 削除済みページとドラフトページは参照元として扱わない。
+///
+pub(in crate::database) fn collect_asset_referrers<R, T>(
+    ref_table: &R,
+    index_table: &T,
+    key: &str,
+) -> Result<Vec<(PageId, String)>>
+where
+    R: ReadableMultimapTable<String, PageId>,
+    T: ReadableTable<PageId, PageIndex>,
+{
+    let mut referrers = Vec::new();
+
+    for entry in ref_table.get(key.to_string())? {
+        let page_id = entry?.value();
+        let index = match index_table.get(page_id.clone())? {
+            Some(index) => index.value(),
+            None => continue,
+        };
+
+        if index.is_draft() || index.deleted() {
+            continue;
+        }
+
+        referrers.push((page_id, index.path()));
+    }
+
+    Ok(referrers)
+}
+
+///
+/// ページソースからアセット参照の参照先キーを抽出する
+///
+/// # 引数
+/// * `base_path` - 基準となるページパス
+/// * `source` - ページソース
+///
+/// # 戻り値
+/// 参照先キーの集合を返す。
+///
+/// # 注記
+/// 抽出対象は以下の通り。
+///  - `[]()`および`![]()`のリンク先に記述された`asset:`記法
+///  - `![[asset:...]]`マクロ
+///
+/// `asset::{ファイル名}`のようにページパスを空にした記法は共有アセットへの
+/// 参照として扱う。
+///
+pub(in crate::database) fn extract_asset_ref_keys(
+    base_path: &str,
+    source: &str,
+) -> BTreeSet<String> {
+    let mut keys = BTreeSet::new();
+
+    for (index, _) in source.match_indices(ASSET_PREFIX) {
+        /*
+         * 記法の文脈に応じた終端文字の決定
+         */
+        let before = &source[..index];
+        let terminators: &[char] = if before.ends_with("[[") {
+            &[']', '|']
+        } else if before.ends_with('(') || before.ends_with("(!") {
+            &[')']
+        } else {
+            // リンク先やマクロ以外に現れた文字列は対象外
+            continue;
+        };
+
+        /*
+         * 参照指定の切り出しと解決
+         */
+        let rest = &source[index..];
+        let end = rest
+            .find(|ch: char| ch.is_whitespace() || terminators.contains(&ch))
+            .unwrap_or(rest.len());
+        if let Some(key) = resolve_asset_ref(base_path, &rest[..end]) {
+            keys.insert(key);
+        }
+    }
+
+    keys
+}
+
+///
+/// `asset:`記法を参照先キーへ解決する
+///
+/// # 引数
+/// * `base_path` - 基準となるページパス
+/// * `spec` - `asset:`記法の文字列
+///
+/// # 戻り値
+/// 参照先キーを返す。解決できない場合は`None`を返す。
+///
+fn resolve_asset_ref(base_path: &str, spec: &str) -> Option<String> {
+    let rest = spec.strip_prefix(ASSET_PREFIX)?;
+
+    /*
+     * 共有アセット参照の判定
+     */
+    if let Some(file) = rest.strip_prefix(':') {
+        if file.is_empty() || file.contains(':') {
+            return None;
+        }
+        return Some(asset_ref_key(None, &decode_percent(file)));
+    }
+
+    /*
+     * ページパスとファイル名の分解
+     */
+    let (path, file) = match rest.rfind(':') {
+        Some(index) => (&rest[..index], &rest[index + 1..]),
+        None => match rest.rfind('/') {
+            Some(0) => (".", &rest[1..]),
+            Some(index) => (&rest[..index], &rest[index + 1..]),
+            None => (".", rest),
+        },
+    };
+
+    if file.is_empty() {
+        return None;
+    }
+
+    let path = normalize_page_path(base_path, path)?;
+    Some(asset_ref_key(Some(&path), &decode_percent(file)))
+}
+
+///
+/// パーセントエンコードされたファイル名を復号する
+///
+/// # 引数
+/// * `value` - 対象文字列
+///
+/// # 戻り値
+/// 復号した文字列を返す。UTF-8として不正な場合は元の文字列を返す。
+///
+fn decode_percent(value: &str) -> String {
+    if !value.contains('%') {
+        return value.to_string();
+    }
+
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        if bytes[pos] == b'%' && pos + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[pos + 1..pos + 3]).ok();
+            let byte = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if let Some(byte) = byte {
+                decoded.push(byte);
+                pos += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[pos]);
+        pos += 1;
+    }
+
+    String::from_utf8(decoded).unwrap_or_else(|_| value.to_string())
+}
+
+///
+/// ページソースに対応するアセット参照逆引き索引を同期する
+///
+/// # 引数
+/// * `txn` - ページ正本と同じwrite transaction
+/// * `page_id` - 同期対象ページID
+/// * `current_path` - 同期対象ページのcurrent path
+/// * `source` - 保存予定の最新ページソース
+///
+/// # 戻り値
+/// 同期に成功した場合は`Ok(())`を返す。
+///
+pub(in crate::database) fn sync_asset_refs_for_source_in_txn(
+    txn: &WriteTransaction,
+    page_id: &PageId,
+    current_path: &str,
+    source: &str,
+) -> Result<()> {
+    /*
+     * 旧参照の解除
+     */
+    remove_asset_refs_by_page_ids_in_txn(txn, std::slice::from_ref(page_id))?;
+
+    /*
+     * 新しい参照の登録
+     */
+    let mut ref_table = txn.open_multimap_table(ASSET_REF_TABLE)?;
+    let mut page_ref_table = txn.open_multimap_table(PAGE_ASSET_REF_TABLE)?;
+    for key in extract_asset_ref_keys(current_path, source) {
+        let _ = ref_table.insert(key.clone(), page_id.clone())?;
+        let _ = page_ref_table.insert(page_id.clone(), key)?;
+    }
+
+    Ok(())
+}
+
+///
+/// 指定ページ群のアセット参照を逆引き索引から除去する
+///
+/// # 引数
+/// * `txn` - ページ正本と同じwrite transaction
+/// * `page_ids` - 参照を解除するページID群
+///
+/// # 戻り値
+/// 除去に成功した場合は`Ok(())`を返す。
+///
+pub(in crate::database) fn remove_asset_refs_by_page_ids_in_txn(
+    txn: &WriteTransaction,
+    page_ids: &[PageId],
+) -> Result<()> {
+    let mut ref_table = txn.open_multimap_table(ASSET_REF_TABLE)?;
+    let mut page_ref_table = txn.open_multimap_table(PAGE_ASSET_REF_TABLE)?;
+
+    for page_id in page_ids {
+        let mut keys = Vec::new();
+        for entry in page_ref_table.remove_all(page_id.clone())? {
+            keys.push(entry?.value());
+        }
+        for key in keys {
+            let _ = ref_table.remove(key, page_id.clone())?;
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// 既存ページからアセット参照逆引き索引を初期構築する
+///
+/// # 引数
+/// * `txn` - DB初期化と同じwrite transaction
+///
+/// # 戻り値
+/// 構築済みまたは初期構築成功時は`Ok(())`を返す。
+///
+pub(in crate::database) fn initialize_asset_refs_in_txn(
+    txn: &WriteTransaction,
+) -> Result<()> {
+    {
+        let state_table = txn.open_table(ASSET_REF_STATE_TABLE)?;
+        if let Some(state) = state_table.get(ASSET_REF_STATE_KEY)? {
+            if state.value() == ASSET_REF_STATE_VERSION {
+                return Ok(());
+            }
+            return Err(anyhow!("unsupported asset reference state"));
+        }
+    }
+
+    let _ = rebuild_asset_refs_in_txn(txn)?;
+    Ok(())
+}
+
+///
+/// 全ページの最新ソースからアセット参照逆引き索引を再構成する
+///
+/// # 引数
+/// * `txn` - 再構成全体を所有するwrite transaction
+///
+/// # 戻り値
+/// 登録した参照の件数を返す。
+///
+pub(in crate::database) fn rebuild_asset_refs_in_txn(
+    txn: &WriteTransaction,
+) -> Result<usize> {
+    /*
+     * 最新ページソースからの参照収集
+     */
+    let mut entries = Vec::new();
+    {
+        let index_table = txn.open_table(PAGE_INDEX_TABLE)?;
+        let source_table = txn.open_table(PAGE_SOURCE_TABLE)?;
+        for item in index_table.iter()? {
+            let (page_id, index) = item?;
+            let page_id = page_id.value();
+            let index = index.value();
+            if index.is_draft() || index.deleted() {
+                continue;
+            }
+
+            let source = source_table
+                .get((page_id.clone(), index.latest()))?
+                .ok_or_else(|| anyhow!("latest page source missing"))?
+                .value()
+                .source();
+            for key in extract_asset_ref_keys(&index.path(), &source) {
+                entries.push((key, page_id.clone()));
+            }
+        }
+    }
+
+    /*
+     * 既存索引の置換
+     */
+    let mut ref_table = txn.open_multimap_table(ASSET_REF_TABLE)?;
+    let mut page_ref_table = txn.open_multimap_table(PAGE_ASSET_REF_TABLE)?;
+    let mut old_keys = Vec::new();
+    for item in ref_table.iter()? {
+        old_keys.push(item?.0.value());
+    }
+    for key in old_keys {
+        let _ = ref_table.remove_all(key)?;
+    }
+    let mut old_pages = Vec::new();
+    for item in page_ref_table.iter()? {
+        old_pages.push(item?.0.value());
+    }
+    for page_id in old_pages {
+        let _ = page_ref_table.remove_all(page_id)?;
+    }
+
+    for (key, page_id) in &entries {
+        let _ = ref_table.insert(key.clone(), page_id.clone())?;
+        let _ = page_ref_table.insert(page_id.clone(), key.clone())?;
+    }
+    drop(ref_table);
+    drop(page_ref_table);
+
+    let mut state_table = txn.open_table(ASSET_REF_STATE_TABLE)?;
+    state_table.insert(ASSET_REF_STATE_KEY, ASSET_REF_STATE_VERSION)?;
+
+    Ok(entries.len())
+}
@@ -8,9 +8,12 @@
 //! データベース一覧出力用のエントリ型を定義するモジュール
 //!
 
+use std::path::PathBuf;
+
 use chrono::{DateTime, Local};
 
 use crate::database::types::{
+    AssetHash,
     AssetId,
     LockToken,
     PageId,
@@ -241,6 +244,9 @@ pub(crate) struct LockListEntry {
     /// 有効期限
     expire: DateTime<Local>,
 
+    /// 取得日時
+    acquired: Option<DateTime<Local>>,
+
     /// ユーザ名
     user_name: String,
 }
@@ -272,6 +278,12 @@ pub(crate) struct AssetListEntry {
 
     /// 削除済みフラグ
     deleted: bool,
+
+    /// 共有アセットフラグ
+    shared: bool,
+
+    /// 参照元ページ数
+    references: usize,
 }
 
 ///
@@ -291,6 +303,154 @@ pub(crate) enum AssetMoveResult {
     NameConflict,
 }
 
+///
+/// 分割アップロードの完了結果
+///
+#[derive(Debug)]
+pub(crate) enum AssetUploadResult {
+    /// 新規アセットとして登録した
+    Created(AssetId),
+
+    /// 既存アセットの新しいリビジョンとして登録した
+    Replaced {
+        asset_id: AssetId,
+        revision: u64,
+    },
+}
+
+///
+/// asset fsck で検出したアセット実体の不整合
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum AssetBlobIssue {
+    /// アセット情報に内容ハッシュ値が記録されていない
+    HashMissing(AssetId),
+
+    /// 実体ファイルが存在しない
+    BlobMissing(AssetHash),
+
+    /// 実体ファイルの内容が内容ハッシュ値と一致しない
+    BlobCorrupted(AssetHash),
+
+    /// 記録された参照数が実際の参照数と一致しない
+    RefCountMismatch {
+        hash: AssetHash,
+        recorded: u64,
+        actual: u64,
+    },
+
+    /// どのアセット情報からも参照されていない実体
+    Unreferenced(AssetHash),
+}
+
+impl AssetBlobIssue {
+    ///
+    /// 修復可能な不整合か否かを返す
+    ///
+    /// # 戻り値
+    /// 参照数の再計算や不要な実体の削除で解消できる場合は`true`を返す。
+    ///
+    pub(crate) fn is_repairable(&self) -> bool {
+        matches!(
+            self,
+            Self::RefCountMismatch { .. } | Self::Unreferenced(_)
+        )
+    }
+}
+
+impl std::fmt::Display for AssetBlobIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HashMissing(asset_id) => {
+                write!(f, "hash missing: asset {}", asset_id)
+            }
+            Self::BlobMissing(hash) => write!(f, "blob missing: {}", hash),
+            Self::BlobCorrupted(hash) => {
+                write!(f, "blob corrupted: {}", hash)
+            }
+            Self::RefCountMismatch {
+                hash,
+                recorded,
+                actual,
+            } => write!(
+                f,
+                "ref count mismatch: {} recorded={} actual={}",
+                hash, recorded, actual
+            ),
+            Self::Unreferenced(hash) => {
+                write!(f, "unreferenced blob: {}", hash)
+            }
+        }
+    }
+}
+
+///
+/// asset gc で検出した回収対象
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum AssetGcItem {
+    /// どのアセット情報・履歴からも参照されていない実体
+    OrphanBlob {
+        hash: AssetHash,
+        size: u64,
+    },
+
+    /// 管理対象外となったファイル(中断された受信データ等)
+    StrayFile {
+        path: PathBuf,
+        size: u64,
+    },
+
+    /// 実体ファイルが存在しないアセット
+    MissingBlob {
+        asset_id: AssetId,
+        hash: AssetHash,
+    },
+
+    /// どのページのリビジョンからも参照されていないアセット
+    Unreferenced {
+        asset_id: AssetId,
+        size: u64,
+    },
+}
+
+impl AssetGcItem {
+    ///
+    /// 回収によって解放されるサイズを返す
+    ///
+    /// # 戻り値
+    /// ファイルの削除で解放されるバイト数を返す。ファイルを削除しない項目
+    /// の場合は0を返す。
+    ///
+    pub(crate) fn reclaimable_size(&self) -> u64 {
+        match self {
+            Self::OrphanBlob { size, .. } | Self::StrayFile { size, .. } => {
+                *size
+            }
+            Self::MissingBlob { .. } | Self::Unreferenced { .. } => 0,
+        }
+    }
+}
+
+impl std::fmt::Display for AssetGcItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OrphanBlob { hash, size } => {
+                write!(f, "orphan blob: {} ({} bytes)", hash, size)
+            }
+            Self::StrayFile { path, size } => {
+                write!(f, "stray file: {} ({} bytes)", path.display(), size)
+            }
+            Self::MissingBlob { asset_id, hash } => {
+                write!(f, "missing blob: asset {} ({})", asset_id, hash)
+            }
+            Self::Unreferenced { asset_id, size } => {
+                write!(f, "unreferenced asset: {} ({} bytes)", asset_id, size)
+            }
+        }
+    }
+}
+
 impl AssetListEntry {
     ///
     /// アセット一覧用の情報を生成する。
@@ -304,6 +464,8 @@ impl AssetListEntry {
     /// * `user_name` - 登録ユーザ名
     /// * `page_path` - 所有ページパス
     /// * `deleted` - 削除済みフラグ
+    /// * `shared` - 共有アセットフラグ
+    /// * `references` - 参照元ページ数
     ///
     /// # 戻り値
     /// AssetListEntryを返す。
@@ -317,6 +479,8 @@ impl AssetListEntry {
         user_name: String,
         page_path: Option<String>,
         deleted: bool,
+        shared: bool,
+        references: usize,
     ) -> Self {
         Self {
             id,
@@ -327,6 +491,8 @@ impl AssetListEntry {
             user_name,
             page_path,
             deleted,
+            shared,
+            references,
         }
     }
     ///
@@ -393,7 +559,7 @@ impl AssetListEntry {
     /// 所有ページパスへのアクセサ
     ///
     /// # 戻り値
-    /// 所有ページのパスを返す。ゾンビの場合はNone。
+    /// 所有ページのパスを返す。共有アセットとゾンビの場合はNone。
     ///
     pub(crate) fn page_path(&self) -> Option<String> {
         self.page_path.clone()
@@ -416,7 +582,27 @@ impl AssetListEntry {
     /// ゾンビ状態の場合は`true`を返す。
     ///
     pub(crate) fn is_zombie(&self) -> bool {
-        self.page_path.is_none()
+        self.page_path.is_none() && !self.shared
+    }
+
+    ///
+    /// 共有アセットの判定
+    ///
+    /// # 戻り値
+    /// 共有アセットの場合は`true`を返す。
+    ///
+    pub(crate) fn is_shared(&self) -> bool {
+        self.shared
+    }
+
+    ///
+    /// 参照元ページ数へのアクセサ
+    ///
+    /// # 戻り値
+    /// アセットを参照している有効なページの数を返す。
+    ///
+    pub(crate) fn references(&self) -> usize {
+        self.references
     }
 
     #[cfg(test)]
@@ -455,6 +641,8 @@ impl AssetListEntry {
             user_name,
             page_path,
             deleted,
+            shared: false,
+            references: 0,
         }
     }
 }
@@ -468,6 +656,7 @@ impl LockListEntry {
     /// * `page_id` - ページID
     /// * `page_path` - ページパス
     /// * `expire` - 有効期限
+    /// * `acquired` - 取得日時
     /// * `user_name` - ユーザ名
     ///
     /// # 戻り値
@@ -478,6 +667,7 @@ impl LockListEntry {
         page_id: PageId,
         page_path: String,
         expire: DateTime<Local>,
+        acquired: Option<DateTime<Local>>,
         user_name: String,
     ) -> Self {
         Self {
@@ -485,6 +675,7 @@ impl LockListEntry {
             page_id,
             page_path,
             expire,
+            acquired,
             user_name,
         }
     }
@@ -529,6 +720,16 @@ impl LockListEntry {
         self.expire
     }
 
+    ///
+    /// 取得日時へのアクセサ
+    ///
+    /// # 戻り値
+    /// 取得日時を記録していないロックでは`None`を返す。
+    ///
+    pub(crate) fn acquired(&self) -> Option<DateTime<Local>> {
+        self.acquired
+    }
+
     ///
     /// ユーザ名へのアクセサ
     ///
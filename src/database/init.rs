@@ -12,15 +12,24 @@ use anyhow::{Context, Result};
 use redb::Database;
 
 use super::schema::{
+    ASSET_BLOB_STATE_TABLE,
+    ASSET_BLOB_TABLE,
     ASSET_GROUP_TABLE,
     ASSET_INFO_TABLE,
     ASSET_LOOKUP_TABLE,
+    ASSET_REF_STATE_TABLE,
+    ASSET_REF_TABLE,
+    ASSET_REVISION_TABLE,
+    ASSET_UPLOAD_TABLE,
     BEARER_TOKEN_ID_TABLE,
     BEARER_TOKEN_TABLE,
+    BROKEN_LOCK_TABLE,
     DELETED_PAGE_PATH_TABLE,
+    EXPORT_HISTORY_TABLE,
     LOCK_INFO_TABLE,
     MCP_PRIMITIVE_NAME_STATE_TABLE,
     MCP_PRIMITIVE_NAME_TABLE,
+    PAGE_ASSET_REF_TABLE,
     PAGE_INDEX_TABLE,
     PAGE_PATH_TABLE,
     PAGE_SOURCE_TABLE,
@@ -28,10 +37,12 @@ use super::schema::{
     RESOURCE_CANDIDATE_TABLE,
     RESOURCE_URI_INDEX_STATE_TABLE,
     RESOURCE_URI_INDEX_TABLE,
+    SHARED_ASSET_LOOKUP_TABLE,
     TEMPLATE_CANDIDATE_TABLE,
     USER_ID_TABLE,
     USER_INFO_TABLE,
 };
+use super::asset_refs::initialize_asset_refs_in_txn;
 use super::primitive_names::initialize_mcp_primitive_names_in_txn;
 
 ///
@@ -61,10 +72,19 @@ use super::primitive_names::initialize_mcp_primitive_names_in_txn;
 ///  - ASSET_GROUP_TABLE: アセット情報テーブル
 ///  - ASSET_LOOKUP_TABLE: アセットID特定テーブル
 ///  - ASSET_GROUP_TABLE: ページ所属アセット群取得テーブル
+///  - SHARED_ASSET_LOOKUP_TABLE: 共有アセットID特定テーブル
+///  - ASSET_REF_TABLE: アセット参照逆引きテーブル
+///  - PAGE_ASSET_REF_TABLE: ページ別アセット参照テーブル
+///  - ASSET_REF_STATE_TABLE: アセット参照逆引き索引構築状態
+///  - ASSET_BLOB_TABLE: アセット実体テーブル
+///  - ASSET_BLOB_STATE_TABLE: 旧形式アセットファイル移行状態
+///  - ASSET_REVISION_TABLE: アセット履歴テーブル
+///  - ASSET_UPLOAD_TABLE: 分割アップロードセッションテーブル
 ///  - USER_ID_TABLE: ユーザIDテーブル
 ///  - USER_INFO_TABLE: ユーザ情報テーブル
 ///  - BEARER_TOKEN_TABLE: Bearerトークン主テーブル
 ///  - BEARER_TOKEN_ID_TABLE: BearerトークンID変換テーブル
+///  - EXPORT_HISTORY_TABLE: エクスポート履歴テーブル
 ///
 pub(in crate::database) fn init_database(db: &mut Database) -> Result<()> {
     /*
@@ -145,6 +165,11 @@ pub(in crate::database) fn init_database(db: &mut Database) -> Result<()> {
             .open_table(LOCK_INFO_TABLE)
             .context("create LOCK_INFO_TABLE")?;
 
+        // 強制解除ロックテーブル
+        let _ = txn
+            .open_table(BROKEN_LOCK_TABLE)
+            .context("create BROKEN_LOCK_TABLE")?;
+
         // アセット情報テーブル
         let _ = txn
             .open_table(ASSET_INFO_TABLE)
@@ -160,6 +185,49 @@ pub(in crate::database) fn init_database(db: &mut Database) -> Result<()> {
             .open_multimap_table(ASSET_GROUP_TABLE)
             .context("create ASSET_GROUP_TABLE")?;
 
+        // 共有アセットID特定テーブル
+        let _ = txn
+            .open_table(SHARED_ASSET_LOOKUP_TABLE)
+            .context("create SHARED_ASSET_LOOKUP_TABLE")?;
+
+        // アセット参照逆引きテーブル
+        let _ = txn
+            .open_multimap_table(ASSET_REF_TABLE)
+            .context("create ASSET_REF_TABLE")?;
+
+        // ページ別アセット参照テーブル
+        let _ = txn
+            .open_multimap_table(PAGE_ASSET_REF_TABLE)
+            .context("create PAGE_ASSET_REF_TABLE")?;
+
+        // アセット参照逆引き索引構築状態テーブル
+        let _ = txn
+            .open_table(ASSET_REF_STATE_TABLE)
+            .context("create ASSET_REF_STATE_TABLE")?;
+
+        initialize_asset_refs_in_txn(&txn)
+            .context("initialize asset references")?;
+
+        // アセット実体テーブル
+        let _ = txn
+            .open_table(ASSET_BLOB_TABLE)
+            .context("create ASSET_BLOB_TABLE")?;
+
+        // 旧形式アセットファイル移行状態テーブル
+        let _ = txn
+            .open_table(ASSET_BLOB_STATE_TABLE)
+            .context("create ASSET_BLOB_STATE_TABLE")?;
+
+        // アセット履歴テーブル
+        let _ = txn
+            .open_table(ASSET_REVISION_TABLE)
+            .context("create ASSET_REVISION_TABLE")?;
+
+        // 分割アップロードセッションテーブル
+        let _ = txn
+            .open_table(ASSET_UPLOAD_TABLE)
+            .context("create ASSET_UPLOAD_TABLE")?;
+
         /*
          * ユーザ関連テーブル作成
          */
@@ -185,6 +253,14 @@ pub(in crate::database) fn init_database(db: &mut Database) -> Result<()> {
         let _ = txn
             .open_table(BEARER_TOKEN_ID_TABLE)
             .context("create BEARER_TOKEN_ID_TABLE")?;
+
+        /*
+         * export/import関連テーブル作成
+         */
+        // エクスポート履歴テーブル
+        let _ = txn
+            .open_table(EXPORT_HISTORY_TABLE)
+            .context("create EXPORT_HISTORY_TABLE")?;
     }
 
     /*
@@ -299,7 +299,10 @@ fn is_schema_char(ch: char) -> bool {
 /// # 戻り値
 /// 正規化したパスを返す。対象外の場合は`None`を返す。
 ///
-fn normalize_page_path(base_path: &str, link: &str) -> Option<String> {
+pub(in crate::database) fn normalize_page_path(
+    base_path: &str,
+    link: &str,
+) -> Option<String> {
     /*
      * 事前の判定
      */
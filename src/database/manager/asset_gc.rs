@@ -0,0 +1,452 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! アセット格納ディレクトリの回収処理を提供するモジュール
+//!
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use redb::{ReadableDatabase, ReadableTable};
+
+use super::DatabaseManager;
+use super::assets::remove_empty_dirs;
+use crate::database::asset_refs::{
+    asset_ref_key_for_info,
+    extract_asset_ref_keys,
+};
+use crate::database::entries::AssetGcItem;
+use crate::database::schema::{
+    ASSET_BLOB_DIR_NAME,
+    ASSET_BLOB_TABLE,
+    ASSET_INFO_TABLE,
+    ASSET_REVISION_TABLE,
+    ASSET_UPLOAD_DIR_NAME,
+    ASSET_UPLOAD_TABLE,
+    ASSET_VARIANT_DIR_NAME,
+    PAGE_INDEX_TABLE,
+    PAGE_SOURCE_TABLE,
+};
+use crate::database::types::{AssetHash, AssetId};
+
+/// インポート時の一時ファイル格納ディレクトリ名
+const ASSET_STAGING_DIR_NAME: &str = ".staging";
+
+/// 書き込み途中のファイルを回収対象から除外する猶予時間(秒)
+const ASSET_GC_GRACE_SECS: u64 = 60 * 60;
+
+///
+/// 回収処理の対象として集めたデータベース上の情報
+///
+struct AssetGcSnapshot {
+    /// アセット情報・履歴から参照されている内容ハッシュ値
+    referenced: HashSet<AssetHash>,
+
+    /// blob 管理情報に記録されている内容ハッシュ値
+    recorded: HashSet<AssetHash>,
+
+    /// 旧形式ファイルからの移行が済んでいないアセットID
+    unmigrated: HashSet<String>,
+
+    /// 進行中の分割アップロードのセッションID
+    uploads: HashSet<String>,
+}
+
+impl DatabaseManager {
+    ///
+    /// アセット格納ディレクトリの回収
+    ///
+    /// # 概要
+    /// アセット格納ディレクトリとデータベースを突き合わせ、参照されていない
+    /// 実体や中断された受信データ等の不要なファイル、実体ファイルを失った
+    /// アセット、どのページのリビジョンからも参照されていないアセットを
+    /// 検出する。
+    ///
+    /// # 引数
+    /// * `remove` - 不要なファイルを削除する場合は`true`
+    /// * `delete_unreferenced` - 参照されていないアセットを削除状態にする
+    ///   場合は`true`
+    ///
+    /// # 戻り値
+    /// 検出した回収対象の一覧を返す。
+    ///
+    /// # 注記
+    /// 更新から一定時間経過していないファイルは書き込み途中の可能性がある
+    /// ため対象としない。参照されていないアセットはソフトデリートのみ行い、
+    /// 実体の解放は`asset purge`に委ねる。
+    ///
+    pub(crate) fn collect_asset_garbage(
+        &self,
+        remove: bool,
+        delete_unreferenced: bool,
+    ) -> Result<Vec<AssetGcItem>> {
+        let mut items = Vec::new();
+
+        /*
+         * データベース側の情報の収集
+         */
+        let snapshot = {
+            let txn = self.db.begin_read()?;
+            let info_table = txn.open_table(ASSET_INFO_TABLE)?;
+            let revision_table = txn.open_table(ASSET_REVISION_TABLE)?;
+            let blob_table = txn.open_table(ASSET_BLOB_TABLE)?;
+            let upload_table = txn.open_table(ASSET_UPLOAD_TABLE)?;
+            let index_table = txn.open_table(PAGE_INDEX_TABLE)?;
+            let source_table = txn.open_table(PAGE_SOURCE_TABLE)?;
+
+            /*
+             * 全リビジョンのページソースからの参照先キーの収集
+             */
+            let mut paths = HashMap::new();
+            let mut ref_keys = HashSet::new();
+            for entry in source_table.iter()? {
+                let (key, value) = entry?;
+                let (page_id, _) = key.value();
+                if !paths.contains_key(&page_id) {
+                    let path = index_table
+                        .get(page_id.clone())?
+                        .map(|index| index.value().path());
+                    paths.insert(page_id.clone(), path);
+                }
+
+                if let Some(Some(path)) = paths.get(&page_id) {
+                    let source = value.value().source();
+                    ref_keys.extend(extract_asset_ref_keys(path, &source));
+                }
+            }
+
+            /*
+             * アセット情報の検査
+             */
+            let mut referenced = HashSet::new();
+            let mut unmigrated = HashSet::new();
+            for entry in info_table.iter()? {
+                let (key, value) = entry?;
+                let asset_id = key.value();
+                let asset_info = value.value();
+                let hash = match asset_info.hash() {
+                    Some(hash) => hash,
+                    None => {
+                        unmigrated.insert(asset_id.to_string());
+                        continue;
+                    }
+                };
+
+                referenced.insert(hash);
+
+                if !self.asset_blob_path(&hash).is_file() {
+                    items.push(AssetGcItem::MissingBlob {
+                        asset_id: asset_id.clone(),
+                        hash,
+                    });
+                }
+
+                if asset_info.deleted() || asset_info.is_zombie() {
+                    continue;
+                }
+
+                let unreferenced =
+                    asset_ref_key_for_info(&index_table, &asset_info)?
+                        .is_some_and(|ref_key| !ref_keys.contains(&ref_key));
+                if unreferenced {
+                    items.push(AssetGcItem::Unreferenced {
+                        asset_id,
+                        size: asset_info.size(),
+                    });
+                }
+            }
+
+            for entry in revision_table.iter()? {
+                let (_, value) = entry?;
+                if let Some(hash) = value.value().hash() {
+                    referenced.insert(hash);
+                }
+            }
+
+            let mut recorded = HashSet::new();
+            for entry in blob_table.iter()? {
+                recorded.insert(entry?.0.value());
+            }
+
+            let mut uploads = HashSet::new();
+            for entry in upload_table.iter()? {
+                uploads.insert(entry?.0.value().to_string());
+            }
+
+            AssetGcSnapshot {
+                referenced,
+                recorded,
+                unmigrated,
+                uploads,
+            }
+        };
+
+        /*
+         * 格納ディレクトリ側の検査
+         */
+        let threshold = SystemTime::now()
+            .checked_sub(Duration::from_secs(ASSET_GC_GRACE_SECS))
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let mut orphans = Vec::new();
+        let mut strays = Vec::new();
+
+        for (path, size, modified) in list_files(&self.asset_path)? {
+            if modified > threshold {
+                continue;
+            }
+
+            let relative = match path.strip_prefix(&self.asset_path) {
+                Ok(relative) => relative,
+                Err(_) => continue,
+            };
+
+            match classify_asset_file(relative, &snapshot) {
+                FileClass::Keep => {}
+                FileClass::OrphanBlob(hash) => {
+                    items.push(AssetGcItem::OrphanBlob { hash, size });
+                    orphans.push(hash);
+                }
+                FileClass::Stray => {
+                    items.push(AssetGcItem::StrayFile {
+                        path: path.clone(),
+                        size,
+                    });
+                    strays.push(path);
+                }
+            }
+        }
+
+        /*
+         * 実体ファイルを失った blob 管理情報の検出
+         */
+        for hash in &snapshot.recorded {
+            let stale = !snapshot.referenced.contains(hash)
+                && !orphans.contains(hash)
+                && !self.asset_blob_path(hash).exists();
+            if stale {
+                items.push(AssetGcItem::OrphanBlob {
+                    hash: *hash,
+                    size: 0,
+                });
+                orphans.push(*hash);
+            }
+        }
+
+        /*
+         * 回収
+         */
+        if remove {
+            self.remove_orphan_blobs(&orphans)?;
+
+            for path in &strays {
+                if fs::remove_file(path).is_ok() {
+                    remove_empty_dirs(path, &self.asset_path);
+                }
+            }
+        }
+
+        if delete_unreferenced {
+            for item in &items {
+                if let AssetGcItem::Unreferenced { asset_id, .. } = item {
+                    self.delete_asset(asset_id)?;
+                }
+            }
+        }
+
+        Ok(items)
+    }
+
+    ///
+    /// 参照されていない実体の削除
+    ///
+    /// # 概要
+    /// 書き込みトランザクション内で参照が無いことを再確認したうえで blob
+    /// 管理情報を削除し、コミット後に実体ファイルを削除する。
+    ///
+    /// # 引数
+    /// * `hashes` - 削除対象の内容ハッシュ値一覧
+    ///
+    /// # 戻り値
+    /// 削除に成功した場合は`Ok(())`を返す。
+    ///
+    fn remove_orphan_blobs(&self, hashes: &[AssetHash]) -> Result<()> {
+        if hashes.is_empty() {
+            return Ok(());
+        }
+
+        /*
+         * 参照の再確認と管理情報の削除
+         */
+        let txn = self.db.begin_write()?;
+        let removable = {
+            let info_table = txn.open_table(ASSET_INFO_TABLE)?;
+            let revision_table = txn.open_table(ASSET_REVISION_TABLE)?;
+            let mut blob_table = txn.open_table(ASSET_BLOB_TABLE)?;
+
+            let mut referenced = HashSet::new();
+            for entry in info_table.iter()? {
+                if let Some(hash) = entry?.1.value().hash() {
+                    referenced.insert(hash);
+                }
+            }
+            for entry in revision_table.iter()? {
+                if let Some(hash) = entry?.1.value().hash() {
+                    referenced.insert(hash);
+                }
+            }
+
+            let mut removable = Vec::new();
+            for hash in hashes {
+                if referenced.contains(hash) {
+                    continue;
+                }
+
+                let _ = blob_table.remove(*hash)?;
+                removable.push(*hash);
+            }
+
+            removable
+        };
+        txn.commit()?;
+
+        /*
+         * 実体ファイルの削除
+         */
+        for hash in removable {
+            let path = self.asset_blob_path(&hash);
+            if fs::remove_file(&path).is_ok() {
+                remove_empty_dirs(&path, &self.asset_path);
+            }
+            self.remove_asset_variants(&hash);
+        }
+
+        Ok(())
+    }
+}
+
+///
+/// 格納ディレクトリ内のファイルの分類
+///
+enum FileClass {
+    /// 管理対象として保持するファイル
+    Keep,
+
+    /// 参照されていない実体ファイル
+    OrphanBlob(AssetHash),
+
+    /// 不要なファイル
+    Stray,
+}
+
+///
+/// アセット格納ディレクトリ内のファイルを分類する
+///
+/// # 引数
+/// * `relative` - 格納ディレクトリからの相対パス
+/// * `snapshot` - データベース側の情報
+///
+/// # 戻り値
+/// ファイルの分類を返す。
+///
+/// # 注記
+/// 管理下の領域(blob・縮小版キャッシュ・受信データ・インポート用一時
+/// ファイル・旧形式のアセットファイル)以外のファイルには関与しない。
+///
+fn classify_asset_file(relative: &Path, snapshot: &AssetGcSnapshot) -> FileClass {
+    let parts: Vec<&str> = relative
+        .iter()
+        .filter_map(|part| part.to_str())
+        .collect();
+    let file_name = match parts.last() {
+        Some(name) => *name,
+        None => return FileClass::Keep,
+    };
+
+    match parts[0] {
+        ASSET_BLOB_DIR_NAME => match AssetHash::from_hex(file_name) {
+            Ok(hash) if snapshot.referenced.contains(&hash) => FileClass::Keep,
+            Ok(hash) => FileClass::OrphanBlob(hash),
+            Err(_) => FileClass::Stray,
+        },
+
+        ASSET_VARIANT_DIR_NAME => {
+            let referenced = parts
+                .get(3)
+                .and_then(|raw| AssetHash::from_hex(raw).ok())
+                .is_some_and(|hash| snapshot.referenced.contains(&hash));
+            if referenced && parts.len() == 5 {
+                FileClass::Keep
+            } else {
+                FileClass::Stray
+            }
+        }
+
+        ASSET_UPLOAD_DIR_NAME => {
+            let active = file_name
+                .strip_suffix(".part")
+                .is_some_and(|id| snapshot.uploads.contains(id));
+            if active {
+                FileClass::Keep
+            } else {
+                FileClass::Stray
+            }
+        }
+
+        ASSET_STAGING_DIR_NAME => FileClass::Stray,
+
+        _ => {
+            /*
+             * 旧形式(アセットID単位)のファイルは配置規則に一致し、かつ
+             * 移行待ちでないものに限り対象とする
+             */
+            let legacy = parts.len() == 3
+                && AssetId::from_string(file_name).is_ok()
+                && file_name.get(..2) == Some(parts[0])
+                && file_name.get(2..5) == Some(parts[1]);
+            if legacy && !snapshot.unmigrated.contains(file_name) {
+                FileClass::Stray
+            } else {
+                FileClass::Keep
+            }
+        }
+    }
+}
+
+///
+/// ディレクトリ配下のファイルの列挙
+///
+/// # 引数
+/// * `root` - 列挙を開始するディレクトリ
+///
+/// # 戻り値
+/// ファイルのパス、サイズ、更新日時の一覧を返す。
+///
+fn list_files(root: &Path) -> Result<Vec<(PathBuf, u64, SystemTime)>> {
+    let mut files = Vec::new();
+    if !root.is_dir() {
+        return Ok(files);
+    }
+
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let meta = entry.metadata()?;
+            if meta.is_dir() {
+                dirs.push(entry.path());
+            } else if meta.is_file() {
+                let modified = meta.modified().unwrap_or(SystemTime::now());
+                files.push((entry.path(), meta.len(), modified));
+            }
+        }
+    }
+
+    Ok(files)
+}
@@ -0,0 +1,502 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! アセットのアップロード受信(一時ファイルと分割アップロード)を扱う
+//! モジュール
+//!
+
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration as StdDuration, SystemTime};
+
+use anyhow::{Result, anyhow};
+use chrono::{Duration, Local};
+use redb::{ReadableDatabase, ReadableTable};
+
+use super::DatabaseManager;
+use crate::database::entries::AssetUploadResult;
+use crate::database::schema::{ASSET_UPLOAD_DIR_NAME, ASSET_UPLOAD_TABLE};
+use crate::database::types::{
+    AssetUploadInfo,
+    AssetUploadTarget,
+    UploadId,
+    UserId,
+};
+
+/// 分割アップロードのセッションを破棄するまでの無通信時間(時間)
+const ASSET_UPLOAD_EXPIRE_HOURS: i64 = 24;
+
+impl DatabaseManager {
+    ///
+    /// 受信用一時ファイルパスの生成
+    ///
+    /// # 概要
+    /// アップロードデータをメモリに展開せずに受信するための一時ファイルの
+    /// パスを払い出す。格納ディレクトリが無い場合は作成する。
+    ///
+    /// # 戻り値
+    /// 未使用の一時ファイルパスを返す。
+    ///
+    pub(crate) fn new_asset_staging_path(&self) -> Result<PathBuf> {
+        let dir = self.asset_upload_dir();
+        fs::create_dir_all(&dir)?;
+        Ok(dir.join(format!("{}.tmp", UploadId::new())))
+    }
+
+    ///
+    /// 分割アップロードのセッション作成
+    ///
+    /// # 引数
+    /// * `user_name` - セッションを作成するユーザ名
+    /// * `target` - 登録先
+    /// * `mime` - 登録時に使用するMIME種別
+    /// * `size` - アップロード総サイズ(バイト)
+    ///
+    /// # 戻り値
+    /// 作成したセッションIDを返す。
+    ///
+    /// # 注記
+    /// 作成時に期限切れのセッションを破棄する。
+    ///
+    pub(crate) fn create_asset_upload(
+        &self,
+        user_name: &str,
+        target: AssetUploadTarget,
+        mime: &str,
+        size: u64,
+    ) -> Result<UploadId> {
+        /*
+         * 期限切れセッションの破棄
+         */
+        self.purge_expired_asset_uploads()?;
+
+        /*
+         * 登録先の検証
+         */
+        let user_id = self.resolve_upload_user(user_name)?;
+        match &target {
+            AssetUploadTarget::NewAsset { page_id, file_name } => {
+                let index = self
+                    .get_page_index_by_id(page_id)?
+                    .ok_or_else(|| {
+                        anyhow!(crate::database::DbError::PageNotFound)
+                    })?;
+                if index.deleted() {
+                    return Err(anyhow!(crate::database::DbError::PageDeleted));
+                }
+
+                if let Some(asset_id) =
+                    self.get_asset_id_by_page_file(page_id, file_name)?
+                    && let Some(info) = self.get_asset_info_by_id(&asset_id)?
+                    && !info.deleted()
+                {
+                    return Err(anyhow!(
+                        crate::database::DbError::AssetAlreadyExists
+                    ));
+                }
+            }
+
+            AssetUploadTarget::Replace { asset_id } => {
+                let info = self
+                    .get_asset_info_by_id(asset_id)?
+                    .ok_or_else(|| {
+                        anyhow!(crate::database::DbError::AssetNotFound)
+                    })?;
+                if info.deleted() || info.is_zombie() {
+                    return Err(anyhow!(
+                        crate::database::DbError::AssetDeleted
+                    ));
+                }
+            }
+        }
+
+        /*
+         * 受信ファイルの作成とセッションの登録
+         */
+        let upload_id = UploadId::new();
+        let part_path = self.asset_upload_part_path(&upload_id);
+        fs::create_dir_all(self.asset_upload_dir())?;
+        fs::File::create(&part_path)?;
+
+        let info =
+            AssetUploadInfo::new(user_id, target, mime.to_string(), size);
+        let txn = self.db.begin_write()?;
+        let insert_result = (|| -> Result<()> {
+            let mut table = txn.open_table(ASSET_UPLOAD_TABLE)?;
+            table.insert(upload_id.clone(), info)?;
+            Ok(())
+        })();
+
+        if let Err(err) = insert_result.and_then(|_| Ok(txn.commit()?)) {
+            let _ = fs::remove_file(&part_path);
+            return Err(err);
+        }
+
+        Ok(upload_id)
+    }
+
+    ///
+    /// 分割アップロードのセッション情報の取得
+    ///
+    /// # 引数
+    /// * `upload_id` - セッションID
+    /// * `user_name` - 操作ユーザ名
+    ///
+    /// # 戻り値
+    /// セッション情報と受信済みバイト数を返す。
+    ///
+    /// # 注記
+    /// 他ユーザのセッションや期限切れのセッションは存在しないものとして
+    /// 扱う。
+    ///
+    pub(crate) fn get_asset_upload(
+        &self,
+        upload_id: &UploadId,
+        user_name: &str,
+    ) -> Result<(AssetUploadInfo, u64)> {
+        let user_id = self.resolve_upload_user(user_name)?;
+
+        let info = {
+            let txn = self.db.begin_read()?;
+            let table = txn.open_table(ASSET_UPLOAD_TABLE)?;
+            table.get(upload_id.clone())?.map(|entry| entry.value())
+        };
+
+        let info = check_upload_owner(info, &user_id)?;
+        let offset = self.asset_upload_offset(upload_id)?;
+        Ok((info, offset))
+    }
+
+    ///
+    /// 分割アップロードへのデータ追記
+    ///
+    /// # 引数
+    /// * `upload_id` - セッションID
+    /// * `user_name` - 操作ユーザ名
+    /// * `offset` - クライアントが想定する受信済みバイト数
+    /// * `chunk_path` - 追記するデータを格納したファイルのパス
+    ///
+    /// # 戻り値
+    /// 追記後の受信済みバイト数を返す。
+    ///
+    /// # 注記
+    /// `chunk_path`のファイルは成否に関わらず削除される。同一セッションへ
+    /// の追記は書き込みトランザクションにより直列化される。
+    ///
+    pub(crate) fn append_asset_upload(
+        &self,
+        upload_id: &UploadId,
+        user_name: &str,
+        offset: u64,
+        chunk_path: &Path,
+    ) -> Result<u64> {
+        let result = (|| -> Result<u64> {
+            let user_id = self.resolve_upload_user(user_name)?;
+
+            let txn = self.db.begin_write()?;
+            let new_offset = {
+                let mut table = txn.open_table(ASSET_UPLOAD_TABLE)?;
+                let info = table
+                    .get(upload_id.clone())?
+                    .map(|entry| entry.value());
+                let mut info = check_upload_owner(info, &user_id)?;
+
+                /*
+                 * 受信位置とサイズの検証
+                 */
+                let current = self.asset_upload_offset(upload_id)?;
+                if current != offset {
+                    return Err(anyhow!(
+                        crate::database::DbError::UploadOffsetMismatch {
+                            offset: current,
+                        }
+                    ));
+                }
+
+                let chunk_size = fs::metadata(chunk_path)?.len();
+                if current.saturating_add(chunk_size) > info.size() {
+                    return Err(anyhow!(
+                        crate::database::DbError::UploadTooLarge
+                    ));
+                }
+
+                /*
+                 * 受信ファイルへの追記
+                 */
+                let mut part = OpenOptions::new()
+                    .append(true)
+                    .open(self.asset_upload_part_path(upload_id))?;
+                let mut chunk = fs::File::open(chunk_path)?;
+                io::copy(&mut chunk, &mut part)?;
+                part.sync_data()?;
+
+                info.touch();
+                table.insert(upload_id.clone(), info)?;
+
+                current + chunk_size
+            };
+            txn.commit()?;
+
+            Ok(new_offset)
+        })();
+
+        let _ = fs::remove_file(chunk_path);
+        result
+    }
+
+    ///
+    /// 分割アップロードの完了
+    ///
+    /// # 概要
+    /// 受信したデータをアセットとして登録し、セッションを破棄する。
+    ///
+    /// # 引数
+    /// * `upload_id` - セッションID
+    /// * `user_name` - 操作ユーザ名
+    ///
+    /// # 戻り値
+    /// 登録結果を返す。
+    ///
+    /// # 注記
+    /// 受信が完了していない場合はセッションを維持したまま
+    /// `DbError::UploadIncomplete`を返す。登録処理に進んだ後はアセットの
+    /// 登録に失敗した場合もセッションは破棄される。
+    ///
+    pub(crate) fn complete_asset_upload(
+        &self,
+        upload_id: &UploadId,
+        user_name: &str,
+    ) -> Result<AssetUploadResult> {
+        /*
+         * セッションの取り出し
+         */
+        let (info, offset) = self.get_asset_upload(upload_id, user_name)?;
+        if offset != info.size() {
+            return Err(anyhow!(crate::database::DbError::UploadIncomplete));
+        }
+
+        let txn = self.db.begin_write()?;
+        {
+            let mut table = txn.open_table(ASSET_UPLOAD_TABLE)?;
+            if table.remove(upload_id.clone())?.is_none() {
+                return Err(anyhow!(crate::database::DbError::UploadNotFound));
+            }
+        }
+        txn.commit()?;
+
+        /*
+         * アセットの登録
+         */
+        let part_path = self.asset_upload_part_path(upload_id);
+        match info.target() {
+            AssetUploadTarget::NewAsset { page_id, file_name } => {
+                let asset_id = self.create_asset_from_file(
+                    page_id,
+                    file_name,
+                    &info.mime(),
+                    user_name,
+                    &part_path,
+                )?;
+                Ok(AssetUploadResult::Created(asset_id))
+            }
+
+            AssetUploadTarget::Replace { asset_id } => {
+                let revision = self.replace_asset_data_from_file(
+                    asset_id,
+                    &info.mime(),
+                    user_name,
+                    &part_path,
+                )?;
+                Ok(AssetUploadResult::Replaced {
+                    asset_id: asset_id.clone(),
+                    revision,
+                })
+            }
+        }
+    }
+
+    ///
+    /// 分割アップロードの中止
+    ///
+    /// # 引数
+    /// * `upload_id` - セッションID
+    /// * `user_name` - 操作ユーザ名
+    ///
+    /// # 戻り値
+    /// 中止に成功した場合は`Ok(())`を返す。
+    ///
+    pub(crate) fn abort_asset_upload(
+        &self,
+        upload_id: &UploadId,
+        user_name: &str,
+    ) -> Result<()> {
+        let user_id = self.resolve_upload_user(user_name)?;
+
+        let txn = self.db.begin_write()?;
+        {
+            let mut table = txn.open_table(ASSET_UPLOAD_TABLE)?;
+            let info = table
+                .get(upload_id.clone())?
+                .map(|entry| entry.value());
+            check_upload_owner(info, &user_id)?;
+            table.remove(upload_id.clone())?;
+        }
+        txn.commit()?;
+
+        let _ = fs::remove_file(self.asset_upload_part_path(upload_id));
+        Ok(())
+    }
+
+    ///
+    /// 期限切れの受信データの破棄
+    ///
+    /// # 概要
+    /// 一定時間データを受信していないセッションと、中断により残された
+    /// 一時ファイルを削除する。
+    ///
+    /// # 戻り値
+    /// 破棄に成功した場合は`Ok(())`を返す。
+    ///
+    pub(crate) fn purge_expired_asset_uploads(&self) -> Result<()> {
+        let expire = Local::now() - Duration::hours(ASSET_UPLOAD_EXPIRE_HOURS);
+
+        /*
+         * 期限切れセッションの削除
+         */
+        let txn = self.db.begin_write()?;
+        let expired = {
+            let mut table = txn.open_table(ASSET_UPLOAD_TABLE)?;
+            let mut expired = Vec::new();
+            for entry in table.iter()? {
+                let (key, value) = entry?;
+                if value.value().updated() < expire {
+                    expired.push(key.value());
+                }
+            }
+
+            for upload_id in &expired {
+                table.remove(upload_id.clone())?;
+            }
+
+            expired
+        };
+        txn.commit()?;
+
+        for upload_id in &expired {
+            let _ = fs::remove_file(self.asset_upload_part_path(upload_id));
+        }
+
+        /*
+         * 残された一時ファイルの削除
+         */
+        let threshold = SystemTime::now()
+            .checked_sub(StdDuration::from_secs(
+                ASSET_UPLOAD_EXPIRE_HOURS as u64 * 3600,
+            ))
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+
+        if let Ok(entries) = fs::read_dir(self.asset_upload_dir()) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|ext| ext.to_str()) != Some("tmp")
+                {
+                    continue;
+                }
+
+                let modified = entry
+                    .metadata()
+                    .and_then(|meta| meta.modified())
+                    .unwrap_or(SystemTime::now());
+                if modified < threshold {
+                    let _ = fs::remove_file(&path);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// 受信済みバイト数の取得
+    ///
+    /// # 引数
+    /// * `upload_id` - セッションID
+    ///
+    /// # 戻り値
+    /// 受信ファイルのサイズを返す。
+    ///
+    fn asset_upload_offset(&self, upload_id: &UploadId) -> Result<u64> {
+        match fs::metadata(self.asset_upload_part_path(upload_id)) {
+            Ok(meta) => Ok(meta.len()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                Err(anyhow!(crate::database::DbError::UploadNotFound))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    ///
+    /// 操作ユーザIDの解決
+    ///
+    /// # 引数
+    /// * `user_name` - 操作ユーザ名
+    ///
+    /// # 戻り値
+    /// ユーザIDを返す。
+    ///
+    fn resolve_upload_user(&self, user_name: &str) -> Result<UserId> {
+        self.get_user_id_by_name(user_name)?
+            .ok_or_else(|| anyhow!(crate::database::DbError::UserNotFound))
+    }
+
+    ///
+    /// 受信データ格納ディレクトリのパス
+    ///
+    /// # 戻り値
+    /// 受信中のデータを格納するディレクトリのパスを返す。
+    ///
+    fn asset_upload_dir(&self) -> PathBuf {
+        self.asset_path.join(ASSET_UPLOAD_DIR_NAME)
+    }
+
+    ///
+    /// 分割アップロードの受信ファイルパスの生成
+    ///
+    /// # 引数
+    /// * `upload_id` - セッションID
+    ///
+    /// # 戻り値
+    /// 受信ファイルのパスを返す。
+    ///
+    fn asset_upload_part_path(&self, upload_id: &UploadId) -> PathBuf {
+        self.asset_upload_dir().join(format!("{}.part", upload_id))
+    }
+}
+
+///
+/// セッションの所有者と有効期限の検証
+///
+/// # 引数
+/// * `info` - 取得したセッション情報
+/// * `user_id` - 操作ユーザID
+///
+/// # 戻り値
+/// 操作可能な場合はセッション情報を返す。
+///
+fn check_upload_owner(
+    info: Option<AssetUploadInfo>,
+    user_id: &UserId,
+) -> Result<AssetUploadInfo> {
+    let expire = Local::now() - Duration::hours(ASSET_UPLOAD_EXPIRE_HOURS);
+    match info {
+        Some(info) if info.user() == *user_id && info.updated() >= expire => {
+            Ok(info)
+        }
+        _ => Err(anyhow!(crate::database::DbError::UploadNotFound)),
+    }
+}
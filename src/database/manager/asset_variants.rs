@@ -0,0 +1,170 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 画像アセットの縮小版キャッシュを扱うモジュール
+//!
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::Result;
+
+use super::DatabaseManager;
+use crate::asset_image::{self, VariantFormat};
+use crate::database::schema::ASSET_VARIANT_DIR_NAME;
+use crate::database::types::AssetHash;
+
+/// 縮小版キャッシュの一時ファイル名に付与する連番
+static VARIANT_TEMP_SEQ: AtomicU64 = AtomicU64::new(0);
+
+impl DatabaseManager {
+    ///
+    /// 縮小版の取得
+    ///
+    /// # 概要
+    /// キャッシュ済みの縮小版があればそれを返し、無ければ元画像から生成して
+    /// キャッシュへ保存する。
+    ///
+    /// # 引数
+    /// * `hash` - 元画像の内容ハッシュ値
+    /// * `mime` - 元画像のMIME種別
+    /// * `width` - 縮小後の幅(`VARIANT_WIDTHS`の候補値)
+    ///
+    /// # 戻り値
+    /// 縮小版のデータと出力形式を返す。元画像の幅が`width`以下で縮小の必要
+    /// が無い場合は`None`を返す。
+    ///
+    pub(crate) fn load_asset_variant(
+        &self,
+        hash: &AssetHash,
+        mime: &str,
+        width: u32,
+    ) -> Result<Option<(Vec<u8>, VariantFormat)>> {
+        let format = VariantFormat::for_mime(mime);
+        let variant_path = self.asset_variant_path(hash, width, format);
+
+        /*
+         * キャッシュの参照
+         */
+        if let Ok(data) = fs::read(&variant_path) {
+            return Ok(Some((data, format)));
+        }
+
+        /*
+         * 元画像からの生成
+         */
+        let data = fs::read(self.asset_blob_path(hash))?;
+        let variant = match asset_image::render_variant(mime, &data, width)? {
+            Some(variant) => variant,
+            None => return Ok(None),
+        };
+
+        /*
+         * キャッシュへの保存(失敗しても生成結果は返す)
+         */
+        if let Some(parent) = variant_path.parent()
+            && fs::create_dir_all(parent).is_ok()
+        {
+            let tmp_path = variant_temp_path(&variant_path);
+            if fs::write(&tmp_path, &variant).is_ok()
+                && fs::rename(&tmp_path, &variant_path).is_err()
+            {
+                let _ = fs::remove_file(&tmp_path);
+            }
+        }
+
+        Ok(Some((variant, format)))
+    }
+
+    ///
+    /// 縮小版キャッシュの削除
+    ///
+    /// # 引数
+    /// * `hash` - 元画像の内容ハッシュ値
+    ///
+    /// # 注記
+    /// キャッシュは内容ハッシュ値単位で保持しているため、同一内容の他の
+    /// アセットが存在する場合も削除する(次回要求時に再生成される)。
+    ///
+    pub(in crate::database) fn remove_asset_variants(&self, hash: &AssetHash) {
+        let dir = self.asset_variant_dir(hash);
+        if dir.is_dir() {
+            let _ = fs::remove_dir_all(&dir);
+        }
+    }
+
+    ///
+    /// 縮小版キャッシュのディレクトリパスの生成
+    ///
+    /// # 引数
+    /// * `hash` - 元画像の内容ハッシュ値
+    ///
+    /// # 戻り値
+    /// 元画像単位のキャッシュディレクトリのパスを返す。
+    ///
+    fn asset_variant_dir(&self, hash: &AssetHash) -> PathBuf {
+        let raw = hash.to_hex();
+        let (dir1, rest) = raw.split_at(2);
+        let (dir2, _) = rest.split_at(2);
+        self.asset_path
+            .join(ASSET_VARIANT_DIR_NAME)
+            .join(dir1)
+            .join(dir2)
+            .join(raw)
+    }
+
+    ///
+    /// 縮小版キャッシュのファイルパスの生成
+    ///
+    /// # 引数
+    /// * `hash` - 元画像の内容ハッシュ値
+    /// * `width` - 縮小後の幅
+    /// * `format` - 出力形式
+    ///
+    /// # 戻り値
+    /// 縮小版キャッシュのファイルパスを返す。
+    ///
+    pub(crate) fn asset_variant_path(
+        &self,
+        hash: &AssetHash,
+        width: u32,
+        format: VariantFormat,
+    ) -> PathBuf {
+        self.asset_variant_dir(hash)
+            .join(format!("{}.{}", width, format.extension()))
+    }
+}
+
+///
+/// 縮小版キャッシュ保存用の一時ファイルパスの生成
+///
+/// # 引数
+/// * `variant_path` - 縮小版キャッシュのファイルパス
+///
+/// # 戻り値
+/// 保存先と同じディレクトリ内の一時ファイルパスを返す。
+///
+/// # 注記
+/// 同一縮小版への同時要求が同じ一時ファイルへ書き込まないよう、プロセスID
+/// と連番を付与する。
+///
+fn variant_temp_path(variant_path: &Path) -> PathBuf {
+    let name = variant_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let seq = VARIANT_TEMP_SEQ.fetch_add(1, Ordering::Relaxed);
+
+    variant_path.with_file_name(format!(
+        ".{}.{}.{}.tmp",
+        name,
+        process::id(),
+        seq
+    ))
+}
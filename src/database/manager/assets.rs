@@ -8,25 +8,75 @@
 //! アセット関連の操作を提供するモジュール
 //!
 
+use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
 
 use anyhow::{Result, anyhow};
+use chrono::Local;
 use redb::{ReadableDatabase, ReadableTable};
 
 use super::DatabaseManager;
-use crate::database::entries::{AssetListEntry, AssetMoveResult};
+use crate::asset_image;
+use crate::database::asset_refs::{
+    asset_ref_key_for_info,
+    collect_asset_referrers,
+};
+use crate::database::entries::{
+    AssetBlobIssue,
+    AssetListEntry,
+    AssetMoveResult,
+};
 use crate::database::schema::{
+    ASSET_BLOB_DIR_NAME,
+    ASSET_BLOB_STATE_TABLE,
+    ASSET_BLOB_TABLE,
     ASSET_GROUP_TABLE,
     ASSET_INFO_TABLE,
     ASSET_LOOKUP_TABLE,
+    ASSET_REF_TABLE,
+    ASSET_REVISION_TABLE,
     PAGE_INDEX_TABLE,
+    SHARED_ASSET_LOOKUP_TABLE,
     USER_ID_TABLE,
     USER_INFO_TABLE,
 };
-use crate::database::types::{AssetId, AssetInfo, PageId};
+use crate::database::txn_helpers::{
+    acquire_asset_blob_in_txn,
+    release_asset_blob_in_txn,
+    remove_asset_revisions_in_txn,
+};
+use crate::database::types::{
+    AssetBlobInfo,
+    AssetHash,
+    AssetId,
+    AssetInfo,
+    AssetRevisionInfo,
+    PageId,
+};
+/// 旧形式アセットファイル移行状態キー
+const ASSET_BLOB_STATE_KEY: u8 = 0;
+
+/// 旧形式アセットファイル移行の現行version
+const ASSET_BLOB_STATE_VERSION: u8 = 1;
+
 /// 孤立した user_id を表示する際の代替ユーザ名
 const UNKNOWN_USERNAME: &str = "unknown";
 
+///
+/// 保存済みのアセット実体の情報
+///
+struct StoredBlob {
+    /// 内容ハッシュ値
+    hash: AssetHash,
+
+    /// サイズ(バイト)
+    size: u64,
+
+    /// 画像の寸法(画像でない場合は`None`)
+    dimensions: Option<(u32, u32)>,
+}
+
 impl DatabaseManager {
     ///
     /// アセット情報の一覧取得
@@ -42,6 +92,7 @@ impl DatabaseManager {
         let info_table = txn.open_table(ASSET_INFO_TABLE)?;
         let user_table = txn.open_table(USER_INFO_TABLE)?;
         let index_table = txn.open_table(PAGE_INDEX_TABLE)?;
+        let ref_table = txn.open_multimap_table(ASSET_REF_TABLE)?;
         let mut assets = Vec::new();
 
         /*
@@ -68,6 +119,14 @@ impl DatabaseManager {
             let page_path = asset_info
                 .page_id()
                 .and_then(|page_id| page_map.get(&page_id).cloned());
+            let references =
+                match asset_ref_key_for_info(&index_table, &asset_info)? {
+                    Some(key) => {
+                        collect_asset_referrers(&ref_table, &index_table, &key)?
+                            .len()
+                    }
+                    None => 0,
+                };
 
             assets.push(AssetListEntry::new(
                 asset_id,
@@ -78,6 +137,8 @@ impl DatabaseManager {
                 user_name,
                 page_path,
                 asset_info.deleted(),
+                asset_info.is_shared(),
+                references,
             ));
         }
 
@@ -108,6 +169,71 @@ impl DatabaseManager {
         Ok(table.get(key)?.map(|entry| entry.value()))
     }
 
+    ///
+    /// 共有アセットのアセットIDを取得
+    ///
+    /// # 引数
+    /// * `file_name` - ファイル名
+    ///
+    /// # 戻り値
+    /// 解決できたアセットIDを返す。存在しない場合は`None`を返す。
+    ///
+    pub(crate) fn get_shared_asset_id_by_name(
+        &self,
+        file_name: &str,
+    ) -> Result<Option<AssetId>> {
+        /*
+         * 読み取りトランザクション開始
+         */
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(SHARED_ASSET_LOOKUP_TABLE)?;
+        Ok(table.get(file_name.to_string())?.map(|entry| entry.value()))
+    }
+
+    ///
+    /// アセットを参照しているページの一覧取得
+    ///
+    /// # 概要
+    /// アセット参照逆引き索引から、アセットを埋め込んでいる有効なページを
+    /// 収集する。
+    ///
+    /// # 引数
+    /// * `asset_id` - アセットID
+    ///
+    /// # 戻り値
+    /// 参照元ページのIDとパスの一覧をパス順で返す。
+    ///
+    pub(crate) fn list_asset_referrers(
+        &self,
+        asset_id: &AssetId,
+    ) -> Result<Vec<(PageId, String)>> {
+        /*
+         * 読み取りトランザクション開始
+         */
+        let txn = self.db.begin_read()?;
+        let info_table = txn.open_table(ASSET_INFO_TABLE)?;
+        let index_table = txn.open_table(PAGE_INDEX_TABLE)?;
+        let ref_table = txn.open_multimap_table(ASSET_REF_TABLE)?;
+
+        /*
+         * 参照先キーの解決と参照元の収集
+         */
+        let asset_info = info_table
+            .get(asset_id.clone())?
+            .ok_or_else(|| anyhow!(crate::database::DbError::AssetNotFound))?
+            .value();
+        let mut referrers =
+            match asset_ref_key_for_info(&index_table, &asset_info)? {
+                Some(key) => {
+                    collect_asset_referrers(&ref_table, &index_table, &key)?
+                }
+                None => Vec::new(),
+            };
+        referrers.sort_by(|a, b| a.1.cmp(&b.1));
+
+        Ok(referrers)
+    }
+
     ///
     /// アセット情報の取得
     ///
@@ -224,9 +350,18 @@ impl DatabaseManager {
         asset_id: &AssetId,
     ) -> Result<Vec<u8>> {
         /*
-         * アセットパスの生成
+         * 内容ハッシュ値の解決
+         */
+        let hash = self
+            .get_asset_info_by_id(asset_id)?
+            .ok_or_else(|| anyhow!(crate::database::DbError::AssetNotFound))?
+            .hash()
+            .ok_or_else(|| anyhow!("asset blob not found: {}", asset_id))?;
+
+        /*
+         * blob の読み込み
          */
-        let path = self.asset_file_path(asset_id);
+        let path = self.asset_blob_path(&hash);
         Ok(fs::read(path)?)
     }
 
@@ -255,21 +390,170 @@ impl DatabaseManager {
         data: &[u8],
     ) -> Result<AssetId> {
         /*
-         * 事前情報の整形
+         * アセット実体の保存
+         */
+        let size = data.len() as u64;
+        let dimensions = asset_image::probe_dimensions(mime, data);
+        let hash = AssetHash::from_data(data);
+        self.store_asset_blob(&hash, data)?;
+
+        /*
+         * アセット情報の登録
+         */
+        self.register_asset(
+            Some(page_id),
+            file_name,
+            mime,
+            user_name,
+            StoredBlob {
+                hash,
+                size,
+                dimensions,
+            },
+        )
+    }
+
+    ///
+    /// ファイルからのアセットの作成
+    ///
+    /// # 概要
+    /// 受信済みのファイルをアセット実体として取り込み、アセット情報を登録
+    /// する。データ全体をメモリに展開しない。
+    ///
+    /// # 引数
+    /// * `page_id` - ページID
+    /// * `file_name` - ファイル名
+    /// * `mime` - MIME種別
+    /// * `user_name` - 登録ユーザ名
+    /// * `staged_path` - アセットデータを格納したファイルのパス
+    ///
+    /// # 戻り値
+    /// 作成したアセットIDを返す。
+    ///
+    /// # 注記
+    /// `staged_path`のファイルは成否に関わらず取り込み後に削除される。
+    ///
+    pub(crate) fn create_asset_from_file(
+        &self,
+        page_id: &PageId,
+        file_name: &str,
+        mime: &str,
+        user_name: &str,
+        staged_path: &Path,
+    ) -> Result<AssetId> {
+        let blob = self.store_asset_blob_from_file(mime, staged_path)?;
+        self.register_asset(Some(page_id), file_name, mime, user_name, blob)
+    }
+
+    ///
+    /// 共有アセットの作成
+    ///
+    /// # 概要
+    /// ページに所属しない共有アセットとしてアセット情報の登録とファイル
+    /// 保存を行う。
+    ///
+    /// # 引数
+    /// * `file_name` - ファイル名
+    /// * `mime` - MIME種別
+    /// * `user_name` - 登録ユーザ名
+    /// * `data` - アセットデータ
+    ///
+    /// # 戻り値
+    /// 作成したアセットIDを返す。
+    ///
+    #[allow(dead_code)]
+    pub(crate) fn create_shared_asset(
+        &self,
+        file_name: &str,
+        mime: &str,
+        user_name: &str,
+        data: &[u8],
+    ) -> Result<AssetId> {
+        /*
+         * アセット実体の保存
          */
-        let file_name = file_name.to_string();
-        let mime = mime.to_string();
         let size = data.len() as u64;
+        let dimensions = asset_image::probe_dimensions(mime, data);
+        let hash = AssetHash::from_data(data);
+        self.store_asset_blob(&hash, data)?;
+
+        /*
+         * アセット情報の登録
+         */
+        self.register_asset(
+            None,
+            file_name,
+            mime,
+            user_name,
+            StoredBlob {
+                hash,
+                size,
+                dimensions,
+            },
+        )
+    }
+
+    ///
+    /// ファイルからの共有アセットの作成
+    ///
+    /// # 引数
+    /// * `file_name` - ファイル名
+    /// * `mime` - MIME種別
+    /// * `user_name` - 登録ユーザ名
+    /// * `staged_path` - アセットデータを格納したファイルのパス
+    ///
+    /// # 戻り値
+    /// 作成したアセットIDを返す。
+    ///
+    /// # 注記
+    /// `staged_path`のファイルは成否に関わらず取り込み後に削除される。
+    ///
+    pub(crate) fn create_shared_asset_from_file(
+        &self,
+        file_name: &str,
+        mime: &str,
+        user_name: &str,
+        staged_path: &Path,
+    ) -> Result<AssetId> {
+        let blob = self.store_asset_blob_from_file(mime, staged_path)?;
+        self.register_asset(None, file_name, mime, user_name, blob)
+    }
 
+    ///
+    /// アセット情報の登録
+    ///
+    /// # 引数
+    /// * `page_id` - ページID(共有アセットの場合は`None`)
+    /// * `file_name` - ファイル名
+    /// * `mime` - MIME種別
+    /// * `user_name` - 登録ユーザ名
+    /// * `blob` - 保存済みのアセット実体
+    ///
+    /// # 戻り値
+    /// 作成したアセットIDを返す。
+    ///
+    /// # 注記
+    /// 登録に失敗した場合、参照されていない実体は破棄される。
+    ///
+    fn register_asset(
+        &self,
+        page_id: Option<&PageId>,
+        file_name: &str,
+        mime: &str,
+        user_name: &str,
+        blob: StoredBlob,
+    ) -> Result<AssetId> {
         /*
-         * アセットファイルの保存
+         * 事前情報の整形
          */
+        let file_name = file_name.to_string();
+        let mime = mime.to_string();
         let asset_id = AssetId::new();
-        let asset_path = self.asset_file_path(&asset_id);
-        if let Some(parent) = asset_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        fs::write(&asset_path, data)?;
+        let StoredBlob {
+            hash,
+            size,
+            dimensions,
+        } = blob;
 
         /*
          * 書き込みトランザクション開始
@@ -279,13 +563,19 @@ impl DatabaseManager {
             let mut info_table = txn.open_table(ASSET_INFO_TABLE)?;
             let mut lookup_table = txn.open_table(ASSET_LOOKUP_TABLE)?;
             let mut group_table = txn.open_multimap_table(ASSET_GROUP_TABLE)?;
+            let mut shared_table = txn.open_table(SHARED_ASSET_LOOKUP_TABLE)?;
+            let mut blob_table = txn.open_table(ASSET_BLOB_TABLE)?;
             let index_table = txn.open_table(PAGE_INDEX_TABLE)?;
 
             /*
              * ページ存在確認
              */
-            if index_table.get(page_id.clone())?.is_none() {
-                return Err(anyhow!(crate::database::DbError::PageNotFound));
+            if let Some(page_id) = page_id
+                && index_table.get(page_id.clone())?.is_none()
+            {
+                return Err(anyhow!(
+                    crate::database::DbError::PageNotFound
+                ));
             }
 
             /*
@@ -307,10 +597,13 @@ impl DatabaseManager {
             /*
              * 既存アセットの確認
              */
-            let lookup_key = (page_id.clone(), file_name.clone());
-            let conflict_id = match lookup_table.get(&lookup_key)? {
-                Some(entry) => Some(entry.value().clone()),
-                None => None,
+            let conflict_id = match page_id {
+                Some(page_id) => lookup_table
+                    .get((page_id.clone(), file_name.clone()))?
+                    .map(|entry| entry.value()),
+                None => shared_table
+                    .get(file_name.clone())?
+                    .map(|entry| entry.value()),
             };
 
             if let Some(conflict_id) = conflict_id {
@@ -321,9 +614,7 @@ impl DatabaseManager {
                     None => false,
                 };
 
-                if deleted_conflict {
-                    let _ = lookup_table.remove(lookup_key.clone());
-                } else {
+                if !deleted_conflict {
                     return Err(anyhow!(
                         crate::database::DbError::AssetAlreadyExists
                     ));
@@ -333,17 +624,41 @@ impl DatabaseManager {
             /*
              * アセット情報の登録
              */
-            let asset_info = AssetInfo::new(
-                asset_id.clone(),
-                page_id.clone(),
-                file_name.clone(),
-                mime,
-                size,
-                user_id,
-            );
+            let mut asset_info = match page_id {
+                Some(page_id) => AssetInfo::new(
+                    asset_id.clone(),
+                    page_id.clone(),
+                    file_name.clone(),
+                    mime,
+                    size,
+                    hash,
+                    user_id,
+                ),
+                None => AssetInfo::new_shared(
+                    asset_id.clone(),
+                    file_name.clone(),
+                    mime,
+                    size,
+                    hash,
+                    user_id,
+                ),
+            };
+            asset_info.set_dimensions(dimensions);
             info_table.insert(asset_id.clone(), asset_info)?;
-            lookup_table.insert(lookup_key, asset_id.clone())?;
-            let _ = group_table.insert(page_id.clone(), asset_id.clone())?;
+            match page_id {
+                Some(page_id) => {
+                    lookup_table.insert(
+                        (page_id.clone(), file_name.clone()),
+                        asset_id.clone(),
+                    )?;
+                    let _ =
+                        group_table.insert(page_id.clone(), asset_id.clone())?;
+                }
+                None => {
+                    shared_table.insert(file_name.clone(), asset_id.clone())?;
+                }
+            }
+            acquire_asset_blob_in_txn(&mut blob_table, &hash, size)?;
 
             Ok(())
         })();
@@ -352,7 +667,8 @@ impl DatabaseManager {
          * 登録失敗時の巻き戻し
          */
         if let Err(err) = insert_result {
-            let _ = fs::remove_file(&asset_path);
+            drop(txn);
+            self.discard_unreferenced_asset_blob(&hash);
             return Err(err);
         }
 
@@ -360,7 +676,7 @@ impl DatabaseManager {
          * コミット
          */
         if let Err(err) = txn.commit() {
-            let _ = fs::remove_file(&asset_path);
+            self.discard_unreferenced_asset_blob(&hash);
             return Err(err.into());
         }
 
@@ -384,9 +700,11 @@ impl DatabaseManager {
          * 書き込みトランザクション開始
          */
         let txn = self.db.begin_write()?;
-        let delete_result = (|| -> Result<()> {
+        let delete_result = (|| -> Result<Vec<AssetHash>> {
             let mut info_table = txn.open_table(ASSET_INFO_TABLE)?;
             let mut lookup_table = txn.open_table(ASSET_LOOKUP_TABLE)?;
+            let mut shared_table = txn.open_table(SHARED_ASSET_LOOKUP_TABLE)?;
+            let revision_table = txn.open_table(ASSET_REVISION_TABLE)?;
             let mut asset_info = match info_table.get(asset_id.clone())? {
                 Some(info) => info.value(),
                 None => {
@@ -403,11 +721,24 @@ impl DatabaseManager {
                 return Err(anyhow!(crate::database::DbError::AssetDeleted));
             }
 
+            /*
+             * 縮小版キャッシュの破棄対象の収集
+             */
+            let mut hashes: Vec<AssetHash> =
+                asset_info.hash().into_iter().collect();
+            for entry in revision_table
+                .range((asset_id.clone(), 0u64)..=(asset_id.clone(), u64::MAX))?
+            {
+                let (_, revision) = entry?;
+                hashes.extend(revision.value().hash());
+            }
+
             /*
              * 削除フラグ更新
              */
             let page_id = asset_info.page_id();
             let file_name = asset_info.file_name();
+            let shared = asset_info.is_shared();
             asset_info.set_deleted(true);
             info_table.insert(asset_id.clone(), asset_info)?;
 
@@ -417,6 +748,8 @@ impl DatabaseManager {
             if let Some(page_id) = page_id {
                 let lookup_key = (page_id, file_name);
                 let _ = lookup_table.remove(lookup_key);
+            } else if shared {
+                let _ = shared_table.remove(file_name);
             } else {
                 let mut remove_keys = Vec::new();
                 for entry in lookup_table.iter()? {
@@ -430,18 +763,23 @@ impl DatabaseManager {
                 }
             }
 
-            Ok(())
+            Ok(hashes)
         })();
 
-        if let Err(err) = delete_result {
-            return Err(err);
-        }
+        let hashes = delete_result?;
 
         /*
          * コミット
          */
         txn.commit()?;
 
+        /*
+         * 縮小版キャッシュの破棄
+         */
+        for hash in &hashes {
+            self.remove_asset_variants(hash);
+        }
+
         Ok(())
     }
 
@@ -457,15 +795,24 @@ impl DatabaseManager {
     /// # 戻り値
     /// 削除に成功した場合は`Ok(())`を返す。
     ///
+    /// # 注記
+    /// 有効なページから参照されているアセットは削除せず、
+    /// `DbError::AssetReferenced`を返す。
+    ///
     pub(crate) fn delete_asset_hard(&self, asset_id: &AssetId) -> Result<()> {
         /*
          * 書き込みトランザクション開始
          */
         let txn = self.db.begin_write()?;
-        let delete_result = (|| -> Result<Option<(PageId, String)>> {
+        let delete_result = (|| -> Result<(Vec<AssetHash>, Vec<AssetHash>)> {
             let mut info_table = txn.open_table(ASSET_INFO_TABLE)?;
             let mut lookup_table = txn.open_table(ASSET_LOOKUP_TABLE)?;
             let mut group_table = txn.open_multimap_table(ASSET_GROUP_TABLE)?;
+            let mut shared_table = txn.open_table(SHARED_ASSET_LOOKUP_TABLE)?;
+            let mut blob_table = txn.open_table(ASSET_BLOB_TABLE)?;
+            let mut revision_table = txn.open_table(ASSET_REVISION_TABLE)?;
+            let index_table = txn.open_table(PAGE_INDEX_TABLE)?;
+            let ref_table = txn.open_multimap_table(ASSET_REF_TABLE)?;
 
             /*
              * 対象アセットの取得
@@ -479,6 +826,19 @@ impl DatabaseManager {
                 }
             };
 
+            /*
+             * 参照中アセットの保護
+             */
+            if let Some(key) = asset_ref_key_for_info(&index_table, &asset_info)? {
+                let referrers =
+                    collect_asset_referrers(&ref_table, &index_table, &key)?;
+                if !referrers.is_empty() {
+                    return Err(anyhow!(
+                        crate::database::DbError::AssetReferenced
+                    ));
+                }
+            }
+
             /*
              * 参照の削除
              */
@@ -488,6 +848,13 @@ impl DatabaseManager {
                 let lookup_key = (page_id.clone(), file_name.clone());
                 let _ = lookup_table.remove(lookup_key);
                 let _ = group_table.remove(page_id, asset_id.clone());
+            } else if asset_info.is_shared() {
+                let registered = shared_table
+                    .get(file_name.clone())?
+                    .map(|entry| entry.value());
+                if registered.as_ref() == Some(asset_id) {
+                    let _ = shared_table.remove(file_name);
+                }
             } else {
                 let mut remove_keys = Vec::new();
                 for entry in lookup_table.iter()? {
@@ -503,16 +870,39 @@ impl DatabaseManager {
             }
 
             /*
-             * 情報の削除
+             * 縮小版キャッシュの破棄対象の収集
+             */
+            let mut hashes: Vec<AssetHash> =
+                asset_info.hash().into_iter().collect();
+            for entry in revision_table
+                .range((asset_id.clone(), 0u64)..=(asset_id.clone(), u64::MAX))?
+            {
+                let (_, revision) = entry?;
+                hashes.extend(revision.value().hash());
+            }
+
+            /*
+             * 情報の削除と実体参照の解除
              */
             let _ = info_table.remove(asset_id.clone())?;
 
-            Ok(page_id.map(|id| (id, file_name)))
+            let mut released = Vec::new();
+            if let Some(hash) = asset_info.hash()
+                && release_asset_blob_in_txn(&mut blob_table, &hash)?
+            {
+                released.push(hash);
+            }
+            remove_asset_revisions_in_txn(
+                &mut revision_table,
+                &mut blob_table,
+                asset_id,
+                &mut released,
+            )?;
+
+            Ok((released, hashes))
         })();
 
-        if let Err(err) = delete_result {
-            return Err(err);
-        }
+        let (released, hashes) = delete_result?;
 
         /*
          * コミット
@@ -520,10 +910,12 @@ impl DatabaseManager {
         txn.commit()?;
 
         /*
-         * アセットファイルの削除
+         * 参照が無くなったアセット実体と縮小版キャッシュの削除
          */
-        let asset_path = self.asset_file_path(asset_id);
-        let _ = fs::remove_file(asset_path);
+        self.remove_asset_blob_files(&released);
+        for hash in &hashes {
+            self.remove_asset_variants(hash);
+        }
 
         Ok(())
     }
@@ -552,6 +944,7 @@ impl DatabaseManager {
         let update_result = (|| -> Result<()> {
             let mut info_table = txn.open_table(ASSET_INFO_TABLE)?;
             let mut lookup_table = txn.open_table(ASSET_LOOKUP_TABLE)?;
+            let mut shared_table = txn.open_table(SHARED_ASSET_LOOKUP_TABLE)?;
 
             /*
              * 対象アセットの取得と状態確認
@@ -593,6 +986,15 @@ impl DatabaseManager {
                 }
                 asset_info.set_file_name(target_name.clone());
                 lookup_table.insert(lookup_key, asset_id.clone())?;
+            } else if asset_info.is_shared() {
+                if shared_table.get(target_name.clone())?.is_some() {
+                    return Err(anyhow!(
+                        crate::database::DbError::AssetAlreadyExists
+                    ));
+                }
+
+                asset_info.set_file_name(target_name.clone());
+                shared_table.insert(target_name, asset_id.clone())?;
             } else if asset_info.file_name() != target_name {
                 asset_info.set_file_name(target_name);
             }
@@ -641,12 +1043,15 @@ impl DatabaseManager {
          * 書き込みトランザクション開始
          */
         let txn = self.db.begin_write()?;
-        let mut conflict_asset: Option<AssetId> = None;
+        let mut released_blobs: Vec<AssetHash> = Vec::new();
 
         let move_result = (|| -> Result<AssetMoveResult> {
             let mut info_table = txn.open_table(ASSET_INFO_TABLE)?;
             let mut lookup_table = txn.open_table(ASSET_LOOKUP_TABLE)?;
             let mut group_table = txn.open_multimap_table(ASSET_GROUP_TABLE)?;
+            let mut shared_table = txn.open_table(SHARED_ASSET_LOOKUP_TABLE)?;
+            let mut blob_table = txn.open_table(ASSET_BLOB_TABLE)?;
+            let mut revision_table = txn.open_table(ASSET_REVISION_TABLE)?;
             let index_table = txn.open_table(PAGE_INDEX_TABLE)?;
 
             /*
@@ -687,13 +1092,25 @@ impl DatabaseManager {
                 } else if !force {
                     return Ok(AssetMoveResult::NameConflict);
                 } else {
-                    conflict_asset = Some(conflict_id.clone());
                     let _ = lookup_table.remove(lookup_key.clone());
                     let _ = group_table.remove(
                         dst_page_id.clone(),
                         conflict_id.clone(),
                     );
-                    let _ = info_table.remove(conflict_id)?;
+                    let conflict_hash = info_table
+                        .remove(conflict_id.clone())?
+                        .and_then(|info| info.value().hash());
+                    if let Some(hash) = conflict_hash
+                        && release_asset_blob_in_txn(&mut blob_table, &hash)?
+                    {
+                        released_blobs.push(hash);
+                    }
+                    remove_asset_revisions_in_txn(
+                        &mut revision_table,
+                        &mut blob_table,
+                        &conflict_id,
+                        &mut released_blobs,
+                    )?;
                 }
             }
 
@@ -704,6 +1121,13 @@ impl DatabaseManager {
                 let src_key = (src_page_id.clone(), file_name.clone());
                 let _ = lookup_table.remove(src_key);
                 let _ = group_table.remove(src_page_id, asset_id.clone());
+            } else if asset_info.is_shared() {
+                let registered = shared_table
+                    .get(file_name.clone())?
+                    .map(|entry| entry.value());
+                if registered.as_ref() == Some(asset_id) {
+                    let _ = shared_table.remove(file_name.clone());
+                }
             }
 
             /*
@@ -723,13 +1147,878 @@ impl DatabaseManager {
         txn.commit()?;
 
         /*
-         * 競合アセットの削除
+         * 競合アセットの実体削除
          */
-        if let Some(conflict) = conflict_asset {
-            let asset_path = self.asset_file_path(&conflict);
-            let _ = fs::remove_file(asset_path);
-        }
+        self.remove_asset_blob_files(&released_blobs);
 
         Ok(move_result)
     }
+
+    ///
+    /// アセット内容の差し替え
+    ///
+    /// # 概要
+    /// 現在の内容を履歴へ退避し、指定データを新しいリビジョンとして登録
+    /// する。
+    ///
+    /// # 引数
+    /// * `asset_id` - 対象アセットID
+    /// * `mime` - 新しいリビジョンのMIME種別
+    /// * `user_name` - 登録ユーザ名
+    /// * `data` - 新しいリビジョンのアセットデータ
+    ///
+    /// # 戻り値
+    /// 登録した新しいリビジョン番号を返す。
+    ///
+    #[allow(dead_code)]
+    pub(crate) fn replace_asset_data(
+        &self,
+        asset_id: &AssetId,
+        mime: &str,
+        user_name: &str,
+        data: &[u8],
+    ) -> Result<u64> {
+        /*
+         * アセット実体の保存
+         */
+        let size = data.len() as u64;
+        let dimensions = asset_image::probe_dimensions(mime, data);
+        let hash = AssetHash::from_data(data);
+        self.store_asset_blob(&hash, data)?;
+
+        /*
+         * 新しいリビジョンの登録
+         */
+        self.register_asset_revision(
+            asset_id,
+            mime,
+            user_name,
+            StoredBlob {
+                hash,
+                size,
+                dimensions,
+            },
+        )
+    }
+
+    ///
+    /// ファイルからのアセット内容の差し替え
+    ///
+    /// # 概要
+    /// 受信済みのファイルを新しいリビジョンの実体として取り込む。データ
+    /// 全体をメモリに展開しない。
+    ///
+    /// # 引数
+    /// * `asset_id` - 対象アセットID
+    /// * `mime` - 新しいリビジョンのMIME種別
+    /// * `user_name` - 登録ユーザ名
+    /// * `staged_path` - アセットデータを格納したファイルのパス
+    ///
+    /// # 戻り値
+    /// 登録した新しいリビジョン番号を返す。
+    ///
+    /// # 注記
+    /// `staged_path`のファイルは成否に関わらず取り込み後に削除される。
+    ///
+    pub(crate) fn replace_asset_data_from_file(
+        &self,
+        asset_id: &AssetId,
+        mime: &str,
+        user_name: &str,
+        staged_path: &Path,
+    ) -> Result<u64> {
+        let blob = self.store_asset_blob_from_file(mime, staged_path)?;
+        self.register_asset_revision(asset_id, mime, user_name, blob)
+    }
+
+    ///
+    /// 新しいリビジョンの登録
+    ///
+    /// # 引数
+    /// * `asset_id` - 対象アセットID
+    /// * `mime` - 新しいリビジョンのMIME種別
+    /// * `user_name` - 登録ユーザ名
+    /// * `blob` - 保存済みのアセット実体
+    ///
+    /// # 戻り値
+    /// 登録した新しいリビジョン番号を返す。
+    ///
+    /// # 注記
+    /// 登録に失敗した場合、参照されていない実体は破棄される。
+    ///
+    fn register_asset_revision(
+        &self,
+        asset_id: &AssetId,
+        mime: &str,
+        user_name: &str,
+        blob: StoredBlob,
+    ) -> Result<u64> {
+        let StoredBlob {
+            hash,
+            size,
+            dimensions,
+        } = blob;
+
+        /*
+         * 書き込みトランザクション開始
+         */
+        let txn = self.db.begin_write()?;
+        let replace_result = (|| -> Result<u64> {
+            let mut info_table = txn.open_table(ASSET_INFO_TABLE)?;
+            let mut blob_table = txn.open_table(ASSET_BLOB_TABLE)?;
+            let mut revision_table = txn.open_table(ASSET_REVISION_TABLE)?;
+
+            /*
+             * 対象アセットの取得と検証
+             */
+            let mut asset_info = match info_table.get(asset_id.clone())? {
+                Some(info) => info.value(),
+                None => {
+                    return Err(anyhow!(
+                        crate::database::DbError::AssetNotFound
+                    ));
+                }
+            };
+
+            if asset_info.deleted() || asset_info.is_zombie() {
+                return Err(anyhow!(crate::database::DbError::AssetDeleted));
+            }
+
+            /*
+             * ユーザIDの解決
+             */
+            let user_id = {
+                let id_table = txn.open_table(USER_ID_TABLE)?;
+                let key = user_name.to_string();
+                match id_table.get(&key)? {
+                    Some(id) => id.value(),
+                    None => {
+                        return Err(anyhow!(
+                            crate::database::DbError::UserNotFound
+                        ));
+                    }
+                }
+            };
+
+            /*
+             * 現在の内容を履歴へ退避(実体参照は履歴側へ引き継ぐ)
+             */
+            let current = asset_info.current_revision();
+            let revision = current.revision() + 1;
+            revision_table.insert((asset_id.clone(), current.revision()), current)?;
+
+            /*
+             * 新しいリビジョンの反映
+             */
+            acquire_asset_blob_in_txn(&mut blob_table, &hash, size)?;
+            let mut new_revision = AssetRevisionInfo::new(
+                revision,
+                mime.to_string(),
+                size,
+                Some(hash),
+                user_id,
+                Local::now(),
+            );
+            new_revision.set_dimensions(dimensions);
+            asset_info.apply_revision(new_revision);
+            info_table.insert(asset_id.clone(), asset_info)?;
+
+            Ok(revision)
+        })();
+
+        /*
+         * 失敗時の巻き戻し
+         */
+        let revision = match replace_result {
+            Ok(revision) => revision,
+            Err(err) => {
+                drop(txn);
+                self.discard_unreferenced_asset_blob(&hash);
+                return Err(err);
+            }
+        };
+
+        /*
+         * コミット
+         */
+        if let Err(err) = txn.commit() {
+            self.discard_unreferenced_asset_blob(&hash);
+            return Err(err.into());
+        }
+
+        Ok(revision)
+    }
+
+    ///
+    /// アセットのリビジョン一覧の取得
+    ///
+    /// # 引数
+    /// * `asset_id` - 対象アセットID
+    ///
+    /// # 戻り値
+    /// 現在のリビジョンを含むリビジョン情報を古い順に返す。
+    ///
+    pub(crate) fn list_asset_revisions(
+        &self,
+        asset_id: &AssetId,
+    ) -> Result<Vec<AssetRevisionInfo>> {
+        let txn = self.db.begin_read()?;
+        let info_table = txn.open_table(ASSET_INFO_TABLE)?;
+        let revision_table = txn.open_table(ASSET_REVISION_TABLE)?;
+
+        let asset_info = match info_table.get(asset_id.clone())? {
+            Some(info) => info.value(),
+            None => {
+                return Err(anyhow!(crate::database::DbError::AssetNotFound));
+            }
+        };
+
+        let mut revisions = Vec::new();
+        for entry in revision_table
+            .range((asset_id.clone(), 0u64)..=(asset_id.clone(), u64::MAX))?
+        {
+            let (_, value) = entry?;
+            revisions.push(value.value());
+        }
+        revisions.push(asset_info.current_revision());
+
+        Ok(revisions)
+    }
+
+    ///
+    /// 指定リビジョンのアセットデータの読み込み
+    ///
+    /// # 引数
+    /// * `asset_id` - 対象アセットID
+    /// * `revision` - 読み込むリビジョン番号
+    ///
+    /// # 戻り値
+    /// リビジョン情報とアセットデータを返す。
+    ///
+    #[allow(dead_code)]
+    pub(crate) fn read_asset_revision_data(
+        &self,
+        asset_id: &AssetId,
+        revision: u64,
+    ) -> Result<(AssetRevisionInfo, Vec<u8>)> {
+        /*
+         * リビジョン情報の解決
+         */
+        let revision_info = {
+            let txn = self.db.begin_read()?;
+            let info_table = txn.open_table(ASSET_INFO_TABLE)?;
+            let revision_table = txn.open_table(ASSET_REVISION_TABLE)?;
+
+            let asset_info = match info_table.get(asset_id.clone())? {
+                Some(info) => info.value(),
+                None => {
+                    return Err(anyhow!(
+                        crate::database::DbError::AssetNotFound
+                    ));
+                }
+            };
+
+            if asset_info.revision() == revision {
+                asset_info.current_revision()
+            } else {
+                match revision_table.get((asset_id.clone(), revision))? {
+                    Some(entry) => entry.value(),
+                    None => {
+                        return Err(anyhow!(
+                            crate::database::DbError::InvalidRevision
+                        ));
+                    }
+                }
+            }
+        };
+
+        /*
+         * blob の読み込み
+         */
+        let hash = revision_info
+            .hash()
+            .ok_or_else(|| anyhow!("asset blob not found: {}", asset_id))?;
+        let data = fs::read(self.asset_blob_path(&hash))?;
+
+        Ok((revision_info, data))
+    }
+
+    ///
+    /// アセットのロールバック
+    ///
+    /// # 概要
+    /// 指定リビジョンを現在のリビジョンに戻し、それより新しいリビジョンを
+    /// 破棄する。
+    ///
+    /// # 引数
+    /// * `asset_id` - 対象アセットID
+    /// * `rollback_to` - ロールバック先のリビジョン番号
+    ///
+    /// # 戻り値
+    /// ロールバックに成功した場合は`Ok(())`を返す。
+    ///
+    pub(crate) fn rollback_asset(
+        &self,
+        asset_id: &AssetId,
+        rollback_to: u64,
+    ) -> Result<()> {
+        /*
+         * 書き込みトランザクション開始
+         */
+        let txn = self.db.begin_write()?;
+        let mut released_blobs = Vec::new();
+
+        {
+            let mut info_table = txn.open_table(ASSET_INFO_TABLE)?;
+            let mut blob_table = txn.open_table(ASSET_BLOB_TABLE)?;
+            let mut revision_table = txn.open_table(ASSET_REVISION_TABLE)?;
+
+            /*
+             * 対象アセットの取得と検証
+             */
+            let mut asset_info = match info_table.get(asset_id.clone())? {
+                Some(info) => info.value(),
+                None => {
+                    return Err(anyhow!(
+                        crate::database::DbError::AssetNotFound
+                    ));
+                }
+            };
+
+            if asset_info.deleted() || asset_info.is_zombie() {
+                return Err(anyhow!(crate::database::DbError::AssetDeleted));
+            }
+
+            let latest = asset_info.revision();
+            if rollback_to == latest {
+                return Ok(());
+            }
+
+            if rollback_to > latest {
+                return Err(anyhow!(crate::database::DbError::InvalidRevision));
+            }
+
+            let target = match revision_table
+                .remove((asset_id.clone(), rollback_to))?
+            {
+                Some(entry) => entry.value(),
+                None => {
+                    return Err(anyhow!(
+                        crate::database::DbError::InvalidRevision
+                    ));
+                }
+            };
+
+            /*
+             * 新しいリビジョンの破棄
+             */
+            for revision in (rollback_to + 1)..latest {
+                let removed = revision_table
+                    .remove((asset_id.clone(), revision))?
+                    .and_then(|entry| entry.value().hash());
+                if let Some(hash) = removed
+                    && release_asset_blob_in_txn(&mut blob_table, &hash)?
+                {
+                    released_blobs.push(hash);
+                }
+            }
+
+            if let Some(hash) = asset_info.hash()
+                && release_asset_blob_in_txn(&mut blob_table, &hash)?
+            {
+                released_blobs.push(hash);
+            }
+
+            /*
+             * ロールバック先の内容を現在のリビジョンへ反映
+             * (実体参照は履歴側から引き継ぐ)
+             */
+            asset_info.apply_revision(target);
+            info_table.insert(asset_id.clone(), asset_info)?;
+        }
+
+        /*
+         * コミット
+         */
+        txn.commit()?;
+
+        /*
+         * 参照が無くなったアセット実体の削除
+         */
+        self.remove_asset_blob_files(&released_blobs);
+
+        Ok(())
+    }
+
+    ///
+    /// アセット実体の保存
+    ///
+    /// # 概要
+    /// 内容ハッシュ値に対応する保存パスへアセットデータを書き込む。同一内容
+    /// の実体が既に存在する場合は書き込みを行わない。
+    ///
+    /// # 引数
+    /// * `hash` - アセットデータの内容ハッシュ値
+    /// * `data` - アセットデータ
+    ///
+    /// # 戻り値
+    /// 新規に書き込んだ場合は`true`、既存の実体を再利用した場合は`false`を
+    /// 返す。
+    ///
+    pub(in crate::database) fn store_asset_blob(
+        &self,
+        hash: &AssetHash,
+        data: &[u8],
+    ) -> Result<bool> {
+        let blob_path = self.asset_blob_path(hash);
+        if blob_path.exists() {
+            return Ok(false);
+        }
+
+        if let Some(parent) = blob_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        /*
+         * 一時ファイル経由で書き込み、途中状態の実体を残さない
+         */
+        let tmp_path = blob_path.with_extension("tmp");
+        fs::write(&tmp_path, data)?;
+        if let Err(err) = fs::rename(&tmp_path, &blob_path) {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(err.into());
+        }
+
+        Ok(true)
+    }
+
+    ///
+    /// ファイルからのアセット実体の保存
+    ///
+    /// # 概要
+    /// 受信済みのファイルの内容ハッシュ値を計算し、対応する保存パスへ移動
+    /// する。同一内容の実体が既に存在する場合はファイルを破棄する。
+    ///
+    /// # 引数
+    /// * `mime` - アセットのMIME種別
+    /// * `staged_path` - アセットデータを格納したファイルのパス
+    ///
+    /// # 戻り値
+    /// 保存したアセット実体の情報を返す。
+    ///
+    fn store_asset_blob_from_file(
+        &self,
+        mime: &str,
+        staged_path: &Path,
+    ) -> Result<StoredBlob> {
+        let result = (|| -> Result<StoredBlob> {
+            /*
+             * 内容ハッシュ値と寸法の取得
+             */
+            let (hash, size) =
+                AssetHash::from_reader(fs::File::open(staged_path)?)?;
+            let dimensions =
+                asset_image::probe_file_dimensions(mime, staged_path);
+
+            /*
+             * 保存パスへの移動(同一ファイルシステム内であれば rename のみ)
+             */
+            let blob_path = self.asset_blob_path(&hash);
+            if !blob_path.exists() {
+                if let Some(parent) = blob_path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+
+                if fs::rename(staged_path, &blob_path).is_err() {
+                    let tmp_path = blob_path.with_extension("tmp");
+                    fs::copy(staged_path, &tmp_path)?;
+                    if let Err(err) = fs::rename(&tmp_path, &blob_path) {
+                        let _ = fs::remove_file(&tmp_path);
+                        return Err(err.into());
+                    }
+                }
+            }
+
+            Ok(StoredBlob {
+                hash,
+                size,
+                dimensions,
+            })
+        })();
+
+        let _ = fs::remove_file(staged_path);
+        result
+    }
+
+    ///
+    /// 参照されていないアセット実体の破棄
+    ///
+    /// # 概要
+    /// blob 管理情報が存在しない(どのアセット情報からも参照されていない)
+    /// 場合に限り、実体ファイルを削除する。
+    ///
+    /// # 引数
+    /// * `hash` - 破棄対象の内容ハッシュ値
+    ///
+    pub(in crate::database) fn discard_unreferenced_asset_blob(
+        &self,
+        hash: &AssetHash,
+    ) {
+        let referenced = (|| -> Result<bool> {
+            let txn = self.db.begin_read()?;
+            let blob_table = txn.open_table(ASSET_BLOB_TABLE)?;
+            Ok(blob_table.get(*hash)?.is_some())
+        })();
+
+        if let Ok(false) = referenced {
+            let _ = fs::remove_file(self.asset_blob_path(hash));
+            self.remove_asset_variants(hash);
+        }
+    }
+
+    ///
+    /// 参照が無くなったアセット実体ファイルの削除
+    ///
+    /// # 引数
+    /// * `hashes` - 参照が無くなった実体の内容ハッシュ値一覧
+    ///
+    /// # 注記
+    /// コミット後に同一内容のアセットが再登録されている可能性があるため、
+    /// 削除直前に参照の有無を再確認する。
+    ///
+    pub(in crate::database) fn remove_asset_blob_files(
+        &self,
+        hashes: &[AssetHash],
+    ) {
+        for hash in hashes {
+            self.discard_unreferenced_asset_blob(hash);
+        }
+    }
+
+    ///
+    /// 旧形式アセットファイルの移行
+    ///
+    /// # 概要
+    /// アセットID単位で保存されていた旧形式のファイルを内容ハッシュ値単位
+    /// の blob へ移し替え、アセット情報に内容ハッシュ値を記録する。
+    ///
+    /// # 戻り値
+    /// 移行に成功した場合は`Ok(())`を返す。
+    ///
+    /// # 注記
+    /// 移行の完了を移行状態テーブルへ記録し、記録済みの場合はアセット情報
+    /// を走査せずに戻る。
+    ///
+    pub(super) fn migrate_legacy_asset_files(&self) -> Result<()> {
+        /*
+         * 移行状態の確認と移行対象の収集
+         */
+        let targets = {
+            let txn = self.db.begin_read()?;
+            let state_table = txn.open_table(ASSET_BLOB_STATE_TABLE)?;
+            if let Some(state) = state_table.get(ASSET_BLOB_STATE_KEY)? {
+                if state.value() == ASSET_BLOB_STATE_VERSION {
+                    return Ok(());
+                }
+                return Err(anyhow!("unsupported asset blob state"));
+            }
+
+            let info_table = txn.open_table(ASSET_INFO_TABLE)?;
+            let mut targets = Vec::new();
+
+            for entry in info_table.iter()? {
+                let (key, value) = entry?;
+                let asset_id = key.value();
+                if value.value().hash().is_some() {
+                    continue;
+                }
+
+                let legacy_path = self.asset_file_path(&asset_id);
+                if legacy_path.is_file() {
+                    targets.push((asset_id, legacy_path));
+                }
+            }
+
+            targets
+        };
+
+        /*
+         * 実体の移し替え
+         */
+        let mut migrated = Vec::new();
+        for (asset_id, legacy_path) in targets {
+            let data = fs::read(&legacy_path)?;
+            let hash = AssetHash::from_data(&data);
+            self.store_asset_blob(&hash, &data)?;
+            migrated.push((asset_id, hash, data.len() as u64, legacy_path));
+        }
+
+        /*
+         * アセット情報への内容ハッシュ値と移行状態の記録
+         */
+        let txn = self.db.begin_write()?;
+        {
+            let mut info_table = txn.open_table(ASSET_INFO_TABLE)?;
+            let mut blob_table = txn.open_table(ASSET_BLOB_TABLE)?;
+            let mut state_table = txn.open_table(ASSET_BLOB_STATE_TABLE)?;
+
+            for (asset_id, hash, size, _) in &migrated {
+                let mut asset_info = match info_table.get(asset_id.clone())? {
+                    Some(info) => info.value(),
+                    None => continue,
+                };
+
+                asset_info.set_hash(*hash);
+                info_table.insert(asset_id.clone(), asset_info)?;
+                acquire_asset_blob_in_txn(&mut blob_table, hash, *size)?;
+            }
+
+            state_table
+                .insert(ASSET_BLOB_STATE_KEY, ASSET_BLOB_STATE_VERSION)?;
+        }
+        txn.commit()?;
+
+        /*
+         * 旧形式ファイルの削除
+         */
+        for (_, _, _, legacy_path) in migrated {
+            let _ = fs::remove_file(&legacy_path);
+            remove_empty_dirs(&legacy_path, &self.asset_path);
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// アセット実体の整合性検査
+    ///
+    /// # 概要
+    /// アセット情報・blob 管理情報・実体ファイルの三者を突き合わせ、欠損や
+    /// 破損、参照数の不一致、参照されていない実体を検出する。
+    ///
+    /// # 引数
+    /// * `repair` - 修復可能な不整合を修復する場合は`true`
+    ///
+    /// # 戻り値
+    /// 検出した不整合の一覧を返す。
+    ///
+    pub(crate) fn check_asset_blobs(
+        &self,
+        repair: bool,
+    ) -> Result<Vec<AssetBlobIssue>> {
+        let mut issues = Vec::new();
+
+        /*
+         * アセット情報と履歴からの参照数集計
+         */
+        let (actual_refs, recorded) = {
+            let txn = self.db.begin_read()?;
+            let info_table = txn.open_table(ASSET_INFO_TABLE)?;
+            let blob_table = txn.open_table(ASSET_BLOB_TABLE)?;
+            let mut actual_refs: HashMap<AssetHash, (u64, u64)> =
+                HashMap::new();
+
+            for entry in info_table.iter()? {
+                let (key, value) = entry?;
+                let asset_info = value.value();
+                match asset_info.hash() {
+                    Some(hash) => {
+                        let counter = actual_refs
+                            .entry(hash)
+                            .or_insert((0, asset_info.size()));
+                        counter.0 += 1;
+                    }
+                    None => {
+                        if asset_info.page_id().is_some() {
+                            issues.push(AssetBlobIssue::HashMissing(
+                                key.value(),
+                            ));
+                        }
+                    }
+                }
+            }
+
+            let revision_table = txn.open_table(ASSET_REVISION_TABLE)?;
+            for entry in revision_table.iter()? {
+                let (_, value) = entry?;
+                let revision = value.value();
+                if let Some(hash) = revision.hash() {
+                    let counter = actual_refs
+                        .entry(hash)
+                        .or_insert((0, revision.size()));
+                    counter.0 += 1;
+                }
+            }
+
+            let mut recorded: HashMap<AssetHash, AssetBlobInfo> =
+                HashMap::new();
+            for entry in blob_table.iter()? {
+                let (key, value) = entry?;
+                recorded.insert(key.value(), value.value());
+            }
+
+            (actual_refs, recorded)
+        };
+
+        /*
+         * 参照されている実体の検査
+         */
+        let mut fixes: Vec<(AssetHash, Option<AssetBlobInfo>)> = Vec::new();
+        for (hash, (actual, size)) in &actual_refs {
+            if let Some(issue) = self.verify_asset_blob_file(hash) {
+                issues.push(issue);
+            }
+
+            let recorded_count = recorded
+                .get(hash)
+                .map(|blob| blob.ref_count())
+                .unwrap_or(0);
+            if recorded_count != *actual {
+                issues.push(AssetBlobIssue::RefCountMismatch {
+                    hash: *hash,
+                    recorded: recorded_count,
+                    actual: *actual,
+                });
+
+                let mut blob = recorded
+                    .get(hash)
+                    .cloned()
+                    .unwrap_or_else(|| AssetBlobInfo::new(*size));
+                blob.set_ref_count(*actual);
+                fixes.push((*hash, Some(blob)));
+            }
+        }
+
+        /*
+         * 参照されていない管理情報・実体ファイルの検出
+         */
+        let mut unreferenced = Vec::new();
+        for hash in recorded.keys() {
+            if !actual_refs.contains_key(hash) {
+                issues.push(AssetBlobIssue::Unreferenced(*hash));
+                fixes.push((*hash, None));
+                unreferenced.push(*hash);
+            }
+        }
+
+        for hash in self.list_asset_blob_files()? {
+            if !actual_refs.contains_key(&hash) && !recorded.contains_key(&hash)
+            {
+                issues.push(AssetBlobIssue::Unreferenced(hash));
+                unreferenced.push(hash);
+            }
+        }
+
+        /*
+         * 修復
+         */
+        if repair && !fixes.is_empty() {
+            let txn = self.db.begin_write()?;
+            {
+                let mut blob_table = txn.open_table(ASSET_BLOB_TABLE)?;
+                for (hash, blob) in fixes {
+                    match blob {
+                        Some(blob) => {
+                            blob_table.insert(hash, blob)?;
+                        }
+                        None => {
+                            let _ = blob_table.remove(hash)?;
+                        }
+                    }
+                }
+            }
+            txn.commit()?;
+        }
+
+        if repair {
+            self.remove_asset_blob_files(&unreferenced);
+        }
+
+        Ok(issues)
+    }
+
+    ///
+    /// アセット実体ファイルの検証
+    ///
+    /// # 引数
+    /// * `hash` - 検証対象の内容ハッシュ値
+    ///
+    /// # 戻り値
+    /// 欠損または破損を検出した場合はその内容を返す。
+    ///
+    fn verify_asset_blob_file(
+        &self,
+        hash: &AssetHash,
+    ) -> Option<AssetBlobIssue> {
+        match fs::read(self.asset_blob_path(hash)) {
+            Ok(data) => {
+                if AssetHash::from_data(&data) != *hash {
+                    Some(AssetBlobIssue::BlobCorrupted(*hash))
+                } else {
+                    None
+                }
+            }
+            Err(_) => Some(AssetBlobIssue::BlobMissing(*hash)),
+        }
+    }
+
+    ///
+    /// 保存済みアセット実体ファイルの列挙
+    ///
+    /// # 戻り値
+    /// blob ディレクトリに存在する実体ファイルの内容ハッシュ値一覧を返す。
+    ///
+    fn list_asset_blob_files(&self) -> Result<Vec<AssetHash>> {
+        let blob_dir = self.asset_path.join(ASSET_BLOB_DIR_NAME);
+        let mut hashes = Vec::new();
+        if !blob_dir.is_dir() {
+            return Ok(hashes);
+        }
+
+        for dir1 in fs::read_dir(&blob_dir)? {
+            let dir1 = dir1?.path();
+            if !dir1.is_dir() {
+                continue;
+            }
+
+            for dir2 in fs::read_dir(&dir1)? {
+                let dir2 = dir2?.path();
+                if !dir2.is_dir() {
+                    continue;
+                }
+
+                for file in fs::read_dir(&dir2)? {
+                    let file = file?.path();
+                    let name = match file.file_name().and_then(|n| n.to_str())
+                    {
+                        Some(name) => name,
+                        None => continue,
+                    };
+
+                    if let Ok(hash) = AssetHash::from_hex(name) {
+                        hashes.push(hash);
+                    }
+                }
+            }
+        }
+
+        Ok(hashes)
+    }
+}
+
+///
+/// 空になった旧形式アセットディレクトリの削除
+///
+/// # 引数
+/// * `path` - 削除したファイルのパス
+/// * `root` - 削除を打ち切るアセット格納ルート
+///
+pub(super) fn remove_empty_dirs(path: &Path, root: &Path) {
+    let mut current = path.parent();
+    while let Some(dir) = current {
+        if dir == root || fs::remove_dir(dir).is_err() {
+            break;
+        }
+        current = dir.parent();
+    }
 }
@@ -114,6 +114,9 @@ pub(crate) enum VerifyBearerTokenFailureReason {
 
     /// 紐付けユーザ未解決
     UserNotFound(TokenId),
+
+    /// 紐付けユーザが無効化されている
+    UserDisabled(TokenId),
 }
 
 #[allow(dead_code)]
@@ -130,6 +133,7 @@ impl VerifyBearerTokenFailureReason {
             Self::Revoked(_) => "revoked",
             Self::Expired(_) => "expired",
             Self::UserNotFound(_) => "user_not_found",
+            Self::UserDisabled(_) => "user_disabled",
         }
     }
 
@@ -145,6 +149,7 @@ impl VerifyBearerTokenFailureReason {
             Self::Revoked(token_id) => Some(token_id),
             Self::Expired(token_id) => Some(token_id),
             Self::UserNotFound(token_id) => Some(token_id),
+            Self::UserDisabled(token_id) => Some(token_id),
         }
     }
 }
@@ -205,6 +210,83 @@ impl VerifyBearerTokenResult {
     }
 }
 
+///
+/// Bearerトークンのローテーション結果
+///
+#[derive(Clone, Debug)]
+pub(crate) struct RotateBearerTokenResult {
+    /// 新たに発行した Bearerトークン平文
+    plaintext: BearerTokenPlaintext,
+
+    /// 更新後の Bearerトークン管理情報
+    token_info: BearerTokenInfo,
+
+    /// ローテーション前の照合用ハッシュ値
+    previous_hash: TokenHash,
+
+    /// ローテーション後の照合用ハッシュ値
+    current_hash: TokenHash,
+
+    /// 旧平文の猶予期限(猶予なしの場合は `None`)
+    grace_until: Option<DateTime<Local>>,
+}
+
+impl RotateBearerTokenResult {
+    ///
+    /// 新たに発行した Bearerトークン平文を返す
+    ///
+    /// # 戻り値
+    /// ローテーションで発行した Bearerトークン平文を返す。
+    ///
+    pub(crate) fn plaintext(&self) -> &BearerTokenPlaintext {
+        &self.plaintext
+    }
+
+    ///
+    /// 更新後の Bearerトークン管理情報を返す
+    ///
+    /// # 戻り値
+    /// ローテーション後の Bearerトークン管理情報を返す。
+    ///
+    pub(crate) fn token_info(&self) -> BearerTokenInfo {
+        self.token_info.clone()
+    }
+
+    ///
+    /// 旧平文の猶予期限を返す
+    ///
+    /// # 戻り値
+    /// 猶予期間を設けた場合は猶予期限を返す。
+    ///
+    pub(crate) fn grace_until(&self) -> Option<DateTime<Local>> {
+        self.grace_until
+    }
+
+    ///
+    /// 監査ログ向けの要約文字列を生成する
+    ///
+    /// # 戻り値
+    /// トークンIDと新旧照合用ハッシュ値の先頭12桁、猶予期限を含む要約を
+    /// 返す。
+    ///
+    pub(crate) fn audit_summary(&self) -> String {
+        let grace_until = match self.grace_until {
+            Some(grace_until) => {
+                grace_until.format("%Y-%m-%dT%H:%M:%S").to_string()
+            }
+            None => "none".to_string(),
+        };
+
+        format!(
+            "token_id={} previous={} current={} grace_until={}",
+            self.token_info.token_id(),
+            &self.previous_hash.to_hex()[..12],
+            &self.current_hash.to_hex()[..12],
+            grace_until,
+        )
+    }
+}
+
 impl DatabaseManager {
     ///
     /// Bearerトークン平文を生成する
@@ -389,6 +471,9 @@ impl DatabaseManager {
     /// # 戻り値
     /// 主テーブルへ保存されている Bearerトークン管理情報の一覧を返す。
     ///
+    /// # 注記
+    /// ローテーションで旧ハッシュ側に残した猶予エントリは含めない。
+    ///
     #[allow(dead_code)]
     pub(crate) fn list_bearer_tokens(&self) -> Result<Vec<BearerTokenInfo>> {
         /*
@@ -400,7 +485,11 @@ impl DatabaseManager {
 
         for entry in token_table.iter()? {
             let (_, info) = entry?;
-            tokens.push(info.value());
+            let info = info.value();
+            if info.is_rotation_grace() {
+                continue;
+            }
+            tokens.push(info);
         }
 
         Ok(tokens)
@@ -546,6 +635,64 @@ impl DatabaseManager {
         Ok(updated)
     }
 
+    ///
+    /// Bearerトークンの任意名と path prefix 制約を更新する
+    ///
+    /// # 引数
+    /// * `token_id` - 更新対象の BearerトークンID
+    /// * `name` - 更新後の任意名(`None` の場合は変更しない)
+    /// * `path_prefixes` - 更新後の path prefix 制約集合(`None` の場合は
+    ///   変更しない)
+    ///
+    /// # 戻り値
+    /// 更新後の Bearerトークン管理情報を返す。
+    ///
+    /// # 注記
+    /// 失効済みトークンは更新できない。
+    ///
+    pub(crate) fn edit_bearer_token(
+        &self,
+        token_id: &TokenId,
+        name: Option<Option<String>>,
+        path_prefixes: Option<PathPrefixSet>,
+    ) -> Result<BearerTokenInfo> {
+        /*
+         * BearerトークンIDから照合用ハッシュ値を解決する
+         */
+        let token_hash = self
+            .get_bearer_token_hash_by_id(token_id)?
+            .ok_or_else(|| anyhow!("token not found: {}", token_id))?;
+
+        /*
+         * 指定された項目のみ更新する
+         */
+        let txn = self.db.begin_write()?;
+        let updated = {
+            let mut token_table = txn.open_table(BEARER_TOKEN_TABLE)?;
+            let mut info = token_table
+                .get(token_hash)?
+                .ok_or_else(|| anyhow!("token not found: {}", token_id))?
+                .value();
+            if info.revoked() {
+                return Err(anyhow!("token already revoked: {}", token_id));
+            }
+
+            let now = Local::now();
+            if let Some(name) = name {
+                info.set_name(name, now);
+            }
+            if let Some(path_prefixes) = path_prefixes {
+                info.set_path_prefixes(path_prefixes, now);
+            }
+
+            token_table.insert(token_hash, info.clone())?;
+            info
+        };
+
+        txn.commit()?;
+        Ok(updated)
+    }
+
     ///
     /// Bearerトークンを単体指定で失効する
     ///
@@ -655,6 +802,10 @@ impl DatabaseManager {
                 let (token_hash, info) = entry?;
                 let mut info = info.value();
 
+                if info.is_rotation_grace() {
+                    continue;
+                }
+
                 if let Some(user_id) = user_id {
                     if info.user_id() != *user_id {
                         continue;
@@ -714,6 +865,18 @@ impl DatabaseManager {
                 .remove(token_hash)?
                 .ok_or_else(|| anyhow!("token not found: {}", token_id))?;
             let _ = token_id_table.remove(token_id.clone())?;
+
+            let mut graces = Vec::new();
+            for entry in token_table.iter()? {
+                let (grace_hash, info) = entry?;
+                if info.value().token_id() == *token_id {
+                    graces.push(grace_hash.value());
+                }
+            }
+
+            for grace_hash in graces {
+                let _ = token_table.remove(grace_hash)?;
+            }
         }
 
         txn.commit()?;
@@ -746,11 +909,17 @@ impl DatabaseManager {
             let mut token_table = txn.open_table(BEARER_TOKEN_TABLE)?;
             let mut token_id_table = txn.open_table(BEARER_TOKEN_ID_TABLE)?;
             let mut targets = Vec::new();
+            let mut graces = Vec::new();
 
             for entry in token_table.iter()? {
                 let (token_hash, info) = entry?;
                 let info = info.value();
 
+                if info.is_rotation_grace() {
+                    graces.push((token_hash.value(), info));
+                    continue;
+                }
+
                 if !bearer_token_matches_filters(
                     &info,
                     None,
@@ -769,6 +938,19 @@ impl DatabaseManager {
                 let _ = token_id_table.remove(token_id.clone())?;
             }
 
+            /*
+             * 削除対象トークンの猶予エントリと猶予切れエントリを掃除する
+             */
+            for (grace_hash, info) in graces {
+                let purged = targets
+                    .iter()
+                    .any(|(_, token_id)| *token_id == info.token_id());
+                let lapsed = info.grace_until().is_some_and(|until| until <= now);
+                if purged || lapsed {
+                    let _ = token_table.remove(grace_hash)?;
+                }
+            }
+
             targets.len()
         };
 
@@ -776,6 +958,121 @@ impl DatabaseManager {
         Ok(deleted_count)
     }
 
+    ///
+    /// Bearerトークンをローテーションする
+    ///
+    /// # 引数
+    /// * `token_id` - ローテーション対象の BearerトークンID
+    /// * `grace` - 旧平文を引き続き受け付ける猶予期間
+    /// * `now` - ローテーション時刻
+    ///
+    /// # 戻り値
+    /// 新たに発行した Bearerトークン平文を含むローテーション結果を返す。
+    ///
+    /// # 注記
+    /// 同じトークンIDのまま照合用ハッシュ値を差し替え、旧ハッシュ値は
+    /// 猶予期限を設定した猶予エントリとして主テーブルへ残す。猶予期間が
+    /// 0 の場合は旧平文を即座に無効化する。失効済み・期限切れのトークンは
+    /// ローテーションできない。
+    ///
+    pub(crate) fn rotate_bearer_token(
+        &self,
+        token_id: &TokenId,
+        grace: Duration,
+        now: DateTime<Local>,
+    ) -> Result<RotateBearerTokenResult> {
+        /*
+         * 書き込みトランザクション開始
+         */
+        let txn = self.db.begin_write()?;
+
+        let rotated = {
+            let mut token_table = txn.open_table(BEARER_TOKEN_TABLE)?;
+            let mut token_id_table = txn.open_table(BEARER_TOKEN_ID_TABLE)?;
+
+            /*
+             * 現行の管理情報を取得して状態を検証する
+             */
+            let old_hash = token_id_table
+                .get(token_id.clone())?
+                .ok_or_else(|| anyhow!("token not found: {}", token_id))?
+                .value();
+            let mut info = token_table
+                .get(old_hash)?
+                .ok_or_else(|| anyhow!("token not found: {}", token_id))?
+                .value();
+            if info.revoked() {
+                return Err(anyhow!("token already revoked: {}", token_id));
+            }
+            if info.expire_at() <= now {
+                return Err(anyhow!("token already expired: {}", token_id));
+            }
+
+            /*
+             * 同一トークンの猶予切れエントリを掃除する
+             */
+            let mut lapsed = Vec::new();
+            for entry in token_table.iter()? {
+                let (grace_hash, grace) = entry?;
+                let grace = grace.value();
+                if grace.token_id() == *token_id
+                    && grace.grace_until().is_some_and(|until| until <= now)
+                {
+                    lapsed.push(grace_hash.value());
+                }
+            }
+
+            for grace_hash in lapsed {
+                let _ = token_table.remove(grace_hash)?;
+            }
+
+            /*
+             * 新しい平文を生成して照合用ハッシュ値を差し替える
+             */
+            let (plaintext, new_hash) = loop {
+                let plaintext = self.generate_bearer_token_plaintext();
+                let token_hash =
+                    Self::calculate_bearer_token_hash(&plaintext);
+
+                if token_table.get(token_hash)?.is_none() {
+                    break (plaintext, token_hash);
+                }
+            };
+
+            info.rotate(now);
+            token_table.insert(new_hash, info.clone())?;
+            token_id_table.insert(token_id.clone(), new_hash)?;
+
+            /*
+             * 旧ハッシュ値を猶予エントリへ置き換える
+             */
+            let grace_until = if grace > Duration::zero() {
+                let grace_until = now + grace;
+                token_table
+                    .insert(old_hash, info.to_rotation_grace(grace_until))?;
+                Some(grace_until)
+            } else {
+                let _ = token_table.remove(old_hash)?;
+                None
+            };
+
+            RotateBearerTokenResult {
+                plaintext,
+                token_info: info,
+                previous_hash: old_hash,
+                current_hash: new_hash,
+                grace_until,
+            }
+        };
+
+        /*
+         * コミット
+         */
+        txn.commit()?;
+
+        Ok(rotated)
+    }
+
     ///
     /// Bearerトークン平文を照合して認証対象ユーザを解決する
     ///
@@ -805,13 +1102,43 @@ impl DatabaseManager {
          */
         let txn = self.db.begin_read()?;
         let token_table = txn.open_table(BEARER_TOKEN_TABLE)?;
-        let token_info = match token_table.get(token_hash)? {
+        let mut token_info = match token_table.get(token_hash)? {
             Some(entry) => entry.value(),
             None => {
                 return Ok(Err(VerifyBearerTokenFailureReason::Unissued));
             }
         };
 
+        /*
+         * ローテーション前の旧平文は猶予期限内に限り現行の管理情報で
+         * 検証する
+         */
+        if let Some(grace_until) = token_info.grace_until() {
+            if grace_until <= Local::now() {
+                return Ok(Err(VerifyBearerTokenFailureReason::Expired(
+                    token_info.token_id(),
+                )));
+            }
+
+            let token_id_table = txn.open_table(BEARER_TOKEN_ID_TABLE)?;
+            let current_hash =
+                match token_id_table.get(token_info.token_id())? {
+                    Some(entry) => entry.value(),
+                    None => {
+                        return Ok(Err(
+                            VerifyBearerTokenFailureReason::Unissued,
+                        ));
+                    }
+                };
+
+            token_info = match token_table.get(current_hash)? {
+                Some(entry) => entry.value(),
+                None => {
+                    return Ok(Err(VerifyBearerTokenFailureReason::Unissued));
+                }
+            };
+        }
+
         if token_info.revoked() {
             return Ok(Err(VerifyBearerTokenFailureReason::Revoked(
                 token_info.token_id(),
@@ -837,6 +1164,12 @@ impl DatabaseManager {
             }
         };
 
+        if user_info.is_disabled() {
+            return Ok(Err(VerifyBearerTokenFailureReason::UserDisabled(
+                token_info.token_id(),
+            )));
+        }
+
         Ok(Ok(VerifyBearerTokenResult::new(token_info, user_info)))
     }
 
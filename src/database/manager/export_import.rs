@@ -8,28 +8,37 @@
 //! export/import 用の低水準 DB API を提供するモジュール
 //!
 
-use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::{Result, anyhow};
-use chrono::Local;
-use redb::{ReadableDatabase, ReadableTable, Table};
+use chrono::{DateTime, Local};
+use redb::{MultimapTable, ReadableDatabase, ReadableTable, Table};
 
 use super::DatabaseManager;
+use crate::asset_image;
 use crate::database::schema::{
+    ASSET_BLOB_TABLE,
     ASSET_GROUP_TABLE,
     ASSET_INFO_TABLE,
     ASSET_LOOKUP_TABLE,
+    ASSET_REVISION_TABLE,
     DELETED_PAGE_PATH_TABLE,
+    EXPORT_HISTORY_TABLE,
     LOCK_INFO_TABLE,
     PAGE_INDEX_TABLE,
     PAGE_PATH_TABLE,
     PAGE_SOURCE_TABLE,
+    SHARED_ASSET_LOOKUP_TABLE,
     USER_ID_TABLE,
     USER_INFO_TABLE,
 };
+use crate::database::asset_refs::{
+    remove_asset_refs_by_page_ids_in_txn,
+    sync_asset_refs_for_source_in_txn,
+};
 use crate::database::primitive_names::{
     remove_mcp_primitive_names_by_page_ids_in_txn,
     sync_mcp_primitive_name_for_source_in_txn,
@@ -38,10 +47,20 @@ use crate::database::resource_uris::{
     remove_resource_uris_by_page_ids_in_txn,
     sync_resource_uri_for_source_in_txn,
 };
-use crate::database::txn_helpers::{delete_draft_in_txn, delete_page_hard_in_txn};
-use crate::database::types::{AssetId, AssetInfo, Id, PageId, PageIndex, PageSource, RenameInfo, UserInfo};
+use crate::database::txn_helpers::{
+    acquire_asset_blob_in_txn,
+    delete_draft_in_txn,
+    delete_page_hard_in_txn,
+    delete_page_soft_in_txn,
+};
+use crate::database::types::{AssetHash, AssetId, AssetInfo, AssetRevisionInfo, ExportHistoryInfo, ExportHistoryKind, Id, PageId, PageIndex, PageSource, RenameInfo, UserInfo};
 use crate::export_import::MigrateExportPageSnapshot;
-use crate::export_import::model::{ExportBundle, ExportRevision, ExportRevisionRename};
+use crate::export_import::model::{
+    ExportBundle,
+    ExportRevision,
+    ExportRevisionRename,
+    ExportType,
+};
 use crate::markdown_source::front_matter::validate_document_front_matter;
 
 ///
@@ -71,6 +90,26 @@ pub(crate) struct ExportRevisionReadRecord {
 pub(crate) struct ExportAssetReadRecord {
     pub(crate) asset: AssetInfo,
     pub(crate) data: Vec<u8>,
+    pub(crate) history: Vec<ExportAssetRevisionReadRecord>,
+}
+
+///
+/// export 用のアセット過去リビジョン収集結果
+///
+#[derive(Clone, Debug)]
+pub(crate) struct ExportAssetRevisionReadRecord {
+    pub(crate) revision: AssetRevisionInfo,
+    pub(crate) data: Vec<u8>,
+}
+
+///
+/// 差分 export 用の存在ページ収集結果
+///
+#[derive(Clone, Debug)]
+pub(crate) struct ExportLiveReadRecord {
+    pub(crate) page_id: PageId,
+    pub(crate) path: String,
+    pub(crate) assets: Vec<(AssetId, String)>,
 }
 
 ///
@@ -83,6 +122,41 @@ pub(crate) struct ExportReadSet {
     pub(crate) users: Vec<UserInfo>,
     pub(crate) assets: Vec<ExportAssetReadRecord>,
     pub(crate) draft_page_ids: Vec<PageId>,
+    pub(crate) live: Vec<ExportLiveReadRecord>,
+    pub(crate) snapshot: DateTime<Local>,
+}
+
+///
+/// 最新ページ出力用のページ収集結果
+///
+#[derive(Clone, Debug)]
+pub(crate) struct LatestPageReadRecord {
+    pub(crate) page_id: PageId,
+    pub(crate) path: String,
+    pub(crate) source: String,
+    pub(crate) timestamp: DateTime<Local>,
+}
+
+///
+/// 最新ページ出力用のアセット収集結果
+///
+#[derive(Clone, Debug)]
+pub(crate) struct LatestAssetReadRecord {
+    pub(crate) asset_id: AssetId,
+    /// 所属ページID(共有アセットの場合は`None`)
+    pub(crate) page_id: Option<PageId>,
+    pub(crate) file_name: String,
+    pub(crate) mime: String,
+    pub(crate) blob_path: PathBuf,
+}
+
+///
+/// 最新ページ出力用の低水準読取結果
+///
+#[derive(Clone, Debug)]
+pub(crate) struct LatestPageReadSet {
+    pub(crate) pages: Vec<LatestPageReadRecord>,
+    pub(crate) assets: Vec<LatestAssetReadRecord>,
 }
 
 impl DatabaseManager {
@@ -96,25 +170,39 @@ impl DatabaseManager {
     /// # 引数
     /// * `base_path` - 起点パス
     /// * `collect_drafts` - ドラフト削除対象も収集する場合は true
+    /// * `since` - 差分収集の起点日時(全件収集の場合は`None`)
     ///
     /// # 戻り値
     /// 収集結果を返す。
     ///
+    /// # 注記
+    /// `since` を指定した場合、ページはその日時より後のリビジョンを持つもの
+    /// のみを、リビジョンとアセット履歴はその日時より後に登録されたものの
+    /// みを収集する。併せて削除・移動の反映用に存在ページとその所属アセッ
+    /// トの配置を収集する。
+    ///
     pub(crate) fn collect_export_read_set(
         &self,
         base_path: &str,
         collect_drafts: bool,
+        since: Option<DateTime<Local>>,
     ) -> Result<ExportReadSet> {
+        /*
+         * 次回の差分起点とするため、読取開始前の日時を記録する
+         */
+        let snapshot = Local::now();
         let txn = self.db.begin_read()?;
         let index_table = txn.open_table(PAGE_INDEX_TABLE)?;
         let source_table = txn.open_table(PAGE_SOURCE_TABLE)?;
         let user_table = txn.open_table(USER_INFO_TABLE)?;
         let group_table = txn.open_multimap_table(ASSET_GROUP_TABLE)?;
         let asset_table = txn.open_table(ASSET_INFO_TABLE)?;
+        let asset_revision_table = txn.open_table(ASSET_REVISION_TABLE)?;
         let mut pages = Vec::new();
         let mut revisions = Vec::new();
         let mut assets = Vec::new();
         let mut draft_page_ids = Vec::new();
+        let mut live = Vec::new();
         let mut user_ids = HashSet::new();
 
         for entry in index_table.iter()? {
@@ -141,26 +229,44 @@ impl DatabaseManager {
                 continue;
             }
 
-            pages.push(ExportPageReadRecord {
-                page_id: page_id.clone(),
-                path,
-                index: index.clone(),
-            });
-
+            /*
+             * リビジョンの収集(差分収集時は起点日時より後のもののみ)
+             */
+            let mut page_revisions = Vec::new();
+            let live_path = path.clone();
             for source_entry in source_table
                 .range((page_id.clone(), 0u64)..=(page_id.clone(), u64::MAX))?
+                .rev()
             {
                 let (key, source) = source_entry?;
                 let (source_page_id, revision) = key.value();
                 let source = source.value();
-                user_ids.insert(source.user());
-                revisions.push(ExportRevisionReadRecord {
+                if since.is_some_and(|since| source.timestamp() <= since) {
+                    break;
+                }
+                page_revisions.push(ExportRevisionReadRecord {
                     page_id: source_page_id,
                     revision,
                     source,
                 });
             }
 
+            if !page_revisions.is_empty() {
+                pages.push(ExportPageReadRecord {
+                    page_id: page_id.clone(),
+                    path,
+                    index: index.clone(),
+                });
+                for record in page_revisions.into_iter().rev() {
+                    user_ids.insert(record.source.user());
+                    revisions.push(record);
+                }
+            }
+
+            /*
+             * アセットの収集(差分収集時は起点日時より後の登録を含むもののみ)
+             */
+            let mut live_assets = Vec::new();
             for asset_entry in group_table.get(page_id.clone())? {
                 let asset_id = asset_entry?.value();
                 let asset_info = match asset_table.get(asset_id.clone())? {
@@ -172,19 +278,58 @@ impl DatabaseManager {
                     continue;
                 }
 
+                live_assets.push((asset_id.clone(), asset_info.file_name()));
+                if since.is_some_and(|since| asset_info.timestamp() <= since) {
+                    continue;
+                }
+
+                let mut history = Vec::new();
+                for revision_entry in asset_revision_table.range(
+                    (asset_id.clone(), 0u64)..=(asset_id.clone(), u64::MAX),
+                )? {
+                    let (_, revision) = revision_entry?;
+                    let revision = revision.value();
+                    if since.is_some_and(|since| revision.timestamp() <= since)
+                    {
+                        continue;
+                    }
+                    let hash = revision.hash().ok_or_else(|| {
+                        anyhow!("asset blob not found: {}", asset_id)
+                    })?;
+                    user_ids.insert(revision.user());
+                    history.push(ExportAssetRevisionReadRecord {
+                        data: fs::read(self.asset_blob_path(&hash))?,
+                        revision,
+                    });
+                }
+
                 user_ids.insert(asset_info.user());
                 assets.push(ExportAssetReadRecord {
                     data: self.read_asset_data(&asset_id)?,
                     asset: asset_info,
+                    history,
+                });
+            }
+
+            if since.is_some() {
+                live.push(ExportLiveReadRecord {
+                    page_id,
+                    path: live_path,
+                    assets: live_assets,
                 });
             }
         }
 
+        /*
+         * ユーザの収集(差分収集時は起点日時より後に更新されたものも含める)
+         */
         let mut users = Vec::new();
         for entry in user_table.iter()? {
             let (_, info) = entry?;
             let info = info.value();
-            if user_ids.contains(&info.id()) {
+            if user_ids.contains(&info.id())
+                || since.is_some_and(|since| info.timestamp() > since)
+            {
                 users.push(info);
             }
         }
@@ -195,14 +340,120 @@ impl DatabaseManager {
             users,
             assets,
             draft_page_ids,
+            live,
+            snapshot,
         })
     }
 
+    ///
+    /// 最新ページ出力(静的サイト/Markdown ツリー)用の低水準読取
+    ///
+    /// # 概要
+    /// 単一 read transaction 内で対象ページの最新ソースと、その所属アセット
+    /// および共有アセットの配置情報をまとめて収集する。
+    ///
+    /// # 引数
+    /// * `base_path` - 起点パス
+    ///
+    /// # 戻り値
+    /// 収集結果を返す。ページはパス順に並ぶ。
+    ///
+    /// # 注記
+    /// ドラフトページ、削除済みページ、削除済みアセットは収集しない。アセッ
+    /// トの実体はコピー時に読み出すため、blob のパスのみを返す。
+    ///
+    pub(crate) fn collect_latest_page_read_set(
+        &self,
+        base_path: &str,
+    ) -> Result<LatestPageReadSet> {
+        let txn = self.db.begin_read()?;
+        let index_table = txn.open_table(PAGE_INDEX_TABLE)?;
+        let source_table = txn.open_table(PAGE_SOURCE_TABLE)?;
+        let group_table = txn.open_multimap_table(ASSET_GROUP_TABLE)?;
+        let asset_table = txn.open_table(ASSET_INFO_TABLE)?;
+        let shared_table = txn.open_table(SHARED_ASSET_LOOKUP_TABLE)?;
+        let mut pages = Vec::new();
+        let mut asset_ids = Vec::new();
+
+        /*
+         * ページ最新ソースの収集
+         */
+        for entry in index_table.iter()? {
+            let (page_id, index) = entry?;
+            let page_id = page_id.value().clone();
+            let index = index.value();
+            let path = match index.current_path() {
+                Some(path) => path.to_string(),
+                None => continue,
+            };
+
+            if !is_target_path(base_path, &path)
+                || index.is_draft()
+                || index.deleted()
+            {
+                continue;
+            }
+
+            let source = source_table
+                .get((page_id.clone(), index.latest()))?
+                .ok_or_else(|| anyhow!("page source not found: {}", page_id))?
+                .value();
+
+            for asset_entry in group_table.get(page_id.clone())? {
+                asset_ids.push(asset_entry?.value());
+            }
+
+            pages.push(LatestPageReadRecord {
+                page_id,
+                path,
+                source: source.source(),
+                timestamp: source.timestamp(),
+            });
+        }
+
+        for entry in shared_table.iter()? {
+            let (_, asset_id) = entry?;
+            asset_ids.push(asset_id.value());
+        }
+
+        /*
+         * アセット配置情報の収集
+         */
+        let mut assets = Vec::new();
+        for asset_id in asset_ids {
+            let asset_info = match asset_table.get(asset_id.clone())? {
+                Some(info) => info.value(),
+                None => continue,
+            };
+
+            if asset_info.deleted() || asset_info.is_zombie() {
+                continue;
+            }
+
+            let hash = asset_info
+                .hash()
+                .ok_or_else(|| anyhow!("asset blob not found: {}", asset_id))?;
+            assets.push(LatestAssetReadRecord {
+                asset_id,
+                page_id: asset_info.page_id(),
+                file_name: asset_info.file_name(),
+                mime: asset_info.mime(),
+                blob_path: self.asset_blob_path(&hash),
+            });
+        }
+
+        pages.sort_by(|left, right| left.path.cmp(&right.path));
+
+        Ok(LatestPageReadSet { pages, assets })
+    }
+
     ///
     /// import 用の低水準 DB 投入
     ///
     /// # 概要
     /// 検証済み bundle を変換せずに DB 永続化型へ写像して一括反映する。
+    /// backup アーカイブの場合は、後続の差分 import の起点としてエクスポート
+    /// 履歴へ取り込みを記録する。
     ///
     /// # 引数
     /// * `bundle` - 反映対象 bundle
@@ -222,6 +473,23 @@ impl DatabaseManager {
         }
 
         let revision_map = build_revision_map(&bundle.revisions);
+        let blob_hashes: HashMap<(AssetId, Option<u64>), AssetHash> = bundle
+            .asset_blobs
+            .iter()
+            .map(|blob| {
+                (
+                    (blob.asset_id.clone(), blob.revision),
+                    AssetHash::from_data(&blob.data),
+                )
+            })
+            .collect();
+        let blob_data: HashMap<(AssetId, Option<u64>), &[u8]> = bundle
+            .asset_blobs
+            .iter()
+            .map(|blob| {
+                ((blob.asset_id.clone(), blob.revision), blob.data.as_slice())
+            })
+            .collect();
         let txn = self.db.begin_write()?;
 
         {
@@ -233,6 +501,9 @@ impl DatabaseManager {
             let mut asset_info_table = txn.open_table(ASSET_INFO_TABLE)?;
             let mut asset_lookup_table = txn.open_table(ASSET_LOOKUP_TABLE)?;
             let mut asset_group_table = txn.open_multimap_table(ASSET_GROUP_TABLE)?;
+            let mut asset_blob_table = txn.open_table(ASSET_BLOB_TABLE)?;
+            let mut asset_revision_table =
+                txn.open_table(ASSET_REVISION_TABLE)?;
 
             for user in &bundle.users {
                 let user_info = UserInfo::new_import(
@@ -285,17 +556,69 @@ impl DatabaseManager {
             }
 
             for asset in &bundle.assets {
-                let asset_info = AssetInfo::new_import(
+                for revision in &asset.history {
+                    let hash = blob_hashes
+                        .get(&(asset.id.clone(), Some(revision.revision)))
+                        .copied();
+                    if let Some(hash) = hash {
+                        acquire_asset_blob_in_txn(
+                            &mut asset_blob_table,
+                            &hash,
+                            revision.size,
+                        )?;
+                    }
+
+                    let mut revision_info = AssetRevisionInfo::new(
+                        revision.revision,
+                        revision.mime.clone(),
+                        revision.size,
+                        hash,
+                        revision.user.clone(),
+                        revision.timestamp,
+                    );
+                    revision_info.set_dimensions(
+                        blob_data
+                            .get(&(asset.id.clone(), Some(revision.revision)))
+                            .and_then(|data| {
+                                asset_image::probe_dimensions(
+                                    &revision.mime,
+                                    data,
+                                )
+                            }),
+                    );
+                    asset_revision_table.insert(
+                        (asset.id.clone(), revision.revision),
+                        revision_info,
+                    )?;
+                }
+
+                let hash = blob_hashes.get(&(asset.id.clone(), None)).copied();
+                if let Some(hash) = hash {
+                    acquire_asset_blob_in_txn(
+                        &mut asset_blob_table,
+                        &hash,
+                        asset.size,
+                    )?;
+                }
+
+                let mut asset_info = AssetInfo::new_import(
                     asset.id.clone(),
                     Some(Id::new()),
                     Some(asset.page.clone()),
                     asset.file_name.clone(),
                     asset.mime.clone(),
                     asset.size,
+                    hash,
                     asset.user.clone(),
                     asset.timestamp,
                     false,
                 );
+                asset_info.set_revision(asset.revision);
+                asset_info.set_dimensions(
+                    blob_data.get(&(asset.id.clone(), None)).and_then(|data| {
+                        asset_image::probe_dimensions(&asset.mime, data)
+                    }),
+                );
                 asset_info_table.insert(asset.id.clone(), asset_info)?;
                 asset_lookup_table.insert(
                     (asset.page.clone(), asset.file_name.clone()),
@@ -327,6 +650,17 @@ impl DatabaseManager {
                     &final_path,
                     &latest.source,
                 )?;
+                sync_asset_refs_for_source_in_txn(
+                    &txn,
+                    &page.id,
+                    &final_path,
+                    &latest.source,
+                )?;
+            }
+
+            if bundle.manifest.export_type == ExportType::Backup {
+                let mut history_table = txn.open_table(EXPORT_HISTORY_TABLE)?;
+                record_imported_export_in_txn(&mut history_table, bundle)?;
             }
         }
 
@@ -338,6 +672,572 @@ impl DatabaseManager {
         Ok(())
     }
 
+    ///
+    /// 差分 import 用の低水準 DB 反映
+    ///
+    /// # 概要
+    /// 検証済みの差分 bundle を既存データへ重ねて反映する。bundle に含まれる
+    /// ユーザ、ページ、リビジョン、アセットは追加または更新し、`live` に含ま
+    /// れないページとアセットは論理削除する。リビジョンを伴わないページの復帰
+    /// やアセットの移動も `live` の配置に合わせて反映する。
+    ///
+    /// # 引数
+    /// * `bundle` - 反映対象 bundle
+    ///
+    /// # 戻り値
+    /// 反映に成功した場合は `Ok(())` を返す。
+    ///
+    pub(crate) fn apply_incremental_import_bundle(
+        &self,
+        bundle: &ExportBundle,
+    ) -> Result<()> {
+        /*
+         * import 対象リビジョンの front matter 検証
+         */
+        for revision in &bundle.revisions {
+            validate_document_front_matter(&revision.source)?;
+        }
+
+        let export_root = bundle.manifest.export_root.as_str();
+        let revision_map = build_revision_map(&bundle.revisions);
+        let blob_hashes: HashMap<(AssetId, Option<u64>), AssetHash> = bundle
+            .asset_blobs
+            .iter()
+            .map(|blob| {
+                (
+                    (blob.asset_id.clone(), blob.revision),
+                    AssetHash::from_data(&blob.data),
+                )
+            })
+            .collect();
+        let blob_data: HashMap<(AssetId, Option<u64>), &[u8]> = bundle
+            .asset_blobs
+            .iter()
+            .map(|blob| {
+                ((blob.asset_id.clone(), blob.revision), blob.data.as_slice())
+            })
+            .collect();
+        let live_pages: HashMap<PageId, String> = bundle
+            .live
+            .iter()
+            .map(|page| {
+                (page.id.clone(), rebuild_absolute_path(export_root, &page.path))
+            })
+            .collect();
+        let live_assets: HashMap<AssetId, (PageId, String)> = bundle
+            .live
+            .iter()
+            .flat_map(|page| {
+                page.assets.iter().map(move |asset| {
+                    (
+                        asset.id.clone(),
+                        (page.id.clone(), asset.file_name.clone()),
+                    )
+                })
+            })
+            .collect();
+        let bundle_page_ids: HashSet<PageId> =
+            bundle.pages.iter().map(|page| page.id.clone()).collect();
+        let mut deleted_page_ids = Vec::new();
+        let mut synced_page_ids: Vec<PageId> =
+            bundle.pages.iter().map(|page| page.id.clone()).collect();
+        let txn = self.db.begin_write()?;
+
+        {
+            let mut user_id_table = txn.open_table(USER_ID_TABLE)?;
+            let mut user_info_table = txn.open_table(USER_INFO_TABLE)?;
+            let mut page_path_table = txn.open_table(PAGE_PATH_TABLE)?;
+            let mut deleted_path_table =
+                txn.open_multimap_table(DELETED_PAGE_PATH_TABLE)?;
+            let mut page_index_table = txn.open_table(PAGE_INDEX_TABLE)?;
+            let mut page_source_table = txn.open_table(PAGE_SOURCE_TABLE)?;
+            let mut lock_table = txn.open_table(LOCK_INFO_TABLE)?;
+            let mut asset_info_table = txn.open_table(ASSET_INFO_TABLE)?;
+            let mut asset_lookup_table = txn.open_table(ASSET_LOOKUP_TABLE)?;
+            let mut asset_group_table =
+                txn.open_multimap_table(ASSET_GROUP_TABLE)?;
+            let mut asset_blob_table = txn.open_table(ASSET_BLOB_TABLE)?;
+            let mut asset_revision_table =
+                txn.open_table(ASSET_REVISION_TABLE)?;
+            let mut history_table = txn.open_table(EXPORT_HISTORY_TABLE)?;
+
+            /*
+             * live に含まれないページの論理削除
+             */
+            for entry in page_index_table.iter()? {
+                let (page_id, index) = entry?;
+                let page_id = page_id.value().clone();
+                let index = index.value();
+                if index.is_draft() || index.deleted() {
+                    continue;
+                }
+                let Some(path) = index.current_path() else {
+                    continue;
+                };
+                if is_target_path(export_root, path)
+                    && !live_pages.contains_key(&page_id)
+                {
+                    deleted_page_ids.push(page_id);
+                }
+            }
+
+            for page_id in &deleted_page_ids {
+                delete_page_soft_in_txn(
+                    page_id,
+                    &mut page_path_table,
+                    &mut deleted_path_table,
+                    &mut page_index_table,
+                    &mut lock_table,
+                    &mut asset_info_table,
+                    &mut asset_lookup_table,
+                    &asset_group_table,
+                )?;
+            }
+
+            /*
+             * live に含まれないアセットの論理削除と配置の付け替え
+             */
+            let mut asset_updates = Vec::new();
+            for entry in asset_info_table.iter()? {
+                let (asset_id, info) = entry?;
+                let asset_id = asset_id.value().clone();
+                let info = info.value();
+                if info.is_shared() {
+                    continue;
+                }
+
+                match live_assets.get(&asset_id) {
+                    Some((page_id, file_name)) => {
+                        if info.deleted()
+                            || info.page_id().as_ref() != Some(page_id)
+                            || info.file_name() != *file_name
+                        {
+                            asset_updates.push((
+                                asset_id,
+                                info,
+                                Some((page_id.clone(), file_name.clone())),
+                            ));
+                        }
+                    }
+                    None => {
+                        let in_live_page = info
+                            .page_id()
+                            .is_some_and(|page_id| live_pages.contains_key(&page_id));
+                        if !info.deleted() && in_live_page {
+                            asset_updates.push((asset_id, info, None));
+                        }
+                    }
+                }
+            }
+
+            for (_, info, _) in &asset_updates {
+                if info.deleted() {
+                    continue;
+                }
+                if let Some(page_id) = info.page_id() {
+                    let _ = asset_lookup_table
+                        .remove((page_id, info.file_name()))?;
+                }
+            }
+
+            for (asset_id, mut info, placement) in asset_updates {
+                match placement {
+                    Some((page_id, file_name)) => {
+                        let old_page_id = info
+                            .page_id()
+                            .filter(|old_page_id| *old_page_id != page_id);
+                        if let Some(old_page_id) = old_page_id {
+                            let _ = asset_group_table
+                                .remove(old_page_id, asset_id.clone())?;
+                        }
+                        info.set_page_id(page_id.clone());
+                        info.set_file_name(file_name.clone());
+                        info.set_deleted(false);
+                        asset_info_table.insert(asset_id.clone(), info)?;
+                        asset_lookup_table
+                            .insert((page_id.clone(), file_name), asset_id.clone())?;
+                        let _ = asset_group_table.insert(page_id, asset_id)?;
+                    }
+                    None => {
+                        info.set_deleted(true);
+                        asset_info_table.insert(asset_id, info)?;
+                    }
+                }
+            }
+
+            /*
+             * ユーザの追加・更新
+             */
+            for user in &bundle.users {
+                let existing = user_info_table
+                    .get(user.id.clone())?
+                    .map(|entry| entry.value());
+                let renamed = existing
+                    .filter(|existing| existing.username() != user.username);
+                if let Some(existing) = renamed {
+                    let _ = user_id_table.remove(existing.username())?;
+                }
+
+                let user_info = UserInfo::new_import(
+                    user.id.clone(),
+                    user.username.clone(),
+                    user.password.clone(),
+                    user.salt,
+                    user.display_name.clone(),
+                    user.attributes.clone(),
+                    Local::now(),
+                );
+                user_id_table.insert(user.username.clone(), user.id.clone())?;
+                user_info_table.insert(user.id.clone(), user_info)?;
+            }
+
+            /*
+             * 更新対象ページの旧パス解除
+             */
+            let mut restored_pages = Vec::new();
+            for page in &bundle.pages {
+                let existing = page_index_table
+                    .get(page.id.clone())?
+                    .map(|entry| entry.value());
+                let Some(index) = existing else {
+                    continue;
+                };
+
+                release_page_paths(
+                    &page.id,
+                    &index,
+                    &mut page_path_table,
+                    &mut deleted_path_table,
+                    &mut lock_table,
+                )?;
+                for revision in index.earliest()..page.earliest {
+                    let _ = page_source_table.remove((page.id.clone(), revision))?;
+                }
+            }
+
+            for (page_id, path) in &live_pages {
+                if bundle_page_ids.contains(page_id) {
+                    continue;
+                }
+
+                let mut index = page_index_table
+                    .get(page_id.clone())?
+                    .map(|entry| entry.value())
+                    .ok_or_else(|| {
+                        anyhow!("incremental import base page not found: {}", page_id)
+                    })?;
+                if !index.deleted() && index.current_path() == Some(path.as_str()) {
+                    continue;
+                }
+
+                release_page_paths(
+                    page_id,
+                    &index,
+                    &mut page_path_table,
+                    &mut deleted_path_table,
+                    &mut lock_table,
+                )?;
+                index.set_path(path.clone());
+                restored_pages.push((page_id.clone(), path.clone(), index));
+            }
+
+            /*
+             * ページとリビジョンの追加・更新
+             */
+            for page in &bundle.pages {
+                let final_path = rebuild_absolute_path(export_root, &page.path);
+                ensure_page_path_available(&page_path_table, &final_path, &page.id)?;
+
+                let page_index = PageIndex::new_page_import(
+                    page.id.clone(),
+                    final_path.clone(),
+                    page.latest,
+                    page.earliest,
+                    page.rename_revisions.clone().unwrap_or_default(),
+                );
+                page_path_table.insert(final_path, page.id.clone())?;
+                page_index_table.insert(page.id.clone(), page_index)?;
+
+                if let Some(revisions) = revision_map.get(&page.id) {
+                    for revision in revisions {
+                        let page_source = PageSource::new_import(
+                            revision.revision,
+                            Some(Id::new()),
+                            revision.timestamp,
+                            revision.user.clone(),
+                            convert_export_rename(revision.rename.as_ref()),
+                            revision.source.clone(),
+                        );
+                        page_source_table.insert(
+                            (revision.page.clone(), revision.revision),
+                            page_source,
+                        )?;
+                    }
+                }
+            }
+
+            for (page_id, path, index) in restored_pages {
+                ensure_page_path_available(&page_path_table, &path, &page_id)?;
+                page_path_table.insert(path, page_id.clone())?;
+                page_index_table.insert(page_id.clone(), index)?;
+                synced_page_ids.push(page_id);
+            }
+
+            /*
+             * アセットの追加・更新
+             */
+            for asset in &bundle.assets {
+                let existing = asset_info_table
+                    .get(asset.id.clone())?
+                    .map(|entry| entry.value());
+
+                if let Some(info) = &existing {
+                    if info.revision() >= asset.revision.max(1) {
+                        continue;
+                    }
+
+                    /*
+                     * 現在の内容を過去リビジョンへ移す(実体の参照数は据え置き)
+                     */
+                    if info.hash().is_some()
+                        && asset_revision_table
+                            .get((asset.id.clone(), info.revision()))?
+                            .is_none()
+                    {
+                        asset_revision_table.insert(
+                            (asset.id.clone(), info.revision()),
+                            info.current_revision(),
+                        )?;
+                    }
+
+                    if let Some(page_id) = info.page_id() {
+                        if !info.deleted() {
+                            let _ = asset_lookup_table
+                                .remove((page_id.clone(), info.file_name()))?;
+                        }
+                        if page_id != asset.page {
+                            let _ = asset_group_table
+                                .remove(page_id, asset.id.clone())?;
+                        }
+                    }
+                }
+
+                for revision in &asset.history {
+                    if asset_revision_table
+                        .get((asset.id.clone(), revision.revision))?
+                        .is_some()
+                    {
+                        continue;
+                    }
+
+                    let hash = blob_hashes
+                        .get(&(asset.id.clone(), Some(revision.revision)))
+                        .copied();
+                    if let Some(hash) = hash {
+                        acquire_asset_blob_in_txn(
+                            &mut asset_blob_table,
+                            &hash,
+                            revision.size,
+                        )?;
+                    }
+
+                    let mut revision_info = AssetRevisionInfo::new(
+                        revision.revision,
+                        revision.mime.clone(),
+                        revision.size,
+                        hash,
+                        revision.user.clone(),
+                        revision.timestamp,
+                    );
+                    revision_info.set_dimensions(
+                        blob_data
+                            .get(&(asset.id.clone(), Some(revision.revision)))
+                            .and_then(|data| {
+                                asset_image::probe_dimensions(
+                                    &revision.mime,
+                                    data,
+                                )
+                            }),
+                    );
+                    asset_revision_table.insert(
+                        (asset.id.clone(), revision.revision),
+                        revision_info,
+                    )?;
+                }
+
+                let hash = blob_hashes.get(&(asset.id.clone(), None)).copied();
+                if let Some(hash) = hash {
+                    acquire_asset_blob_in_txn(
+                        &mut asset_blob_table,
+                        &hash,
+                        asset.size,
+                    )?;
+                }
+
+                let mut asset_info = AssetInfo::new_import(
+                    asset.id.clone(),
+                    Some(Id::new()),
+                    Some(asset.page.clone()),
+                    asset.file_name.clone(),
+                    asset.mime.clone(),
+                    asset.size,
+                    hash,
+                    asset.user.clone(),
+                    asset.timestamp,
+                    false,
+                );
+                asset_info.set_revision(asset.revision);
+                asset_info.set_dimensions(
+                    blob_data.get(&(asset.id.clone(), None)).and_then(|data| {
+                        asset_image::probe_dimensions(&asset.mime, data)
+                    }),
+                );
+                asset_info_table.insert(asset.id.clone(), asset_info)?;
+                asset_lookup_table.insert(
+                    (asset.page.clone(), asset.file_name.clone()),
+                    asset.id.clone(),
+                )?;
+                let _ = asset_group_table
+                    .insert(asset.page.clone(), asset.id.clone())?;
+            }
+
+            /*
+             * 派生索引の同期
+             */
+            for page_id in &synced_page_ids {
+                let Some(index) = page_index_table
+                    .get(page_id.clone())?
+                    .map(|entry| entry.value())
+                else {
+                    continue;
+                };
+                let Some(path) = index.current_path().map(str::to_string) else {
+                    continue;
+                };
+                let latest = page_source_table
+                    .get((page_id.clone(), index.latest()))?
+                    .map(|entry| entry.value())
+                    .ok_or_else(|| anyhow!("latest revision missing"))?;
+                sync_mcp_primitive_name_for_source_in_txn(
+                    &txn,
+                    page_id,
+                    &latest.source(),
+                )?;
+                sync_resource_uri_for_source_in_txn(
+                    &txn,
+                    page_id,
+                    &path,
+                    &latest.source(),
+                )?;
+                sync_asset_refs_for_source_in_txn(
+                    &txn,
+                    page_id,
+                    &path,
+                    &latest.source(),
+                )?;
+            }
+
+            record_imported_export_in_txn(&mut history_table, bundle)?;
+        }
+
+        txn.commit()?;
+
+        self.sync_resource_candidates_for_page_ids(&synced_page_ids)?;
+        self.sync_prompt_candidates_for_page_ids(&synced_page_ids)?;
+        self.remove_template_candidates_by_page_ids(&deleted_page_ids)?;
+        self.remove_prompt_candidates_by_page_ids(&deleted_page_ids)?;
+        self.remove_resource_candidates_by_page_ids(&deleted_page_ids)?;
+
+        Ok(())
+    }
+
+    ///
+    /// エクスポート履歴の記録
+    ///
+    /// # 引数
+    /// * `export_id` - エクスポートID
+    /// * `timestamp` - アーカイブが表すスナップショットの取得日時
+    /// * `base_export` - 差分アーカイブの起点となったエクスポートID
+    ///
+    /// # 戻り値
+    /// 成功時は `Ok(())` を返す。
+    ///
+    pub(crate) fn record_export_history(
+        &self,
+        export_id: &Id,
+        timestamp: DateTime<Local>,
+        base_export: Option<Id>,
+    ) -> Result<()> {
+        let txn = self.db.begin_write()?;
+
+        {
+            let mut table = txn.open_table(EXPORT_HISTORY_TABLE)?;
+            table.insert(
+                export_id.clone(),
+                ExportHistoryInfo::new(
+                    ExportHistoryKind::Exported,
+                    timestamp,
+                    base_export,
+                ),
+            )?;
+        }
+
+        txn.commit()?;
+
+        Ok(())
+    }
+
+    ///
+    /// エクスポート履歴の取得
+    ///
+    /// # 引数
+    /// * `export_id` - エクスポートID
+    ///
+    /// # 戻り値
+    /// 記録されている場合はエクスポート履歴情報を返す。
+    ///
+    pub(crate) fn get_export_history(
+        &self,
+        export_id: &Id,
+    ) -> Result<Option<ExportHistoryInfo>> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(EXPORT_HISTORY_TABLE)?;
+
+        Ok(table.get(export_id.clone())?.map(|entry| entry.value()))
+    }
+
+    ///
+    /// 最後に取り込んだ backup アーカイブの取得
+    ///
+    /// # 戻り値
+    /// 取り込み済みアーカイブのうちスナップショット日時が最も新しいものの
+    /// エクスポートIDと履歴情報を返す。取り込み履歴が無い場合は`None`を返す。
+    ///
+    pub(crate) fn latest_imported_export(
+        &self,
+    ) -> Result<Option<(Id, ExportHistoryInfo)>> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(EXPORT_HISTORY_TABLE)?;
+        let mut latest: Option<(Id, ExportHistoryInfo)> = None;
+
+        for entry in table.iter()? {
+            let (export_id, info) = entry?;
+            let info = info.value();
+            if info.kind() != ExportHistoryKind::Imported {
+                continue;
+            }
+            if latest
+                .as_ref()
+                .is_some_and(|(_, current)| current.timestamp() >= info.timestamp())
+            {
+                continue;
+            }
+            latest = Some((export_id.value().clone(), info));
+        }
+
+        Ok(latest)
+    }
+
     ///
     /// migrate export 用の削除連動
     ///
@@ -360,7 +1260,7 @@ impl DatabaseManager {
         lock_page_ids: &[PageId],
     ) -> Result<()> {
         let txn = self.db.begin_write()?;
-        let mut removed_asset_ids = Vec::new();
+        let mut released_blobs = Vec::new();
 
         {
             let index_table = txn.open_table(PAGE_INDEX_TABLE)?;
@@ -383,6 +1283,9 @@ impl DatabaseManager {
             let mut asset_lookup_table = txn.open_table(ASSET_LOOKUP_TABLE)?;
             let mut asset_group_table =
                 txn.open_multimap_table(ASSET_GROUP_TABLE)?;
+            let mut asset_blob_table = txn.open_table(ASSET_BLOB_TABLE)?;
+            let mut asset_revision_table =
+                txn.open_table(ASSET_REVISION_TABLE)?;
 
             for page in exported_pages {
                 delete_page_hard_in_txn(
@@ -395,7 +1298,9 @@ impl DatabaseManager {
                     &mut asset_info_table,
                     &mut asset_lookup_table,
                     &mut asset_group_table,
-                    &mut removed_asset_ids,
+                    &mut asset_blob_table,
+                    &mut asset_revision_table,
+                    &mut released_blobs,
                 )?;
             }
             let page_ids: Vec<PageId> = exported_pages
@@ -410,11 +1315,15 @@ impl DatabaseManager {
                 &txn,
                 &page_ids,
             )?;
+            remove_asset_refs_by_page_ids_in_txn(
+                &txn,
+                &page_ids,
+            )?;
         }
 
         {
             for page_id in draft_page_ids {
-                removed_asset_ids.extend(delete_draft_in_txn(&txn, page_id)?);
+                released_blobs.extend(delete_draft_in_txn(&txn, page_id)?);
             }
         }
 
@@ -432,9 +1341,7 @@ impl DatabaseManager {
         self.remove_prompt_candidates_by_page_ids(&page_ids)?;
         self.remove_resource_candidates_by_page_ids(&page_ids)?;
 
-        for asset_id in removed_asset_ids {
-            let _ = fs::remove_file(self.asset_file_path(&asset_id));
-        }
+        self.remove_asset_blob_files(&released_blobs);
 
         Ok(())
     }
@@ -443,7 +1350,7 @@ impl DatabaseManager {
     /// アセット実体の一時配置
     ///
     /// # 引数
-    /// * `asset_id` - 対象アセットID
+    /// * `hash` - アセットデータの内容ハッシュ値
     /// * `data` - アセットデータ
     ///
     /// # 戻り値
@@ -451,10 +1358,10 @@ impl DatabaseManager {
     ///
     pub(crate) fn stage_asset_blob(
         &self,
-        asset_id: &AssetId,
+        hash: &AssetHash,
         data: &[u8],
     ) -> Result<PathBuf> {
-        let staged_path = self.staged_asset_file_path(asset_id)?;
+        let staged_path = self.staged_asset_file_path(hash)?;
         if let Some(parent) = staged_path.parent() {
             fs::create_dir_all(parent)?;
         }
@@ -467,25 +1374,31 @@ impl DatabaseManager {
     ///
     /// # 引数
     /// * `staged_path` - 一時ファイルパス
-    /// * `asset_id` - 対象アセットID
+    /// * `hash` - アセットデータの内容ハッシュ値
     ///
     /// # 戻り値
-    /// 成功時は `Ok(())` を返す。
+    /// 新規に配置した場合は `true`、同一内容の実体が既に存在したため一時
+    /// ファイルを破棄した場合は `false` を返す。
     ///
     pub(crate) fn commit_staged_asset_blob<P>(
         &self,
         staged_path: P,
-        asset_id: &AssetId,
-    ) -> Result<()>
+        hash: &AssetHash,
+    ) -> Result<bool>
     where
         P: AsRef<Path>,
     {
-        let final_path = self.asset_file_path(asset_id);
+        let final_path = self.asset_blob_path(hash);
+        if final_path.exists() {
+            self.discard_staged_asset_blob(staged_path)?;
+            return Ok(false);
+        }
+
         if let Some(parent) = final_path.parent() {
             fs::create_dir_all(parent)?;
         }
         fs::rename(staged_path.as_ref(), final_path)?;
-        Ok(())
+        Ok(true)
     }
 
     ///
@@ -513,7 +1426,7 @@ impl DatabaseManager {
 
     fn staged_asset_file_path(
         &self,
-        asset_id: &AssetId,
+        hash: &AssetHash,
     ) -> Result<PathBuf> {
         let unique = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -522,11 +1435,102 @@ impl DatabaseManager {
         Ok(
             self.asset_path
                 .join(".staging")
-                .join(format!("{}-{}.tmp", asset_id, unique)),
+                .join(format!("{}-{}.tmp", hash, unique)),
         )
     }
 }
 
+///
+/// 取り込んだ backup アーカイブをエクスポート履歴へ記録する
+///
+/// # 引数
+/// * `history_table` - エクスポート履歴テーブル
+/// * `bundle` - 取り込んだ bundle
+///
+/// # 戻り値
+/// 成功時は `Ok(())` を返す。
+///
+fn record_imported_export_in_txn(
+    history_table: &mut Table<'_, Id, ExportHistoryInfo>,
+    bundle: &ExportBundle,
+) -> Result<()> {
+    let export_id = bundle.manifest.export_id.clone().unwrap_or_else(Id::new);
+    history_table.insert(
+        export_id,
+        ExportHistoryInfo::new(
+            ExportHistoryKind::Imported,
+            bundle.manifest.timestamp,
+            bundle.manifest.base_export.clone(),
+        ),
+    )?;
+    Ok(())
+}
+
+///
+/// 差分 import で更新するページの現在パス・削除時パス・ロックを解除する
+///
+/// # 引数
+/// * `page_id` - 対象ページID
+/// * `index` - 更新前のページインデックス
+/// * `path_table` - ページパスインデックステーブル
+/// * `deleted_path_table` - 削除済みページパスインデックステーブル
+/// * `lock_table` - ロック情報テーブル
+///
+/// # 戻り値
+/// 成功時は `Ok(())` を返す。
+///
+fn release_page_paths(
+    page_id: &PageId,
+    index: &PageIndex,
+    path_table: &mut Table<'_, String, PageId>,
+    deleted_path_table: &mut MultimapTable<'_, String, PageId>,
+    lock_table: &mut Table<
+        '_,
+        crate::database::types::LockToken,
+        crate::database::types::LockInfo,
+    >,
+) -> Result<()> {
+    if let Some(path) = index.current_path() {
+        let _ = path_table.remove(path.to_string())?;
+    }
+    if let Some(path) = index.last_deleted_path() {
+        let _ = deleted_path_table.remove(path.to_string(), page_id.clone())?;
+    }
+    if let Some(token) = index.lock_token() {
+        let _ = lock_table.remove(token)?;
+    }
+    Ok(())
+}
+
+///
+/// 差分 import の配置先パスが他ページに使われていないことを確認する
+///
+/// # 引数
+/// * `path_table` - ページパスインデックステーブル
+/// * `path` - 配置先パス
+/// * `page_id` - 配置するページID
+///
+/// # 戻り値
+/// 配置可能な場合は `Ok(())` を返す。
+///
+fn ensure_page_path_available(
+    path_table: &Table<'_, String, PageId>,
+    path: &str,
+    page_id: &PageId,
+) -> Result<()> {
+    let occupant = path_table
+        .get(path.to_string())?
+        .map(|entry| entry.value());
+    match occupant {
+        Some(occupant) if occupant != *page_id => Err(anyhow!(
+            "incremental import page path conflict: path={}, page_id={}",
+            path,
+            occupant
+        )),
+        _ => Ok(()),
+    }
+}
+
 fn build_revision_map(
     revisions: &[ExportRevision],
 ) -> BTreeMap<PageId, Vec<&ExportRevision>> {
@@ -8,22 +8,24 @@
 //! ロック関連の操作を提供するモジュール
 //!
 
-use std::fs;
-
 use anyhow::{anyhow, Result};
-use chrono::Local;
+use chrono::{Duration, Local};
 use redb::{ReadableDatabase, ReadableTable};
 
 use super::DatabaseManager;
 use crate::database::entries::LockListEntry;
 use crate::database::schema::{
+    BROKEN_LOCK_TABLE,
     LOCK_INFO_TABLE,
     PAGE_INDEX_TABLE,
     USER_ID_TABLE,
     USER_INFO_TABLE,
 };
 use crate::database::txn_helpers::{delete_draft_in_txn, find_lock_by_page};
-use crate::database::types::{AssetId, LockInfo, LockToken, PageId};
+use crate::database::types::{AssetHash, LockInfo, LockToken, PageId};
+
+/// 強制解除したロック情報の保持期間(ロックの有効期限からの時間)
+const BROKEN_LOCK_RETENTION_HOURS: i64 = 24;
 
 impl DatabaseManager {
     ///
@@ -46,7 +48,7 @@ impl DatabaseManager {
         let txn = self.db.begin_write()?;
         let mut commit = false;
         let mut result = None;
-        let mut asset_ids: Vec<AssetId> = Vec::new();
+        let mut released_blobs: Vec<AssetHash> = Vec::new();
         let mut delete_draft = false;
 
         {
@@ -77,8 +79,14 @@ impl DatabaseManager {
                         }
                     }
                     None => {
-                        delete_draft = true;
-                        commit = true;
+                        /*
+                         * 強制解除されたドラフトは保持者の復帰を待つ
+                         */
+                        let broken_table = txn.open_table(BROKEN_LOCK_TABLE)?;
+                        if find_lock_by_page(&broken_table, page_id)?.is_none() {
+                            delete_draft = true;
+                            commit = true;
+                        }
                     }
                 }
             } else if let Some(token) = index.lock_token() {
@@ -106,7 +114,7 @@ impl DatabaseManager {
         }
 
         if delete_draft {
-            asset_ids = delete_draft_in_txn(&txn, page_id)?;
+            released_blobs = delete_draft_in_txn(&txn, page_id)?;
             commit = true;
         }
 
@@ -117,10 +125,7 @@ impl DatabaseManager {
             txn.commit()?;
         }
 
-        for asset_id in asset_ids {
-            let asset_path = self.asset_file_path(&asset_id);
-            let _ = fs::remove_file(asset_path);
-        }
+        self.remove_asset_blob_files(&released_blobs);
 
         Ok(result)
     }
@@ -245,6 +250,13 @@ impl DatabaseManager {
          */
         let txn = self.db.begin_write()?;
         let lock_info = {
+            /*
+             * 強制解除済みロックの確認
+             */
+            if txn.open_table(BROKEN_LOCK_TABLE)?.get(token.clone())?.is_some() {
+                return Err(anyhow!(crate::database::DbError::LockBroken));
+            }
+
             let mut index_table = txn.open_table(PAGE_INDEX_TABLE)?;
             let mut lock_table = txn.open_table(LOCK_INFO_TABLE)?;
             let now = Local::now();
@@ -392,12 +404,19 @@ impl DatabaseManager {
          * 書き込みトランザクション開始
          */
         let txn = self.db.begin_write()?;
-        let mut asset_ids: Vec<AssetId> = Vec::new();
+        let mut released_blobs: Vec<AssetHash> = Vec::new();
         let mut delete_draft = false;
         let mut needs_commit = false;
         let mut result: Result<()> = Ok(());
 
         {
+            /*
+             * 強制解除済みロックの確認
+             */
+            if txn.open_table(BROKEN_LOCK_TABLE)?.get(token.clone())?.is_some() {
+                return Err(anyhow!(crate::database::DbError::LockBroken));
+            }
+
             let mut index_table = txn.open_table(PAGE_INDEX_TABLE)?;
             let mut lock_table = txn.open_table(LOCK_INFO_TABLE)?;
             let now = Local::now();
@@ -482,7 +501,7 @@ impl DatabaseManager {
         }
 
         if delete_draft {
-            asset_ids = delete_draft_in_txn(&txn, page_id)?;
+            released_blobs = delete_draft_in_txn(&txn, page_id)?;
             needs_commit = true;
         }
 
@@ -490,10 +509,7 @@ impl DatabaseManager {
             txn.commit()?;
         }
 
-        for asset_id in asset_ids {
-            let asset_path = self.asset_file_path(&asset_id);
-            let _ = fs::remove_file(asset_path);
-        }
+        self.remove_asset_blob_files(&released_blobs);
 
         result
     }
@@ -509,7 +525,7 @@ impl DatabaseManager {
          * 書き込みトランザクション開始
          */
         let txn = self.db.begin_write()?;
-        let mut asset_ids: Vec<AssetId> = Vec::new();
+        let mut released_blobs: Vec<AssetHash> = Vec::new();
         let mut draft_pages: Vec<PageId> = Vec::new();
         let (removed, mut needs_commit) = {
             let mut index_table = txn.open_table(PAGE_INDEX_TABLE)?;
@@ -562,12 +578,50 @@ impl DatabaseManager {
             }
         }?;
 
+        /*
+         * 保持期間を過ぎた強制解除ロックの削除
+         */
+        {
+            let index_table = txn.open_table(PAGE_INDEX_TABLE)?;
+            let lock_table = txn.open_table(LOCK_INFO_TABLE)?;
+            let mut broken_table = txn.open_table(BROKEN_LOCK_TABLE)?;
+            let limit = Local::now() - Duration::hours(BROKEN_LOCK_RETENTION_HOURS);
+
+            let mut outdated = Vec::new();
+            for entry in broken_table.iter()? {
+                let (token, info) = entry?;
+                let info = info.value();
+                if info.expire() <= limit {
+                    outdated.push((token.value().clone(), info.page()));
+                }
+            }
+
+            for (token, page_id) in outdated {
+                let _ = broken_table.remove(token)?;
+                needs_commit = true;
+
+                /*
+                 * 保持者が復帰しなかったドラフトの削除
+                 */
+                let is_draft = index_table
+                    .get(page_id.clone())?
+                    .is_some_and(|entry| entry.value().is_draft());
+                if is_draft
+                    && find_lock_by_page(&lock_table, &page_id)?.is_none()
+                    && find_lock_by_page(&broken_table, &page_id)?.is_none()
+                    && !draft_pages.contains(&page_id)
+                {
+                    draft_pages.push(page_id);
+                }
+            }
+        }
+
         /*
          * コミット
          */
         for page_id in draft_pages {
             let mut deleted = delete_draft_in_txn(&txn, &page_id)?;
-            asset_ids.append(&mut deleted);
+            released_blobs.append(&mut deleted);
             needs_commit = true;
         }
 
@@ -575,10 +629,7 @@ impl DatabaseManager {
             txn.commit()?;
         }
 
-        for asset_id in asset_ids {
-            let asset_path = self.asset_file_path(&asset_id);
-            let _ = fs::remove_file(asset_path);
-        }
+        self.remove_asset_blob_files(&released_blobs);
 
         Ok(removed)
     }
@@ -650,6 +701,7 @@ impl DatabaseManager {
                 info.page(),
                 page_path,
                 info.expire(),
+                info.acquired(),
                 user_name,
             ));
         }
@@ -657,6 +709,90 @@ impl DatabaseManager {
         Ok(locks)
     }
 
+    ///
+    /// ロックの強制解除
+    ///
+    /// # 引数
+    /// * `token` - ロック解除トークン
+    ///
+    /// # 戻り値
+    /// 解除したロック情報を返す。ロックが存在しない場合は`None`を返す。
+    ///
+    /// # 注記
+    /// ロック情報のみを削除し、ドラフトページとそのアセットは削除しない。
+    /// 解除したロック情報は強制解除ロックとして一定期間保持し、元の保持者が
+    /// そのトークンを用いた場合に`DbError::LockBroken`を返すために用いる。
+    /// 保持期間内であれば、元の保持者はドラフトページのロックを取得し直して
+    /// 編集を続けられる。
+    ///
+    pub(crate) fn break_lock(
+        &self,
+        token: &LockToken,
+    ) -> Result<Option<LockInfo>> {
+        /*
+         * 書き込みトランザクション開始
+         */
+        let txn = self.db.begin_write()?;
+
+        let lock_info = {
+            let mut index_table = txn.open_table(PAGE_INDEX_TABLE)?;
+            let mut lock_table = txn.open_table(LOCK_INFO_TABLE)?;
+            let mut broken_table = txn.open_table(BROKEN_LOCK_TABLE)?;
+
+            /*
+             * ロック情報の削除
+             */
+            let lock_info = match lock_table.remove(token.clone())? {
+                Some(info) => info.value(),
+                None => return Ok(None),
+            };
+
+            /*
+             * ページインデックスのロック解除
+             */
+            let page_id = lock_info.page();
+            let index = index_table
+                .get(page_id.clone())?
+                .map(|entry| entry.value());
+            if let Some(mut index) = index
+                && index.lock_token() == Some(token.clone())
+            {
+                index.set_lock_token(None);
+                index_table.insert(page_id, index)?;
+            }
+
+            /*
+             * 強制解除ロックとして記録
+             */
+            broken_table.insert(token.clone(), lock_info.clone())?;
+
+            lock_info
+        };
+
+        /*
+         * コミット
+         */
+        txn.commit()?;
+
+        Ok(Some(lock_info))
+    }
+
+    ///
+    /// 強制解除されたロックか否かの判定
+    ///
+    /// # 引数
+    /// * `token` - ロック解除トークン
+    ///
+    /// # 戻り値
+    /// 保持期間内の強制解除ロックのトークンであれば`true`を返す。
+    ///
+    pub(crate) fn is_broken_lock(&self, token: &LockToken) -> Result<bool> {
+        let txn = self.db.begin_read()?;
+        let broken_table = txn.open_table(BROKEN_LOCK_TABLE)?;
+
+        Ok(broken_table.get(token.clone())?.is_some())
+    }
+
     ///
     /// ロック情報の削除
     ///
@@ -672,7 +808,7 @@ impl DatabaseManager {
          */
         let txn = self.db.begin_write()?;
         let mut removed = false;
-        let mut asset_ids: Vec<AssetId> = Vec::new();
+        let mut released_blobs: Vec<AssetHash> = Vec::new();
         let mut draft_to_delete: Option<PageId> = None;
 
         /*
@@ -740,7 +876,7 @@ impl DatabaseManager {
          */
         if let Some(page_id) = draft_to_delete {
             let mut deleted = delete_draft_in_txn(&txn, &page_id)?;
-            asset_ids.append(&mut deleted);
+            released_blobs.append(&mut deleted);
             removed = true;
         }
 
@@ -751,10 +887,7 @@ impl DatabaseManager {
             txn.commit()?;
         }
 
-        for asset_id in asset_ids {
-            let asset_path = self.asset_file_path(&asset_id);
-            let _ = fs::remove_file(asset_path);
-        }
+        self.remove_asset_blob_files(&released_blobs);
 
         Ok(removed)
     }
@@ -780,7 +913,7 @@ impl DatabaseManager {
          */
         let txn = self.db.begin_write()?;
         let mut removed = false;
-        let mut asset_ids: Vec<AssetId> = Vec::new();
+        let mut released_blobs: Vec<AssetHash> = Vec::new();
         let mut draft_to_delete: Option<PageId> = None;
 
         {
@@ -813,7 +946,7 @@ impl DatabaseManager {
         }
 
         if let Some(draft_page_id) = draft_to_delete {
-            asset_ids = delete_draft_in_txn(&txn, &draft_page_id)?;
+            released_blobs = delete_draft_in_txn(&txn, &draft_page_id)?;
             removed = true;
         }
 
@@ -821,10 +954,7 @@ impl DatabaseManager {
             txn.commit()?;
         }
 
-        for asset_id in asset_ids {
-            let asset_path = self.asset_file_path(&asset_id);
-            let _ = fs::remove_file(asset_path);
-        }
+        self.remove_asset_blob_files(&released_blobs);
 
         Ok(removed)
     }
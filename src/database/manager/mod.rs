@@ -17,6 +17,7 @@ use redb::{Database, ReadableDatabase};
 
 use super::init::init_database;
 use super::schema::{
+    ASSET_BLOB_DIR_NAME,
     DbError,
     DEFAULT_ROOT_SOURCE,
     DEFAULT_SANDBOX_SOURCE,
@@ -27,8 +28,11 @@ use super::schema::{
     SANDBOX_SAMPLE_CSV_FILE_NAME,
     SANDBOX_SAMPLE_CSV_SOURCE,
 };
-use super::types::{AssetId, PageId};
+use super::types::{AssetHash, AssetId, PageId};
 
+pub(crate) mod asset_gc;
+pub(crate) mod asset_uploads;
+pub(crate) mod asset_variants;
 pub(crate) mod assets;
 pub(crate) mod bearer_tokens;
 pub(crate) mod derived_rebuild;
@@ -49,7 +53,6 @@ pub(crate) struct DatabaseManager {
     db: Database,
 
     /// アセットデータ格納ディレクトリへのパス
-    #[allow(dead_code)]
     asset_path: PathBuf,
 
     /// テスト用resource候補同期失敗フラグ
@@ -85,12 +88,19 @@ impl DatabaseManager {
             Err(err) => return Err(err.into()),
         };
 
-        Ok(Self {
+        let manager = Self {
             db,
             asset_path: asset_path.as_ref().into(),
             #[cfg(test)]
             fail_resource_candidate_sync_for_test: AtomicBool::new(false),
-        })
+        };
+
+        /*
+         * 旧形式アセットファイルの blob ストアへの移行
+         */
+        manager.migrate_legacy_asset_files()?;
+
+        Ok(manager)
     }
 
     ///
@@ -146,6 +156,26 @@ impl DatabaseManager {
         })
     }
 
+    ///
+    /// テスト用にアセット実体の参照数を取得する
+    ///
+    /// # 引数
+    /// * `hash` - 対象の内容ハッシュ値
+    ///
+    /// # 戻り値
+    /// blob管理情報が存在する場合は参照数を返す。
+    ///
+    #[cfg(test)]
+    pub(crate) fn get_asset_blob_ref_count_for_test(
+        &self,
+        hash: &AssetHash,
+    ) -> Result<Option<u64>> {
+        let txn = self.db.begin_read()?;
+        let table = txn.open_table(super::schema::ASSET_BLOB_TABLE)?;
+
+        Ok(table.get(*hash)?.map(|entry| entry.value().ref_count()))
+    }
+
     ///
     /// テスト用にresource URI逆引き索引の所有者を変更する
     ///
@@ -307,13 +337,16 @@ impl DatabaseManager {
     }
 
     ///
-    /// アセット保存パスの生成
+    /// 旧形式のアセット保存パスの生成
     ///
     /// # 引数
     /// * `asset_id` - アセットID
     ///
     /// # 戻り値
-    /// アセット保存パスを返す。
+    /// アセットID単位で保存していた旧形式のアセット保存パスを返す。
+    ///
+    /// # 注記
+    /// 内容ハッシュ値単位の blob ストアへの移行処理でのみ使用する。
     ///
     pub(crate) fn asset_file_path(&self, asset_id: &AssetId) -> PathBuf {
         let raw = asset_id.to_string();
@@ -325,4 +358,24 @@ impl DatabaseManager {
         let (dir2, _) = rest.split_at(3);
         self.asset_path.join(dir1).join(dir2).join(raw)
     }
+
+    ///
+    /// アセット実体(blob)の保存パスの生成
+    ///
+    /// # 引数
+    /// * `hash` - 実体の内容ハッシュ値
+    ///
+    /// # 戻り値
+    /// アセット実体の保存パスを返す。
+    ///
+    pub(crate) fn asset_blob_path(&self, hash: &AssetHash) -> PathBuf {
+        let raw = hash.to_hex();
+        let (dir1, rest) = raw.split_at(2);
+        let (dir2, _) = rest.split_at(2);
+        self.asset_path
+            .join(ASSET_BLOB_DIR_NAME)
+            .join(dir1)
+            .join(dir2)
+            .join(raw)
+    }
 }
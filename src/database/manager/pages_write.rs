@@ -9,13 +9,16 @@
 //!
 
 use std::collections::HashSet;
-use std::fs;
 
 use anyhow::{anyhow, Result};
 use chrono::Local;
 use redb::{ReadableMultimapTable, ReadableTable};
 
 use super::DatabaseManager;
+use crate::database::asset_refs::{
+    remove_asset_refs_by_page_ids_in_txn,
+    sync_asset_refs_for_source_in_txn,
+};
 use crate::database::link_refs::{build_link_refs, build_link_refs_with_table};
 use crate::database::primitive_names::{
     remove_mcp_primitive_names_by_page_ids_in_txn,
@@ -28,9 +31,11 @@ use crate::database::resource_uris::{
 };
 use crate::markdown_source::front_matter::validate_document_front_matter;
 use crate::database::schema::{
+    ASSET_BLOB_TABLE,
     ASSET_GROUP_TABLE,
     ASSET_INFO_TABLE,
     ASSET_LOOKUP_TABLE,
+    ASSET_REVISION_TABLE,
     DELETED_PAGE_PATH_TABLE,
     DbError,
     LOCK_INFO_TABLE,
@@ -51,7 +56,6 @@ use crate::database::txn_helpers::{
     verify_page_lock_in_txn,
 };
 use crate::database::types::{
-    AssetId,
     LockInfo,
     LockToken,
     PageId,
@@ -236,6 +240,12 @@ impl DatabaseManager {
                 &path,
                 &source,
             )?;
+            sync_asset_refs_for_source_in_txn(
+                &txn,
+                &page_id,
+                &path,
+                &source,
+            )?;
             let page_source = PageSource::new(source, user_id, rename_info);
 
             /*
@@ -350,6 +360,65 @@ impl DatabaseManager {
         user_name: &str,
         source: String,
         amend: bool,
+    ) -> Result<()> {
+        self.write_page_source(page_id, user_name, source, amend, None)
+    }
+
+    ///
+    /// 最新リビジョンを確認した上でのページの書き込み
+    ///
+    /// # 引数
+    /// * `page_id` - ページID
+    /// * `user_name` - ページを編集したユーザの名前
+    /// * `source` - ページソース
+    /// * `expected_latest_revision` - 書き込み直前に期待する最新リビジョン
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`を返す。最新リビジョンが期待値と異なる場合は
+    /// `DbError::RevisionConflict`を`Err()`でラップして返す。
+    ///
+    /// # 注記
+    /// 最新リビジョンの確認と新規リビジョンの追加は同一の書き込みトランザクション
+    /// 内で行うため、確認後に他の更新が割り込むことはない。
+    ///
+    pub(crate) fn put_page_if_latest(
+        &self,
+        page_id: &PageId,
+        user_name: &str,
+        source: String,
+        expected_latest_revision: u64,
+    ) -> Result<()> {
+        self.write_page_source(
+            page_id,
+            user_name,
+            source,
+            false,
+            Some(expected_latest_revision),
+        )
+    }
+
+    ///
+    /// ページソース書き込みの共通処理
+    ///
+    /// # 引数
+    /// * `page_id` - ページID
+    /// * `user_name` - ページを編集したユーザの名前
+    /// * `source` - ページソース
+    /// * `amend` - 最新リビジョンを上書きする場合は`true`
+    /// * `expected_latest_revision` - 期待する最新リビジョン(確認しない場合は
+    ///   `None`)
+    ///
+    /// # 戻り値
+    /// 処理が成功した場合は`Ok(())`を返す。失敗した場合はエラー情報を`Err()`で
+    /// ラップして返す。
+    ///
+    fn write_page_source(
+        &self,
+        page_id: &PageId,
+        user_name: &str,
+        source: String,
+        amend: bool,
+        expected_latest_revision: Option<u64>,
     ) -> Result<()> {
         /*
          * front matter 検証
@@ -382,6 +451,16 @@ impl DatabaseManager {
                 Some(entry) => entry.value(),
                 None => return Err(anyhow!(DbError::PageNotFound)),
             };
+
+            /*
+             * 最新リビジョンの検証
+             */
+            if expected_latest_revision
+                .is_some_and(|expected| index.latest() != expected)
+            {
+                return Err(anyhow!(DbError::RevisionConflict));
+            }
+
             sync_mcp_primitive_name_for_source_in_txn(
                 &txn,
                 page_id,
@@ -397,6 +476,12 @@ impl DatabaseManager {
                 &current_path,
                 &source,
             )?;
+            sync_asset_refs_for_source_in_txn(
+                &txn,
+                page_id,
+                &current_path,
+                &source,
+            )?;
 
             if index.is_draft() {
                 if amend {
@@ -550,6 +635,12 @@ impl DatabaseManager {
                 &current_path,
                 &request.source,
             )?;
+            sync_asset_refs_for_source_in_txn(
+                &txn,
+                &request.page_id,
+                &current_path,
+                &request.source,
+            )?;
 
             if request.allow_amend {
                 if latest_source.user() != user_id {
@@ -617,7 +708,7 @@ impl DatabaseManager {
          * 書き込みトランザクション開始
          */
         let txn = self.db.begin_write()?;
-        let mut asset_ids = Vec::new();
+        let mut released_blobs = Vec::new();
 
         let target_ids = {
             let mut path_table = txn.open_table(PAGE_PATH_TABLE)?;
@@ -628,6 +719,8 @@ impl DatabaseManager {
             let mut asset_table = txn.open_table(ASSET_INFO_TABLE)?;
             let mut lookup_table = txn.open_table(ASSET_LOOKUP_TABLE)?;
             let mut group_table = txn.open_multimap_table(ASSET_GROUP_TABLE)?;
+            let mut blob_table = txn.open_table(ASSET_BLOB_TABLE)?;
+            let mut revision_table = txn.open_table(ASSET_REVISION_TABLE)?;
 
             /*
              * 起点ページの取得と検証
@@ -700,6 +793,10 @@ impl DatabaseManager {
                     &txn,
                     &target_ids,
                 )?;
+                remove_asset_refs_by_page_ids_in_txn(
+                    &txn,
+                    &target_ids,
+                )?;
                 let mut source_table = txn.open_table(PAGE_SOURCE_TABLE)?;
                 for target_id in targets.iter() {
                     delete_page_hard_in_txn(
@@ -712,7 +809,9 @@ impl DatabaseManager {
                         &mut asset_table,
                         &mut lookup_table,
                         &mut group_table,
-                        &mut asset_ids,
+                        &mut blob_table,
+                        &mut revision_table,
+                        &mut released_blobs,
                     )?;
                 }
             } else {
@@ -745,10 +844,7 @@ impl DatabaseManager {
             self.remove_template_candidates_by_page_ids(&target_ids)?;
             self.remove_prompt_candidates_by_page_ids(&target_ids)?;
             self.remove_resource_candidates_by_page_ids(&target_ids)?;
-            for asset_id in asset_ids {
-                let asset_path = self.asset_file_path(&asset_id);
-                let _ = fs::remove_file(asset_path);
-            }
+            self.remove_asset_blob_files(&released_blobs);
         }
 
         Ok(target_ids)
@@ -832,6 +928,12 @@ impl DatabaseManager {
                 &current_path,
                 &target_source.source(),
             )?;
+            sync_asset_refs_for_source_in_txn(
+                &txn,
+                page_id,
+                &current_path,
+                &target_source.source(),
+            )?;
 
             /*
              * ロック検証
@@ -1131,6 +1233,12 @@ impl DatabaseManager {
                     &new_path,
                     &latest_source.source(),
                 )?;
+                sync_asset_refs_for_source_in_txn(
+                    &txn,
+                    &target_id,
+                    &new_path,
+                    &latest_source.source(),
+                )?;
 
                 path_table.remove(&src_path)?;
                 path_table.insert(&new_path, target_id.clone())?;
@@ -1224,7 +1332,7 @@ impl DatabaseManager {
          * 書き込みトランザクション開始
          */
         let txn = self.db.begin_write()?;
-        let mut asset_ids = Vec::new();
+        let mut released_blobs = Vec::new();
 
         {
             let mut path_table = txn.open_table(PAGE_PATH_TABLE)?;
@@ -1302,7 +1410,7 @@ impl DatabaseManager {
              * ページ削除
              */
             if index.is_draft() {
-                asset_ids = delete_draft_in_txn(&txn, page_id)?;
+                released_blobs = delete_draft_in_txn(&txn, page_id)?;
             } else {
                 delete_page_soft_in_txn(
                     page_id,
@@ -1327,10 +1435,7 @@ impl DatabaseManager {
         /*
          * アセットファイルの削除
          */
-        for asset_id in asset_ids {
-            let asset_path = self.asset_file_path(&asset_id);
-            let _ = fs::remove_file(asset_path);
-        }
+        self.remove_asset_blob_files(&released_blobs);
 
         Ok(())
     }
@@ -1353,7 +1458,6 @@ impl DatabaseManager {
          * 書き込みトランザクション開始
          */
         let txn = self.db.begin_write()?;
-        let asset_ids: Vec<AssetId>;
 
         {
             let mut lock_table = txn.open_table(LOCK_INFO_TABLE)?;
@@ -1371,14 +1475,11 @@ impl DatabaseManager {
             }
         }
 
-        asset_ids = delete_draft_in_txn(&txn, page_id)?;
+        let released_blobs = delete_draft_in_txn(&txn, page_id)?;
 
         txn.commit()?;
 
-        for asset_id in asset_ids {
-            let asset_path = self.asset_file_path(&asset_id);
-            let _ = fs::remove_file(asset_path);
-        }
+        self.remove_asset_blob_files(&released_blobs);
 
         Ok(())
     }
@@ -1475,6 +1576,12 @@ impl DatabaseManager {
                 &restore_to,
                 &latest_source.source(),
             )?;
+            sync_asset_refs_for_source_in_txn(
+                &txn,
+                page_id,
+                &restore_to,
+                &latest_source.source(),
+            )?;
 
             if with_assets {
                 /*
@@ -1666,6 +1773,12 @@ impl DatabaseManager {
                     &new_path,
                     &latest_source.source(),
                 )?;
+                sync_asset_refs_for_source_in_txn(
+                    &txn,
+                    &target_id,
+                    &new_path,
+                    &latest_source.source(),
+                )?;
 
                 if with_assets {
                     /*
@@ -1735,7 +1848,7 @@ impl DatabaseManager {
          * 書き込みトランザクション開始
          */
         let txn = self.db.begin_write()?;
-        let mut asset_ids = Vec::new();
+        let mut released_blobs = Vec::new();
 
         {
             let mut path_table = txn.open_table(PAGE_PATH_TABLE)?;
@@ -1747,6 +1860,8 @@ impl DatabaseManager {
             let mut asset_table = txn.open_table(ASSET_INFO_TABLE)?;
             let mut lookup_table = txn.open_table(ASSET_LOOKUP_TABLE)?;
             let mut group_table = txn.open_multimap_table(ASSET_GROUP_TABLE)?;
+            let mut blob_table = txn.open_table(ASSET_BLOB_TABLE)?;
+            let mut revision_table = txn.open_table(ASSET_REVISION_TABLE)?;
 
             delete_page_hard_in_txn(
                 page_id,
@@ -1758,7 +1873,9 @@ impl DatabaseManager {
                 &mut asset_table,
                 &mut lookup_table,
                 &mut group_table,
-                &mut asset_ids,
+                &mut blob_table,
+                &mut revision_table,
+                &mut released_blobs,
             )?;
             remove_mcp_primitive_names_by_page_ids_in_txn(
                 &txn,
@@ -1768,6 +1885,10 @@ impl DatabaseManager {
                 &txn,
                 &[page_id.clone()],
             )?;
+            remove_asset_refs_by_page_ids_in_txn(
+                &txn,
+                std::slice::from_ref(page_id),
+            )?;
         }
 
         /*
@@ -1782,10 +1903,7 @@ impl DatabaseManager {
         /*
          * アセットファイルの削除
          */
-        for asset_id in asset_ids {
-            let asset_path = self.asset_file_path(&asset_id);
-            let _ = fs::remove_file(asset_path);
-        }
+        self.remove_asset_blob_files(&released_blobs);
 
         Ok(())
     }
@@ -1924,6 +2042,12 @@ impl DatabaseManager {
                 &dst_path,
                 &latest_source.source(),
             )?;
+            sync_asset_refs_for_source_in_txn(
+                &txn,
+                &page_id,
+                &dst_path,
+                &latest_source.source(),
+            )?;
 
             path_table.remove(&path)?;
             path_table.insert(&dst_path, page_id)?;
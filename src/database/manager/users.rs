@@ -9,6 +9,7 @@
 //!
 
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
 use redb::{ReadableDatabase, ReadableTable, ReadableTableMetadata};
 
 use super::DatabaseManager;
@@ -18,7 +19,13 @@ use crate::database::schema::{
     USER_ID_TABLE,
     USER_INFO_TABLE,
 };
-use crate::database::types::{UserAttributeSet, UserId, UserInfo};
+use crate::database::types::{
+    LockoutPolicy,
+    MfaEnrollment,
+    UserAttributeSet,
+    UserId,
+    UserInfo,
+};
 
 impl DatabaseManager {
     ///
@@ -159,9 +166,9 @@ impl DatabaseManager {
         };
 
         /*
-         * Basic認証可否とパスワードを検証する
+         * 無効化状態・Basic認証可否とパスワードを検証する
          */
-        if !user_info.allows_basic_auth() {
+        if user_info.is_disabled() || !user_info.allows_basic_auth() {
             return Ok(false);
         }
 
@@ -453,4 +460,336 @@ impl DatabaseManager {
 
         Ok(())
     }
+
+    ///
+    /// ユーザ情報の一括更新
+    ///
+    /// # 引数
+    /// * `username` - 更新対象のユーザ名
+    /// * `display_name` - 表示名
+    /// * `password` - パスワード
+    /// * `attributes` - ユーザ属性集合
+    /// * `disabled` - 無効化状態
+    /// * `unlock` - ログイン失敗によるロックアウトを解除する場合は`true`
+    ///
+    /// # 戻り値
+    /// 更新に成功した場合は`Ok(())`を返す。
+    ///
+    /// # 注記
+    /// 指定された変更はすべて単一の書き込みトランザクションで反映する。
+    /// 途中で失敗した場合はいずれの変更も反映しない。
+    ///
+    pub(crate) fn edit_user(
+        &self,
+        username: &str,
+        display_name: Option<&str>,
+        password: Option<&str>,
+        attributes: Option<UserAttributeSet>,
+        disabled: Option<bool>,
+        unlock: bool,
+    ) -> Result<()> {
+        self.modify_user_info(username, |user_info| {
+            if let Some(name) = display_name {
+                user_info.set_display_name(name);
+            }
+
+            if let Some(password) = password {
+                user_info.set_password(password);
+            }
+
+            if let Some(attributes) = attributes {
+                user_info.set_attributes(attributes);
+            }
+
+            if let Some(disabled) = disabled {
+                user_info.set_disabled(disabled);
+            }
+
+            if unlock {
+                user_info.clear_login_failures();
+            }
+
+            Ok(())
+        })
+    }
+
+    ///
+    /// 最終アクティビティ日時の記録
+    ///
+    /// # 引数
+    /// * `username` - 記録対象のユーザ名
+    /// * `now` - 記録する日時
+    ///
+    /// # 戻り値
+    /// 処理に成功した場合は`Ok(())`を返す。
+    ///
+    /// # 注記
+    /// 認証の都度書き込みトランザクションを発行しないよう、前回記録から
+    /// 一定時間経過している場合のみ更新する。
+    ///
+    pub(crate) fn record_user_activity(
+        &self,
+        username: &str,
+        now: DateTime<Local>,
+    ) -> Result<()> {
+        /*
+         * 記録要否の判定
+         */
+        let needs_record = match self.get_user_info_by_name(username)? {
+            Some(user_info) => user_info.needs_activity_record(now),
+            None => return Ok(()),
+        };
+
+        if !needs_record {
+            return Ok(());
+        }
+
+        /*
+         * 最終アクティビティ日時の更新
+         */
+        self.modify_user_info(username, |user_info| {
+            user_info.set_last_activity(now);
+            Ok(())
+        })
+    }
+
+    ///
+    /// ログイン失敗の記録
+    ///
+    /// # 引数
+    /// * `username` - 記録対象のユーザ名
+    /// * `now` - 失敗日時
+    /// * `policy` - ロックアウト方針
+    ///
+    /// # 戻り値
+    /// 今回の失敗でロックアウトされた場合は`Ok(Some(解除日時))`を返す。
+    /// ユーザが存在しない場合は何もせず`Ok(None)`を返す。
+    ///
+    pub(crate) fn record_user_login_failure(
+        &self,
+        username: &str,
+        now: DateTime<Local>,
+        policy: &LockoutPolicy,
+    ) -> Result<Option<DateTime<Local>>> {
+        if self.get_user_info_by_name(username)?.is_none() {
+            return Ok(None);
+        }
+
+        self.modify_user_info(username, |user_info| {
+            Ok(user_info.record_login_failure(now, policy))
+        })
+    }
+
+    ///
+    /// ログイン失敗記録の消去
+    ///
+    /// # 引数
+    /// * `username` - 対象のユーザ名
+    ///
+    /// # 戻り値
+    /// 処理に成功した場合は`Ok(())`を返す。
+    ///
+    /// # 注記
+    /// 認証成功の都度書き込みトランザクションを発行しないよう、消去すべき
+    /// 記録がある場合のみ更新する。
+    ///
+    pub(crate) fn clear_user_login_failures(&self, username: &str) -> Result<()> {
+        let needs_clear = match self.get_user_info_by_name(username)? {
+            Some(user_info) => {
+                user_info.failed_logins() > 0
+                    || user_info.locked_until().is_some()
+            }
+            None => return Ok(()),
+        };
+
+        if !needs_clear {
+            return Ok(());
+        }
+
+        self.modify_user_info(username, |user_info| {
+            user_info.clear_login_failures();
+            Ok(())
+        })
+    }
+
+    ///
+    /// ユーザのロックアウト解除
+    ///
+    /// # 引数
+    /// * `username` - 対象のユーザ名
+    ///
+    /// # 戻り値
+    /// 解除すべきロックアウトまたは失敗記録があった場合は`Ok(true)`を返す。
+    /// ユーザが存在しない場合はエラーを返す。
+    ///
+    pub(crate) fn unlock_user(&self, username: &str) -> Result<bool> {
+        self.modify_user_info(username, |user_info| {
+            Ok(user_info.clear_login_failures())
+        })
+    }
+
+    ///
+    /// 二要素認証の登録
+    ///
+    /// # 引数
+    /// * `username` - 登録対象のユーザ名
+    /// * `force` - 登録済みの場合に再登録する場合は`true`
+    ///
+    /// # 戻り値
+    /// 登録に成功した場合は利用者へ提示する平文情報を返す。
+    ///
+    /// # 注記
+    /// 登録済みで`force`が指定されていない場合はエラーを返す。
+    ///
+    pub(crate) fn enroll_user_mfa(
+        &self,
+        username: &str,
+        force: bool,
+    ) -> Result<MfaEnrollment> {
+        self.modify_user_info(username, |user_info| {
+            if user_info.is_mfa_enrolled() && !force {
+                return Err(anyhow!("mfa already enrolled: {}", username));
+            }
+
+            Ok(user_info.enroll_mfa())
+        })
+    }
+
+    ///
+    /// 二要素認証の登録解除
+    ///
+    /// # 引数
+    /// * `username` - 解除対象のユーザ名
+    ///
+    /// # 戻り値
+    /// 解除に成功した場合は`Ok(())`を返す。未登録の場合はエラーを返す。
+    ///
+    pub(crate) fn disable_user_mfa(&self, username: &str) -> Result<()> {
+        self.modify_user_info(username, |user_info| {
+            if !user_info.is_mfa_enrolled() {
+                return Err(anyhow!("mfa not enrolled: {}", username));
+            }
+
+            user_info.clear_mfa();
+            Ok(())
+        })
+    }
+
+    ///
+    /// 二要素認証コードの照合
+    ///
+    /// # 引数
+    /// * `username` - 照合対象のユーザ名
+    /// * `code` - TOTP コードまたはリカバリコード
+    /// * `now` - 照合基準時刻
+    ///
+    /// # 戻り値
+    /// 照合に成功した場合は`Ok(true)`を返す。ユーザが存在しない場合や
+    /// 二要素認証が未登録の場合は`Ok(false)`を返す。
+    ///
+    /// # 注記
+    /// 照合に成功した場合のみ、受理済み時間ステップやリカバリコードの消費を
+    /// 永続化する。
+    ///
+    pub(crate) fn verify_user_mfa_code(
+        &self,
+        username: &str,
+        code: &str,
+        now: DateTime<Local>,
+    ) -> Result<bool> {
+        /*
+         * 書き込みトランザクション開始
+         */
+        let key = username.to_string();
+        let txn = self.db.begin_write()?;
+
+        /*
+         * 照合と照合結果の反映
+         */
+        let verified = {
+            let id_table = txn.open_table(USER_ID_TABLE)?;
+            let user_id = match id_table.get(&key)? {
+                Some(id) => id.value(),
+                None => return Ok(false),
+            };
+
+            let mut info_table = txn.open_table(USER_INFO_TABLE)?;
+            let mut user_info = match info_table.get(user_id.clone())? {
+                Some(info) => info.value(),
+                None => return Ok(false),
+            };
+
+            if !user_info.verify_mfa_code(code, now) {
+                false
+            } else {
+                info_table.insert(user_id, user_info)?;
+                true
+            }
+        };
+
+        /*
+         * 照合成功時のみコミット
+         */
+        if verified {
+            txn.commit()?;
+        } else {
+            txn.abort()?;
+        }
+
+        Ok(verified)
+    }
+
+    ///
+    /// ユーザ情報の読み出し・変更・書き戻し
+    ///
+    /// # 引数
+    /// * `username` - 対象のユーザ名
+    /// * `modify` - ユーザ情報を変更するクロージャ
+    ///
+    /// # 戻り値
+    /// クロージャの戻り値を返す。クロージャがエラーを返した場合は変更を
+    /// 破棄する。
+    ///
+    fn modify_user_info<T, F>(&self, username: &str, modify: F) -> Result<T>
+    where
+        F: FnOnce(&mut UserInfo) -> Result<T>,
+    {
+        /*
+         * 書き込みトランザクション開始
+         */
+        let key = username.to_string();
+        let txn = self.db.begin_write()?;
+
+        /*
+         * ユーザ情報の変更
+         */
+        let result = {
+            let id_table = txn.open_table(USER_ID_TABLE)?;
+            let user_id = match id_table.get(&key)? {
+                Some(id) => id.value(),
+                None => {
+                    return Err(anyhow!("user not found: {}", username));
+                }
+            };
+
+            let mut info_table = txn.open_table(USER_INFO_TABLE)?;
+            let mut user_info = match info_table.get(user_id.clone())? {
+                Some(info) => info.value(),
+                None => {
+                    return Err(anyhow!("user not found: {}", username));
+                }
+            };
+
+            let result = modify(&mut user_info)?;
+            info_table.insert(user_id, user_info)?;
+            result
+        };
+
+        /*
+         * コミット
+         */
+        txn.commit()?;
+
+        Ok(result)
+    }
 }
@@ -10,6 +10,7 @@
 
 pub(crate) mod types;
 
+mod asset_refs;
 mod entries;
 mod init;
 mod link_refs;
@@ -26,8 +27,11 @@ mod txn_helpers;
 
 #[allow(unused_imports)]
 pub(crate) use entries::{
+    AssetBlobIssue,
+    AssetGcItem,
     AssetListEntry,
     AssetMoveResult,
+    AssetUploadResult,
     LockListEntry,
     PageListEntry,
     PromptSourceEntry,
@@ -11,10 +11,16 @@
 use redb::{MultimapTableDefinition, TableDefinition};
 
 use crate::database::types::{
+    AssetBlobInfo,
+    AssetHash,
     AssetId,
     AssetInfo,
+    AssetRevisionInfo,
+    AssetUploadInfo,
     BearerTokenInfo,
+    ExportHistoryInfo,
     LockInfo,
+    Id,
     LockToken,
     McpPrimitiveKind,
     McpPrimitiveNameKey,
@@ -26,6 +32,7 @@ use crate::database::types::{
     TemplateCandidateEntry,
     TokenId,
     TokenHash,
+    UploadId,
     UserId,
     UserInfo,
 };
@@ -89,6 +96,11 @@ pub(in crate::database) static LOCK_INFO_TABLE:
     TableDefinition<LockToken, LockInfo> =
         TableDefinition::new("lock_info_table");
 
+/// 強制解除ロックテーブル (ロック解除トークン => 強制解除したロック情報)
+pub(in crate::database) static BROKEN_LOCK_TABLE:
+    TableDefinition<LockToken, LockInfo> =
+        TableDefinition::new("broken_lock_table");
+
 /// アセット情報テーブル (アセットID => アセット情報)
 pub(in crate::database) static ASSET_INFO_TABLE:
     TableDefinition<AssetId, AssetInfo> =
@@ -104,6 +116,52 @@ pub(in crate::database) static ASSET_GROUP_TABLE:
     MultimapTableDefinition<PageId, AssetId> =
         MultimapTableDefinition::new("asset_group_table");
 
+/// 共有アセットID特定テーブル (ファイル名 => アセットID)
+pub(in crate::database) static SHARED_ASSET_LOOKUP_TABLE:
+    TableDefinition<String, AssetId> =
+        TableDefinition::new("shared_asset_lookup_table");
+
+/// アセット参照逆引きテーブル (参照先キー => [参照元ページID])
+///
+/// 参照先キーはページ所属アセットの場合は`{ページパス}:{ファイル名}`、共有
+/// アセットの場合は`:{ファイル名}`の形式とする。
+pub(in crate::database) static ASSET_REF_TABLE:
+    MultimapTableDefinition<String, PageId> =
+        MultimapTableDefinition::new("asset_ref_table");
+
+/// ページ別アセット参照テーブル (参照元ページID => [参照先キー])
+pub(in crate::database) static PAGE_ASSET_REF_TABLE:
+    MultimapTableDefinition<PageId, String> =
+        MultimapTableDefinition::new("page_asset_ref_table");
+
+/// アセット参照逆引き索引の構築状態テーブル
+pub(in crate::database) static ASSET_REF_STATE_TABLE:
+    TableDefinition<u8, u8> =
+        TableDefinition::new("asset_ref_state_table");
+
+/// アセット実体テーブル (内容ハッシュ値 => blob管理情報)
+pub(in crate::database) static ASSET_BLOB_TABLE:
+    TableDefinition<AssetHash, AssetBlobInfo> =
+        TableDefinition::new("asset_blob_table");
+
+/// 旧形式アセットファイル移行状態テーブル
+pub(in crate::database) static ASSET_BLOB_STATE_TABLE:
+    TableDefinition<u8, u8> =
+        TableDefinition::new("asset_blob_state_table");
+
+/// アセット履歴テーブル ((アセットID,リビジョン番号) => リビジョン情報)
+///
+/// 現在のリビジョンより古いリビジョンのみを保持する(現在のリビジョンは
+/// ASSET_INFO_TABLE が保持する)。
+pub(in crate::database) static ASSET_REVISION_TABLE:
+    TableDefinition<(AssetId, u64), AssetRevisionInfo> =
+        TableDefinition::new("asset_revision_table");
+
+/// 分割アップロードセッションテーブル (セッションID => セッション情報)
+pub(in crate::database) static ASSET_UPLOAD_TABLE:
+    TableDefinition<UploadId, AssetUploadInfo> =
+        TableDefinition::new("asset_upload_table");
+
 /// ユーザIDテーブル (ユーザ名 => ユーザID)
 pub(in crate::database) static USER_ID_TABLE: TableDefinition<String, UserId> =
     TableDefinition::new("user_id_table");
@@ -125,6 +183,20 @@ pub(in crate::database) static BEARER_TOKEN_ID_TABLE:
     TableDefinition<TokenId, TokenHash> =
         TableDefinition::new("bearer_token_id_table");
 
+/// エクスポート履歴テーブル (エクスポートID => エクスポート履歴情報)
+pub(in crate::database) static EXPORT_HISTORY_TABLE:
+    TableDefinition<Id, ExportHistoryInfo> =
+        TableDefinition::new("export_history_table");
+
+/// アセット実体(blob)を格納するサブディレクトリ名
+pub(in crate::database) const ASSET_BLOB_DIR_NAME: &str = "blobs";
+
+/// 画像アセットの縮小版キャッシュを格納するサブディレクトリ名
+pub(in crate::database) const ASSET_VARIANT_DIR_NAME: &str = "variants";
+
+/// 受信中のアップロードデータを格納するサブディレクトリ名
+pub(in crate::database) const ASSET_UPLOAD_DIR_NAME: &str = "uploads";
+
 /// ルートページのパス
 pub(in crate::database) const ROOT_PAGE_PATH: &str = "/";
 
@@ -195,6 +267,9 @@ pub(crate) enum DbError {
     /// ロック情報に対する権限がない
     LockForbidden,
 
+    /// ロックが管理者により強制解除された
+    LockBroken,
+
     /// amend指定が許可されない
     AmendForbidden,
 
@@ -216,6 +291,23 @@ pub(crate) enum DbError {
     /// アセットがすでに存在する
     AssetAlreadyExists,
 
+    /// アセットがページから参照されている
+    AssetReferenced,
+
+    /// 分割アップロードのセッションが存在しない
+    UploadNotFound,
+
+    /// 分割アップロードの受信位置が一致しない
+    UploadOffsetMismatch {
+        offset: u64,
+    },
+
+    /// 分割アップロードの受信データが宣言サイズを超えた
+    UploadTooLarge,
+
+    /// 分割アップロードの受信が完了していない
+    UploadIncomplete,
+
     /// アセットの移動先ページが削除済み
     #[allow(dead_code)]
     AssetMovePageDeleted,
@@ -250,6 +342,7 @@ impl std::fmt::Display for DbError {
             DbError::DraftPage => write!(f, "draft page"),
             DbError::LockNotFound => write!(f, "lock not found"),
             DbError::LockForbidden => write!(f, "lock forbidden"),
+            DbError::LockBroken => write!(f, "lock broken"),
             DbError::AmendForbidden => write!(f, "amend forbidden"),
             DbError::RevisionConflict => write!(f, "revision conflict"),
             DbError::PageDeleted => write!(f, "page deleted"),
@@ -257,6 +350,13 @@ impl std::fmt::Display for DbError {
             DbError::AssetNotFound => write!(f, "asset not found"),
             DbError::AssetDeleted => write!(f, "asset deleted"),
             DbError::AssetAlreadyExists => write!(f, "asset already exists"),
+            DbError::AssetReferenced => write!(f, "asset is still referenced"),
+            DbError::UploadNotFound => write!(f, "upload not found"),
+            DbError::UploadOffsetMismatch { offset } => {
+                write!(f, "upload offset mismatch: offset={}", offset)
+            }
+            DbError::UploadTooLarge => write!(f, "upload too large"),
+            DbError::UploadIncomplete => write!(f, "upload incomplete"),
             DbError::AssetMovePageDeleted => {
                 write!(f, "asset move page deleted")
             }
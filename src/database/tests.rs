@@ -13,6 +13,7 @@ use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use chrono::{DateTime, Local};
+use data_encoding::BASE32_NOPAD;
 use redb::Database;
 use redb::ReadableDatabase;
 use redb::ReadableTable;
@@ -22,10 +23,16 @@ use redb::TypeName;
 use redb::Value;
 use serde::Serialize;
 
+use super::AssetUploadResult;
 use super::DatabaseManager;
+use super::DbError;
+use super::asset_refs::extract_asset_ref_keys;
 use super::init::init_database;
 use super::link_refs::build_link_refs;
 use super::schema::{
+    ASSET_BLOB_STATE_TABLE,
+    ASSET_BLOB_TABLE,
+    ASSET_INFO_TABLE,
     BEARER_TOKEN_ID_TABLE,
     BEARER_TOKEN_TABLE,
     MCP_PRIMITIVE_NAME_TABLE,
@@ -39,11 +46,15 @@ use super::schema::{
     TEMPLATE_CANDIDATE_TABLE,
 };
 use super::types::{
+    AssetHash,
     AssetId,
+    AssetInfo,
+    AssetUploadTarget,
     BearerScope,
     BearerScopeSet,
     BearerTokenInfo,
     BearerTokenPlaintext,
+    LockoutPolicy,
     McpPrimitiveKind,
     McpPrimitiveNameKey,
     PageId,
@@ -61,10 +72,13 @@ use super::types::{
     UserAttributeSet,
     UserId,
     UserInfo,
+    totp_code,
 };
 use super::manager::bearer_tokens::VerifyBearerTokenFailureReason;
 use super::manager::pages_write::AppendPageRequest;
 use super::{
+    AssetBlobIssue,
+    AssetGcItem,
     ResourceListEntry,
     ResourceListSource,
 };
@@ -76,6 +90,7 @@ use super::resource_list::{
 use crate::export_import::MigrateExportPageSnapshot;
 use crate::export_import::model::{
     ExportAsset,
+    ExportAssetBlob,
     ExportBundle,
     ExportPage,
     ExportRevision,
@@ -6107,6 +6122,463 @@ fn db_bearer_token_delete_user_removes_related_tokens_from_list_and_verify() {
     fs::remove_dir_all(base_dir).expect("cleanup failed");
 }
 
+///
+/// TOTP コード生成が RFC 6238 のテストベクタと一致することを確認する。
+///
+/// # 注記
+/// RFC 6238 付録B の SHA-1 用秘密鍵を用い、T=59 と T=1111111109 の
+/// 8桁期待値の下位6桁と比較する。
+///
+#[test]
+fn totp_code_matches_rfc6238_vectors() {
+    let secret = b"12345678901234567890";
+
+    assert_eq!(totp_code(secret, 59 / 30), 287_082);
+    assert_eq!(totp_code(secret, 1_111_111_109 / 30), 81_804);
+}
+
+///
+/// 二要素認証の登録・照合・解除が一連の操作として機能することを確認する。
+///
+/// # 注記
+/// 登録後に TOTP コードを照合し、古い時間ステップの再利用拒否、
+/// リカバリコードの一回限りの消費、二重登録拒否、解除を順に検証する。
+///
+#[test]
+fn db_user_mfa_enroll_verify_and_disable() {
+    /*
+     * テスト用データベースとユーザを準備する
+     */
+    let (base_dir, db_path) = prepare_test_dirs();
+    let asset_path = base_dir.join("assets");
+    let manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("open manager failed");
+    manager
+        .add_user("alice", "pass", None)
+        .expect("add alice failed");
+
+    /*
+     * 登録して TOTP コードを照合する
+     */
+    let enrollment = manager
+        .enroll_user_mfa("alice", false)
+        .expect("enroll failed");
+    assert_eq!(enrollment.recovery_codes().len(), 10);
+    assert!(enrollment
+        .otpauth_uri("LuWiki", "alice")
+        .starts_with("otpauth://totp/LuWiki:alice?secret="));
+    assert!(manager.enroll_user_mfa("alice", false).is_err());
+
+    let secret = BASE32_NOPAD
+        .decode(enrollment.secret().as_bytes())
+        .expect("decode secret failed");
+    let now = Local::now();
+    let step = (now.timestamp() / 30) as u64;
+    let current = format!("{:06}", totp_code(&secret, step));
+    let previous = format!("{:06}", totp_code(&secret, step - 1));
+
+    assert!(manager
+        .verify_user_mfa_code("alice", &current, now)
+        .expect("verify current failed"));
+    assert!(!manager
+        .verify_user_mfa_code("alice", &previous, now)
+        .expect("verify previous failed"));
+    assert!(!manager
+        .verify_user_mfa_code("alice", "000000x", now)
+        .expect("verify invalid failed"));
+
+    /*
+     * リカバリコードが一回限りで消費されることを検証する
+     */
+    let recovery = enrollment.recovery_codes()[0].to_uppercase();
+    assert!(manager
+        .verify_user_mfa_code("alice", &recovery, now)
+        .expect("verify recovery failed"));
+    assert!(!manager
+        .verify_user_mfa_code("alice", &recovery, now)
+        .expect("verify reused recovery failed"));
+
+    let user = manager
+        .get_user_info_by_name("alice")
+        .expect("get user failed")
+        .expect("user missing");
+    assert!(user.requires_mfa());
+    assert_eq!(user.mfa().expect("mfa missing").recovery_code_count(), 9);
+
+    /*
+     * 解除後は登録情報が無くなることを検証する
+     */
+    manager.disable_user_mfa("alice").expect("disable failed");
+    let user = manager
+        .get_user_info_by_name("alice")
+        .expect("get user failed")
+        .expect("user missing");
+    assert!(!user.is_mfa_enrolled());
+    assert!(!user.requires_mfa());
+    assert!(manager.disable_user_mfa("alice").is_err());
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// ユーザの無効化が Basic認証・Bearer認証の双方を拒否することを確認する。
+///
+/// # 注記
+/// 無効化中は照合が失敗し、再有効化後に元の資格情報で照合できること、
+/// 最終アクティビティ日時が記録間隔内では更新されないことを検証する。
+///
+#[test]
+fn db_user_disable_blocks_basic_and_bearer() {
+    /*
+     * テスト用データベースとユーザ・トークンを準備する
+     */
+    let (base_dir, db_path) = prepare_test_dirs();
+    let asset_path = base_dir.join("assets");
+    let manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("open manager failed");
+    manager
+        .add_user("alice", "pass", None)
+        .expect("add alice failed");
+    let (token, token_info) = manager
+        .create_bearer_token(
+            "alice",
+            BearerScopeSet::from_iter([BearerScope::Read]),
+            PathPrefixSet::new(),
+            chrono::Duration::minutes(30),
+            None,
+        )
+        .expect("create bearer token failed");
+
+    /*
+     * 無効化中は双方の照合が失敗する
+     */
+    manager
+        .edit_user("alice", None, None, None, Some(true), false)
+        .expect("disable failed");
+    assert!(!manager.verify_user("alice", "pass").expect("verify failed"));
+    assert_eq!(
+        manager
+            .verify_bearer_token(&token)
+            .expect("verify bearer failed")
+            .expect_err("bearer verify must fail"),
+        VerifyBearerTokenFailureReason::UserDisabled(token_info.token_id()),
+    );
+
+    /*
+     * 再有効化後は元の資格情報で照合できる
+     */
+    manager
+        .edit_user("alice", None, None, None, Some(false), false)
+        .expect("enable failed");
+    assert!(manager.verify_user("alice", "pass").expect("verify failed"));
+    assert!(manager
+        .verify_bearer_token(&token)
+        .expect("verify bearer failed")
+        .is_ok());
+
+    /*
+     * 最終アクティビティ日時は記録間隔内では更新されない
+     */
+    let now = Local::now();
+    manager
+        .record_user_activity("alice", now)
+        .expect("record activity failed");
+    manager
+        .record_user_activity("alice", now + chrono::Duration::seconds(10))
+        .expect("record activity failed");
+    let user = manager
+        .get_user_info_by_name("alice")
+        .expect("get user failed")
+        .expect("user missing");
+    assert_eq!(user.last_activity(), Some(now));
+    assert!(!user.is_disabled());
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// ログイン失敗の記録が閾値到達後に指数的なロックアウトへ至ることを確認する。
+///
+/// # 注記
+/// 閾値未満ではロックされず、閾値到達後は失敗の都度ロック期間が倍増して
+/// 上限で打ち止めになること、解除操作で失敗記録が消去されることを検証する。
+///
+#[test]
+fn db_user_login_failures_lock_with_backoff() {
+    let (base_dir, db_path) = prepare_test_dirs();
+    let asset_path = base_dir.join("assets");
+    let manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("open manager failed");
+    manager
+        .add_user("alice", "pass", None)
+        .expect("add alice failed");
+    let policy = LockoutPolicy::new(
+        3,
+        chrono::Duration::seconds(10),
+        chrono::Duration::seconds(30),
+    );
+    let now = Local::now();
+
+    /*
+     * 閾値未満ではロックされない
+     */
+    for _ in 0..2 {
+        let locked = manager
+            .record_user_login_failure("alice", now, &policy)
+            .expect("record failure failed");
+        assert_eq!(locked, None);
+    }
+
+    /*
+     * 閾値到達後はロック期間が倍増し上限で打ち止めになる
+     */
+    for expected in [10, 20, 30, 30] {
+        let locked = manager
+            .record_user_login_failure("alice", now, &policy)
+            .expect("record failure failed");
+        assert_eq!(locked, Some(now + chrono::Duration::seconds(expected)));
+    }
+
+    let user = manager
+        .get_user_info_by_name("alice")
+        .expect("get user failed")
+        .expect("user missing");
+    assert_eq!(user.failed_logins(), 6);
+    assert!(user.is_locked(now));
+    assert!(!user.is_locked(now + chrono::Duration::seconds(30)));
+
+    /*
+     * 存在しないユーザは記録されない
+     */
+    assert_eq!(
+        manager
+            .record_user_login_failure("bob", now, &policy)
+            .expect("record failure failed"),
+        None,
+    );
+
+    /*
+     * 解除操作で失敗記録が消去される
+     */
+    assert!(manager.unlock_user("alice").expect("unlock failed"));
+    assert!(!manager.unlock_user("alice").expect("unlock failed"));
+    assert!(manager.unlock_user("bob").is_err());
+
+    let user = manager
+        .get_user_info_by_name("alice")
+        .expect("get user failed")
+        .expect("user missing");
+    assert_eq!(user.failed_logins(), 0);
+    assert!(!user.is_locked(now));
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// ユーザ情報・属性・無効化状態・ロックアウト解除を一括で更新できる
+/// ことを確認する。
+///
+/// # 注記
+/// 存在しないユーザの指定時はエラーとなることも検証する。
+///
+#[test]
+fn db_edit_user_applies_all_changes() {
+    let (base_dir, db_path) = prepare_test_dirs();
+    let asset_path = base_dir.join("assets");
+    let manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("open manager failed");
+    let now = Local::now();
+    let policy = LockoutPolicy::new(
+        1,
+        chrono::Duration::seconds(10),
+        chrono::Duration::seconds(30),
+    );
+    manager
+        .add_user("alice", "pass", None)
+        .expect("add alice failed");
+    manager
+        .record_user_login_failure("alice", now, &policy)
+        .expect("record failure failed");
+
+    manager
+        .edit_user(
+            "alice",
+            Some("Alice"),
+            Some("newpass"),
+            Some(UserAttributeSet::from_iter([UserAttribute::ReadOnly])),
+            Some(true),
+            true,
+        )
+        .expect("edit user failed");
+
+    let user = manager
+        .get_user_info_by_name("alice")
+        .expect("get user failed")
+        .expect("user missing");
+    assert_eq!(user.display_name(), "Alice");
+    assert!(user.attributes().contains(UserAttribute::ReadOnly));
+    assert!(user.is_disabled());
+    assert_eq!(user.failed_logins(), 0);
+
+    manager
+        .edit_user("alice", None, None, None, Some(false), false)
+        .expect("enable user failed");
+    assert!(manager.verify_user("alice", "newpass").expect("verify failed"));
+
+    assert!(manager
+        .edit_user("bob", Some("Bob"), None, None, Some(true), false)
+        .is_err());
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// Bearerトークンのローテーション後、猶予期限まで旧平文でも
+/// 認証できることを確認する。
+///
+/// # 注記
+/// トークンIDが維持され一覧に猶予エントリが現れないこと、失効が旧平文
+/// にも反映されること、猶予切れや猶予なしの旧平文が拒否されること、
+/// 不要になった猶予エントリが掃除されることを検証する。
+///
+#[test]
+fn db_bearer_token_rotation_keeps_previous_hash_during_grace() {
+    /*
+     * テスト用データベースとトークンを準備する
+     */
+    let (base_dir, db_path) = prepare_test_dirs();
+    let asset_path = base_dir.join("assets");
+    let manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("open manager failed");
+    manager
+        .add_user("alice", "pass", None)
+        .expect("add alice failed");
+    let (old_plaintext, info) = manager
+        .create_bearer_token(
+            "alice",
+            BearerScopeSet::from_iter([BearerScope::Read]),
+            PathPrefixSet::new(),
+            chrono::Duration::hours(12),
+            None,
+        )
+        .expect("create bearer token failed");
+    let token_id = info.token_id();
+
+    /*
+     * 猶予付きでローテーションすると新旧いずれの平文でも認証できる
+     */
+    let rotated = manager
+        .rotate_bearer_token(
+            &token_id,
+            chrono::Duration::hours(1),
+            Local::now(),
+        )
+        .expect("rotate bearer token failed");
+    let new_plaintext = rotated.plaintext().clone();
+    assert_eq!(rotated.token_info().token_id(), token_id);
+    assert!(rotated.token_info().rotated_at().is_some());
+    assert!(rotated.grace_until().is_some());
+
+    for plaintext in [&old_plaintext, &new_plaintext] {
+        let verified = manager
+            .verify_bearer_token(plaintext)
+            .expect("verify rotated token failed")
+            .expect("rotated token should be valid");
+        assert_eq!(verified.token_info().token_id(), token_id);
+        assert!(!verified.token_info().is_rotation_grace());
+    }
+
+    let listed = manager
+        .list_bearer_tokens()
+        .expect("list bearer tokens failed");
+    assert_eq!(listed.len(), 1);
+
+    /*
+     * 猶予期間中の失効は旧平文にも反映される
+     */
+    manager
+        .revoke_bearer_token_by_id(&token_id)
+        .expect("revoke bearer token failed");
+    assert!(matches!(
+        manager
+            .verify_bearer_token(&old_plaintext)
+            .expect("verify old token after revoke failed"),
+        Err(VerifyBearerTokenFailureReason::Revoked(_))
+    ));
+    assert!(manager
+        .rotate_bearer_token(
+            &token_id,
+            chrono::Duration::hours(1),
+            Local::now(),
+        )
+        .is_err());
+
+    /*
+     * 猶予切れ・猶予なしの旧平文は拒否される
+     */
+    let (old_plaintext, info) = manager
+        .create_bearer_token(
+            "alice",
+            BearerScopeSet::from_iter([BearerScope::Read]),
+            PathPrefixSet::new(),
+            chrono::Duration::hours(12),
+            None,
+        )
+        .expect("create bearer token failed");
+    let token_id = info.token_id();
+    let rotated = manager
+        .rotate_bearer_token(
+            &token_id,
+            chrono::Duration::minutes(30),
+            Local::now() - chrono::Duration::hours(1),
+        )
+        .expect("rotate bearer token failed");
+    assert!(matches!(
+        manager
+            .verify_bearer_token(&old_plaintext)
+            .expect("verify lapsed token failed"),
+        Err(VerifyBearerTokenFailureReason::Expired(_))
+    ));
+
+    let middle_plaintext = rotated.plaintext().clone();
+    let rotated = manager
+        .rotate_bearer_token(&token_id, chrono::Duration::zero(), Local::now())
+        .expect("rotate bearer token without grace failed");
+    assert!(rotated.grace_until().is_none());
+    assert!(matches!(
+        manager
+            .verify_bearer_token(&middle_plaintext)
+            .expect("verify replaced token failed"),
+        Err(VerifyBearerTokenFailureReason::Unissued)
+    ));
+    assert!(manager
+        .verify_bearer_token(rotated.plaintext())
+        .expect("verify current token failed")
+        .is_ok());
+
+    /*
+     * 失効済みトークンの削除で猶予エントリも削除され、再ローテーション
+     * で猶予切れエントリが掃除されている
+     */
+    assert_eq!(
+        manager
+            .purge_bearer_tokens(false, true, Local::now())
+            .expect("purge revoked tokens failed"),
+        1,
+    );
+    drop(manager);
+    let db = Database::create(&db_path).expect("reopen db failed");
+    let txn = db.begin_read().expect("begin read failed");
+    let token_table = txn
+        .open_table(BEARER_TOKEN_TABLE)
+        .expect("open bearer token table failed");
+    assert_eq!(token_table.len().expect("count tokens failed"), 1);
+    drop(txn);
+    drop(db);
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
 ///
 /// テスト用の一時ディレクトリとDBパスを生成する。
 ///
@@ -6254,18 +6726,11 @@ fn current_page_state_can_be_resolved_by_path() {
 }
 
 ///
-/// 複数ページIDの current path 情報を一括取得できることを
-/// 確認する。
-///
-/// # 注記
-/// 通常ページと draft ページを作成し、
-/// `get_current_page_paths_by_ids` の戻り値を検証する。
+/// ロックの強制解除がドラフトページを残し、元の保持者へ強制解除を
+/// 通知できることを確認する。
 ///
 #[test]
-fn current_page_paths_can_be_resolved_by_ids() {
-    /*
-     * テスト用データベースと対象ユーザを準備する
-     */
+fn db_break_lock_keeps_draft_page() {
     let (base_dir, db_path) = prepare_test_dirs();
     let asset_path = base_dir.join("assets");
     let manager = DatabaseManager::open(&db_path, &asset_path)
@@ -6275,28 +6740,115 @@ fn current_page_paths_can_be_resolved_by_ids() {
         .expect("add user failed");
 
     /*
-     * 通常ページと draft ページを作成する
+     * ドラフトページのロックを強制解除する
      */
-    let page_id = manager
-        .create_page("/mcp/page2", "user", "# page2".to_string())
-        .expect("create page failed");
-    let (draft_page_id, _) = manager
-        .create_draft_page("/mcp/draft2", "user")
+    let (page_id, lock_info) = manager
+        .create_draft_page("/draft/broken", "user")
         .expect("create draft page failed");
+    let token = lock_info.token();
+
+    let broken = manager
+        .break_lock(&token)
+        .expect("break lock failed")
+        .expect("lock missing");
+    assert_eq!(broken.page(), page_id);
+    assert!(manager.break_lock(&token).expect("break lock failed").is_none());
 
     /*
-     * 一括解決結果を検証する
+     * ロック情報の参照や掃除ではドラフトページが削除されない
      */
-    let paths = manager
-        .get_current_page_paths_by_ids(&[
-            page_id.clone(),
-            draft_page_id.clone(),
-            PageId::new(),
-        ])
-        .expect("resolve current paths failed");
-    let page_info = paths.get(&page_id).expect("page path info missing");
-    assert_eq!(page_info.current_path(), "/mcp/page2");
-    assert!(!page_info.deleted());
+    assert!(manager
+        .get_page_lock_info(&page_id)
+        .expect("get lock info failed")
+        .is_none());
+    manager
+        .cleanup_expired_locks()
+        .expect("cleanup expired locks failed");
+    assert!(manager
+        .get_page_index_by_id(&page_id)
+        .expect("get page index failed")
+        .expect("draft page missing")
+        .is_draft());
+
+    /*
+     * 元のトークンでの延長と解除は強制解除として拒否される
+     */
+    assert!(manager.is_broken_lock(&token).expect("check broken lock failed"));
+    let err = manager
+        .renew_page_lock(&page_id, "user", &token)
+        .expect_err("renew must fail");
+    assert!(matches!(err.downcast_ref::<DbError>(), Some(DbError::LockBroken)));
+    let err = manager
+        .release_page_lock(&page_id, "user", &token)
+        .expect_err("release must fail");
+    assert!(matches!(err.downcast_ref::<DbError>(), Some(DbError::LockBroken)));
+
+    /*
+     * ロックを取得し直して保存を続けられる
+     */
+    let lock_info = manager
+        .acquire_page_lock(&page_id, "user")
+        .expect("acquire lock failed");
+    manager
+        .put_page(&page_id, "user", "# recovered".to_string(), false)
+        .expect("put page failed");
+    manager
+        .release_page_lock(&page_id, "user", &lock_info.token())
+        .expect("release lock failed");
+    let index = manager
+        .get_page_index_by_id(&page_id)
+        .expect("get page index failed")
+        .expect("page missing");
+    assert!(!index.is_draft());
+    assert_eq!(index.latest(), 1);
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// 複数ページIDの current path 情報を一括取得できることを
+/// 確認する。
+///
+/// # 注記
+/// 通常ページと draft ページを作成し、
+/// `get_current_page_paths_by_ids` の戻り値を検証する。
+///
+#[test]
+fn current_page_paths_can_be_resolved_by_ids() {
+    /*
+     * テスト用データベースと対象ユーザを準備する
+     */
+    let (base_dir, db_path) = prepare_test_dirs();
+    let asset_path = base_dir.join("assets");
+    let manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("open manager failed");
+    manager
+        .add_user("user", "pass", None)
+        .expect("add user failed");
+
+    /*
+     * 通常ページと draft ページを作成する
+     */
+    let page_id = manager
+        .create_page("/mcp/page2", "user", "# page2".to_string())
+        .expect("create page failed");
+    let (draft_page_id, _) = manager
+        .create_draft_page("/mcp/draft2", "user")
+        .expect("create draft page failed");
+
+    /*
+     * 一括解決結果を検証する
+     */
+    let paths = manager
+        .get_current_page_paths_by_ids(&[
+            page_id.clone(),
+            draft_page_id.clone(),
+            PageId::new(),
+        ])
+        .expect("resolve current paths failed");
+    let page_info = paths.get(&page_id).expect("page path info missing");
+    assert_eq!(page_info.current_path(), "/mcp/page2");
+    assert!(!page_info.deleted());
     assert!(!page_info.draft());
 
     let draft_info = paths
@@ -6486,6 +7038,63 @@ fn append_page_by_id_rejects_revision_conflict() {
     fs::remove_dir_all(base_dir).expect("cleanup failed");
 }
 
+///
+/// `put_page_if_latest` が最新 revision 不一致を
+/// 競合として拒否することを確認する。
+///
+/// # 注記
+/// 保存前に別更新で revision を進め、期待 revision が古い要求を失敗させる。
+/// 期待 revision が最新と一致する要求は新規 revision として保存される。
+///
+#[test]
+fn put_page_if_latest_rejects_moved_head() {
+    /*
+     * テスト用データベースと対象ページを準備する
+     */
+    let (base_dir, db_path) = prepare_test_dirs();
+    let asset_path = base_dir.join("assets");
+    let manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("open manager failed");
+    manager
+        .add_user("user", "pass", None)
+        .expect("add user failed");
+    let page_id = manager
+        .create_page("/merge/head", "user", "# base".to_string())
+        .expect("create page failed");
+
+    /*
+     * 先行更新で latest revision を進める
+     */
+    manager
+        .put_page(&page_id, "user", "# base\nv2".to_string(), false)
+        .expect("put page failed");
+
+    /*
+     * 古い revision 前提の保存が拒否されることを検証する
+     */
+    let err = manager
+        .put_page_if_latest(&page_id, "user", "# stale".to_string(), 1)
+        .expect_err("put page should fail");
+    assert!(matches!(
+        err.downcast_ref::<super::schema::DbError>(),
+        Some(super::schema::DbError::RevisionConflict),
+    ));
+
+    /*
+     * 最新 revision 前提の保存が成功することを検証する
+     */
+    manager
+        .put_page_if_latest(&page_id, "user", "# base\nv3".to_string(), 2)
+        .expect("put page if latest failed");
+    let index = manager
+        .get_page_index_by_id(&page_id)
+        .expect("get page index failed")
+        .expect("page index missing");
+    assert_eq!(index.latest(), 3);
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
 ///
 /// `append` 競合確認 API が最新 revision・更新者・ロック状態を
 /// 返せることを確認する。
@@ -6662,7 +7271,7 @@ fn export_import_low_level_db_api_works() {
         .expect("create resource page failed");
 
     let read_set = manager
-        .collect_export_read_set("/tree", true)
+        .collect_export_read_set("/tree", true, None)
         .expect("collect export read set failed");
     assert_eq!(read_set.pages.len(), 2);
     assert_eq!(read_set.revisions.len(), 2);
@@ -6744,19 +7353,22 @@ fn import_bundle_and_asset_staging_api_work() {
     let page_id = PageId::new();
     let resource_page_id = PageId::new();
     let asset_id = AssetId::new();
+    let asset_hash = AssetHash::from_data(b"hello");
     let timestamp = Local::now();
     let staged_path = manager
-        .stage_asset_blob(&asset_id, b"hello")
+        .stage_asset_blob(&asset_hash, b"hello")
         .expect("stage asset failed");
     assert!(staged_path.exists());
-    manager
-        .commit_staged_asset_blob(&staged_path, &asset_id)
-        .expect("commit staged asset failed");
+    assert!(
+        manager
+            .commit_staged_asset_blob(&staged_path, &asset_hash)
+            .expect("commit staged asset failed")
+    );
     assert!(!staged_path.exists());
 
-    let discard_asset_id = AssetId::new();
+    let discard_hash = AssetHash::from_data(b"discard");
     let discard_path = manager
-        .stage_asset_blob(&discard_asset_id, b"discard")
+        .stage_asset_blob(&discard_hash, b"discard")
         .expect("stage discard asset failed");
     manager
         .discard_staged_asset_blob(&discard_path)
@@ -6812,8 +7424,16 @@ fn import_bundle_and_asset_staging_api_work() {
         file_name: "hello.txt".to_string(),
         mime: "text/plain".to_string(),
         size: 5,
+        sha256: None,
+        revision: 1,
         user: user_id.clone(),
         timestamp,
+        history: Vec::new(),
+    });
+    bundle.asset_blobs.push(ExportAssetBlob {
+        asset_id: asset_id.clone(),
+        revision: None,
+        data: b"hello".to_vec(),
     });
     bundle.sync_manifest_counts();
 
@@ -6859,10 +7479,852 @@ fn import_bundle_and_asset_staging_api_work() {
             .expect("read asset data failed"),
         b"hello".to_vec()
     );
+    assert_eq!(
+        manager
+            .get_asset_info_by_id(&asset_id)
+            .expect("get asset info failed")
+            .expect("asset info missing")
+            .hash(),
+        Some(asset_hash)
+    );
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// 同一内容のアセットが一つの実体を参照数付きで共有することを確認する。
+///
+#[test]
+fn asset_blob_store_deduplicates_and_counts_references() {
+    let (base_dir, db_path) = prepare_test_dirs();
+    let asset_path = base_dir.join("assets");
+    let manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("open manager failed");
+
+    manager
+        .add_user("user", "pass", None)
+        .expect("add user failed");
+    let page_id = manager
+        .create_page("/dedup", "user", "# dedup".to_string())
+        .expect("create page failed");
+    let first_id = manager
+        .create_asset(&page_id, "a.txt", "text/plain", "user", b"same")
+        .expect("create first asset failed");
+    let second_id = manager
+        .create_asset(&page_id, "b.txt", "text/plain", "user", b"same")
+        .expect("create second asset failed");
+
+    /*
+     * 二つのアセットが同一の実体を参照する
+     */
+    let hash = AssetHash::from_data(b"same");
+    let blob_path = manager.asset_blob_path(&hash);
+    assert!(blob_path.is_file());
+    assert_eq!(asset_blob_ref_count_for_test(&manager, &hash), Some(2));
+    assert!(
+        manager
+            .check_asset_blobs(false)
+            .expect("check asset blobs failed")
+            .is_empty()
+    );
+
+    /*
+     * 一方を削除しても実体は残り、両方削除すると実体も削除される
+     */
+    manager
+        .delete_asset_hard(&first_id)
+        .expect("delete first asset failed");
+    assert!(blob_path.is_file());
+    assert_eq!(asset_blob_ref_count_for_test(&manager, &hash), Some(1));
+    assert_eq!(
+        manager
+            .read_asset_data(&second_id)
+            .expect("read second asset failed"),
+        b"same".to_vec()
+    );
+
+    manager
+        .delete_asset_hard(&second_id)
+        .expect("delete second asset failed");
+    assert!(!blob_path.exists());
+    assert_eq!(asset_blob_ref_count_for_test(&manager, &hash), None);
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// アセットの差し替えで履歴が残り、ロールバックと削除で実体の参照数が
+/// 正しく更新されることを確認する。
+///
+#[test]
+fn asset_revisions_keep_history_and_rollback() {
+    let (base_dir, db_path) = prepare_test_dirs();
+    let asset_path = base_dir.join("assets");
+    let manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("open manager failed");
+
+    manager
+        .add_user("user", "pass", None)
+        .expect("add user failed");
+    let page_id = manager
+        .create_page("/revision", "user", "# revision".to_string())
+        .expect("create page failed");
+    let asset_id = manager
+        .create_asset(&page_id, "a.txt", "text/plain", "user", b"first")
+        .expect("create asset failed");
+
+    /*
+     * 差し替えで新しいリビジョンが作成され、旧内容は履歴から読める
+     */
+    let revision = manager
+        .replace_asset_data(&asset_id, "text/markdown", "user", b"second")
+        .expect("replace asset failed");
+    assert_eq!(revision, 2);
+    manager
+        .replace_asset_data(&asset_id, "text/plain", "user", b"third")
+        .expect("replace asset failed");
+
+    let info = manager
+        .get_asset_info_by_id(&asset_id)
+        .expect("get asset info failed")
+        .expect("asset info missing");
+    assert_eq!(info.revision(), 3);
+    assert_eq!(info.size(), 5);
+    assert_eq!(
+        manager.read_asset_data(&asset_id).expect("read asset failed"),
+        b"third".to_vec()
+    );
+
+    let revisions = manager
+        .list_asset_revisions(&asset_id)
+        .expect("list revisions failed");
+    assert_eq!(
+        revisions
+            .iter()
+            .map(|revision| revision.revision())
+            .collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+
+    let (revision_info, data) = manager
+        .read_asset_revision_data(&asset_id, 2)
+        .expect("read revision failed");
+    assert_eq!(revision_info.mime(), "text/markdown");
+    assert_eq!(data, b"second".to_vec());
+
+    let err = manager
+        .read_asset_revision_data(&asset_id, 4)
+        .expect_err("unknown revision must fail");
+    assert!(matches!(
+        err.downcast_ref::<super::schema::DbError>(),
+        Some(super::schema::DbError::InvalidRevision)
+    ));
+
+    /*
+     * ロールバックで新しいリビジョンの実体は解放される
+     */
+    let second_hash = AssetHash::from_data(b"second");
+    let third_hash = AssetHash::from_data(b"third");
+    manager
+        .rollback_asset(&asset_id, 1)
+        .expect("rollback asset failed");
+    assert_eq!(
+        manager.read_asset_data(&asset_id).expect("read asset failed"),
+        b"first".to_vec()
+    );
+    assert_eq!(
+        manager
+            .list_asset_revisions(&asset_id)
+            .expect("list revisions failed")
+            .len(),
+        1
+    );
+    assert!(!manager.asset_blob_path(&second_hash).exists());
+    assert!(!manager.asset_blob_path(&third_hash).exists());
+    assert!(
+        manager
+            .check_asset_blobs(false)
+            .expect("check asset blobs failed")
+            .is_empty()
+    );
+
+    /*
+     * 完全削除で履歴を含む全ての実体が解放される
+     */
+    manager
+        .replace_asset_data(&asset_id, "text/plain", "user", b"second")
+        .expect("replace asset failed");
+    manager
+        .delete_asset_hard(&asset_id)
+        .expect("delete asset failed");
+    let first_hash = AssetHash::from_data(b"first");
+    assert_eq!(asset_blob_ref_count_for_test(&manager, &first_hash), None);
+    assert_eq!(asset_blob_ref_count_for_test(&manager, &second_hash), None);
+    assert!(!manager.asset_blob_path(&first_hash).exists());
+    assert!(!manager.asset_blob_path(&second_hash).exists());
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// asset fsck の検査処理が実体の破損と不要な実体を検出・修復することを
+/// 確認する。
+///
+#[test]
+fn check_asset_blobs_detects_corruption_and_repairs_unreferenced() {
+    let (base_dir, db_path) = prepare_test_dirs();
+    let asset_path = base_dir.join("assets");
+    let manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("open manager failed");
+
+    manager
+        .add_user("user", "pass", None)
+        .expect("add user failed");
+    let page_id = manager
+        .create_page("/fsck", "user", "# fsck".to_string())
+        .expect("create page failed");
+    manager
+        .create_asset(&page_id, "a.txt", "text/plain", "user", b"original")
+        .expect("create asset failed");
+
+    /*
+     * 実体の改竄と孤立した実体の配置
+     */
+    let hash = AssetHash::from_data(b"original");
+    fs::write(manager.asset_blob_path(&hash), b"tampered")
+        .expect("tamper blob failed");
+
+    let orphan_hash = AssetHash::from_data(b"orphan");
+    let orphan_path = manager.asset_blob_path(&orphan_hash);
+    fs::create_dir_all(orphan_path.parent().expect("parent missing"))
+        .expect("create orphan dir failed");
+    fs::write(&orphan_path, b"orphan").expect("write orphan failed");
+
+    let issues = manager
+        .check_asset_blobs(true)
+        .expect("check asset blobs failed");
+    assert!(issues.contains(&AssetBlobIssue::BlobCorrupted(hash)));
+    assert!(issues.contains(&AssetBlobIssue::Unreferenced(orphan_hash)));
+    assert!(!orphan_path.exists());
 
     fs::remove_dir_all(base_dir).expect("cleanup failed");
 }
 
+///
+/// 旧形式(アセットID単位)のアセットファイルが起動時に blob へ移行される
+/// ことを確認する。
+///
+#[test]
+fn open_migrates_legacy_asset_files_to_blob_store() {
+    let (base_dir, db_path) = prepare_test_dirs();
+    let asset_path = base_dir.join("assets");
+    let manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("open manager failed");
+
+    manager
+        .add_user("user", "pass", None)
+        .expect("add user failed");
+    let page_id = manager
+        .create_page("/legacy", "user", "# legacy".to_string())
+        .expect("create page failed");
+    let asset_id = manager
+        .create_asset(&page_id, "old.txt", "text/plain", "user", b"legacy")
+        .expect("create asset failed");
+    let hash = AssetHash::from_data(b"legacy");
+    let blob_path = manager.asset_blob_path(&hash);
+    let legacy_path = manager.asset_file_path(&asset_id);
+    drop(manager);
+
+    /*
+     * 旧形式の保存状態を再現する(移行状態の記録が無い旧データベース)
+     */
+    revert_asset_to_legacy_for_test(&db_path, &asset_id, &hash, true);
+    fs::create_dir_all(legacy_path.parent().expect("parent missing"))
+        .expect("create legacy dir failed");
+    fs::rename(&blob_path, &legacy_path).expect("move blob failed");
+
+    /*
+     * 再オープンで移行される
+     */
+    let manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("reopen manager failed");
+    assert!(blob_path.is_file());
+    assert!(!legacy_path.exists());
+    assert_eq!(asset_blob_ref_count_for_test(&manager, &hash), Some(1));
+    assert_eq!(
+        manager
+            .read_asset_data(&asset_id)
+            .expect("read asset failed"),
+        b"legacy".to_vec()
+    );
+    drop(manager);
+
+    /*
+     * 移行済みのデータベースでは再走査しない
+     */
+    revert_asset_to_legacy_for_test(&db_path, &asset_id, &hash, false);
+    fs::create_dir_all(legacy_path.parent().expect("parent missing"))
+        .expect("create legacy dir failed");
+    fs::rename(&blob_path, &legacy_path).expect("move blob failed");
+
+    let _manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("reopen manager failed");
+    assert!(legacy_path.is_file());
+    assert!(!blob_path.exists());
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// アセット情報を内容ハッシュ値を持たない旧形式の状態へ戻す
+///
+/// # 引数
+/// * `db_path` - データベースファイルのパス
+/// * `asset_id` - 対象のアセットID
+/// * `hash` - 対象アセットの内容ハッシュ値
+/// * `clear_state` - 移行状態の記録も消去する場合は`true`
+///
+fn revert_asset_to_legacy_for_test(
+    db_path: &Path,
+    asset_id: &AssetId,
+    hash: &AssetHash,
+    clear_state: bool,
+) {
+    let db = Database::create(db_path).expect("open db failed");
+    let txn = db.begin_write().expect("begin write failed");
+    {
+        let mut info_table =
+            txn.open_table(ASSET_INFO_TABLE).expect("open info failed");
+        let info = info_table
+            .get(asset_id.clone())
+            .expect("get info failed")
+            .expect("info missing")
+            .value();
+        let legacy_info = AssetInfo::new_import(
+            info.id(),
+            None,
+            info.page_id(),
+            info.file_name(),
+            info.mime(),
+            info.size(),
+            None,
+            info.user(),
+            info.timestamp(),
+            info.deleted(),
+        );
+        info_table
+            .insert(asset_id.clone(), legacy_info)
+            .expect("insert info failed");
+        let mut blob_table =
+            txn.open_table(ASSET_BLOB_TABLE).expect("open blob failed");
+        blob_table.remove(*hash).expect("remove blob failed");
+
+        if clear_state {
+            let mut state_table = txn
+                .open_table(ASSET_BLOB_STATE_TABLE)
+                .expect("open state failed");
+            state_table.remove(0).expect("remove state failed");
+        }
+    }
+    txn.commit().expect("commit failed");
+}
+
+///
+/// 画像アセットの寸法が記録され、縮小版キャッシュが生成・破棄されることを
+/// 確認する。
+///
+#[test]
+fn asset_image_variants_are_cached_and_invalidated() {
+    let (base_dir, db_path) = prepare_test_dirs();
+    let asset_path = base_dir.join("assets");
+    let manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("open manager failed");
+
+    manager
+        .add_user("user", "pass", None)
+        .expect("add user failed");
+    let page_id = manager
+        .create_page("/image", "user", "# image".to_string())
+        .expect("create page failed");
+
+    let image = image::DynamicImage::ImageRgb8(image::RgbImage::new(800, 600));
+    let mut data = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut data), image::ImageFormat::Png)
+        .expect("encode png failed");
+
+    let asset_id = manager
+        .create_asset(&page_id, "a.png", "image/png", "user", &data)
+        .expect("create asset failed");
+    let text_id = manager
+        .create_asset(&page_id, "a.txt", "text/plain", "user", b"text")
+        .expect("create text asset failed");
+
+    /*
+     * 画像の場合のみ寸法が記録される
+     */
+    let info = manager
+        .get_asset_info_by_id(&asset_id)
+        .expect("get asset info failed")
+        .expect("asset info missing");
+    assert_eq!(info.dimensions(), Some((800, 600)));
+
+    let text_info = manager
+        .get_asset_info_by_id(&text_id)
+        .expect("get asset info failed")
+        .expect("asset info missing");
+    assert_eq!(text_info.dimensions(), None);
+
+    /*
+     * 縮小版が生成されキャッシュに保存される
+     */
+    let hash = AssetHash::from_data(&data);
+    let (variant, format) = manager
+        .load_asset_variant(&hash, "image/png", 320)
+        .expect("load variant failed")
+        .expect("variant missing");
+    assert_eq!(format, crate::asset_image::VariantFormat::Png);
+    assert_eq!(
+        crate::asset_image::probe_dimensions("image/png", &variant),
+        Some((320, 240))
+    );
+
+    let variant_path = manager.asset_variant_path(&hash, 320, format);
+    assert!(variant_path.is_file());
+
+    assert!(
+        manager
+            .load_asset_variant(&hash, "image/png", 960)
+            .expect("load variant failed")
+            .is_none()
+    );
+
+    /*
+     * 削除時にキャッシュが破棄される
+     */
+    manager
+        .delete_asset(&asset_id)
+        .expect("delete asset failed");
+    assert!(!variant_path.exists());
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// 分割アップロードのセッションで受信したデータがアセットとして登録される
+/// ことを確認する。
+///
+#[test]
+fn asset_upload_session_appends_and_completes() {
+    let (base_dir, db_path) = prepare_test_dirs();
+    let asset_path = base_dir.join("assets");
+    let manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("open manager failed");
+
+    manager
+        .add_user("user", "pass", None)
+        .expect("add user failed");
+    manager
+        .add_user("other", "pass", None)
+        .expect("add user failed");
+    let page_id = manager
+        .create_page("/upload", "user", "# upload".to_string())
+        .expect("create page failed");
+
+    let upload_id = manager
+        .create_asset_upload(
+            "user",
+            AssetUploadTarget::NewAsset {
+                page_id: page_id.clone(),
+                file_name: "large.bin".to_string(),
+            },
+            "application/octet-stream",
+            10,
+        )
+        .expect("create upload failed");
+
+    /*
+     * 分割した追記と受信位置の検証
+     */
+    let append = |offset: u64, data: &[u8]| {
+        let chunk_path = manager
+            .new_asset_staging_path()
+            .expect("staging path failed");
+        fs::write(&chunk_path, data).expect("write chunk failed");
+        let result =
+            manager.append_asset_upload(&upload_id, "user", offset, &chunk_path);
+        assert!(!chunk_path.exists());
+        result
+    };
+
+    assert_eq!(append(0, b"01234").expect("append failed"), 5);
+
+    let err = append(0, b"01234").expect_err("offset mismatch must fail");
+    assert!(matches!(
+        err.downcast_ref::<super::schema::DbError>(),
+        Some(super::schema::DbError::UploadOffsetMismatch { offset: 5 })
+    ));
+
+    let err = append(5, b"567890").expect_err("oversize must fail");
+    assert!(matches!(
+        err.downcast_ref::<super::schema::DbError>(),
+        Some(super::schema::DbError::UploadTooLarge)
+    ));
+
+    let err = manager
+        .complete_asset_upload(&upload_id, "user")
+        .expect_err("incomplete upload must fail");
+    assert!(matches!(
+        err.downcast_ref::<super::schema::DbError>(),
+        Some(super::schema::DbError::UploadIncomplete)
+    ));
+
+    assert!(manager.get_asset_upload(&upload_id, "other").is_err());
+    assert_eq!(append(5, b"56789").expect("append failed"), 10);
+
+    /*
+     * 完了によりアセットが登録されセッションは破棄される
+     */
+    let asset_id = match manager
+        .complete_asset_upload(&upload_id, "user")
+        .expect("complete upload failed")
+    {
+        AssetUploadResult::Created(asset_id) => asset_id,
+        AssetUploadResult::Replaced { .. } => panic!("unexpected replace"),
+    };
+
+    assert_eq!(
+        manager.read_asset_data(&asset_id).expect("read asset failed"),
+        b"0123456789".to_vec()
+    );
+    assert!(manager.get_asset_upload(&upload_id, "user").is_err());
+    assert_eq!(
+        fs::read_dir(asset_path.join("uploads"))
+            .expect("read upload dir failed")
+            .count(),
+        0
+    );
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// ページソース中のアセット参照記法から参照先キーを抽出できることを
+/// 確認する。
+///
+#[test]
+fn extract_asset_ref_keys_resolves_page_and_shared_refs() {
+    let source = concat!(
+        "![logo](asset::logo.png)\n",
+        "[doc](asset:./child:spec%20v1.pdf \"title\")\n",
+        "![[asset:/other:diagram.svg]]\n",
+        "![[asset:local.txt|alias]]\n",
+        "[page](/not/asset) asset:ignored.png\n",
+    );
+
+    let keys = extract_asset_ref_keys("/base", source);
+    let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+
+    assert_eq!(
+        keys,
+        vec![
+            "/base/child:spec v1.pdf",
+            "/base:local.txt",
+            "/other:diagram.svg",
+            ":logo.png",
+        ]
+    );
+}
+
+///
+/// 共有アセットがページに所属せずファイル名で登録・解決され、同名の
+/// 登録が競合として扱われることを確認する。
+///
+#[test]
+fn shared_assets_are_registered_by_name() {
+    let (base_dir, db_path) = prepare_test_dirs();
+    let asset_path = base_dir.join("assets");
+    let manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("open manager failed");
+
+    manager
+        .add_user("user", "pass", None)
+        .expect("add user failed");
+    let asset_id = manager
+        .create_shared_asset("logo.png", "image/png", "user", b"logo")
+        .expect("create shared asset failed");
+
+    /*
+     * 共有アセットとして登録され、ファイル名で解決できる
+     */
+    let info = manager
+        .get_asset_info_by_id(&asset_id)
+        .expect("get asset info failed")
+        .expect("asset info missing");
+    assert!(info.is_shared());
+    assert!(!info.is_zombie());
+    assert_eq!(info.page_id(), None);
+    assert_eq!(
+        manager
+            .get_shared_asset_id_by_name("logo.png")
+            .expect("lookup shared asset failed"),
+        Some(asset_id.clone())
+    );
+
+    let err = manager
+        .create_shared_asset("logo.png", "image/png", "user", b"other")
+        .expect_err("duplicate shared asset must fail");
+    assert!(matches!(
+        err.downcast_ref::<DbError>(),
+        Some(DbError::AssetAlreadyExists)
+    ));
+
+    /*
+     * 削除と復帰で名前の登録が解除・再登録される
+     */
+    manager.delete_asset(&asset_id).expect("delete asset failed");
+    assert_eq!(
+        manager
+            .get_shared_asset_id_by_name("logo.png")
+            .expect("lookup shared asset failed"),
+        None
+    );
+    manager
+        .undelete_asset(&asset_id, None)
+        .expect("undelete asset failed");
+    assert_eq!(
+        manager
+            .get_shared_asset_id_by_name("logo.png")
+            .expect("lookup shared asset failed"),
+        Some(asset_id)
+    );
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// アセット参照の逆引き索引がページの保存・改名・削除に追従し、参照中の
+/// アセットのパージが拒否されることを確認する。
+///
+#[test]
+fn asset_referrers_follow_page_updates_and_protect_purge() {
+    let (base_dir, db_path) = prepare_test_dirs();
+    let asset_path = base_dir.join("assets");
+    let manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("open manager failed");
+
+    manager
+        .add_user("user", "pass", None)
+        .expect("add user failed");
+    let owner_id = manager
+        .create_page("/owner", "user", "# owner".to_string())
+        .expect("create owner page failed");
+    let page_asset_id = manager
+        .create_asset(&owner_id, "chart.png", "image/png", "user", b"chart")
+        .expect("create page asset failed");
+    let shared_id = manager
+        .create_shared_asset("logo.png", "image/png", "user", b"logo")
+        .expect("create shared asset failed");
+
+    /*
+     * ページ保存で参照元が登録される
+     */
+    let source = "![](asset::logo.png)\n![](asset:../owner:chart.png)\n";
+    let page_id = manager
+        .create_page("/user", "user", source.to_string())
+        .expect("create page failed");
+    let expected = vec![(page_id.clone(), "/user".to_string())];
+    assert_eq!(
+        manager
+            .list_asset_referrers(&shared_id)
+            .expect("list referrers failed"),
+        expected
+    );
+    assert_eq!(
+        manager
+            .list_asset_referrers(&page_asset_id)
+            .expect("list referrers failed"),
+        expected
+    );
+
+    /*
+     * 参照中のアセットはパージできない
+     */
+    manager.delete_asset(&shared_id).expect("delete asset failed");
+    let err = manager
+        .delete_asset_hard(&shared_id)
+        .expect_err("purging referenced asset must fail");
+    assert!(matches!(
+        err.downcast_ref::<DbError>(),
+        Some(DbError::AssetReferenced)
+    ));
+
+    /*
+     * 改名で相対参照が解決し直される
+     */
+    manager
+        .rename_page("/user", "/nested/user")
+        .expect("rename page failed");
+    assert!(
+        manager
+            .list_asset_referrers(&page_asset_id)
+            .expect("list referrers failed")
+            .is_empty()
+    );
+    assert_eq!(
+        manager
+            .list_asset_referrers(&shared_id)
+            .expect("list referrers failed"),
+        vec![(page_id.clone(), "/nested/user".to_string())]
+    );
+
+    /*
+     * 参照を外すとパージできる
+     */
+    manager
+        .put_page(&page_id, "user", "# no refs".to_string(), false)
+        .expect("put page failed");
+    assert!(
+        manager
+            .list_asset_referrers(&shared_id)
+            .expect("list referrers failed")
+            .is_empty()
+    );
+    manager
+        .delete_asset_hard(&shared_id)
+        .expect("purge unreferenced asset failed");
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// asset gc の回収処理が不要なファイルと参照されていないアセットを検出し、
+/// 指定に応じて回収することを確認する。
+///
+#[test]
+fn collect_asset_garbage_detects_and_removes_garbage() {
+    let (base_dir, db_path) = prepare_test_dirs();
+    let asset_path = base_dir.join("assets");
+    let manager = DatabaseManager::open(&db_path, &asset_path)
+        .expect("open manager failed");
+    let old = SystemTime::now() - std::time::Duration::from_secs(2 * 60 * 60);
+    let place = |path: &std::path::Path, data: &[u8], modified| {
+        fs::create_dir_all(path.parent().expect("parent missing"))
+            .expect("create dir failed");
+        fs::write(path, data).expect("write file failed");
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .expect("open file failed")
+            .set_modified(modified)
+            .expect("set modified failed");
+    };
+
+    manager
+        .add_user("user", "pass", None)
+        .expect("add user failed");
+    let page_id = manager
+        .create_page("/gc", "user", "![](asset:used.png)".to_string())
+        .expect("create page failed");
+    manager
+        .create_asset(&page_id, "used.png", "image/png", "user", b"used")
+        .expect("create used asset failed");
+    let unused_id = manager
+        .create_asset(&page_id, "unused.txt", "text/plain", "user", b"unused")
+        .expect("create unused asset failed");
+    let lost_id = manager
+        .create_asset(&page_id, "lost.txt", "text/plain", "user", b"lost")
+        .expect("create lost asset failed");
+
+    /*
+     * 不要なファイルの配置と実体の欠損
+     */
+    let orphan_hash = AssetHash::from_data(b"orphan");
+    let orphan_path = manager.asset_blob_path(&orphan_hash);
+    place(&orphan_path, b"orphan", old);
+    let stray_path = asset_path.join("uploads").join("stale.part");
+    place(&stray_path, b"stale", old);
+    let fresh_path = asset_path.join("uploads").join("fresh.tmp");
+    place(&fresh_path, b"fresh", SystemTime::now());
+    let lost_hash = AssetHash::from_data(b"lost");
+    fs::remove_file(manager.asset_blob_path(&lost_hash))
+        .expect("remove blob failed");
+
+    /*
+     * 表示のみの場合はファイルを残す
+     */
+    let items = manager
+        .collect_asset_garbage(false, false)
+        .expect("collect garbage failed");
+    assert!(items.contains(&AssetGcItem::OrphanBlob {
+        hash: orphan_hash,
+        size: 6,
+    }));
+    assert!(items.contains(&AssetGcItem::StrayFile {
+        path: stray_path.clone(),
+        size: 5,
+    }));
+    assert!(items.contains(&AssetGcItem::MissingBlob {
+        asset_id: lost_id.clone(),
+        hash: lost_hash,
+    }));
+    assert!(items.contains(&AssetGcItem::Unreferenced {
+        asset_id: unused_id.clone(),
+        size: 6,
+    }));
+    assert_eq!(
+        items.iter().map(AssetGcItem::reclaimable_size).sum::<u64>(),
+        11
+    );
+    assert!(orphan_path.exists());
+    assert!(stray_path.exists());
+
+    /*
+     * 回収指定時は不要なファイルを削除し、参照の無いアセットを削除状態にする
+     */
+    manager
+        .collect_asset_garbage(true, true)
+        .expect("collect garbage failed");
+    assert!(!orphan_path.exists());
+    assert!(!stray_path.exists());
+    assert!(fresh_path.exists());
+    assert!(
+        manager
+            .get_asset_info_by_id(&unused_id)
+            .expect("get asset info failed")
+            .expect("asset info missing")
+            .deleted()
+    );
+    assert!(
+        manager
+            .get_asset_info_by_id(&lost_id)
+            .expect("get asset info failed")
+            .expect("asset info missing")
+            .deleted()
+    );
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// blob 管理情報の参照数を取得する。
+///
+/// # 引数
+/// * `manager` - 対象データベース
+/// * `hash` - 対象の内容ハッシュ値
+///
+/// # 戻り値
+/// 管理情報が存在する場合は参照数を返す。
+///
+fn asset_blob_ref_count_for_test(
+    manager: &DatabaseManager,
+    hash: &AssetHash,
+) -> Option<u64> {
+    manager
+        .get_asset_blob_ref_count_for_test(hash)
+        .expect("get asset blob ref count failed")
+}
+
 ///
 /// テスト用の一意なサフィックス文字列を生成する。
 ///
@@ -13,14 +13,17 @@ use chrono::{DateTime, Local};
 use redb::{MultimapTable, ReadableMultimapTable, ReadableTable, Table};
 
 use crate::database::types::{
-    AssetId, AssetInfo, LockInfo, LockToken, PageId, PageIndex, PageSource,
+    AssetBlobInfo, AssetHash, AssetId, AssetInfo, AssetRevisionInfo, LockInfo,
+    LockToken, PageId, PageIndex, PageSource,
 };
 
 use super::schema::{
     is_root_path,
+    ASSET_BLOB_TABLE,
     ASSET_GROUP_TABLE,
     ASSET_INFO_TABLE,
     ASSET_LOOKUP_TABLE,
+    ASSET_REVISION_TABLE,
     DbError,
     PAGE_INDEX_TABLE,
     PAGE_PATH_TABLE,
@@ -66,23 +69,131 @@ where
     Ok(None)
 }
 
+///
+/// アセット実体への参照を登録する(トランザクション内部処理)
+///
+/// # 概要
+/// 内容ハッシュ値に対応する blob 管理情報の参照数を加算する。管理情報が
+/// 存在しない場合は新規に登録する。
+///
+/// # 引数
+/// * `blob_table` - アセット実体テーブル
+/// * `hash` - 参照する実体の内容ハッシュ値
+/// * `size` - 実体のサイズ(バイト)
+///
+/// # 戻り値
+/// 成功時は`Ok(())`を返す。
+///
+pub(in crate::database) fn acquire_asset_blob_in_txn<'txn>(
+    blob_table: &mut Table<'txn, AssetHash, AssetBlobInfo>,
+    hash: &AssetHash,
+    size: u64,
+) -> Result<()> {
+    let mut blob = match blob_table.get(*hash)? {
+        Some(entry) => entry.value(),
+        None => AssetBlobInfo::new(size),
+    };
+
+    blob.acquire();
+    blob_table.insert(*hash, blob)?;
+
+    Ok(())
+}
+
+///
+/// アセット実体への参照を解除する(トランザクション内部処理)
+///
+/// # 概要
+/// 内容ハッシュ値に対応する blob 管理情報の参照数を減算し、参照が無く
+/// なった場合は管理情報を削除する。
+///
+/// # 引数
+/// * `blob_table` - アセット実体テーブル
+/// * `hash` - 参照を解除する実体の内容ハッシュ値
+///
+/// # 戻り値
+/// 参照が無くなり実体ファイルを削除すべき場合は`true`を返す。
+///
+pub(in crate::database) fn release_asset_blob_in_txn<'txn>(
+    blob_table: &mut Table<'txn, AssetHash, AssetBlobInfo>,
+    hash: &AssetHash,
+) -> Result<bool> {
+    let mut blob = match blob_table.get(*hash)? {
+        Some(entry) => entry.value(),
+        None => return Ok(false),
+    };
+
+    if blob.release() {
+        let _ = blob_table.remove(*hash)?;
+        return Ok(true);
+    }
+
+    blob_table.insert(*hash, blob)?;
+
+    Ok(false)
+}
+
+///
+/// アセット履歴の削除(トランザクション内部処理)
+///
+/// # 概要
+/// 指定アセットの過去リビジョンを全て削除し、各リビジョンが参照していた
+/// アセット実体の参照を解除する。
+///
+/// # 引数
+/// * `revision_table` - アセット履歴テーブル
+/// * `blob_table` - アセット実体テーブル
+/// * `asset_id` - 対象アセットID
+/// * `released_blobs` - 参照が無くなったアセット実体の内容ハッシュ値の
+///   収集先
+///
+/// # 戻り値
+/// 成功時は`Ok(())`を返す。
+///
+pub(in crate::database) fn remove_asset_revisions_in_txn<'txn>(
+    revision_table: &mut Table<'txn, (AssetId, u64), AssetRevisionInfo>,
+    blob_table: &mut Table<'txn, AssetHash, AssetBlobInfo>,
+    asset_id: &AssetId,
+    released_blobs: &mut Vec<AssetHash>,
+) -> Result<()> {
+    let mut revisions = Vec::new();
+    for entry in revision_table
+        .range((asset_id.clone(), 0u64)..=(asset_id.clone(), u64::MAX))?
+    {
+        let (key, value) = entry?;
+        revisions.push((key.value(), value.value()));
+    }
+
+    for (key, revision) in revisions {
+        let _ = revision_table.remove(key)?;
+        if let Some(hash) = revision.hash()
+            && release_asset_blob_in_txn(blob_table, &hash)?
+        {
+            released_blobs.push(hash);
+        }
+    }
+
+    Ok(())
+}
+
 ///
 /// ドラフトページの削除(トランザクション内部処理)
 ///
 /// # 概要
-/// ドラフトページと紐付くテーブル情報を削除し、アセットIDを返す。
+/// ドラフトページと紐付くテーブル情報を削除し、参照が無くなったアセット
+/// 実体の内容ハッシュ値を返す。
 ///
 /// # 引数
 /// * `txn` - 書き込みトランザクション
 /// * `page_id` - 対象ページID
 ///
 /// # 戻り値
-/// 削除したアセットID一覧を返す。
+/// 参照が無くなったアセット実体の内容ハッシュ値一覧を返す。
 ///
 pub(in crate::database) fn delete_draft_in_txn(
     txn: &redb::WriteTransaction,
     page_id: &PageId,
-) -> Result<Vec<AssetId>> {
+) -> Result<Vec<AssetHash>> {
     /*
      * テーブルの準備
      */
@@ -91,7 +202,9 @@ pub(in crate::database) fn delete_draft_in_txn(
     let mut asset_table = txn.open_table(ASSET_INFO_TABLE)?;
     let mut lookup_table = txn.open_table(ASSET_LOOKUP_TABLE)?;
     let mut group_table = txn.open_multimap_table(ASSET_GROUP_TABLE)?;
-    let mut asset_ids = Vec::new();
+    let mut blob_table = txn.open_table(ASSET_BLOB_TABLE)?;
+    let mut revision_table = txn.open_table(ASSET_REVISION_TABLE)?;
+    let mut released_blobs = Vec::new();
 
     /*
      * ページ情報の取得と検証
@@ -114,16 +227,24 @@ pub(in crate::database) fn delete_draft_in_txn(
      */
     for entry in group_table.remove_all(page_id.clone())? {
         let asset_id = entry?.value();
-        let file_name = match asset_table.get(asset_id.clone())? {
-            Some(info_guard) => info_guard.value().file_name(),
-            None => {
-                asset_ids.push(asset_id);
-                continue;
-            }
+        let asset_info = match asset_table.get(asset_id.clone())? {
+            Some(info_guard) => info_guard.value(),
+            None => continue,
         };
-        let _ = lookup_table.remove((page_id.clone(), file_name));
+        let _ = lookup_table.remove((page_id.clone(), asset_info.file_name()));
         let _ = asset_table.remove(asset_id.clone());
-        asset_ids.push(asset_id);
+
+        if let Some(hash) = asset_info.hash()
+            && release_asset_blob_in_txn(&mut blob_table, &hash)?
+        {
+            released_blobs.push(hash);
+        }
+        remove_asset_revisions_in_txn(
+            &mut revision_table,
+            &mut blob_table,
+            &asset_id,
+            &mut released_blobs,
+        )?;
     }
 
     /*
@@ -132,7 +253,7 @@ pub(in crate::database) fn delete_draft_in_txn(
     let _ = path_table.remove(&index.path());
     let _ = index_table.remove(page_id.clone());
 
-    Ok(asset_ids)
+    Ok(released_blobs)
 }
 
 ///
@@ -504,7 +625,10 @@ pub(in crate::database) fn delete_page_soft_in_txn<'txn>(
 /// * `asset_table` - アセット情報テーブル
 /// * `lookup_table` - アセットID特定テーブル
 /// * `group_table` - ページ所属アセット群取得テーブル
-/// * `asset_ids` - 削除対象アセットIDの収集先
+/// * `blob_table` - アセット実体テーブル
+/// * `revision_table` - アセット履歴テーブル
+/// * `released_blobs` - 参照が無くなったアセット実体の内容ハッシュ値の
+///   収集先
 ///
 /// # 戻り値
 /// 成功時は`Ok(())`を返す。
@@ -519,7 +643,9 @@ pub(in crate::database) fn delete_page_hard_in_txn<'txn>(
     asset_table: &mut Table<'txn, AssetId, AssetInfo>,
     lookup_table: &mut Table<'txn, (PageId, String), AssetId>,
     group_table: &mut MultimapTable<'txn, PageId, AssetId>,
-    asset_ids: &mut Vec<AssetId>,
+    blob_table: &mut Table<'txn, AssetHash, AssetBlobInfo>,
+    revision_table: &mut Table<'txn, (AssetId, u64), AssetRevisionInfo>,
+    released_blobs: &mut Vec<AssetHash>,
 ) -> Result<()> {
     /*
      * ページ情報取得と保護判定
@@ -557,10 +683,21 @@ pub(in crate::database) fn delete_page_hard_in_txn<'txn>(
         let file_name = asset_info.file_name();
         let _ = lookup_table.remove((page_id.clone(), file_name));
 
+        if let Some(hash) = asset_info.take_hash()
+            && release_asset_blob_in_txn(blob_table, &hash)?
+        {
+            released_blobs.push(hash);
+        }
+        remove_asset_revisions_in_txn(
+            revision_table,
+            blob_table,
+            &asset_id,
+            released_blobs,
+        )?;
+
         asset_info.set_deleted(true);
         asset_info.clear_page_id();
         asset_table.insert(asset_id.clone(), asset_info)?;
-        asset_ids.push(asset_id);
     }
 
     /*
@@ -17,13 +17,37 @@ use anyhow::{Error, Result, anyhow};
 use argon2::password_hash::{PasswordHash, SaltString};
 use argon2::{Argon2, PasswordHasher, PasswordVerifier};
 use chrono::{DateTime, Duration, Local};
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
 use rand_core::{OsRng, RngCore};
 use redb::{Key, TypeName, Value};
 use serde::de;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha1::Sha1;
 use sha2::{Digest, Sha256};
 use ulid::{DecodeError, Ulid};
 
+/// TOTP の時間ステップ幅(秒)
+const TOTP_PERIOD_SECONDS: i64 = 30;
+
+/// TOTP コードの桁数
+const TOTP_DIGITS: u32 = 6;
+
+/// TOTP 照合時に前後へ許容する時間ステップ数
+const TOTP_SKEW_STEPS: i64 = 1;
+
+/// TOTP 共有秘密鍵のバイト長
+const MFA_SECRET_LENGTH: usize = 20;
+
+/// 登録時に発行するリカバリコードの個数
+const MFA_RECOVERY_CODE_COUNT: usize = 10;
+
+/// リカバリコード1個あたりの乱数バイト長
+const MFA_RECOVERY_CODE_BYTES: usize = 5;
+
+/// 最終アクティビティ日時を記録し直すまでの最小間隔(秒)
+const USER_ACTIVITY_RECORD_INTERVAL_SECS: i64 = 60;
+
 /// 短縮IDで利用する base62 文字集合
 #[allow(dead_code)]
 const BASE62_ALPHABET: &[u8; 62] =
@@ -340,6 +364,11 @@ pub(crate) type AssetId = Id;
 ///
 pub(crate) type UserId = Id;
 
+///
+/// アップロードセッションID型の定義(可読性を向上させるための別名定義)
+///
+pub(crate) type UploadId = Id;
+
 ///
 /// BearerトークンID型の定義(可読性を向上させるための別名定義)
 ///
@@ -651,6 +680,29 @@ impl PathPrefixSet {
         self.is_empty() || self.contains("/")
     }
 
+    ///
+    /// 指定 path へのアクセスが許可されているかを返す
+    ///
+    /// # 引数
+    /// * `path` - 判定対象の正規化済み絶対 path
+    ///
+    /// # 戻り値
+    /// いずれかの path prefix にパス境界を考慮して一致する場合、または全領域
+    /// アクセス可の場合は `true` を返す。
+    ///
+    pub(crate) fn allows_path(&self, path: &str) -> bool {
+        if self.allows_all() {
+            return true;
+        }
+
+        self.prefixes.iter().any(|prefix| {
+            path == prefix
+                || path
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|suffix| suffix.starts_with('/'))
+        })
+    }
+
     ///
     /// path prefix 集合が空かを返す
     ///
@@ -729,6 +781,14 @@ pub(crate) struct BearerTokenInfo {
 
     /// 任意のトークン名
     name: Option<String>,
+
+    /// 最終ローテーション日時
+    #[serde(default)]
+    rotated_at: Option<DateTime<Local>>,
+
+    /// ローテーション猶予期限(旧ハッシュ側の猶予エントリでのみ設定)
+    #[serde(default)]
+    grace_until: Option<DateTime<Local>>,
 }
 
 #[allow(dead_code)]
@@ -772,6 +832,8 @@ impl BearerTokenInfo {
             expire_at: now + ttl,
             revoked: false,
             name,
+            rotated_at: None,
+            grace_until: None,
         }
     }
 
@@ -875,6 +937,72 @@ impl BearerTokenInfo {
         self.name.clone()
     }
 
+    ///
+    /// 最終ローテーション日時へのアクセサ
+    ///
+    /// # 戻り値
+    /// ローテーション済みの場合は最終ローテーション日時を返す。
+    ///
+    pub(crate) fn rotated_at(&self) -> Option<DateTime<Local>> {
+        self.rotated_at
+    }
+
+    ///
+    /// ローテーション猶予期限へのアクセサ
+    ///
+    /// # 戻り値
+    /// 旧ハッシュ側の猶予エントリの場合は猶予期限を返す。
+    ///
+    pub(crate) fn grace_until(&self) -> Option<DateTime<Local>> {
+        self.grace_until
+    }
+
+    ///
+    /// ローテーション猶予エントリか否かを返す
+    ///
+    /// # 戻り値
+    /// 旧ハッシュ側の猶予エントリの場合は `true` を返す。
+    ///
+    pub(crate) fn is_rotation_grace(&self) -> bool {
+        self.grace_until.is_some()
+    }
+
+    ///
+    /// ローテーションを反映する
+    ///
+    /// # 引数
+    /// * `now` - ローテーション時刻
+    ///
+    /// # 戻り値
+    /// なし
+    ///
+    /// # 注記
+    /// 新しい平文の発行に合わせて有効期限も TTL 分延長する。
+    ///
+    pub(crate) fn rotate(&mut self, now: DateTime<Local>) {
+        self.rotated_at = Some(now);
+        self.expire_at = now + self.ttl;
+        self.updated_at = now;
+    }
+
+    ///
+    /// 旧ハッシュ側へ残す猶予エントリを生成する
+    ///
+    /// # 引数
+    /// * `grace_until` - 猶予期限
+    ///
+    /// # 戻り値
+    /// 猶予期限を設定した管理情報の複製を返す。
+    ///
+    pub(crate) fn to_rotation_grace(
+        &self,
+        grace_until: DateTime<Local>,
+    ) -> Self {
+        let mut grace = self.clone();
+        grace.grace_until = Some(grace_until);
+        grace
+    }
+
     ///
     /// TTL延長を反映する
     ///
@@ -922,6 +1050,25 @@ impl BearerTokenInfo {
         self.updated_at = updated_at;
     }
 
+    ///
+    /// 任意名を更新する
+    ///
+    /// # 引数
+    /// * `name` - 更新後の任意名(`None` で名前を取り除く)
+    /// * `updated_at` - 更新時刻
+    ///
+    /// # 戻り値
+    /// なし
+    ///
+    pub(crate) fn set_name(
+        &mut self,
+        name: Option<String>,
+        updated_at: DateTime<Local>,
+    ) {
+        self.name = name;
+        self.updated_at = updated_at;
+    }
+
     ///
     /// テスト用に日時項目を上書きする
     ///
@@ -990,6 +1137,8 @@ impl BearerTokenInfo {
             expire_at,
             revoked,
             name,
+            rotated_at: None,
+            grace_until: None,
         }
     }
 }
@@ -1106,7 +1255,7 @@ impl TokenHash {
     /// # 戻り値
     /// 32 バイト固定長のハッシュ値を返す。
     ///
-    pub(crate) fn to_bytes(&self) -> [u8; 32] {
+    pub(crate) fn to_bytes(self) -> [u8; 32] {
         self.0
     }
 
@@ -1116,7 +1265,7 @@ impl TokenHash {
     /// # 戻り値
     /// 小文字16進のハッシュ文字列を返す。
     ///
-    pub(crate) fn to_hex(&self) -> String {
+    pub(crate) fn to_hex(self) -> String {
         self.0.iter().map(|b| format!("{:02x}", b)).collect()
     }
 }
@@ -2711,265 +2860,247 @@ mod page_source_v1 {
 }
 
 ///
-/// アセット情報構造体
+/// アセット実体の内容ハッシュ値(SHA-256)
 ///
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub(crate) struct AssetInfo {
-    /// アセットID
-    id: AssetId,
-
-    /// 実体識別用のインスタンスID
-    #[serde(default)]
-    instance_id: Option<Id>,
-
-    /// 所属ページID(Noneの場合はゾンビ状態)
-    #[serde(default)]
-    page_id: Option<PageId>,
-
-    /// ファイル名
-    file_name: String,
-
-    /// MIME種別
-    mime: String,
-
-    /// バイナリサイズ
-    size: u64,
-
-    /// 登録ユーザID
-    user: UserId,
-
-    /// アップロードした日時
-    timestamp: DateTime<Local>,
-
-    /// 削除済みフラグ
-    deleted: bool,
-}
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub(crate) struct AssetHash([u8; 32]);
 
-impl AssetInfo {
+impl AssetHash {
     ///
-    /// アセット情報の生成
+    /// アセットデータから内容ハッシュ値を生成する
     ///
     /// # 引数
-    /// * `id` - アセットID
-    /// * `page_id` - 所属ページID
-    /// * `file_name` - ファイル名
-    /// * `mime` - MIME種別
-    /// * `size` - バイナリサイズ(バイト)
-    /// * `user` - 登録ユーザID
+    /// * `data` - アセットデータ
     ///
     /// # 戻り値
-    /// 生成したアセット情報を返す。
+    /// SHA-256 で計算した内容ハッシュ値を返す。
     ///
-    pub(crate) fn new(
-        id: AssetId,
-        page_id: PageId,
-        file_name: String,
-        mime: String,
-        size: u64,
-        user: UserId,
-    ) -> Self {
-        Self {
-            id,
-            instance_id: Some(Id::new()),
-            page_id: Some(page_id),
-            file_name,
-            mime,
-            size,
-            user,
-            timestamp: Local::now(),
-            deleted: false,
-        }
+    pub(crate) fn from_data(data: &[u8]) -> Self {
+        let digest = Sha256::digest(data);
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(digest.as_slice());
+        Self(bytes)
     }
 
     ///
-    /// import 用のアセット情報生成
+    /// 読み込みストリームから内容ハッシュ値を生成する
     ///
     /// # 引数
-    /// * `id` - アセットID
-    /// * `instance_id` - 実体識別用インスタンスID
-    /// * `page_id` - 所属ページID
-    /// * `file_name` - ファイル名
-    /// * `mime` - MIME種別
-    /// * `size` - サイズ
-    /// * `user` - 登録ユーザID
-    /// * `timestamp` - 登録日時
-    /// * `deleted` - 削除済みフラグ
+    /// * `reader` - アセットデータの読み込み元
     ///
     /// # 戻り値
-    /// import 用に復元したアセット情報を返す。
+    /// SHA-256 で計算した内容ハッシュ値と読み込んだバイト数を返す。
     ///
-    pub(crate) fn new_import(
-        id: AssetId,
-        instance_id: Option<Id>,
-        page_id: Option<PageId>,
-        file_name: String,
-        mime: String,
-        size: u64,
-        user: UserId,
-        timestamp: DateTime<Local>,
-        deleted: bool,
-    ) -> Self {
-        Self {
-            id,
-            instance_id,
-            page_id,
-            file_name,
-            mime,
-            size,
-            user,
-            timestamp,
-            deleted,
+    /// # 注記
+    /// データ全体をメモリに展開せずに計算する。
+    ///
+    pub(crate) fn from_reader<R>(mut reader: R) -> Result<(Self, u64)>
+    where
+        R: std::io::Read,
+    {
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0u8; 64 * 1024];
+        let mut size = 0u64;
+
+        loop {
+            let len = reader.read(&mut buf)?;
+            if len == 0 {
+                break;
+            }
+            hasher.update(&buf[..len]);
+            size += len as u64;
         }
-    }
 
-    ///
-    /// アセットIDへのアクセサ
-    ///
-    /// # 戻り値
-    /// アセットIDを返す。
-    ///
-    #[allow(dead_code)]
-    pub(crate) fn id(&self) -> AssetId {
-        self.id.clone()
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(hasher.finalize().as_slice());
+        Ok((Self(bytes), size))
     }
 
     ///
-    /// インスタンスIDへのアクセサ
+    /// 16進文字列から内容ハッシュ値を生成する
+    ///
+    /// # 引数
+    /// * `hex` - 64桁の16進文字列
     ///
     /// # 戻り値
-    /// インスタンスIDを返す。
+    /// 変換に成功した場合は内容ハッシュ値を返す。
     ///
-    pub(crate) fn instance_id(&self) -> Option<Id> {
-        self.instance_id.clone()
+    pub(crate) fn from_hex(hex: &str) -> Result<Self> {
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(anyhow!("invalid asset hash length"));
+        }
+
+        let mut bytes = [0u8; 32];
+        for (index, chunk) in hex.as_bytes().chunks(2).enumerate() {
+            let text = std::str::from_utf8(chunk)?;
+            bytes[index] = u8::from_str_radix(text, 16)?;
+        }
+
+        Ok(Self(bytes))
     }
 
     ///
-    /// 所属ページIDへのアクセサ
+    /// 生バイト列表現へのアクセサ
     ///
     /// # 戻り値
-    /// 所属ページIDを返す。
+    /// 32 バイト固定長のハッシュ値を返す。
     ///
-    pub(crate) fn page_id(&self) -> Option<PageId> {
-        self.page_id.clone()
+    pub(crate) fn to_bytes(self) -> [u8; 32] {
+        self.0
     }
 
     ///
-    /// ゾンビ状態の判定
+    /// 16進文字列へ変換する
     ///
     /// # 戻り値
-    /// 所属ページを持たない場合は`true`を返す。
+    /// 小文字16進のハッシュ文字列を返す。
     ///
-    pub(crate) fn is_zombie(&self) -> bool {
-        self.page_id.is_none()
+    pub(crate) fn to_hex(self) -> String {
+        self.0.iter().map(|b| format!("{:02x}", b)).collect()
     }
+}
 
-    ///
-    /// 所属ページIDの更新
-    ///
-    /// # 引数
-    /// * `page_id` - 更新後の所属ページID
-    ///
-    /// # 戻り値
-    /// なし
-    ///
-    pub(crate) fn set_page_id(&mut self, page_id: PageId) {
-        self.page_id = Some(page_id);
+// Displayトレイトの実装
+impl Display for AssetHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_hex())
     }
+}
 
-    ///
-    /// 所属ページIDのクリア
-    ///
-    /// # 戻り値
-    /// なし
-    ///
-    pub(crate) fn clear_page_id(&mut self) {
-        self.page_id = None;
+// Valueトレイトの実装
+impl Value for AssetHash {
+    type SelfType<'a> = AssetHash;
+    type AsBytes<'a> = [u8; 32];
+
+    fn fixed_width() -> Option<usize> {
+        Some(32)
     }
 
-    ///
-    /// ファイル名へのアクセサ
-    ///
-    /// # 戻り値
-    /// ファイル名を返す。
-    ///
-    pub(crate) fn file_name(&self) -> String {
-        self.file_name.clone()
+    fn type_name() -> TypeName {
+        TypeName::new("AssetHash")
     }
 
-    ///
-    /// ファイル名の更新
-    ///
-    /// # 引数
-    /// * `file_name` - 更新後のファイル名
-    ///
-    pub(crate) fn set_file_name(&mut self, file_name: String) {
-        self.file_name = file_name;
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        value.to_bytes()
     }
 
-    ///
-    /// MIME種別へのアクセサ
-    ///
-    /// # 戻り値
-    /// MIME種別を返す。
-    ///
-    pub(crate) fn mime(&self) -> String {
-        self.mime.clone()
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(data);
+        Self(bytes)
+    }
+}
+
+// Keyトレイトの実装
+impl Key for AssetHash {
+    fn compare(a: &[u8], b: &[u8]) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+// Serializeトレイトの実装
+impl Serialize for AssetHash {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_hex())
+        } else {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+}
+
+// Deserializeトレイトの実装
+impl<'de> Deserialize<'de> for AssetHash {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let string = String::deserialize(deserializer)?;
+            Self::from_hex(&string).map_err(de::Error::custom)
+        } else {
+            Ok(Self(<[u8; 32]>::deserialize(deserializer)?))
+        }
     }
+}
+
+///
+/// アセット実体(blob)の管理情報
+///
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct AssetBlobInfo {
+    /// 実体のサイズ(バイト)
+    size: u64,
+
+    /// 実体を参照しているアセット情報の数
+    ref_count: u64,
+}
 
+impl AssetBlobInfo {
     ///
-    /// バイナリサイズへのアクセサ
+    /// blob管理情報の生成
+    ///
+    /// # 引数
+    /// * `size` - 実体のサイズ(バイト)
     ///
     /// # 戻り値
-    /// バイナリサイズ(バイト)を返す。
+    /// 参照数 0 の blob 管理情報を返す。
     ///
-    pub(crate) fn size(&self) -> u64 {
-        self.size
+    pub(crate) fn new(size: u64) -> Self {
+        Self { size, ref_count: 0 }
     }
 
     ///
-    /// 登録ユーザIDへのアクセサ
+    /// 参照数へのアクセサ
     ///
     /// # 戻り値
-    /// 登録ユーザIDを返す。
+    /// 実体を参照しているアセット情報の数を返す。
     ///
-    pub(crate) fn user(&self) -> UserId {
-        self.user.clone()
+    pub(crate) fn ref_count(&self) -> u64 {
+        self.ref_count
     }
 
     ///
-    /// 登録日時へのアクセサ
-    ///
-    /// # 戻り値
-    /// 登録日時を返す。
+    /// 参照数の加算
     ///
-    pub(crate) fn timestamp(&self) -> DateTime<Local> {
-        self.timestamp
+    pub(crate) fn acquire(&mut self) {
+        self.ref_count = self.ref_count.saturating_add(1);
     }
 
     ///
-    /// 削除済みフラグへのアクセサ
+    /// 参照数の減算
     ///
     /// # 戻り値
-    /// 削除済みの場合は`true`を返す。
+    /// 参照が無くなった場合は`true`を返す。
     ///
-    pub(crate) fn deleted(&self) -> bool {
-        self.deleted
+    pub(crate) fn release(&mut self) -> bool {
+        self.ref_count = self.ref_count.saturating_sub(1);
+        self.ref_count == 0
     }
 
     ///
-    /// 削除済みフラグの更新
+    /// 参照数の設定
     ///
     /// # 引数
-    /// * `deleted` - 更新後の削除済みフラグ
+    /// * `ref_count` - 設定する参照数
     ///
-    pub(crate) fn set_deleted(&mut self, deleted: bool) {
-        self.deleted = deleted;
+    pub(crate) fn set_ref_count(&mut self, ref_count: u64) {
+        self.ref_count = ref_count;
     }
 }
 
 // Valueトレイトの実装
-impl Value for AssetInfo {
-    type SelfType<'a> = AssetInfo;
+impl Value for AssetBlobInfo {
+    type SelfType<'a> = AssetBlobInfo;
     type AsBytes<'a> = Vec<u8>;
 
     fn fixed_width() -> Option<usize> {
@@ -2977,7 +3108,7 @@ impl Value for AssetInfo {
     }
 
     fn type_name() -> TypeName {
-        TypeName::new("AssetInfo")
+        TypeName::new("AssetBlobInfo")
     }
 
     fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
@@ -2998,468 +3129,1936 @@ impl Value for AssetInfo {
 }
 
 ///
-/// ユーザ情報構造体
+/// アセットのリビジョン情報
 ///
 #[derive(Clone, Debug, Deserialize, Serialize)]
-pub(crate) struct UserInfo {
-    /// ユーザID
-    id: UserId,
-
-    /// 表示名
-    username: String,
-
-    /// ハッシュ化済みパスワード
-    password: String,
+pub(crate) struct AssetRevisionInfo {
+    /// リビジョン番号
+    revision: u64,
 
-    /// ハッシュ時に与えるソルトデータ
-    salt: [u8; 16],
+    /// MIME種別
+    mime: String,
 
-    /// 表示名
-    display_name: String,
+    /// バイナリサイズ
+    size: u64,
 
-    /// ユーザ属性集合
+    /// 実体の内容ハッシュ値
     #[serde(default)]
-    attributes: UserAttributeSet,
+    hash: Option<AssetHash>,
 
-    /// 最終更新日時
-    timestamp: DateTime<Local>,
-}
+    /// 登録ユーザID
+    user: UserId,
 
-///
-/// ユーザ属性種別
-///
-#[derive(
-    Clone,
-    Copy,
-    Debug,
-    Deserialize,
-    Eq,
-    Hash,
-    Ord,
-    PartialEq,
-    PartialOrd,
-    Serialize,
-)]
-pub(crate) enum UserAttribute {
-    /// Basic認証を拒否する属性
-    #[serde(rename = "NoBasicAuth")]
-    NoBasicAuth,
+    /// アップロードした日時
+    timestamp: DateTime<Local>,
 
-    /// write 系操作を拒否する属性
-    #[serde(rename = "ReadOnly")]
-    ReadOnly,
+    /// 画像の寸法(幅, 高さ)(画像でない場合は`None`)
+    #[serde(default)]
+    dimensions: Option<(u32, u32)>,
 }
 
-impl UserAttribute {
+impl AssetRevisionInfo {
     ///
-    /// 属性の文字列表現を返す
+    /// リビジョン情報の生成
+    ///
+    /// # 引数
+    /// * `revision` - リビジョン番号
+    /// * `mime` - MIME種別
+    /// * `size` - バイナリサイズ(バイト)
+    /// * `hash` - 実体の内容ハッシュ値
+    /// * `user` - 登録ユーザID
+    /// * `timestamp` - アップロードした日時
     ///
     /// # 戻り値
-    /// 外部仕様で利用する属性名を返す。
+    /// 生成したリビジョン情報を返す。
     ///
-    pub(crate) fn as_str(&self) -> &'static str {
-        match self {
-            Self::NoBasicAuth => "NoBasicAuth",
-            Self::ReadOnly => "ReadOnly",
+    pub(crate) fn new(
+        revision: u64,
+        mime: String,
+        size: u64,
+        hash: Option<AssetHash>,
+        user: UserId,
+        timestamp: DateTime<Local>,
+    ) -> Self {
+        Self {
+            revision,
+            mime,
+            size,
+            hash,
+            user,
+            timestamp,
+            dimensions: None,
         }
     }
-}
-
-impl Display for UserAttribute {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.as_str())
-    }
-}
-
-impl TryFrom<&str> for UserAttribute {
-    type Error = Error;
 
-    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
-        match value {
-            "NoBasicAuth" => Ok(Self::NoBasicAuth),
-            "ReadOnly" => Ok(Self::ReadOnly),
-            _ => Err(anyhow!("invalid user attribute: {}", value)),
-        }
+    ///
+    /// リビジョン番号へのアクセサ
+    ///
+    /// # 戻り値
+    /// リビジョン番号を返す。
+    ///
+    pub(crate) fn revision(&self) -> u64 {
+        self.revision
     }
-}
-
-///
-/// ユーザ属性集合
-///
-#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
-pub(crate) struct UserAttributeSet {
-    /// 保持している属性集合
-    attributes: BTreeSet<UserAttribute>,
-}
 
-impl UserAttributeSet {
     ///
-    /// 空の属性集合を生成する
+    /// MIME種別へのアクセサ
     ///
     /// # 戻り値
-    /// 属性を持たない集合を返す。
+    /// MIME種別を返す。
     ///
-    pub(crate) fn new() -> Self {
-        Self::default()
+    pub(crate) fn mime(&self) -> String {
+        self.mime.clone()
     }
 
     ///
-    /// 属性を追加する
-    ///
-    /// # 引数
-    /// * `attribute` - 追加する属性
+    /// バイナリサイズへのアクセサ
     ///
     /// # 戻り値
-    /// 追加前に同一属性が存在しなかった場合は `true` を返す。
+    /// バイナリサイズを返す。
     ///
-    pub(crate) fn insert(&mut self, attribute: UserAttribute) -> bool {
-        self.attributes.insert(attribute)
+    pub(crate) fn size(&self) -> u64 {
+        self.size
     }
 
     ///
-    /// 属性を削除する
-    ///
-    /// # 引数
-    /// * `attribute` - 削除する属性
+    /// 内容ハッシュ値へのアクセサ
     ///
     /// # 戻り値
-    /// 削除対象が存在した場合は `true` を返す。
+    /// 実体の内容ハッシュ値を返す。
     ///
-    pub(crate) fn remove(&mut self, attribute: UserAttribute) -> bool {
-        self.attributes.remove(&attribute)
+    pub(crate) fn hash(&self) -> Option<AssetHash> {
+        self.hash
     }
 
     ///
-    /// 属性が含まれているかを返す
-    ///
-    /// # 引数
-    /// * `attribute` - 判定対象の属性
+    /// 登録ユーザIDへのアクセサ
     ///
     /// # 戻り値
-    /// 集合内に同一属性が存在する場合は `true` を返す。
+    /// 登録ユーザIDを返す。
     ///
-    pub(crate) fn contains(&self, attribute: UserAttribute) -> bool {
-        self.attributes.contains(&attribute)
+    pub(crate) fn user(&self) -> UserId {
+        self.user.clone()
     }
 
     ///
-    /// 属性集合が空かを返す
+    /// 登録日時へのアクセサ
     ///
     /// # 戻り値
-    /// 集合が空の場合は `true` を返す。
+    /// 登録日時を返す。
     ///
-    pub(crate) fn is_empty(&self) -> bool {
-        self.attributes.is_empty()
+    pub(crate) fn timestamp(&self) -> DateTime<Local> {
+        self.timestamp
     }
 
     ///
-    /// 属性列挙子へのイテレータを返す
+    /// 画像寸法へのアクセサ
     ///
     /// # 戻り値
-    /// 属性列挙子への参照を順序付きで返すイテレータを返す。
+    /// 画像の寸法(幅, 高さ)を返す。画像でない場合は`None`を返す。
     ///
-    pub(crate) fn iter(&self) -> impl Iterator<Item = &UserAttribute> {
-        self.attributes.iter()
+    pub(crate) fn dimensions(&self) -> Option<(u32, u32)> {
+        self.dimensions
     }
-}
 
-impl FromIterator<UserAttribute> for UserAttributeSet {
-    fn from_iter<T: IntoIterator<Item = UserAttribute>>(iter: T) -> Self {
+    ///
+    /// 画像寸法の更新
+    ///
+    /// # 引数
+    /// * `dimensions` - 更新後の画像寸法
+    ///
+    pub(crate) fn set_dimensions(&mut self, dimensions: Option<(u32, u32)>) {
+        self.dimensions = dimensions;
+    }
+}
+
+// Valueトレイトの実装
+impl Value for AssetRevisionInfo {
+    type SelfType<'a> = AssetRevisionInfo;
+    type AsBytes<'a> = Vec<u8>;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("AssetRevisionInfo")
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        rmp_serde::from_slice::<Self>(data)
+            .expect("invalid MessagePack packed bytes")
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        rmp_serde::to_vec_named(value)
+            .expect("failed to serialize to MessagePack bytes")
+    }
+}
+
+///
+/// アップロードセッションの登録先
+///
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) enum AssetUploadTarget {
+    /// ページへの新規アセットとして登録する
+    NewAsset {
+        /// 登録先ページID
+        page_id: PageId,
+
+        /// ファイル名
+        file_name: String,
+    },
+
+    /// 既存アセットの新しいリビジョンとして登録する
+    Replace {
+        /// 差し替え対象のアセットID
+        asset_id: AssetId,
+    },
+}
+
+///
+/// 分割アップロードのセッション情報
+///
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct AssetUploadInfo {
+    /// セッションを作成したユーザID
+    user: UserId,
+
+    /// 登録先
+    target: AssetUploadTarget,
+
+    /// MIME種別
+    mime: String,
+
+    /// 宣言されたアップロード総サイズ(バイト)
+    size: u64,
+
+    /// セッションを作成した日時
+    created: DateTime<Local>,
+
+    /// 最後にデータを受信した日時
+    updated: DateTime<Local>,
+}
+
+impl AssetUploadInfo {
+    ///
+    /// セッション情報の生成
+    ///
+    /// # 引数
+    /// * `user` - セッションを作成したユーザID
+    /// * `target` - 登録先
+    /// * `mime` - MIME種別
+    /// * `size` - アップロード総サイズ(バイト)
+    ///
+    /// # 戻り値
+    /// 生成したセッション情報を返す。
+    ///
+    pub(crate) fn new(
+        user: UserId,
+        target: AssetUploadTarget,
+        mime: String,
+        size: u64,
+    ) -> Self {
+        let now = Local::now();
+        Self {
+            user,
+            target,
+            mime,
+            size,
+            created: now,
+            updated: now,
+        }
+    }
+
+    ///
+    /// ユーザIDへのアクセサ
+    ///
+    /// # 戻り値
+    /// セッションを作成したユーザIDを返す。
+    ///
+    pub(crate) fn user(&self) -> UserId {
+        self.user.clone()
+    }
+
+    ///
+    /// 登録先へのアクセサ
+    ///
+    /// # 戻り値
+    /// セッションの登録先を返す。
+    ///
+    pub(crate) fn target(&self) -> &AssetUploadTarget {
+        &self.target
+    }
+
+    ///
+    /// MIME種別へのアクセサ
+    ///
+    /// # 戻り値
+    /// 登録時に使用するMIME種別を返す。
+    ///
+    pub(crate) fn mime(&self) -> String {
+        self.mime.clone()
+    }
+
+    ///
+    /// アップロード総サイズへのアクセサ
+    ///
+    /// # 戻り値
+    /// セッション作成時に宣言された総サイズを返す。
+    ///
+    pub(crate) fn size(&self) -> u64 {
+        self.size
+    }
+
+    ///
+    /// 作成日時へのアクセサ
+    ///
+    /// # 戻り値
+    /// セッションを作成した日時を返す。
+    ///
+    #[allow(dead_code)]
+    pub(crate) fn created(&self) -> DateTime<Local> {
+        self.created
+    }
+
+    ///
+    /// 最終受信日時へのアクセサ
+    ///
+    /// # 戻り値
+    /// 最後にデータを受信した日時を返す。
+    ///
+    pub(crate) fn updated(&self) -> DateTime<Local> {
+        self.updated
+    }
+
+    ///
+    /// 最終受信日時の更新
+    ///
+    pub(crate) fn touch(&mut self) {
+        self.updated = Local::now();
+    }
+}
+
+// Valueトレイトの実装
+impl Value for AssetUploadInfo {
+    type SelfType<'a> = AssetUploadInfo;
+    type AsBytes<'a> = Vec<u8>;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("AssetUploadInfo")
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        rmp_serde::from_slice::<Self>(data)
+            .expect("invalid MessagePack packed bytes")
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        rmp_serde::to_vec_named(value)
+            .expect("failed to serialize to MessagePack bytes")
+    }
+}
+
+///
+/// アセット情報構造体
+///
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct AssetInfo {
+    /// アセットID
+    id: AssetId,
+
+    /// 実体識別用のインスタンスID
+    #[serde(default)]
+    instance_id: Option<Id>,
+
+    /// 所属ページID(Noneの場合はゾンビ状態)
+    #[serde(default)]
+    page_id: Option<PageId>,
+
+    /// ファイル名
+    file_name: String,
+
+    /// MIME種別
+    mime: String,
+
+    /// バイナリサイズ
+    size: u64,
+
+    /// 実体の内容ハッシュ値(実体を持たない場合は`None`)
+    #[serde(default)]
+    hash: Option<AssetHash>,
+
+    /// 登録ユーザID
+    user: UserId,
+
+    /// アップロードした日時
+    timestamp: DateTime<Local>,
+
+    /// 削除済みフラグ
+    deleted: bool,
+
+    /// 現在のリビジョン番号(0は旧形式で1として扱う)
+    #[serde(default)]
+    revision: u64,
+
+    /// 画像の寸法(幅, 高さ)(画像でない場合は`None`)
+    #[serde(default)]
+    dimensions: Option<(u32, u32)>,
+
+    /// 共有アセットフラグ(ページに所属しないアセットの場合は`true`)
+    #[serde(default)]
+    shared: bool,
+}
+
+impl AssetInfo {
+    ///
+    /// アセット情報の生成
+    ///
+    /// # 引数
+    /// * `id` - アセットID
+    /// * `page_id` - 所属ページID
+    /// * `file_name` - ファイル名
+    /// * `mime` - MIME種別
+    /// * `size` - バイナリサイズ(バイト)
+    /// * `hash` - 実体の内容ハッシュ値
+    /// * `user` - 登録ユーザID
+    ///
+    /// # 戻り値
+    /// 生成したアセット情報を返す。
+    ///
+    pub(crate) fn new(
+        id: AssetId,
+        page_id: PageId,
+        file_name: String,
+        mime: String,
+        size: u64,
+        hash: AssetHash,
+        user: UserId,
+    ) -> Self {
+        Self {
+            id,
+            instance_id: Some(Id::new()),
+            page_id: Some(page_id),
+            file_name,
+            mime,
+            size,
+            hash: Some(hash),
+            user,
+            timestamp: Local::now(),
+            deleted: false,
+            revision: 1,
+            dimensions: None,
+            shared: false,
+        }
+    }
+
+    ///
+    /// 共有アセット情報の生成
+    ///
+    /// # 引数
+    /// * `id` - アセットID
+    /// * `file_name` - ファイル名
+    /// * `mime` - MIME種別
+    /// * `size` - バイナリサイズ(バイト)
+    /// * `hash` - 実体の内容ハッシュ値
+    /// * `user` - 登録ユーザID
+    ///
+    /// # 戻り値
+    /// 生成したアセット情報を返す。
+    ///
+    pub(crate) fn new_shared(
+        id: AssetId,
+        file_name: String,
+        mime: String,
+        size: u64,
+        hash: AssetHash,
+        user: UserId,
+    ) -> Self {
+        Self {
+            id,
+            instance_id: Some(Id::new()),
+            page_id: None,
+            file_name,
+            mime,
+            size,
+            hash: Some(hash),
+            user,
+            timestamp: Local::now(),
+            deleted: false,
+            revision: 1,
+            dimensions: None,
+            shared: true,
+        }
+    }
+
+    ///
+    /// import 用のアセット情報生成
+    ///
+    /// # 引数
+    /// * `id` - アセットID
+    /// * `instance_id` - 実体識別用インスタンスID
+    /// * `page_id` - 所属ページID
+    /// * `file_name` - ファイル名
+    /// * `mime` - MIME種別
+    /// * `size` - サイズ
+    /// * `hash` - 実体の内容ハッシュ値
+    /// * `user` - 登録ユーザID
+    /// * `timestamp` - 登録日時
+    /// * `deleted` - 削除済みフラグ
+    ///
+    /// # 戻り値
+    /// import 用に復元したアセット情報を返す。
+    ///
+    pub(crate) fn new_import(
+        id: AssetId,
+        instance_id: Option<Id>,
+        page_id: Option<PageId>,
+        file_name: String,
+        mime: String,
+        size: u64,
+        hash: Option<AssetHash>,
+        user: UserId,
+        timestamp: DateTime<Local>,
+        deleted: bool,
+    ) -> Self {
+        Self {
+            id,
+            instance_id,
+            page_id,
+            file_name,
+            mime,
+            size,
+            hash,
+            user,
+            timestamp,
+            deleted,
+            revision: 1,
+            dimensions: None,
+            shared: false,
+        }
+    }
+
+    ///
+    /// アセットIDへのアクセサ
+    ///
+    /// # 戻り値
+    /// アセットIDを返す。
+    ///
+    #[allow(dead_code)]
+    pub(crate) fn id(&self) -> AssetId {
+        self.id.clone()
+    }
+
+    ///
+    /// インスタンスIDへのアクセサ
+    ///
+    /// # 戻り値
+    /// インスタンスIDを返す。
+    ///
+    pub(crate) fn instance_id(&self) -> Option<Id> {
+        self.instance_id.clone()
+    }
+
+    ///
+    /// 所属ページIDへのアクセサ
+    ///
+    /// # 戻り値
+    /// 所属ページIDを返す。
+    ///
+    pub(crate) fn page_id(&self) -> Option<PageId> {
+        self.page_id.clone()
+    }
+
+    ///
+    /// ゾンビ状態の判定
+    ///
+    /// # 戻り値
+    /// 共有アセットではなく、所属ページを持たない場合は`true`を返す。
+    ///
+    pub(crate) fn is_zombie(&self) -> bool {
+        self.page_id.is_none() && !self.shared
+    }
+
+    ///
+    /// 共有アセットの判定
+    ///
+    /// # 戻り値
+    /// ページに所属しない共有アセットの場合は`true`を返す。
+    ///
+    pub(crate) fn is_shared(&self) -> bool {
+        self.shared
+    }
+
+    ///
+    /// 所属ページIDの更新
+    ///
+    /// # 引数
+    /// * `page_id` - 更新後の所属ページID
+    ///
+    /// # 戻り値
+    /// なし
+    ///
+    /// # 注記
+    /// 共有アセットに対して呼び出した場合はページ所属アセットに変更される。
+    ///
+    pub(crate) fn set_page_id(&mut self, page_id: PageId) {
+        self.page_id = Some(page_id);
+        self.shared = false;
+    }
+
+    ///
+    /// 所属ページIDのクリア
+    ///
+    /// # 戻り値
+    /// なし
+    ///
+    pub(crate) fn clear_page_id(&mut self) {
+        self.page_id = None;
+    }
+
+    ///
+    /// ファイル名へのアクセサ
+    ///
+    /// # 戻り値
+    /// ファイル名を返す。
+    ///
+    pub(crate) fn file_name(&self) -> String {
+        self.file_name.clone()
+    }
+
+    ///
+    /// ファイル名の更新
+    ///
+    /// # 引数
+    /// * `file_name` - 更新後のファイル名
+    ///
+    pub(crate) fn set_file_name(&mut self, file_name: String) {
+        self.file_name = file_name;
+    }
+
+    ///
+    /// MIME種別へのアクセサ
+    ///
+    /// # 戻り値
+    /// MIME種別を返す。
+    ///
+    pub(crate) fn mime(&self) -> String {
+        self.mime.clone()
+    }
+
+    ///
+    /// バイナリサイズへのアクセサ
+    ///
+    /// # 戻り値
+    /// バイナリサイズ(バイト)を返す。
+    ///
+    pub(crate) fn size(&self) -> u64 {
+        self.size
+    }
+
+    ///
+    /// 内容ハッシュ値へのアクセサ
+    ///
+    /// # 戻り値
+    /// 実体の内容ハッシュ値を返す。実体を持たない場合は`None`を返す。
+    ///
+    pub(crate) fn hash(&self) -> Option<AssetHash> {
+        self.hash
+    }
+
+    ///
+    /// 内容ハッシュ値の更新
+    ///
+    /// # 引数
+    /// * `hash` - 更新後の内容ハッシュ値
+    ///
+    pub(crate) fn set_hash(&mut self, hash: AssetHash) {
+        self.hash = Some(hash);
+    }
+
+    ///
+    /// 内容ハッシュ値の取り外し
+    ///
+    /// # 戻り値
+    /// 取り外した内容ハッシュ値を返す。
+    ///
+    /// # 注記
+    /// 実体を破棄したアセット情報(ゾンビ等)で blob 参照を解除する際に使用
+    /// する。
+    ///
+    pub(crate) fn take_hash(&mut self) -> Option<AssetHash> {
+        self.hash.take()
+    }
+
+    ///
+    /// 登録ユーザIDへのアクセサ
+    ///
+    /// # 戻り値
+    /// 登録ユーザIDを返す。
+    ///
+    pub(crate) fn user(&self) -> UserId {
+        self.user.clone()
+    }
+
+    ///
+    /// 登録日時へのアクセサ
+    ///
+    /// # 戻り値
+    /// 登録日時を返す。
+    ///
+    pub(crate) fn timestamp(&self) -> DateTime<Local> {
+        self.timestamp
+    }
+
+    ///
+    /// 削除済みフラグへのアクセサ
+    ///
+    /// # 戻り値
+    /// 削除済みの場合は`true`を返す。
+    ///
+    pub(crate) fn deleted(&self) -> bool {
+        self.deleted
+    }
+
+    ///
+    /// 削除済みフラグの更新
+    ///
+    /// # 引数
+    /// * `deleted` - 更新後の削除済みフラグ
+    ///
+    pub(crate) fn set_deleted(&mut self, deleted: bool) {
+        self.deleted = deleted;
+    }
+
+    ///
+    /// 現在のリビジョン番号へのアクセサ
+    ///
+    /// # 戻り値
+    /// 現在のリビジョン番号を返す。
+    ///
+    pub(crate) fn revision(&self) -> u64 {
+        self.revision.max(1)
+    }
+
+    ///
+    /// 現在のリビジョン番号の更新
+    ///
+    /// # 引数
+    /// * `revision` - 更新後のリビジョン番号
+    ///
+    pub(crate) fn set_revision(&mut self, revision: u64) {
+        self.revision = revision;
+    }
+
+    ///
+    /// 現在の内容をリビジョン情報として取り出す
+    ///
+    /// # 戻り値
+    /// 現在のリビジョンのリビジョン情報を返す。
+    ///
+    pub(crate) fn current_revision(&self) -> AssetRevisionInfo {
+        AssetRevisionInfo {
+            revision: self.revision(),
+            mime: self.mime.clone(),
+            size: self.size,
+            hash: self.hash,
+            user: self.user.clone(),
+            timestamp: self.timestamp,
+            dimensions: self.dimensions,
+        }
+    }
+
+    ///
+    /// 内容の差し替え
+    ///
+    /// # 概要
+    /// 新しいリビジョンの内容で現在の内容を置き換え、インスタンスIDを
+    /// 更新する。
+    ///
+    /// # 引数
+    /// * `revision` - 差し替え後のリビジョン情報
+    ///
+    pub(crate) fn apply_revision(&mut self, revision: AssetRevisionInfo) {
+        self.instance_id = Some(Id::new());
+        self.mime = revision.mime;
+        self.size = revision.size;
+        self.hash = revision.hash;
+        self.user = revision.user;
+        self.timestamp = revision.timestamp;
+        self.revision = revision.revision;
+        self.dimensions = revision.dimensions;
+    }
+
+    ///
+    /// 画像寸法へのアクセサ
+    ///
+    /// # 戻り値
+    /// 画像の寸法(幅, 高さ)を返す。画像でない場合は`None`を返す。
+    ///
+    pub(crate) fn dimensions(&self) -> Option<(u32, u32)> {
+        self.dimensions
+    }
+
+    ///
+    /// 画像寸法の更新
+    ///
+    /// # 引数
+    /// * `dimensions` - 更新後の画像寸法
+    ///
+    pub(crate) fn set_dimensions(&mut self, dimensions: Option<(u32, u32)>) {
+        self.dimensions = dimensions;
+    }
+}
+
+// Valueトレイトの実装
+impl Value for AssetInfo {
+    type SelfType<'a> = AssetInfo;
+    type AsBytes<'a> = Vec<u8>;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("AssetInfo")
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        rmp_serde::from_slice::<Self>(data)
+            .expect("invalid MessagePack packed bytes")
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        rmp_serde::to_vec_named(value)
+            .expect("failed to serialize to MessagePack bytes")
+    }
+}
+
+///
+/// ユーザ情報構造体
+///
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct UserInfo {
+    /// ユーザID
+    id: UserId,
+
+    /// 表示名
+    username: String,
+
+    /// ハッシュ化済みパスワード
+    password: String,
+
+    /// ハッシュ時に与えるソルトデータ
+    salt: [u8; 16],
+
+    /// 表示名
+    display_name: String,
+
+    /// ユーザ属性集合
+    #[serde(default)]
+    attributes: UserAttributeSet,
+
+    /// 二要素認証(TOTP)の登録情報
+    #[serde(default)]
+    mfa: Option<MfaInfo>,
+
+    /// 無効化日時(無効化されていない場合は`None`)
+    #[serde(default)]
+    disabled_at: Option<DateTime<Local>>,
+
+    /// 最終アクティビティ日時(認証に成功した最終日時)
+    #[serde(default)]
+    last_activity: Option<DateTime<Local>>,
+
+    /// 連続したログイン失敗回数
+    #[serde(default)]
+    failed_logins: u32,
+
+    /// ログイン失敗によるロックアウトの解除日時
+    #[serde(default)]
+    locked_until: Option<DateTime<Local>>,
+
+    /// 最終更新日時
+    timestamp: DateTime<Local>,
+}
+
+///
+/// ユーザ属性種別
+///
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    Hash,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+)]
+pub(crate) enum UserAttribute {
+    /// Basic認証を拒否する属性
+    #[serde(rename = "NoBasicAuth")]
+    NoBasicAuth,
+
+    /// write 系操作を拒否する属性
+    #[serde(rename = "ReadOnly")]
+    ReadOnly,
+
+    /// Basic認証時に二要素認証を必須とする属性
+    #[serde(rename = "RequireMfa")]
+    RequireMfa,
+
+    /// 管理者向けAPIの利用を許可する属性
+    #[serde(rename = "Admin")]
+    Admin,
+}
+
+impl UserAttribute {
+    ///
+    /// 属性の文字列表現を返す
+    ///
+    /// # 戻り値
+    /// 外部仕様で利用する属性名を返す。
+    ///
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::NoBasicAuth => "NoBasicAuth",
+            Self::ReadOnly => "ReadOnly",
+            Self::RequireMfa => "RequireMfa",
+            Self::Admin => "Admin",
+        }
+    }
+}
+
+impl Display for UserAttribute {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl TryFrom<&str> for UserAttribute {
+    type Error = Error;
+
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        match value {
+            "NoBasicAuth" => Ok(Self::NoBasicAuth),
+            "ReadOnly" => Ok(Self::ReadOnly),
+            "RequireMfa" => Ok(Self::RequireMfa),
+            "Admin" => Ok(Self::Admin),
+            _ => Err(anyhow!("invalid user attribute: {}", value)),
+        }
+    }
+}
+
+///
+/// ユーザ属性集合
+///
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct UserAttributeSet {
+    /// 保持している属性集合
+    attributes: BTreeSet<UserAttribute>,
+}
+
+impl UserAttributeSet {
+    ///
+    /// 空の属性集合を生成する
+    ///
+    /// # 戻り値
+    /// 属性を持たない集合を返す。
+    ///
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// 属性を追加する
+    ///
+    /// # 引数
+    /// * `attribute` - 追加する属性
+    ///
+    /// # 戻り値
+    /// 追加前に同一属性が存在しなかった場合は `true` を返す。
+    ///
+    pub(crate) fn insert(&mut self, attribute: UserAttribute) -> bool {
+        self.attributes.insert(attribute)
+    }
+
+    ///
+    /// 属性を削除する
+    ///
+    /// # 引数
+    /// * `attribute` - 削除する属性
+    ///
+    /// # 戻り値
+    /// 削除対象が存在した場合は `true` を返す。
+    ///
+    pub(crate) fn remove(&mut self, attribute: UserAttribute) -> bool {
+        self.attributes.remove(&attribute)
+    }
+
+    ///
+    /// 属性が含まれているかを返す
+    ///
+    /// # 引数
+    /// * `attribute` - 判定対象の属性
+    ///
+    /// # 戻り値
+    /// 集合内に同一属性が存在する場合は `true` を返す。
+    ///
+    pub(crate) fn contains(&self, attribute: UserAttribute) -> bool {
+        self.attributes.contains(&attribute)
+    }
+
+    ///
+    /// 属性集合が空かを返す
+    ///
+    /// # 戻り値
+    /// 集合が空の場合は `true` を返す。
+    ///
+    pub(crate) fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+
+    ///
+    /// 属性列挙子へのイテレータを返す
+    ///
+    /// # 戻り値
+    /// 属性列挙子への参照を順序付きで返すイテレータを返す。
+    ///
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &UserAttribute> {
+        self.attributes.iter()
+    }
+}
+
+impl FromIterator<UserAttribute> for UserAttributeSet {
+    fn from_iter<T: IntoIterator<Item = UserAttribute>>(iter: T) -> Self {
         Self {
             attributes: iter.into_iter().collect(),
         }
     }
-}
+}
+
+impl UserInfo {
+    ///
+    /// ユーザ情報の作成
+    ///
+    /// # 引数
+    /// * `name` - ユーザ名
+    /// * `password` - 登録するパスワード
+    /// * `display_name` - 表示名
+    ///
+    /// # 戻り値
+    /// 生成したユーザ情報をパックしたオブジェクトを返す。
+    ///
+    /// # 注記
+    /// 本関数を呼び出すとユーザ情報を生成する。引数`name`で指定されたユーザ名は
+    /// 表示名として使用される。引数`password`で指定されたパスワードはそのまま保
+    /// 存せず、ランダムに生成されたソルトデータと掛け合わせたデータでハッシュ化
+    /// し、その結果を文字列化した物を格納する。
+    ///
+    #[allow(dead_code)]
+    pub(crate) fn new<S>(name: S, password: S, display_name: Option<S>) -> Self
+    where
+        S: AsRef<str>,
+    {
+        Self::new_with_attributes(
+            name,
+            Some(password.as_ref()),
+            display_name,
+            UserAttributeSet::new(),
+        )
+    }
+
+    ///
+    /// 属性付きユーザ情報の作成
+    ///
+    /// # 引数
+    /// * `name` - ユーザ名
+    /// * `password` - 登録するパスワード
+    /// * `display_name` - 表示名
+    /// * `attributes` - ユーザ属性集合
+    ///
+    /// # 戻り値
+    /// 属性を反映したユーザ情報を返す。
+    ///
+    /// # 注記
+    /// `password` が `None` の場合は、Basic認証を使わないユーザ向けに
+    /// ランダムな内部専用文字列を用いてハッシュ値を生成する。
+    ///
+    pub(crate) fn new_with_attributes<S>(
+        name: S,
+        password: Option<&str>,
+        display_name: Option<S>,
+        attributes: UserAttributeSet,
+    ) -> Self
+    where
+        S: AsRef<str>,
+    {
+        /*
+         * パスワード入力値を決定する
+         */
+        let password_value = password
+            .map(str::to_string)
+            .unwrap_or_else(|| UserId::new().to_string());
+
+        /*
+         * ソルトデータの生成
+         */
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let salt_string =
+            SaltString::encode_b64(&salt).expect("salt encode failed");
+        let argon2 = Argon2::default();
+        let hashed = argon2
+            .hash_password(password_value.as_bytes(), &salt_string)
+            .expect("hash failed")
+            .to_string();
+
+        /*
+         * ユーザ情報の構築
+         */
+        Self {
+            id: UserId::new(),
+            username: name.as_ref().to_string(),
+            password: hashed,
+            salt,
+            display_name: display_name.unwrap_or(name).as_ref().to_string(),
+            attributes,
+            mfa: None,
+            disabled_at: None,
+            last_activity: None,
+            failed_logins: 0,
+            locked_until: None,
+            timestamp: Local::now(),
+        }
+    }
+
+    ///
+    /// import 用のユーザ情報生成
+    ///
+    /// # 引数
+    /// * `id` - ユーザID
+    /// * `username` - ユーザ名
+    /// * `password` - ハッシュ済みパスワード
+    /// * `salt` - ソルト
+    /// * `display_name` - 表示名
+    /// * `attributes` - ユーザ属性集合
+    /// * `timestamp` - 更新日時
+    ///
+    /// # 戻り値
+    /// import 用に復元したユーザ情報を返す。
+    ///
+    pub(crate) fn new_import(
+        id: UserId,
+        username: String,
+        password: String,
+        salt: [u8; 16],
+        display_name: String,
+        attributes: UserAttributeSet,
+        timestamp: DateTime<Local>,
+    ) -> Self {
+        Self {
+            id,
+            username,
+            password,
+            salt,
+            display_name,
+            attributes,
+            mfa: None,
+            disabled_at: None,
+            last_activity: None,
+            failed_logins: 0,
+            locked_until: None,
+            timestamp,
+        }
+    }
+
+    ///
+    /// ユーザIDへのアクセサ
+    ///
+    /// # 戻り値
+    /// ユーザIDオブジェクトを返す
+    ///
+    pub(crate) fn id(&self) -> UserId {
+        self.id.clone()
+    }
+
+    ///
+    /// ユーザ名へのアクセサ
+    ///
+    /// # 戻り値
+    /// ユーザ名を返す
+    ///
+    pub(crate) fn username(&self) -> String {
+        self.username.clone()
+    }
+
+    ///
+    /// ハッシュ化済みパスワードへのアクセサ
+    ///
+    /// # 戻り値
+    /// ハッシュ化済みパスワードを返す。
+    ///
+    pub(crate) fn password(&self) -> String {
+        self.password.clone()
+    }
+
+    ///
+    /// ソルトデータへのアクセサ
+    ///
+    /// # 戻り値
+    /// ソルトデータを返す。
+    ///
+    pub(crate) fn salt(&self) -> [u8; 16] {
+        self.salt
+    }
+
+    ///
+    /// 表示名へのアクセサ
+    ///
+    /// # 戻り値
+    /// 表示名を返す
+    ///
+    pub(crate) fn display_name(&self) -> String {
+        self.display_name.clone()
+    }
+
+    ///
+    /// ユーザ属性集合へのアクセサ
+    ///
+    /// # 戻り値
+    /// ユーザ属性集合を返す。
+    ///
+    pub(crate) fn attributes(&self) -> UserAttributeSet {
+        self.attributes.clone()
+    }
+
+    ///
+    /// 更新日時へのアクセサ
+    ///
+    /// # 戻り値
+    /// 更新日時表を返す
+    ///
+    pub(crate) fn timestamp(&self) -> DateTime<Local> {
+        self.timestamp.clone()
+    }
+
+    ///
+    /// 表示名の更新
+    ///
+    /// # 戻り値
+    /// 表示名を返す
+    ///
+    pub(crate) fn set_display_name(&mut self, name: &str) {
+        self.display_name = name.to_string();
+        self.timestamp = Local::now();
+    }
+
+    ///
+    /// パスワードの更新
+    ///
+    /// # 戻り値
+    /// 表示名を返す
+    ///
+    pub(crate) fn set_password(&mut self, password: &str) {
+        /*
+         * ソルトデータの生成
+         */
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+
+        /*
+         * パスワードのハッシュ化
+         */
+        let salt_string =
+            SaltString::encode_b64(&salt).expect("salt encode failed");
+        let argon2 = Argon2::default();
+        let hashed = argon2
+            .hash_password(password.as_bytes(), &salt_string)
+            .expect("hash failed")
+            .to_string();
+
+        /*
+         * 更新内容の反映
+         */
+        self.password = hashed;
+        self.salt = salt;
+        self.timestamp = Local::now();
+    }
 
-impl UserInfo {
     ///
-    /// ユーザ情報の作成
+    /// ユーザ属性集合を更新する
     ///
     /// # 引数
-    /// * `name` - ユーザ名
-    /// * `password` - 登録するパスワード
-    /// * `display_name` - 表示名
+    /// * `attributes` - 更新後のユーザ属性集合
+    ///
+    pub(crate) fn set_attributes(&mut self, attributes: UserAttributeSet) {
+        self.attributes = attributes;
+        self.timestamp = Local::now();
+    }
+
+    ///
+    /// パスワードの検証
+    ///
+    /// # 引数
+    /// * `password` - 検証対象のパスワード
     ///
     /// # 戻り値
-    /// 生成したユーザ情報をパックしたオブジェクトを返す。
+    /// 検証に成功した場合は`true`、失敗した場合は`false`を返す。
     ///
-    /// # 注記
-    /// 本関数を呼び出すとユーザ情報を生成する。引数`name`で指定されたユーザ名は
-    /// 表示名として使用される。引数`password`で指定されたパスワードはそのまま保
-    /// 存せず、ランダムに生成されたソルトデータと掛け合わせたデータでハッシュ化
-    /// し、その結果を文字列化した物を格納する。
+    pub(crate) fn verify_password(&self, password: &str) -> bool {
+        let parsed = match PasswordHash::new(&self.password) {
+            Ok(hash) => hash,
+            Err(_) => return false,
+        };
+
+        Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok()
+    }
+
+    ///
+    /// Basic認証を許可できるかを返す
+    ///
+    /// # 戻り値
+    /// `NoBasicAuth` 属性を持たない場合は `true` を返す。
+    ///
+    pub(crate) fn allows_basic_auth(&self) -> bool {
+        !self.attributes.contains(UserAttribute::NoBasicAuth)
+    }
+
+    ///
+    /// write 系操作を許可できるかを返す
+    ///
+    /// # 戻り値
+    /// `ReadOnly` 属性を持たない場合は `true` を返す。
     ///
     #[allow(dead_code)]
-    pub(crate) fn new<S>(name: S, password: S, display_name: Option<S>) -> Self
-    where
-        S: AsRef<str>,
-    {
-        Self::new_with_attributes(
-            name,
-            Some(password.as_ref()),
-            display_name,
-            UserAttributeSet::new(),
-        )
+    pub(crate) fn allows_write(&self) -> bool {
+        !self.attributes.contains(UserAttribute::ReadOnly)
     }
 
     ///
-    /// 属性付きユーザ情報の作成
+    /// 無効化状態かを返す
     ///
-    /// # 引数
-    /// * `name` - ユーザ名
-    /// * `password` - 登録するパスワード
-    /// * `display_name` - 表示名
-    /// * `attributes` - ユーザ属性集合
+    /// # 戻り値
+    /// 無効化されている場合は `true` を返す。
+    ///
+    /// # 注記
+    /// 無効化されたユーザは Basic認証・Bearer認証のいずれも拒否される。
+    ///
+    pub(crate) fn is_disabled(&self) -> bool {
+        self.disabled_at.is_some()
+    }
+
+    ///
+    /// 無効化日時へのアクセサ
     ///
     /// # 戻り値
-    /// 属性を反映したユーザ情報を返す。
+    /// 無効化されている場合は無効化日時を返す。
+    ///
+    pub(crate) fn disabled_at(&self) -> Option<DateTime<Local>> {
+        self.disabled_at
+    }
+
+    ///
+    /// 無効化状態を更新する
+    ///
+    /// # 引数
+    /// * `disabled` - 無効化する場合は `true`
     ///
     /// # 注記
-    /// `password` が `None` の場合は、Basic認証を使わないユーザ向けに
-    /// ランダムな内部専用文字列を用いてハッシュ値を生成する。
+    /// 既に同じ状態の場合は無効化日時を含め何も変更しない。
     ///
-    pub(crate) fn new_with_attributes<S>(
-        name: S,
-        password: Option<&str>,
-        display_name: Option<S>,
-        attributes: UserAttributeSet,
-    ) -> Self
-    where
-        S: AsRef<str>,
-    {
-        /*
-         * パスワード入力値を決定する
-         */
-        let password_value = password
-            .map(str::to_string)
-            .unwrap_or_else(|| UserId::new().to_string());
+    pub(crate) fn set_disabled(&mut self, disabled: bool) {
+        if disabled == self.is_disabled() {
+            return;
+        }
 
-        /*
-         * ソルトデータの生成
-         */
-        let mut salt = [0u8; 16];
-        OsRng.fill_bytes(&mut salt);
-        let salt_string =
-            SaltString::encode_b64(&salt).expect("salt encode failed");
-        let argon2 = Argon2::default();
-        let hashed = argon2
-            .hash_password(password_value.as_bytes(), &salt_string)
-            .expect("hash failed")
-            .to_string();
+        let now = Local::now();
+        self.disabled_at = if disabled { Some(now) } else { None };
+        self.timestamp = now;
+    }
 
-        /*
-         * ユーザ情報の構築
-         */
-        Self {
-            id: UserId::new(),
-            username: name.as_ref().to_string(),
-            password: hashed,
-            salt,
-            display_name: display_name.unwrap_or(name).as_ref().to_string(),
-            attributes,
-            timestamp: Local::now(),
+    ///
+    /// 最終アクティビティ日時へのアクセサ
+    ///
+    /// # 戻り値
+    /// 認証に成功したことがある場合は最終日時を返す。
+    ///
+    pub(crate) fn last_activity(&self) -> Option<DateTime<Local>> {
+        self.last_activity
+    }
+
+    ///
+    /// 最終アクティビティ日時の記録要否を返す
+    ///
+    /// # 引数
+    /// * `now` - 判定基準時刻
+    ///
+    /// # 戻り値
+    /// 前回記録から `USER_ACTIVITY_RECORD_INTERVAL_SECS` 秒以上経過して
+    /// いる場合は `true` を返す。
+    ///
+    pub(crate) fn needs_activity_record(&self, now: DateTime<Local>) -> bool {
+        match self.last_activity {
+            Some(last) => {
+                now - last
+                    >= Duration::seconds(USER_ACTIVITY_RECORD_INTERVAL_SECS)
+            }
+            None => true,
         }
     }
 
     ///
-    /// import 用のユーザ情報生成
+    /// 最終アクティビティ日時を記録する
     ///
     /// # 引数
-    /// * `id` - ユーザID
-    /// * `username` - ユーザ名
-    /// * `password` - ハッシュ済みパスワード
-    /// * `salt` - ソルト
-    /// * `display_name` - 表示名
-    /// * `attributes` - ユーザ属性集合
-    /// * `timestamp` - 更新日時
+    /// * `now` - 記録する日時
+    ///
+    /// # 注記
+    /// ユーザ情報の更新ではないため最終更新日時は変更しない。
+    ///
+    pub(crate) fn set_last_activity(&mut self, now: DateTime<Local>) {
+        self.last_activity = Some(now);
+    }
+
+    ///
+    /// 連続したログイン失敗回数へのアクセサ
     ///
     /// # 戻り値
-    /// import 用に復元したユーザ情報を返す。
+    /// 最後に認証へ成功して以降のログイン失敗回数を返す。
     ///
-    pub(crate) fn new_import(
-        id: UserId,
-        username: String,
-        password: String,
-        salt: [u8; 16],
-        display_name: String,
-        attributes: UserAttributeSet,
-        timestamp: DateTime<Local>,
-    ) -> Self {
-        Self {
-            id,
-            username,
-            password,
-            salt,
-            display_name,
-            attributes,
-            timestamp,
+    pub(crate) fn failed_logins(&self) -> u32 {
+        self.failed_logins
+    }
+
+    ///
+    /// ロックアウト解除日時へのアクセサ
+    ///
+    /// # 戻り値
+    /// ロックアウトされたことがある場合は解除日時を返す(解除日時を過ぎて
+    /// いる場合も含む)。
+    ///
+    pub(crate) fn locked_until(&self) -> Option<DateTime<Local>> {
+        self.locked_until
+    }
+
+    ///
+    /// ロックアウト中かを返す
+    ///
+    /// # 引数
+    /// * `now` - 判定基準時刻
+    ///
+    /// # 戻り値
+    /// 解除日時に達していないロックアウトがある場合は `true` を返す。
+    ///
+    pub(crate) fn is_locked(&self, now: DateTime<Local>) -> bool {
+        self.locked_until.is_some_and(|until| now < until)
+    }
+
+    ///
+    /// ログイン失敗を記録する
+    ///
+    /// # 引数
+    /// * `now` - 失敗日時
+    /// * `policy` - ロックアウト方針
+    ///
+    /// # 戻り値
+    /// 今回の失敗でロックアウトされた場合は解除日時を返す。
+    ///
+    /// # 注記
+    /// ユーザ情報の更新ではないため最終更新日時は変更しない。
+    ///
+    pub(crate) fn record_login_failure(
+        &mut self,
+        now: DateTime<Local>,
+        policy: &LockoutPolicy,
+    ) -> Option<DateTime<Local>> {
+        self.failed_logins = self.failed_logins.saturating_add(1);
+
+        let until = now + policy.lockout_duration(self.failed_logins)?;
+        self.locked_until = Some(until);
+        Some(until)
+    }
+
+    ///
+    /// ログイン失敗の記録を消去する
+    ///
+    /// # 戻り値
+    /// 消去すべき記録があった場合は `true` を返す。
+    ///
+    pub(crate) fn clear_login_failures(&mut self) -> bool {
+        if self.failed_logins == 0 && self.locked_until.is_none() {
+            return false;
         }
+
+        self.failed_logins = 0;
+        self.locked_until = None;
+        true
     }
 
     ///
-    /// ユーザIDへのアクセサ
+    /// 二要素認証登録情報へのアクセサ
     ///
     /// # 戻り値
-    /// ユーザIDオブジェクトを返す
+    /// 登録済みの場合は登録情報の複製を返す。
     ///
-    pub(crate) fn id(&self) -> UserId {
-        self.id.clone()
+    pub(crate) fn mfa(&self) -> Option<MfaInfo> {
+        self.mfa.clone()
     }
 
     ///
-    /// ユーザ名へのアクセサ
+    /// 二要素認証が登録済みかを返す
     ///
     /// # 戻り値
-    /// ユーザ名を返す
+    /// TOTP 秘密鍵が登録されている場合は `true` を返す。
     ///
-    pub(crate) fn username(&self) -> String {
-        self.username.clone()
+    pub(crate) fn is_mfa_enrolled(&self) -> bool {
+        self.mfa.is_some()
     }
 
     ///
-    /// ハッシュ化済みパスワードへのアクセサ
+    /// Basic認証時に二要素認証を要求するかを返す
     ///
     /// # 戻り値
-    /// ハッシュ化済みパスワードを返す。
+    /// 二要素認証が登録済み、または `RequireMfa` 属性を持つ場合は
+    /// `true` を返す。
     ///
-    pub(crate) fn password(&self) -> String {
-        self.password.clone()
+    /// # 注記
+    /// `RequireMfa` 属性を持ち未登録のユーザは、コードを照合できないため
+    /// 結果としてBasic認証が常に拒否される。
+    ///
+    pub(crate) fn requires_mfa(&self) -> bool {
+        self.mfa.is_some()
+            || self.attributes.contains(UserAttribute::RequireMfa)
+    }
+
+    ///
+    /// 二要素認証を登録する
+    ///
+    /// # 戻り値
+    /// 利用者へ一度だけ提示する秘密鍵とリカバリコードを返す。
+    ///
+    /// # 注記
+    /// 既存の登録情報は破棄され、新しい秘密鍵とリカバリコードへ置き換わる。
+    ///
+    pub(crate) fn enroll_mfa(&mut self) -> MfaEnrollment {
+        let (info, enrollment) = MfaInfo::generate();
+        self.mfa = Some(info);
+        self.timestamp = Local::now();
+
+        enrollment
+    }
+
+    ///
+    /// 二要素認証の登録を解除する
+    ///
+    pub(crate) fn clear_mfa(&mut self) {
+        self.mfa = None;
+        self.timestamp = Local::now();
+    }
+
+    ///
+    /// 二要素認証コードの照合
+    ///
+    /// # 引数
+    /// * `code` - TOTP コードまたはリカバリコード
+    /// * `now` - 照合基準時刻
+    ///
+    /// # 戻り値
+    /// 照合に成功した場合は `true` を返す。未登録の場合は常に `false` を返す。
+    ///
+    /// # 注記
+    /// 照合に成功した場合は受理済み時間ステップの更新、またはリカバリコード
+    /// の消費が行われるため、呼び出し側で永続化すること。
+    ///
+    pub(crate) fn verify_mfa_code(
+        &mut self,
+        code: &str,
+        now: DateTime<Local>,
+    ) -> bool {
+        match self.mfa.as_mut() {
+            Some(mfa) => mfa.verify_code(code, now),
+            None => false,
+        }
     }
+}
+
+///
+/// ログイン失敗によるロックアウト方針
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct LockoutPolicy {
+    /// ロックアウトを開始する連続失敗回数(0 の場合はロックアウトしない)
+    threshold: u32,
 
+    /// 初回ロックアウト期間
+    base_duration: Duration,
+
+    /// ロックアウト期間の上限
+    max_duration: Duration,
+}
+
+impl LockoutPolicy {
     ///
-    /// ソルトデータへのアクセサ
+    /// ロックアウト方針の生成
+    ///
+    /// # 引数
+    /// * `threshold` - ロックアウトを開始する連続失敗回数
+    /// * `base_duration` - 初回ロックアウト期間
+    /// * `max_duration` - ロックアウト期間の上限
     ///
     /// # 戻り値
-    /// ソルトデータを返す。
+    /// 生成したロックアウト方針を返す。
     ///
-    pub(crate) fn salt(&self) -> [u8; 16] {
-        self.salt
+    pub(crate) fn new(
+        threshold: u32,
+        base_duration: Duration,
+        max_duration: Duration,
+    ) -> Self {
+        Self {
+            threshold,
+            base_duration,
+            max_duration,
+        }
     }
 
     ///
-    /// 表示名へのアクセサ
+    /// 連続失敗回数に応じたロックアウト期間を返す
+    ///
+    /// # 引数
+    /// * `failures` - 連続失敗回数
     ///
     /// # 戻り値
-    /// 表示名を返す
+    /// ロックアウトが必要な場合はその期間を返す。
     ///
-    pub(crate) fn display_name(&self) -> String {
-        self.display_name.clone()
+    /// # 注記
+    /// 閾値に達した時点で初回期間とし、以降は失敗の都度倍増させる
+    /// (上限で打ち止め)。
+    ///
+    pub(crate) fn lockout_duration(&self, failures: u32) -> Option<Duration> {
+        if self.threshold == 0 || failures < self.threshold {
+            return None;
+        }
+
+        let shift = (failures - self.threshold).min(30);
+        let duration = self
+            .base_duration
+            .checked_mul(1i32 << shift)
+            .unwrap_or(self.max_duration);
+
+        Some(duration.min(self.max_duration))
     }
+}
+
+///
+/// 二要素認証(TOTP)の登録情報
+///
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct MfaInfo {
+    /// TOTP 共有秘密鍵
+    secret: Vec<u8>,
+
+    /// 未使用リカバリコードの照合用ハッシュ値
+    recovery_codes: Vec<TokenHash>,
+
+    /// 最後に受理した TOTP 時間ステップ
+    #[serde(default)]
+    last_step: u64,
 
+    /// 登録日時
+    enrolled_at: DateTime<Local>,
+}
+
+impl MfaInfo {
     ///
-    /// ユーザ属性集合へのアクセサ
+    /// 新しい登録情報を生成する
     ///
     /// # 戻り値
-    /// ユーザ属性集合を返す。
+    /// 保存用の登録情報と、利用者へ提示する平文情報の組を返す。
     ///
-    pub(crate) fn attributes(&self) -> UserAttributeSet {
-        self.attributes.clone()
+    fn generate() -> (Self, MfaEnrollment) {
+        /*
+         * 共有秘密鍵の生成
+         */
+        let mut secret = vec![0u8; MFA_SECRET_LENGTH];
+        OsRng.fill_bytes(&mut secret);
+
+        /*
+         * リカバリコードの生成
+         */
+        let mut plain_codes = Vec::with_capacity(MFA_RECOVERY_CODE_COUNT);
+        let mut hashed_codes = Vec::with_capacity(MFA_RECOVERY_CODE_COUNT);
+        for _ in 0..MFA_RECOVERY_CODE_COUNT {
+            let mut raw = [0u8; MFA_RECOVERY_CODE_BYTES];
+            OsRng.fill_bytes(&mut raw);
+            let encoded = BASE32_NOPAD.encode(&raw).to_ascii_lowercase();
+            let (head, tail) = encoded.split_at(encoded.len() / 2);
+            let code = format!("{}-{}", head, tail);
+
+            hashed_codes.push(hash_recovery_code(&code));
+            plain_codes.push(code);
+        }
+
+        let enrollment = MfaEnrollment {
+            secret: BASE32_NOPAD.encode(&secret),
+            recovery_codes: plain_codes,
+        };
+        let info = Self {
+            secret,
+            recovery_codes: hashed_codes,
+            last_step: 0,
+            enrolled_at: Local::now(),
+        };
+
+        (info, enrollment)
     }
 
     ///
-    /// 更新日時へのアクセサ
+    /// 登録日時へのアクセサ
     ///
     /// # 戻り値
-    /// 更新日時表を返す
+    /// 登録日時を返す。
     ///
-    pub(crate) fn timestamp(&self) -> DateTime<Local> {
-        self.timestamp.clone()
+    pub(crate) fn enrolled_at(&self) -> DateTime<Local> {
+        self.enrolled_at
     }
 
     ///
-    /// 表示名の更新
+    /// 未使用リカバリコード数へのアクセサ
     ///
     /// # 戻り値
-    /// 表示名を返す
+    /// 未使用のリカバリコード数を返す。
     ///
-    pub(crate) fn set_display_name(&mut self, name: &str) {
-        self.display_name = name.to_string();
-        self.timestamp = Local::now();
+    pub(crate) fn recovery_code_count(&self) -> usize {
+        self.recovery_codes.len()
     }
 
     ///
-    /// パスワードの更新
+    /// コードの照合
+    ///
+    /// # 引数
+    /// * `code` - TOTP コードまたはリカバリコード
+    /// * `now` - 照合基準時刻
     ///
     /// # 戻り値
-    /// 表示名を返す
+    /// 照合に成功した場合は `true` を返す。
     ///
-    pub(crate) fn set_password(&mut self, password: &str) {
-        /*
-         * ソルトデータの生成
-         */
-        let mut salt = [0u8; 16];
-        OsRng.fill_bytes(&mut salt);
+    /// # 注記
+    /// 数字のみのコードは TOTP として前後 `TOTP_SKEW_STEPS` の範囲で照合し、
+    /// 最後に受理した時間ステップより古いものは拒否する。同一ステップの
+    /// 再受理は、ブラウザが並行送信するリクエストを通すために許容する。
+    /// それ以外はリカバリコードとして照合し、一致したものを消費する。
+    ///
+    fn verify_code(&mut self, code: &str, now: DateTime<Local>) -> bool {
+        let code = code.trim();
 
         /*
-         * パスワードのハッシュ化
+         * TOTP コードとしての照合
          */
-        let salt_string =
-            SaltString::encode_b64(&salt).expect("salt encode failed");
-        let argon2 = Argon2::default();
-        let hashed = argon2
-            .hash_password(password.as_bytes(), &salt_string)
-            .expect("hash failed")
-            .to_string();
+        if code.len() == TOTP_DIGITS as usize
+            && code.chars().all(|ch| ch.is_ascii_digit())
+        {
+            let current = now.timestamp().div_euclid(TOTP_PERIOD_SECONDS);
+
+            for delta in -TOTP_SKEW_STEPS..=TOTP_SKEW_STEPS {
+                let step = current + delta;
+                if step < 0 || (step as u64) < self.last_step {
+                    continue;
+                }
+
+                let expect = format!(
+                    "{:0width$}",
+                    totp_code(&self.secret, step as u64),
+                    width = TOTP_DIGITS as usize,
+                );
+                if expect == code {
+                    self.last_step = step as u64;
+                    return true;
+                }
+            }
+
+            return false;
+        }
 
         /*
-         * 更新内容の反映
+         * リカバリコードとしての照合
          */
-        self.password = hashed;
-        self.salt = salt;
-        self.timestamp = Local::now();
+        let hash = hash_recovery_code(code);
+        match self.recovery_codes.iter().position(|entry| *entry == hash) {
+            Some(index) => {
+                self.recovery_codes.remove(index);
+                true
+            }
+            None => false,
+        }
     }
+}
 
-    ///
-    /// ユーザ属性集合を更新する
-    ///
-    /// # 引数
-    /// * `attributes` - 更新後のユーザ属性集合
-    ///
-    pub(crate) fn set_attributes(&mut self, attributes: UserAttributeSet) {
-        self.attributes = attributes;
-        self.timestamp = Local::now();
-    }
+///
+/// 二要素認証登録時に利用者へ提示する平文情報
+///
+/// # 注記
+/// 本構造体は保存されない。秘密鍵とリカバリコードの平文は登録時に一度だけ
+/// 提示する。
+///
+#[derive(Clone)]
+pub(crate) struct MfaEnrollment {
+    /// base32 表現の TOTP 共有秘密鍵
+    secret: String,
 
+    /// リカバリコード平文
+    recovery_codes: Vec<String>,
+}
+
+impl MfaEnrollment {
     ///
-    /// パスワードの検証
-    ///
-    /// # 引数
-    /// * `password` - 検証対象のパスワード
+    /// base32 表現の秘密鍵へのアクセサ
     ///
     /// # 戻り値
-    /// 検証に成功した場合は`true`、失敗した場合は`false`を返す。
+    /// 認証アプリへ手入力する場合に用いる秘密鍵文字列を返す。
     ///
-    pub(crate) fn verify_password(&self, password: &str) -> bool {
-        let parsed = match PasswordHash::new(&self.password) {
-            Ok(hash) => hash,
-            Err(_) => return false,
-        };
-
-        Argon2::default()
-            .verify_password(password.as_bytes(), &parsed)
-            .is_ok()
+    pub(crate) fn secret(&self) -> &str {
+        &self.secret
     }
 
     ///
-    /// Basic認証を許可できるかを返す
+    /// リカバリコード平文へのアクセサ
     ///
     /// # 戻り値
-    /// `NoBasicAuth` 属性を持たない場合は `true` を返す。
+    /// 発行したリカバリコードの一覧を返す。
     ///
-    pub(crate) fn allows_basic_auth(&self) -> bool {
-        !self.attributes.contains(UserAttribute::NoBasicAuth)
+    pub(crate) fn recovery_codes(&self) -> &[String] {
+        &self.recovery_codes
     }
 
     ///
-    /// write 系操作を許可できるかを返す
-    ///
-    /// # 戻り値
-    /// `ReadOnly` 属性を持たない場合は `true` を返す。
+    /// otpauth URI の生成
     ///
-    #[allow(dead_code)]
-    pub(crate) fn allows_write(&self) -> bool {
-        !self.attributes.contains(UserAttribute::ReadOnly)
+    /// # 引数
+    /// * `issuer` - 認証アプリに表示する発行者名
+    /// * `account` - 認証アプリに表示するアカウント名
+    ///
+    /// # 戻り値
+    /// 認証アプリへ登録するための `otpauth://totp/...` 形式の URI を返す。
+    ///
+    pub(crate) fn otpauth_uri(&self, issuer: &str, account: &str) -> String {
+        format!(
+            "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1\
+             &digits={}&period={}",
+            encode_uri_component(issuer),
+            encode_uri_component(account),
+            self.secret,
+            encode_uri_component(issuer),
+            TOTP_DIGITS,
+            TOTP_PERIOD_SECONDS,
+        )
+    }
+}
+
+impl std::fmt::Debug for MfaEnrollment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[redacted mfa enrollment]")
+    }
+}
+
+///
+/// TOTP コードの算出 (RFC 6238 / HMAC-SHA1)
+///
+/// # 引数
+/// * `secret` - 共有秘密鍵
+/// * `step` - 時間ステップ
+///
+/// # 戻り値
+/// `TOTP_DIGITS` 桁に切り詰めたコード値を返す。
+///
+pub(crate) fn totp_code(secret: &[u8], step: u64) -> u32 {
+    /*
+     * HMAC-SHA1 の計算
+     */
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret)
+        .expect("hmac accepts any key length");
+    mac.update(&step.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    /*
+     * 動的切り詰め (RFC 4226 5.3節)
+     */
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = ((digest[offset] as u32 & 0x7f) << 24)
+        | ((digest[offset + 1] as u32) << 16)
+        | ((digest[offset + 2] as u32) << 8)
+        | (digest[offset + 3] as u32);
+
+    binary % 10u32.pow(TOTP_DIGITS)
+}
+
+///
+/// リカバリコードの照合用ハッシュ値を生成する
+///
+/// # 引数
+/// * `code` - リカバリコード平文
+///
+/// # 戻り値
+/// 区切り文字と大文字小文字の差異を除去した上で計算したハッシュ値を返す。
+///
+fn hash_recovery_code(code: &str) -> TokenHash {
+    let normalized = code
+        .chars()
+        .filter(|ch| ch.is_ascii_alphanumeric())
+        .map(|ch| ch.to_ascii_lowercase())
+        .collect::<String>();
+
+    TokenHash::from_token(&normalized)
+}
+
+///
+/// URI 構成要素のパーセントエンコード
+///
+/// # 引数
+/// * `value` - エンコード対象文字列
+///
+/// # 戻り値
+/// RFC 3986 の非予約文字以外をエンコードした文字列を返す。
+///
+fn encode_uri_component(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
     }
+
+    encoded
 }
 
 #[cfg(test)]
@@ -3491,6 +5090,11 @@ impl UserInfo {
             salt: [0u8; 16],
             display_name: display_name.to_string(),
             attributes,
+            mfa: None,
+            disabled_at: None,
+            last_activity: None,
+            failed_logins: 0,
+            locked_until: None,
             timestamp,
         }
     }
@@ -3543,6 +5147,10 @@ pub(crate) struct LockInfo {
 
     /// 表示名
     expire: DateTime<Local>,
+
+    /// ロックの取得日時(記録以前に取得されたロックでは`None`)
+    #[serde(default)]
+    acquired: Option<DateTime<Local>>,
 }
 
 #[allow(dead_code)]
@@ -3561,11 +5169,14 @@ impl LockInfo {
     /// 本関数を呼び出すとロック情報を生成する。
     ///
     pub(crate) fn new(page: &PageId, user: &UserId) -> Self {
+        let now = Local::now();
+
         Self {
             token: LockToken::new(),
             page: page.clone(),
-            expire: Local::now() + Duration::minutes(5),
+            expire: now + Duration::minutes(5),
             user: user.clone(),
+            acquired: Some(now),
         }
     }
 
@@ -3609,6 +5220,20 @@ impl LockInfo {
         self.user.clone()
     }
 
+    ///
+    /// 取得日時へのアクセサ
+    ///
+    /// # 戻り値
+    /// ロックの取得日時を返す。取得日時を記録していないロックでは`None`を
+    /// 返す。
+    ///
+    /// # 注記
+    /// 有効期間の延長では更新しない。
+    ///
+    pub(crate) fn acquired(&self) -> Option<DateTime<Local>> {
+        self.acquired
+    }
+
     ///
     /// ロックオブジェクトの有効期間の延長
     ///
@@ -3659,3 +5284,113 @@ impl Value for LockInfo {
             .expect("failed to serialize to MessagePack bytes")
     }
 }
+
+///
+/// エクスポート履歴の記録種別
+///
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) enum ExportHistoryKind {
+    /// 本DBから出力したアーカイブ
+    Exported,
+
+    /// 本DBへ取り込んだアーカイブ
+    Imported,
+}
+
+///
+/// エクスポート履歴情報構造体
+///
+/// backup 用アーカイブの出力・取り込みを記録し、差分エクスポートの起点
+/// 解決と差分インポートの連続性検証に用いる。
+///
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct ExportHistoryInfo {
+    /// 記録種別
+    kind: ExportHistoryKind,
+
+    /// アーカイブが表すスナップショットの取得日時
+    timestamp: DateTime<Local>,
+
+    /// 差分アーカイブの起点となったエクスポートID(完全アーカイブは`None`)
+    #[serde(default)]
+    base_export: Option<Id>,
+
+    /// 記録した日時
+    recorded: DateTime<Local>,
+}
+
+impl ExportHistoryInfo {
+    ///
+    /// エクスポート履歴情報の生成
+    ///
+    /// # 引数
+    /// * `kind` - 記録種別
+    /// * `timestamp` - スナップショットの取得日時
+    /// * `base_export` - 起点となったエクスポートID
+    ///
+    /// # 戻り値
+    /// 生成したエクスポート履歴情報を返す。
+    ///
+    pub(crate) fn new(
+        kind: ExportHistoryKind,
+        timestamp: DateTime<Local>,
+        base_export: Option<Id>,
+    ) -> Self {
+        Self {
+            kind,
+            timestamp,
+            base_export,
+            recorded: Local::now(),
+        }
+    }
+
+    ///
+    /// 記録種別へのアクセサ
+    ///
+    /// # 戻り値
+    /// 記録種別を返す。
+    ///
+    pub(crate) fn kind(&self) -> ExportHistoryKind {
+        self.kind
+    }
+
+    ///
+    /// スナップショット取得日時へのアクセサ
+    ///
+    /// # 戻り値
+    /// スナップショットの取得日時を返す。
+    ///
+    pub(crate) fn timestamp(&self) -> DateTime<Local> {
+        self.timestamp
+    }
+}
+
+// Valueトレイトの実装
+impl Value for ExportHistoryInfo {
+    type SelfType<'a> = ExportHistoryInfo;
+    type AsBytes<'a> = Vec<u8>;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("ExportHistoryInfo")
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        rmp_serde::from_slice::<Self>(data)
+            .expect("invalid MessagePack packed bytes")
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        rmp_serde::to_vec_named(value)
+            .expect("failed to serialize to MessagePack bytes")
+    }
+}
@@ -20,6 +20,8 @@ use zip::ZipArchive;
 use super::archive_write::{
     ASSETS_ENTRY_NAME,
     ASSET_ENTRY_PREFIX,
+    ASSET_HISTORY_ENTRY_PREFIX,
+    LIVE_ENTRY_NAME,
     MANIFEST_ENTRY_NAME,
     PAGES_ENTRY_NAME,
     REVISIONS_ENTRY_NAME,
@@ -29,6 +31,7 @@ use super::model::{
     ExportAsset,
     ExportAssetBlob,
     ExportBundle,
+    ExportLivePage,
     ExportManifest,
     ExportPage,
     ExportRevision,
@@ -99,6 +102,11 @@ where
         read_jsonl_entry(&mut archive, REVISIONS_ENTRY_NAME, password)?;
     let assets: Vec<ExportAsset> =
         read_jsonl_entry(&mut archive, ASSETS_ENTRY_NAME, password)?;
+    let live: Vec<ExportLivePage> = if manifest.is_incremental() {
+        read_jsonl_entry(&mut archive, LIVE_ENTRY_NAME, password)?
+    } else {
+        Vec::new()
+    };
 
     let manifest_context = ManifestContext {
         export_type: manifest.export_type,
@@ -113,6 +121,7 @@ where
         revisions,
         assets,
         asset_blobs: Vec::new(),
+        live,
         manifest_context,
     };
 
@@ -120,12 +129,26 @@ where
      * assets.jsonl に対応する実体ファイルを読み込む
      */
     for asset in &bundle.assets {
-        let entry_name = asset_entry_name(&asset.id.to_string());
+        let entry_name = asset_entry_name(&asset.id.to_string(), None);
         let data = read_binary_entry(&mut archive, &entry_name, password)?;
         bundle.asset_blobs.push(ExportAssetBlob {
             asset_id: asset.id.clone(),
+            revision: None,
             data,
         });
+
+        for revision in &asset.history {
+            let entry_name = asset_entry_name(
+                &asset.id.to_string(),
+                Some(revision.revision),
+            );
+            let data = read_binary_entry(&mut archive, &entry_name, password)?;
+            bundle.asset_blobs.push(ExportAssetBlob {
+                asset_id: asset.id.clone(),
+                revision: Some(revision.revision),
+                data,
+            });
+        }
     }
 
     Ok(bundle)
@@ -270,11 +293,14 @@ fn validate_entry_path(entry_name: &str) -> Result<()> {
         || entry_name == PAGES_ENTRY_NAME
         || entry_name == REVISIONS_ENTRY_NAME
         || entry_name == ASSETS_ENTRY_NAME
+        || entry_name == LIVE_ENTRY_NAME
     {
         return Ok(());
     }
 
-    if entry_name.starts_with(ASSET_ENTRY_PREFIX) {
+    if entry_name.starts_with(ASSET_ENTRY_PREFIX)
+        || entry_name.starts_with(ASSET_HISTORY_ENTRY_PREFIX)
+    {
         let path = Path::new(entry_name);
         if path.is_absolute() {
             return Err(anyhow!("zip entry path must be relative: {}", entry_name));
@@ -290,12 +316,22 @@ fn validate_entry_path(entry_name: &str) -> Result<()> {
 ///
 /// # 引数
 /// * `asset_id` - アセット ID
+/// * `revision` - 過去リビジョンの場合はリビジョン番号
 ///
 /// # 戻り値
-/// `assets/<asset_id>` 形式のエントリ名を返す。
-///
-fn asset_entry_name(asset_id: &str) -> String {
-    format!("{}{}", ASSET_ENTRY_PREFIX, asset_id)
+/// `assets/<asset_id>` 形式のエントリ名を返す。過去リビジョンの場合は
+/// `asset_history/<asset_id>/<revision>` 形式となる。
+///
+fn asset_entry_name(asset_id: &str, revision: Option<u64>) -> String {
+    match revision {
+        Some(revision) => format!(
+            "{}{}/{}",
+            ASSET_HISTORY_ENTRY_PREFIX,
+            asset_id,
+            revision
+        ),
+        None => format!("{}{}", ASSET_ENTRY_PREFIX, asset_id),
+    }
 }
 
 #[cfg(test)]
@@ -421,11 +457,15 @@ mod tests {
             file_name: "asset.txt".to_string(),
             mime: "text/plain".to_string(),
             size: 5,
+            sha256: None,
+            revision: 1,
             user: user_id,
             timestamp: chrono::Local::now(),
+            history: Vec::new(),
         });
         bundle.asset_blobs.push(ExportAssetBlob {
             asset_id,
+            revision: None,
             data: b"hello".to_vec(),
         });
         bundle.sync_manifest_counts();
@@ -10,7 +10,7 @@
 #![allow(dead_code)]
 
 use std::fs::{self, File, OpenOptions};
-use std::io::{self, Cursor, Seek, Write};
+use std::io::{self, Cursor, Read, Seek, Write};
 #[cfg(target_family = "windows")]
 use std::os::windows::ffi::OsStrExt;
 use std::path::{Path, PathBuf};
@@ -28,7 +28,9 @@ pub(crate) const USERS_ENTRY_NAME: &str = "users.jsonl";
 pub(crate) const PAGES_ENTRY_NAME: &str = "pages.jsonl";
 pub(crate) const REVISIONS_ENTRY_NAME: &str = "revisions.jsonl";
 pub(crate) const ASSETS_ENTRY_NAME: &str = "assets.jsonl";
+pub(crate) const LIVE_ENTRY_NAME: &str = "live.jsonl";
 pub(crate) const ASSET_ENTRY_PREFIX: &str = "assets/";
+pub(crate) const ASSET_HISTORY_ENTRY_PREFIX: &str = "asset_history/";
 
 ///
 /// ZIP で使用した暗号方式
@@ -56,6 +58,49 @@ pub(crate) struct PreparedArchiveOutput {
     pub(crate) write_result: ArchiveWriteResult,
 }
 
+///
+/// 一時ファイル上に書き出した完成済みアーカイブ
+///
+/// # 注記
+/// 読み出し用に開いたまま保持し、破棄時に一時ファイルを削除する。
+///
+#[derive(Debug)]
+pub(crate) struct TemporaryArchive {
+    path: PathBuf,
+    file: Option<File>,
+    size: u64,
+}
+
+impl TemporaryArchive {
+    ///
+    /// アーカイブのバイト数へのアクセサ
+    ///
+    pub(crate) fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+// Readトレイトの実装
+impl Read for TemporaryArchive {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.file.as_mut() {
+            Some(file) => file.read(buf),
+            None => Ok(0),
+        }
+    }
+}
+
+// Dropトレイトの実装
+impl Drop for TemporaryArchive {
+    fn drop(&mut self) {
+        /*
+         * Windows では開いたままのファイルを削除できないため先に閉じる
+         */
+        self.file.take();
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
 ///
 /// export bundle を出力先へ書き出す
 ///
@@ -157,6 +202,111 @@ pub(crate) fn discard_prepared_bundle_output(
     }
 }
 
+///
+/// export bundle を一時アーカイブへ書き出す
+///
+/// # 引数
+/// * `bundle` - 出力対象 bundle
+/// * `password` - ZIP パスワード
+///
+/// # 戻り値
+/// 読み出し位置を先頭に戻した一時アーカイブと書込結果を返す。
+///
+pub(crate) fn write_bundle_to_temporary_archive(
+    bundle: &ExportBundle,
+    password: Option<&str>,
+) -> Result<(TemporaryArchive, ArchiveWriteResult)> {
+    let temp_path = build_temp_archive_path(None)?;
+    let mut archive = TemporaryArchive {
+        path: temp_path.clone(),
+        file: None,
+        size: 0,
+    };
+
+    let result = write_bundle_to_file(bundle, &temp_path, password)?;
+    let file = File::open(&temp_path).with_context(|| {
+        format!("open temp archive failed: {}", temp_path.display())
+    })?;
+    archive.size = file.metadata()?.len();
+    archive.file = Some(file);
+
+    Ok((archive, result))
+}
+
+///
+/// 受信したアーカイブを出力先へ書き出す
+///
+/// # 引数
+/// * `reader` - アーカイブの読み出し元
+/// * `output_path` - 出力先パス、`"-"` の場合は標準出力
+///
+/// # 戻り値
+/// 書き出したバイト数を返す。
+///
+/// # 注記
+/// ファイル出力時は一時ファイルへ全体を書き出してから最終出力先へ確定する
+/// ため、途中で失敗した場合も既存ファイルを壊さない。
+///
+pub(crate) fn write_archive_stream_to_output<R>(
+    reader: &mut R,
+    output_path: &str,
+) -> Result<u64>
+where
+    R: Read,
+{
+    /*
+     * 標準出力向け出力を処理
+     */
+    if output_path == "-" {
+        let mut stdout = io::stdout().lock();
+        let size = io::copy(reader, &mut stdout)
+            .context("write archive to stdout failed")?;
+        stdout.flush().context("flush stdout failed")?;
+        return Ok(size);
+    }
+
+    /*
+     * 一時ファイルへ書き出して最終出力先へ確定
+     */
+    let output = Path::new(output_path);
+    let temp_path = build_temp_archive_path(Some(output))?;
+    match copy_to_new_file(reader, &temp_path) {
+        Ok(size) => {
+            replace_output_file(&temp_path, output)?;
+            Ok(size)
+        }
+        Err(err) => {
+            let _ = fs::remove_file(&temp_path);
+            Err(err)
+        }
+    }
+}
+
+///
+/// 読み出し元の内容を新規ファイルへ書き出す
+///
+/// # 引数
+/// * `reader` - 読み出し元
+/// * `path` - 新規作成するファイルのパス
+///
+/// # 戻り値
+/// 書き出したバイト数を返す。
+///
+fn copy_to_new_file<R>(reader: &mut R, path: &Path) -> Result<u64>
+where
+    R: Read,
+{
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .with_context(|| format!("create temp archive failed: {}", path.display()))?;
+    let size = io::copy(reader, &mut file).context("receive archive failed")?;
+    file.sync_all().context("sync temp archive failed")?;
+
+    Ok(size)
+}
+
 ///
 /// export bundle を任意 writer へ ZIP 書込する
 ///
@@ -301,6 +451,14 @@ where
         &bundle.assets,
         write_options.file_options,
     )?;
+    if bundle.manifest.is_incremental() {
+        write_jsonl_entry(
+            zip,
+            LIVE_ENTRY_NAME,
+            &bundle.live,
+            write_options.file_options,
+        )?;
+    }
 
     for blob in &bundle.asset_blobs {
         let entry_name = asset_entry_name(blob);
@@ -614,10 +772,19 @@ fn build_temp_archive_path(output: Option<&Path>) -> Result<PathBuf> {
 /// * `blob` - アセット実体
 ///
 /// # 戻り値
-/// `assets/<asset_id>` 形式のエントリ名を返す。
+/// `assets/<asset_id>` 形式のエントリ名を返す。過去リビジョンの実体は
+/// `asset_history/<asset_id>/<revision>` 形式となる。
 ///
 pub(crate) fn asset_entry_name(blob: &ExportAssetBlob) -> String {
-    format!("{}{}", ASSET_ENTRY_PREFIX, blob.asset_id)
+    match blob.revision {
+        Some(revision) => format!(
+            "{}{}/{}",
+            ASSET_HISTORY_ENTRY_PREFIX,
+            blob.asset_id,
+            revision
+        ),
+        None => format!("{}{}", ASSET_ENTRY_PREFIX, blob.asset_id),
+    }
 }
 
 #[cfg(test)]
@@ -757,11 +924,15 @@ mod tests {
             file_name: "image.png".to_string(),
             mime: "image/png".to_string(),
             size: 3,
+            sha256: None,
+            revision: 1,
             user: user_id,
             timestamp: chrono::Local::now(),
+            history: Vec::new(),
         });
         bundle.asset_blobs.push(ExportAssetBlob {
             asset_id,
+            revision: None,
             data: vec![1, 2, 3],
         });
         bundle.sync_manifest_counts();
@@ -0,0 +1,1015 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! DokuWiki のデータディレクトリの読み込み
+//!
+//! `data/pages`の現行ページ、`data/attic`の過去版、`data/meta`の変更履歴、
+//! `data/media`のメディアファイルを読み込み、DokuWiki 記法を Markdown へ
+//! 変換する。
+//!
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Local, TimeZone};
+use flate2::read::GzDecoder;
+use serde_yaml_ng::Mapping;
+
+use super::foreign_wiki::{
+    ForeignAsset,
+    ForeignPage,
+    ForeignRevision,
+    ForeignWiki,
+    UNKNOWN_CONTRIBUTOR,
+    asset_link_target,
+    compose_front_matter,
+    encode_link_target,
+    markdown_link,
+    normalize_tag,
+    relative_link_target,
+};
+
+/// 名前空間の開始ページ名
+const START_PAGE: &str = "start";
+
+/// 画像として埋め込むメディアの拡張子
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "svg", "webp"];
+
+///
+/// DokuWiki のデータディレクトリの読み込み
+///
+/// # 引数
+/// * `data_dir` - `pages/`を含むデータディレクトリのパス
+///
+/// # 戻り値
+/// 変換済みのページ群を返す。
+///
+/// # 注記
+/// ページ ID の`:`は階層の区切りとして扱う。名前空間の開始ページ
+/// (`<ns>:start`)は、同名のページが無い場合に名前空間自体のページとする。
+/// メディアファイルは最初に参照したページへ所属させ、参照されないものは
+/// warning を出力して読み飛ばす。
+///
+pub(crate) fn read_dokuwiki_data(data_dir: &Path) -> Result<ForeignWiki> {
+    let pages_dir = data_dir.join("pages");
+    if !pages_dir.is_dir() {
+        bail!(
+            "pages directory not found (specify DokuWiki data directory): {}",
+            data_dir.display()
+        );
+    }
+
+    /*
+     * ページ ID と相対パスの対応付け
+     */
+    let mut page_files = BTreeMap::new();
+    for path in list_files(&pages_dir)? {
+        if let Some(id) = file_id(&pages_dir, &path, ".txt") {
+            page_files.insert(id, path);
+        }
+    }
+
+    let ids: BTreeSet<String> = page_files.keys().cloned().collect();
+    let page_paths: HashMap<String, String> = ids
+        .iter()
+        .map(|id| (id.clone(), page_rel_path(id, &ids)))
+        .collect();
+
+    let mut media_files = BTreeMap::new();
+    let media_dir = data_dir.join("media");
+    if media_dir.is_dir() {
+        for path in list_files(&media_dir)? {
+            if let Some(id) = file_id(&media_dir, &path, "") {
+                media_files.insert(id, path);
+            }
+        }
+    }
+
+    /*
+     * 各ページのリビジョンの読み込みと変換
+     */
+    let mut wiki = ForeignWiki::default();
+    let mut media_owners: BTreeMap<String, String> = BTreeMap::new();
+    let mut pages = Vec::new();
+    for (id, path) in &page_files {
+        let rel_path = page_paths[id].clone();
+        let revisions = read_page_history(data_dir, id, path)?;
+
+        let mut page = ForeignPage {
+            rel_path: rel_path.clone(),
+            ..Default::default()
+        };
+        let mut warnings = Vec::new();
+        for (timestamp, user, text) in revisions {
+            let mut converter = DokuwikiConverter {
+                id,
+                rel_path: &rel_path,
+                page_paths: &page_paths,
+                media_files: &media_files,
+                media_owners: &mut media_owners,
+                tags: Vec::new(),
+                warnings: Vec::new(),
+            };
+            let body = converter.convert(&text);
+            let front_matter =
+                compose_front_matter(&converter.tags, Mapping::new())?;
+            warnings = converter.warnings;
+            page.revisions.push(ForeignRevision {
+                timestamp,
+                user,
+                source: format!("{}{}", front_matter, body),
+            });
+        }
+
+        /*
+         * 変換時の警告は最新リビジョンの分のみ出力する
+         */
+        for (code, message) in warnings {
+            wiki.warn(code, format!("{} ({})", message, id));
+        }
+        pages.push(page);
+    }
+
+    /*
+     * メディアファイルの所属先ページへの割り当て
+     */
+    for (media_id, path) in &media_files {
+        let Some(owner) = media_owners.get(media_id) else {
+            wiki.warn(
+                "unreferenced_media",
+                format!("media not referenced by any page skipped: {}", media_id),
+            );
+            continue;
+        };
+        let Some(page) = pages.iter_mut().find(|page| &page.rel_path == owner)
+        else {
+            continue;
+        };
+
+        let file_name = media_file_name(media_id).to_string();
+        if page.assets.iter().any(|asset| asset.file_name == file_name) {
+            wiki.warn(
+                "duplicate_attachment_name",
+                format!("media with same name skipped: {}", media_id),
+            );
+            continue;
+        }
+
+        let data = fs::read(path)
+            .with_context(|| format!("read media failed: {}", path.display()))?;
+        page.assets.push(ForeignAsset { file_name, data });
+    }
+
+    wiki.pages = pages;
+    Ok(wiki)
+}
+
+///
+/// ディレクトリ配下のファイルの列挙
+///
+fn list_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(current) = stack.pop() {
+        let entries = fs::read_dir(&current)
+            .with_context(|| format!("read dir failed: {}", current.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            let hidden = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with('.'));
+            if hidden {
+                continue;
+            }
+            if path.is_dir() {
+                stack.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+///
+/// ファイルパスから ID への変換
+///
+/// # 引数
+/// * `base` - `pages/`または`media/`ディレクトリ
+/// * `path` - ファイルのパス
+/// * `suffix` - 除去する拡張子(ページの場合は`.txt`)
+///
+/// # 注記
+/// DokuWiki はファイル名の非 ASCII 文字をパーセントエンコードして保存する
+/// ため、デコードした上で ID とする。
+///
+fn file_id(base: &Path, path: &Path, suffix: &str) -> Option<String> {
+    let relative = path.strip_prefix(base).ok()?;
+    let mut segments = Vec::new();
+    for component in relative.components() {
+        segments.push(percent_decode(component.as_os_str().to_str()?));
+    }
+    let last = segments.pop()?;
+    let last = last.strip_suffix(suffix)?.to_string();
+    if last.is_empty() {
+        return None;
+    }
+    segments.push(last);
+    Some(segments.join(":"))
+}
+
+///
+/// パーセントエンコードのデコード
+///
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' && index + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[index + 1..index + 3]).ok();
+            if let Some(value) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(value);
+                index += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[index]);
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+///
+/// ページ ID から相対パスへの変換
+///
+/// # 注記
+/// `<ns>:start`は`<ns>`ページが存在しない場合に限り`<ns>`へ対応付ける。
+///
+fn page_rel_path(id: &str, ids: &BTreeSet<String>) -> String {
+    let mut segments: Vec<&str> = id.split(':').collect();
+    if segments.len() > 1 && segments.last() == Some(&START_PAGE) {
+        let namespace = segments[..segments.len() - 1].join(":");
+        if !ids.contains(&namespace) {
+            segments.pop();
+        }
+    }
+    segments.join("/")
+}
+
+///
+/// メディア ID からファイル名の取得
+///
+fn media_file_name(media_id: &str) -> &str {
+    media_id.rsplit(':').next().unwrap_or(media_id)
+}
+
+///
+/// ページのリビジョン履歴の読み込み
+///
+/// # 戻り値
+/// 作成日時順に並べた (作成日時, 編集者名, 本文) の一覧を返す。
+///
+/// # 注記
+/// 過去版は`attic/`の gzip ファイルから、編集者は`meta/<id>.changes`から
+/// 取得する。現行版が最新の過去版と同一の場合は重複して登録しない。
+///
+fn read_page_history(
+    data_dir: &Path,
+    id: &str,
+    current_path: &Path,
+) -> Result<Vec<(DateTime<Local>, String, String)>> {
+    let relative: PathBuf = id.split(':').collect();
+    let page_name = relative
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let parent = relative.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    /*
+     * 変更履歴(編集者)の読み込み
+     */
+    let mut changes: BTreeMap<i64, String> = BTreeMap::new();
+    let changes_path = data_dir
+        .join("meta")
+        .join(&parent)
+        .join(format!("{}.changes", page_name));
+    if changes_path.is_file() {
+        let text = fs::read_to_string(&changes_path).with_context(|| {
+            format!("read changes failed: {}", changes_path.display())
+        })?;
+        for line in text.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let Some(timestamp) =
+                fields.first().and_then(|ts| ts.trim().parse::<i64>().ok())
+            else {
+                continue;
+            };
+            let user = fields
+                .get(4)
+                .copied()
+                .filter(|user| !user.trim().is_empty())
+                .or_else(|| fields.get(1).copied())
+                .map(str::trim)
+                .filter(|user| !user.is_empty())
+                .unwrap_or(UNKNOWN_CONTRIBUTOR);
+            changes.insert(timestamp, user.to_string());
+        }
+    }
+    let user_at = |timestamp: i64| {
+        changes
+            .get(&timestamp)
+            .cloned()
+            .unwrap_or_else(|| UNKNOWN_CONTRIBUTOR.to_string())
+    };
+
+    /*
+     * 過去版の読み込み
+     */
+    let mut revisions: Vec<(i64, String)> = Vec::new();
+    let attic_dir = data_dir.join("attic").join(&parent);
+    if attic_dir.is_dir() {
+        for entry in fs::read_dir(&attic_dir)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str())
+            else {
+                continue;
+            };
+            let Some(timestamp) = name
+                .strip_suffix(".txt.gz")
+                .and_then(|rest| rest.rsplit_once('.'))
+                .filter(|(stem, _)| percent_decode(stem) == page_name)
+                .and_then(|(_, ts)| ts.parse::<i64>().ok())
+            else {
+                continue;
+            };
+
+            let mut text = String::new();
+            GzDecoder::new(fs::File::open(&path)?)
+                .read_to_string(&mut text)
+                .with_context(|| format!("read attic failed: {}", path.display()))?;
+            revisions.push((timestamp, text));
+        }
+    }
+    revisions.sort_by_key(|(timestamp, _)| *timestamp);
+
+    /*
+     * 現行版の追加
+     */
+    let current = fs::read_to_string(current_path).with_context(|| {
+        format!("read page failed: {}", current_path.display())
+    })?;
+    let duplicated = revisions.last().is_some_and(|(_, text)| *text == current);
+    if !duplicated {
+        let last_attic = revisions.last().map_or(i64::MIN, |(ts, _)| *ts);
+        let timestamp = changes
+            .keys()
+            .next_back()
+            .copied()
+            .filter(|ts| *ts > last_attic)
+            .or_else(|| {
+                fs::metadata(current_path)
+                    .and_then(|meta| meta.modified())
+                    .ok()
+                    .map(|time| DateTime::<Local>::from(time).timestamp())
+            })
+            .unwrap_or(last_attic.saturating_add(1));
+        revisions.push((timestamp, current));
+    }
+
+    revisions
+        .into_iter()
+        .map(|(timestamp, text)| {
+            let datetime = Local
+                .timestamp_opt(timestamp, 0)
+                .single()
+                .ok_or_else(|| anyhow!("invalid revision timestamp: {}", timestamp))?;
+            Ok((datetime, user_at(timestamp), text))
+        })
+        .collect()
+}
+
+///
+/// ID の解決
+///
+/// # 引数
+/// * `current` - 参照元ページの ID
+/// * `raw` - 記述された ID
+///
+/// # 注記
+/// 先頭が`:`の場合は絶対指定、`.`の場合は参照元の名前空間からの相対指定、
+/// `:`を含む場合は絶対指定、それ以外は参照元と同じ名前空間として扱う。
+/// 末尾が`:`の場合は名前空間の開始ページを指す。
+///
+fn resolve_id(current: &str, raw: &str) -> String {
+    let raw = raw.trim().to_lowercase().replace(' ', "_");
+    let mut namespace: Vec<&str> = current.split(':').collect();
+    namespace.pop();
+
+    let mut segments: Vec<String> = if let Some(rest) = raw.strip_prefix(':') {
+        rest.split(':').map(str::to_string).collect()
+    } else if raw.starts_with('.') {
+        let mut segments: Vec<String> =
+            namespace.iter().map(|segment| segment.to_string()).collect();
+        for part in raw.split(':') {
+            match part {
+                "." => {}
+                ".." => {
+                    segments.pop();
+                }
+                _ => segments.push(part.trim_start_matches('.').to_string()),
+            }
+        }
+        segments
+    } else if raw.contains(':') {
+        raw.split(':').map(str::to_string).collect()
+    } else {
+        let mut segments: Vec<String> =
+            namespace.iter().map(|segment| segment.to_string()).collect();
+        segments.push(raw.clone());
+        segments
+    };
+
+    if segments.last().is_some_and(|segment| segment.is_empty()) {
+        segments.pop();
+        segments.push(START_PAGE.to_string());
+    }
+    segments.retain(|segment| !segment.is_empty());
+    segments.join(":")
+}
+
+///
+/// DokuWiki 記法から Markdown への変換器
+///
+struct DokuwikiConverter<'a> {
+    /// 変換中のページの ID
+    id: &'a str,
+
+    /// 変換中のページの相対パス
+    rel_path: &'a str,
+
+    /// ページ ID と相対パスの対応
+    page_paths: &'a HashMap<String, String>,
+
+    /// メディア ID とファイルパスの対応
+    media_files: &'a BTreeMap<String, PathBuf>,
+
+    /// メディア ID と所属先ページの相対パスの対応
+    media_owners: &'a mut BTreeMap<String, String>,
+
+    /// `{{tag>...}}`から収集したタグ
+    tags: Vec<String>,
+
+    /// 変換できなかった記述の警告
+    warnings: Vec<(&'static str, String)>,
+}
+
+impl DokuwikiConverter<'_> {
+    ///
+    /// 本文の変換
+    ///
+    /// # 注記
+    /// 見出し、強調、リンク、メディア、箇条書き、表、コードブロックを
+    /// 変換する。
+    ///
+    fn convert(&mut self, text: &str) -> String {
+        let mut output = Vec::new();
+        let mut code_close: Option<&'static str> = None;
+        let mut indented_code = false;
+        let mut table: Vec<&str> = Vec::new();
+
+        for line in text.lines() {
+            /*
+             * コードブロックの継続
+             */
+            if let Some(close) = code_close {
+                match line.find(close) {
+                    Some(end) => {
+                        if !line[..end].is_empty() {
+                            output.push(line[..end].to_string());
+                        }
+                        output.push("```".to_string());
+                        code_close = None;
+                    }
+                    None => output.push(line.to_string()),
+                }
+                continue;
+            }
+
+            let trimmed = line.trim();
+
+            /*
+             * 表の継続
+             */
+            if trimmed.starts_with('^') || trimmed.starts_with('|') {
+                table.push(trimmed);
+                continue;
+            }
+            if !table.is_empty() {
+                output.extend(self.convert_table(&table));
+                table.clear();
+            }
+
+            /*
+             * 箇条書き
+             */
+            let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+            let list = trimmed
+                .strip_prefix("* ")
+                .map(|content| ("- ", content))
+                .or_else(|| trimmed.strip_prefix("- ").map(|content| ("1. ", content)));
+            if let Some((marker, content)) = list.filter(|_| indent >= 2) {
+                if indented_code {
+                    output.push("```".to_string());
+                    indented_code = false;
+                }
+                let depth = (indent / 2).max(1);
+                output.push(format!(
+                    "{}{}{}",
+                    "   ".repeat(depth - 1),
+                    marker,
+                    self.convert_inline(content.trim())
+                ));
+                continue;
+            }
+
+            /*
+             * 字下げによるコードブロック
+             */
+            if indent >= 2 && !trimmed.is_empty() {
+                if !indented_code {
+                    output.push("```".to_string());
+                    indented_code = true;
+                }
+                output.push(line[2.min(line.len())..].to_string());
+                continue;
+            }
+            if indented_code {
+                output.push("```".to_string());
+                indented_code = false;
+            }
+
+            /*
+             * `<code>`・`<file>`ブロックの開始
+             */
+            if let Some((lang, close, rest)) = open_code_block(trimmed) {
+                output.push(format!("```{}", lang));
+                match rest.find(close) {
+                    Some(end) => {
+                        if !rest[..end].is_empty() {
+                            output.push(rest[..end].to_string());
+                        }
+                        output.push("```".to_string());
+                    }
+                    None => {
+                        if !rest.is_empty() {
+                            output.push(rest.to_string());
+                        }
+                        code_close = Some(close);
+                    }
+                }
+                continue;
+            }
+
+            output.push(self.convert_block_line(trimmed));
+        }
+
+        if code_close.is_some() || indented_code {
+            output.push("```".to_string());
+        }
+        if !table.is_empty() {
+            output.extend(self.convert_table(&table));
+        }
+
+        let mut body = output.join("\n");
+        body.push('\n');
+        body
+    }
+
+    ///
+    /// 1行で完結するブロック要素の変換
+    ///
+    fn convert_block_line(&mut self, line: &str) -> String {
+        /*
+         * 見出し(`=`の数が多いほど上位の見出し)
+         */
+        let leading = line.len() - line.trim_start_matches('=').len();
+        let trailing = line.len() - line.trim_end_matches('=').len();
+        let count = leading.min(trailing);
+        if count >= 2 && line.len() > count * 2 {
+            let level = 7usize.saturating_sub(count.min(6)).max(1);
+            let text = line[leading..line.len() - trailing].trim();
+            return format!("{} {}", "#".repeat(level), self.convert_inline(text));
+        }
+
+        /*
+         * 水平線
+         */
+        if line.len() >= 4 && line.chars().all(|ch| ch == '-') {
+            return "---".to_string();
+        }
+
+        self.convert_inline(line)
+    }
+
+    ///
+    /// 表の変換
+    ///
+    /// # 注記
+    /// 先頭行を見出し行として扱う。セル結合は表現できないため空セルとなる。
+    ///
+    fn convert_table(&mut self, lines: &[&str]) -> Vec<String> {
+        let rows: Vec<Vec<String>> = lines
+            .iter()
+            .map(|line| {
+                let inner = &line[1..];
+                let inner = inner
+                    .strip_suffix(['^', '|'])
+                    .unwrap_or(inner);
+                split_table_cells(inner)
+                    .into_iter()
+                    .map(|cell| self.convert_inline(cell.trim()).replace('|', "\\|"))
+                    .collect()
+            })
+            .collect();
+
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut output = Vec::new();
+        for (index, row) in rows.iter().enumerate() {
+            let cells: Vec<&str> = (0..columns)
+                .map(|column| row.get(column).map(String::as_str).unwrap_or(""))
+                .collect();
+            output.push(format!("| {} |", cells.join(" | ")));
+            if index == 0 {
+                output.push(format!("|{}", " --- |".repeat(columns)));
+            }
+        }
+        output.push(String::new());
+        output
+    }
+
+    ///
+    /// インライン要素の変換
+    ///
+    fn convert_inline(&mut self, text: &str) -> String {
+        let mut output = String::new();
+        let mut rest = text;
+
+        while !rest.is_empty() {
+            let special = if let Some(after) = rest.strip_prefix("%%") {
+                after
+                    .find("%%")
+                    .map(|end| (after[..end].to_string(), end + 4))
+            } else if let Some(after) = rest.strip_prefix("<nowiki>") {
+                after
+                    .find("</nowiki>")
+                    .map(|end| (after[..end].to_string(), end + 17))
+            } else if let Some(after) = rest.strip_prefix("''") {
+                after
+                    .find("''")
+                    .map(|end| (format!("`{}`", &after[..end]), end + 4))
+            } else if let Some(after) = rest.strip_prefix("<del>") {
+                after.find("</del>").map(|end| {
+                    (format!("~~{}~~", self.convert_inline(&after[..end])), end + 11)
+                })
+            } else if rest.starts_with("//") && !output.ends_with(':') {
+                Some(("*".to_string(), 2))
+            } else if let Some(after) = rest.strip_prefix("[[") {
+                after.find("]]").map(|end| {
+                    (self.convert_link(&after[..end]), end + 4)
+                })
+            } else if let Some(after) = rest.strip_prefix("{{") {
+                after.find("}}").map(|end| {
+                    (self.convert_media(&after[..end]), end + 4)
+                })
+            } else if let Some(after) = rest.strip_prefix("~~") {
+                after
+                    .find("~~")
+                    .map(|end| &after[..end])
+                    .filter(|word| {
+                        !word.is_empty()
+                            && word.chars().all(|ch| ch.is_ascii_uppercase())
+                    })
+                    .map(|word| (String::new(), word.len() + 4))
+            } else {
+                None
+            };
+
+            match special {
+                Some((replacement, consumed)) => {
+                    output.push_str(&replacement);
+                    rest = &rest[consumed..];
+                }
+                None => {
+                    let ch = rest.chars().next().unwrap_or_default();
+                    output.push(ch);
+                    rest = &rest[ch.len_utf8()..];
+                }
+            }
+        }
+
+        output
+    }
+
+    ///
+    /// リンクの変換
+    ///
+    /// # 引数
+    /// * `inner` - `[[`と`]]`の間の文字列
+    ///
+    fn convert_link(&mut self, inner: &str) -> String {
+        let (target, text) = match inner.split_once('|') {
+            Some((target, text)) => (target.trim(), Some(text.trim())),
+            None => (inner.trim(), None),
+        };
+        let display = text
+            .filter(|text| !text.is_empty())
+            .map(|text| self.convert_inline(text))
+            .unwrap_or_else(|| target.to_string());
+
+        /*
+         * 外部リンク・InterWiki リンク
+         */
+        if target.contains("://") || target.starts_with("mailto:") {
+            return markdown_link(&display, &encode_link_target(target));
+        }
+        if target.contains('>') {
+            self.warnings.push((
+                "interwiki_link",
+                format!("interwiki link not converted: {}", target),
+            ));
+            return display;
+        }
+
+        /*
+         * ページへのリンク
+         */
+        let (page, fragment) = match target.split_once('#') {
+            Some((page, fragment)) => (page, Some(fragment.trim())),
+            None => (target, None),
+        };
+        let mut link_target = if page.trim().is_empty() {
+            String::new()
+        } else {
+            let id = resolve_id(self.id, page);
+            let path = self
+                .page_paths
+                .get(&id)
+                .cloned()
+                .unwrap_or_else(|| id.replace(':', "/"));
+            encode_link_target(&relative_link_target(self.rel_path, &path))
+        };
+        if let Some(fragment) = fragment.filter(|fragment| !fragment.is_empty()) {
+            link_target.push('#');
+            link_target.push_str(&encode_link_target(fragment));
+        }
+        if link_target.is_empty() {
+            return display;
+        }
+
+        markdown_link(&display, &link_target)
+    }
+
+    ///
+    /// メディア・プラグイン記法の変換
+    ///
+    /// # 引数
+    /// * `inner` - `{{`と`}}`の間の文字列
+    ///
+    /// # 注記
+    /// `{{tag>...}}`はタグとして収集する。その他のプラグイン記法は変換
+    /// できないためコードとして残す。
+    ///
+    fn convert_media(&mut self, inner: &str) -> String {
+        if let Some(tags) = inner.trim().strip_prefix("tag>") {
+            self.tags.extend(tags.split_whitespace().filter_map(normalize_tag));
+            return String::new();
+        }
+
+        let (target, title) = match inner.split_once('|') {
+            Some((target, title)) => (target.trim(), Some(title.trim())),
+            None => (inner.trim(), None),
+        };
+        let target = target.split('?').next().unwrap_or_default().trim();
+
+        if target.contains("://") {
+            let title = title.unwrap_or_default();
+            return format!("![{}]({})", title, encode_link_target(target));
+        }
+        if target.contains('>') || target.is_empty() {
+            self.warnings.push((
+                "plugin_not_converted",
+                format!("plugin syntax not converted: {{{{{}}}}}", inner),
+            ));
+            return format!("`{{{{{}}}}}`", inner);
+        }
+
+        let media_id = resolve_id(self.id, target);
+        if !self.media_files.contains_key(&media_id) {
+            self.warnings.push((
+                "unresolved_media",
+                format!("media not found: {}", media_id),
+            ));
+            return format!("`{{{{{}}}}}`", inner);
+        }
+
+        let owner = self
+            .media_owners
+            .entry(media_id.clone())
+            .or_insert_with(|| self.rel_path.to_string())
+            .clone();
+        let file_name = media_file_name(&media_id);
+        let link_target = asset_link_target(self.rel_path, &owner, file_name);
+        let title = title
+            .filter(|title| !title.is_empty())
+            .unwrap_or(file_name);
+
+        let image = file_name
+            .rsplit_once('.')
+            .is_some_and(|(_, ext)| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+        if image {
+            format!("!{}", markdown_link(title, &link_target))
+        } else {
+            markdown_link(title, &link_target)
+        }
+    }
+}
+
+///
+/// `<code>`・`<file>`ブロックの開始タグの判定
+///
+/// # 戻り値
+/// 言語名、終了タグ、開始タグ以降の文字列を返す。
+///
+fn open_code_block(line: &str) -> Option<(String, &'static str, &str)> {
+    for (tag, close) in [("<code", "</code>"), ("<file", "</file>")] {
+        let Some(after) = line.strip_prefix(tag) else {
+            continue;
+        };
+        if !after.starts_with('>') && !after.starts_with(' ') {
+            continue;
+        }
+        let end = after.find('>')?;
+        let lang = after[..end]
+            .split_whitespace()
+            .next()
+            .filter(|lang| *lang != "-")
+            .unwrap_or_default()
+            .to_string();
+        return Some((lang, close, &after[end + 1..]));
+    }
+
+    None
+}
+
+///
+/// 表の行のセルへの分割
+///
+/// # 注記
+/// リンク・メディア記法内の`|`はセルの区切りとして扱わない。
+///
+fn split_table_cells(inner: &str) -> Vec<&str> {
+    let mut cells = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let bytes = inner.as_bytes();
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'[' | b'{' if bytes.get(index + 1) == Some(&bytes[index]) => {
+                depth += 1;
+                index += 2;
+                continue;
+            }
+            b']' | b'}' if bytes.get(index + 1) == Some(&bytes[index]) => {
+                depth = depth.saturating_sub(1);
+                index += 2;
+                continue;
+            }
+            b'|' | b'^' if depth == 0 => {
+                cells.push(&inner[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    cells.push(&inner[start..]);
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+
+    use super::*;
+
+    ///
+    /// gzip 圧縮したファイルの書き込み
+    ///
+    fn write_gzip(path: &Path, text: &str) {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        fs::write(path, encoder.finish().unwrap()).unwrap();
+    }
+
+    ///
+    /// データディレクトリの履歴・メディア・記法が変換されることを確認する。
+    ///
+    /// # 注記
+    /// 1) 過去版と変更履歴を持つページ、名前空間の開始ページ、メディアを
+    ///    含むデータディレクトリを作成する
+    /// 2) 読み込み結果のページ・リビジョン・アセット・本文を確認する
+    ///
+    #[test]
+    fn read_dokuwiki_data_converts_history_media_and_syntax() {
+        let dir = tempfile::tempdir().expect("create temp dir failed");
+        let data = dir.path();
+        fs::create_dir_all(data.join("pages/wiki")).unwrap();
+        fs::create_dir_all(data.join("attic/wiki")).unwrap();
+        fs::create_dir_all(data.join("meta/wiki")).unwrap();
+        fs::create_dir_all(data.join("media/wiki")).unwrap();
+
+        fs::write(
+            data.join("pages/wiki/start.txt"),
+            "====== Welcome ======\n\
+             **bold** //italic// ''mono'' <del>old</del> see https://example.com\n\
+             [[guide|the guide]] [[:other]] [[https://example.com|site]]\n  \
+             * one\n    * two\n  - first\n\
+             {{wiki:logo.png|Logo}}\n\
+             ^ A ^ B ^\n| 1 | 2 |\n\
+             <code rust>\nfn main() {}\n</code>\n\
+             {{tag>doc sample}}\n~~NOTOC~~\n",
+        )
+        .unwrap();
+        fs::write(data.join("pages/wiki/guide.txt"), "back to [[start]]\n")
+            .unwrap();
+        fs::write(data.join("pages/other.txt"), "{{wiki:logo.png}}\n").unwrap();
+        write_gzip(&data.join("attic/wiki/guide.1600000000.txt.gz"), "draft\n");
+        write_gzip(
+            &data.join("attic/wiki/guide.1600000100.txt.gz"),
+            "back to [[start]]\n",
+        );
+        fs::write(
+            data.join("meta/wiki/guide.changes"),
+            "1600000000\t192.0.2.1\tC\twiki:guide\t\tcreated\t\t\n\
+             1600000100\t192.0.2.1\tE\twiki:guide\talice\tedit\t\t\n",
+        )
+        .unwrap();
+        fs::write(data.join("media/wiki/logo.png"), b"png").unwrap();
+        fs::write(data.join("media/wiki/unused.pdf"), b"pdf").unwrap();
+
+        let wiki = read_dokuwiki_data(data).expect("read data failed");
+        let paths: Vec<&str> =
+            wiki.pages.iter().map(|page| page.rel_path.as_str()).collect();
+        assert_eq!(paths, vec!["other", "wiki/guide", "wiki"]);
+
+        let other = &wiki.pages[0];
+        assert_eq!(other.revisions[0].source, "![logo.png](asset:logo.png)\n");
+        assert_eq!(other.assets.len(), 1);
+        assert_eq!(other.assets[0].file_name, "logo.png");
+
+        let guide = &wiki.pages[1];
+        assert_eq!(guide.revisions.len(), 2);
+        assert_eq!(guide.revisions[0].user, "192.0.2.1");
+        assert_eq!(guide.revisions[0].source, "draft\n");
+        assert_eq!(guide.revisions[1].user, "alice");
+        assert_eq!(guide.revisions[1].source, "back to [start](..)\n");
+
+        let start = &wiki.pages[2];
+        let source = &start.revisions[0].source;
+        assert!(source.starts_with("---\nwiki:\n  tags:\n  - doc\n  - sample\n---\n"));
+        assert!(source.contains("# Welcome\n"));
+        assert!(source.contains(
+            "**bold** *italic* `mono` ~~old~~ see https://example.com"
+        ));
+        assert!(source.contains(
+            "[the guide](guide) [:other](../other) [site](https://example.com)"
+        ));
+        assert!(source.contains("- one\n   - two\n1. first\n"));
+        assert!(source.contains("![Logo](asset:../other:logo.png)"));
+        assert!(source.contains("| A | B |\n| --- | --- |\n| 1 | 2 |"));
+        assert!(source.contains("```rust\nfn main() {}\n```"));
+        assert!(!source.contains("NOTOC"));
+
+        assert!(
+            wiki.warnings
+                .iter()
+                .any(|warning| warning.code == "unreferenced_media")
+        );
+    }
+}
@@ -17,11 +17,15 @@ use super::model::{
     ExportActiveRename,
     ExportAsset,
     ExportAssetBlob,
+    ExportAssetRevision,
     ExportBundle,
+    ExportLiveAsset,
+    ExportLivePage,
     ExportPage,
     ExportRemovedByMigrate,
     ExportRevision,
     ExportRevisionRename,
+    ExportSince,
     ExportUser,
 };
 use super::policy::{
@@ -64,15 +68,56 @@ pub(crate) struct ExportCollectResult {
 pub(crate) fn collect_export(
     db: &DatabaseManager,
     policy: &ExportImportPolicy,
+) -> Result<ExportCollectResult> {
+    collect_export_since(db, policy, None)
+}
+
+///
+/// 起点日時以降の変更に絞った export 用 bundle の収集
+///
+/// # 引数
+/// * `db` - DB マネージャ
+/// * `policy` - export ポリシー
+/// * `since` - 差分 export の起点(全件収集の場合は`None`)
+///
+/// # 戻り値
+/// 収集結果を返す。
+///
+pub(crate) fn collect_export_since(
+    db: &DatabaseManager,
+    policy: &ExportImportPolicy,
+    since: Option<&ExportSince>,
 ) -> Result<ExportCollectResult> {
     let read_set = db.collect_export_read_set(
         policy.export_root(),
         policy.export_type().as_str() == "migrate",
+        since.map(|since| since.timestamp),
     )?;
     let mut bundle = ExportBundle::new(policy.manifest_context());
     let mut exported_page_ids = Vec::new();
     let draft_page_ids = read_set.draft_page_ids;
 
+    /*
+     * manifest の日時は次回の差分起点となる読取開始日時とする
+     */
+    bundle.manifest.timestamp = read_set.snapshot;
+    if let Some(since) = since {
+        bundle.manifest.since = Some(since.timestamp);
+        bundle.manifest.base_export = since.base_export.clone();
+    }
+
+    for entry in read_set.live {
+        bundle.live.push(ExportLivePage {
+            id: entry.page_id,
+            path: relativize_path(policy.export_root(), &entry.path)?,
+            assets: entry
+                .assets
+                .into_iter()
+                .map(|(id, file_name)| ExportLiveAsset { id, file_name })
+                .collect(),
+        });
+    }
+
     for entry in read_set.pages {
         exported_page_ids.push(entry.page_id.clone());
         bundle.pages.push(ExportPage {
@@ -106,6 +151,24 @@ pub(crate) fn collect_export(
 
     for asset_entry in read_set.assets {
         let asset = asset_entry.asset;
+        let mut history = Vec::new();
+        for revision_entry in asset_entry.history {
+            let revision = revision_entry.revision;
+            history.push(ExportAssetRevision {
+                revision: revision.revision(),
+                mime: revision.mime(),
+                size: revision.size(),
+                sha256: revision.hash(),
+                user: revision.user(),
+                timestamp: revision.timestamp(),
+            });
+            bundle.asset_blobs.push(ExportAssetBlob {
+                asset_id: asset.id(),
+                revision: Some(revision.revision()),
+                data: revision_entry.data,
+            });
+        }
+
         bundle.assets.push(ExportAsset {
             id: asset.id(),
             page: asset
@@ -114,11 +177,15 @@ pub(crate) fn collect_export(
             file_name: asset.file_name(),
             mime: asset.mime(),
             size: asset.size(),
+            sha256: asset.hash(),
+            revision: asset.revision(),
             user: asset.user(),
             timestamp: asset.timestamp(),
+            history,
         });
         bundle.asset_blobs.push(ExportAssetBlob {
             asset_id: asset.id(),
+            revision: None,
             data: asset_entry.data,
         });
     }
@@ -193,7 +260,10 @@ fn sort_bundle(bundle: &mut ExportBundle) {
         (revision.page.to_string(), revision.revision)
     });
     bundle.assets.sort_by_key(|asset| asset.id.to_string());
-    bundle.asset_blobs.sort_by_key(|blob| blob.asset_id.to_string());
+    bundle
+        .asset_blobs
+        .sort_by_key(|blob| (blob.asset_id.to_string(), blob.revision));
+    bundle.live.sort_by_key(|page| page.path.clone());
 }
 
 fn build_lock_page_ids(
@@ -236,13 +306,18 @@ fn relativize_path(base_path: &str, path: &str) -> Result<String> {
 #[cfg(test)]
 mod tests {
     use std::fs;
+    use std::io::Cursor;
     use std::path::{Path, PathBuf};
     use std::time::{SystemTime, UNIX_EPOCH};
 
     use super::*;
     use crate::database::DatabaseManager;
     use crate::database::types::UserAttribute;
+    use crate::export_import::archive_read::read_bundle_from_reader;
+    use crate::export_import::archive_write::write_bundle_to_writer;
+    use crate::export_import::import_apply::apply_import;
     use crate::export_import::policy::ExportImportPolicy;
+    use crate::export_import::validate::validate_import;
 
     #[test]
     fn backup_collect_preserves_rename_and_assets() {
@@ -297,6 +372,90 @@ mod tests {
         fs::remove_dir_all(base_dir).expect("cleanup failed");
     }
 
+    ///
+    /// アセットの過去リビジョンが export に含まれ、import で復元されることを
+    /// 確認する。
+    ///
+    #[test]
+    fn backup_collect_roundtrips_asset_history() {
+        let (base_dir, db_path, asset_path) = prepare_test_dirs();
+        let manager = DatabaseManager::open(&db_path, &asset_path)
+            .expect("open manager failed");
+        manager
+            .add_user("alice", "pass", None)
+            .expect("add user failed");
+
+        let page_id = manager
+            .create_page("/tree/a", "alice", "# body".to_string())
+            .expect("create page failed");
+        let asset_id = manager
+            .create_asset(&page_id, "note.txt", "text/plain", "alice", b"v1")
+            .expect("create asset failed");
+        manager
+            .replace_asset_data(&asset_id, "text/plain", "alice", b"v2")
+            .expect("replace asset failed");
+
+        /*
+         * 過去リビジョンの収集
+         */
+        let collected = collect_export(&manager, &ExportImportPolicy::backup())
+            .expect("collect export failed");
+        assert_eq!(collected.bundle.assets.len(), 1);
+        assert_eq!(collected.bundle.assets[0].revision, 2);
+        assert_eq!(collected.bundle.assets[0].history.len(), 1);
+        assert_eq!(collected.bundle.asset_blobs.len(), 2);
+
+        /*
+         * ZIP を経由した別 DB への復元
+         */
+        let mut archive = Cursor::new(Vec::new());
+        write_bundle_to_writer(&collected.bundle, &mut archive, None)
+            .expect("write archive failed");
+        archive.set_position(0);
+        let bundle = read_bundle_from_reader(archive, None)
+            .expect("read archive failed");
+        assert_eq!(bundle.asset_blobs, collected.bundle.asset_blobs);
+
+        let (restore_dir, restore_db_path, restore_asset_path) =
+            prepare_test_dirs();
+        let restored = DatabaseManager::open(
+            &restore_db_path,
+            &restore_asset_path,
+        )
+        .expect("open restore manager failed");
+        let policy = ExportImportPolicy::backup();
+        let validated =
+            validate_import(&restored, &policy, &[], false, false, bundle)
+                .expect("validate import failed");
+        apply_import(&restored, &policy, &[], validated)
+            .expect("apply import failed");
+
+        let revisions = restored
+            .list_asset_revisions(&asset_id)
+            .expect("list revisions failed");
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(
+            restored
+                .read_asset_revision_data(&asset_id, 1)
+                .expect("read revision failed")
+                .1,
+            b"v1".to_vec()
+        );
+        assert_eq!(
+            restored.read_asset_data(&asset_id).expect("read asset failed"),
+            b"v2".to_vec()
+        );
+        assert!(
+            restored
+                .check_asset_blobs(false)
+                .expect("check asset blobs failed")
+                .is_empty()
+        );
+
+        fs::remove_dir_all(base_dir).expect("cleanup failed");
+        fs::remove_dir_all(restore_dir).expect("cleanup failed");
+    }
+
     #[test]
     fn migrate_collect_normalizes_rename_and_collects_delete_targets() {
         let (base_dir, db_path, asset_path) = prepare_test_dirs();
@@ -0,0 +1,607 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 他の Wiki システムからの取り込みに共通する処理
+//!
+//! MediaWiki や DokuWiki から読み込んだページ履歴を migrate 用の bundle へ
+//! 変換し、アーカイブの migrate import と同じ検証・反映処理で取り込む。
+//!
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Local};
+use mime_guess::MimeGuess;
+use serde_yaml_ng::{Mapping, Value};
+
+use super::import_apply::apply_import;
+use super::model::{
+    ExportAsset,
+    ExportAssetBlob,
+    ExportBundle,
+    ExportPage,
+    ExportRevision,
+    ExportType,
+    ExportUser,
+    ManifestContext,
+};
+use super::policy::ExportImportPolicy;
+use super::validate::{ValidationWarning, validate_import};
+use crate::database::DatabaseManager;
+use crate::database::types::{AssetHash, AssetId, PageId, UserId};
+
+/// 変換した bundle の基準パス(取り込み時は`--migrate`の指定先へ再配置する)
+const FOREIGN_EXPORT_ROOT: &str = "/import";
+
+/// 編集者を特定できないリビジョンに記録する編集者名
+pub(crate) const UNKNOWN_CONTRIBUTOR: &str = "(unknown)";
+
+///
+/// 他 Wiki 取り込み要求
+///
+#[derive(Clone, Debug)]
+pub(crate) struct ForeignImportRequest {
+    /// 入力パス
+    pub(crate) input_path: PathBuf,
+
+    /// 取り込み先のプレフィクス
+    pub(crate) prefix: String,
+
+    /// ユーザマッピング
+    pub(crate) user_map: Vec<(String, String)>,
+
+    /// マッピングされない編集者に代わって記録するユーザ名
+    pub(crate) fallback_user: Option<String>,
+
+    /// リハーサルモード
+    pub(crate) dry_run: bool,
+
+    /// strict-mode
+    pub(crate) strict_mode: bool,
+
+    /// 破損リンクを `about:invalid` へ置換するか否か
+    pub(crate) fix_broken_link: bool,
+}
+
+///
+/// 他 Wiki 取り込み結果
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ForeignImportResult {
+    /// 作成したページ
+    pub(crate) pages: Vec<PageId>,
+
+    /// 作成したリビジョン数
+    pub(crate) revisions: usize,
+
+    /// 作成したアセット
+    pub(crate) assets: Vec<AssetId>,
+
+    /// 変換時および検証時の warning
+    pub(crate) warnings: Vec<ValidationWarning>,
+}
+
+///
+/// 他 Wiki から読み込んだページ群
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ForeignWiki {
+    /// ページ(取り込み先からの相対パス順)
+    pub(crate) pages: Vec<ForeignPage>,
+
+    /// 変換時の warning
+    pub(crate) warnings: Vec<ValidationWarning>,
+}
+
+impl ForeignWiki {
+    ///
+    /// 編集者一覧の取得
+    ///
+    /// # 戻り値
+    /// 全リビジョンの編集者名を重複無しで返す。
+    ///
+    pub(crate) fn contributors(&self) -> BTreeSet<String> {
+        self.pages
+            .iter()
+            .flat_map(|page| page.revisions.iter())
+            .map(|revision| revision.user.clone())
+            .collect()
+    }
+
+    ///
+    /// warning の追加
+    ///
+    /// # 引数
+    /// * `code` - warning コード
+    /// * `message` - メッセージ
+    ///
+    pub(crate) fn warn(&mut self, code: &'static str, message: String) {
+        self.warnings.push(ValidationWarning { code, message });
+    }
+}
+
+///
+/// 他 Wiki のページ
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ForeignPage {
+    /// 取り込み先からの相対パス(起点ページは空文字列)
+    pub(crate) rel_path: String,
+
+    /// リビジョン(古い順)
+    pub(crate) revisions: Vec<ForeignRevision>,
+
+    /// ページ所属アセット
+    pub(crate) assets: Vec<ForeignAsset>,
+}
+
+///
+/// 他 Wiki のページリビジョン
+///
+#[derive(Clone, Debug)]
+pub(crate) struct ForeignRevision {
+    /// 作成日時
+    pub(crate) timestamp: DateTime<Local>,
+
+    /// 元の Wiki 上での編集者名
+    pub(crate) user: String,
+
+    /// 変換済みのページソース
+    pub(crate) source: String,
+}
+
+///
+/// 他 Wiki のアセット
+///
+#[derive(Clone, Debug)]
+pub(crate) struct ForeignAsset {
+    /// ファイル名
+    pub(crate) file_name: String,
+
+    /// 内容
+    pub(crate) data: Vec<u8>,
+}
+
+///
+/// 他 Wiki から読み込んだページ群の取り込み
+///
+/// # 引数
+/// * `db` - データベースマネージャ
+/// * `wiki` - 読み込み済みのページ群
+/// * `request` - 取り込み要求
+///
+/// # 戻り値
+/// 取り込み結果を返す。
+///
+/// # 注記
+/// 編集者は`--user-map`の指定、同名の既存ユーザ、`fallback_user`の順に
+/// 既存ユーザへ対応付ける。認証情報を持たないため、対応付けられない編集者
+/// が残る場合はエラーとする。
+///
+pub(crate) fn import_foreign_wiki(
+    db: &DatabaseManager,
+    wiki: ForeignWiki,
+    request: &ForeignImportRequest,
+) -> Result<ForeignImportResult> {
+    if wiki.pages.is_empty() {
+        bail!("no pages found: {}", request.input_path.display());
+    }
+
+    /*
+     * bundle への変換と編集者の対応付け
+     */
+    let bundle = build_foreign_bundle(&wiki)?;
+    let user_map = complete_user_map(
+        db,
+        &wiki.contributors(),
+        &request.user_map,
+        request.fallback_user.as_deref(),
+    )?;
+    let policy = ExportImportPolicy::migrate(FOREIGN_EXPORT_ROOT)?
+        .with_relocate_prefix(request.prefix.clone());
+
+    /*
+     * migrate import と同じ検証と反映
+     */
+    let validated = validate_import(
+        db,
+        &policy,
+        &user_map,
+        request.strict_mode,
+        request.fix_broken_link,
+        bundle,
+    )?;
+
+    let mut warnings = wiki.warnings;
+    warnings.extend(validated.warnings.iter().cloned());
+
+    let result = ForeignImportResult {
+        pages: validated
+            .bundle
+            .pages
+            .iter()
+            .map(|page| page.id.clone())
+            .collect(),
+        revisions: validated.bundle.revisions.len(),
+        assets: validated
+            .bundle
+            .assets
+            .iter()
+            .map(|asset| asset.id.clone())
+            .collect(),
+        warnings,
+    };
+
+    if !request.dry_run {
+        apply_import(db, &policy, &user_map, validated)?;
+    }
+
+    Ok(result)
+}
+
+///
+/// ページ群の migrate 用 bundle への変換
+///
+/// # 引数
+/// * `wiki` - 読み込み済みのページ群
+///
+/// # 戻り値
+/// 変換した bundle を返す。
+///
+/// # 注記
+/// 編集者は認証情報を持たない仮ユーザとして格納する。取り込み時は全ての
+/// 仮ユーザを既存ユーザへ対応付けるため、仮ユーザ自体は登録されない。
+/// アセットの編集者と作成日時は所属ページの最新リビジョンに合わせる。
+///
+fn build_foreign_bundle(wiki: &ForeignWiki) -> Result<ExportBundle> {
+    let mut bundle = ExportBundle::new(ManifestContext {
+        export_type: ExportType::Migrate,
+        export_root: FOREIGN_EXPORT_ROOT.to_string(),
+        relocate_prefix: None,
+    });
+
+    let user_ids: BTreeMap<String, UserId> = wiki
+        .contributors()
+        .into_iter()
+        .map(|name| (name, UserId::new()))
+        .collect();
+    for (name, id) in &user_ids {
+        bundle.users.push(ExportUser {
+            id: id.clone(),
+            username: name.clone(),
+            password: String::new(),
+            salt: [0u8; 16],
+            display_name: name.clone(),
+            attributes: Default::default(),
+        });
+    }
+
+    for page in &wiki.pages {
+        let Some(latest) = page.revisions.last() else {
+            bail!("page has no revisions: {}", page.rel_path);
+        };
+        let page_id = PageId::new();
+
+        bundle.pages.push(ExportPage {
+            id: page_id.clone(),
+            path: page.rel_path.clone(),
+            latest: page.revisions.len() as u64,
+            earliest: 1,
+            rename_revisions: None,
+        });
+
+        for (index, revision) in page.revisions.iter().enumerate() {
+            bundle.revisions.push(ExportRevision {
+                page: page_id.clone(),
+                revision: index as u64 + 1,
+                timestamp: revision.timestamp,
+                user: user_ids[&revision.user].clone(),
+                rename: None,
+                source: revision.source.clone(),
+            });
+        }
+
+        for asset in &page.assets {
+            let asset_id = AssetId::new();
+            bundle.assets.push(ExportAsset {
+                id: asset_id.clone(),
+                page: page_id.clone(),
+                file_name: asset.file_name.clone(),
+                mime: MimeGuess::from_path(&asset.file_name)
+                    .first_or_octet_stream()
+                    .essence_str()
+                    .to_string(),
+                size: asset.data.len() as u64,
+                sha256: Some(AssetHash::from_data(&asset.data)),
+                revision: 1,
+                user: user_ids[&latest.user].clone(),
+                timestamp: latest.timestamp,
+                history: Vec::new(),
+            });
+            bundle.asset_blobs.push(ExportAssetBlob {
+                asset_id,
+                revision: None,
+                data: asset.data.clone(),
+            });
+        }
+    }
+
+    bundle.sync_manifest_counts();
+    Ok(bundle)
+}
+
+///
+/// 全編集者を既存ユーザへ対応付けるユーザマッピングの構築
+///
+/// # 引数
+/// * `db` - データベースマネージャ
+/// * `contributors` - 編集者名一覧
+/// * `user_map` - 明示指定されたユーザマッピング
+/// * `fallback_user` - 対応付けられない編集者に代わって記録するユーザ名
+///
+/// # 戻り値
+/// 全編集者を網羅したユーザマッピングを返す。
+///
+fn complete_user_map(
+    db: &DatabaseManager,
+    contributors: &BTreeSet<String>,
+    user_map: &[(String, String)],
+    fallback_user: Option<&str>,
+) -> Result<Vec<(String, String)>> {
+    let mut mappings = user_map.to_vec();
+    let mapped: HashSet<String> =
+        user_map.iter().map(|(src, _)| src.clone()).collect();
+
+    let mut unmapped = Vec::new();
+    for contributor in contributors {
+        if mapped.contains(contributor) {
+            continue;
+        }
+
+        if db.get_user_id_by_name(contributor)?.is_some() {
+            mappings.push((contributor.clone(), contributor.clone()));
+        } else if let Some(fallback_user) = fallback_user {
+            mappings.push((contributor.clone(), fallback_user.to_string()));
+        } else {
+            unmapped.push(contributor.as_str());
+        }
+    }
+
+    if !unmapped.is_empty() {
+        bail!(
+            "contributors are not mapped to existing users (use --user-map or --user): {}",
+            unmapped.join(", ")
+        );
+    }
+
+    Ok(mappings)
+}
+
+///
+/// ページ間の相対リンク先の算出
+///
+/// # 引数
+/// * `from` - リンク元ページの相対パス
+/// * `to` - リンク先ページの相対パス
+///
+/// # 戻り値
+/// リンク元ページを基準とした相対パスを返す。
+///
+/// # 注記
+/// ページリンクの相対パスはリンク元ページ自身を起点に解決されるため、
+/// 兄弟ページへのリンクは`../<名前>`となる。
+///
+pub(crate) fn relative_link_target(from: &str, to: &str) -> String {
+    let from: Vec<&str> =
+        from.split('/').filter(|segment| !segment.is_empty()).collect();
+    let to: Vec<&str> =
+        to.split('/').filter(|segment| !segment.is_empty()).collect();
+
+    let common = from
+        .iter()
+        .zip(&to)
+        .take_while(|(left, right)| left == right)
+        .count();
+
+    let mut parts = vec![".."; from.len() - common];
+    parts.extend(&to[common..]);
+
+    if parts.is_empty() {
+        ".".to_string()
+    } else {
+        parts.join("/")
+    }
+}
+
+///
+/// アセット参照記法の生成
+///
+/// # 引数
+/// * `from` - 参照元ページの相対パス
+/// * `owner` - アセット所属ページの相対パス
+/// * `file_name` - アセットのファイル名
+///
+/// # 戻り値
+/// `asset:`記法の参照先文字列を返す。
+///
+pub(crate) fn asset_link_target(
+    from: &str,
+    owner: &str,
+    file_name: &str,
+) -> String {
+    if from == owner {
+        format!("asset:{}", encode_link_target(file_name))
+    } else {
+        format!(
+            "asset:{}:{}",
+            encode_link_target(&relative_link_target(from, owner)),
+            encode_link_target(file_name)
+        )
+    }
+}
+
+///
+/// Markdown リンク先として記述できない文字のエンコード
+///
+/// # 引数
+/// * `target` - リンク先
+///
+/// # 戻り値
+/// 空白、丸括弧、`%`をパーセントエンコードした文字列を返す。
+///
+pub(crate) fn encode_link_target(target: &str) -> String {
+    let mut encoded = String::with_capacity(target.len());
+    for ch in target.chars() {
+        match ch {
+            ' ' => encoded.push_str("%20"),
+            '%' => encoded.push_str("%25"),
+            '(' => encoded.push_str("%28"),
+            ')' => encoded.push_str("%29"),
+            _ => encoded.push(ch),
+        }
+    }
+    encoded
+}
+
+///
+/// Markdown リンクの生成
+///
+/// # 引数
+/// * `text` - リンクテキスト
+/// * `target` - エンコード済みのリンク先
+///
+/// # 戻り値
+/// `[text](target)`形式の文字列を返す。
+///
+pub(crate) fn markdown_link(text: &str, target: &str) -> String {
+    let text = text.replace('[', "\\[").replace(']', "\\]");
+    format!("[{}]({})", text, target)
+}
+
+///
+/// タグ名の正規化
+///
+/// # 引数
+/// * `raw` - 元のタグ名
+///
+/// # 戻り値
+/// 先頭の`#`を除き、空白を`_`へ置き換えたタグ名を返す。空の場合は`None`
+/// を返す。
+///
+pub(crate) fn normalize_tag(raw: &str) -> Option<String> {
+    let tag = raw
+        .trim()
+        .trim_start_matches('#')
+        .split(|ch: char| ch.is_whitespace() || ch.is_control())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+
+    if tag.is_empty() { None } else { Some(tag) }
+}
+
+///
+/// front matter の生成
+///
+/// # 引数
+/// * `tags` - `wiki.tags`へ記録するタグ
+/// * `custom_meta` - `custom_meta`へ記録する値
+///
+/// # 戻り値
+/// 区切り行を含む front matter を返す。記録する値が無い場合は空文字列を
+/// 返す。
+///
+pub(crate) fn compose_front_matter(
+    tags: &[String],
+    custom_meta: Mapping,
+) -> Result<String> {
+    let mut root = Mapping::new();
+
+    let mut unique = Vec::new();
+    for tag in tags {
+        if !unique.contains(tag) {
+            unique.push(tag.clone());
+        }
+    }
+    if !unique.is_empty() {
+        let mut wiki = Mapping::new();
+        wiki.insert(
+            Value::String("tags".to_string()),
+            Value::Sequence(unique.into_iter().map(Value::String).collect()),
+        );
+        root.insert(Value::String("wiki".to_string()), Value::Mapping(wiki));
+    }
+    if !custom_meta.is_empty() {
+        root.insert(
+            Value::String("custom_meta".to_string()),
+            Value::Mapping(custom_meta),
+        );
+    }
+
+    if root.is_empty() {
+        return Ok(String::new());
+    }
+
+    Ok(format!("---\n{}---\n", serde_yaml_ng::to_string(&root)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///
+    /// ページ間の相対リンク先を算出できることを確認する。
+    ///
+    #[test]
+    fn relative_link_target_resolves_from_page_itself() {
+        assert_eq!(relative_link_target("a/b", "a/c"), "../c");
+        assert_eq!(relative_link_target("a", "a/b"), "b");
+        assert_eq!(relative_link_target("a/b", "a"), "..");
+        assert_eq!(relative_link_target("", "x/y"), "x/y");
+        assert_eq!(relative_link_target("a/b", "c"), "../../c");
+        assert_eq!(relative_link_target("a", "a"), ".");
+        assert_eq!(asset_link_target("a", "a", "my file.png"), "asset:my%20file.png");
+        assert_eq!(asset_link_target("b", "a", "x.png"), "asset:../a:x.png");
+    }
+
+    ///
+    /// タグと任意の値から front matter を生成できることを確認する。
+    ///
+    #[test]
+    fn compose_front_matter_builds_wiki_tags() {
+        let tags = vec![
+            normalize_tag("#project").unwrap(),
+            normalize_tag("big idea").unwrap(),
+            normalize_tag("project").unwrap(),
+        ];
+        assert_eq!(normalize_tag(" # "), None);
+
+        let mut meta = Mapping::new();
+        meta.insert(
+            Value::String("aliases".to_string()),
+            Value::String("alt".to_string()),
+        );
+        let front_matter =
+            compose_front_matter(&tags, meta).expect("compose failed");
+        let source = format!("{}body\n", front_matter);
+
+        let parsed = crate::markdown_source::front_matter::parse_document_front_matter(&source)
+            .expect("parse failed")
+            .expect("front matter missing");
+        assert_eq!(
+            parsed.wiki().and_then(|wiki| wiki.tags()),
+            Some(&["project".to_string(), "big_idea".to_string()][..])
+        );
+        assert!(parsed.custom_meta().is_some());
+
+        assert_eq!(
+            compose_front_matter(&[], Mapping::new()).expect("compose failed"),
+            ""
+        );
+    }
+}
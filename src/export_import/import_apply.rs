@@ -14,11 +14,17 @@ use std::path::PathBuf;
 
 use anyhow::{Result, anyhow, bail};
 
+use super::markdown_tree::MarkdownImportResult;
 use super::model::ExportBundle;
 use super::policy::{ExportImportPolicy, PlacementRule};
-use super::validate::ValidatedImportBundle;
-use crate::database::DatabaseManager;
-use crate::database::types::{AssetId, PageId, UserId};
+use super::validate::{
+    MarkdownAssetAction,
+    MarkdownPageAction,
+    ValidatedImportBundle,
+    ValidatedMarkdownImport,
+};
+use crate::database::{AppendPageRequest, DatabaseManager};
+use crate::database::types::{AssetHash, PageId, UserId};
 
 ///
 /// import 反映の入口
@@ -42,6 +48,11 @@ pub(crate) fn apply_import(
      * 反映前条件の再確認と bundle 整形
      */
     validate_apply_target(db, policy)?;
+    if policy.placement_rule() == PlacementRule::ApplyOntoRestoredDatabase
+        && !user_map.is_empty()
+    {
+        bail!("user_map is not supported for incremental import");
+    }
 
     let mut bundle = validated.bundle;
     apply_user_map(db, &mut bundle, user_map)?;
@@ -51,6 +62,16 @@ pub(crate) fn apply_import(
      * アセットを一時配置し、DB commit 後に本配置する
      */
     let staged_assets = stage_asset_blobs(db, &bundle)?;
+    if policy.placement_rule() == PlacementRule::ApplyOntoRestoredDatabase {
+        return match db.apply_incremental_import_bundle(&bundle) {
+            Ok(()) => commit_staged_assets(db, staged_assets),
+            Err(err) => {
+                discard_staged_assets(db, staged_assets);
+                Err(err)
+            }
+        };
+    }
+
     match db.insert_import_bundle(&bundle) {
         Ok(()) => {
             commit_staged_assets(db, staged_assets)?;
@@ -162,6 +183,11 @@ fn apply_user_map(
         if let Some(target_id) = mapped_user_ids.get(&asset.user) {
             asset.user = target_id.clone();
         }
+        for revision in &mut asset.history {
+            if let Some(target_id) = mapped_user_ids.get(&revision.user) {
+                revision.user = target_id.clone();
+            }
+        }
     }
 
     /*
@@ -215,13 +241,22 @@ fn relocate_bundle(
 fn stage_asset_blobs(
     db: &DatabaseManager,
     bundle: &ExportBundle,
-) -> Result<Vec<(AssetId, PathBuf)>> {
+) -> Result<Vec<(AssetHash, PathBuf)>> {
     let mut staged_assets = Vec::new();
+    let mut staged_hashes = HashSet::new();
 
     for blob in &bundle.asset_blobs {
-        match db.stage_asset_blob(&blob.asset_id, &blob.data) {
+        /*
+         * 同一内容の実体は一度だけ配置する
+         */
+        let hash = AssetHash::from_data(&blob.data);
+        if !staged_hashes.insert(hash) {
+            continue;
+        }
+
+        match db.stage_asset_blob(&hash, &blob.data) {
             Ok(staged_path) => {
-                staged_assets.push((blob.asset_id.clone(), staged_path));
+                staged_assets.push((hash, staged_path));
             }
 
             Err(err) => {
@@ -246,23 +281,25 @@ fn stage_asset_blobs(
 ///
 fn commit_staged_assets(
     db: &DatabaseManager,
-    staged_assets: Vec<(AssetId, PathBuf)>,
+    staged_assets: Vec<(AssetHash, PathBuf)>,
 ) -> Result<()> {
-    let mut committed_asset_ids = Vec::new();
+    let mut committed_hashes = Vec::new();
     let mut pending_assets = staged_assets.into_iter();
 
-    while let Some((asset_id, staged_path)) = pending_assets.next() {
-        match db.commit_staged_asset_blob(&staged_path, &asset_id) {
-            Ok(()) => {
-                committed_asset_ids.push(asset_id);
+    while let Some((hash, staged_path)) = pending_assets.next() {
+        match db.commit_staged_asset_blob(&staged_path, &hash) {
+            Ok(true) => {
+                committed_hashes.push(hash);
             }
 
+            Ok(false) => {}
+
             Err(err) => {
                 let _ = db.discard_staged_asset_blob(&staged_path);
                 for (_, pending_path) in pending_assets {
                     let _ = db.discard_staged_asset_blob(pending_path);
                 }
-                remove_committed_asset_files(db, &committed_asset_ids);
+                remove_committed_asset_files(db, &committed_hashes);
                 return Err(err);
             }
         }
@@ -283,7 +320,7 @@ fn commit_staged_assets(
 ///
 fn discard_staged_assets(
     db: &DatabaseManager,
-    staged_assets: Vec<(AssetId, PathBuf)>,
+    staged_assets: Vec<(AssetHash, PathBuf)>,
 ) {
     for (_, staged_path) in staged_assets {
         let _ = db.discard_staged_asset_blob(staged_path);
@@ -295,20 +332,107 @@ fn discard_staged_assets(
 ///
 /// # 引数
 /// * `db` - 反映対象 DB
-/// * `asset_ids` - 削除対象アセットID一覧
+/// * `hashes` - 今回新規に配置した実体の内容ハッシュ値一覧
 ///
 /// # 戻り値
 /// なし
 ///
 fn remove_committed_asset_files(
     db: &DatabaseManager,
-    asset_ids: &[AssetId],
+    hashes: &[AssetHash],
 ) {
-    for asset_id in asset_ids {
-        let _ = fs::remove_file(db.asset_file_path(asset_id));
+    for hash in hashes {
+        let _ = fs::remove_file(db.asset_blob_path(hash));
     }
 }
 
+///
+/// Markdown ツリー import 反映の入口
+///
+/// # 引数
+/// * `db` - 反映対象 DB
+/// * `user_name` - 編集者として記録するユーザ名
+/// * `validated` - 検証済みの反映内容
+///
+/// # 戻り値
+/// 反映結果を返す。
+///
+/// # 注記
+/// ページ単位で反映を確定するため、途中で失敗した場合はそれまでの反映が
+/// 残る。検証後に他の編集が入った場合はリビジョン競合としてエラーになる。
+///
+pub(crate) fn apply_markdown_import(
+    db: &DatabaseManager,
+    user_name: &str,
+    validated: ValidatedMarkdownImport,
+) -> Result<MarkdownImportResult> {
+    let mut result = MarkdownImportResult {
+        warnings: validated.warnings,
+        ..Default::default()
+    };
+
+    for page in validated.pages {
+        /*
+         * ページの反映
+         */
+        let page_id = match page.action {
+            MarkdownPageAction::Create { source } => {
+                let page_id = db.create_page(&page.path, user_name, source)?;
+                result.created_pages.push(page_id.clone());
+                page_id
+            }
+            MarkdownPageAction::Update {
+                page_id,
+                expected_revision,
+                source,
+            } => {
+                db.append_page_by_id(&AppendPageRequest::new(
+                    page_id.clone(),
+                    user_name.to_string(),
+                    source,
+                    expected_revision,
+                    false,
+                ))?;
+                result.updated_pages.push(page_id.clone());
+                page_id
+            }
+            MarkdownPageAction::Unchanged { page_id } => {
+                result.unchanged_pages += 1;
+                page_id
+            }
+            MarkdownPageAction::AssetsOnly { page_id } => page_id,
+        };
+
+        /*
+         * ページ所属アセットの反映
+         */
+        for asset in page.assets {
+            let asset_id = match asset.action {
+                MarkdownAssetAction::Create => db.create_asset(
+                    &page_id,
+                    &asset.file_name,
+                    &asset.mime,
+                    user_name,
+                    &asset.data,
+                )?,
+                MarkdownAssetAction::Replace { asset_id } => {
+                    db.replace_asset_data(
+                        &asset_id,
+                        &asset.mime,
+                        user_name,
+                        &asset.data,
+                    )?;
+                    asset_id
+                }
+                MarkdownAssetAction::Unchanged => continue,
+            };
+            result.assets.push(asset_id);
+        }
+    }
+
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs;
@@ -595,6 +719,9 @@ mod tests {
                 page_count: 1,
                 revision_count: 1,
                 asset_count: 1,
+                export_id: None,
+                base_export: None,
+                since: None,
             },
             users: vec![ExportUser {
                 id: user_id.clone(),
@@ -627,13 +754,18 @@ mod tests {
                 file_name: "hello.txt".to_string(),
                 mime: "text/plain".to_string(),
                 size: 5,
+                sha256: None,
+                revision: 1,
                 user: user_id,
                 timestamp,
+                history: Vec::new(),
             }],
             asset_blobs: vec![ExportAssetBlob {
                 asset_id,
+                revision: None,
                 data: b"hello".to_vec(),
             }],
+            live: Vec::new(),
             manifest_context: ManifestContext {
                 export_type: ExportType::Backup,
                 export_root: "/".to_string(),
@@ -657,6 +789,9 @@ mod tests {
                 page_count: 1,
                 revision_count: 1,
                 asset_count: 1,
+                export_id: None,
+                base_export: None,
+                since: None,
             },
             users: vec![ExportUser {
                 id: user_id.clone(),
@@ -693,13 +828,18 @@ mod tests {
                 file_name: "move.txt".to_string(),
                 mime: "text/plain".to_string(),
                 size: 5,
+                sha256: None,
+                revision: 1,
                 user: user_id,
                 timestamp,
+                history: Vec::new(),
             }],
             asset_blobs: vec![ExportAssetBlob {
                 asset_id,
+                revision: None,
                 data: b"moved".to_vec(),
             }],
+            live: Vec::new(),
             manifest_context: ManifestContext {
                 export_type: ExportType::Migrate,
                 export_root: "/src".to_string(),
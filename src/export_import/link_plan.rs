@@ -168,6 +168,69 @@ pub(crate) fn build_migrate_link_plan(
     Ok(plan)
 }
 
+///
+/// 単一ソースのリンク問題
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct SourceLinkIssue {
+    pub(crate) kind: LinkIssueKind,
+    pub(crate) raw_target: String,
+    pub(crate) rewritten: bool,
+}
+
+///
+/// 単一ソースのリンク計画
+///
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct SourceLinkPlan {
+    pub(crate) issues: Vec<SourceLinkIssue>,
+    pub(crate) source: String,
+}
+
+///
+/// ページ ID を持たないソースのリンク計画を構築する。
+///
+/// # 引数
+/// * `base_path` - ソースの最終配置パス
+/// * `destination_root` - 取り込み後のルートパス
+/// * `source` - Markdown ソース
+/// * `fix_broken_link` - 破損ページリンクを `about:invalid` へ置換する場合は true
+///
+/// # 戻り値
+/// 検出したリンク問題と、必要時は書き換え後のソースを返す。
+///
+/// # 注記
+/// Markdown ツリー import のように、取り込み前にページ ID が確定しない
+/// ソースに対して migrate import と同じ規則を適用するために用いる。
+/// strict-mode の判定は呼び出し側で `LinkIssueKind::is_strict_error()`
+/// を用いて行う。
+///
+pub(crate) fn build_source_link_plan(
+    base_path: &str,
+    destination_root: &str,
+    source: &str,
+    fix_broken_link: bool,
+) -> SourceLinkPlan {
+    let scan = scan_revision_links(base_path, destination_root, source);
+    let issues = scan
+        .issues
+        .into_iter()
+        .map(|issue| SourceLinkIssue {
+            rewritten: fix_broken_link && issue.kind.is_page_rewrite_target(),
+            kind: issue.kind,
+            raw_target: issue.raw_target,
+        })
+        .collect();
+
+    let source = if fix_broken_link && !scan.replacements.is_empty() {
+        apply_replacements(source, &scan.replacements)
+    } else {
+        source.to_string()
+    };
+
+    SourceLinkPlan { issues, source }
+}
+
 ///
 /// 1 revision 分のリンク解析結果
 ///
@@ -369,6 +432,10 @@ fn classify_asset_link(
     target: &str,
 ) -> Option<(LinkIssueKind, Option<String>)> {
     let parsed = parse_asset_spec(target)?;
+    if parsed.path.is_empty() {
+        // 共有アセットはページツリーに属さないため対象外
+        return None;
+    }
     let resolved = resolve_page_path(base_path, &parsed.path)?;
     if !is_path_in_tree(destination_root, &resolved) {
         return Some((LinkIssueKind::TreeExternalAssetLink, Some(resolved)));
@@ -757,11 +824,15 @@ mod tests {
             file_name: "note.txt".to_string(),
             mime: "text/plain".to_string(),
             size: 5,
+            sha256: None,
+            revision: 1,
             user: user_id,
             timestamp: chrono::Local::now(),
+            history: Vec::new(),
         });
         bundle.asset_blobs.push(ExportAssetBlob {
             asset_id: bundle.assets[0].id.clone(),
+            revision: None,
             data: b"hello".to_vec(),
         });
 
@@ -837,4 +908,21 @@ mod tests {
 
         assert!(err.to_string().contains("strict-mode"));
     }
+
+    #[test]
+    fn build_source_link_plan_reports_and_rewrites_without_page_id() {
+        let source = "[out](../../outside) [in](./child) [abs](/x)";
+
+        let plan = build_source_link_plan("/dst/page", "/dst", source, false);
+        assert_eq!(plan.issues.len(), 2);
+        assert!(plan.issues.iter().all(|issue| !issue.rewritten));
+        assert_eq!(plan.source, source);
+
+        let plan = build_source_link_plan("/dst/page", "/dst", source, true);
+        assert!(plan.issues.iter().all(|issue| issue.rewritten));
+        assert_eq!(
+            plan.source,
+            "[out](about:invalid) [in](./child) [abs](about:invalid)"
+        );
+    }
 }
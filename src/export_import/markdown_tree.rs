@@ -0,0 +1,620 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! Markdown ディレクトリツリー形式の export/import
+//!
+//! 1ページを1つの`.md`ファイルとして Wiki 上のパスに対応する位置へ配置し、
+//! ページ所属アセットは`<ページ名>.assets/`ディレクトリへ配置する。
+//!
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+use super::import_apply::apply_markdown_import;
+use super::static_site::{check_output_dir, write_output_dir};
+use super::validate::{ValidationWarning, validate_markdown_import};
+use crate::database::DatabaseManager;
+use crate::database::types::{AssetId, PageId};
+
+/// 起点ページ(ディレクトリ自身のページ)を表すファイル名の語幹
+pub(crate) const INDEX_FILE_STEM: &str = "_index";
+
+/// ページファイルの拡張子
+const PAGE_FILE_EXTENSION: &str = ".md";
+
+/// アセットディレクトリ名の接尾辞
+const ASSET_DIR_SUFFIX: &str = ".assets";
+
+///
+/// Markdown ツリー export 要求
+///
+#[derive(Clone, Debug)]
+pub(crate) struct MarkdownExportRequest {
+    /// エクスポート起点パス
+    pub(crate) root: String,
+
+    /// 出力先ディレクトリ
+    pub(crate) output_dir: PathBuf,
+
+    /// リハーサルモード
+    pub(crate) dry_run: bool,
+}
+
+///
+/// Markdown ツリー export 結果
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct MarkdownExportResult {
+    /// 出力したページ数
+    pub(crate) page_count: usize,
+
+    /// 出力したアセット数
+    pub(crate) asset_count: usize,
+}
+
+///
+/// Markdown ツリー import 要求
+///
+#[derive(Clone, Debug)]
+pub(crate) struct MarkdownImportRequest {
+    /// 入力ディレクトリ
+    pub(crate) input_dir: PathBuf,
+
+    /// 取り込み先のプレフィクス
+    pub(crate) prefix: String,
+
+    /// 編集者として記録するユーザ名
+    pub(crate) user_name: String,
+
+    /// リハーサルモード
+    pub(crate) dry_run: bool,
+
+    /// strict-mode
+    pub(crate) strict_mode: bool,
+
+    /// 破損リンクを `about:invalid` へ置換するか否か
+    pub(crate) fix_broken_link: bool,
+}
+
+///
+/// Markdown ツリー import 結果
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MarkdownImportResult {
+    /// 新規作成したページ
+    pub(crate) created_pages: Vec<PageId>,
+
+    /// 新しいリビジョンを追加したページ
+    pub(crate) updated_pages: Vec<PageId>,
+
+    /// 内容に変更が無かったページ数
+    pub(crate) unchanged_pages: usize,
+
+    /// 作成または更新したアセット
+    pub(crate) assets: Vec<AssetId>,
+
+    /// 検証時の warning
+    pub(crate) warnings: Vec<ValidationWarning>,
+}
+
+///
+/// 読み込んだ Markdown ツリー
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MarkdownTree {
+    /// ページ(起点からの相対パス順)
+    pub(crate) pages: Vec<MarkdownTreePage>,
+
+    /// 取り込み対象外として無視したファイル
+    pub(crate) ignored: Vec<PathBuf>,
+}
+
+///
+/// Markdown ツリー上のページ
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MarkdownTreePage {
+    /// 起点からの相対パス(起点ページは空文字列)
+    pub(crate) rel_path: String,
+
+    /// ページソース(アセットディレクトリのみ存在する場合は`None`)
+    pub(crate) source: Option<String>,
+
+    /// ページ所属アセット
+    pub(crate) assets: Vec<MarkdownTreeAsset>,
+}
+
+///
+/// Markdown ツリー上のアセット
+///
+#[derive(Clone, Debug)]
+pub(crate) struct MarkdownTreeAsset {
+    /// ファイル名
+    pub(crate) file_name: String,
+
+    /// 実体ファイルのパス
+    pub(crate) file_path: PathBuf,
+}
+
+///
+/// Markdown ツリー形式での export
+///
+/// # 引数
+/// * `db` - データベースマネージャ
+/// * `request` - export 要求
+///
+/// # 戻り値
+/// export 結果を返す。
+///
+/// # 注記
+/// 起点配下の各ページの最新ソースを front matter を含めてそのまま出力する。
+/// 共有アセットは出力しない。
+///
+pub(crate) fn export_markdown_tree(
+    db: &DatabaseManager,
+    request: MarkdownExportRequest,
+) -> Result<MarkdownExportResult> {
+    check_output_dir(&request.output_dir)?;
+
+    /*
+     * 出力対象の収集
+     */
+    let read_set = db.collect_latest_page_read_set(&request.root)?;
+    if read_set.pages.is_empty() {
+        bail!("no pages to export: {}", request.root);
+    }
+
+    let mut rel_paths = BTreeMap::new();
+    for page in &read_set.pages {
+        let rel_path = relative_page_path(&request.root, &page.path)
+            .ok_or_else(|| anyhow!("page out of export root: {}", page.path))?;
+        check_exportable_rel_path(&rel_path)
+            .with_context(|| format!("page can not be exported: {}", page.path))?;
+        rel_paths.insert(page.page_id.clone(), rel_path);
+    }
+
+    let assets: Vec<_> = read_set
+        .assets
+        .iter()
+        .filter_map(|asset| {
+            let rel_path = rel_paths.get(asset.page_id.as_ref()?)?;
+            Some((rel_path, asset))
+        })
+        .collect();
+
+    let result = MarkdownExportResult {
+        page_count: read_set.pages.len(),
+        asset_count: assets.len(),
+    };
+
+    if request.dry_run {
+        return Ok(result);
+    }
+
+    /*
+     * ツリーの出力
+     */
+    write_output_dir(&request.output_dir, |work_dir| {
+        for page in &read_set.pages {
+            let rel_path = &rel_paths[&page.page_id];
+            let file_path = work_dir.join(page_file_rel_path(rel_path));
+            write_file(&file_path, page.source.as_bytes())?;
+        }
+
+        for (rel_path, asset) in &assets {
+            let dir = work_dir.join(asset_dir_rel_path(rel_path));
+            fs::create_dir_all(&dir)?;
+            fs::copy(&asset.blob_path, dir.join(&asset.file_name))
+                .with_context(|| {
+                    format!("copy asset failed: {}", asset.blob_path.display())
+                })?;
+        }
+
+        Ok(())
+    })?;
+
+    Ok(result)
+}
+
+///
+/// Markdown ツリー形式での import
+///
+/// # 引数
+/// * `db` - データベースマネージャ
+/// * `request` - import 要求
+///
+/// # 戻り値
+/// import 結果を返す。
+///
+/// # 注記
+/// 取り込み先に存在しないページは新規作成し、存在するページは内容が異なる
+/// 場合のみ新しいリビジョンを追加する。全ページの検証を終えてから反映を
+/// 開始するが、反映処理自体はページ単位で確定するため途中で失敗した場合
+/// は反映済みのページが残る(再実行時は変更の無いページを読み飛ばす)。
+///
+pub(crate) fn import_markdown_tree(
+    db: &DatabaseManager,
+    request: MarkdownImportRequest,
+) -> Result<MarkdownImportResult> {
+    let tree = read_markdown_tree(&request.input_dir)?;
+    if tree.pages.is_empty() {
+        bail!("no pages found: {}", request.input_dir.display());
+    }
+
+    import_loaded_markdown_tree(db, &request, tree)
+}
+
+///
+/// 読み込み済み Markdown ツリーの import
+///
+/// # 引数
+/// * `db` - データベースマネージャ
+/// * `request` - import 要求
+/// * `tree` - 読み込み済みの Markdown ツリー
+///
+/// # 戻り値
+/// import 結果を返す。
+///
+/// # 注記
+/// 他形式から変換したツリーの取り込みにも用いる。
+///
+pub(crate) fn import_loaded_markdown_tree(
+    db: &DatabaseManager,
+    request: &MarkdownImportRequest,
+    tree: MarkdownTree,
+) -> Result<MarkdownImportResult> {
+    let validated = validate_markdown_import(
+        db,
+        &request.prefix,
+        &request.user_name,
+        request.strict_mode,
+        request.fix_broken_link,
+        tree,
+    )?;
+
+    if request.dry_run {
+        return Ok(MarkdownImportResult {
+            warnings: validated.warnings,
+            ..Default::default()
+        });
+    }
+
+    apply_markdown_import(db, &request.user_name, validated)
+}
+
+///
+/// Markdown ツリーの読み込み
+///
+/// # 引数
+/// * `input_dir` - 入力ディレクトリ
+///
+/// # 戻り値
+/// 読み込んだツリーを返す。
+///
+/// # 注記
+/// `.`で始まるファイルとディレクトリ(`.git`等)は読み飛ばす。
+/// `<名前>/_index.md`は`<名前>.md`と同じページとして扱う。
+///
+pub(crate) fn read_markdown_tree(input_dir: &Path) -> Result<MarkdownTree> {
+    if !input_dir.is_dir() {
+        bail!("input path is not a directory: {}", input_dir.display());
+    }
+
+    let mut pages = BTreeMap::new();
+    let mut ignored = Vec::new();
+    read_tree_dir(input_dir, "", &mut pages, &mut ignored)?;
+
+    Ok(MarkdownTree {
+        pages: pages.into_values().collect(),
+        ignored,
+    })
+}
+
+///
+/// ディレクトリ1階層分の読み込み
+///
+/// # 引数
+/// * `dir` - 読み込むディレクトリ
+/// * `rel_dir` - 起点からの相対パス
+/// * `pages` - 読み込んだページ(相対パス => ページ)
+/// * `ignored` - 無視したファイル
+///
+fn read_tree_dir(
+    dir: &Path,
+    rel_dir: &str,
+    pages: &mut BTreeMap<String, MarkdownTreePage>,
+    ignored: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let mut entries = fs::read_dir(dir)
+        .with_context(|| format!("read dir failed: {}", dir.display()))?
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let name = entry
+            .file_name()
+            .into_string()
+            .map_err(|_| anyhow!("invalid file name: {}", path.display()))?;
+        if name.starts_with('.') {
+            continue;
+        }
+
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            match name.strip_suffix(ASSET_DIR_SUFFIX) {
+                Some(stem) if !stem.is_empty() => {
+                    let rel_path = join_rel_path(rel_dir, stem);
+                    let page = pages.entry(rel_path.clone()).or_insert_with(|| {
+                        MarkdownTreePage {
+                            rel_path,
+                            ..Default::default()
+                        }
+                    });
+                    read_asset_dir(&path, page)?;
+                }
+                _ => {
+                    let rel_path = join_rel_path(rel_dir, &name);
+                    read_tree_dir(&path, &rel_path, pages, ignored)?;
+                }
+            }
+            continue;
+        }
+
+        let stem = match name.strip_suffix(PAGE_FILE_EXTENSION) {
+            Some(stem) if !stem.is_empty() && file_type.is_file() => stem,
+            _ => {
+                ignored.push(path);
+                continue;
+            }
+        };
+
+        let rel_path = join_rel_path(rel_dir, stem);
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("read page file failed: {}", path.display()))?;
+        let page = pages.entry(rel_path.clone()).or_insert_with(|| {
+            MarkdownTreePage {
+                rel_path: rel_path.clone(),
+                ..Default::default()
+            }
+        });
+        if page.source.is_some() {
+            bail!("duplicate page file: {}", path.display());
+        }
+        page.source = Some(source);
+    }
+
+    Ok(())
+}
+
+///
+/// アセットディレクトリの読み込み
+///
+fn read_asset_dir(dir: &Path, page: &mut MarkdownTreePage) -> Result<()> {
+    let mut entries = fs::read_dir(dir)
+        .with_context(|| format!("read dir failed: {}", dir.display()))?
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let name = entry
+            .file_name()
+            .into_string()
+            .map_err(|_| anyhow!("invalid file name: {}", path.display()))?;
+        if name.starts_with('.') {
+            continue;
+        }
+
+        if !entry.file_type()?.is_file() {
+            bail!("unexpected entry in asset directory: {}", path.display());
+        }
+
+        page.assets.push(MarkdownTreeAsset {
+            file_name: name,
+            file_path: path,
+        });
+    }
+
+    Ok(())
+}
+
+///
+/// 相対パスの連結
+///
+/// # 注記
+/// 語幹が`_index`の場合はディレクトリ自身のページを表す。
+///
+fn join_rel_path(rel_dir: &str, name: &str) -> String {
+    if name == INDEX_FILE_STEM {
+        rel_dir.to_string()
+    } else if rel_dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", rel_dir, name)
+    }
+}
+
+///
+/// 起点からの相対パスの算出
+///
+/// # 引数
+/// * `root` - 起点パス
+/// * `path` - ページパス
+///
+/// # 戻り値
+/// 起点配下の場合は相対パス(起点自身は空文字列)を返す。
+///
+pub(crate) fn relative_page_path(root: &str, path: &str) -> Option<String> {
+    let rest = if root == "/" {
+        path.strip_prefix('/')?
+    } else if path == root {
+        ""
+    } else {
+        path.strip_prefix(root)?.strip_prefix('/')?
+    };
+
+    Some(rest.to_string())
+}
+
+///
+/// ツリーへ出力可能な相対パスか否かの確認
+///
+/// # 注記
+/// import 時に別の意味で解釈されるパス要素(`_index`、`.assets`で終わる
+/// 名前、`.`で始まる名前)を含む場合はエラーとする。
+///
+pub(crate) fn check_exportable_rel_path(rel_path: &str) -> Result<()> {
+    for segment in rel_path.split('/').filter(|segment| !segment.is_empty()) {
+        if segment == INDEX_FILE_STEM
+            || segment.ends_with(ASSET_DIR_SUFFIX)
+            || segment.ends_with(PAGE_FILE_EXTENSION)
+            || segment.starts_with('.')
+        {
+            bail!("reserved path segment: {}", segment);
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// ページファイルの相対パス
+///
+pub(crate) fn page_file_rel_path(rel_path: &str) -> String {
+    if rel_path.is_empty() {
+        format!("{}{}", INDEX_FILE_STEM, PAGE_FILE_EXTENSION)
+    } else {
+        format!("{}{}", rel_path, PAGE_FILE_EXTENSION)
+    }
+}
+
+///
+/// ページファイルの相対パスから起点からの相対パスへの変換
+///
+/// # 引数
+/// * `file_rel_path` - `/`区切りのページファイルの相対パス
+///
+/// # 戻り値
+/// ページファイルとして解釈できる場合は起点からの相対パスを返す。
+///
+/// # 注記
+/// `page_file_rel_path()`の逆変換で、`.md`以外のファイル、アセット
+/// ディレクトリ配下のファイル、`.`で始まるパス要素を含むファイルは
+/// `None`を返す。
+///
+pub(crate) fn page_rel_path_from_file(file_rel_path: &str) -> Option<String> {
+    let stem = file_rel_path.strip_suffix(PAGE_FILE_EXTENSION)?;
+    let (dir, name) = match stem.rsplit_once('/') {
+        Some((dir, name)) => (dir, name),
+        None => ("", stem),
+    };
+    if name.is_empty() {
+        return None;
+    }
+
+    let reserved = dir.split('/').filter(|segment| !segment.is_empty()).any(
+        |segment| segment.starts_with('.') || segment.ends_with(ASSET_DIR_SUFFIX),
+    );
+    if reserved || name.starts_with('.') {
+        return None;
+    }
+
+    Some(join_rel_path(dir, name))
+}
+
+///
+/// アセットディレクトリの相対パス
+///
+fn asset_dir_rel_path(rel_path: &str) -> String {
+    if rel_path.is_empty() {
+        format!("{}{}", INDEX_FILE_STEM, ASSET_DIR_SUFFIX)
+    } else {
+        format!("{}{}", rel_path, ASSET_DIR_SUFFIX)
+    }
+}
+
+///
+/// ファイルの書き出し(親ディレクトリも作成する)
+///
+fn write_file(path: &Path, data: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, data)
+        .with_context(|| format!("write file failed: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::tempdir;
+
+    #[test]
+    fn read_markdown_tree_maps_files_to_page_paths() {
+        let dir = tempdir().expect("create temp dir failed");
+        let root = dir.path();
+        fs::create_dir_all(root.join("docs/guide.assets")).unwrap();
+        fs::create_dir_all(root.join("notes")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join("_index.md"), "# top\n").unwrap();
+        fs::write(root.join("docs.md"), "# docs\n").unwrap();
+        fs::write(root.join("docs/guide.md"), "# guide\n").unwrap();
+        fs::write(root.join("docs/guide.assets/a.png"), b"png").unwrap();
+        fs::write(root.join("notes/_index.md"), "# notes\n").unwrap();
+        fs::write(root.join("notes/readme.txt"), "ignored").unwrap();
+        fs::write(root.join(".git/config"), "ignored").unwrap();
+
+        let tree = read_markdown_tree(root).expect("read tree failed");
+        let paths: Vec<&str> =
+            tree.pages.iter().map(|page| page.rel_path.as_str()).collect();
+        assert_eq!(paths, vec!["", "docs", "docs/guide", "notes"]);
+        assert_eq!(tree.pages[2].assets.len(), 1);
+        assert_eq!(tree.pages[2].assets[0].file_name, "a.png");
+        assert_eq!(tree.ignored, vec![root.join("notes/readme.txt")]);
+    }
+
+    #[test]
+    fn read_markdown_tree_rejects_duplicate_page_files() {
+        let dir = tempdir().expect("create temp dir failed");
+        let root = dir.path();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs.md"), "# a\n").unwrap();
+        fs::write(root.join("docs/_index.md"), "# b\n").unwrap();
+
+        let err = read_markdown_tree(root).expect_err("duplicate must fail");
+        assert!(err.to_string().contains("duplicate page file"));
+    }
+
+    #[test]
+    fn reserved_segments_are_not_exportable() {
+        assert!(check_exportable_rel_path("a/b").is_ok());
+        assert!(check_exportable_rel_path("").is_ok());
+        assert!(check_exportable_rel_path("a/_index").is_err());
+        assert!(check_exportable_rel_path("a.assets/b").is_err());
+        assert!(check_exportable_rel_path(".hidden").is_err());
+    }
+
+    #[test]
+    fn page_rel_path_from_file_inverts_page_file_rel_path() {
+        for rel_path in ["", "docs", "docs/guide"] {
+            let file = page_file_rel_path(rel_path);
+            assert_eq!(
+                page_rel_path_from_file(&file).as_deref(),
+                Some(rel_path)
+            );
+        }
+
+        assert_eq!(page_rel_path_from_file("docs/a.png"), None);
+        assert_eq!(page_rel_path_from_file("docs.assets/a.md"), None);
+        assert_eq!(page_rel_path_from_file(".github/a.md"), None);
+    }
+}
@@ -0,0 +1,980 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! MediaWiki の XML ダンプの読み込み
+//!
+//! `Special:Export`や`dumpBackup.php`で出力した XML ダンプから標準名前空間
+//! のページを読み込み、各リビジョンの wikitext を Markdown へ変換する。
+//!
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Local};
+use roxmltree::{Document, Node, ParsingOptions};
+use serde_yaml_ng::Mapping;
+
+use super::foreign_wiki::{
+    ForeignPage,
+    ForeignRevision,
+    ForeignWiki,
+    UNKNOWN_CONTRIBUTOR,
+    compose_front_matter,
+    encode_link_target,
+    markdown_link,
+    normalize_tag,
+    relative_link_target,
+};
+
+/// 取り込み対象とする名前空間(標準名前空間)
+const MAIN_NAMESPACE: i64 = 0;
+
+/// ページリンクとして扱わない組み込みの名前空間接頭辞
+const BUILTIN_NAMESPACES: &[&str] = &["category", "file", "image", "media"];
+
+/// 変換前のリビジョン (作成日時, 編集者名, wikitext)
+type RawRevision = (DateTime<Local>, String, String);
+
+///
+/// MediaWiki の XML ダンプの読み込み
+///
+/// # 引数
+/// * `path` - XML ダンプのパス
+///
+/// # 戻り値
+/// 変換済みのページ群を返す。
+///
+/// # 注記
+/// 標準名前空間以外のページ(ノート、利用者ページ、テンプレート等)は
+/// warning を出力して読み飛ばす。ページのタイトルはそのまま相対パスとし、
+/// `/`を含むサブページは階層として扱う。
+///
+pub(crate) fn read_mediawiki_dump(path: &Path) -> Result<ForeignWiki> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("read dump failed: {}", path.display()))?;
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    let document = Document::parse_with_options(&text, options)
+        .map_err(|err| anyhow!("invalid MediaWiki dump: {}", err))?;
+
+    let namespaces: HashSet<String> = document
+        .descendants()
+        .filter(|node| node.has_tag_name_local("namespace"))
+        .filter_map(|node| node.text())
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect();
+
+    /*
+     * ページとリビジョンの収集
+     */
+    let mut wiki = ForeignWiki::default();
+    let mut pages: BTreeMap<String, (String, Vec<RawRevision>)> = BTreeMap::new();
+    for page in document
+        .descendants()
+        .filter(|node| node.has_tag_name_local("page"))
+    {
+        let title = child_text(page, "title").unwrap_or_default();
+        let namespace = child_text(page, "ns")
+            .and_then(|ns| ns.trim().parse::<i64>().ok())
+            .unwrap_or(MAIN_NAMESPACE);
+        if namespace != MAIN_NAMESPACE {
+            wiki.warn(
+                "skipped_namespace",
+                format!("page outside main namespace skipped: {}", title),
+            );
+            continue;
+        }
+
+        let rel_path = title_to_rel_path(&title);
+        if rel_path.is_empty() {
+            wiki.warn("invalid_title", "page with empty title skipped".to_string());
+            continue;
+        }
+
+        let entry = pages
+            .entry(rel_path)
+            .or_insert_with(|| (canonical_title(&title), Vec::new()));
+        for revision in children(page, "revision") {
+            entry.1.push(read_revision(revision)?);
+        }
+    }
+
+    /*
+     * wikitext の変換
+     */
+    for (rel_path, (title, mut revisions)) in pages {
+        if revisions.is_empty() {
+            wiki.warn(
+                "empty_page",
+                format!("page without revisions skipped: {}", title),
+            );
+            continue;
+        }
+        revisions.sort_by_key(|(timestamp, _, _)| *timestamp);
+
+        let mut page = ForeignPage {
+            rel_path: rel_path.clone(),
+            ..Default::default()
+        };
+        let mut warnings = Vec::new();
+        for (timestamp, user, text) in revisions {
+            let mut converter = WikitextConverter::new(&rel_path, &namespaces);
+            let body = converter.convert(&text);
+            let front_matter = compose_front_matter(&converter.tags, Mapping::new())?;
+            warnings = converter.warnings;
+            page.revisions.push(ForeignRevision {
+                timestamp,
+                user,
+                source: format!("{}{}", front_matter, body),
+            });
+        }
+
+        /*
+         * 変換時の警告は最新リビジョンの分のみ出力する
+         */
+        for (code, message) in warnings {
+            wiki.warn(code, format!("{} ({})", message, title));
+        }
+        wiki.pages.push(page);
+    }
+
+    Ok(wiki)
+}
+
+///
+/// 要素名(名前空間を除く)の判定
+///
+trait LocalTagName {
+    fn has_tag_name_local(&self, name: &str) -> bool;
+}
+
+impl LocalTagName for Node<'_, '_> {
+    fn has_tag_name_local(&self, name: &str) -> bool {
+        self.is_element() && self.tag_name().name() == name
+    }
+}
+
+///
+/// 指定名の子要素の列挙
+///
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.has_tag_name_local(name))
+}
+
+///
+/// 指定名の子要素のテキストの取得
+///
+fn child_text(node: Node<'_, '_>, name: &str) -> Option<String> {
+    children(node, name)
+        .next()
+        .map(|child| child.text().unwrap_or_default().to_string())
+}
+
+///
+/// リビジョン要素の読み込み
+///
+/// # 戻り値
+/// 作成日時、編集者名、wikitext を返す。
+///
+/// # 注記
+/// 編集者が匿名の場合は IP アドレスを、秘匿されている場合は
+/// `UNKNOWN_CONTRIBUTOR`を編集者名とする。
+///
+fn read_revision(node: Node<'_, '_>) -> Result<RawRevision> {
+    let raw_timestamp = child_text(node, "timestamp")
+        .ok_or_else(|| anyhow!("revision timestamp is missing"))?;
+    let timestamp = DateTime::parse_from_rfc3339(raw_timestamp.trim())
+        .map_err(|err| anyhow!("invalid revision timestamp: {} ({})", raw_timestamp, err))?
+        .with_timezone(&Local);
+
+    let user = children(node, "contributor")
+        .next()
+        .and_then(|contributor| {
+            child_text(contributor, "username").or_else(|| child_text(contributor, "ip"))
+        })
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| UNKNOWN_CONTRIBUTOR.to_string());
+
+    let text = child_text(node, "text").unwrap_or_default();
+    Ok((timestamp, user, text))
+}
+
+///
+/// タイトルの正規化
+///
+/// # 注記
+/// MediaWiki と同様に`_`を空白として扱い、先頭の文字を大文字とする。
+///
+fn canonical_title(raw: &str) -> String {
+    let title = raw
+        .replace('_', " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut chars = title.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+///
+/// タイトルから相対パスへの変換
+///
+fn title_to_rel_path(title: &str) -> String {
+    canonical_title(title)
+        .split('/')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+///
+/// wikitext から Markdown への変換器
+///
+struct WikitextConverter<'a> {
+    /// 変換中のページの相対パス
+    rel_path: &'a str,
+
+    /// ダンプに定義された名前空間名(小文字)
+    namespaces: &'a HashSet<String>,
+
+    /// 分類(Category)から収集したタグ
+    tags: Vec<String>,
+
+    /// 変換できなかった記述の警告
+    warnings: Vec<(&'static str, String)>,
+}
+
+impl<'a> WikitextConverter<'a> {
+    ///
+    /// オブジェクトの生成
+    ///
+    fn new(rel_path: &'a str, namespaces: &'a HashSet<String>) -> Self {
+        Self {
+            rel_path,
+            namespaces,
+            tags: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    ///
+    /// wikitext の変換
+    ///
+    /// # 注記
+    /// 見出し、強調、リンク、箇条書き、表、整形済みテキストを変換する。
+    /// テンプレートの呼び出しは展開できないため、コードとして残す。
+    ///
+    fn convert(&mut self, text: &str) -> String {
+        let mut output = Vec::new();
+        let mut pre_close: Option<&'static str> = None;
+        let mut space_pre = false;
+        let mut table: Option<Vec<&str>> = None;
+        let mut template: Option<(Vec<&str>, isize)> = None;
+
+        for line in text.lines() {
+            /*
+             * 整形済みブロック・表・複数行テンプレートの継続
+             */
+            if let Some(close) = pre_close {
+                match line.find(close) {
+                    Some(end) => {
+                        if !line[..end].is_empty() {
+                            output.push(line[..end].to_string());
+                        }
+                        output.push("```".to_string());
+                        pre_close = None;
+                    }
+                    None => output.push(line.to_string()),
+                }
+                continue;
+            }
+
+            if let Some(lines) = table.as_mut() {
+                if line.trim_start().starts_with("|}") {
+                    let lines = table.take().unwrap_or_default();
+                    output.extend(self.convert_table(&lines));
+                } else {
+                    lines.push(line);
+                }
+                continue;
+            }
+
+            if let Some((lines, depth)) = template.as_mut() {
+                lines.push(line);
+                *depth += brace_depth(line);
+                if *depth <= 0 {
+                    let (lines, _) = template.take().unwrap_or_default();
+                    output.push("```mediawiki".to_string());
+                    output.extend(lines.iter().map(|line| line.to_string()));
+                    output.push("```".to_string());
+                }
+                continue;
+            }
+
+            /*
+             * 行頭空白による整形済みテキスト
+             */
+            if line.starts_with(' ') && !line.trim().is_empty() {
+                if !space_pre {
+                    output.push("```".to_string());
+                    space_pre = true;
+                }
+                output.push(line[1..].to_string());
+                continue;
+            }
+            if space_pre {
+                output.push("```".to_string());
+                space_pre = false;
+            }
+
+            let trimmed = line.trim();
+
+            /*
+             * ブロック要素の開始
+             */
+            if let Some((lang, close, rest)) = open_pre_block(trimmed) {
+                output.push(format!("```{}", lang));
+                match rest.find(close) {
+                    Some(end) => {
+                        if !rest[..end].is_empty() {
+                            output.push(rest[..end].to_string());
+                        }
+                        output.push("```".to_string());
+                    }
+                    None => {
+                        if !rest.is_empty() {
+                            output.push(rest.to_string());
+                        }
+                        pre_close = Some(close);
+                    }
+                }
+                continue;
+            }
+
+            if trimmed.starts_with("{|") {
+                table = Some(Vec::new());
+                continue;
+            }
+
+            if trimmed.starts_with("{{") && brace_depth(trimmed) > 0 {
+                self.warn_template(trimmed);
+                template = Some((vec![line], brace_depth(trimmed)));
+                continue;
+            }
+
+            output.push(self.convert_block_line(trimmed));
+        }
+
+        if pre_close.is_some() || space_pre {
+            output.push("```".to_string());
+        }
+        if let Some(lines) = table {
+            output.extend(self.convert_table(&lines));
+        }
+        if let Some((lines, _)) = template {
+            output.push("```mediawiki".to_string());
+            output.extend(lines.iter().map(|line| line.to_string()));
+            output.push("```".to_string());
+        }
+
+        let mut body = output.join("\n");
+        body.push('\n');
+        body
+    }
+
+    ///
+    /// 1行で完結するブロック要素の変換
+    ///
+    fn convert_block_line(&mut self, line: &str) -> String {
+        /*
+         * リダイレクト
+         */
+        if line.to_uppercase().starts_with("#REDIRECT") {
+            return format!("Redirect: {}", self.convert_inline(line[9..].trim()));
+        }
+
+        /*
+         * 見出し
+         */
+        let leading = line.len() - line.trim_start_matches('=').len();
+        let trailing = line.len() - line.trim_end_matches('=').len();
+        let level = leading.min(trailing).min(6);
+        if level > 0 && line.len() > level * 2 {
+            let text = line[level..line.len() - level].trim();
+            return format!("{} {}", "#".repeat(level), self.convert_inline(text));
+        }
+
+        /*
+         * 水平線
+         */
+        if line.len() >= 4 && line.chars().all(|ch| ch == '-') {
+            return "---".to_string();
+        }
+
+        /*
+         * 箇条書き・番号付きリスト・定義リスト・字下げ
+         */
+        let prefix: String = line
+            .chars()
+            .take_while(|ch| matches!(ch, '*' | '#' | ':' | ';'))
+            .collect();
+        if !prefix.is_empty() {
+            let content = line[prefix.len()..].trim();
+            let depth = prefix.chars().count();
+
+            return match prefix.chars().last() {
+                Some('*') => format!(
+                    "{}- {}",
+                    "   ".repeat(depth - 1),
+                    self.convert_inline(content)
+                ),
+                Some('#') => format!(
+                    "{}1. {}",
+                    "   ".repeat(depth - 1),
+                    self.convert_inline(content)
+                ),
+                Some(';') => match content.split_once(" : ") {
+                    Some((term, definition)) => format!(
+                        "**{}**: {}",
+                        self.convert_inline(term.trim()),
+                        self.convert_inline(definition.trim())
+                    ),
+                    None => format!("**{}**", self.convert_inline(content)),
+                },
+                _ if prefix.chars().all(|ch| ch == ':') => {
+                    format!("{}{}", "> ".repeat(depth), self.convert_inline(content))
+                }
+                _ => format!(
+                    "{}{}",
+                    "   ".repeat(depth - 1),
+                    self.convert_inline(content)
+                ),
+            };
+        }
+
+        self.convert_inline(line)
+    }
+
+    ///
+    /// 表の変換
+    ///
+    /// # 引数
+    /// * `lines` - `{|`と`|}`の間の行
+    ///
+    /// # 戻り値
+    /// Markdown の表の行を返す。
+    ///
+    /// # 注記
+    /// 先頭行を見出し行として扱う。セルの属性指定は破棄する。
+    ///
+    fn convert_table(&mut self, lines: &[&str]) -> Vec<String> {
+        let mut caption = None;
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        let mut current: Vec<String> = Vec::new();
+
+        for line in lines {
+            let trimmed = line.trim();
+            if let Some(rest) = trimmed.strip_prefix("|+") {
+                caption = Some(strip_cell_attributes(rest).to_string());
+            } else if trimmed.starts_with("|-") {
+                if !current.is_empty() {
+                    rows.push(std::mem::take(&mut current));
+                }
+            } else if let Some(rest) = trimmed.strip_prefix('!') {
+                current.extend(
+                    rest.split("!!")
+                        .flat_map(|cell| cell.split("||"))
+                        .map(|cell| strip_cell_attributes(cell).to_string()),
+                );
+            } else if let Some(rest) = trimmed.strip_prefix('|') {
+                current.extend(
+                    rest.split("||")
+                        .map(|cell| strip_cell_attributes(cell).to_string()),
+                );
+            } else if let Some(last) =
+                current.last_mut().filter(|_| !trimmed.is_empty())
+            {
+                last.push(' ');
+                last.push_str(trimmed);
+            }
+        }
+        if !current.is_empty() {
+            rows.push(current);
+        }
+
+        let mut output = Vec::new();
+        if let Some(caption) = caption {
+            output.push(format!("**{}**", self.convert_inline(&caption)));
+            output.push(String::new());
+        }
+
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        for (index, row) in rows.iter().enumerate() {
+            let cells: Vec<String> = (0..columns)
+                .map(|column| {
+                    let cell = row.get(column).map(String::as_str).unwrap_or("");
+                    self.convert_inline(cell.trim()).replace('|', "\\|")
+                })
+                .collect();
+            output.push(format!("| {} |", cells.join(" | ")));
+            if index == 0 {
+                output.push(format!("|{}", " --- |".repeat(columns)));
+            }
+        }
+        output.push(String::new());
+        output
+    }
+
+    ///
+    /// インライン要素の変換
+    ///
+    fn convert_inline(&mut self, text: &str) -> String {
+        let mut output = String::new();
+        let mut plain = String::new();
+        let mut rest = text;
+
+        while !rest.is_empty() {
+            let special = if let Some(after) = rest.strip_prefix("<nowiki>") {
+                let end = after.find("</nowiki>").unwrap_or(after.len());
+                let consumed = (rest.len() - after.len()) + (end + "</nowiki>".len()).min(after.len());
+                Some((after[..end].to_string(), consumed))
+            } else if let Some(after) = rest.strip_prefix("<code>") {
+                let end = after.find("</code>").unwrap_or(after.len());
+                let consumed = (rest.len() - after.len()) + (end + "</code>".len()).min(after.len());
+                Some((format!("`{}`", &after[..end]), consumed))
+            } else if rest.starts_with("{{") {
+                find_closing(rest, "{{", "}}").map(|end| {
+                    self.warn_template(&rest[..end]);
+                    (format!("`{}`", &rest[..end]), end)
+                })
+            } else if rest.starts_with("[[") {
+                find_closing(rest, "[[", "]]").map(|end| {
+                    let trail: String = rest[end..]
+                        .chars()
+                        .take_while(|ch| ch.is_alphabetic())
+                        .collect();
+                    let inner = &rest[2..end - 2];
+                    (self.convert_wikilink(inner, &trail), end + trail.len())
+                })
+            } else if rest.starts_with('[') && is_external_target(&rest[1..]) {
+                rest.find(']').map(|end| {
+                    let inner = &rest[1..end];
+                    let link = match inner.split_once(' ') {
+                        Some((url, text)) => {
+                            let text = convert_quotes(text.trim());
+                            markdown_link(&text, &encode_link_target(url))
+                        }
+                        None => format!("<{}>", inner),
+                    };
+                    (link, end + 1)
+                })
+            } else if let Some(word) = magic_word(rest) {
+                let replacement = if word == "__TOC__" {
+                    "{{toc}}".to_string()
+                } else {
+                    String::new()
+                };
+                Some((replacement, word.len()))
+            } else {
+                None
+            };
+
+            match special {
+                Some((replacement, consumed)) => {
+                    output.push_str(&convert_quotes(&plain));
+                    plain.clear();
+                    output.push_str(&replacement);
+                    rest = &rest[consumed..];
+                }
+                None => {
+                    let ch = rest.chars().next().unwrap_or_default();
+                    plain.push(ch);
+                    rest = &rest[ch.len_utf8()..];
+                }
+            }
+        }
+
+        output.push_str(&convert_quotes(&plain));
+        output
+    }
+
+    ///
+    /// 内部リンクの変換
+    ///
+    /// # 引数
+    /// * `inner` - `[[`と`]]`の間の文字列
+    /// * `trail` - リンク直後に続く英字(リンクテキストに含める)
+    ///
+    /// # 注記
+    /// 分類はタグとして収集し、本文からは取り除く。ファイルの埋め込みは
+    /// 実体がダンプに含まれないためコードとして残す。
+    ///
+    fn convert_wikilink(&mut self, inner: &str, trail: &str) -> String {
+        let (visible, inner) = match inner.strip_prefix(':') {
+            Some(inner) => (true, inner),
+            None => (false, inner),
+        };
+        let (target, text) = match inner.split_once('|') {
+            Some((target, text)) => (target.trim(), Some(text.trim())),
+            None => (inner.trim(), None),
+        };
+
+        /*
+         * 名前空間付きのリンク
+         */
+        if let Some((namespace, name)) = target.split_once(':') {
+            let namespace = namespace.trim().to_lowercase();
+            if !visible && namespace == "category" {
+                self.tags.extend(normalize_tag(name));
+                return String::new();
+            }
+            if !visible && matches!(namespace.as_str(), "file" | "image" | "media") {
+                self.warnings.push((
+                    "file_reference",
+                    format!("file reference not imported: {}", target),
+                ));
+                return format!("`[[{}]]`{}", inner, trail);
+            }
+            if BUILTIN_NAMESPACES.contains(&namespace.as_str())
+                || self.namespaces.contains(&namespace)
+            {
+                let text = text.filter(|text| !text.is_empty()).unwrap_or(target);
+                return format!("{}{}", convert_quotes(text), trail);
+            }
+        }
+
+        /*
+         * ページへのリンク
+         */
+        let (page, fragment) = match target.split_once('#') {
+            Some((page, fragment)) => (page.trim(), Some(fragment.trim())),
+            None => (target, None),
+        };
+        let display = match text {
+            Some(text) if !text.is_empty() => text.to_string(),
+            _ => page.trim_matches('/').to_string(),
+        };
+
+        let mut link_target = if page.is_empty() {
+            String::new()
+        } else {
+            let absolute = self.resolve_title(page);
+            encode_link_target(&relative_link_target(self.rel_path, &absolute))
+        };
+        if let Some(fragment) = fragment.filter(|fragment| !fragment.is_empty()) {
+            link_target.push('#');
+            link_target.push_str(&encode_link_target(fragment));
+        }
+        if link_target.is_empty() {
+            return format!("{}{}", convert_quotes(&display), trail);
+        }
+
+        let display = if display.is_empty() {
+            fragment.unwrap_or_default().to_string()
+        } else {
+            display
+        };
+        markdown_link(&convert_quotes(&format!("{}{}", display, trail)), &link_target)
+    }
+
+    ///
+    /// リンク先タイトルの相対パスへの解決
+    ///
+    /// # 注記
+    /// `/`で始まるリンクはサブページ、`../`で始まるリンクは親ページからの
+    /// 相対指定として扱う。
+    ///
+    fn resolve_title(&self, page: &str) -> String {
+        if page.starts_with('/') || page.starts_with("../") {
+            let mut segments: Vec<&str> = self.rel_path.split('/').collect();
+            let mut rest = page;
+            while let Some(after) = rest.strip_prefix("../") {
+                segments.pop();
+                rest = after;
+            }
+            let mut path = segments.join("/");
+            for segment in rest.split('/').filter(|segment| !segment.trim().is_empty()) {
+                if !path.is_empty() {
+                    path.push('/');
+                }
+                path.push_str(segment.trim());
+            }
+            return path;
+        }
+
+        title_to_rel_path(page)
+    }
+
+    ///
+    /// テンプレート呼び出しの警告
+    ///
+    fn warn_template(&mut self, text: &str) {
+        let name = text
+            .trim_start_matches('{')
+            .split(['|', '}', '\n'])
+            .next()
+            .unwrap_or_default()
+            .trim();
+        self.warnings.push((
+            "template_not_converted",
+            format!("template not converted: {}", name),
+        ));
+    }
+}
+
+///
+/// 整形済みブロックの開始タグの判定
+///
+/// # 戻り値
+/// 言語名、終了タグ、開始タグ以降の文字列を返す。
+///
+fn open_pre_block(line: &str) -> Option<(String, &'static str, &str)> {
+    for (tag, close) in [
+        ("<pre", "</pre>"),
+        ("<syntaxhighlight", "</syntaxhighlight>"),
+        ("<source", "</source>"),
+    ] {
+        let Some(after) = line.strip_prefix(tag) else {
+            continue;
+        };
+        if !after.starts_with('>') && !after.starts_with(' ') {
+            continue;
+        }
+        let end = after.find('>')?;
+        let attributes = &after[..end];
+        let lang = attributes
+            .split_whitespace()
+            .find_map(|attribute| attribute.strip_prefix("lang="))
+            .map(|lang| lang.trim_matches(['"', '\'']).to_string())
+            .unwrap_or_default();
+        return Some((lang, close, &after[end + 1..]));
+    }
+
+    None
+}
+
+///
+/// 行内の`{{`と`}}`の差の算出
+///
+fn brace_depth(line: &str) -> isize {
+    line.matches("{{").count() as isize - line.matches("}}").count() as isize
+}
+
+///
+/// 入れ子を考慮した閉じ記号の探索
+///
+/// # 戻り値
+/// 閉じ記号の直後の位置を返す。
+///
+fn find_closing(text: &str, open: &str, close: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut index = 0;
+    while index < text.len() {
+        let rest = &text[index..];
+        if rest.starts_with(open) {
+            depth += 1;
+            index += open.len();
+        } else if rest.starts_with(close) {
+            depth = depth.checked_sub(1)?;
+            index += close.len();
+            if depth == 0 {
+                return Some(index);
+            }
+        } else {
+            index += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    None
+}
+
+///
+/// 外部リンクの URL か否かの判定
+///
+fn is_external_target(text: &str) -> bool {
+    ["http://", "https://", "ftp://", "mailto:"]
+        .iter()
+        .any(|scheme| text.starts_with(scheme))
+}
+
+///
+/// マジックワードの判定
+///
+fn magic_word(text: &str) -> Option<&str> {
+    let rest = text.strip_prefix("__")?;
+    let end = rest.find("__")?;
+    let word = &rest[..end];
+    if !word.is_empty() && word.chars().all(|ch| ch.is_ascii_uppercase()) {
+        Some(&text[..end + 4])
+    } else {
+        None
+    }
+}
+
+///
+/// セル属性指定の除去
+///
+fn strip_cell_attributes(cell: &str) -> &str {
+    let mut depth = 0usize;
+    for (index, ch) in cell.char_indices() {
+        match ch {
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            '|' if depth == 0 => {
+                let attributes = &cell[..index];
+                if attributes.contains('=') {
+                    return cell[index + 1..].trim();
+                }
+                return cell.trim();
+            }
+            _ => {}
+        }
+    }
+    cell.trim()
+}
+
+///
+/// 太字・斜体の変換
+///
+fn convert_quotes(text: &str) -> String {
+    text.replace("'''''", "***")
+        .replace("'''", "**")
+        .replace("''", "*")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    ///
+    /// XML ダンプのリビジョン履歴と wikitext が変換されることを確認する。
+    ///
+    /// # 注記
+    /// 1) 2ページ(一方は2リビジョン)と利用者ページを含むダンプを作成する
+    /// 2) ダンプを読み込み、ページ・リビジョン・変換結果を確認する
+    ///
+    #[test]
+    fn read_mediawiki_dump_converts_revisions_and_wikitext() {
+        let dir = tempfile::tempdir().expect("create temp dir failed");
+        let path = dir.path().join("dump.xml");
+        fs::write(
+            &path,
+            r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.11/">
+  <siteinfo>
+    <namespaces>
+      <namespace key="0" />
+      <namespace key="2">User</namespace>
+    </namespaces>
+  </siteinfo>
+  <page>
+    <title>Main Page</title>
+    <ns>0</ns>
+    <revision>
+      <timestamp>2020-01-02T00:00:00Z</timestamp>
+      <contributor><username>Bob</username></contributor>
+      <text>== Intro ==
+'''Bold''' and ''italic'' see [[Guide/Setup|setup]] and [[User:Bob]].
+* one
+** two
+# first
+[https://example.com Example]
+{{Infobox}}
+[[Category:Docs]]
+&lt;pre&gt;code [[x]]&lt;/pre&gt;
+{|
+! A !! B
+|-
+| 1 || 2
+|}</text>
+    </revision>
+    <revision>
+      <timestamp>2020-01-01T00:00:00Z</timestamp>
+      <contributor><ip>192.0.2.1</ip></contributor>
+      <text>first</text>
+    </revision>
+  </page>
+  <page>
+    <title>Guide/Setup</title>
+    <ns>0</ns>
+    <revision>
+      <timestamp>2020-01-03T00:00:00Z</timestamp>
+      <contributor><username>Alice</username></contributor>
+      <text>Back to [[main_Page]] or [[/Detail]].</text>
+    </revision>
+  </page>
+  <page>
+    <title>User:Bob</title>
+    <ns>2</ns>
+    <revision>
+      <timestamp>2020-01-03T00:00:00Z</timestamp>
+      <contributor><username>Bob</username></contributor>
+      <text>profile</text>
+    </revision>
+  </page>
+</mediawiki>
+"#,
+        )
+        .unwrap();
+
+        let wiki = read_mediawiki_dump(&path).expect("read dump failed");
+        assert_eq!(wiki.pages.len(), 2);
+        assert_eq!(
+            wiki.contributors().into_iter().collect::<Vec<_>>(),
+            vec!["192.0.2.1", "Alice", "Bob"]
+        );
+
+        let guide = &wiki.pages[0];
+        assert_eq!(guide.rel_path, "Guide/Setup");
+        assert_eq!(
+            guide.revisions[0].source,
+            "Back to [main_Page](../../Main%20Page) or [Detail](Detail).\n"
+        );
+
+        let main = &wiki.pages[1];
+        assert_eq!(main.rel_path, "Main Page");
+        assert_eq!(main.revisions.len(), 2);
+        assert_eq!(main.revisions[0].user, "192.0.2.1");
+        assert_eq!(main.revisions[0].source, "first\n");
+        let source = &main.revisions[1].source;
+        assert!(source.starts_with("---\nwiki:\n  tags:\n  - Docs\n---\n"));
+        assert!(source.contains("## Intro\n"));
+        assert!(source.contains(
+            "**Bold** and *italic* see [setup](../Guide/Setup) and User:Bob."
+        ));
+        assert!(source.contains("- one\n   - two\n1. first\n"));
+        assert!(source.contains("[Example](https://example.com)"));
+        assert!(source.contains("`{{Infobox}}`"));
+        assert!(source.contains("```\ncode [[x]]\n```"));
+        assert!(source.contains("| A | B |\n| --- | --- |\n| 1 | 2 |"));
+
+        assert!(
+            wiki.warnings
+                .iter()
+                .any(|warning| warning.code == "skipped_namespace")
+        );
+        assert!(
+            wiki.warnings
+                .iter()
+                .any(|warning| warning.code == "template_not_converted")
+        );
+    }
+}
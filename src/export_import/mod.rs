@@ -22,19 +22,33 @@ pub(crate) mod archive_write;
 pub(crate) mod validate;
 pub(crate) mod link_plan;
 pub(crate) mod import_apply;
+pub(crate) mod markdown_tree;
+pub(crate) mod foreign_wiki;
+pub(crate) mod obsidian;
+pub(crate) mod mediawiki;
+pub(crate) mod dokuwiki;
+pub(crate) mod static_render;
+pub(crate) mod static_site;
 
-use anyhow::{Result, bail};
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Local};
 
 use crate::database::DatabaseManager;
-use crate::database::types::PageId;
+use crate::database::types::{Id, PageId};
 
 pub(crate) use export_collect::*;
 pub(crate) use archive_read::*;
 pub(crate) use archive_write::*;
+pub(crate) use dokuwiki::*;
+pub(crate) use foreign_wiki::*;
 pub(crate) use import_apply::*;
 pub(crate) use link_plan::*;
+pub(crate) use markdown_tree::*;
+pub(crate) use mediawiki::*;
 pub(crate) use model::*;
+pub(crate) use obsidian::*;
 pub(crate) use policy::*;
+pub(crate) use static_site::*;
 pub(crate) use validate::*;
 
 ///
@@ -46,6 +60,7 @@ pub(crate) struct ExportRequest {
     pub(crate) dry_run: bool,
     pub(crate) output_path: String,
     pub(crate) password: Option<String>,
+    pub(crate) since: Option<String>,
 }
 
 ///
@@ -81,6 +96,15 @@ pub(crate) struct ImportResult {
     pub(crate) dry_run: bool,
 }
 
+///
+/// オンラインバックアップの結果
+///
+#[derive(Debug)]
+pub(crate) struct OnlineBackup {
+    pub(crate) manifest: ExportManifest,
+    pub(crate) archive: TemporaryArchive,
+}
+
 ///
 /// migrate export 削除直前の再確認用ページ情報
 ///
@@ -109,6 +133,12 @@ pub(crate) fn export(
         bail!("output path is required");
     }
 
+    if request.since.is_some()
+        && request.policy.export_type() != ExportType::Backup
+    {
+        bail!("incremental export is only supported for backup export");
+    }
+
     match request.policy.export_type() {
         ExportType::Backup => export_backup(_db, request),
         ExportType::Migrate => export_migrate(_db, request),
@@ -119,13 +149,22 @@ fn export_backup(
     db: &DatabaseManager,
     request: ExportRequest,
 ) -> Result<ExportResult> {
-    let collected = export_collect::collect_export(db, &request.policy)?;
+    let since = match request.since.as_deref() {
+        Some(value) => Some(resolve_export_since(db, value)?),
+        None => None,
+    };
+    let collected = export_collect::collect_export_since(
+        db,
+        &request.policy,
+        since.as_ref(),
+    )?;
     if !request.dry_run {
         archive_write::write_bundle_to_output(
             &collected.bundle,
             &request.output_path,
             request.password.as_deref(),
         )?;
+        record_backup_export(db, &collected.bundle.manifest)?;
     }
 
     Ok(ExportResult {
@@ -135,6 +174,68 @@ fn export_backup(
     })
 }
 
+///
+/// 稼働中サーバ向けの backup export
+///
+/// # 引数
+/// * `db` - DB マネージャ
+/// * `since` - 差分 export の起点(完全 backup の場合は`None`)
+/// * `password` - ZIP パスワード
+///
+/// # 戻り値
+/// 一時ファイルへ書き出したアーカイブとその manifest を返す。
+///
+/// # 注記
+/// 収集は単一の読み取りトランザクションで行うため、サーバを停止せずに
+/// 一貫したスナップショットを得られる。
+///
+pub(crate) fn backup_to_temporary_archive(
+    db: &DatabaseManager,
+    since: Option<&ExportSince>,
+    password: Option<&str>,
+) -> Result<OnlineBackup> {
+    let collected = export_collect::collect_export_since(
+        db,
+        &ExportImportPolicy::backup(),
+        since,
+    )?;
+    let (archive, _) = archive_write::write_bundle_to_temporary_archive(
+        &collected.bundle,
+        password,
+    )?;
+    record_backup_export(db, &collected.bundle.manifest)?;
+
+    Ok(OnlineBackup {
+        manifest: collected.bundle.manifest,
+        archive,
+    })
+}
+
+///
+/// 後続の差分 export の起点として backup 出力を記録する
+///
+/// # 引数
+/// * `db` - DB マネージャ
+/// * `manifest` - 出力したアーカイブの manifest
+///
+/// # 戻り値
+/// 記録に成功した場合は `Ok(())` を返す。
+///
+fn record_backup_export(
+    db: &DatabaseManager,
+    manifest: &ExportManifest,
+) -> Result<()> {
+    if let Some(export_id) = manifest.export_id.as_ref() {
+        db.record_export_history(
+            export_id,
+            manifest.timestamp,
+            manifest.base_export.clone(),
+        )?;
+    }
+
+    Ok(())
+}
+
 fn export_migrate(
     db: &DatabaseManager,
     request: ExportRequest,
@@ -193,6 +294,44 @@ fn export_migrate(
     }
 }
 
+///
+/// 差分 export の起点指定を解決する
+///
+/// # 引数
+/// * `db` - DB マネージャ
+/// * `value` - RFC 3339 形式の日時、またはエクスポートID
+///
+/// # 戻り値
+/// 解決した起点を返す。
+///
+/// # 注記
+/// エクスポートIDを指定した場合は、そのアーカイブのスナップショット日時を
+/// 起点とする。エクスポートIDは本DBで出力または取り込みを記録したもの
+/// のみ解決できる。
+///
+pub(crate) fn resolve_export_since(
+    db: &DatabaseManager,
+    value: &str,
+) -> Result<ExportSince> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(ExportSince {
+            timestamp: timestamp.with_timezone(&Local),
+            base_export: None,
+        });
+    }
+
+    let export_id = Id::from_string(value)
+        .map_err(|_| anyhow!("invalid since value: {}", value))?;
+    let history = db
+        .get_export_history(&export_id)?
+        .ok_or_else(|| anyhow!("export not found: {}", export_id))?;
+
+    Ok(ExportSince {
+        timestamp: history.timestamp(),
+        base_export: Some(export_id),
+    })
+}
+
 fn build_migrate_export_page_snapshots(
     bundle: &ExportBundle,
 ) -> Vec<MigrateExportPageSnapshot> {
@@ -400,6 +539,7 @@ mod tests {
                 dry_run: false,
                 output_path: output_path.to_string_lossy().to_string(),
                 password: None,
+                since: None,
             },
         )
         .expect("migrate export failed");
@@ -489,6 +629,184 @@ mod tests {
         fs::remove_dir_all(base_dir).expect("cleanup failed");
     }
 
+    #[test]
+    fn incremental_backup_chain_restores_changes() {
+        let (base_dir, db_path, asset_path) = prepare_test_dirs();
+        let manager = DatabaseManager::open(&db_path, &asset_path)
+            .expect("open manager failed");
+        manager
+            .add_user("alice", "pass", None)
+            .expect("add user failed");
+
+        let page_a = manager
+            .create_page("/a", "alice", "# a".to_string())
+            .expect("create page a failed");
+        let page_b = manager
+            .create_page("/b", "alice", "# b".to_string())
+            .expect("create page b failed");
+        manager
+            .create_page("/c", "alice", "# c".to_string())
+            .expect("create page c failed");
+        let asset_id = manager
+            .create_asset(&page_a, "x.txt", "text/plain", "alice", b"old")
+            .expect("create asset failed");
+
+        /*
+         * 完全アーカイブの出力
+         */
+        let full_path = base_dir.join("full.zip");
+        let full = export(
+            &manager,
+            ExportRequest {
+                policy: ExportImportPolicy::backup(),
+                dry_run: false,
+                output_path: full_path.to_string_lossy().to_string(),
+                password: None,
+                since: None,
+            },
+        )
+        .expect("full export failed");
+        let full_export_id = full
+            .bundle
+            .manifest
+            .export_id
+            .clone()
+            .expect("export id missing");
+
+        /*
+         * 追加・更新・改名・削除を行ってから差分アーカイブを出力
+         */
+        manager
+            .put_page(&page_a, "alice", "# a2".to_string(), false)
+            .expect("update page failed");
+        let page_d = manager
+            .create_page("/d", "alice", "# d".to_string())
+            .expect("create page d failed");
+        manager.rename_page("/b", "/b2").expect("rename page failed");
+        let page_c = manager
+            .get_page_id_by_path("/c")
+            .expect("resolve page c failed")
+            .expect("page c missing");
+        manager.delete_page_by_id(&page_c).expect("delete page failed");
+        manager
+            .replace_asset_data(&asset_id, "text/plain", "alice", b"new")
+            .expect("replace asset failed");
+
+        let incremental_path = base_dir.join("incremental.zip");
+        let incremental = export(
+            &manager,
+            ExportRequest {
+                policy: ExportImportPolicy::backup(),
+                dry_run: false,
+                output_path: incremental_path.to_string_lossy().to_string(),
+                password: None,
+                since: Some(full_export_id.to_string()),
+            },
+        )
+        .expect("incremental export failed");
+
+        assert!(incremental.bundle.manifest.is_incremental());
+        assert_eq!(
+            incremental.bundle.manifest.base_export,
+            Some(full_export_id)
+        );
+        let mut changed_pages: Vec<PageId> = incremental
+            .bundle
+            .pages
+            .iter()
+            .map(|page| page.id.clone())
+            .collect();
+        let mut expected_pages =
+            vec![page_a.clone(), page_b.clone(), page_d.clone()];
+        changed_pages.sort();
+        expected_pages.sort();
+        assert_eq!(changed_pages, expected_pages);
+        assert_eq!(incremental.bundle.live.len(), 3);
+
+        /*
+         * 差分アーカイブは完全アーカイブの取り込み前には適用できない
+         */
+        let restored_db_path = base_dir.join("restored.redb");
+        let restored_asset_path = base_dir.join("restored_assets");
+        fs::create_dir_all(&restored_asset_path)
+            .expect("create asset dir failed");
+        let restored =
+            DatabaseManager::open(&restored_db_path, &restored_asset_path)
+                .expect("open restored manager failed");
+        let import_request = |path: &Path, policy: ExportImportPolicy| {
+            ImportRequest {
+                policy,
+                dry_run: false,
+                input_path: path.to_string_lossy().to_string(),
+                password: None,
+                user_map: Vec::new(),
+                strict_mode: false,
+                fix_broken_link: false,
+            }
+        };
+
+        let err = import(
+            &restored,
+            import_request(&incremental_path, ExportImportPolicy::incremental()),
+        )
+        .expect_err("incremental import should fail");
+        assert!(err.to_string().contains("requires restored database"));
+
+        import(
+            &restored,
+            import_request(&full_path, ExportImportPolicy::backup()),
+        )
+        .expect("full import failed");
+        import(
+            &restored,
+            import_request(&incremental_path, ExportImportPolicy::incremental()),
+        )
+        .expect("incremental import failed");
+
+        /*
+         * 差分の反映結果を確認
+         */
+        let index = restored
+            .get_page_index_by_id(&page_a)
+            .expect("get page index failed")
+            .expect("page a missing");
+        let source = restored
+            .get_page_source(&page_a, index.latest())
+            .expect("get page source failed")
+            .expect("page source missing");
+        assert_eq!(source.source(), "# a2");
+        assert_eq!(
+            restored.get_page_id_by_path("/d").expect("resolve page d failed"),
+            Some(page_d)
+        );
+        assert_eq!(
+            restored
+                .get_page_id_by_path("/b2")
+                .expect("resolve page b2 failed"),
+            Some(page_b)
+        );
+        assert!(
+            restored
+                .get_page_id_by_path("/b")
+                .expect("resolve page b failed")
+                .is_none()
+        );
+        assert!(
+            restored
+                .get_page_id_by_path("/c")
+                .expect("resolve page c failed")
+                .is_none()
+        );
+        assert_eq!(
+            restored
+                .read_asset_data(&asset_id)
+                .expect("read asset failed"),
+            b"new"
+        );
+
+        fs::remove_dir_all(base_dir).expect("cleanup failed");
+    }
+
     #[test]
     fn prepared_output_guard_rollback_restores_existing_output() {
         let base_dir = Path::new("tests").join("tmp").join(unique_suffix());
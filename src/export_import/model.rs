@@ -15,6 +15,7 @@ use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
 
 use crate::database::types::{
+    AssetHash,
     AssetId,
     Id,
     PageId,
@@ -61,6 +62,12 @@ pub(crate) struct ExportManifest {
     pub(crate) page_count: u64,
     pub(crate) revision_count: u64,
     pub(crate) asset_count: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) export_id: Option<Id>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) base_export: Option<Id>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) since: Option<DateTime<Local>>,
 }
 
 impl ExportManifest {
@@ -86,8 +93,33 @@ impl ExportManifest {
             page_count: 0,
             revision_count: 0,
             asset_count: 0,
+            export_id: Some(Id::new()),
+            base_export: None,
+            since: None,
         }
     }
+
+    ///
+    /// 差分アーカイブか否かの判定
+    ///
+    /// # 戻り値
+    /// 起点日時以降の変更のみを格納した差分アーカイブの場合は true を返す。
+    ///
+    pub(crate) fn is_incremental(&self) -> bool {
+        self.since.is_some()
+    }
+}
+
+///
+/// 差分 export の起点
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ExportSince {
+    /// この日時より後の変更を収集する
+    pub(crate) timestamp: DateTime<Local>,
+
+    /// 起点をエクスポートIDで指定した場合のエクスポートID
+    pub(crate) base_export: Option<Id>,
 }
 
 ///
@@ -181,16 +213,63 @@ pub(crate) struct ExportAsset {
     pub(crate) file_name: String,
     pub(crate) mime: String,
     pub(crate) size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) sha256: Option<AssetHash>,
+    #[serde(default)]
+    pub(crate) revision: u64,
+    pub(crate) user: UserId,
+    pub(crate) timestamp: DateTime<Local>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) history: Vec<ExportAssetRevision>,
+}
+
+///
+/// assets.jsonl 内の過去リビジョンモデル
+///
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct ExportAssetRevision {
+    pub(crate) revision: u64,
+    pub(crate) mime: String,
+    pub(crate) size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) sha256: Option<AssetHash>,
     pub(crate) user: UserId,
     pub(crate) timestamp: DateTime<Local>,
 }
 
+///
+/// live.jsonl の 1 行モデル
+///
+/// 差分アーカイブ作成時点で存在するページと、その所属アセットの配置を表す。
+/// 差分 import はここに含まれないページ・アセットを削除済みとして扱い、
+/// リビジョンを伴わない復帰や移動もここから反映する。
+///
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct ExportLivePage {
+    pub(crate) id: PageId,
+    pub(crate) path: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) assets: Vec<ExportLiveAsset>,
+}
+
+///
+/// live.jsonl 内のアセット配置モデル
+///
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct ExportLiveAsset {
+    pub(crate) id: AssetId,
+    pub(crate) file_name: String,
+}
+
 ///
 /// ZIP 書込専用のアセット実体モデル
 ///
+/// `revision` が `None` の場合は現在のリビジョンの実体を表す。
+///
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub(crate) struct ExportAssetBlob {
     pub(crate) asset_id: AssetId,
+    pub(crate) revision: Option<u64>,
     pub(crate) data: Vec<u8>,
 }
 
@@ -205,6 +284,7 @@ pub(crate) struct ExportBundle {
     pub(crate) revisions: Vec<ExportRevision>,
     pub(crate) assets: Vec<ExportAsset>,
     pub(crate) asset_blobs: Vec<ExportAssetBlob>,
+    pub(crate) live: Vec<ExportLivePage>,
     pub(crate) manifest_context: ManifestContext,
 }
 
@@ -231,6 +311,7 @@ impl ExportBundle {
             revisions: Vec::new(),
             assets: Vec::new(),
             asset_blobs: Vec::new(),
+            live: Vec::new(),
             manifest_context,
         }
     }
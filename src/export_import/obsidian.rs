@@ -0,0 +1,667 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! Obsidian 保管庫(vault)の取り込み
+//!
+//! 保管庫内の`.md`ファイルをページとして Markdown ツリーへ変換し、
+//! Markdown ツリー形式の取り込みと同じ検証・反映処理で取り込む。
+//! `[[wikilink]]`はページ間の相対リンクへ、`![[embed]]`は所属アセットへの
+//! 参照へ書き換え、front matter の`tags`は`wiki.tags`へ移す。
+//!
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use mime_guess::MimeGuess;
+use serde_yaml_ng::{Mapping, Value};
+
+use super::foreign_wiki::{
+    asset_link_target,
+    compose_front_matter,
+    encode_link_target,
+    markdown_link,
+    normalize_tag,
+    relative_link_target,
+};
+use super::markdown_tree::{
+    MarkdownImportRequest,
+    MarkdownImportResult,
+    MarkdownTree,
+    MarkdownTreeAsset,
+    MarkdownTreePage,
+    import_loaded_markdown_tree,
+};
+use super::validate::ValidationWarning;
+use crate::database::DatabaseManager;
+use crate::markdown_source::front_matter::extract_front_matter;
+
+/// ノートファイルの拡張子
+const NOTE_FILE_EXTENSION: &str = ".md";
+
+///
+/// 読み込んだ保管庫
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ObsidianVault {
+    /// 変換済みの Markdown ツリー
+    pub(crate) tree: MarkdownTree,
+
+    /// 変換時の warning
+    pub(crate) warnings: Vec<ValidationWarning>,
+}
+
+///
+/// Obsidian 保管庫の取り込み
+///
+/// # 引数
+/// * `db` - データベースマネージャ
+/// * `request` - import 要求(`input_dir`は保管庫のディレクトリ)
+///
+/// # 戻り値
+/// import 結果を返す。
+///
+pub(crate) fn import_obsidian_vault(
+    db: &DatabaseManager,
+    request: MarkdownImportRequest,
+) -> Result<MarkdownImportResult> {
+    let vault = read_obsidian_vault(&request.input_dir)?;
+    if vault.tree.pages.is_empty() {
+        bail!("no notes found: {}", request.input_dir.display());
+    }
+
+    let mut result = import_loaded_markdown_tree(db, &request, vault.tree)?;
+    result.warnings.splice(0..0, vault.warnings);
+    Ok(result)
+}
+
+///
+/// Obsidian 保管庫の読み込みと変換
+///
+/// # 引数
+/// * `vault_dir` - 保管庫のディレクトリ
+///
+/// # 戻り値
+/// 変換済みの保管庫を返す。
+///
+/// # 注記
+/// `.`で始まるファイルとディレクトリ(`.obsidian`、`.trash`等)は読み飛ばす。
+/// 添付ファイルは最初に埋め込んだノート(相対パス順)の所属アセットとし、
+/// どのノートからも参照されない添付ファイルは無視する。
+///
+pub(crate) fn read_obsidian_vault(vault_dir: &Path) -> Result<ObsidianVault> {
+    if !vault_dir.is_dir() {
+        bail!("input path is not a directory: {}", vault_dir.display());
+    }
+
+    let mut index = VaultIndex::default();
+    scan_vault_dir(vault_dir, "", &mut index)?;
+
+    /*
+     * ノート単位の変換
+     */
+    let mut warnings = Vec::new();
+    let mut owners: BTreeMap<String, String> = BTreeMap::new();
+    let mut pages = Vec::new();
+    for (rel_path, path) in &index.notes {
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("read note failed: {}", path.display()))?;
+        let mut converter = NoteConverter {
+            index: &index,
+            rel_path,
+            owners: &mut owners,
+            warnings: &mut warnings,
+        };
+        let source = converter.convert(&raw)?;
+        pages.push(MarkdownTreePage {
+            rel_path: rel_path.clone(),
+            source: Some(source),
+            assets: Vec::new(),
+        });
+    }
+
+    /*
+     * 添付ファイルの所属ノートへの割り当て
+     */
+    let mut ignored = Vec::new();
+    for (rel_file, path) in &index.attachments {
+        let Some(owner) = owners.get(rel_file) else {
+            ignored.push(path.clone());
+            continue;
+        };
+
+        let page = pages
+            .iter_mut()
+            .find(|page| &page.rel_path == owner)
+            .ok_or_else(|| anyhow!("attachment owner missing: {}", owner))?;
+        let file_name = file_name_of(rel_file).to_string();
+        if page.assets.iter().any(|asset| asset.file_name == file_name) {
+            warnings.push(ValidationWarning {
+                code: "duplicate_attachment_name",
+                message: format!(
+                    "attachment with the same name already attached: {} ({})",
+                    rel_file, owner
+                ),
+            });
+            continue;
+        }
+        page.assets.push(MarkdownTreeAsset {
+            file_name,
+            file_path: path.clone(),
+        });
+    }
+
+    Ok(ObsidianVault {
+        tree: MarkdownTree { pages, ignored },
+        warnings,
+    })
+}
+
+///
+/// 保管庫内のファイル一覧
+///
+#[derive(Clone, Debug, Default)]
+struct VaultIndex {
+    /// ノート(拡張子を除いた相対パス => ファイルパス)
+    notes: BTreeMap<String, PathBuf>,
+
+    /// 添付ファイル(相対パス => ファイルパス)
+    attachments: BTreeMap<String, PathBuf>,
+}
+
+impl VaultIndex {
+    ///
+    /// ノート名の解決
+    ///
+    /// # 引数
+    /// * `target` - リンクに記述されたノート名
+    ///
+    /// # 戻り値
+    /// 解決できた場合はノートの相対パスを返す。
+    ///
+    fn resolve_note(&self, target: &str) -> Option<&String> {
+        let target = target.strip_suffix(NOTE_FILE_EXTENSION).unwrap_or(target);
+        resolve_name(self.notes.keys(), target)
+    }
+
+    ///
+    /// 添付ファイル名の解決
+    ///
+    /// # 引数
+    /// * `target` - 埋め込みに記述されたファイル名
+    ///
+    /// # 戻り値
+    /// 解決できた場合は添付ファイルの相対パスを返す。
+    ///
+    fn resolve_attachment(&self, target: &str) -> Option<&String> {
+        resolve_name(self.attachments.keys(), target)
+    }
+}
+
+///
+/// 名前による解決
+///
+/// # 注記
+/// Obsidian と同様に大文字小文字を区別せず、相対パス全体または末尾の
+/// 一致で解決する。複数が一致する場合は階層の浅いものを優先する。
+///
+fn resolve_name<'a, I>(candidates: I, target: &str) -> Option<&'a String>
+where
+    I: Iterator<Item = &'a String>,
+{
+    let target = target.trim().trim_start_matches('/').to_lowercase();
+    if target.is_empty() {
+        return None;
+    }
+    let suffix = format!("/{}", target);
+
+    candidates
+        .filter(|candidate| {
+            let candidate = candidate.to_lowercase();
+            candidate == target || candidate.ends_with(&suffix)
+        })
+        .min_by_key(|candidate| (candidate.matches('/').count(), *candidate))
+}
+
+///
+/// 保管庫ディレクトリ1階層分の走査
+///
+fn scan_vault_dir(dir: &Path, rel_dir: &str, index: &mut VaultIndex) -> Result<()> {
+    let mut entries = fs::read_dir(dir)
+        .with_context(|| format!("read dir failed: {}", dir.display()))?
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let name = entry
+            .file_name()
+            .into_string()
+            .map_err(|_| anyhow!("invalid file name: {}", path.display()))?;
+        if name.starts_with('.') {
+            continue;
+        }
+
+        let rel_path = if rel_dir.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", rel_dir, name)
+        };
+
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            scan_vault_dir(&path, &rel_path, index)?;
+        } else if !file_type.is_file() {
+            continue;
+        } else if let Some(stem) = rel_path.strip_suffix(NOTE_FILE_EXTENSION) {
+            index.notes.insert(stem.to_string(), path);
+        } else {
+            index.attachments.insert(rel_path, path);
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// 相対パスからファイル名部分の取り出し
+///
+fn file_name_of(rel_path: &str) -> &str {
+    rel_path.rsplit('/').next().unwrap_or(rel_path)
+}
+
+///
+/// ノート1件分の変換器
+///
+struct NoteConverter<'a> {
+    /// 保管庫内のファイル一覧
+    index: &'a VaultIndex,
+
+    /// 変換中のノートの相対パス
+    rel_path: &'a str,
+
+    /// 添付ファイルの所属ノート(添付ファイルの相対パス => ノートの相対パス)
+    owners: &'a mut BTreeMap<String, String>,
+
+    /// warning の追加先
+    warnings: &'a mut Vec<ValidationWarning>,
+}
+
+impl NoteConverter<'_> {
+    ///
+    /// ノートソースの変換
+    ///
+    /// # 引数
+    /// * `raw` - ノートのソース
+    ///
+    /// # 戻り値
+    /// 変換後のページソースを返す。
+    ///
+    fn convert(&mut self, raw: &str) -> Result<String> {
+        let (front_matter, body) = self.convert_front_matter(raw)?;
+
+        /*
+         * コードブロック外の行のみ書き換える
+         */
+        let mut output = front_matter;
+        let mut fence: Option<String> = None;
+        for line in body.split_inclusive('\n') {
+            let trimmed = line.trim_start();
+            match fence.as_deref() {
+                Some(marker) => {
+                    if trimmed.trim_end().starts_with(marker) {
+                        fence = None;
+                    }
+                    output.push_str(line);
+                }
+                None => {
+                    if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                        fence = Some(trimmed[..3].to_string());
+                        output.push_str(line);
+                    } else {
+                        output.push_str(&self.convert_line(line));
+                    }
+                }
+            }
+        }
+
+        Ok(output)
+    }
+
+    ///
+    /// front matter の変換
+    ///
+    /// # 戻り値
+    /// 変換後の front matter と本文を返す。
+    ///
+    /// # 注記
+    /// `tags`(および`tag`)は`wiki.tags`へ、それ以外のプロパティは
+    /// `custom_meta`へ移す。YAML として解釈できない場合は warning を出力
+    /// してプロパティを破棄する。
+    ///
+    fn convert_front_matter<'s>(
+        &mut self,
+        raw: &'s str,
+    ) -> Result<(String, &'s str)> {
+        let extracted = match extract_front_matter(raw) {
+            Ok(Some(extracted)) => extracted,
+            Ok(None) | Err(_) => return Ok((String::new(), raw)),
+        };
+
+        let mapping = match serde_yaml_ng::from_str::<Value>(extracted.front_matter()) {
+            Ok(Value::Mapping(mapping)) => mapping,
+            Ok(Value::Null) => Mapping::new(),
+            _ => {
+                self.warn(
+                    "invalid_properties",
+                    format!("note properties discarded: {}", self.rel_path),
+                );
+                return Ok((String::new(), extracted.body()));
+            }
+        };
+
+        let mut tags = Vec::new();
+        let mut custom_meta = Mapping::new();
+        for (key, value) in mapping {
+            let Value::String(key) = key else {
+                continue;
+            };
+            if key == "tags" || key == "tag" {
+                collect_tags(&value, &mut tags);
+            } else {
+                custom_meta.insert(Value::String(key), value);
+            }
+        }
+
+        Ok((compose_front_matter(&tags, custom_meta)?, extracted.body()))
+    }
+
+    ///
+    /// 1行分の書き換え
+    ///
+    /// # 注記
+    /// インラインコード内は書き換えない。
+    ///
+    fn convert_line(&mut self, line: &str) -> String {
+        let mut output = String::with_capacity(line.len());
+        let mut rest = line;
+
+        while !rest.is_empty() {
+            /*
+             * インラインコードの読み飛ばし
+             */
+            if rest.starts_with('`') {
+                let ticks = rest.len() - rest.trim_start_matches('`').len();
+                let marker = &rest[..ticks];
+                match rest[ticks..].find(marker) {
+                    Some(end) => {
+                        let end = ticks + end + ticks;
+                        output.push_str(&rest[..end]);
+                        rest = &rest[end..];
+                    }
+                    None => {
+                        output.push_str(rest);
+                        rest = "";
+                    }
+                }
+                continue;
+            }
+
+            /*
+             * wikilink と埋め込みの書き換え
+             */
+            let embed = rest.starts_with("![[");
+            if embed || rest.starts_with("[[") {
+                let start = if embed { 3 } else { 2 };
+                if let Some(end) = rest[start..].find("]]") {
+                    let inner = &rest[start..start + end];
+                    let original = &rest[..start + end + 2];
+                    output.push_str(&self.convert_link(inner, embed, original));
+                    rest = &rest[start + end + 2..];
+                    continue;
+                }
+            }
+
+            let ch = rest.chars().next().unwrap_or_default();
+            output.push(ch);
+            rest = &rest[ch.len_utf8()..];
+        }
+
+        output
+    }
+
+    ///
+    /// wikilink と埋め込みの書き換え
+    ///
+    /// # 引数
+    /// * `inner` - `[[`と`]]`の間の文字列
+    /// * `embed` - 埋め込み(`![[...]]`)の場合は true
+    /// * `original` - 書き換え前の記述
+    ///
+    /// # 戻り値
+    /// 書き換え後の記述を返す。
+    ///
+    fn convert_link(&mut self, inner: &str, embed: bool, original: &str) -> String {
+        let (target, alias) = match inner.split_once('|') {
+            Some((target, alias)) => (target.trim(), Some(alias.trim())),
+            None => (inner.trim(), None),
+        };
+        let (name, fragment) = match target.split_once('#') {
+            Some((name, fragment)) => (name.trim(), Some(fragment.trim())),
+            None => (target, None),
+        };
+
+        /*
+         * 同一ノート内の見出しへのリンク
+         */
+        if name.is_empty() {
+            let Some(fragment) = fragment.filter(|fragment| !fragment.is_empty()) else {
+                return original.to_string();
+            };
+            let text = alias.unwrap_or(fragment);
+            return markdown_link(text, &format!("#{}", encode_link_target(fragment)));
+        }
+
+        /*
+         * 添付ファイルへの参照
+         */
+        let note = self.index.resolve_note(name).cloned();
+        if note.is_none() && !name.ends_with(NOTE_FILE_EXTENSION) {
+            if let Some(rel_file) = self.index.resolve_attachment(name) {
+                return self.attachment_link(rel_file, embed, alias);
+            }
+            if file_name_of(name).contains('.') {
+                self.warn(
+                    "unresolved_attachment",
+                    format!("attachment not found: {} ({})", name, self.rel_path),
+                );
+                return original.to_string();
+            }
+        }
+
+        /*
+         * ノートへのリンク(未解決の場合は保管庫直下のノートとみなす)
+         */
+        let note = match note {
+            Some(note) => note,
+            None => name
+                .strip_suffix(NOTE_FILE_EXTENSION)
+                .unwrap_or(name)
+                .trim_start_matches('/')
+                .to_string(),
+        };
+        let mut link_target =
+            encode_link_target(&relative_link_target(self.rel_path, &note));
+        if let Some(fragment) = fragment.filter(|fragment| {
+            !fragment.is_empty() && !fragment.starts_with('^')
+        }) {
+            link_target.push('#');
+            link_target.push_str(&encode_link_target(fragment));
+        }
+
+        let text = match alias {
+            Some(alias) if !embed && !alias.is_empty() => alias.to_string(),
+            _ => file_name_of(&note).to_string(),
+        };
+        markdown_link(&text, &link_target)
+    }
+
+    ///
+    /// 添付ファイルへの参照の生成
+    ///
+    /// # 注記
+    /// 添付ファイルが未割り当ての場合は変換中のノートへ割り当てる。画像の
+    /// 埋め込みは画像として、それ以外はリンクとして記述する。
+    ///
+    fn attachment_link(
+        &mut self,
+        rel_file: &str,
+        embed: bool,
+        alias: Option<&str>,
+    ) -> String {
+        let owner = self
+            .owners
+            .entry(rel_file.to_string())
+            .or_insert_with(|| self.rel_path.to_string())
+            .clone();
+        let file_name = file_name_of(rel_file);
+        let target = asset_link_target(self.rel_path, &owner, file_name);
+
+        let is_image = MimeGuess::from_path(file_name)
+            .first()
+            .is_some_and(|mime| mime.type_() == "image");
+        if embed && is_image {
+            format!("![{}]({})", file_name, target)
+        } else {
+            let text = alias
+                .filter(|alias| !embed && !alias.is_empty())
+                .unwrap_or(file_name);
+            markdown_link(text, &target)
+        }
+    }
+
+    ///
+    /// warning の追加
+    ///
+    fn warn(&mut self, code: &'static str, message: String) {
+        self.warnings.push(ValidationWarning { code, message });
+    }
+}
+
+///
+/// プロパティ値からのタグの収集
+///
+/// # 注記
+/// 文字列の場合はカンマまたは空白で区切られたタグ列として扱う。
+///
+fn collect_tags(value: &Value, tags: &mut Vec<String>) {
+    match value {
+        Value::String(raw) => {
+            tags.extend(
+                raw.split(|ch: char| ch == ',' || ch.is_whitespace())
+                    .filter_map(normalize_tag),
+            );
+        }
+        Value::Number(number) => {
+            tags.extend(normalize_tag(&number.to_string()));
+        }
+        Value::Sequence(values) => {
+            for value in values {
+                collect_tags(value, tags);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::markdown_source::front_matter::parse_document_front_matter;
+
+    ///
+    /// wikilink、埋め込み、タグが LuWiki の記法へ変換されることを確認する。
+    ///
+    /// # 注記
+    /// 1) ノートと添付ファイルを持つ保管庫を作成する
+    /// 2) 保管庫を読み込み、各ノートのソースと所属アセットを確認する
+    ///
+    #[test]
+    fn read_obsidian_vault_converts_links_embeds_and_tags() {
+        let dir = tempfile::tempdir().expect("create temp dir failed");
+        let vault = dir.path();
+        fs::create_dir_all(vault.join(".obsidian")).unwrap();
+        fs::create_dir_all(vault.join("notes/deep")).unwrap();
+        fs::create_dir_all(vault.join("attachments")).unwrap();
+        fs::write(vault.join(".obsidian/app.json"), "{}").unwrap();
+        fs::write(
+            vault.join("Home.md"),
+            "---\ntags: [project, \"#idea\"]\naliases: [Start]\n---\n\
+             See [[Target Note|the target]] and [[Target Note#Intro]].\n\
+             ![[diagram.png]]\n\
+             `[[not a link]]`\n\
+             ```\n[[in code]]\n```\n",
+        )
+        .unwrap();
+        fs::write(
+            vault.join("notes/deep/Target Note.md"),
+            "Back to [[Home]], see ![[diagram.png|200]] and ![[missing.png]].\n",
+        )
+        .unwrap();
+        fs::write(vault.join("attachments/diagram.png"), b"png").unwrap();
+        fs::write(vault.join("attachments/unused.pdf"), b"pdf").unwrap();
+
+        let vault = read_obsidian_vault(vault).expect("read vault failed");
+        let pages = &vault.tree.pages;
+        assert_eq!(pages.len(), 2);
+
+        let home = &pages[0];
+        assert_eq!(home.rel_path, "Home");
+        let source = home.source.as_deref().unwrap();
+        assert!(source.contains(
+            "[the target](../notes/deep/Target%20Note)"
+        ));
+        assert!(source.contains(
+            "[Target Note](../notes/deep/Target%20Note#Intro)"
+        ));
+        assert!(source.contains("![diagram.png](asset:diagram.png)"));
+        assert!(source.contains("`[[not a link]]`"));
+        assert!(source.contains("[[in code]]"));
+        let front_matter = parse_document_front_matter(source)
+            .expect("parse failed")
+            .expect("front matter missing");
+        assert_eq!(
+            front_matter.wiki().and_then(|wiki| wiki.tags()),
+            Some(&["project".to_string(), "idea".to_string()][..])
+        );
+        assert!(front_matter.custom_meta().unwrap().contains_key("aliases"));
+        assert_eq!(home.assets.len(), 1);
+        assert_eq!(home.assets[0].file_name, "diagram.png");
+
+        let target = &pages[1];
+        assert_eq!(target.rel_path, "notes/deep/Target Note");
+        let source = target.source.as_deref().unwrap();
+        assert!(source.contains("[Home](../../../Home)"));
+        assert!(source.contains(
+            "![diagram.png](asset:../../../Home:diagram.png)"
+        ));
+        assert!(source.contains("![[missing.png]]"));
+        assert!(target.assets.is_empty());
+
+        assert_eq!(vault.tree.ignored.len(), 1);
+        assert!(vault.tree.ignored[0].ends_with("unused.pdf"));
+        assert!(
+            vault
+                .warnings
+                .iter()
+                .any(|warning| warning.code == "unresolved_attachment")
+        );
+    }
+}
@@ -38,6 +38,7 @@ pub(crate) enum PageRenameRevisionsMode {
 pub(crate) enum PlacementRule {
     RestoreIntoEmptyDatabase,
     RelocateByPrefix,
+    ApplyOntoRestoredDatabase,
 }
 
 ///
@@ -99,6 +100,26 @@ impl ExportImportPolicy {
         }
     }
 
+    ///
+    /// 差分 backup 用ポリシーの生成
+    ///
+    /// # 戻り値
+    /// 差分 backup 用ポリシーを返す。
+    ///
+    /// # 注記
+    /// 差分アーカイブは復元済み DB へ重ねて適用するため、既存データとの
+    /// ID 重複検証は行わず、取り込み済みアーカイブとの連続性と既存データを
+    /// 含めた参照整合性を検証する。
+    ///
+    pub(crate) fn incremental() -> Self {
+        let mut policy = Self::backup();
+        policy.placement_rule = PlacementRule::ApplyOntoRestoredDatabase;
+        policy.validation_policy.check_id_duplicates = false;
+        policy.validation_policy.check_username_duplicates = false;
+        policy.validation_policy.check_reference_integrity = false;
+        policy
+    }
+
     ///
     /// migrate 用ポリシーの生成
     ///
@@ -271,6 +292,21 @@ mod tests {
         assert!(policy.validation_policy().normalize_rename_for_migrate);
     }
 
+    #[test]
+    fn incremental_policy_applies_onto_restored_database() {
+        let policy = ExportImportPolicy::incremental();
+
+        assert_eq!(policy.export_type(), ExportType::Backup);
+        assert_eq!(policy.export_root(), "/");
+        assert_eq!(
+            policy.placement_rule(),
+            PlacementRule::ApplyOntoRestoredDatabase
+        );
+        assert!(policy.validation_policy().check_manifest_counts);
+        assert!(policy.validation_policy().check_asset_blob_integrity);
+        assert!(!policy.validation_policy().check_id_duplicates);
+    }
+
     #[test]
     fn migrate_policy_rejects_root() {
         let err = ExportImportPolicy::migrate("/")
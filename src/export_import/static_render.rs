@@ -0,0 +1,1742 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 静的サイト出力用の Markdown レンダリング処理
+//!
+//! フロントエンドの表示処理(マクロ展開とリンク解決)と同じ規則でページを
+//! HTML へ変換する。ページ間リンクとアセット参照はサイト内の相対パスへ
+//! 置き換える。
+//!
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag, TagEnd, html};
+
+use crate::database::types::AssetId;
+use crate::fts::extract_markdown_sections;
+use crate::markdown_source::front_matter::extract_front_matter;
+
+/// アセット参照記法の接頭辞
+const ASSET_PREFIX: &str = "asset:";
+
+/// コード領域退避用プレースホルダの区切り文字
+const CODE_MASK_DELIMITER: char = '\u{7}';
+
+/// アセット出力先ディレクトリ名
+pub(crate) const ASSET_DIR_NAME: &str = "_assets";
+
+///
+/// 静的サイト上のアセット
+///
+#[derive(Clone, Debug)]
+pub(crate) struct SiteAsset {
+    /// アセットID
+    pub(crate) id: AssetId,
+
+    /// ファイル名
+    pub(crate) file_name: String,
+
+    /// MIME種別
+    pub(crate) mime: String,
+
+    /// 実体ファイルのパス
+    pub(crate) blob_path: PathBuf,
+}
+
+impl SiteAsset {
+    ///
+    /// サイトルートからの出力先パス
+    ///
+    /// # 戻り値
+    /// `_assets/{アセットID}/{ファイル名}`形式のパスを返す。
+    ///
+    pub(crate) fn output_path(&self) -> String {
+        format!("{}/{}/{}", ASSET_DIR_NAME, self.id, self.file_name)
+    }
+}
+
+///
+/// 静的サイトの構成情報
+///
+/// # 注記
+/// ページパスは Wiki 上の絶対パスで保持し、出力先はエクスポート起点から
+/// の相対位置で決定する。
+///
+#[derive(Clone, Debug)]
+pub(crate) struct SiteMap {
+    /// エクスポート起点パス
+    root: String,
+
+    /// 出力対象ページのパス
+    pages: BTreeSet<String>,
+
+    /// ページ所属アセット((ページパス, ファイル名) => アセット)
+    page_assets: BTreeMap<(String, String), SiteAsset>,
+
+    /// 共有アセット(ファイル名 => アセット)
+    shared_assets: BTreeMap<String, SiteAsset>,
+}
+
+impl SiteMap {
+    ///
+    /// オブジェクトの生成
+    ///
+    /// # 引数
+    /// * `root` - エクスポート起点パス
+    ///
+    pub(crate) fn new(root: &str) -> Self {
+        Self {
+            root: root.to_string(),
+            pages: BTreeSet::new(),
+            page_assets: BTreeMap::new(),
+            shared_assets: BTreeMap::new(),
+        }
+    }
+
+    ///
+    /// エクスポート起点パスへのアクセサ
+    ///
+    pub(crate) fn root(&self) -> &str {
+        &self.root
+    }
+
+    ///
+    /// 出力対象ページの登録
+    ///
+    pub(crate) fn add_page(&mut self, path: &str) {
+        self.pages.insert(path.to_string());
+    }
+
+    ///
+    /// アセットの登録
+    ///
+    /// # 引数
+    /// * `page_path` - 所属ページのパス(共有アセットの場合は`None`)
+    /// * `asset` - 登録するアセット
+    ///
+    pub(crate) fn add_asset(&mut self, page_path: Option<&str>, asset: SiteAsset) {
+        match page_path {
+            Some(path) => {
+                self.page_assets
+                    .insert((path.to_string(), asset.file_name.clone()), asset);
+            }
+            None => {
+                self.shared_assets.insert(asset.file_name.clone(), asset);
+            }
+        }
+    }
+
+    ///
+    /// 出力対象ページのパス一覧
+    ///
+    /// # 戻り値
+    /// パス順の一覧を返す。
+    ///
+    pub(crate) fn page_paths(&self) -> impl Iterator<Item = &String> {
+        self.pages.iter()
+    }
+
+    ///
+    /// 出力対象ページか否かの判定
+    ///
+    pub(crate) fn has_page(&self, path: &str) -> bool {
+        self.pages.contains(path)
+    }
+
+    ///
+    /// ページ所属アセットの一覧
+    ///
+    /// # 引数
+    /// * `page_path` - ページパス
+    ///
+    /// # 戻り値
+    /// ファイル名順の一覧を返す。
+    ///
+    pub(crate) fn page_assets(&self, page_path: &str) -> Vec<&SiteAsset> {
+        self.page_assets
+            .range((page_path.to_string(), String::new())..)
+            .take_while(|((path, _), _)| path == page_path)
+            .map(|(_, asset)| asset)
+            .collect()
+    }
+
+    ///
+    /// 全てのページ所属アセット
+    ///
+    pub(crate) fn all_page_assets(&self) -> impl Iterator<Item = &SiteAsset> {
+        self.page_assets.values()
+    }
+
+    ///
+    /// 共有アセットの検索
+    ///
+    /// # 引数
+    /// * `output_path` - サイトルートからの出力先パス
+    ///
+    /// # 戻り値
+    /// 該当する共有アセットを返す。
+    ///
+    pub(crate) fn find_shared_asset_by_output_path(
+        &self,
+        output_path: &str,
+    ) -> Option<&SiteAsset> {
+        self.shared_assets
+            .values()
+            .find(|asset| asset.output_path() == output_path)
+    }
+
+    ///
+    /// ページのサイト内相対セグメント列
+    ///
+    /// # 引数
+    /// * `path` - ページパス
+    ///
+    /// # 戻り値
+    /// エクスポート起点からのセグメント列を返す。起点配下にない場合は
+    /// `None`を返す。
+    ///
+    pub(crate) fn page_segments(&self, path: &str) -> Option<Vec<String>> {
+        let rest = if self.root == "/" {
+            path.strip_prefix('/')?
+        } else if path == self.root {
+            ""
+        } else {
+            path.strip_prefix(&self.root)?.strip_prefix('/')?
+        };
+
+        Some(
+            rest.split('/')
+                .filter(|segment| !segment.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+
+    ///
+    /// ページの出力先パス
+    ///
+    /// # 引数
+    /// * `path` - ページパス
+    ///
+    /// # 戻り値
+    /// サイトルートからの出力先パス(未エンコード)を返す。起点ページは
+    /// `index.html`、それ以外は`{相対パス}/index.html`となる。
+    ///
+    pub(crate) fn page_output_path(&self, path: &str) -> Option<String> {
+        let segments = self.page_segments(path)?;
+        if segments.is_empty() {
+            Some("index.html".to_string())
+        } else {
+            Some(format!("{}/index.html", segments.join("/")))
+        }
+    }
+
+    ///
+    /// ページの出力先ディレクトリ階層の深さ
+    ///
+    /// # 引数
+    /// * `path` - ページパス
+    ///
+    /// # 戻り値
+    /// サイトルートからの階層数を返す。
+    ///
+    pub(crate) fn page_depth(&self, path: &str) -> usize {
+        self.page_segments(path)
+            .map(|segments| segments.len())
+            .unwrap_or(0)
+    }
+
+    ///
+    /// ページ間の相対リンクの生成
+    ///
+    /// # 引数
+    /// * `from` - リンク元ページのパス
+    /// * `to` - リンク先ページのパス
+    ///
+    /// # 戻り値
+    /// リンク先が出力対象の場合は相対URLを返す。
+    ///
+    fn page_href(&self, from: &str, to: &str) -> Option<String> {
+        if !self.has_page(to) {
+            return None;
+        }
+
+        let target = self.page_output_path(to)?;
+        Some(relative_href(self.page_depth(from), &target))
+    }
+
+    ///
+    /// アセット参照の解決
+    ///
+    /// # 引数
+    /// * `page_path` - 参照元ページのパス
+    /// * `spec` - `asset:`記法の文字列
+    ///
+    /// # 戻り値
+    /// 解決できたアセットを返す。
+    ///
+    fn resolve_asset(&self, page_path: &str, spec: &str) -> Option<&SiteAsset> {
+        let (path, file) = parse_asset_spec(spec)?;
+        let file = decode_percent(&file);
+
+        if path.is_empty() {
+            return self.shared_assets.get(&file);
+        }
+
+        let path = resolve_page_path(page_path, &path)?;
+        self.page_assets.get(&(path, file))
+    }
+}
+
+///
+/// ページのレンダリング結果
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RenderedPage {
+    /// ページタイトル
+    pub(crate) title: String,
+
+    /// 本文HTML
+    pub(crate) body_html: String,
+
+    /// 検索索引用テキスト
+    pub(crate) search_text: String,
+
+    /// 参照したアセットの出力先パス
+    pub(crate) used_assets: BTreeSet<String>,
+
+    /// 解決できなかった参照の一覧
+    pub(crate) issues: Vec<String>,
+}
+
+///
+/// ページのレンダリング
+///
+/// # 引数
+/// * `site` - 静的サイトの構成情報
+/// * `page_path` - 対象ページのパス
+/// * `source` - 対象ページの最新ソース
+///
+/// # 戻り値
+/// レンダリング結果を返す。
+///
+/// # 注記
+/// front matter は出力しない。レンダリング時変換型マクロと特例型マクロを
+/// 展開した上で HTML へ変換し、埋め込み HTML はテキストとして出力する。
+///
+pub(crate) fn render_page(
+    site: &SiteMap,
+    page_path: &str,
+    source: &str,
+) -> RenderedPage {
+    let mut renderer = PageRenderer {
+        site,
+        page_path,
+        result: RenderedPage::default(),
+    };
+
+    /*
+     * front matter の除去
+     */
+    let body = match extract_front_matter(source) {
+        Ok(Some(extracted)) => extracted.body(),
+        _ => source,
+    };
+
+    /*
+     * マクロ展開とHTML変換
+     */
+    let markdown = renderer.expand_macros(body);
+    renderer.result.title = extract_title(&markdown, page_path);
+    renderer.result.body_html = renderer.render_markdown(&markdown);
+
+    let sections = extract_markdown_sections(&markdown);
+    renderer.result.search_text = collapse_whitespace(&format!(
+        "{}\n{}",
+        sections.headings, sections.body
+    ));
+
+    renderer.result
+}
+
+///
+/// 1ページ分のレンダリング状態
+///
+struct PageRenderer<'a> {
+    site: &'a SiteMap,
+    page_path: &'a str,
+    result: RenderedPage,
+}
+
+///
+/// リンク終端の扱い
+///
+enum LinkEnd {
+    /// 元のタグのまま閉じる
+    Keep,
+
+    /// タグを出力しない(未解決リンク)
+    Drop,
+
+    /// 画像をリンクとして閉じる
+    ImageAsLink,
+}
+
+impl PageRenderer<'_> {
+    ///
+    /// マクロの展開
+    ///
+    /// # 引数
+    /// * `source` - front matter 除去済みの Markdown
+    ///
+    /// # 戻り値
+    /// 展開後の Markdown を返す。
+    ///
+    fn expand_macros(&mut self, source: &str) -> String {
+        let (masked, codes) = mask_code_regions(source);
+        let special = restore_code_regions(&expand_special_macros(&masked), &codes);
+
+        let (masked, codes) = mask_code_regions(&special);
+        let mut result = String::with_capacity(masked.len());
+        let mut rest = masked.as_str();
+
+        while let Some((start, end)) = find_inline_macro(rest) {
+            result.push_str(&rest[..start]);
+            let raw = &rest[start..end];
+            let expr = &raw[2..raw.len() - 2];
+            match self.expand_render_macro(expr, &masked) {
+                Some(expanded) => result.push_str(&expanded),
+                None => result.push_str(raw),
+            }
+            rest = &rest[end..];
+        }
+        result.push_str(rest);
+
+        restore_code_regions(&result, &codes)
+    }
+
+    ///
+    /// レンダリング時変換型マクロの展開
+    ///
+    /// # 引数
+    /// * `expr` - `{{`と`}}`の間の文字列
+    /// * `source` - コード領域退避済みのページ全体
+    ///
+    /// # 戻り値
+    /// 展開結果を返す。対象外のマクロや展開できないマクロは`None`を返す。
+    ///
+    fn expand_render_macro(&mut self, expr: &str, source: &str) -> Option<String> {
+        let (name, args) = parse_macro(expr)?;
+        let expanded = match name.as_str() {
+            "children" => self.expand_children(&args),
+            "toc" => expand_toc(&args, source),
+            "include_code" => self.expand_include_code(&args),
+            "include_csv" => self.expand_include_csv(&args),
+            _ => return None,
+        };
+
+        if expanded.is_none() {
+            self.result
+                .issues
+                .push(format!("macro not expanded: {{{{{}}}}}", expr));
+        }
+        expanded
+    }
+
+    ///
+    /// childrenマクロの展開
+    ///
+    fn expand_children(&self, raw_args: &[String]) -> Option<String> {
+        let args = MacroArgs::parse(raw_args);
+        let recursive = args.has_flag(&["recursive", "r"]);
+        let depth_raw = args.value(&["depth", "d"]);
+
+        let depth = match (recursive, depth_raw) {
+            (true, Some(_)) => return None,
+            (true, None) => usize::MAX,
+            (false, Some(raw)) => match raw.parse::<usize>() {
+                Ok(depth) if depth > 0 => depth,
+                _ => return None,
+            },
+            (false, None) => 1,
+        };
+
+        let base_depth = count_path_segments(self.page_path);
+        let items: Vec<String> = self
+            .site
+            .page_paths()
+            .filter(|path| is_descendant_path(self.page_path, path))
+            .filter(|path| {
+                let relative = count_path_segments(path) - base_depth;
+                relative > 0 && relative <= depth
+            })
+            .map(|path| {
+                format!(
+                    "- [{}]({})",
+                    escape_markdown_text(&extract_page_name(path)),
+                    link_destination(path)
+                )
+            })
+            .collect();
+
+        Some(items.join("\n"))
+    }
+
+    ///
+    /// include_codeマクロの展開
+    ///
+    fn expand_include_code(&mut self, raw_args: &[String]) -> Option<String> {
+        let args = MacroArgs::parse(raw_args);
+        let src = args.value(&["src", "s"])?;
+        let (text, mime) = self.load_asset_text(src)?;
+        if !mime.starts_with("text/") {
+            return None;
+        }
+
+        let lang = args
+            .value(&["lang", "l"])
+            .map(str::to_string)
+            .or_else(|| language_from_mime(&mime).map(str::to_string))
+            .unwrap_or_default();
+        let fence = if text.contains("```") { "~~~" } else { "```" };
+
+        Some(format!("{}{}\n{}\n{}", fence, lang, text, fence))
+    }
+
+    ///
+    /// include_csvマクロの展開
+    ///
+    fn expand_include_csv(&mut self, raw_args: &[String]) -> Option<String> {
+        let args = MacroArgs::parse(raw_args);
+        let src = args.value(&["src", "s"])?;
+        let (text, mime) = self.load_asset_text(src)?;
+        if mime != "text/csv" {
+            return None;
+        }
+
+        let rows = parse_csv(&text);
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if rows.is_empty() {
+            return Some(String::new());
+        }
+
+        let mut lines = Vec::with_capacity(rows.len() + 1);
+        for (index, row) in rows.iter().enumerate() {
+            let mut cells: Vec<String> =
+                row.iter().map(|cell| escape_markdown_text(cell)).collect();
+            cells.resize(width, String::new());
+            lines.push(format!("| {} |", cells.join(" | ")));
+            if index == 0 {
+                lines.push(format!("| {} |", vec!["---"; width].join(" | ")));
+            }
+        }
+
+        Some(lines.join("\n"))
+    }
+
+    ///
+    /// マクロで参照するアセットのテキスト読み込み
+    ///
+    /// # 引数
+    /// * `src` - `src`引数の値
+    ///
+    /// # 戻り値
+    /// (テキスト, MIME種別)を返す。
+    ///
+    fn load_asset_text(&mut self, src: &str) -> Option<(String, String)> {
+        let spec = if src.starts_with(ASSET_PREFIX) {
+            src.to_string()
+        } else {
+            format!("{}{}", ASSET_PREFIX, src)
+        };
+        let asset = self.site.resolve_asset(self.page_path, &spec)?;
+        let data = fs::read(&asset.blob_path).ok()?;
+        let mime = asset
+            .mime
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+
+        Some((String::from_utf8_lossy(&data).into_owned(), mime))
+    }
+
+    ///
+    /// Markdown から HTML への変換
+    ///
+    /// # 引数
+    /// * `markdown` - マクロ展開済みの Markdown
+    ///
+    /// # 戻り値
+    /// 本文 HTML を返す。
+    ///
+    fn render_markdown(&mut self, markdown: &str) -> String {
+        let events: Vec<Event> =
+            Parser::new_ext(markdown, markdown_options()).collect();
+        let mut output = Vec::with_capacity(events.len());
+        let mut used_slugs = HashSet::new();
+        let mut link_ends = Vec::new();
+
+        for (index, event) in events.iter().enumerate() {
+            match event {
+                Event::Start(Tag::Heading { level, classes, attrs, .. }) => {
+                    /*
+                     * 見出しへアンカーを付与
+                     */
+                    let text = heading_text(&events[index + 1..]);
+                    let slug = create_unique_slug(&slugify_heading(&text), &mut used_slugs);
+                    output.push(Event::Start(Tag::Heading {
+                        level: *level,
+                        id: Some(CowStr::from(slug)),
+                        classes: classes.clone(),
+                        attrs: attrs.clone(),
+                    }));
+                }
+                Event::Start(Tag::Link { link_type, dest_url, title, id }) => {
+                    match self.rewrite_link(dest_url) {
+                        Some(href) => {
+                            output.push(Event::Start(Tag::Link {
+                                link_type: *link_type,
+                                dest_url: CowStr::from(href),
+                                title: title.clone(),
+                                id: id.clone(),
+                            }));
+                            link_ends.push(LinkEnd::Keep);
+                        }
+                        None => link_ends.push(LinkEnd::Drop),
+                    }
+                }
+                Event::Start(Tag::Image { link_type, dest_url, title, id }) => {
+                    self.push_image(
+                        &mut output,
+                        &mut link_ends,
+                        Tag::Image {
+                            link_type: *link_type,
+                            dest_url: dest_url.clone(),
+                            title: title.clone(),
+                            id: id.clone(),
+                        },
+                    );
+                }
+                Event::End(TagEnd::Link) | Event::End(TagEnd::Image) => {
+                    match link_ends.pop() {
+                        Some(LinkEnd::Drop) => {}
+                        Some(LinkEnd::ImageAsLink) => {
+                            output.push(Event::End(TagEnd::Link));
+                        }
+                        _ => output.push(event.clone()),
+                    }
+                }
+                Event::Start(Tag::HtmlBlock) => {
+                    output.push(Event::Start(Tag::Paragraph));
+                }
+                Event::End(TagEnd::HtmlBlock) => {
+                    output.push(Event::End(TagEnd::Paragraph));
+                }
+                Event::Html(text) | Event::InlineHtml(text) => {
+                    output.push(Event::Text(text.clone()));
+                }
+                _ => output.push(event.clone()),
+            }
+        }
+
+        let mut body = String::with_capacity(markdown.len() * 2);
+        html::push_html(&mut body, output.into_iter());
+        body
+    }
+
+    ///
+    /// 画像タグの出力
+    ///
+    /// # 注記
+    /// 画像以外のアセットを埋め込んだ場合はリンクとして出力する。
+    ///
+    fn push_image<'e>(
+        &mut self,
+        output: &mut Vec<Event<'e>>,
+        link_ends: &mut Vec<LinkEnd>,
+        tag: Tag<'e>,
+    ) {
+        let Tag::Image { link_type, dest_url, title, id } = tag else {
+            return;
+        };
+
+        if !dest_url.starts_with(ASSET_PREFIX) {
+            if link_scheme(&dest_url).is_some_and(|scheme| !is_allowed_scheme(&scheme)) {
+                self.result
+                    .issues
+                    .push(format!("unsafe link dropped: {}", dest_url));
+                link_ends.push(LinkEnd::Drop);
+                return;
+            }
+
+            output.push(Event::Start(Tag::Image { link_type, dest_url, title, id }));
+            link_ends.push(LinkEnd::Keep);
+            return;
+        }
+
+        let asset = match self.site.resolve_asset(self.page_path, &dest_url) {
+            Some(asset) => asset,
+            None => {
+                self.result
+                    .issues
+                    .push(format!("asset not found: {}", dest_url));
+                link_ends.push(LinkEnd::Drop);
+                return;
+            }
+        };
+
+        let output_path = asset.output_path();
+        let href = relative_href(self.site.page_depth(self.page_path), &output_path);
+        let is_image = asset.mime.starts_with("image/");
+        self.result.used_assets.insert(output_path);
+
+        if is_image {
+            output.push(Event::Start(Tag::Image {
+                link_type,
+                dest_url: CowStr::from(href),
+                title,
+                id,
+            }));
+            link_ends.push(LinkEnd::Keep);
+        } else {
+            output.push(Event::Start(Tag::Link {
+                link_type,
+                dest_url: CowStr::from(href),
+                title,
+                id,
+            }));
+            link_ends.push(LinkEnd::ImageAsLink);
+        }
+    }
+
+    ///
+    /// リンク先の書き換え
+    ///
+    /// # 引数
+    /// * `dest` - 元のリンク先
+    ///
+    /// # 戻り値
+    /// 書き換え後のリンク先を返す。サイト内で解決できない場合は`None`を返す。
+    ///
+    fn rewrite_link(&mut self, dest: &str) -> Option<String> {
+        let depth = self.site.page_depth(self.page_path);
+
+        /*
+         * アセットへのリンク
+         */
+        if dest.starts_with(ASSET_PREFIX) {
+            return match self.site.resolve_asset(self.page_path, dest) {
+                Some(asset) => {
+                    let output_path = asset.output_path();
+                    let href = relative_href(depth, &output_path);
+                    self.result.used_assets.insert(output_path);
+                    Some(href)
+                }
+                None => {
+                    self.result.issues.push(format!("asset not found: {}", dest));
+                    None
+                }
+            };
+        }
+
+        /*
+         * 外部リンクとページ内リンクはそのまま(許可しないスキーマは除去)
+         */
+        if let Some(scheme) = link_scheme(dest) {
+            if is_allowed_scheme(&scheme) {
+                return Some(dest.to_string());
+            }
+            self.result.issues.push(format!("unsafe link dropped: {}", dest));
+            return None;
+        }
+
+        if dest.is_empty() {
+            return Some(dest.to_string());
+        }
+
+        let split = dest.find(['#', '?']).unwrap_or(dest.len());
+        let (path, suffix) = dest.split_at(split);
+        if path.is_empty() {
+            return Some(dest.to_string());
+        }
+
+        /*
+         * ページへのリンク
+         */
+        let href = resolve_page_path(self.page_path, path)
+            .and_then(|resolved| self.site.page_href(self.page_path, &resolved));
+        match href {
+            Some(href) => Some(format!("{}{}", href, suffix)),
+            None => {
+                self.result.issues.push(format!("page not found: {}", dest));
+                None
+            }
+        }
+    }
+}
+
+///
+/// マクロ引数の解析結果
+///
+struct MacroArgs {
+    flags: HashSet<String>,
+    values: BTreeMap<String, String>,
+}
+
+impl MacroArgs {
+    ///
+    /// 引数列の解析
+    ///
+    fn parse(args: &[String]) -> Self {
+        let mut flags = HashSet::new();
+        let mut values = BTreeMap::new();
+
+        for arg in args.iter().map(|arg| arg.trim()) {
+            if arg.is_empty() {
+                continue;
+            }
+            match arg.split_once('=') {
+                Some((key, value)) => {
+                    let key = key.trim().to_lowercase();
+                    let value = value.trim();
+                    if !key.is_empty() && !value.is_empty() {
+                        values.insert(key, value.to_string());
+                    }
+                }
+                None => {
+                    flags.insert(arg.to_lowercase());
+                }
+            }
+        }
+
+        Self { flags, values }
+    }
+
+    ///
+    /// フラグ指定の有無
+    ///
+    fn has_flag(&self, keys: &[&str]) -> bool {
+        keys.iter().any(|key| self.flags.contains(*key))
+    }
+
+    ///
+    /// 値指定の取得
+    ///
+    fn value(&self, keys: &[&str]) -> Option<&str> {
+        keys.iter()
+            .find_map(|key| self.values.get(*key))
+            .map(String::as_str)
+    }
+}
+
+///
+/// Markdown パーサのオプション
+///
+fn markdown_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+}
+
+///
+/// マクロ式の分解
+///
+/// # 引数
+/// * `expr` - `{{`と`}}`の間の文字列
+///
+/// # 戻り値
+/// (マクロ名, 引数列)を返す。
+///
+fn parse_macro(expr: &str) -> Option<(String, Vec<String>)> {
+    let tokens: Vec<&str> = expr
+        .split(':')
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .collect();
+    let name = tokens.first()?.to_lowercase();
+
+    /*
+     * アセットパス中の`:`で分割された src 引数を連結する
+     */
+    if name != "include_code" && name != "include_csv" {
+        let args = tokens[1..].iter().map(|token| token.to_string()).collect();
+        return Some((name, args));
+    }
+
+    let mut args: Vec<String> = Vec::new();
+    let mut current: Option<(String, bool)> = None;
+    for token in &tokens[1..] {
+        let lower = token.to_lowercase();
+        let is_src = lower.starts_with("src=") || lower.starts_with("s=");
+        let starts_new = is_src || lower.starts_with("lang=") || lower.starts_with("l=");
+
+        if starts_new {
+            if let Some((arg, _)) = current.take() {
+                args.push(arg);
+            }
+            current = Some((token.to_string(), is_src));
+            continue;
+        }
+
+        match current.as_mut() {
+            Some((arg, true)) => {
+                arg.push(':');
+                arg.push_str(token);
+            }
+            _ => {
+                if let Some((arg, _)) = current.take() {
+                    args.push(arg);
+                }
+                args.push(token.to_string());
+            }
+        }
+    }
+    if let Some((arg, _)) = current {
+        args.push(arg);
+    }
+
+    Some((name, args))
+}
+
+///
+/// インラインマクロ記法の検索
+///
+/// # 引数
+/// * `source` - 検索対象
+///
+/// # 戻り値
+/// `{{...}}`の開始位置と終了位置を返す。
+///
+fn find_inline_macro(source: &str) -> Option<(usize, usize)> {
+    let mut offset = 0;
+    while let Some(found) = source[offset..].find("{{") {
+        let start = offset + found;
+        let body_start = start + 2;
+        let body_len = source[body_start..]
+            .find(['{', '}', '\n'])
+            .unwrap_or(source.len() - body_start);
+        let body_end = body_start + body_len;
+
+        if body_len > 0 && source[body_end..].starts_with("}}") {
+            return Some((start, body_end + 2));
+        }
+        offset = start + 1;
+    }
+
+    None
+}
+
+///
+/// tocマクロの展開
+///
+/// # 引数
+/// * `raw_args` - マクロ引数
+/// * `source` - コード領域退避済みのページ全体
+///
+fn expand_toc(raw_args: &[String], source: &str) -> Option<String> {
+    let args = MacroArgs::parse(raw_args);
+    let depth = match args.value(&["depth", "d"]) {
+        Some(raw) => match raw.parse::<usize>() {
+            Ok(depth) if depth > 0 => depth,
+            _ => return None,
+        },
+        None => 3,
+    };
+
+    let max_level = 6.min(1 + depth);
+    let mut used_slugs = HashSet::new();
+    let mut items = Vec::new();
+    for line in source.lines() {
+        let Some((level, text)) = parse_heading_line(line) else {
+            continue;
+        };
+        if level < 2 || level > max_level {
+            continue;
+        }
+
+        let anchor = create_unique_slug(&slugify_heading(text), &mut used_slugs);
+        items.push(format!(
+            "{}- [{}](#{})",
+            "  ".repeat(level - 2),
+            escape_markdown_text(text),
+            anchor
+        ));
+    }
+
+    Some(items.join("\n"))
+}
+
+///
+/// ATX見出し行の分解
+///
+/// # 戻り値
+/// (見出しレベル, 見出しテキスト)を返す。
+///
+fn parse_heading_line(line: &str) -> Option<(usize, &str)> {
+    let line = line.trim_end_matches('\r');
+    let level = line.chars().take_while(|ch| *ch == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+
+    let rest = &line[level..];
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let text = rest.trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some((level, text))
+    }
+}
+
+///
+/// ページタイトルの抽出
+///
+/// # 戻り値
+/// 最初のレベル1見出し、無い場合はページ名を返す。
+///
+fn extract_title(markdown: &str, page_path: &str) -> String {
+    markdown
+        .lines()
+        .filter_map(parse_heading_line)
+        .find(|(level, _)| *level == 1)
+        .map(|(_, text)| text.to_string())
+        .unwrap_or_else(|| extract_page_name(page_path))
+}
+
+///
+/// 特例型マクロ(ページリンク/アセット埋め込み)の展開
+///
+/// # 引数
+/// * `source` - コード領域退避済みの Markdown
+///
+/// # 戻り値
+/// 通常の Markdown リンクへ展開した文字列を返す。
+///
+fn expand_special_macros(source: &str) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut rest = source;
+
+    while let Some(start) = rest.find("[[") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("]]") else {
+            break;
+        };
+        let inner = &after[..end];
+        let embed = rest[..start].ends_with('!');
+
+        let replaced = if inner.contains(['[', ']', '\n']) {
+            None
+        } else if embed && inner.starts_with(ASSET_PREFIX) {
+            expand_asset_embed(inner)
+        } else {
+            expand_page_link(inner)
+        };
+
+        match replaced {
+            Some(replaced) => {
+                let prefix_end = if embed && inner.starts_with(ASSET_PREFIX) {
+                    start - 1
+                } else {
+                    start
+                };
+                result.push_str(&rest[..prefix_end]);
+                result.push_str(&replaced);
+                rest = &after[end + 2..];
+            }
+            None => {
+                result.push_str(&rest[..start + 2]);
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+
+    result
+}
+
+///
+/// `![[asset:...]]`の展開
+///
+fn expand_asset_embed(inner: &str) -> Option<String> {
+    let path = inner[ASSET_PREFIX.len()..].trim();
+    if path.is_empty() {
+        return None;
+    }
+
+    Some(format!(
+        "![{}]({})",
+        escape_markdown_text(&extract_asset_name(path)),
+        link_destination(&format!("{}{}", ASSET_PREFIX, path))
+    ))
+}
+
+///
+/// `[[path]]`および`[[path|alias]]`の展開
+///
+fn expand_page_link(inner: &str) -> Option<String> {
+    let (path, alias) = match inner.split_once('|') {
+        Some((path, alias)) => (path, Some(alias)),
+        None => (inner, None),
+    };
+
+    /*
+     * フラグメントを除くパス部が空の場合は対象外
+     */
+    let (page, fragment) = match path.split_once('#') {
+        Some((page, fragment)) => (page, Some(fragment)),
+        None => (path, None),
+    };
+    if page.is_empty() || fragment.is_some_and(str::is_empty) {
+        return None;
+    }
+
+    let path = path.trim();
+    if path.is_empty() {
+        return None;
+    }
+
+    let label = match alias {
+        Some(alias) => {
+            let alias = alias.trim();
+            if alias.is_empty() {
+                return None;
+            }
+            alias.to_string()
+        }
+        None => extract_page_name(path),
+    };
+
+    Some(format!(
+        "[{}]({})",
+        escape_markdown_text(&label),
+        link_destination(path)
+    ))
+}
+
+///
+/// コード領域(フェンス/インラインコード)の退避
+///
+/// # 引数
+/// * `source` - 対象文字列
+///
+/// # 戻り値
+/// (退避後の文字列, 退避したコード領域)を返す。
+///
+fn mask_code_regions(source: &str) -> (String, Vec<String>) {
+    let ranges = collect_code_ranges(source);
+    let mut masked = String::with_capacity(source.len());
+    let mut codes = Vec::with_capacity(ranges.len());
+    let mut last = 0;
+
+    for (start, end) in ranges {
+        masked.push_str(&source[last..start]);
+        masked.push_str(&code_mask_token(codes.len()));
+        codes.push(source[start..end].to_string());
+        last = end;
+    }
+    masked.push_str(&source[last..]);
+
+    (masked, codes)
+}
+
+///
+/// 退避したコード領域の復元
+///
+fn restore_code_regions(source: &str, codes: &[String]) -> String {
+    let mut restored = source.to_string();
+    for (index, code) in codes.iter().enumerate() {
+        restored = restored.replace(&code_mask_token(index), code);
+    }
+    restored
+}
+
+///
+/// コード領域退避用プレースホルダの生成
+///
+fn code_mask_token(index: usize) -> String {
+    format!("{0}CODE_MASK_{1}{0}", CODE_MASK_DELIMITER, index)
+}
+
+///
+/// コード領域の範囲収集
+///
+/// # 戻り値
+/// 重複の無い昇順の(開始, 終了)位置を返す。
+///
+fn collect_code_ranges(source: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut fence: Option<(&str, usize)> = None;
+    let mut offset = 0;
+
+    for raw_line in source.split_inclusive('\n') {
+        let line_start = offset;
+        let line = raw_line.trim_end_matches(['\n', '\r']);
+        let line_end = line_start + line.len();
+        let trimmed = line.trim();
+        offset += raw_line.len();
+
+        match fence {
+            None if trimmed.starts_with("```") || trimmed.starts_with("~~~") => {
+                let marker = if trimmed.starts_with("```") { "```" } else { "~~~" };
+                fence = Some((marker, line_start));
+            }
+            Some((marker, start)) if trimmed.starts_with(marker) => {
+                ranges.push((start, line_end));
+                fence = None;
+            }
+            Some(_) => {}
+            None => collect_inline_code_ranges(line, line_start, &mut ranges),
+        }
+    }
+
+    if let Some((_, start)) = fence {
+        ranges.push((start, source.len()));
+    }
+
+    ranges
+}
+
+///
+/// 1行分のインラインコード範囲の収集
+///
+fn collect_inline_code_ranges(
+    line: &str,
+    line_offset: usize,
+    ranges: &mut Vec<(usize, usize)>,
+) {
+    let bytes = line.as_bytes();
+    let mut index = 0;
+
+    while index < bytes.len() {
+        if bytes[index] != b'`' {
+            index += 1;
+            continue;
+        }
+
+        let ticks = bytes[index..].iter().take_while(|byte| **byte == b'`').count();
+        let marker = &line[index..index + ticks];
+        let Some(found) = line[index + ticks..].find(marker) else {
+            break;
+        };
+        let end = index + ticks + found + ticks;
+        ranges.push((line_offset + index, line_offset + end));
+        index = end;
+    }
+}
+
+///
+/// 見出し内テキストの収集
+///
+/// # 引数
+/// * `events` - 見出し開始直後からのイベント列
+///
+fn heading_text(events: &[Event]) -> String {
+    let mut text = String::new();
+    for event in events {
+        match event {
+            Event::End(TagEnd::Heading(_)) => break,
+            Event::Text(value) | Event::Code(value) => text.push_str(value),
+            _ => {}
+        }
+    }
+    text
+}
+
+///
+/// 見出しテキストからアンカー文字列を生成
+///
+/// # 注記
+/// フロントエンドの`slugifyHeading()`と同じ規則で生成する。
+///
+pub(crate) fn slugify_heading(text: &str) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return "section".to_string();
+    }
+
+    let mut slug = String::with_capacity(trimmed.len());
+    let mut in_space = false;
+    for ch in trimmed.to_lowercase().chars() {
+        if ch.is_whitespace() {
+            if !in_space {
+                slug.push('-');
+            }
+            in_space = true;
+            continue;
+        }
+        in_space = false;
+
+        let allowed = ch.is_ascii_alphanumeric()
+            || ch == '_'
+            || ch == '-'
+            || ('\u{3040}'..='\u{30ff}').contains(&ch)
+            || ('\u{3400}'..='\u{9fff}').contains(&ch);
+        if allowed {
+            slug.push(ch);
+        }
+    }
+
+    slug
+}
+
+///
+/// 重複しないアンカー文字列の生成
+///
+fn create_unique_slug(base: &str, used: &mut HashSet<String>) -> String {
+    let mut slug = base.to_string();
+    let mut index = 1;
+    while used.contains(&slug) {
+        slug = format!("{}-{}", base, index);
+        index += 1;
+    }
+    used.insert(slug.clone());
+    slug
+}
+
+///
+/// `asset:`記法の分解
+///
+/// # 戻り値
+/// (ページパス, ファイル名)を返す。共有アセットの場合ページパスは空となる。
+///
+fn parse_asset_spec(spec: &str) -> Option<(String, String)> {
+    let rest = spec.strip_prefix(ASSET_PREFIX)?;
+    if rest.is_empty() {
+        return None;
+    }
+
+    let (path, file) = match rest.rfind(':') {
+        Some(index) => (&rest[..index], &rest[index + 1..]),
+        None => match rest.rfind('/') {
+            Some(0) => (".", &rest[1..]),
+            Some(index) => (&rest[..index], &rest[index + 1..]),
+            None => (".", rest),
+        },
+    };
+
+    if file.is_empty() {
+        return None;
+    }
+
+    Some((path.to_string(), file.to_string()))
+}
+
+///
+/// ページ相対パスの解決
+///
+/// # 引数
+/// * `base_path` - 基準ページパス
+/// * `target_path` - 相対または絶対ターゲット
+///
+/// # 戻り値
+/// 解決済み絶対パスを返す。
+///
+fn resolve_page_path(base_path: &str, target_path: &str) -> Option<String> {
+    if target_path.trim().is_empty() || target_path.starts_with('#') {
+        return None;
+    }
+
+    if target_path.starts_with('/') {
+        return Some(clean_path(target_path));
+    }
+
+    let base = if base_path.trim().is_empty() { "/" } else { base_path };
+    if target_path == "." {
+        return Some(clean_path(base));
+    }
+
+    Some(clean_path(&format!("{}/{}", base.trim_end_matches('/'), target_path)))
+}
+
+///
+/// パスの正規化
+///
+fn clean_path(path: &str) -> String {
+    let mut stack: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                stack.pop();
+            }
+            _ => stack.push(part),
+        }
+    }
+    format!("/{}", stack.join("/"))
+}
+
+///
+/// 配下のページか否かの判定
+///
+fn is_descendant_path(base: &str, path: &str) -> bool {
+    if base == "/" {
+        return path != "/";
+    }
+    path.strip_prefix(base)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+///
+/// パスのセグメント数
+///
+fn count_path_segments(path: &str) -> usize {
+    path.split('/').filter(|segment| !segment.is_empty()).count()
+}
+
+///
+/// ページ名(パスの最後の要素)の抽出
+///
+pub(crate) fn extract_page_name(path: &str) -> String {
+    let path = path.split('#').next().unwrap_or_default();
+    if path == "/" {
+        return "/".to_string();
+    }
+    path.rsplit('/')
+        .find(|segment| !segment.is_empty())
+        .unwrap_or(path)
+        .to_string()
+}
+
+///
+/// アセット名の抽出
+///
+fn extract_asset_name(path: &str) -> String {
+    match path.rfind(['/', ':']) {
+        Some(index) => path[index + 1..].to_string(),
+        None => path.to_string(),
+    }
+}
+
+///
+/// Markdown テキスト中の特殊文字のエスケープ
+///
+fn escape_markdown_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(ch, '\\' | '[' | ']' | '|') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+///
+/// Markdown リンク先の表記
+///
+/// # 注記
+/// 空白や括弧を含む場合は`<...>`で囲む。
+///
+fn link_destination(dest: &str) -> String {
+    if dest.contains(|ch: char| ch.is_whitespace() || ch == '(' || ch == ')') {
+        format!("<{}>", dest)
+    } else {
+        dest.to_string()
+    }
+}
+
+///
+/// リンク先のスキーマの抽出
+///
+/// # 戻り値
+/// スキーマ付きのリンクの場合は小文字化したスキーマを返す。相対リンクや
+/// ページ内リンクの場合は`None`を返す。
+///
+/// # 注記
+/// ブラウザと同じく先頭の制御文字・空白と、途中のタブ・改行を無視して
+/// 判定する。
+///
+fn link_scheme(dest: &str) -> Option<String> {
+    let normalized: String = dest
+        .trim_start_matches(|ch: char| ch <= ' ')
+        .chars()
+        .filter(|ch| !matches!(ch, '\t' | '\n' | '\r'))
+        .collect();
+    let (scheme, _) = normalized.split_once(':')?;
+    let mut chars = scheme.chars();
+    let valid = chars.next().is_some_and(|ch| ch.is_ascii_alphabetic())
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '.' | '-'));
+    valid.then(|| scheme.to_ascii_lowercase())
+}
+
+///
+/// 静的サイトへの出力を許可するスキーマか否かの判定
+///
+/// # 注記
+/// `javascript:`や`data:`等のスクリプト実行に繋がるスキーマを排除するため
+/// 許可リスト方式とする。
+///
+fn is_allowed_scheme(scheme: &str) -> bool {
+    matches!(scheme, "http" | "https" | "mailto")
+}
+
+///
+/// パーセントエンコードされた文字列の復号
+///
+/// # 戻り値
+/// 復号した文字列を返す。UTF-8として不正な場合は元の文字列を返す。
+///
+fn decode_percent(value: &str) -> String {
+    if !value.contains('%') {
+        return value.to_string();
+    }
+
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut pos = 0;
+    while pos < bytes.len() {
+        if bytes[pos] == b'%' && pos + 2 < bytes.len() {
+            let byte = std::str::from_utf8(&bytes[pos + 1..pos + 3])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if let Some(byte) = byte {
+                decoded.push(byte);
+                pos += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[pos]);
+        pos += 1;
+    }
+
+    String::from_utf8(decoded).unwrap_or_else(|_| value.to_string())
+}
+
+///
+/// URLパスのエンコード
+///
+/// # 引数
+/// * `path` - `/`区切りの未エンコードパス
+///
+/// # 戻り値
+/// 各セグメントをパーセントエンコードしたパスを返す。
+///
+pub(crate) fn encode_url_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~' | b'/') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+///
+/// サイト内相対URLの生成
+///
+/// # 引数
+/// * `depth` - リンク元ページの階層数
+/// * `target` - サイトルートからのリンク先パス(未エンコード)
+///
+pub(crate) fn relative_href(depth: usize, target: &str) -> String {
+    format!("{}{}", "../".repeat(depth), encode_url_path(target))
+}
+
+///
+/// HTML 特殊文字のエスケープ
+///
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+///
+/// 連続する空白の圧縮
+///
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+///
+/// MIME種別からコードブロックの言語指定を推測する
+///
+fn language_from_mime(mime: &str) -> Option<&'static str> {
+    let lang = match mime {
+        "text/plain" => "text",
+        "text/markdown" | "text/x-markdown" => "markdown",
+        "application/json" => "json",
+        "text/yaml" | "application/x-yaml" => "yaml",
+        "text/x-rust" | "text/rust" => "rust",
+        "text/javascript" | "application/javascript" => "javascript",
+        "text/typescript" => "typescript",
+        "text/x-shellscript" => "bash",
+        "text/x-python" => "python",
+        "text/x-go" => "go",
+        "text/x-csharp" => "csharp",
+        "text/x-sql" => "sql",
+        _ => return None,
+    };
+    Some(lang)
+}
+
+///
+/// CSVの解析
+///
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut in_quote = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if in_quote {
+            if ch == '"' {
+                if chars.peek() == Some(&'"') {
+                    cell.push('"');
+                    chars.next();
+                } else {
+                    in_quote = false;
+                }
+            } else {
+                cell.push(ch);
+            }
+            continue;
+        }
+
+        match ch {
+            '"' => in_quote = true,
+            ',' => row.push(std::mem::take(&mut cell)),
+            '\r' | '\n' => {
+                if ch == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => cell.push(ch),
+        }
+    }
+
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_site() -> SiteMap {
+        let mut site = SiteMap::new("/");
+        for path in ["/", "/docs", "/docs/a", "/docs/a/deep", "/docs/b"] {
+            site.add_page(path);
+        }
+        site.add_asset(
+            Some("/docs"),
+            SiteAsset {
+                id: AssetId::new(),
+                file_name: "diagram.png".to_string(),
+                mime: "image/png".to_string(),
+                blob_path: PathBuf::from("/nonexistent"),
+            },
+        );
+        site
+    }
+
+    #[test]
+    fn special_macros_expand_to_markdown_links() {
+        let expanded = expand_special_macros(
+            "see [[/docs/a]] and [[b|Bee]] ![[asset:/docs:diagram.png]] [[]]",
+        );
+
+        assert_eq!(
+            expanded,
+            "see [a](/docs/a) and [Bee](b) ![diagram.png](asset:/docs:diagram.png) [[]]"
+        );
+    }
+
+    #[test]
+    fn render_page_rewrites_links_relative_to_page() {
+        let site = build_site();
+        let rendered = render_page(
+            &site,
+            "/docs",
+            "# Docs\n\n[[a]] [[/missing]] ![[asset:diagram.png]]\n\n## Part One\n\n<b>raw</b>\n\n`[[a]]`\n",
+        );
+
+        assert_eq!(rendered.title, "Docs");
+        assert!(rendered.body_html.contains("href=\"../docs/a/index.html\""));
+        assert!(rendered.body_html.contains("src=\"../_assets/"));
+        assert!(rendered.body_html.contains("<h2 id=\"part-one\">"));
+        assert!(rendered.body_html.contains("&lt;b&gt;raw&lt;/b&gt;"));
+        assert!(rendered.body_html.contains("<code>[[a]]</code>"));
+        assert!(!rendered.body_html.contains("/missing"));
+        assert_eq!(rendered.issues, vec!["page not found: /missing".to_string()]);
+        assert_eq!(rendered.used_assets.len(), 1);
+    }
+
+    #[test]
+    fn render_page_drops_unsafe_link_schemes() {
+        let site = build_site();
+        let rendered = render_page(
+            &site,
+            "/docs",
+            "[x](javascript:alert(1)) [y](JavaScript:alert(2)) [z](data:text/html,hi) \
+             ![i](vbscript:run) [ok](https://example.com/) [m](mailto:a@example.com) [f](#top)\n",
+        );
+
+        assert!(!rendered.body_html.contains("href=\"javascript:"));
+        assert!(!rendered.body_html.to_lowercase().contains("javascript:"));
+        assert!(!rendered.body_html.contains("data:"));
+        assert!(!rendered.body_html.contains("vbscript:"));
+        assert!(rendered.body_html.starts_with("<p>x y z i "));
+        assert!(rendered.body_html.contains("href=\"https://example.com/\""));
+        assert!(rendered.body_html.contains("href=\"mailto:a@example.com\""));
+        assert!(rendered.body_html.contains("href=\"#top\""));
+        assert_eq!(rendered.issues.len(), 4);
+    }
+
+    #[test]
+    fn render_macros_expand_children_and_toc() {
+        let site = build_site();
+        let rendered = render_page(
+            &site,
+            "/docs",
+            "{{toc}}\n\n## First\n\n### Second\n\n{{children}}\n\n{{children:recursive}}\n",
+        );
+
+        assert!(rendered.body_html.contains("<a href=\"#first\">First</a>"));
+        assert!(rendered.body_html.contains("<a href=\"#second\">Second</a>"));
+        assert_eq!(rendered.body_html.matches("href=\"../docs/a/index.html\"").count(), 2);
+        assert_eq!(
+            rendered.body_html.matches("href=\"../docs/a/deep/index.html\"").count(),
+            1
+        );
+        assert!(rendered.issues.is_empty());
+    }
+
+    #[test]
+    fn slugify_heading_matches_frontend_rules() {
+        assert_eq!(slugify_heading("Hello  World!"), "hello-world");
+        assert_eq!(slugify_heading("概要 と まとめ"), "概要-と-まとめ");
+        assert_eq!(slugify_heading("   "), "section");
+    }
+}
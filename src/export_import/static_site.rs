@@ -0,0 +1,651 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 静的 HTML サイトの出力処理
+//!
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use super::static_render::{
+    ASSET_DIR_NAME, RenderedPage, SiteAsset, SiteMap, escape_html,
+    extract_page_name, relative_href, render_page,
+};
+use crate::database::DatabaseManager;
+
+/// サイト共通ファイルの出力先ディレクトリ名
+const SITE_DIR_NAME: &str = "_site";
+
+/// スタイルシート
+const STYLE_CSS: &str = r#"body {
+  margin: 0;
+  font-family: sans-serif;
+  line-height: 1.6;
+  color: #222;
+}
+header, footer {
+  padding: 0.5em 1em;
+  background: #f4f4f4;
+}
+header {
+  display: flex;
+  gap: 1em;
+  align-items: center;
+  border-bottom: 1px solid #ddd;
+}
+header form {
+  margin-left: auto;
+}
+footer {
+  border-top: 1px solid #ddd;
+  font-size: 0.85em;
+  color: #666;
+}
+nav.breadcrumbs, main, section.attachments {
+  max-width: 60em;
+  margin: 0 auto;
+  padding: 0 1em;
+}
+nav.breadcrumbs {
+  padding-top: 0.5em;
+  font-size: 0.9em;
+}
+pre {
+  overflow-x: auto;
+  padding: 0.5em;
+  background: #f6f8fa;
+}
+table {
+  border-collapse: collapse;
+}
+th, td {
+  border: 1px solid #ccc;
+  padding: 0.2em 0.5em;
+}
+img {
+  max-width: 100%;
+}
+"#;
+
+/// 検索ページのスクリプト
+const SEARCH_JS: &str = r#"(function () {
+  var index = window.LUWIKI_SEARCH_INDEX || [];
+  var params = new URLSearchParams(window.location.search);
+  var query = (params.get("q") || "").trim();
+  var input = document.getElementById("search-query");
+  var list = document.getElementById("search-results");
+  input.value = query;
+  if (!query) {
+    return;
+  }
+  var terms = query.toLowerCase().split(/\s+/);
+  var hits = index.filter(function (entry) {
+    var text = (entry.title + " " + entry.path + " " + entry.text).toLowerCase();
+    return terms.every(function (term) { return text.indexOf(term) >= 0; });
+  });
+  if (hits.length === 0) {
+    var empty = document.createElement("li");
+    empty.textContent = "no results";
+    list.appendChild(empty);
+    return;
+  }
+  hits.forEach(function (entry) {
+    var item = document.createElement("li");
+    var link = document.createElement("a");
+    link.href = "../" + entry.url;
+    link.textContent = entry.title;
+    item.appendChild(link);
+    item.appendChild(document.createTextNode(" " + entry.path));
+    list.appendChild(item);
+  });
+})();
+"#;
+
+///
+/// 静的サイト出力要求
+///
+#[derive(Clone, Debug)]
+pub(crate) struct StaticSiteRequest {
+    /// エクスポート起点パス
+    pub(crate) root: String,
+
+    /// 出力先ディレクトリ
+    pub(crate) output_dir: PathBuf,
+
+    /// リハーサルモード
+    pub(crate) dry_run: bool,
+
+    /// 未解決参照をエラーとして扱うか否か
+    pub(crate) strict_mode: bool,
+}
+
+///
+/// 静的サイト出力結果
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct StaticSiteResult {
+    /// 出力したページ数
+    pub(crate) page_count: usize,
+
+    /// 出力したアセット数
+    pub(crate) asset_count: usize,
+
+    /// 解決できなかった参照の一覧
+    pub(crate) issues: Vec<String>,
+}
+
+///
+/// 検索索引のエントリ
+///
+#[derive(Serialize)]
+struct SearchIndexEntry<'a> {
+    path: &'a str,
+    title: &'a str,
+    url: String,
+    text: &'a str,
+}
+
+///
+/// 出力するページ
+///
+struct SitePage {
+    path: String,
+    timestamp: DateTime<Local>,
+    rendered: RenderedPage,
+}
+
+///
+/// 静的 HTML サイトの出力
+///
+/// # 引数
+/// * `db` - データベースマネージャ
+/// * `request` - 出力要求
+///
+/// # 戻り値
+/// 出力結果を返す。
+///
+/// # 注記
+/// 起点配下の最新ページを HTML へ変換し、ページ所属アセットと参照された
+/// 共有アセットをコピーする。出力は一時ディレクトリへ作成した後に出力先
+/// へ移動するため、失敗時に中途半端なサイトは残らない。
+///
+pub(crate) fn export_static_site(
+    db: &DatabaseManager,
+    request: StaticSiteRequest,
+) -> Result<StaticSiteResult> {
+    /*
+     * 出力先の確認
+     */
+    check_output_dir(&request.output_dir)?;
+
+    /*
+     * サイト構成の収集
+     */
+    let read_set = db.collect_latest_page_read_set(&request.root)?;
+    if read_set.pages.is_empty() {
+        bail!("no pages to export: {}", request.root);
+    }
+
+    let mut site = SiteMap::new(&request.root);
+    let mut page_paths = BTreeMap::new();
+    for page in &read_set.pages {
+        check_reserved_path(&site, &page.path)?;
+        site.add_page(&page.path);
+        page_paths.insert(page.page_id.clone(), page.path.clone());
+    }
+
+    for asset in read_set.assets {
+        let page_path = match asset.page_id.as_ref() {
+            Some(page_id) => match page_paths.get(page_id) {
+                Some(path) => Some(path.as_str()),
+                None => continue,
+            },
+            None => None,
+        };
+
+        site.add_asset(
+            page_path,
+            SiteAsset {
+                id: asset.asset_id,
+                file_name: asset.file_name,
+                mime: asset.mime,
+                blob_path: asset.blob_path,
+            },
+        );
+    }
+
+    /*
+     * ページのレンダリング
+     */
+    let mut pages = Vec::with_capacity(read_set.pages.len());
+    let mut issues = Vec::new();
+    for page in read_set.pages {
+        let rendered = render_page(&site, &page.path, &page.source);
+        issues.extend(
+            rendered
+                .issues
+                .iter()
+                .map(|issue| format!("{}: {}", page.path, issue)),
+        );
+        pages.push(SitePage {
+            path: page.path,
+            timestamp: page.timestamp,
+            rendered,
+        });
+    }
+
+    if request.strict_mode && !issues.is_empty() {
+        bail!("unresolved references found:\n{}", issues.join("\n"));
+    }
+
+    /*
+     * 出力対象アセットの確定
+     */
+    let mut used_assets = BTreeSet::new();
+    for page in &pages {
+        used_assets.extend(page.rendered.used_assets.iter().cloned());
+    }
+
+    let mut assets: BTreeMap<String, &SiteAsset> = site
+        .all_page_assets()
+        .map(|asset| (asset.output_path(), asset))
+        .collect();
+    for output_path in &used_assets {
+        if let Some(asset) = site.find_shared_asset_by_output_path(output_path) {
+            assets.insert(output_path.clone(), asset);
+        }
+    }
+
+    let result = StaticSiteResult {
+        page_count: pages.len(),
+        asset_count: assets.len(),
+        issues,
+    };
+
+    if request.dry_run {
+        return Ok(result);
+    }
+
+    /*
+     * 一時ディレクトリへの出力と配置
+     */
+    write_output_dir(&request.output_dir, |work_dir| {
+        write_site(work_dir, &site, &pages, &assets)
+    })?;
+
+    Ok(result)
+}
+
+///
+/// 出力先ディレクトリの確認
+///
+/// # 注記
+/// 既存ファイルを上書きしないよう、存在しないか空のディレクトリのみ許可
+/// する。
+///
+pub(super) fn check_output_dir(output_dir: &Path) -> Result<()> {
+    if !output_dir.exists() {
+        return Ok(());
+    }
+
+    if !output_dir.is_dir() {
+        bail!("output path is not a directory: {}", output_dir.display());
+    }
+
+    if fs::read_dir(output_dir)?.next().is_some() {
+        bail!("output directory is not empty: {}", output_dir.display());
+    }
+
+    Ok(())
+}
+
+///
+/// 一時ディレクトリを介した出力先ディレクトリの作成
+///
+/// # 引数
+/// * `output_dir` - 出力先ディレクトリ(存在しないか空であること)
+/// * `write` - 一時ディレクトリへ出力内容を書き出す処理
+///
+/// # 戻り値
+/// 出力に成功した場合は`Ok(())`を返す。
+///
+/// # 注記
+/// 出力先と同じ親ディレクトリに作成した一時ディレクトリへ書き出した後に
+/// 出力先へ移動する。失敗時は一時ディレクトリを削除する。
+///
+pub(super) fn write_output_dir<F>(output_dir: &Path, write: F) -> Result<()>
+where
+    F: FnOnce(&Path) -> Result<()>,
+{
+    let work_dir = build_work_dir_path(output_dir)?;
+    if let Err(err) = write(&work_dir) {
+        let _ = fs::remove_dir_all(&work_dir);
+        return Err(err);
+    }
+
+    let removed = if output_dir.exists() {
+        fs::remove_dir(output_dir)
+    } else {
+        Ok(())
+    };
+    if let Err(err) = removed {
+        let _ = fs::remove_dir_all(&work_dir);
+        return Err(err).with_context(|| {
+            format!("remove output dir failed: {}", output_dir.display())
+        });
+    }
+    if let Err(err) = fs::rename(&work_dir, output_dir) {
+        let _ = fs::remove_dir_all(&work_dir);
+        return Err(err).with_context(|| {
+            format!("move output dir failed: {}", output_dir.display())
+        });
+    }
+
+    Ok(())
+}
+
+///
+/// 予約ディレクトリと衝突するページの確認
+///
+fn check_reserved_path(site: &SiteMap, path: &str) -> Result<()> {
+    let first = site
+        .page_segments(path)
+        .and_then(|segments| segments.into_iter().next());
+
+    match first.as_deref() {
+        Some(ASSET_DIR_NAME) | Some(SITE_DIR_NAME) => Err(anyhow!(
+            "page path conflicts with reserved directory: {}",
+            path
+        )),
+        _ => Ok(()),
+    }
+}
+
+///
+/// 一時出力ディレクトリのパス生成
+///
+fn build_work_dir_path(output_dir: &Path) -> Result<PathBuf> {
+    let name = output_dir
+        .file_name()
+        .ok_or_else(|| anyhow!("invalid output path: {}", output_dir.display()))?
+        .to_string_lossy()
+        .to_string();
+    let parent = match output_dir.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    Ok(parent.join(format!(".{}.{}.tmp", name, std::process::id())))
+}
+
+///
+/// サイト一式の書き出し
+///
+fn write_site(
+    work_dir: &Path,
+    site: &SiteMap,
+    pages: &[SitePage],
+    assets: &BTreeMap<String, &SiteAsset>,
+) -> Result<()> {
+    fs::create_dir_all(work_dir.join(SITE_DIR_NAME)).with_context(|| {
+        format!("create output dir failed: {}", work_dir.display())
+    })?;
+
+    /*
+     * ページの出力
+     */
+    for page in pages {
+        let output_path = site
+            .page_output_path(&page.path)
+            .ok_or_else(|| anyhow!("page out of export root: {}", page.path))?;
+        let html = build_page_html(site, page);
+        write_file(&work_dir.join(&output_path), html.as_bytes())?;
+    }
+
+    if !site.has_page(site.root()) {
+        let html = build_index_html(site, pages);
+        write_file(&work_dir.join("index.html"), html.as_bytes())?;
+    }
+
+    /*
+     * アセットのコピー
+     */
+    for (output_path, asset) in assets {
+        let target = work_dir.join(output_path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&asset.blob_path, &target).with_context(|| {
+            format!("copy asset failed: {}", asset.blob_path.display())
+        })?;
+    }
+
+    /*
+     * 共通ファイルと検索索引の出力
+     */
+    let site_dir = work_dir.join(SITE_DIR_NAME);
+    write_file(&site_dir.join("style.css"), STYLE_CSS.as_bytes())?;
+    write_file(&site_dir.join("search.js"), SEARCH_JS.as_bytes())?;
+    write_file(&site_dir.join("search.html"), build_search_html().as_bytes())?;
+    write_file(
+        &site_dir.join("search_index.js"),
+        build_search_index(site, pages)?.as_bytes(),
+    )?;
+
+    Ok(())
+}
+
+///
+/// ファイルの書き出し(親ディレクトリも作成する)
+///
+fn write_file(path: &Path, data: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, data)
+        .with_context(|| format!("write file failed: {}", path.display()))
+}
+
+///
+/// ページHTMLの生成
+///
+fn build_page_html(site: &SiteMap, page: &SitePage) -> String {
+    let depth = site.page_depth(&page.path);
+
+    /*
+     * パンくずリスト
+     */
+    let mut crumbs = Vec::new();
+    let mut current = String::new();
+    let segments = site.page_segments(&page.path).unwrap_or_default();
+    crumbs.push(build_crumb(site, &page.path, site.root(), &page_label(site.root())));
+    for segment in &segments {
+        current.push('/');
+        current.push_str(segment);
+        let path = if site.root() == "/" {
+            current.clone()
+        } else {
+            format!("{}{}", site.root(), current)
+        };
+        crumbs.push(build_crumb(site, &page.path, &path, segment));
+    }
+
+    /*
+     * 添付ファイル一覧
+     */
+    let attachments: Vec<String> = site
+        .page_assets(&page.path)
+        .into_iter()
+        .map(|asset| {
+            format!(
+                "<li><a href=\"{}\">{}</a></li>",
+                escape_html(&relative_href(depth, &asset.output_path())),
+                escape_html(&asset.file_name)
+            )
+        })
+        .collect();
+    let attachments = if attachments.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "<section class=\"attachments\">\n<h2>Attachments</h2>\n<ul>\n{}\n</ul>\n</section>\n",
+            attachments.join("\n")
+        )
+    };
+
+    let footer = format!(
+        "{} &middot; last updated {}",
+        escape_html(&page.path),
+        page.timestamp.format("%Y-%m-%d %H:%M:%S")
+    );
+
+    build_document(
+        depth,
+        &page.rendered.title,
+        &format!(
+            "<nav class=\"breadcrumbs\">{}</nav>\n<main>\n{}</main>\n{}",
+            crumbs.join(" / "),
+            page.rendered.body_html,
+            attachments
+        ),
+        &footer,
+    )
+}
+
+///
+/// パンくずリストの要素生成
+///
+fn build_crumb(site: &SiteMap, from: &str, path: &str, label: &str) -> String {
+    match site.page_output_path(path).filter(|_| site.has_page(path)) {
+        Some(target) => format!(
+            "<a href=\"{}\">{}</a>",
+            escape_html(&relative_href(site.page_depth(from), &target)),
+            escape_html(label)
+        ),
+        None => escape_html(label),
+    }
+}
+
+///
+/// パンくずリスト等に表示するページ名
+///
+fn page_label(path: &str) -> String {
+    extract_page_name(path)
+}
+
+///
+/// 起点ページが無い場合の目次ページの生成
+///
+fn build_index_html(site: &SiteMap, pages: &[SitePage]) -> String {
+    let items: Vec<String> = pages
+        .iter()
+        .filter_map(|page| {
+            let target = site.page_output_path(&page.path)?;
+            Some(format!(
+                "<li><a href=\"{}\">{}</a> {}</li>",
+                escape_html(&relative_href(0, &target)),
+                escape_html(&page.rendered.title),
+                escape_html(&page.path)
+            ))
+        })
+        .collect();
+
+    build_document(
+        0,
+        site.root(),
+        &format!("<main>\n<h1>Pages</h1>\n<ul>\n{}\n</ul>\n</main>\n", items.join("\n")),
+        &escape_html(site.root()),
+    )
+}
+
+///
+/// 検索ページの生成
+///
+fn build_search_html() -> String {
+    build_document(
+        1,
+        "Search",
+        "<main>\n<h1>Search</h1>\n<ul id=\"search-results\"></ul>\n</main>\n\
+         <script src=\"search_index.js\"></script>\n\
+         <script src=\"search.js\"></script>\n",
+        "",
+    )
+}
+
+///
+/// 検索索引スクリプトの生成
+///
+/// # 注記
+/// file:// で開いた場合にも読み込めるよう、JSON ではなくグローバル変数へ
+/// 代入するスクリプトとして出力する。
+///
+fn build_search_index(site: &SiteMap, pages: &[SitePage]) -> Result<String> {
+    let entries: Vec<SearchIndexEntry> = pages
+        .iter()
+        .filter_map(|page| {
+            Some(SearchIndexEntry {
+                path: &page.path,
+                title: &page.rendered.title,
+                url: relative_href(0, &site.page_output_path(&page.path)?),
+                text: &page.rendered.search_text,
+            })
+        })
+        .collect();
+
+    Ok(format!(
+        "window.LUWIKI_SEARCH_INDEX = {};\n",
+        serde_json::to_string(&entries)?
+    ))
+}
+
+///
+/// HTML文書全体の生成
+///
+/// # 引数
+/// * `depth` - サイトルートからの階層数
+/// * `title` - 文書タイトル
+/// * `body` - 本文部分の HTML
+/// * `footer` - フッタ部分の HTML
+///
+fn build_document(depth: usize, title: &str, body: &str, footer: &str) -> String {
+    let site_href = |name: &str| {
+        escape_html(&relative_href(depth, &format!("{}/{}", SITE_DIR_NAME, name)))
+    };
+
+    format!(
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n\
+         <link rel=\"stylesheet\" href=\"{style}\">\n\
+         </head>\n\
+         <body>\n\
+         <header>\n\
+         <a href=\"{home}\">Home</a>\n\
+         <form action=\"{search}\" method=\"get\">\n\
+         <input id=\"search-query\" type=\"search\" name=\"q\" placeholder=\"Search\">\n\
+         </form>\n\
+         </header>\n\
+         {body}\
+         <footer>{footer}</footer>\n\
+         </body>\n\
+         </html>\n",
+        title = escape_html(title),
+        style = site_href("style.css"),
+        home = escape_html(&relative_href(depth, "index.html")),
+        search = site_href("search.html"),
+        body = body,
+        footer = footer,
+    )
+}
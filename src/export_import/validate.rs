@@ -9,19 +9,29 @@
 //!
 
 use std::collections::{HashMap, HashSet};
+use std::fs;
 
 use anyhow::{Result, anyhow, bail};
+use mime_guess::MimeGuess;
 
-use super::link_plan::{LinkRewritePlan, build_migrate_link_plan};
+use super::link_plan::{
+    LinkRewritePlan,
+    build_migrate_link_plan,
+    build_source_link_plan,
+};
+use super::markdown_tree::MarkdownTree;
 use super::model::{
     ExportBundle,
+    ExportManifest,
     ExportRemovedByMigrate,
     ExportRevisionRename,
     ExportType,
 };
 use super::policy::{ExportImportPolicy, PlacementRule};
 use crate::database::DatabaseManager;
-use crate::database::types::{AssetId, PageId, UserId};
+use crate::database::types::{AssetHash, AssetId, PageId, UserId};
+use crate::markdown_source::front_matter::validate_document_front_matter;
+use crate::rest_api::{validate_asset_file_name, validate_page_path};
 
 ///
 /// import 前検証の warning
@@ -67,7 +77,7 @@ pub(crate) fn validate_import(
      * import 種別と共通整合性の検証
      */
     validate_export_type(policy, &bundle)?;
-    validate_import_target(db, policy)?;
+    validate_import_target(db, policy, &bundle)?;
 
     let validation = policy.validation_policy();
     if validation.check_manifest_counts {
@@ -86,6 +96,11 @@ pub(crate) fn validate_import(
     if validation.check_asset_blob_integrity {
         validate_asset_blob_integrity(&bundle)?;
     }
+    if policy.placement_rule() == PlacementRule::ApplyOntoRestoredDatabase {
+        validate_bundle_id_duplicates(&bundle)?;
+        validate_incremental_users(db, &bundle)?;
+        validate_incremental_references(db, &bundle)?;
+    }
     if validation.check_destination_conflicts {
         validate_destination_conflicts(db, policy, &bundle)?;
     }
@@ -132,18 +147,227 @@ pub(crate) fn validate_import(
 /// # 引数
 /// * `db` - 検証対象 DB
 /// * `policy` - import ポリシー
+/// * `bundle` - 読み込み済み export bundle
 ///
 /// # 戻り値
 /// 条件を満たす場合は `Ok(())` を返す。
 ///
+/// # 注記
+/// 差分アーカイブは、取り込み済みアーカイブのスナップショット以前を起点と
+/// している場合のみ受け付ける。
+///
 fn validate_import_target(
     db: &DatabaseManager,
     policy: &ExportImportPolicy,
+    bundle: &ExportBundle,
 ) -> Result<()> {
-    if policy.placement_rule() == PlacementRule::RestoreIntoEmptyDatabase
-        && !is_database_empty(db)?
-    {
-        bail!("backup import requires empty database");
+    match policy.placement_rule() {
+        PlacementRule::RestoreIntoEmptyDatabase => {
+            if bundle.manifest.is_incremental() {
+                bail!("incremental archive requires restored database");
+            }
+            if !is_database_empty(db)? {
+                bail!("backup import requires empty database");
+            }
+        }
+
+        PlacementRule::ApplyOntoRestoredDatabase => {
+            let Some(since) = bundle.manifest.since else {
+                bail!("archive is not incremental");
+            };
+            let Some((_, head)) = db.latest_imported_export()? else {
+                bail!("incremental archive requires restored database");
+            };
+            if since > head.timestamp() {
+                bail!(
+                    "incremental archive does not follow imported data: since={} imported={}",
+                    since.to_rfc3339(),
+                    head.timestamp().to_rfc3339()
+                );
+            }
+        }
+
+        PlacementRule::RelocateByPrefix => {}
+    }
+
+    Ok(())
+}
+
+///
+/// 連続して取り込むアーカイブ列の連続性を検証
+///
+/// # 引数
+/// * `manifests` - 取り込み順に並べた manifest 群
+///
+/// # 戻り値
+/// 連続している場合は `Ok(())` を返す。
+///
+/// # 注記
+/// 2 件目以降は差分アーカイブであり、かつ直前のアーカイブのスナップショット
+/// 以前を起点としている必要がある。
+///
+pub(crate) fn validate_import_chain(manifests: &[&ExportManifest]) -> Result<()> {
+    for pair in manifests.windows(2) {
+        let (prev, next) = (pair[0], pair[1]);
+        if prev.export_type != ExportType::Backup {
+            bail!("only backup archives can be chained");
+        }
+        let Some(since) = next.since else {
+            bail!("chained archive is not incremental");
+        };
+        if since > prev.timestamp {
+            bail!(
+                "incremental archive does not follow previous archive: since={} previous={}",
+                since.to_rfc3339(),
+                prev.timestamp.to_rfc3339()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// 差分 bundle のユーザ情報を既存データと照合
+///
+/// # 引数
+/// * `db` - 検証対象 DB
+/// * `bundle` - 検証対象 bundle
+///
+/// # 戻り値
+/// 矛盾がない場合は `Ok(())` を返す。
+///
+fn validate_incremental_users(
+    db: &DatabaseManager,
+    bundle: &ExportBundle,
+) -> Result<()> {
+    let mut seen = HashSet::new();
+    for user in &bundle.users {
+        if !seen.insert(user.username.clone()) {
+            bail!("duplicate username in bundle: {}", user.username);
+        }
+        let conflicted = db
+            .get_user_id_by_name(&user.username)?
+            .is_some_and(|user_id| user_id != user.id);
+        if conflicted {
+            bail!("username already exists: {}", user.username);
+        }
+    }
+    Ok(())
+}
+
+///
+/// 差分 bundle の参照整合性を既存データ込みで検証
+///
+/// # 引数
+/// * `db` - 検証対象 DB
+/// * `bundle` - 検証対象 bundle
+///
+/// # 戻り値
+/// 参照整合性が保たれている場合は `Ok(())` を返す。
+///
+/// # 注記
+/// 差分 bundle は変更分のみを含むため、bundle 外の参照先は import 先 DB に
+/// 存在していればよい。
+///
+fn validate_incremental_references(
+    db: &DatabaseManager,
+    bundle: &ExportBundle,
+) -> Result<()> {
+    /*
+     * 参照先 ID 集合の構築
+     */
+    let page_ids: HashSet<PageId> =
+        bundle.pages.iter().map(|page| page.id.clone()).collect();
+    let user_ids: HashSet<UserId> =
+        bundle.users.iter().map(|user| user.id.clone()).collect();
+    let asset_ids: HashSet<AssetId> =
+        bundle.assets.iter().map(|asset| asset.id.clone()).collect();
+    let page_exists = |page_id: &PageId| -> Result<bool> {
+        Ok(page_ids.contains(page_id)
+            || db.get_page_index_by_id(page_id)?.is_some())
+    };
+    let user_exists = |user_id: &UserId| -> Result<bool> {
+        Ok(user_ids.contains(user_id)
+            || db.get_user_name_by_id(user_id)?.is_some())
+    };
+
+    /*
+     * revision と page の参照検証
+     */
+    let mut revisions_by_page: HashMap<PageId, HashSet<u64>> = HashMap::new();
+    for revision in &bundle.revisions {
+        if !page_ids.contains(&revision.page) {
+            bail!("revision references unknown page_id: {}", revision.page);
+        }
+        if !user_exists(&revision.user)? {
+            bail!("revision references unknown user_id: {}", revision.user);
+        }
+        revisions_by_page
+            .entry(revision.page.clone())
+            .or_default()
+            .insert(revision.revision);
+    }
+
+    for page in &bundle.pages {
+        let contains_latest = revisions_by_page
+            .get(&page.id)
+            .is_some_and(|revisions| revisions.contains(&page.latest));
+        if !contains_latest {
+            bail!(
+                "page latest revision is missing: {} revision={}",
+                page.id,
+                page.latest
+            );
+        }
+    }
+
+    /*
+     * asset と asset blob の参照検証
+     */
+    for asset in &bundle.assets {
+        if !page_exists(&asset.page)? {
+            bail!("asset references unknown page_id: {}", asset.page);
+        }
+        if !user_exists(&asset.user)? {
+            bail!("asset references unknown user_id: {}", asset.user);
+        }
+        for revision in &asset.history {
+            if revision.revision == 0
+                || revision.revision >= asset.revision.max(1)
+            {
+                bail!(
+                    "asset history has invalid revision: {} revision={}",
+                    asset.id,
+                    revision.revision
+                );
+            }
+            if !user_exists(&revision.user)? {
+                bail!("asset references unknown user_id: {}", revision.user);
+            }
+        }
+    }
+
+    for blob in &bundle.asset_blobs {
+        if !asset_ids.contains(&blob.asset_id) {
+            bail!("asset blob references unknown asset_id: {}", blob.asset_id);
+        }
+    }
+
+    /*
+     * live 一覧の参照検証
+     */
+    for page in &bundle.live {
+        if !page_exists(&page.id)? {
+            bail!("live entry references unknown page_id: {}", page.id);
+        }
+        for asset in &page.assets {
+            if !asset_ids.contains(&asset.id)
+                && db.get_asset_info_by_id(&asset.id)?.is_none()
+            {
+                bail!("live entry references unknown asset_id: {}", asset.id);
+            }
+        }
     }
 
     Ok(())
@@ -394,6 +618,23 @@ fn validate_reference_integrity(bundle: &ExportBundle) -> Result<()> {
         if !user_ids.contains(&asset.user) {
             bail!("asset references unknown user_id: {}", asset.user);
         }
+
+        let mut revisions = HashSet::new();
+        for revision in &asset.history {
+            if revision.revision == 0
+                || revision.revision >= asset.revision.max(1)
+                || !revisions.insert(revision.revision)
+            {
+                bail!(
+                    "asset history has invalid revision: {} revision={}",
+                    asset.id,
+                    revision.revision
+                );
+            }
+            if !user_ids.contains(&revision.user) {
+                bail!("asset references unknown user_id: {}", revision.user);
+            }
+        }
     }
 
     for blob in &bundle.asset_blobs {
@@ -418,12 +659,10 @@ fn validate_asset_blob_integrity(bundle: &ExportBundle) -> Result<()> {
     /*
      * blob 側の重複とサイズ表の構築
      */
-    let mut blob_sizes = HashMap::new();
+    let mut blobs = HashMap::new();
     for blob in &bundle.asset_blobs {
-        if blob_sizes
-            .insert(blob.asset_id.clone(), blob.data.len() as u64)
-            .is_some()
-        {
+        let key = (blob.asset_id.clone(), blob.revision);
+        if blobs.insert(key, &blob.data).is_some() {
             bail!("duplicate asset blob in bundle: {}", blob.asset_id);
         }
     }
@@ -432,15 +671,64 @@ fn validate_asset_blob_integrity(bundle: &ExportBundle) -> Result<()> {
      * asset メタデータとの突き合わせ
      */
     for asset in &bundle.assets {
-        let actual_size = blob_sizes
-            .get(&asset.id)
+        let data = blobs
+            .get(&(asset.id.clone(), None))
             .ok_or_else(|| anyhow!("asset blob is missing: {}", asset.id))?;
-        if *actual_size != asset.size {
+        verify_asset_blob(&asset.id, data, asset.size, asset.sha256)?;
+
+        for revision in &asset.history {
+            let data = blobs
+                .get(&(asset.id.clone(), Some(revision.revision)))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "asset blob is missing: {} revision={}",
+                        asset.id,
+                        revision.revision
+                    )
+                })?;
+            verify_asset_blob(&asset.id, data, revision.size, revision.sha256)?;
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// アセット実体のサイズとハッシュを検証
+///
+/// # 引数
+/// * `asset_id` - 対象アセットID
+/// * `data` - アセット実体
+/// * `size` - 期待するサイズ
+/// * `sha256` - 期待するハッシュ値
+///
+/// # 戻り値
+/// 一致する場合は `Ok(())` を返す。
+///
+fn verify_asset_blob(
+    asset_id: &AssetId,
+    data: &[u8],
+    size: u64,
+    sha256: Option<AssetHash>,
+) -> Result<()> {
+    let actual_size = data.len() as u64;
+    if actual_size != size {
+        bail!(
+            "asset blob size mismatch: {} expected={} actual={}",
+            asset_id,
+            size,
+            actual_size
+        );
+    }
+
+    if let Some(expected) = sha256 {
+        let actual = AssetHash::from_data(data);
+        if actual != expected {
             bail!(
-                "asset blob size mismatch: {} expected={} actual={}",
-                asset.id,
-                asset.size,
-                actual_size
+                "asset blob hash mismatch: {} expected={} actual={}",
+                asset_id,
+                expected,
+                actual
             );
         }
     }
@@ -793,6 +1081,320 @@ where
     Ok(())
 }
 
+///
+/// Markdown ツリー import のページ反映内容
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum MarkdownPageAction {
+    /// ページを新規作成する
+    Create { source: String },
+
+    /// 既存ページへ新しいリビジョンを追加する
+    Update {
+        page_id: PageId,
+        expected_revision: u64,
+        source: String,
+    },
+
+    /// 既存ページの内容に変更が無い
+    Unchanged { page_id: PageId },
+
+    /// ページソースを持たずアセットのみを反映する
+    AssetsOnly { page_id: PageId },
+}
+
+///
+/// Markdown ツリー import のアセット反映内容
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum MarkdownAssetAction {
+    /// アセットを新規作成する
+    Create,
+
+    /// 既存アセットの内容を置き換える
+    Replace { asset_id: AssetId },
+
+    /// 既存アセットの内容に変更が無い
+    Unchanged,
+}
+
+///
+/// Markdown ツリー import の検証済みアセット
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ValidatedMarkdownAsset {
+    pub(crate) file_name: String,
+    pub(crate) mime: String,
+    pub(crate) data: Vec<u8>,
+    pub(crate) action: MarkdownAssetAction,
+}
+
+///
+/// Markdown ツリー import の検証済みページ
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ValidatedMarkdownPage {
+    pub(crate) path: String,
+    pub(crate) action: MarkdownPageAction,
+    pub(crate) assets: Vec<ValidatedMarkdownAsset>,
+}
+
+///
+/// Markdown ツリー import 前検証の結果
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ValidatedMarkdownImport {
+    pub(crate) pages: Vec<ValidatedMarkdownPage>,
+    pub(crate) warnings: Vec<ValidationWarning>,
+}
+
+///
+/// Markdown ツリー import 前検証の入口
+///
+/// # 引数
+/// * `db` - 検証対象 DB
+/// * `prefix` - 取り込み先のプレフィクス
+/// * `user_name` - 編集者として記録するユーザ名
+/// * `strict_mode` - strict-mode 有効時は true
+/// * `fix_broken_link` - 破損ページリンクを `about:invalid` へ置換する場合は true
+/// * `tree` - 読み込み済み Markdown ツリー
+///
+/// # 戻り値
+/// ページ単位の反映内容と warning 一覧を返す。
+///
+/// # 注記
+/// リンク検証は取り込み先が`/`以外の場合のみ行う(`/`への取り込みでは
+/// ツリー外リンクと絶対リンクがそのまま有効なため)。
+///
+pub(crate) fn validate_markdown_import(
+    db: &DatabaseManager,
+    prefix: &str,
+    user_name: &str,
+    strict_mode: bool,
+    fix_broken_link: bool,
+    tree: MarkdownTree,
+) -> Result<ValidatedMarkdownImport> {
+    if db.get_user_id_by_name(user_name)?.is_none() {
+        bail!("user not found: {}", user_name);
+    }
+
+    let mut warnings: Vec<ValidationWarning> = tree
+        .ignored
+        .iter()
+        .map(|path| ValidationWarning {
+            code: "ignored_file",
+            message: format!("file ignored: {}", path.display()),
+        })
+        .collect();
+
+    let mut pages = Vec::new();
+    for page in tree.pages {
+        let path = rebuild_absolute_path(prefix, &page.rel_path);
+        if let Err(message) = validate_page_path(&path) {
+            bail!("invalid page path: {} ({})", path, message);
+        }
+
+        /*
+         * ページソースの検証とリンク計画
+         */
+        let source = match page.source {
+            Some(source) => {
+                validate_document_front_matter(&source).map_err(|err| {
+                    anyhow!("invalid front matter: {} ({})", path, err)
+                })?;
+                Some(plan_markdown_source_links(
+                    &path,
+                    prefix,
+                    source,
+                    strict_mode,
+                    fix_broken_link,
+                    &mut warnings,
+                )?)
+            }
+            None => None,
+        };
+
+        /*
+         * 取り込み先ページとの照合
+         */
+        let state = db.get_current_page_state_by_path(&path)?;
+        if let Some(state) = state.as_ref() {
+            if state.page_index().is_draft() {
+                bail!("draft page exists at import path: {}", path);
+            }
+            if db.get_page_lock_info(&state.page_id())?.is_some() {
+                bail!("page is locked: {}", path);
+            }
+        }
+
+        let action = match (state, source) {
+            (None, Some(source)) => MarkdownPageAction::Create { source },
+            (None, None) => {
+                bail!("page for asset directory not found: {}", path);
+            }
+            (Some(state), None) => MarkdownPageAction::AssetsOnly {
+                page_id: state.page_id(),
+            },
+            (Some(state), Some(source)) => {
+                let latest = state.latest_source().map(|latest| latest.source());
+                if latest.as_deref() == Some(source.as_str()) {
+                    MarkdownPageAction::Unchanged {
+                        page_id: state.page_id(),
+                    }
+                } else {
+                    MarkdownPageAction::Update {
+                        page_id: state.page_id(),
+                        expected_revision: state.latest_revision().unwrap_or(0),
+                        source,
+                    }
+                }
+            }
+        };
+
+        /*
+         * アセットの検証
+         */
+        let page_id = match &action {
+            MarkdownPageAction::Create { .. } => None,
+            MarkdownPageAction::Update { page_id, .. }
+            | MarkdownPageAction::Unchanged { page_id }
+            | MarkdownPageAction::AssetsOnly { page_id } => Some(page_id),
+        };
+        let mut assets = Vec::new();
+        for asset in page.assets {
+            if let Err(message) = validate_asset_file_name(&asset.file_name) {
+                bail!(
+                    "invalid asset file name: {}/{} ({})",
+                    path,
+                    asset.file_name,
+                    message
+                );
+            }
+
+            let data = fs::read(&asset.file_path).map_err(|err| {
+                anyhow!(
+                    "read asset file failed: {} ({})",
+                    asset.file_path.display(),
+                    err
+                )
+            })?;
+            let action = match page_id {
+                Some(page_id) => {
+                    plan_markdown_asset(db, page_id, &asset.file_name, &data)?
+                }
+                None => MarkdownAssetAction::Create,
+            };
+
+            assets.push(ValidatedMarkdownAsset {
+                mime: MimeGuess::from_path(&asset.file_name)
+                    .first_or_octet_stream()
+                    .essence_str()
+                    .to_string(),
+                file_name: asset.file_name,
+                data,
+                action,
+            });
+        }
+
+        pages.push(ValidatedMarkdownPage {
+            path,
+            action,
+            assets,
+        });
+    }
+
+    Ok(ValidatedMarkdownImport { pages, warnings })
+}
+
+///
+/// Markdown ソースのリンク計画
+///
+/// # 引数
+/// * `path` - 取り込み先のページパス
+/// * `prefix` - 取り込み先のプレフィクス
+/// * `source` - ページソース
+/// * `strict_mode` - strict-mode 有効時は true
+/// * `fix_broken_link` - 破損ページリンクを `about:invalid` へ置換する場合は true
+/// * `warnings` - warning の追加先
+///
+/// # 戻り値
+/// 反映するページソースを返す。
+///
+fn plan_markdown_source_links(
+    path: &str,
+    prefix: &str,
+    source: String,
+    strict_mode: bool,
+    fix_broken_link: bool,
+    warnings: &mut Vec<ValidationWarning>,
+) -> Result<String> {
+    if prefix == "/" {
+        return Ok(source);
+    }
+
+    let plan = build_source_link_plan(path, prefix, &source, fix_broken_link);
+    for issue in &plan.issues {
+        if strict_mode && issue.kind.is_strict_error() {
+            bail!(
+                "migration link issue is not allowed in strict-mode: {} kind={} target={}",
+                path,
+                issue.kind.warning_code(),
+                issue.raw_target
+            );
+        }
+
+        let code = if issue.rewritten {
+            "broken_link_rewritten"
+        } else {
+            issue.kind.warning_code()
+        };
+        warnings.push(ValidationWarning {
+            code,
+            message: format!(
+                "migration link issue detected: {} kind={} target={}",
+                path, code, issue.raw_target
+            ),
+        });
+    }
+
+    Ok(plan.source)
+}
+
+///
+/// Markdown ツリー上のアセットと既存アセットの照合
+///
+/// # 引数
+/// * `db` - 検証対象 DB
+/// * `page_id` - 所属ページID
+/// * `file_name` - ファイル名
+/// * `data` - アセットの内容
+///
+/// # 戻り値
+/// アセットの反映内容を返す。
+///
+fn plan_markdown_asset(
+    db: &DatabaseManager,
+    page_id: &PageId,
+    file_name: &str,
+    data: &[u8],
+) -> Result<MarkdownAssetAction> {
+    let info = match db.get_asset_id_by_page_file(page_id, file_name)? {
+        Some(asset_id) => db
+            .get_asset_info_by_id(&asset_id)?
+            .filter(|info| !info.deleted())
+            .map(|info| (asset_id, info)),
+        None => None,
+    };
+
+    Ok(match info {
+        Some((_, info)) if info.hash() == Some(AssetHash::from_data(data)) => {
+            MarkdownAssetAction::Unchanged
+        }
+        Some((asset_id, _)) => MarkdownAssetAction::Replace { asset_id },
+        None => MarkdownAssetAction::Create,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs;
@@ -1222,11 +1824,15 @@ mod tests {
             file_name: "note.txt".to_string(),
             mime: "text/plain".to_string(),
             size: 5,
+            sha256: None,
+            revision: 1,
             user: user_id,
             timestamp: chrono::Local::now(),
+            history: Vec::new(),
         });
         bundle.asset_blobs.push(ExportAssetBlob {
             asset_id,
+            revision: None,
             data: b"hello".to_vec(),
         });
         bundle.sync_manifest_counts();
@@ -46,9 +46,28 @@ use tantivy::tokenizer::{
 use tantivy::{doc, Index, Score, TantivyDocument, Term};
 
 use crate::database::DatabaseManager;
-use crate::database::types::PageId;
+use crate::database::types::{AssetId, AssetInfo, PageId};
 use crate::markdown_source::front_matter::extract_front_matter;
 
+///
+/// インデックス対象とするテキストアセットのサイズ上限(バイト)
+///
+const ASSET_TEXT_LIMIT_SIZE: u64 = 1024 * 1024;
+
+///
+/// テキストとして扱う`text/*`以外のMIME種別
+///
+const TEXT_LIKE_MIME_TYPES: &[&str] = &[
+    "application/json",
+    "application/xml",
+    "application/yaml",
+    "application/x-yaml",
+    "application/toml",
+    "application/javascript",
+    "application/x-sh",
+    "application/sql",
+];
+
 ///
 /// 全文検索インデックス固有エラー
 ///
@@ -93,6 +112,9 @@ pub(crate) enum FtsSearchTarget {
 
     /// front matter
     FrontMatter,
+
+    /// テキスト形式のアセット
+    Assets,
 }
 
 ///
@@ -157,6 +179,9 @@ impl FtsIndexConfig {
 ///
 /// インデックスに登録するページ単位の文書
 ///
+/// # 注記
+/// アセット文書の場合は`asset_id`が設定され、本文は`asset_text`に格納される。
+///
 #[derive(Clone, Debug)]
 pub(crate) struct FtsDocument {
     page_id: PageId,
@@ -167,6 +192,9 @@ pub(crate) struct FtsDocument {
     body: String,
     code: String,
     front_matter: String,
+    asset_id: Option<AssetId>,
+    asset_name: String,
+    asset_text: String,
 }
 
 impl FtsDocument {
@@ -208,6 +236,49 @@ impl FtsDocument {
             body,
             code,
             front_matter,
+            asset_id: None,
+            asset_name: String::new(),
+            asset_text: String::new(),
+        }
+    }
+
+    ///
+    /// アセット文書情報の生成
+    ///
+    /// # 引数
+    /// * `page_id` - 所属ページID
+    /// * `revision` - 所属ページの最新リビジョン番号
+    /// * `deleted` - 所属ページの削除済みフラグ
+    /// * `asset_id` - アセットID
+    /// * `file_name` - アセットのファイル名
+    /// * `text` - アセットの内容
+    ///
+    /// # 戻り値
+    /// 生成した文書情報
+    ///
+    /// # 注記
+    /// アセット文書は常に最新リビジョンとして扱う。
+    ///
+    pub(crate) fn new_asset(
+        page_id: PageId,
+        revision: u64,
+        deleted: bool,
+        asset_id: AssetId,
+        file_name: String,
+        text: String,
+    ) -> Self {
+        Self {
+            page_id,
+            revision,
+            deleted,
+            is_latest: true,
+            headings: String::new(),
+            body: String::new(),
+            code: String::new(),
+            front_matter: String::new(),
+            asset_id: Some(asset_id),
+            asset_name: file_name,
+            asset_text: text,
         }
     }
 }
@@ -222,6 +293,8 @@ pub(crate) struct FtsSearchResult {
     score: Score,
     deleted: bool,
     snippet: String,
+    asset_id: Option<AssetId>,
+    file_name: Option<String>,
 }
 
 impl FtsSearchResult {
@@ -274,6 +347,26 @@ impl FtsSearchResult {
     pub(crate) fn snippet(&self) -> String {
         self.snippet.clone()
     }
+
+    ///
+    /// アセットIDへのアクセサ
+    ///
+    /// # 戻り値
+    /// アセットにヒットした場合はアセットID、ページの場合は`None`
+    ///
+    pub(crate) fn asset_id(&self) -> Option<AssetId> {
+        self.asset_id.clone()
+    }
+
+    ///
+    /// アセットのファイル名へのアクセサ
+    ///
+    /// # 戻り値
+    /// アセットにヒットした場合はファイル名、ページの場合は`None`
+    ///
+    pub(crate) fn file_name(&self) -> Option<String> {
+        self.file_name.clone()
+    }
 }
 
 ///
@@ -582,6 +675,9 @@ struct FtsSchema {
     body: Field,
     code: Field,
     front_matter: Field,
+    asset_id: Field,
+    asset_name: Field,
+    assets: Field,
 }
 
 impl FtsSchema {
@@ -624,7 +720,10 @@ impl FtsSchema {
         let body = builder.add_text_field("body", text_options.clone());
         let code = builder.add_text_field("code", text_options.clone());
         let front_matter =
-            builder.add_text_field("front_matter", text_options);
+            builder.add_text_field("front_matter", text_options.clone());
+        let asset_id = builder.add_text_field("asset_id", STRING | STORED);
+        let asset_name = builder.add_text_field("asset_name", STORED);
+        let assets = builder.add_text_field("assets", text_options);
 
         let schema = builder.build();
 
@@ -641,6 +740,9 @@ impl FtsSchema {
             body,
             code,
             front_matter,
+            asset_id,
+            asset_name,
+            assets,
         })
     }
 
@@ -671,6 +773,9 @@ impl FtsSchema {
         let code = required_schema_field(&schema, "code")?;
         let front_matter =
             required_schema_field(&schema, "front_matter")?;
+        let asset_id = required_schema_field(&schema, "asset_id")?;
+        let asset_name = required_schema_field(&schema, "asset_name")?;
+        let assets = required_schema_field(&schema, "assets")?;
 
         /*
          * スキーマの返却
@@ -685,6 +790,9 @@ impl FtsSchema {
             body,
             code,
             front_matter,
+            asset_id,
+            asset_name,
+            assets,
         })
     }
 }
@@ -792,6 +900,39 @@ impl FtsIndexManager {
         Ok(Self { index, schema })
     }
 
+    ///
+    /// 登録文書をtantivyの文書に変換する
+    ///
+    /// # 引数
+    /// * `item` - 登録文書
+    ///
+    /// # 戻り値
+    /// 変換したtantivyの文書
+    ///
+    fn build_document(&self, item: &FtsDocument) -> TantivyDocument {
+        let mut doc = doc!(
+            self.schema.page_id => item.page_id.to_string(),
+            self.schema.revision => item.revision,
+            self.schema.deleted => item.deleted,
+            self.schema.is_latest => item.is_latest,
+            self.schema.headings => item.headings.clone(),
+            self.schema.body => item.body.clone(),
+            self.schema.code => item.code.clone(),
+            self.schema.front_matter => item.front_matter.clone(),
+        );
+
+        /*
+         * アセット文書の場合はアセット情報を付与する
+         */
+        if let Some(asset_id) = &item.asset_id {
+            doc.add_text(self.schema.asset_id, asset_id.to_string());
+            doc.add_text(self.schema.asset_name, &item.asset_name);
+            doc.add_text(self.schema.assets, &item.asset_text);
+        }
+
+        doc
+    }
+
     ///
     /// インデックスを再構築する
     ///
@@ -814,17 +955,7 @@ impl FtsIndexManager {
          * 文書の追加
          */
         for item in docs {
-            let doc = doc!(
-                self.schema.page_id => item.page_id.to_string(),
-                self.schema.revision => item.revision,
-                self.schema.deleted => item.deleted,
-                self.schema.is_latest => item.is_latest,
-                self.schema.headings => item.headings.clone(),
-                self.schema.body => item.body.clone(),
-                self.schema.code => item.code.clone(),
-                self.schema.front_matter => item.front_matter.clone(),
-            );
-            writer.add_document(doc)?;
+            writer.add_document(self.build_document(item))?;
         }
 
         /*
@@ -864,6 +995,7 @@ impl FtsIndexManager {
             FtsSearchTarget::Body => self.schema.body,
             FtsSearchTarget::Code => self.schema.code,
             FtsSearchTarget::FrontMatter => self.schema.front_matter,
+            FtsSearchTarget::Assets => self.schema.assets,
         };
 
         /*
@@ -937,6 +1069,22 @@ impl FtsIndexManager {
                 .and_then(|value| value.as_bool())
                 .unwrap_or(false);
 
+            let asset_id = match doc
+                .get_first(self.schema.asset_id)
+                .and_then(|value| value.as_str())
+            {
+                Some(asset_id) => Some(
+                    AssetId::from_string(asset_id)
+                        .map_err(|err| anyhow!("invalid asset_id: {}", err))?,
+                ),
+                None => None,
+            };
+
+            let file_name = doc
+                .get_first(self.schema.asset_name)
+                .and_then(|value| value.as_str())
+                .map(|value| value.to_string());
+
             let snippet = snippet_generator.snippet_from_doc(&doc).to_html();
 
             /*
@@ -948,6 +1096,8 @@ impl FtsIndexManager {
                 score,
                 deleted,
                 snippet,
+                asset_id,
+                file_name,
             });
         }
 
@@ -991,17 +1141,7 @@ impl FtsIndexManager {
          * 文書の追加
          */
         for item in docs {
-            let doc = doc!(
-                self.schema.page_id => item.page_id.to_string(),
-                self.schema.revision => item.revision,
-                self.schema.deleted => item.deleted,
-                self.schema.is_latest => item.is_latest,
-                self.schema.headings => item.headings.clone(),
-                self.schema.body => item.body.clone(),
-                self.schema.code => item.code.clone(),
-                self.schema.front_matter => item.front_matter.clone(),
-            );
-            writer.add_document(doc)?;
+            writer.add_document(self.build_document(item))?;
         }
 
         /*
@@ -1044,17 +1184,7 @@ impl FtsIndexManager {
             writer.delete_term(term);
 
             for item in docs {
-                let doc = doc!(
-                    self.schema.page_id => item.page_id.to_string(),
-                    self.schema.revision => item.revision,
-                    self.schema.deleted => item.deleted,
-                    self.schema.is_latest => item.is_latest,
-                    self.schema.headings => item.headings.clone(),
-                    self.schema.body => item.body.clone(),
-                    self.schema.code => item.code.clone(),
-                    self.schema.front_matter => item.front_matter.clone(),
-                );
-                writer.add_document(doc)?;
+                writer.add_document(self.build_document(item))?;
             }
         }
 
@@ -1129,6 +1259,53 @@ impl FtsIndexManager {
         Ok(())
     }
 
+    ///
+    /// 特定アセットの文書を置き換える
+    ///
+    /// # 概要
+    /// アセットIDで既存文書を削除し、
+    /// 新しい文書群を登録する。
+    ///
+    /// # 引数
+    /// * `asset_id` - 対象アセットID
+    /// * `docs` - 登録する文書一覧(空の場合は削除のみ)
+    ///
+    /// # 戻り値
+    /// 処理に成功した場合は`Ok(())`
+    ///
+    fn replace_asset_docs(
+        &self,
+        asset_id: &AssetId,
+        docs: &[FtsDocument],
+    ) -> Result<()> {
+        /*
+         * ライタの準備
+         */
+        let mut writer = self.index.writer::<TantivyDocument>(50_000_000)?;
+
+        /*
+         * 既存文書の削除
+         */
+        let term = Term::from_field_text(
+            self.schema.asset_id,
+            &asset_id.to_string(),
+        );
+        writer.delete_term(term);
+
+        /*
+         * 文書の追加
+         */
+        for item in docs {
+            writer.add_document(self.build_document(item))?;
+        }
+
+        /*
+         * コミット
+         */
+        writer.commit()?;
+        Ok(())
+    }
+
     ///
     /// セグメントの強制マージを実行する
     ///
@@ -1275,6 +1452,38 @@ pub(crate) fn delete_page_index(
     index_manager.delete_page_docs(page_id)
 }
 
+///
+/// アセット単位でインデックスを更新する
+///
+/// # 概要
+/// アセットの現在の状態から文書を構築し直す。削除済みのアセットや
+/// テキスト形式でないアセットの場合は文書の削除のみを行う。
+///
+/// # 引数
+/// * `config` - インデックス設定
+/// * `manager` - データベースマネージャ
+/// * `asset_id` - 対象アセットID
+///
+/// # 戻り値
+/// 処理に成功した場合は`Ok(())`
+///
+pub(crate) fn reindex_asset(
+    config: &FtsIndexConfig,
+    manager: &DatabaseManager,
+    asset_id: &AssetId,
+) -> Result<()> {
+    /*
+     * 登録文書の構築
+     */
+    let docs = build_documents_for_asset(manager, asset_id)?;
+
+    /*
+     * インデックスの更新
+     */
+    let index_manager = FtsIndexManager::open(config)?;
+    index_manager.replace_asset_docs(asset_id, &docs)
+}
+
 ///
 /// 指定ページのインデックスを更新する
 ///
@@ -1493,9 +1702,170 @@ fn build_documents_for_page(
         )?);
     }
 
+    docs.extend(build_asset_documents_for_page(
+        manager,
+        page_id,
+        latest,
+        deleted,
+    )?);
+
     Ok(docs)
 }
 
+///
+/// アセット単位の登録文書を構築する
+///
+/// # 概要
+/// アセット情報と所属ページのインデックスを参照し、
+/// 検索文書を生成する。
+///
+/// # 引数
+/// * `manager` - データベースマネージャ
+/// * `asset_id` - 対象アセットID
+///
+/// # 戻り値
+/// 登録対象の文書一覧(対象外の場合は空)
+///
+fn build_documents_for_asset(
+    manager: &DatabaseManager,
+    asset_id: &AssetId,
+) -> Result<Vec<FtsDocument>> {
+    /*
+     * アセット情報と所属ページの取得
+     */
+    let info = match manager.get_asset_info_by_id(asset_id)? {
+        Some(info) => info,
+        None => return Ok(Vec::new()),
+    };
+
+    let page_id = match info.page_id() {
+        Some(page_id) => page_id,
+        None => return Ok(Vec::new()),
+    };
+
+    let index = match manager.get_page_index_by_id(&page_id)? {
+        Some(index) if !index.is_draft() => index,
+        _ => return Ok(Vec::new()),
+    };
+
+    /*
+     * 文書の構築
+     */
+    let doc = build_asset_document(
+        manager,
+        &page_id,
+        index.latest(),
+        index.deleted(),
+        &info,
+    )?;
+
+    Ok(doc.into_iter().collect())
+}
+
+///
+/// MIME種別がテキスト形式かを判定する
+///
+/// # 引数
+/// * `mime` - MIME種別
+///
+/// # 戻り値
+/// テキスト形式の場合は`true`
+///
+pub(crate) fn is_text_asset_mime(mime: &str) -> bool {
+    let essence = mime
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    essence.starts_with("text/")
+        || essence.ends_with("+json")
+        || essence.ends_with("+xml")
+        || TEXT_LIKE_MIME_TYPES.contains(&essence.as_str())
+}
+
+///
+/// ページ所属アセットの登録文書を構築する
+///
+/// # 概要
+/// 削除済みでないテキスト形式のアセットごとに、所属ページに
+/// 紐付いた文書を生成する。
+///
+/// # 引数
+/// * `manager` - データベースマネージャ
+/// * `page_id` - 所属ページID
+/// * `revision` - 所属ページの最新リビジョン番号
+/// * `deleted` - 所属ページの削除済みフラグ
+///
+/// # 戻り値
+/// 登録対象の文書一覧
+///
+pub(crate) fn build_asset_documents_for_page(
+    manager: &DatabaseManager,
+    page_id: &PageId,
+    revision: u64,
+    deleted: bool,
+) -> Result<Vec<FtsDocument>> {
+    let mut docs = Vec::new();
+    for info in manager.list_page_assets(page_id)? {
+        docs.extend(build_asset_document(
+            manager,
+            page_id,
+            revision,
+            deleted,
+            &info,
+        )?);
+    }
+
+    Ok(docs)
+}
+
+///
+/// アセット単体の登録文書を構築する
+///
+/// # 引数
+/// * `manager` - データベースマネージャ
+/// * `page_id` - 所属ページID
+/// * `revision` - 所属ページの最新リビジョン番号
+/// * `deleted` - 所属ページの削除済みフラグ
+/// * `info` - アセット情報
+///
+/// # 戻り値
+/// 登録対象の場合は文書、対象外の場合は`None`
+///
+/// # 注記
+/// 削除済み、テキスト形式でない、またはサイズ上限を超えるアセットは
+/// 登録対象外とする。UTF-8として不正なバイト列は置換文字に変換する。
+///
+fn build_asset_document(
+    manager: &DatabaseManager,
+    page_id: &PageId,
+    revision: u64,
+    deleted: bool,
+    info: &AssetInfo,
+) -> Result<Option<FtsDocument>> {
+    if info.deleted()
+        || !is_text_asset_mime(&info.mime())
+        || info.size() > ASSET_TEXT_LIMIT_SIZE
+    {
+        return Ok(None);
+    }
+
+    let data = manager
+        .read_asset_data(&info.id())
+        .with_context(|| format!("read asset {}", info.id()))?;
+
+    Ok(Some(FtsDocument::new_asset(
+        page_id.clone(),
+        revision,
+        deleted,
+        info.id(),
+        info.file_name(),
+        String::from_utf8_lossy(&data).into_owned(),
+    )))
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs;
@@ -1509,8 +1879,10 @@ mod tests {
         build_document_from_source,
         delete_pages_index,
         extract_markdown_sections,
+        is_text_asset_mime,
         normalize_front_matter_text,
         rebuild_index,
+        reindex_asset,
         search_index,
         update_pages_index,
     };
@@ -1775,6 +2147,112 @@ mod tests {
         assert_eq!(second_results[0].page_id(), second_id);
     }
 
+    #[test]
+    fn text_assets_are_indexed_as_separate_documents() {
+        let dir = tempdir().expect("tempdir failed");
+        let db_path = dir.path().join("database.redb");
+        let asset_path = dir.path().join("assets");
+        let config = FtsIndexConfig::new(dir.path().join("fts-index"));
+        let manager = DatabaseManager::open(&db_path, &asset_path)
+            .expect("database open failed");
+        manager
+            .add_user("user", "pass", None)
+            .expect("add user failed");
+
+        let page_id = manager
+            .create_page(
+                "/fts-asset/page",
+                "user",
+                "# page\nbodytext\n".to_string(),
+            )
+            .expect("page create failed");
+        let text_id = manager
+            .create_asset(
+                &page_id,
+                "settings.toml",
+                "text/plain; charset=utf-8",
+                "user",
+                b"listen = \"assetmarker\"\n",
+            )
+            .expect("text asset create failed");
+        manager
+            .create_asset(
+                &page_id,
+                "blob.bin",
+                "application/octet-stream",
+                "user",
+                b"assetmarker",
+            )
+            .expect("binary asset create failed");
+
+        update_pages_index(&config, &manager, &[page_id.clone()], false)
+            .expect("page update failed");
+
+        /*
+         * テキスト形式のアセットのみが assets 対象でヒットする
+         */
+        let results = search_index(
+            &config,
+            FtsSearchTarget::Assets,
+            "assetmarker",
+            false,
+            false,
+        )
+        .expect("asset search failed");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].page_id(), page_id);
+        assert_eq!(results[0].asset_id(), Some(text_id.clone()));
+        assert_eq!(results[0].file_name().as_deref(), Some("settings.toml"));
+        assert!(results[0].snippet().contains("assetmarker"));
+
+        let results = search_index(
+            &config,
+            FtsSearchTarget::Body,
+            "bodytext",
+            false,
+            false,
+        )
+        .expect("body search failed");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].asset_id(), None);
+
+        /*
+         * 削除したアセットは文書から除去される
+         */
+        manager.delete_asset(&text_id).expect("asset delete failed");
+        reindex_asset(&config, &manager, &text_id)
+            .expect("asset reindex failed");
+        let results = search_index(
+            &config,
+            FtsSearchTarget::Assets,
+            "assetmarker",
+            false,
+            false,
+        )
+        .expect("asset search after delete failed");
+        assert!(results.is_empty());
+
+        let results = search_index(
+            &config,
+            FtsSearchTarget::Body,
+            "bodytext",
+            false,
+            false,
+        )
+        .expect("body search after delete failed");
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn is_text_asset_mime_accepts_text_like_types() {
+        assert!(is_text_asset_mime("text/markdown"));
+        assert!(is_text_asset_mime("text/csv; charset=utf-8"));
+        assert!(is_text_asset_mime("application/json"));
+        assert!(is_text_asset_mime("application/ld+json"));
+        assert!(!is_text_asset_mime("image/png"));
+        assert!(!is_text_asset_mime("application/octet-stream"));
+    }
+
     #[test]
     fn delete_pages_index_removes_multiple_pages_in_one_call() {
         let dir = tempdir().expect("tempdir failed");
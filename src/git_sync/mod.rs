@@ -0,0 +1,1429 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! ページツリーと Git リポジトリの双方向同期
+//!
+//! ページの各リビジョンを1コミットとしてローカルの bare リポジトリへ
+//! 複製(push)し、リポジトリ側で追加されたコミットを新しいリビジョンと
+//! して取り込む(pull)。リポジトリ側でのページファイルの削除はページの
+//! 削除として取り込む。ページファイルの配置は Markdown ツリー形式の
+//! export と同じ規則に従う。
+//!
+
+mod state;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Local};
+use git2::{
+    Commit,
+    Delta,
+    Index,
+    IndexEntry,
+    IndexTime,
+    Oid,
+    Repository,
+    RepositoryInitOptions,
+    Signature,
+    Sort,
+    Time,
+};
+
+use crate::database::types::{Id, PageId, PageIndex, PageSource};
+use crate::database::{AppendPageRequest, DatabaseManager};
+use crate::export_import::{
+    check_exportable_rel_path,
+    page_file_rel_path,
+    page_rel_path_from_file,
+    relative_page_path,
+};
+use crate::markdown_source::front_matter::validate_document_front_matter;
+use crate::rest_api::validate_page_path;
+pub(crate) use state::{PageSyncState, SyncState};
+
+/// 同期対象のブランチ名
+pub(crate) const SYNC_BRANCH: &str = "main";
+
+/// 同期対象のブランチの参照名
+const SYNC_BRANCH_REF: &str = "refs/heads/main";
+
+/// コミットの作成者メールアドレスに用いるドメイン
+const AUTHOR_EMAIL_DOMAIN: &str = "luwiki.invalid";
+
+/// 削除等の反映コミットの作成者名
+const SYNC_AUTHOR_NAME: &str = "luwiki";
+
+/// 編集者が削除済みの場合の作成者名
+const UNKNOWN_AUTHOR_NAME: &str = "unknown";
+
+///
+/// サーバでの定期同期設定
+///
+#[derive(Clone, Debug)]
+pub(crate) struct GitSyncConfig {
+    /// 同期先リポジトリ
+    pub(crate) repository: PathBuf,
+
+    /// 未登録のコミット作成者に代わって記録するユーザ名
+    pub(crate) fallback_user: Option<String>,
+
+    /// 同期間隔
+    pub(crate) interval: Duration,
+}
+
+///
+/// push 要求
+///
+#[derive(Clone, Debug)]
+pub(crate) struct GitPushRequest {
+    /// 同期先リポジトリ
+    pub(crate) repository: PathBuf,
+
+    /// リハーサルモード
+    pub(crate) dry_run: bool,
+}
+
+///
+/// push 結果
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct GitPushResult {
+    /// 複製したリビジョン数
+    pub(crate) revisions: usize,
+
+    /// 作成したコミット数
+    pub(crate) commits: usize,
+
+    /// 同期後のブランチ先頭
+    pub(crate) head: Option<String>,
+
+    /// 警告
+    pub(crate) warnings: Vec<String>,
+}
+
+///
+/// pull 要求
+///
+#[derive(Clone, Debug)]
+pub(crate) struct GitPullRequest {
+    /// 同期元リポジトリ
+    pub(crate) repository: PathBuf,
+
+    /// 未登録のコミット作成者に代わって記録するユーザ名
+    pub(crate) fallback_user: Option<String>,
+
+    /// 競合したページも取り込むか否か
+    pub(crate) force: bool,
+
+    /// リハーサルモード
+    pub(crate) dry_run: bool,
+}
+
+///
+/// pull 結果
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct GitPullResult {
+    /// 取り込んだコミット数
+    pub(crate) commits: usize,
+
+    /// 取り込み対象のページファイル変更数
+    pub(crate) changes: usize,
+
+    /// 新規作成したページ
+    pub(crate) created_pages: Vec<PageId>,
+
+    /// 新しいリビジョンを追加したページ
+    pub(crate) updated_pages: Vec<PageId>,
+
+    /// 削除したページ
+    pub(crate) deleted_pages: Vec<PageId>,
+
+    /// 内容が同一のため読み飛ばした変更数
+    pub(crate) unchanged: usize,
+
+    /// 競合したページパス(`force`指定時のみ)
+    pub(crate) conflicts: Vec<String>,
+
+    /// 警告
+    pub(crate) warnings: Vec<String>,
+}
+
+///
+/// リポジトリ上のファイル変更
+///
+#[derive(Clone, Debug)]
+enum FileChange {
+    /// ファイルの書き込み
+    Write { file: String, content: String },
+
+    /// ファイルの削除
+    Remove { file: String },
+}
+
+///
+/// push で作成するコミット
+///
+#[derive(Clone, Debug)]
+struct PushCommit {
+    /// 作成者名
+    author: String,
+
+    /// 作成日時
+    timestamp: DateTime<Local>,
+
+    /// コミットメッセージ
+    message: String,
+
+    /// ファイル変更
+    changes: Vec<FileChange>,
+}
+
+///
+/// pull で取り込むページ変更
+///
+#[derive(Clone, Debug)]
+struct PullChange {
+    /// 変更を含むコミット
+    commit: Oid,
+
+    /// コミット作成者名
+    author: String,
+
+    /// ページパス
+    path: String,
+
+    /// 変更後のページソース(ファイルが削除された場合は`None`)
+    source: Option<String>,
+}
+
+///
+/// pull での反映先
+///
+#[derive(Clone, Debug)]
+enum PullTarget {
+    /// 既存ページ
+    Existing(PageId),
+
+    /// 新規ページ
+    New,
+}
+
+///
+/// ページリビジョンをリポジトリへ複製する
+///
+/// # 引数
+/// * `db` - データベースマネージャ
+/// * `request` - push 要求
+///
+/// # 戻り値
+/// push 結果を返す。
+///
+/// # 注記
+/// 前回の同期以降に追加・amend されたリビジョンを作成日時順に1コミット
+/// ずつ作成し、最後にページの削除と復帰をまとめた1コミットを作成する。
+/// リポジトリ側に未取り込みのコミットが存在する場合はエラーとする。
+///
+pub(crate) fn push(
+    db: &DatabaseManager,
+    request: &GitPushRequest,
+) -> Result<GitPushResult> {
+    let repo = open_repository(&request.repository, !request.dry_run)?;
+    let mut state = SyncState::load(&request.repository)?;
+
+    /*
+     * リポジトリ側の未取り込みコミットの確認
+     */
+    let head = match repo.as_ref() {
+        Some(repo) => branch_head(repo)?,
+        None => None,
+    };
+    if head.map(|oid| oid.to_string()) != state.head {
+        bail!(
+            "repository has commits not pulled yet: run `git-sync pull` first"
+        );
+    }
+
+    /*
+     * コミット内容の計画
+     */
+    let mut result = GitPushResult {
+        head: state.head.clone(),
+        ..Default::default()
+    };
+    let (commits, pages) = plan_push(db, &state, &mut result)?;
+    result.commits = commits.len();
+
+    let repo = match repo {
+        Some(repo) if !request.dry_run && !commits.is_empty() => repo,
+        _ => return Ok(result),
+    };
+
+    /*
+     * コミットの作成と同期状態の保存
+     */
+    let head = write_commits(&repo, head, &commits)?;
+    state.head = Some(head.to_string());
+    state.pages = pages;
+    state.save(&request.repository)?;
+    result.head = state.head;
+
+    Ok(result)
+}
+
+///
+/// リポジトリ側のコミットをページリビジョンとして取り込む
+///
+/// # 引数
+/// * `db` - データベースマネージャ
+/// * `request` - pull 要求
+///
+/// # 戻り値
+/// pull 結果を返す。
+///
+/// # 注記
+/// 前回の同期以降にブランチへ追加されたコミットを first-parent で辿り、
+/// 変更されたページファイルを順に新しいリビジョンとして追加する。
+/// Wiki 側でも同期後に更新されたページ(最新リビジョンのインスタンスIDが
+/// 同期時と異なるページ)は競合として扱い、`force`指定が無い限り何も
+/// 反映せずにエラーとする。ページファイルの削除はページの削除として
+/// 反映する。
+///
+pub(crate) fn pull(
+    db: &DatabaseManager,
+    request: &GitPullRequest,
+) -> Result<GitPullResult> {
+    let repo = open_repository(&request.repository, false)?.ok_or_else(|| {
+        anyhow!("repository not found: {}", request.repository.display())
+    })?;
+    let mut state = SyncState::load(&request.repository)?;
+    let mut result = GitPullResult::default();
+
+    /*
+     * 取り込み範囲の決定
+     */
+    let Some(head) = branch_head(&repo)? else {
+        return Ok(result);
+    };
+    let base = state
+        .head
+        .as_deref()
+        .map(Oid::from_str)
+        .transpose()
+        .context("invalid sync state head")?;
+    if base == Some(head) {
+        return Ok(result);
+    }
+    if let Some(base) = base {
+        let contained = repo.graph_descendant_of(head, base)?;
+        if !contained {
+            bail!(
+                "branch {} does not contain the last synchronized commit {}",
+                SYNC_BRANCH,
+                base
+            );
+        }
+    }
+
+    /*
+     * 変更内容の収集と検証
+     */
+    let (commits, changes) =
+        collect_pull_changes(&repo, base, head, &mut result)?;
+    result.commits = commits;
+    result.changes = changes.len();
+
+    let mut targets = plan_pull(db, &state, &changes, &mut result)?;
+    let users = resolve_pull_users(db, &changes, request)?;
+
+    if !result.conflicts.is_empty() && !request.force {
+        bail!(
+            "pages changed in both wiki and repository: {}",
+            result.conflicts.join(", ")
+        );
+    }
+
+    if request.dry_run {
+        return Ok(result);
+    }
+
+    /*
+     * ページへの反映
+     */
+    let mut created = HashMap::new();
+    for change in &changes {
+        let page_id = match &targets[&change.path] {
+            PullTarget::Existing(page_id) => Some(page_id.clone()),
+            PullTarget::New => created.get(&change.path).cloned(),
+        };
+        let user_name = &users[&change.author];
+
+        /*
+         * ページファイルの削除の反映
+         */
+        let Some(source) = change.source.as_ref() else {
+            match page_id {
+                Some(page_id) => {
+                    remove_pulled_page(db, &mut state, &page_id, &change.path)?;
+                    result.updated_pages.retain(|id| *id != page_id);
+                    result.created_pages.retain(|id| *id != page_id);
+                    result.deleted_pages.push(page_id);
+                    state.save(&request.repository)?;
+                }
+                None => result.unchanged += 1,
+            }
+
+            /*
+             * 同じパスへの以降の書き込みは新規ページとして扱う
+             */
+            created.remove(&change.path);
+            targets.insert(change.path.clone(), PullTarget::New);
+            continue;
+        };
+
+        let page_id = match page_id {
+            Some(page_id) => {
+                let index = db
+                    .get_page_index_by_id(&page_id)?
+                    .ok_or_else(|| anyhow!("page not found: {}", change.path))?;
+                let latest = db.get_page_source(&page_id, index.latest())?;
+                if latest.is_some_and(|latest| latest.source() == *source) {
+                    result.unchanged += 1;
+                } else {
+                    db.append_page_by_id(&AppendPageRequest::new(
+                        page_id.clone(),
+                        user_name.clone(),
+                        source.clone(),
+                        index.latest(),
+                        false,
+                    ))?;
+                    if !result.updated_pages.contains(&page_id)
+                        && !result.created_pages.contains(&page_id)
+                    {
+                        result.updated_pages.push(page_id.clone());
+                    }
+                }
+                page_id
+            }
+            None => {
+                let page_id =
+                    db.create_page(&change.path, user_name, source.clone())?;
+                created.insert(change.path.clone(), page_id.clone());
+                result.created_pages.push(page_id.clone());
+                page_id
+            }
+        };
+
+        /*
+         * 反映済みの変更を再実行時に読み飛ばせるよう都度記録する
+         */
+        record_pulled_page(db, &mut state, &page_id, &change.path)?;
+        state.save(&request.repository)?;
+    }
+
+    state.head = Some(head.to_string());
+    state.save(&request.repository)?;
+
+    Ok(result)
+}
+
+///
+/// リポジトリのオープン
+///
+/// # 引数
+/// * `path` - リポジトリディレクトリ
+/// * `create` - 存在しない場合に作成するか否か
+///
+/// # 戻り値
+/// オープンしたリポジトリを返す。存在せず作成もしない場合は`None`を返す。
+///
+fn open_repository(path: &Path, create: bool) -> Result<Option<Repository>> {
+    if path.exists() {
+        let repo = Repository::open_bare(path).with_context(|| {
+            format!("open git repository failed: {}", path.display())
+        })?;
+        return Ok(Some(repo));
+    }
+
+    if !create {
+        return Ok(None);
+    }
+
+    let mut options = RepositoryInitOptions::new();
+    options.bare(true).initial_head(SYNC_BRANCH);
+    let repo = Repository::init_opts(path, &options).with_context(|| {
+        format!("create git repository failed: {}", path.display())
+    })?;
+    Ok(Some(repo))
+}
+
+///
+/// 同期対象ブランチの先頭コミットの取得
+///
+fn branch_head(repo: &Repository) -> Result<Option<Oid>> {
+    match repo.find_reference(SYNC_BRANCH_REF) {
+        Ok(reference) => Ok(Some(reference.peel_to_commit()?.id())),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+///
+/// ページパスからリポジトリ上のファイルパスへの変換
+///
+/// # 戻り値
+/// ファイルとして表現できないパス(予約されたパス要素を含む)の場合は
+/// `None`を返す。
+///
+fn page_file_path(path: &str) -> Option<String> {
+    let rel_path = relative_page_path("/", path)?;
+    check_exportable_rel_path(&rel_path).ok()?;
+    Some(page_file_rel_path(&rel_path))
+}
+
+///
+/// リポジトリ上のファイルパスからページパスへの変換
+///
+fn page_path_from_file(file: &str) -> Option<String> {
+    page_rel_path_from_file(file).map(|rel_path| format!("/{}", rel_path))
+}
+
+///
+/// push 内容の計画
+///
+/// # 引数
+/// * `db` - データベースマネージャ
+/// * `state` - 現在の同期状態
+/// * `result` - 複製リビジョン数と警告の記録先
+///
+/// # 戻り値
+/// 作成するコミットの一覧と、push 後のページ単位の同期状態を返す。
+///
+fn plan_push(
+    db: &DatabaseManager,
+    state: &SyncState,
+    result: &mut GitPushResult,
+) -> Result<(Vec<PushCommit>, BTreeMap<PageId, PageSyncState>)> {
+    let mut pages = state.pages.clone();
+    let indexes: BTreeMap<PageId, PageIndex> = db
+        .list_page_index_entries()?
+        .into_iter()
+        .map(|entry| (entry.page_id(), entry.index()))
+        .filter(|(_, index)| !index.is_draft())
+        .collect();
+
+    /*
+     * 未同期リビジョンの収集
+     */
+    let mut revisions = Vec::new();
+    for (page_id, index) in &indexes {
+        let known = pages.get(page_id);
+        for source in collect_pending_revisions(db, page_id, index, known)? {
+            revisions.push((page_id.clone(), source));
+        }
+    }
+    revisions.sort_by(|(a_id, a), (b_id, b)| {
+        a.timestamp()
+            .cmp(&b.timestamp())
+            .then_with(|| a_id.cmp(b_id))
+            .then_with(|| a.revision().cmp(&b.revision()))
+    });
+
+    /*
+     * リビジョンごとのコミット
+     */
+    let mut user_names = HashMap::new();
+    let mut commits = Vec::new();
+    for (page_id, source) in revisions {
+        let known = pages.get(&page_id);
+        let path = source
+            .rename()
+            .to()
+            .or_else(|| known.map(|page| page.path.clone()))
+            .unwrap_or_else(|| indexes[&page_id].path());
+        let Some(file) = page_file_path(&path) else {
+            result.warnings.push(format!("page can not be mirrored: {}", path));
+            continue;
+        };
+
+        let mut changes = Vec::new();
+        let old_file = known
+            .filter(|page| !page.removed && page.path != path)
+            .and_then(|page| page_file_path(&page.path));
+        if let Some(old_file) = old_file {
+            changes.push(FileChange::Remove { file: old_file });
+        }
+        changes.push(FileChange::Write {
+            file,
+            content: source.source(),
+        });
+
+        let subject = match known {
+            None => format!("Create {}", path),
+            Some(page) if page.path != path => {
+                format!("Move {} to {}", page.path, path)
+            }
+            Some(_) => format!("Update {}", path),
+        };
+        commits.push(PushCommit {
+            author: user_name(db, &mut user_names, &source)?,
+            timestamp: source.timestamp(),
+            message: format!(
+                "{}\n\nLuwiki-Page-Id: {}\nLuwiki-Revision: {}\n",
+                subject,
+                page_id,
+                source.revision()
+            ),
+            changes,
+        });
+
+        pages.insert(
+            page_id,
+            PageSyncState {
+                path,
+                revision: source.revision(),
+                instance_id: source.instance_id(),
+                removed: false,
+            },
+        );
+        result.revisions += 1;
+    }
+
+    /*
+     * 削除・復帰の反映
+     */
+    let changes = reconcile_pages(db, &indexes, &mut pages)?;
+    if !changes.is_empty() {
+        commits.push(PushCommit {
+            author: SYNC_AUTHOR_NAME.to_string(),
+            timestamp: Local::now(),
+            message: "Sync deleted and restored pages\n".to_string(),
+            changes,
+        });
+    }
+
+    Ok((commits, pages))
+}
+
+///
+/// 未同期リビジョンの収集
+///
+/// # 引数
+/// * `db` - データベースマネージャ
+/// * `page_id` - ページID
+/// * `index` - ページインデックス
+/// * `known` - ページの同期状態
+///
+/// # 戻り値
+/// 同期済みリビジョンより新しいリビジョンを返す。同期済みリビジョンが
+/// amend されている(インスタンスIDが異なる)場合はそのリビジョンも含める。
+///
+fn collect_pending_revisions(
+    db: &DatabaseManager,
+    page_id: &PageId,
+    index: &PageIndex,
+    known: Option<&PageSyncState>,
+) -> Result<Vec<PageSource>> {
+    let start = match known {
+        Some(known) => match db.get_page_source(page_id, known.revision)? {
+            Some(source) if source.instance_id() == known.instance_id => {
+                known.revision + 1
+            }
+            _ => known.revision.max(index.earliest()),
+        },
+        None => index.earliest(),
+    };
+
+    let mut sources = Vec::new();
+    for revision in start..=index.latest() {
+        if let Some(source) = db.get_page_source(page_id, revision)? {
+            sources.push(source);
+        }
+    }
+
+    Ok(sources)
+}
+
+///
+/// 削除・復帰・リビジョンを伴わない移動の反映
+///
+/// # 引数
+/// * `db` - データベースマネージャ
+/// * `indexes` - ページインデックス(ドラフトを除く)
+/// * `pages` - ページ単位の同期状態(更新する)
+///
+/// # 戻り値
+/// リポジトリへのファイル変更を返す。
+///
+fn reconcile_pages(
+    db: &DatabaseManager,
+    indexes: &BTreeMap<PageId, PageIndex>,
+    pages: &mut BTreeMap<PageId, PageSyncState>,
+) -> Result<Vec<FileChange>> {
+    let mut changes = Vec::new();
+
+    for (page_id, page) in pages.iter_mut() {
+        let current = indexes
+            .get(page_id)
+            .and_then(|index| index.current_path().map(|path| (index, path)));
+
+        match current {
+            Some((index, path)) => {
+                if !page.removed && page.path == path {
+                    continue;
+                }
+                let Some(file) = page_file_path(path) else {
+                    continue;
+                };
+                let Some(source) = db.get_page_source(page_id, index.latest())?
+                else {
+                    continue;
+                };
+
+                let old_file = Some(&*page)
+                    .filter(|page| !page.removed)
+                    .and_then(|page| page_file_path(&page.path));
+                if let Some(old_file) = old_file {
+                    changes.push(FileChange::Remove { file: old_file });
+                }
+                changes.push(FileChange::Write {
+                    file,
+                    content: source.source(),
+                });
+
+                *page = PageSyncState {
+                    path: path.to_string(),
+                    revision: source.revision(),
+                    instance_id: source.instance_id(),
+                    removed: false,
+                };
+            }
+            None => {
+                if page.removed {
+                    continue;
+                }
+                if let Some(file) = page_file_path(&page.path) {
+                    changes.push(FileChange::Remove { file });
+                }
+                page.removed = true;
+            }
+        }
+    }
+
+    Ok(changes)
+}
+
+///
+/// リビジョン作成者のユーザ名の取得
+///
+fn user_name(
+    db: &DatabaseManager,
+    cache: &mut HashMap<Id, String>,
+    source: &PageSource,
+) -> Result<String> {
+    let user_id = source.user();
+    if let Some(name) = cache.get(&user_id) {
+        return Ok(name.clone());
+    }
+
+    let name = db
+        .get_user_name_by_id(&user_id)?
+        .unwrap_or_else(|| UNKNOWN_AUTHOR_NAME.to_string());
+    cache.insert(user_id, name.clone());
+    Ok(name)
+}
+
+///
+/// コミットの書き込み
+///
+/// # 引数
+/// * `repo` - リポジトリ
+/// * `head` - 現在のブランチ先頭
+/// * `commits` - 作成するコミット
+///
+/// # 戻り値
+/// 更新後のブランチ先頭を返す。
+///
+fn write_commits(
+    repo: &Repository,
+    head: Option<Oid>,
+    commits: &[PushCommit],
+) -> Result<Oid> {
+    let mut parent: Option<Commit> =
+        head.map(|oid| repo.find_commit(oid)).transpose()?;
+    let mut index = Index::new()?;
+    if let Some(commit) = &parent {
+        index.read_tree(&commit.tree()?)?;
+    }
+
+    for commit in commits {
+        for change in &commit.changes {
+            match change {
+                FileChange::Write { file, content } => {
+                    let blob = repo.blob(content.as_bytes())?;
+                    index.add(&index_entry(file, blob, content.len()))?;
+                }
+                FileChange::Remove { file } => {
+                    index.remove_path(Path::new(file))?;
+                }
+            }
+        }
+
+        let tree = repo.find_tree(index.write_tree_to(repo)?)?;
+        let signature = signature(&commit.author, &commit.timestamp)?;
+        let parents: Vec<&Commit> = parent.iter().collect();
+        let oid = repo.commit(
+            None,
+            &signature,
+            &signature,
+            &commit.message,
+            &tree,
+            &parents,
+        )?;
+        parent = Some(repo.find_commit(oid)?);
+    }
+
+    let head = parent
+        .map(|commit| commit.id())
+        .ok_or_else(|| anyhow!("no commits written"))?;
+    repo.reference(SYNC_BRANCH_REF, head, true, "luwiki git-sync push")?;
+    Ok(head)
+}
+
+///
+/// インデックスエントリの生成
+///
+fn index_entry(file: &str, blob: Oid, size: usize) -> IndexEntry {
+    IndexEntry {
+        ctime: IndexTime::new(0, 0),
+        mtime: IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode: 0o100644,
+        uid: 0,
+        gid: 0,
+        file_size: size as u32,
+        id: blob,
+        flags: file.len().min(0xfff) as u16,
+        flags_extended: 0,
+        path: file.as_bytes().to_vec(),
+    }
+}
+
+///
+/// コミット署名の生成
+///
+/// # 注記
+/// メールアドレスはユーザ名と予約ドメインから生成する。
+///
+fn signature(
+    name: &str,
+    timestamp: &DateTime<Local>,
+) -> Result<Signature<'static>> {
+    let time = Time::new(
+        timestamp.timestamp(),
+        timestamp.offset().local_minus_utc() / 60,
+    );
+    let email = format!("{}@{}", name, AUTHOR_EMAIL_DOMAIN);
+    Ok(Signature::new(name, &email, &time)?)
+}
+
+///
+/// 取り込み対象の変更の収集
+///
+/// # 引数
+/// * `repo` - リポジトリ
+/// * `base` - 前回同期したコミット
+/// * `head` - ブランチ先頭
+/// * `result` - 警告の記録先
+///
+/// # 戻り値
+/// 対象コミット数と、コミット順のページ変更を返す。
+///
+fn collect_pull_changes(
+    repo: &Repository,
+    base: Option<Oid>,
+    head: Oid,
+    result: &mut GitPullResult,
+) -> Result<(usize, Vec<PullChange>)> {
+    let mut walk = repo.revwalk()?;
+    walk.push(head)?;
+    if let Some(base) = base {
+        walk.hide(base)?;
+    }
+    walk.simplify_first_parent()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+
+    let mut commits = 0;
+    let mut changes = Vec::new();
+    for oid in walk {
+        let commit = repo.find_commit(oid?)?;
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let diff = repo.diff_tree_to_tree(
+            parent_tree.as_ref(),
+            Some(&commit.tree()?),
+            None,
+        )?;
+        commits += 1;
+
+        let author = commit.author().name().unwrap_or_default().to_string();
+        for delta in diff.deltas() {
+            let file = delta
+                .new_file()
+                .path()
+                .or_else(|| delta.old_file().path())
+                .and_then(|path| path.to_str())
+                .unwrap_or_default()
+                .to_string();
+
+            match (delta.status(), page_path_from_file(&file)) {
+                (Delta::Added | Delta::Modified, Some(path)) => {
+                    let blob = repo.find_blob(delta.new_file().id())?;
+                    let source = String::from_utf8(blob.content().to_vec())
+                        .map_err(|_| {
+                            anyhow!(
+                                "page file is not UTF-8: {} ({})",
+                                file,
+                                oid_short(commit.id())
+                            )
+                        })?;
+                    changes.push(PullChange {
+                        commit: commit.id(),
+                        author: author.clone(),
+                        path,
+                        source: Some(source),
+                    });
+                }
+                (Delta::Deleted, Some(path)) => changes.push(PullChange {
+                    commit: commit.id(),
+                    author: author.clone(),
+                    path,
+                    source: None,
+                }),
+                _ => result.warnings.push(format!(
+                    "file ignored: {} ({})",
+                    file,
+                    oid_short(commit.id())
+                )),
+            }
+        }
+    }
+
+    Ok((commits, changes))
+}
+
+///
+/// 取り込み先の決定と競合の検出
+///
+/// # 引数
+/// * `db` - データベースマネージャ
+/// * `state` - 現在の同期状態
+/// * `changes` - 取り込むページ変更
+/// * `result` - 競合の記録先
+///
+/// # 戻り値
+/// ページパスごとの反映先を返す。
+///
+fn plan_pull(
+    db: &DatabaseManager,
+    state: &SyncState,
+    changes: &[PullChange],
+    result: &mut GitPullResult,
+) -> Result<HashMap<String, PullTarget>> {
+    let mut targets = HashMap::new();
+
+    for change in changes {
+        if let Err(message) = validate_page_path(&change.path) {
+            bail!("invalid page path: {} ({})", change.path, message);
+        }
+        if let Some(source) = change.source.as_deref() {
+            validate_document_front_matter(source).map_err(|err| {
+                anyhow!(
+                    "invalid front matter: {} ({}): {}",
+                    change.path,
+                    oid_short(change.commit),
+                    err
+                )
+            })?;
+        }
+
+        if targets.contains_key(&change.path) {
+            continue;
+        }
+
+        /*
+         * Wiki 側の現在状態との照合
+         */
+        let current = db.get_current_page_state_by_path(&change.path)?;
+        if let Some(current) = current.as_ref() {
+            if current.page_index().is_draft() {
+                bail!("draft page exists at path: {}", change.path);
+            }
+            if db.get_page_lock_info(&current.page_id())?.is_some() {
+                bail!("page is locked: {}", change.path);
+            }
+        }
+
+        let known = state.find_by_path(&change.path);
+        let (target, conflict) = match (known, current) {
+            (Some((page_id, page)), Some(current))
+                if current.page_id() == *page_id =>
+            {
+                let instance_id = current
+                    .latest_source()
+                    .and_then(|source| source.instance_id());
+                let changed = current.latest_revision() != Some(page.revision)
+                    || instance_id != page.instance_id;
+                (PullTarget::Existing(page_id.clone()), changed)
+            }
+            (_, Some(current)) => {
+                (PullTarget::Existing(current.page_id()), true)
+            }
+            (Some(_), None) => (PullTarget::New, true),
+            (None, None) => (PullTarget::New, false),
+        };
+
+        if conflict {
+            result.conflicts.push(change.path.clone());
+        }
+        targets.insert(change.path.clone(), target);
+    }
+
+    Ok(targets)
+}
+
+///
+/// コミット作成者と記録するユーザの対応付け
+///
+/// # 注記
+/// 作成者名と同名のユーザが登録されている場合はそのユーザとし、それ以外は
+/// 代替ユーザとする。代替ユーザが指定されていない場合はエラーとする。
+///
+fn resolve_pull_users(
+    db: &DatabaseManager,
+    changes: &[PullChange],
+    request: &GitPullRequest,
+) -> Result<HashMap<String, String>> {
+    if let Some(user) = request.fallback_user.as_deref() {
+        let registered = db.get_user_id_by_name(user)?.is_some();
+        if !registered {
+            bail!("user not found: {}", user);
+        }
+    }
+
+    let mut users = HashMap::new();
+    let mut unknown = HashSet::new();
+    for change in changes {
+        if users.contains_key(&change.author) {
+            continue;
+        }
+
+        let registered = !change.author.is_empty()
+            && db.get_user_id_by_name(&change.author)?.is_some();
+        let user = if registered {
+            Some(change.author.clone())
+        } else {
+            request.fallback_user.clone()
+        };
+
+        match user {
+            Some(user) => {
+                users.insert(change.author.clone(), user);
+            }
+            None => {
+                unknown.insert(change.author.clone());
+            }
+        }
+    }
+
+    if !unknown.is_empty() {
+        let mut unknown: Vec<_> = unknown.into_iter().collect();
+        unknown.sort();
+        bail!(
+            "commit authors are not registered users (use --user): {}",
+            unknown.join(", ")
+        );
+    }
+
+    Ok(users)
+}
+
+///
+/// 取り込んだページの同期状態の記録
+///
+/// # 注記
+/// 同じパスを占めていた他のページは、リポジトリ上のファイルを失った
+/// ものとして扱う(次回の push で現在のパスへ書き戻される)。
+///
+fn record_pulled_page(
+    db: &DatabaseManager,
+    state: &mut SyncState,
+    page_id: &PageId,
+    path: &str,
+) -> Result<()> {
+    let index = db
+        .get_page_index_by_id(page_id)?
+        .ok_or_else(|| anyhow!("page not found: {}", path))?;
+    let source = db.get_page_source(page_id, index.latest())?;
+
+    for (other_id, page) in state.pages.iter_mut() {
+        if other_id != page_id && page.path == path {
+            page.removed = true;
+        }
+    }
+
+    state.pages.insert(
+        page_id.clone(),
+        PageSyncState {
+            path: path.to_string(),
+            revision: index.latest(),
+            instance_id: source.and_then(|source| source.instance_id()),
+            removed: false,
+        },
+    );
+    Ok(())
+}
+
+///
+/// リポジトリ側で削除されたページの反映
+///
+/// # 引数
+/// * `db` - データベースマネージャ
+/// * `state` - 同期状態(更新する)
+/// * `page_id` - 削除するページID
+/// * `path` - リポジトリ上でのページパス
+///
+/// # 注記
+/// ページを削除し、同期状態をリポジトリからファイルを削除済みとして
+/// 記録する(次回の push で削除のコミットを作成しない)。
+///
+fn remove_pulled_page(
+    db: &DatabaseManager,
+    state: &mut SyncState,
+    page_id: &PageId,
+    path: &str,
+) -> Result<()> {
+    let index = db
+        .get_page_index_by_id(page_id)?
+        .ok_or_else(|| anyhow!("page not found: {}", path))?;
+    if !index.deleted() {
+        db.delete_page_by_id(page_id)?;
+    }
+
+    let source = db.get_page_source(page_id, index.latest())?;
+    state.pages.insert(
+        page_id.clone(),
+        PageSyncState {
+            path: path.to_string(),
+            revision: index.latest(),
+            instance_id: source.and_then(|source| source.instance_id()),
+            removed: true,
+        },
+    );
+    Ok(())
+}
+
+///
+/// コミットIDの短縮表記
+///
+fn oid_short(oid: Oid) -> String {
+    oid.to_string().chars().take(10).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::{TempDir, tempdir};
+
+    ///
+    /// テスト用のデータベースとリポジトリパスの準備
+    ///
+    fn prepare() -> (TempDir, DatabaseManager, PathBuf) {
+        let dir = tempdir().expect("create temp dir failed");
+        let asset_path = dir.path().join("assets");
+        std::fs::create_dir_all(&asset_path).expect("create assets failed");
+        let manager =
+            DatabaseManager::open(&dir.path().join("db.redb"), &asset_path)
+                .expect("open manager failed");
+        manager.add_user("alice", "pass", None).expect("add user failed");
+        let repository = dir.path().join("git");
+        (dir, manager, repository)
+    }
+
+    ///
+    /// ブランチ先頭のファイル内容の取得
+    ///
+    fn read_file(repository: &Path, file: &str) -> Option<String> {
+        let repo = Repository::open_bare(repository).expect("open failed");
+        let head = branch_head(&repo).expect("head failed")?;
+        let tree = repo.find_commit(head).unwrap().tree().unwrap();
+        let entry = tree.get_path(Path::new(file)).ok()?;
+        let blob = repo.find_blob(entry.id()).unwrap();
+        Some(String::from_utf8(blob.content().to_vec()).unwrap())
+    }
+
+    ///
+    /// リポジトリ側への外部コミットの追加
+    ///
+    fn commit_external(
+        repository: &Path,
+        author: &str,
+        file: &str,
+        content: &str,
+    ) {
+        let repo = Repository::open_bare(repository).expect("open failed");
+        let head = branch_head(&repo).expect("head failed");
+        write_commits(
+            &repo,
+            head,
+            &[PushCommit {
+                author: author.to_string(),
+                timestamp: Local::now(),
+                message: "external edit\n".to_string(),
+                changes: vec![FileChange::Write {
+                    file: file.to_string(),
+                    content: content.to_string(),
+                }],
+            }],
+        )
+        .expect("commit failed");
+    }
+
+    ///
+    /// リポジトリ側でのファイル削除コミットの追加
+    ///
+    fn remove_external(repository: &Path, author: &str, file: &str) {
+        let repo = Repository::open_bare(repository).expect("open failed");
+        let head = branch_head(&repo).expect("head failed");
+        write_commits(
+            &repo,
+            head,
+            &[PushCommit {
+                author: author.to_string(),
+                timestamp: Local::now(),
+                message: "external removal\n".to_string(),
+                changes: vec![FileChange::Remove {
+                    file: file.to_string(),
+                }],
+            }],
+        )
+        .expect("commit failed");
+    }
+
+    fn push_request(repository: &Path) -> GitPushRequest {
+        GitPushRequest {
+            repository: repository.to_path_buf(),
+            dry_run: false,
+        }
+    }
+
+    fn pull_request(repository: &Path, force: bool) -> GitPullRequest {
+        GitPullRequest {
+            repository: repository.to_path_buf(),
+            fallback_user: None,
+            force,
+            dry_run: false,
+        }
+    }
+
+    #[test]
+    fn push_mirrors_each_revision_as_commit() {
+        let (_dir, manager, repository) = prepare();
+        let page_id = manager
+            .create_page("/docs", "alice", "# docs\n".to_string())
+            .expect("create page failed");
+        manager
+            .create_page("/docs/guide", "alice", "# guide\n".to_string())
+            .expect("create page failed");
+        manager
+            .append_page_by_id(&AppendPageRequest::new(
+                page_id.clone(),
+                "alice".to_string(),
+                "# docs v2\n".to_string(),
+                1,
+                false,
+            ))
+            .expect("append page failed");
+
+        let dry_run = push(
+            &manager,
+            &GitPushRequest {
+                repository: repository.clone(),
+                dry_run: true,
+            },
+        )
+        .expect("dry run failed");
+        assert_eq!(dry_run.commits, 3);
+        assert!(!repository.exists());
+
+        let result = push(&manager, &push_request(&repository))
+            .expect("push failed");
+        assert_eq!(result.revisions, 3);
+        assert_eq!(result.commits, 3);
+        assert_eq!(
+            read_file(&repository, "docs.md").as_deref(),
+            Some("# docs v2\n")
+        );
+        assert_eq!(
+            read_file(&repository, "docs/guide.md").as_deref(),
+            Some("# guide\n")
+        );
+
+        let repo = Repository::open_bare(&repository).expect("open failed");
+        let head = branch_head(&repo).unwrap().expect("head missing");
+        let head = repo.find_commit(head).unwrap();
+        assert_eq!(head.author().name(), Some("alice"));
+        assert!(head.message().unwrap().contains(&format!(
+            "Luwiki-Page-Id: {}",
+            page_id
+        )));
+
+        let again = push(&manager, &push_request(&repository))
+            .expect("push failed");
+        assert_eq!(again.commits, 0);
+
+        /*
+         * 削除したページはファイルを削除するコミットとして反映される
+         */
+        manager.delete_page_by_id(&page_id).expect("delete page failed");
+        let deleted = push(&manager, &push_request(&repository))
+            .expect("push failed");
+        assert_eq!(deleted.revisions, 0);
+        assert_eq!(deleted.commits, 1);
+        assert_eq!(read_file(&repository, "docs.md"), None);
+        assert!(read_file(&repository, "docs/guide.md").is_some());
+    }
+
+    #[test]
+    fn pull_appends_repository_commits_as_revisions() {
+        let (_dir, manager, repository) = prepare();
+        let page_id = manager
+            .create_page("/docs", "alice", "# docs\n".to_string())
+            .expect("create page failed");
+        push(&manager, &push_request(&repository)).expect("push failed");
+
+        commit_external(&repository, "alice", "docs.md", "# docs edited\n");
+        commit_external(&repository, "alice", "notes/new.md", "# new\n");
+
+        let err = push(&manager, &push_request(&repository))
+            .expect_err("push must require pull");
+        assert!(err.to_string().contains("git-sync pull"));
+
+        let result = pull(&manager, &pull_request(&repository, false))
+            .expect("pull failed");
+        assert_eq!(result.commits, 2);
+        assert_eq!(result.updated_pages, vec![page_id.clone()]);
+        assert_eq!(result.created_pages.len(), 1);
+
+        let source = manager
+            .get_page_source(&page_id, 2)
+            .expect("get source failed")
+            .expect("revision missing");
+        assert_eq!(source.source(), "# docs edited\n");
+        assert!(
+            manager
+                .get_page_id_by_path("/notes/new")
+                .expect("resolve page failed")
+                .is_some()
+        );
+
+        /*
+         * 取り込んだリビジョンは再度 push されない
+         */
+        let pushed = push(&manager, &push_request(&repository))
+            .expect("push failed");
+        assert_eq!(pushed.commits, 0);
+    }
+
+    #[test]
+    fn pull_deletes_pages_removed_in_repository() {
+        let (_dir, manager, repository) = prepare();
+        manager
+            .create_page("/docs", "alice", "# docs\n".to_string())
+            .expect("create page failed");
+        let guide_id = manager
+            .create_page("/docs/guide", "alice", "# guide\n".to_string())
+            .expect("create page failed");
+        push(&manager, &push_request(&repository)).expect("push failed");
+
+        remove_external(&repository, "alice", "docs/guide.md");
+
+        let result = pull(&manager, &pull_request(&repository, false))
+            .expect("pull failed");
+        assert_eq!(result.commits, 1);
+        assert_eq!(result.deleted_pages, vec![guide_id.clone()]);
+        assert!(result.warnings.is_empty());
+        let index = manager
+            .get_page_index_by_id(&guide_id)
+            .expect("get index failed")
+            .expect("index missing");
+        assert!(index.deleted());
+
+        /*
+         * 取り込んだ削除は再度 push されない
+         */
+        let pushed = push(&manager, &push_request(&repository))
+            .expect("push failed");
+        assert_eq!(pushed.commits, 0);
+        assert!(read_file(&repository, "docs.md").is_some());
+        assert_eq!(read_file(&repository, "docs/guide.md"), None);
+
+        /*
+         * 削除後に同じパスへ追加されたファイルは新規ページとなる
+         */
+        commit_external(&repository, "alice", "docs/guide.md", "# again\n");
+        let result = pull(&manager, &pull_request(&repository, false))
+            .expect("pull failed");
+        assert_eq!(result.created_pages.len(), 1);
+        assert_ne!(result.created_pages[0], guide_id);
+    }
+
+    #[test]
+    fn pull_rejects_pages_changed_on_both_sides() {
+        let (_dir, manager, repository) = prepare();
+        let page_id = manager
+            .create_page("/docs", "alice", "# docs\n".to_string())
+            .expect("create page failed");
+        push(&manager, &push_request(&repository)).expect("push failed");
+
+        manager
+            .append_page_by_id(&AppendPageRequest::new(
+                page_id.clone(),
+                "alice".to_string(),
+                "# wiki edit\n".to_string(),
+                1,
+                false,
+            ))
+            .expect("append page failed");
+        commit_external(&repository, "bob", "docs.md", "# repo edit\n");
+
+        let err = pull(&manager, &pull_request(&repository, true))
+            .expect_err("unknown author must fail");
+        assert!(err.to_string().contains("bob"));
+
+        let mut request = pull_request(&repository, false);
+        request.fallback_user = Some("alice".to_string());
+        let err = pull(&manager, &request).expect_err("conflict must fail");
+        assert!(err.to_string().contains("/docs"));
+
+        request.force = true;
+        let result = pull(&manager, &request).expect("forced pull failed");
+        assert_eq!(result.conflicts, vec!["/docs".to_string()]);
+        let source = manager
+            .get_page_source(&page_id, 3)
+            .expect("get source failed")
+            .expect("revision missing");
+        assert_eq!(source.source(), "# repo edit\n");
+    }
+}
@@ -0,0 +1,121 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! Git 同期状態の永続化
+//!
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::database::types::{Id, PageId};
+
+/// 同期状態ファイル名(Git リポジトリディレクトリ直下に配置する)
+const STATE_FILE_NAME: &str = "luwiki-sync.json";
+
+///
+/// ページ単位の同期状態
+///
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct PageSyncState {
+    /// リポジトリ上でのページパス
+    pub(crate) path: String,
+
+    /// 同期済みのリビジョン番号
+    pub(crate) revision: u64,
+
+    /// 同期済みリビジョンのインスタンスID
+    pub(crate) instance_id: Option<Id>,
+
+    /// リポジトリからファイルを削除済みか否か
+    #[serde(default)]
+    pub(crate) removed: bool,
+}
+
+///
+/// リポジトリ単位の同期状態
+///
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct SyncState {
+    /// 最後に同期したブランチ先頭のコミットID
+    pub(crate) head: Option<String>,
+
+    /// ページ単位の同期状態
+    #[serde(default)]
+    pub(crate) pages: BTreeMap<PageId, PageSyncState>,
+}
+
+impl SyncState {
+    ///
+    /// 同期状態の読み込み
+    ///
+    /// # 引数
+    /// * `repo_dir` - Git リポジトリディレクトリ
+    ///
+    /// # 戻り値
+    /// 読み込んだ同期状態を返す。ファイルが存在しない場合は空の状態を返す。
+    ///
+    pub(crate) fn load(repo_dir: &Path) -> Result<Self> {
+        let path = state_path(repo_dir);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let data = fs::read(&path).with_context(|| {
+            format!("read sync state failed: {}", path.display())
+        })?;
+        serde_json::from_slice(&data)
+            .with_context(|| format!("invalid sync state: {}", path.display()))
+    }
+
+    ///
+    /// 同期状態の保存
+    ///
+    /// # 引数
+    /// * `repo_dir` - Git リポジトリディレクトリ
+    ///
+    /// # 注記
+    /// 一時ファイルへ書き出した後に置き換えるため、保存途中で中断しても
+    /// 以前の状態が残る。
+    ///
+    pub(crate) fn save(&self, repo_dir: &Path) -> Result<()> {
+        let path = state_path(repo_dir);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp_path, &path).with_context(|| {
+            format!("write sync state failed: {}", path.display())
+        })
+    }
+
+    ///
+    /// パスに対応する同期済みページの検索
+    ///
+    /// # 引数
+    /// * `path` - ページパス
+    ///
+    /// # 戻り値
+    /// リポジトリ上に存在するページの場合はページIDと同期状態を返す。
+    ///
+    pub(crate) fn find_by_path(
+        &self,
+        path: &str,
+    ) -> Option<(&PageId, &PageSyncState)> {
+        self.pages
+            .iter()
+            .find(|(_, page)| !page.removed && page.path == path)
+    }
+}
+
+///
+/// 同期状態ファイルのパス
+///
+fn state_path(repo_dir: &Path) -> PathBuf {
+    repo_dir.join(STATE_FILE_NAME)
+}
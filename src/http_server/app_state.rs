@@ -8,11 +8,16 @@
 //! HTTPサーバが共有する状態をまとめたモジュール
 //!
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
+use log::warn;
+
 use crate::audit::AuditSink;
-use crate::cmd_args::FrontendConfig;
+use crate::audit::model::AuditRecord;
+use crate::auth::{LoginFailureTracker, MfaSessionStore};
+use crate::cmd_args::{AuthConfig, FrontendConfig};
+use crate::collab::CollabHub;
 use crate::database::DatabaseManager;
 use crate::fts::FtsIndexConfig;
 
@@ -43,6 +48,18 @@ pub(crate) struct AppState {
 
     /// 監査ログ投入入口
     audit_sink: Option<Arc<RwLock<AuditSink>>>,
+
+    /// 二要素認証済みセッション
+    mfa_sessions: MfaSessionStore,
+
+    /// 認証設定
+    auth_config: AuthConfig,
+
+    /// 接続元アドレス単位のログイン失敗記録
+    login_failures: LoginFailureTracker,
+
+    /// 共同編集セッションの管理(共同編集無効時は`None`)
+    collab_hub: Option<Arc<CollabHub>>,
 }
 
 impl AppState {
@@ -81,9 +98,44 @@ impl AppState {
             wiki_icon,
             asset_limit_size,
             audit_sink,
+            mfa_sessions: MfaSessionStore::new(),
+            auth_config: AuthConfig::default(),
+            login_failures: LoginFailureTracker::new(),
+            collab_hub: None,
         }
     }
 
+    ///
+    /// 認証設定の差し替え
+    ///
+    /// # 引数
+    /// * `auth_config` - 適用する認証設定
+    ///
+    /// # 戻り値
+    /// 認証設定を差し替えたオブジェクトを返す。
+    ///
+    /// # 注記
+    /// `new()` で生成した直後は既定の認証設定が適用されている。
+    ///
+    pub(crate) fn with_auth_config(mut self, auth_config: AuthConfig) -> Self {
+        self.auth_config = auth_config;
+        self
+    }
+
+    ///
+    /// 共同編集セッション管理の設定
+    ///
+    /// # 引数
+    /// * `collab_hub` - 共同編集セッションの管理
+    ///
+    /// # 戻り値
+    /// 共同編集を有効にしたオブジェクトを返す。
+    ///
+    pub(crate) fn with_collab_hub(mut self, collab_hub: Arc<CollabHub>) -> Self {
+        self.collab_hub = Some(collab_hub);
+        self
+    }
+
     ///
     /// データベースマネージャオブジェクトへのアクセサ
     ///
@@ -151,6 +203,16 @@ impl AppState {
         self.asset_limit_size
     }
 
+    ///
+    /// 共同編集セッション管理へのアクセサ
+    ///
+    /// # 戻り値
+    /// 共同編集が有効な場合は`Some(Arc<CollabHub>)`を返す。
+    ///
+    pub(crate) fn collab_hub(&self) -> Option<Arc<CollabHub>> {
+        self.collab_hub.clone()
+    }
+
     ///
     /// 監査ログ投入入口へのアクセサ
     ///
@@ -161,6 +223,77 @@ impl AppState {
         self.audit_sink.clone()
     }
 
+    ///
+    /// 監査ログ出力ディレクトリへのアクセサ
+    ///
+    /// # 戻り値
+    /// 監査ログをローカルファイルへ出力している場合は出力先ディレクトリを
+    /// 返す。
+    ///
+    pub(crate) fn audit_log_dir(&self) -> Option<PathBuf> {
+        let audit_sink = self.audit_sink.as_ref()?;
+        let sink = audit_sink.read().ok()?;
+        sink.output_dir().map(Path::to_path_buf)
+    }
+
+    ///
+    /// 監査レコードの記録
+    ///
+    /// # 引数
+    /// * `record` - 記録する監査レコード
+    ///
+    /// # 注記
+    /// 監査ログ投入入口が無い場合は何もしない。記録の失敗は操作自体を
+    /// 失敗させず、警告ログの出力にとどめる。
+    ///
+    pub(crate) fn record_audit(&self, record: AuditRecord) {
+        let Some(audit_sink) = self.audit_sink.as_ref() else {
+            return;
+        };
+
+        let mut sink = match audit_sink.write() {
+            Ok(sink) => sink,
+            Err(_) => {
+                warn!("audit sink lock failed");
+                return;
+            }
+        };
+
+        if let Err(err) = sink.record(record) {
+            warn!("audit log record failed: {}", err);
+        }
+    }
+
+    ///
+    /// 二要素認証済みセッションへのアクセサ
+    ///
+    /// # 戻り値
+    /// 二要素認証済みセッション管理オブジェクトへの参照を返す。
+    ///
+    pub(crate) fn mfa_sessions(&self) -> &MfaSessionStore {
+        &self.mfa_sessions
+    }
+
+    ///
+    /// 認証設定へのアクセサ
+    ///
+    /// # 戻り値
+    /// 認証設定への参照を返す。
+    ///
+    pub(crate) fn auth_config(&self) -> &AuthConfig {
+        &self.auth_config
+    }
+
+    ///
+    /// 接続元アドレス単位のログイン失敗記録へのアクセサ
+    ///
+    /// # 戻り値
+    /// ログイン失敗管理オブジェクトへの参照を返す。
+    ///
+    pub(crate) fn login_failures(&self) -> &LoginFailureTracker {
+        &self.login_failures
+    }
+
     ///
     /// データベースマネージャオブジェクトへのアクセサ
     ///
@@ -34,18 +34,23 @@ use tokio::sync::oneshot;
 use tokio::signal::windows::{ctrl_close, ctrl_logoff, ctrl_shutdown};
 use tokio::time;
 
-use crate::audit::buffer::AppendAuditBuffer;
-use crate::audit::retention::{
-    AuditRetentionPolicy,
-    build_retention_plan,
-    execute_retention_plan,
-};
+use crate::audit::forward::AuditOutputConfig;
+use crate::audit::retention::AuditRetentionPolicy;
 use crate::audit::rotation::AuditRotationPolicy;
 use crate::audit::sink::AuditSink;
-use crate::audit::writer::{AuditWriter, AuditWriterConfig};
-use crate::cmd_args::FrontendConfig;
-use crate::database::DatabaseManager;
-use crate::fts::FtsIndexConfig;
+use crate::audit::writer::AuditWriterConfig;
+use crate::cmd_args::{AuthConfig, FrontendConfig};
+use crate::collab::{CollabHub, DEFAULT_CHECKPOINT_INTERVAL};
+use crate::database::{AssetGcItem, DatabaseManager};
+use crate::fts::{self, FtsIndexConfig};
+use crate::git_sync::{
+    self,
+    GitPullRequest,
+    GitPullResult,
+    GitPushRequest,
+    GitPushResult,
+    GitSyncConfig,
+};
 use crate::mcp::McpEndpoint;
 use crate::mcp::session_manager::ManagedSessionManager;
 use crate::rest_api;
@@ -66,6 +71,9 @@ pub(crate) struct AuditLogConfig {
 
     /// 監査ログローテーション閾値サイズ
     rotate_size: u64,
+
+    /// 監査ログの出力先設定
+    outputs: AuditOutputConfig,
 }
 
 /// 外部停止通知
@@ -79,6 +87,7 @@ impl AuditLogConfig {
     /// * `output_dir` - 監査ログ出力先ディレクトリ
     /// * `retention` - 監査ログ保持期間
     /// * `rotate_size` - ローテーション閾値サイズ(バイト)
+    /// * `outputs` - 出力先設定(ローカルファイルおよび外部転送先)
     ///
     /// # 戻り値
     /// 生成した監査ログ設定を返す。
@@ -87,11 +96,13 @@ impl AuditLogConfig {
         output_dir: PathBuf,
         retention: ChronoDuration,
         rotate_size: u64,
+        outputs: AuditOutputConfig,
     ) -> Self {
         Self {
             output_dir,
             retention,
             rotate_size,
+            outputs,
         }
     }
 }
@@ -133,11 +144,15 @@ fn payload_too_large_handler<B>(
 /// * `port` - バインド先ポート番号
 /// * `manager` - データベースマネージャ
 /// * `frontend_config` - フロントエンド設定
+/// * `auth_config` - 認証設定
 /// * `fts_config` - 全文検索設定
 /// * `template_root` - テンプレートルート
 /// * `wiki_title` - Wikiタイトル
 /// * `wiki_icon` - Wikiアイコン画像ファイルのパス
 /// * `asset_limit_size` - アセット上限サイズ(バイト)
+/// * `asset_gc_interval` - 不要なアセットファイルの定期回収間隔
+/// * `git_sync_config` - Gitリポジトリへの定期同期設定
+/// * `collab_enabled` - 共同編集を有効にする場合は`true`
 /// * `use_tls` - TLSを使用する場合は`true`
 /// * `cert_path` - 証明書ファイルパス
 /// * `cert_is_explicit` - 証明書パスが明示指定なら`true`
@@ -152,11 +167,15 @@ pub(crate) fn run(
     port: u16,
     manager: DatabaseManager,
     frontend_config: FrontendConfig,
+    auth_config: AuthConfig,
     fts_config: FtsIndexConfig,
     template_root: Option<String>,
     wiki_title: String,
     wiki_icon: Option<PathBuf>,
     asset_limit_size: u64,
+    asset_gc_interval: Option<Duration>,
+    git_sync_config: Option<GitSyncConfig>,
+    collab_enabled: bool,
     use_tls: bool,
     cert_path: PathBuf,
     cert_is_explicit: bool,
@@ -182,16 +201,21 @@ pub(crate) fn run(
     /*
      * サーバインスタンスの生成
      */
-    let audit_sink = if mcp_endpoint.is_some() {
-        let config = audit_config
-            .clone()
-            .ok_or_else(|| anyhow!("audit config missing for MCP"))?;
-        run_audit_retention(&config)?;
-        Some(Arc::new(RwLock::new(build_audit_sink(&config))))
-    } else {
-        None
+    if mcp_endpoint.is_some() && audit_config.is_none() {
+        return Err(anyhow!("audit config missing for MCP"));
+    }
+
+    let audit_sink = match audit_config.as_ref() {
+        Some(config) => {
+            let mut sink = build_audit_sink(config)?;
+            run_audit_retention(&mut sink, config)?;
+            Some(Arc::new(RwLock::new(sink)))
+        }
+        None => None,
     };
-    let state = web::Data::new(Arc::new(RwLock::new(AppState::new(
+    let collab_hub = collab_enabled
+        .then(|| Arc::new(CollabHub::new(DEFAULT_CHECKPOINT_INTERVAL)));
+    let mut app_state = AppState::new(
         manager,
         frontend_config,
         fts_config,
@@ -200,7 +224,11 @@ pub(crate) fn run(
         wiki_icon,
         asset_limit_size,
         audit_sink,
-    ))));
+    ).with_auth_config(auth_config);
+    if let Some(hub) = collab_hub.as_ref() {
+        app_state = app_state.with_collab_hub(hub.clone());
+    }
+    let state = web::Data::new(Arc::new(RwLock::new(app_state)));
     let mcp_session_manager =
         mcp_endpoint.as_ref().map(|_| ManagedSessionManager::new());
 
@@ -230,7 +258,28 @@ pub(crate) fn run(
     /*
      * ロック期限切れ監視タスクの起動
      */
-    rt.spawn(lock_cleanup_task(state));
+    rt.spawn(lock_cleanup_task(state.clone()));
+
+    /*
+     * 共同編集セッションの定期処理タスクの起動
+     */
+    if let Some(hub) = collab_hub {
+        rt.spawn(collab_tick_task(state.clone(), hub));
+    }
+
+    /*
+     * アセット定期回収タスクの起動
+     */
+    if let Some(interval) = asset_gc_interval {
+        rt.spawn(asset_gc_task(state.clone(), interval));
+    }
+
+    /*
+     * Git定期同期タスクの起動
+     */
+    if let Some(config) = git_sync_config {
+        rt.spawn(git_sync_task(state, config));
+    }
 
     /*
      * 外部停止通知待ちタスクの起動
@@ -281,38 +330,37 @@ pub(crate) fn run(
 /// # 戻り値
 /// 設定値で初期化した監査ログ投入入口を返す。
 ///
-fn build_audit_sink(config: &AuditLogConfig) -> AuditSink {
-    let writer = AuditWriter::new(AuditWriterConfig {
-        output_dir: config.output_dir.clone(),
-        rotation_policy: AuditRotationPolicy::new(config.rotate_size),
-    });
-
-    AuditSink::new(AppendAuditBuffer::new(), writer)
+fn build_audit_sink(config: &AuditLogConfig) -> Result<AuditSink> {
+    AuditSink::open(
+        AuditWriterConfig {
+            output_dir: config.output_dir.clone(),
+            rotation_policy: AuditRotationPolicy::new(config.rotate_size),
+        },
+        &config.outputs,
+    )
 }
 
 ///
 /// 起動時の保持期間超過ログ削除を実行する
 ///
 /// # 引数
+/// * `sink` - 監査ログ投入入口
 /// * `config` - 監査ログ設定
 ///
 /// # 戻り値
 /// 保持削除処理に成功した場合は `Ok(())` を返す。
 ///
-fn run_audit_retention(config: &AuditLogConfig) -> Result<()> {
-    /*
-     * 出力先がまだ存在しない場合は何もしない
-     */
-    if !config.output_dir.exists() {
-        return Ok(());
-    }
-
-    /*
-     * 保持削除計画の構築と実行
-     */
+/// # 注記
+/// 削除はハッシュチェーンへアンカーを記録するため writer 経由で行う。
+/// 出力先がまだ存在しない場合や、ローカルファイルへ出力しない場合は
+/// 何もしない。
+///
+fn run_audit_retention(
+    sink: &mut AuditSink,
+    config: &AuditLogConfig,
+) -> Result<()> {
     let policy = AuditRetentionPolicy::new(config.retention);
-    let plan = build_retention_plan(&config.output_dir, &policy, Utc::now())?;
-    let _ = execute_retention_plan(plan);
+    sink.apply_retention(&policy, Utc::now())?;
 
     Ok(())
 }
@@ -456,6 +504,40 @@ fn shutdown_signal_hook(handle: ServerHandle, signal: ShutdownSignal) {
     });
 }
 
+///
+/// 共同編集セッションの定期処理タスク
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `hub` - 共同編集セッションの管理
+///
+/// # 注記
+/// セッションが保持するページロックの延長と、未保存の編集の定期保存を
+/// 行う。
+///
+async fn collab_tick_task(
+    state: web::Data<Arc<RwLock<AppState>>>,
+    hub: Arc<CollabHub>,
+) {
+    let mut interval = time::interval(Duration::from_secs(10));
+
+    loop {
+        interval.tick().await;
+
+        let state = match state.read() {
+            Ok(state) => state,
+            Err(_) => {
+                warn!("collab tick skipped: state lock failed");
+                continue;
+            }
+        };
+
+        if let Err(err) = hub.tick(&state) {
+            warn!("collab tick failed: {:?}", err);
+        }
+    }
+}
+
 ///
 /// ロック期限切れ監視タスク
 ///
@@ -500,6 +582,198 @@ async fn lock_cleanup_task(state: web::Data<Arc<RwLock<AppState>>>) {
     }
 }
 
+///
+/// 不要なアセットファイルの定期回収タスク
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `period` - 回収間隔
+///
+/// # 戻り値
+/// なし
+///
+/// # 注記
+/// 回収対象は不要なファイルのみとし、参照されていないアセットの削除は
+/// 行わない。
+///
+async fn asset_gc_task(
+    state: web::Data<Arc<RwLock<AppState>>>,
+    period: Duration,
+) {
+    /*
+     * 回収間隔の初期化(起動直後の実行は行わない)
+     */
+    let mut interval =
+        time::interval_at(time::Instant::now() + period, period);
+
+    loop {
+        interval.tick().await;
+
+        /*
+         * 不要なファイルの回収
+         */
+        let state = state.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let state = match state.read() {
+                Ok(state) => state,
+                Err(_) => return Err(anyhow!("state lock failed")),
+            };
+            state.db().collect_asset_garbage(true, false)
+        })
+        .await;
+
+        /*
+         * 結果の記録
+         */
+        match result {
+            Ok(Ok(items)) => {
+                let reclaimed: u64 =
+                    items.iter().map(AssetGcItem::reclaimable_size).sum();
+                if reclaimed > 0 {
+                    info!("asset gc reclaimed {} bytes", reclaimed);
+                }
+            }
+            Ok(Err(err)) => warn!("asset gc failed: {}", err),
+            Err(err) => warn!("asset gc failed: {}", err),
+        }
+    }
+}
+
+///
+/// Gitリポジトリへの定期同期タスク
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `config` - 定期同期設定
+///
+/// # 戻り値
+/// なし
+///
+/// # 注記
+/// リポジトリ側のコミットの取り込み(pull)を行った後に、リポジトリへの
+/// 複製(push)を行う。取り込みに失敗した場合(Wiki 側との競合等)は複製も
+/// 行わず、警告を記録して次回に持ち越す。取り込んだページは全文検索
+/// インデックスへ反映する。
+///
+async fn git_sync_task(
+    state: web::Data<Arc<RwLock<AppState>>>,
+    config: GitSyncConfig,
+) {
+    /*
+     * 同期間隔の初期化(起動直後の実行は行わない)
+     */
+    let mut interval = time::interval_at(
+        time::Instant::now() + config.interval,
+        config.interval,
+    );
+
+    loop {
+        interval.tick().await;
+
+        /*
+         * リポジトリとの双方向同期
+         */
+        let state = state.clone();
+        let config = config.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let state = match state.read() {
+                Ok(state) => state,
+                Err(_) => return Err(anyhow!("state lock failed")),
+            };
+            run_git_sync(&state, &config)
+        })
+        .await;
+
+        /*
+         * 結果の記録
+         */
+        match result {
+            Ok(Ok((pulled, pushed))) => {
+                for warning in pulled.warnings.iter().chain(&pushed.warnings) {
+                    warn!("git sync: {}", warning);
+                }
+                if pulled.commits > 0 {
+                    info!(
+                        "git sync pulled {} commits: created={} updated={} \
+                         deleted={}",
+                        pulled.commits,
+                        pulled.created_pages.len(),
+                        pulled.updated_pages.len(),
+                        pulled.deleted_pages.len()
+                    );
+                }
+                if pushed.commits > 0 {
+                    info!(
+                        "git sync pushed {} revisions in {} commits",
+                        pushed.revisions,
+                        pushed.commits
+                    );
+                }
+            }
+            Ok(Err(err)) => warn!("git sync failed: {}", err),
+            Err(err) => warn!("git sync failed: {}", err),
+        }
+    }
+}
+
+///
+/// Gitリポジトリとの1回分の同期
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `config` - 定期同期設定
+///
+/// # 戻り値
+/// pull と push の結果を返す。
+///
+fn run_git_sync(
+    state: &AppState,
+    config: &GitSyncConfig,
+) -> Result<(GitPullResult, GitPushResult)> {
+    /*
+     * リポジトリ側のコミットの取り込み(未作成のリポジトリは読み飛ばす)
+     */
+    let pulled = if config.repository.exists() {
+        let request = GitPullRequest {
+            repository: config.repository.clone(),
+            fallback_user: config.fallback_user.clone(),
+            force: false,
+            dry_run: false,
+        };
+        git_sync::pull(state.db(), &request)?
+    } else {
+        GitPullResult::default()
+    };
+
+    /*
+     * 取り込んだページの全文検索インデックスへの反映
+     */
+    let reindex = pulled
+        .created_pages
+        .iter()
+        .chain(&pulled.updated_pages)
+        .map(|page_id| (page_id, false))
+        .chain(pulled.deleted_pages.iter().map(|page_id| (page_id, true)));
+    for (page_id, deleted) in reindex {
+        if let Err(err) =
+            fts::reindex_page(state.fts_config(), state.db(), page_id, deleted)
+        {
+            warn!("git sync: fts update failed: {}", err);
+        }
+    }
+
+    /*
+     * リポジトリへの複製
+     */
+    let request = GitPushRequest {
+        repository: config.repository.clone(),
+        dry_run: false,
+    };
+    let pushed = git_sync::push(state.db(), &request)?;
+
+    Ok((pulled, pushed))
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs;
@@ -732,4 +1006,98 @@ mod tests {
         std::mem::drop(handle.stop(true));
         tokio::time::sleep(Duration::from_millis(50)).await;
     }
+
+    ///
+    /// 定期同期でリポジトリ側のコミットを取り込んでから複製することを
+    /// 確認する。
+    ///
+    #[test]
+    fn run_git_sync_pulls_repository_commits_before_pushing() {
+        use git2::{Repository, Signature};
+
+        use super::run_git_sync;
+        use crate::git_sync::GitSyncConfig;
+
+        let dir = tempdir().expect("tempdir failed");
+        let db_path = dir.path().join("database.redb");
+        let asset_dir = dir.path().join("assets");
+        let index_dir = dir.path().join("fts");
+        fs::create_dir_all(&asset_dir).expect("create assets dir failed");
+        fs::create_dir_all(&index_dir).expect("create fts dir failed");
+
+        let manager = DatabaseManager::open(&db_path, &asset_dir)
+            .expect("open database failed");
+        manager.add_user("alice", "pass", None).expect("add user failed");
+        manager
+            .create_page("/docs", "alice", "# docs\n".to_string())
+            .expect("create page failed");
+        let state = AppState::new(
+            manager,
+            FrontendConfig::default(),
+            FtsIndexConfig::new(index_dir),
+            None,
+            "LUWIKI".to_string(),
+            None,
+            1024 * 1024,
+            None,
+        );
+        let config = GitSyncConfig {
+            repository: dir.path().join("wiki.git"),
+            fallback_user: None,
+            interval: Duration::from_secs(60),
+        };
+
+        /*
+         * 初回はリポジトリを作成して複製のみ行う
+         */
+        let (pulled, pushed) =
+            run_git_sync(&state, &config).expect("git sync failed");
+        assert_eq!(pulled.commits, 0);
+        assert_eq!(pushed.commits, 1);
+
+        /*
+         * リポジトリ側でページファイルを削除する
+         */
+        let repo =
+            Repository::open_bare(&config.repository).expect("open failed");
+        let head = repo
+            .find_reference("refs/heads/main")
+            .expect("branch missing")
+            .peel_to_commit()
+            .expect("head commit missing");
+        let mut builder = repo
+            .treebuilder(Some(&head.tree().expect("tree missing")))
+            .expect("tree builder failed");
+        builder.remove("docs.md").expect("remove failed");
+        let tree = repo
+            .find_tree(builder.write().expect("write tree failed"))
+            .expect("find tree failed");
+        let signature =
+            Signature::now("alice", "alice@example.invalid").unwrap();
+        repo.commit(
+            Some("refs/heads/main"),
+            &signature,
+            &signature,
+            "Remove docs",
+            &tree,
+            &[&head],
+        )
+        .expect("commit failed");
+
+        /*
+         * 次回の同期で削除が取り込まれ、複製は行われない
+         */
+        let (pulled, pushed) =
+            run_git_sync(&state, &config).expect("git sync failed");
+        assert_eq!(pulled.commits, 1);
+        assert_eq!(pulled.deleted_pages.len(), 1);
+        assert_eq!(pushed.commits, 0);
+        assert!(
+            state
+                .db()
+                .get_page_id_by_path("/docs")
+                .expect("resolve page failed")
+                .is_none()
+        );
+    }
 }
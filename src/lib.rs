@@ -7,16 +7,19 @@
 //! ライブラリ用の公開モジュール定義
 #![allow(dead_code)]
 
+pub mod asset_image;
 pub mod auth;
 pub mod database;
 pub mod export_import;
 pub mod audit;
 pub mod cmd_args;
+pub mod collab;
 pub mod command;
 pub mod http_server;
 pub mod markdown_source;
 pub mod mcp;
 pub mod fts;
+pub mod git_sync;
 pub mod rest_api;
 
 pub use database::page_source_exists_for_test;
@@ -5,12 +5,15 @@
  */
 
 mod cmd_args;
+pub(crate) mod asset_image;
 pub(crate) mod auth;
 pub(crate) mod audit;
+pub(crate) mod collab;
 pub(crate) mod command;
 pub(crate) mod database;
 pub(crate) mod export_import;
 pub(crate) mod fts;
+pub(crate) mod git_sync;
 pub(crate) mod http_server;
 pub(crate) mod markdown_source;
 pub(crate) mod mcp;
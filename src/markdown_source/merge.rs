@@ -0,0 +1,307 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! ページソースの3-wayマージ処理をまとめたモジュール
+//!
+
+use serde::Serialize;
+use similar::{Algorithm, DiffTag, capture_diff_slices};
+
+///
+/// マージで解消できなかった競合箇所
+///
+/// # 注記
+/// 行番号はいずれも1起点で、各ソースにおける競合箇所の先頭行を表す。
+/// 本文は行末の改行を含めたまま保持する。
+///
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub(crate) struct MergeConflict {
+    /// 基準ソース上の開始行
+    base_line: usize,
+
+    /// 基準ソース上の内容
+    base: String,
+
+    /// 最新ソース上の開始行
+    latest_line: usize,
+
+    /// 最新ソース上の内容
+    latest: String,
+
+    /// 保存要求ソース上の開始行
+    submitted_line: usize,
+
+    /// 保存要求ソース上の内容
+    submitted: String,
+}
+
+///
+/// 3-wayマージの結果
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum MergeOutcome {
+    /// 競合なくマージできた(マージ後のソースを保持)
+    Merged(String),
+
+    /// 競合が存在する(競合箇所の一覧を保持)
+    Conflict(Vec<MergeConflict>),
+}
+
+///
+/// ページソースの3-wayマージ
+///
+/// # 引数
+/// * `base` - 保存要求元が編集を開始したリビジョンのソース
+/// * `latest` - 現在の最新リビジョンのソース
+/// * `submitted` - 保存要求のソース
+///
+/// # 戻り値
+/// 双方の変更が重ならない場合はマージ後のソースを、重なる場合は競合箇所の
+/// 一覧を返す。
+///
+/// # 注記
+/// 行単位で比較する。双方が同一の変更を行った箇所は競合として扱わない。
+/// 隣接する行への変更は重なりとみなして競合とする。
+///
+/// 末尾行の改行の有無は行の内容と切り離して扱う。比較は末尾に改行を
+/// 補った状態で行い、マージ後の末尾改行の有無は保存要求側が変更して
+/// いればその状態を、変更していなければ最新ソースの状態を採用する。
+///
+pub(crate) fn merge_three_way(
+    base: &str,
+    latest: &str,
+    submitted: &str,
+) -> MergeOutcome {
+    /*
+     * 末尾改行の正規化
+     */
+    let final_newline = if has_final_newline(submitted)
+        != has_final_newline(base)
+    {
+        has_final_newline(submitted)
+    } else {
+        has_final_newline(latest)
+    };
+
+    let base = with_final_newline(base);
+    let latest = with_final_newline(latest);
+    let submitted = with_final_newline(submitted);
+
+    let base: Vec<&str> = base.split_inclusive('\n').collect();
+    let latest: Vec<&str> = latest.split_inclusive('\n').collect();
+    let submitted: Vec<&str> = submitted.split_inclusive('\n').collect();
+
+    let latest_map = match_lines(&base, &latest);
+    let submitted_map = match_lines(&base, &submitted);
+
+    let mut merged = String::new();
+    let mut conflicts = Vec::new();
+    let (mut i, mut a, mut b) = (0, 0, 0);
+
+    loop {
+        /*
+         * 次の同期点(3者で一致する基準行)の探索
+         */
+        let mut j = i;
+        let sync = loop {
+            if j >= base.len() {
+                break None;
+            }
+
+            match (latest_map[j], submitted_map[j]) {
+                (Some(ja), Some(jb)) if ja >= a && jb >= b => {
+                    break Some((ja, jb));
+                }
+                _ => j += 1,
+            }
+        };
+        let (ja, jb) = sync.unwrap_or((latest.len(), submitted.len()));
+
+        /*
+         * 同期点までの区間の統合
+         */
+        let base_chunk = &base[i..j];
+        let latest_chunk = &latest[a..ja];
+        let submitted_chunk = &submitted[b..jb];
+
+        if latest_chunk == base_chunk {
+            merged.extend(submitted_chunk.iter().copied());
+        } else if submitted_chunk == base_chunk
+            || submitted_chunk == latest_chunk
+        {
+            merged.extend(latest_chunk.iter().copied());
+        } else {
+            conflicts.push(MergeConflict {
+                base_line: i + 1,
+                base: base_chunk.concat(),
+                latest_line: a + 1,
+                latest: latest_chunk.concat(),
+                submitted_line: b + 1,
+                submitted: submitted_chunk.concat(),
+            });
+        }
+
+        /*
+         * 同期点の出力
+         */
+        match sync {
+            Some((ja, jb)) => {
+                merged.push_str(base[j]);
+                i = j + 1;
+                a = ja + 1;
+                b = jb + 1;
+            }
+            None => break,
+        }
+    }
+
+    if conflicts.is_empty() {
+        if !final_newline && merged.ends_with('\n') {
+            merged.pop();
+        }
+        MergeOutcome::Merged(merged)
+    } else {
+        MergeOutcome::Conflict(conflicts)
+    }
+}
+
+///
+/// 末尾行が改行で終わっているか否かの判定
+///
+/// # 注記
+/// 空のソースは改行で終わっているものとして扱う。
+///
+fn has_final_newline(source: &str) -> bool {
+    source.is_empty() || source.ends_with('\n')
+}
+
+///
+/// 末尾行に改行を補ったソースの生成
+///
+fn with_final_newline(source: &str) -> String {
+    if has_final_newline(source) {
+        source.to_string()
+    } else {
+        format!("{}\n", source)
+    }
+}
+
+///
+/// 基準ソースの各行に対応する比較先の行の算出
+///
+/// # 引数
+/// * `base` - 基準ソースの行一覧
+/// * `other` - 比較先ソースの行一覧
+///
+/// # 戻り値
+/// 基準ソースの行ごとに、一致する比較先の行番号(0起点)を返す。
+///
+fn match_lines(base: &[&str], other: &[&str]) -> Vec<Option<usize>> {
+    let mut map = vec![None; base.len()];
+
+    for op in capture_diff_slices(Algorithm::Myers, base, other) {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        if tag != DiffTag::Equal {
+            continue;
+        }
+
+        for (old, new) in old_range.zip(new_range) {
+            map[old] = Some(new);
+        }
+    }
+
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "# title\nintro\n\n## a\nalpha\n\n## b\nbeta\n";
+
+    ///
+    /// 重ならない変更が両方とも反映されることを確認する。
+    ///
+    #[test]
+    fn merge_three_way_combines_disjoint_changes() {
+        let latest = "# title\nintro (edited)\n\n## a\nalpha\n\n## b\nbeta\n";
+        let submitted = "# title\nintro\n\n## a\nalpha\n\n## b\nbeta\ngamma\n";
+
+        assert_eq!(
+            merge_three_way(BASE, latest, submitted),
+            MergeOutcome::Merged(
+                "# title\nintro (edited)\n\n## a\nalpha\n\n## b\nbeta\ngamma\n"
+                    .to_string()
+            )
+        );
+    }
+
+    ///
+    /// 双方が同一の変更を行った場合は競合とならないことを確認する。
+    ///
+    #[test]
+    fn merge_three_way_accepts_identical_changes() {
+        let changed = "# title\nintro\n\n## a\nALPHA\n\n## b\nbeta\n";
+
+        assert_eq!(
+            merge_three_way(BASE, changed, changed),
+            MergeOutcome::Merged(changed.to_string())
+        );
+        assert_eq!(
+            merge_three_way(BASE, BASE, BASE),
+            MergeOutcome::Merged(BASE.to_string())
+        );
+    }
+
+    ///
+    /// 重なる変更が競合箇所として報告されることを確認する。
+    ///
+    #[test]
+    fn merge_three_way_reports_overlapping_changes() {
+        let latest = "# title\nintro\n\n## a\nalpha 1\n\n## b\nbeta\n";
+        let submitted = "# title\nintro\n\n## a\nalpha 2\nmore\n\n## b\nbeta\n";
+
+        assert_eq!(
+            merge_three_way(BASE, latest, submitted),
+            MergeOutcome::Conflict(vec![MergeConflict {
+                base_line: 5,
+                base: "alpha\n".to_string(),
+                latest_line: 5,
+                latest: "alpha 1\n".to_string(),
+                submitted_line: 5,
+                submitted: "alpha 2\nmore\n".to_string(),
+            }])
+        );
+    }
+
+    ///
+    /// 末尾改行の有無の違いだけでは競合とならないことを確認する。
+    ///
+    #[test]
+    fn merge_three_way_ignores_missing_final_newline() {
+        let base = "# title\nintro\n\n## b\nbeta";
+        let latest = "# title\nintro (edited)\n\n## b\nbeta\n";
+        let submitted = "# title\nintro\n\n## b\nbeta\ngamma\n";
+
+        assert_eq!(
+            merge_three_way(base, latest, submitted),
+            MergeOutcome::Merged(
+                "# title\nintro (edited)\n\n## b\nbeta\ngamma\n".to_string()
+            )
+        );
+
+        let latest = "# title\nintro\n\n## b\nbeta\ndelta";
+        let submitted = "# title\nINTRO\n\n## b\nbeta";
+
+        assert_eq!(
+            merge_three_way(base, latest, submitted),
+            MergeOutcome::Merged(
+                "# title\nINTRO\n\n## b\nbeta\ndelta".to_string()
+            )
+        );
+    }
+}
@@ -9,3 +9,4 @@
 //!
 
 pub mod front_matter;
+pub mod merge;
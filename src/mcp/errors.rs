@@ -11,6 +11,7 @@
 use std::fmt;
 
 use serde::Serialize;
+use serde_json::Value as JsonValue;
 
 ///
 /// MCP公開面で利用する論理エラーコード
@@ -52,6 +53,9 @@ pub(crate) struct McpError {
 
     /// エラー説明
     message: String,
+
+    /// 構造化された詳細情報
+    details: Option<JsonValue>,
 }
 
 impl McpError {
@@ -72,9 +76,24 @@ impl McpError {
         Self {
             code,
             message: message.into(),
+            details: None,
         }
     }
 
+    ///
+    /// 構造化された詳細情報を付与する
+    ///
+    /// # 引数
+    /// * `details` - 詳細情報
+    ///
+    /// # 戻り値
+    /// 詳細情報を付与したエラー情報を返す。
+    ///
+    pub(crate) fn with_details(mut self, details: JsonValue) -> Self {
+        self.details = Some(details);
+        self
+    }
+
     ///
     /// ReadOnly 属性による認可拒否エラーを生成する
     ///
@@ -107,6 +126,16 @@ impl McpError {
     pub(crate) fn message(&self) -> &str {
         &self.message
     }
+
+    ///
+    /// 構造化された詳細情報へのアクセサ
+    ///
+    /// # 戻り値
+    /// 詳細情報が付与されている場合は`Some()`でラップして返す。
+    ///
+    pub(crate) fn details(&self) -> Option<&JsonValue> {
+        self.details.as_ref()
+    }
 }
 
 impl McpErrorCode {
@@ -140,6 +169,10 @@ pub(crate) struct McpErrorResponse {
 
     /// エラー説明
     message: String,
+
+    /// 構造化された詳細情報
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<JsonValue>,
 }
 
 impl McpErrorResponse {
@@ -154,7 +187,11 @@ impl McpErrorResponse {
     /// 生成したエラー応答を返す。
     ///
     pub(crate) fn new(code: &'static str, message: String) -> Self {
-        Self { code, message }
+        Self {
+            code,
+            message,
+            details: None,
+        }
     }
 
     ///
@@ -180,7 +217,10 @@ impl McpErrorResponse {
 
 impl From<McpError> for McpErrorResponse {
     fn from(error: McpError) -> Self {
-        Self::new(error.code().as_str(), error.message().to_string())
+        let mut response =
+            Self::new(error.code().as_str(), error.message().to_string());
+        response.details = error.details().cloned();
+        response
     }
 }
 
@@ -462,6 +462,7 @@ impl McpHandler {
                             db,
                             input.path(),
                             input.content(),
+                            input.base_revision(),
                         )?
                     )?
                     .into(),
@@ -890,6 +891,7 @@ impl McpHandler {
     /// * `address` - 入力元アドレス
     /// * `path` - 更新対象 path
     /// * `content` - 更新後 Markdown 本文
+    /// * `base_revision` - 編集開始時の revision
     ///
     /// # 戻り値
     /// `update_page` の公開応答モデルを返す。
@@ -901,19 +903,26 @@ impl McpHandler {
         address: Option<IpAddr>,
         path: &str,
         content: &str,
+        base_revision: Option<u64>,
     ) -> Result<WritePageResponse, McpError> {
         let request = McpRequestEnvelope::new(
             super::tools::McpToolName::UpdatePage,
-            McpToolRequest::UpdatePage(WritePageRequest::new(
-                path.to_string(),
-                content.to_string(),
-            )),
+            McpToolRequest::UpdatePage(
+                WritePageRequest::new(path.to_string(), content.to_string())
+                    .with_base_revision(base_revision),
+            ),
         );
 
         /*
          * `update_page` を既存 service と監査記録へ橋渡しする
          */
-        let result = match self.service.update_page(auth, db, path, content) {
+        let result = match self.service.update_page(
+            auth,
+            db,
+            path,
+            content,
+            base_revision,
+        ) {
             Ok(result) => self.audit_success(
                 db,
                 auth,
@@ -1320,7 +1329,7 @@ fn build_list_prompts_error_audit_record(
 
     AuditRecord::new(
         AuditOperation::ListPrompts,
-        user_id,
+        Some(user_id),
         auth.token_id().cloned(),
         address,
         None,
@@ -1391,7 +1400,7 @@ fn build_list_resources_error_audit_record(
 
     AuditRecord::new(
         AuditOperation::ListResources,
-        user_id,
+        Some(user_id),
         auth.token_id().cloned(),
         address,
         None,
@@ -1457,7 +1466,7 @@ fn build_read_resource_error_audit_record(
 ) -> AuditRecord {
     AuditRecord::new(
         AuditOperation::ReadResource,
-        user_id,
+        Some(user_id),
         auth.token_id().cloned(),
         address,
         None,
@@ -1521,7 +1530,7 @@ fn build_get_prompt_error_audit_record(
 ) -> AuditRecord {
     AuditRecord::new(
         AuditOperation::GetPrompt,
-        user_id,
+        Some(user_id),
         auth.token_id().cloned(),
         address,
         None,
@@ -1729,7 +1738,7 @@ fn build_error_audit_record(
 
     AuditRecord::new(
         operation,
-        user_id,
+        Some(user_id),
         auth.token_id().cloned(),
         address,
         target_path,
@@ -1751,7 +1760,7 @@ fn build_success_record(
 ) -> AuditRecord {
     AuditRecord::new(
         operation,
-        user_id,
+        Some(user_id),
         auth.token_id().cloned(),
         address,
         target_path,
@@ -2087,7 +2096,7 @@ mod tests {
             )),
         );
         let error = McpService::new()
-            .update_page(&auth, &manager, "/mcp/page", "# updated")
+            .update_page(&auth, &manager, "/mcp/page", "# updated", None)
             .expect_err("update must fail by scope");
 
         handler.record_error(&manager, &auth, None, &request, &error);
@@ -692,6 +692,9 @@ pub(crate) struct WritePageRequest {
 
     /// 本文または追記内容
     content: String,
+
+    /// 編集開始時の revision(`update_page` のみ)
+    base_revision: Option<u64>,
 }
 
 ///
@@ -944,6 +947,12 @@ pub(crate) struct McpSearchPageItem {
 
     /// スニペット
     snippet: String,
+
+    /// ヒットしたアセットID
+    asset_id: Option<String>,
+
+    /// ヒットしたアセットのファイル名
+    file_name: Option<String>,
 }
 
 ///
@@ -1311,7 +1320,28 @@ impl WritePageRequest {
     /// 生成した入力モデルを返す。
     ///
     pub(crate) fn new(path: String, content: String) -> Self {
-        Self { path, content }
+        Self {
+            path,
+            content,
+            base_revision: None,
+        }
+    }
+
+    ///
+    /// 編集開始時の revision を設定する
+    ///
+    /// # 引数
+    /// * `base_revision` - 編集開始時の revision
+    ///
+    /// # 戻り値
+    /// 設定後の入力モデルを返す。
+    ///
+    pub(crate) fn with_base_revision(
+        mut self,
+        base_revision: Option<u64>,
+    ) -> Self {
+        self.base_revision = base_revision;
+        self
     }
 
     ///
@@ -1333,6 +1363,16 @@ impl WritePageRequest {
     pub(crate) fn content(&self) -> &str {
         &self.content
     }
+
+    ///
+    /// 編集開始時の revision を返す
+    ///
+    /// # 戻り値
+    /// 指定されている場合は`Some()`でラップして返す。
+    ///
+    pub(crate) fn base_revision(&self) -> Option<u64> {
+        self.base_revision
+    }
 }
 
 impl RenamePageRequest {
@@ -1992,6 +2032,8 @@ impl From<&super::service::SearchPageItem> for McpSearchPageItem {
             revision: item.revision(),
             score: item.score(),
             snippet: item.snippet().to_string(),
+            asset_id: item.asset_id().map(str::to_string),
+            file_name: item.file_name().map(str::to_string),
         }
     }
 }
@@ -57,6 +57,7 @@ use crate::mcp::tools::{
     ListPagesToolArgs,
     RenamePageToolArgs,
     SearchPagesToolArgs,
+    UpdatePageToolArgs,
     WritePageToolArgs,
     append_page,
     create_page,
@@ -835,11 +836,11 @@ impl LuwikiMcpServer {
     ///
     #[tool(
         name = "update_page",
-        description = "指定した path のページ本文を上書き更新する。"
+        description = "指定した path のページ本文を上書き更新する。base_revision を指定すると、その後に保存された変更と3-wayマージする。"
     )]
     async fn update_page_tool(
         &self,
-        params: Parameters<WritePageToolArgs>,
+        params: Parameters<UpdatePageToolArgs>,
         context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpProtocolError> {
         update_page::execute(self, params, context).await
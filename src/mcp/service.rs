@@ -36,6 +36,7 @@ use crate::database::resource_list::{
     page_resource_uri,
 };
 use crate::database::types::{
+    AssetId,
     BearerScope,
     PageId,
     PageIndex,
@@ -55,6 +56,7 @@ use crate::markdown_source::front_matter::{
     validate_resource_path,
     validate_resource_path_shape,
 };
+use crate::markdown_source::merge::{MergeOutcome, merge_three_way};
 
 use super::errors::{McpError, McpErrorCode};
 use super::model::{
@@ -308,6 +310,12 @@ pub(crate) struct SearchPageItem {
 
     /// スニペット
     snippet: String,
+
+    /// ヒットしたアセットID(ページ本文の場合は`None`)
+    asset_id: Option<String>,
+
+    /// ヒットしたアセットのファイル名(ページ本文の場合は`None`)
+    file_name: Option<String>,
 }
 
 ///
@@ -1053,6 +1061,8 @@ impl SearchPageItem {
     /// * `revision` - 対応 revision
     /// * `score` - 検索スコア
     /// * `snippet` - スニペット
+    /// * `asset_id` - ヒットしたアセットID
+    /// * `file_name` - ヒットしたアセットのファイル名
     ///
     /// # 戻り値
     /// 生成した一覧項目を返す。
@@ -1062,12 +1072,16 @@ impl SearchPageItem {
         revision: u64,
         score: f32,
         snippet: String,
+        asset_id: Option<String>,
+        file_name: Option<String>,
     ) -> Self {
         Self {
             path,
             revision,
             score,
             snippet,
+            asset_id,
+            file_name,
         }
     }
 
@@ -1110,6 +1124,26 @@ impl SearchPageItem {
     pub(crate) fn snippet(&self) -> &str {
         &self.snippet
     }
+
+    ///
+    /// ヒットしたアセットIDを返す
+    ///
+    /// # 戻り値
+    /// アセットにヒットした場合はアセットIDを返す。
+    ///
+    pub(crate) fn asset_id(&self) -> Option<&str> {
+        self.asset_id.as_deref()
+    }
+
+    ///
+    /// ヒットしたアセットのファイル名を返す
+    ///
+    /// # 戻り値
+    /// アセットにヒットした場合はファイル名を返す。
+    ///
+    pub(crate) fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
 }
 
 impl SearchPagesResult {
@@ -2422,6 +2456,8 @@ impl McpService {
                 result.revision(),
                 result.score(),
                 result.snippet(),
+                result.asset_id().map(|asset_id| asset_id.to_string()),
+                result.file_name(),
             ));
         }
 
@@ -2572,16 +2608,22 @@ impl McpService {
     /// * `db` - データベースマネージャ
     /// * `raw_path` - 更新対象 path
     /// * `content` - 更新後 Markdown 本文
+    /// * `base_revision` - 編集開始時の revision
     ///
     /// # 戻り値
     /// ページ更新結果を返す。
     ///
+    /// # 注記
+    /// `base_revision` が最新 revision より古い場合は、最新 revision との
+    /// 3-way マージ結果を保存する。
+    ///
     pub(crate) fn update_page(
         &self,
         auth: &AuthContext,
         db: &DatabaseManager,
         raw_path: &str,
         content: &str,
+        base_revision: Option<u64>,
     ) -> Result<WritePageResult, McpError> {
         /*
          * path 認可とページ解決を行う
@@ -2593,6 +2635,24 @@ impl McpService {
         )?;
         let resolved = self.resolve_page_by_path(db, &normalized_path)?;
 
+        /*
+         * 基準 revision が古い場合は最新 revision とマージする
+         */
+        let merged = match base_revision {
+            Some(base_revision) => self.merge_with_latest_revision(
+                db,
+                &resolved,
+                base_revision,
+                content,
+            )?,
+            None => None,
+        };
+        let summary = match merged {
+            Some(_) => "page updated (merged)",
+            None => "page updated",
+        };
+        let content = merged.unwrap_or_else(|| content.to_string());
+
         /*
          * ロック状態を確認してから保存する
          */
@@ -2600,14 +2660,14 @@ impl McpService {
             db,
             &resolved,
             auth.user().user_id(),
-            content.to_string(),
+            content,
         )?;
 
         Ok(WritePageResult::new(
             resolved.normalized_path().to_string(),
             revision,
             instance_id,
-            "page updated".to_string(),
+            summary.to_string(),
         ))
     }
 
@@ -3153,13 +3213,7 @@ impl McpService {
         auth: &AuthContext,
         target_path: &str,
     ) -> bool {
-        if auth.path_prefixes().allows_all() {
-            return true;
-        }
-
-        auth.path_prefixes()
-            .iter()
-            .any(|prefix| path_matches_prefix(target_path, prefix))
+        auth.path_prefixes().allows_path(target_path)
     }
 
     ///
@@ -3332,6 +3386,68 @@ impl McpService {
         Ok((revision, instance_id.to_string()))
     }
 
+    ///
+    /// 最新 revision との 3-way マージを行う
+    ///
+    /// # 引数
+    /// * `db` - データベースマネージャ
+    /// * `resolved` - 解決済みページ
+    /// * `base_revision` - 編集開始時の revision
+    /// * `content` - 更新後 Markdown 本文
+    ///
+    /// # 戻り値
+    /// `base_revision` が最新の場合は`None`を、古い場合はマージ結果を
+    /// `Some()`でラップして返す。変更が重なる場合は競合箇所を詳細情報に
+    /// 持つ`conflict`エラーを返す。
+    ///
+    fn merge_with_latest_revision(
+        &self,
+        db: &DatabaseManager,
+        resolved: &ResolvedPage,
+        base_revision: u64,
+        content: &str,
+    ) -> Result<Option<String>, McpError> {
+        let (latest_revision, _, latest_source) =
+            self.resolve_revision_source(db, resolved, None)?;
+        if base_revision == latest_revision {
+            return Ok(None);
+        }
+        if base_revision > latest_revision {
+            return Err(McpError::new(
+                McpErrorCode::InvalidInput,
+                "base_revision is newer than latest revision",
+            ));
+        }
+
+        let (_, _, base_source) =
+            self.resolve_revision_source(db, resolved, Some(base_revision))?;
+
+        match merge_three_way(&base_source, &latest_source, content) {
+            MergeOutcome::Merged(merged) => Ok(Some(merged)),
+            MergeOutcome::Conflict(conflicts) => {
+                let conflicts = serde_json::to_value(conflicts).map_err(|err| {
+                    McpError::new(
+                        McpErrorCode::InternalError,
+                        format!("merge conflict serialization failed: {}", err),
+                    )
+                })?;
+                let mut details = JsonObject::new();
+                details.insert(
+                    "base_revision".to_string(),
+                    JsonValue::from(base_revision),
+                );
+                details.insert(
+                    "latest_revision".to_string(),
+                    JsonValue::from(latest_revision),
+                );
+                details.insert("conflicts".to_string(), conflicts);
+
+                Err(McpError::new(McpErrorCode::Conflict, "merge conflict")
+                    .with_details(JsonValue::Object(details)))
+            }
+        }
+    }
+
     ///
     /// 認証済みユーザ名から内部 `UserId` を解決する
     ///
@@ -3589,11 +3705,14 @@ impl McpService {
     ///
     fn merge_search_results(
         &self,
-        merged: &mut HashMap<(PageId, u64), fts::FtsSearchResult>,
+        merged: &mut HashMap<
+            (PageId, u64, Option<AssetId>),
+            fts::FtsSearchResult,
+        >,
         results: Vec<fts::FtsSearchResult>,
     ) {
         for result in results {
-            let key = (result.page_id(), result.revision());
+            let key = (result.page_id(), result.revision(), result.asset_id());
             let replace = match merged.get(&key) {
                 Some(existing) => result.score() > existing.score(),
                 None => true,
@@ -5314,7 +5433,7 @@ mod tests {
         assert_eq!(create_err.code(), McpErrorCode::Forbidden);
 
         let update_err = service
-            .update_page(&auth, &manager, "/mcp/readonly", "# updated")
+            .update_page(&auth, &manager, "/mcp/readonly", "# updated", None)
             .expect_err("read only user must not update page");
         assert_eq!(update_err.code(), McpErrorCode::Forbidden);
 
@@ -6877,7 +6996,7 @@ mod tests {
          * update の保存結果を検証する
          */
         let result = service
-            .update_page(&auth, &manager, "/mcp/update", "# after")
+            .update_page(&auth, &manager, "/mcp/update", "# after", None)
             .expect("update page failed");
         let source = manager
             .get_page_source(&page_id, 2)
@@ -6899,6 +7018,81 @@ mod tests {
         fs::remove_dir_all(base_dir).expect("cleanup failed");
     }
 
+    #[test]
+    fn update_page_merges_stale_base_revision_or_reports_conflict() {
+        /*
+         * テスト用データベースを準備する
+         */
+        let (base_dir, manager) = open_test_manager();
+        manager
+            .add_user("user", "pass", None)
+            .expect("add user failed");
+        let page_id = manager
+            .create_page("/mcp/merge", "user", "one\ntwo\nthree\n".to_string())
+            .expect("create page failed");
+        manager
+            .put_page(&page_id, "user", "ONE\ntwo\nthree\n".to_string(), false)
+            .expect("put page failed");
+        let service = McpService::new();
+        let auth = AuthContext::new(
+            AuthUser::new("user".to_string()),
+            BearerScopeSet::from_iter([BearerScope::Update]),
+            PathPrefixSet::from_iter(["/mcp"]),
+            None,
+        );
+
+        /*
+         * 重ならない変更は最新 revision とマージして保存する
+         */
+        let result = service
+            .update_page(
+                &auth,
+                &manager,
+                "/mcp/merge",
+                "one\ntwo\nTHREE\n",
+                Some(1),
+            )
+            .expect("merge update failed");
+        let source = manager
+            .get_page_source(&page_id, 3)
+            .expect("lookup source failed")
+            .expect("source missing");
+
+        assert_eq!(result.revision(), 3);
+        assert_eq!(result.summary(), "page updated (merged)");
+        assert_eq!(source.source(), "ONE\ntwo\nTHREE\n");
+
+        /*
+         * 重なる変更は競合箇所を詳細情報に持つ conflict とする
+         */
+        let error = service
+            .update_page(
+                &auth,
+                &manager,
+                "/mcp/merge",
+                "one\ntwo\n3\n",
+                Some(2),
+            )
+            .expect_err("overlapping update must conflict");
+
+        assert_eq!(error.code(), McpErrorCode::Conflict);
+        let details = error.details().expect("conflict details missing");
+        assert_eq!(details["base_revision"], 2);
+        assert_eq!(details["latest_revision"], 3);
+        assert_eq!(details["conflicts"][0]["latest"], "THREE\n");
+        assert_eq!(details["conflicts"][0]["submitted"], "3\n");
+
+        /*
+         * 最新より新しい base_revision は入力不正とする
+         */
+        let error = service
+            .update_page(&auth, &manager, "/mcp/merge", "x", Some(4))
+            .expect_err("future base_revision must fail");
+        assert_eq!(error.code(), McpErrorCode::InvalidInput);
+
+        fs::remove_dir_all(base_dir).expect("cleanup failed");
+    }
+
     #[test]
     fn rename_page_moves_page_and_returns_new_path() {
         /*
@@ -140,6 +140,9 @@ pub(crate) enum SearchPagesTargetArg {
 
     /// front matter
     FrontMatter,
+
+    /// テキスト形式のアセット
+    Assets,
 }
 
 impl SearchPagesTargetArg {
@@ -155,6 +158,7 @@ impl SearchPagesTargetArg {
             Self::Body => FtsSearchTarget::Body,
             Self::Code => FtsSearchTarget::Code,
             Self::FrontMatter => FtsSearchTarget::FrontMatter,
+            Self::Assets => FtsSearchTarget::Assets,
         }
     }
 }
@@ -186,6 +190,21 @@ pub(crate) struct WritePageToolArgs {
     pub(crate) content: String,
 }
 
+///
+/// `update_page` 用の tool 引数
+///
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub(crate) struct UpdatePageToolArgs {
+    /// 対象ページの絶対 path
+    pub(crate) path: String,
+
+    /// 更新後の本文全体
+    pub(crate) content: String,
+
+    /// 編集を開始した revision(最新でない場合は最新 revision とマージする)
+    pub(crate) base_revision: Option<u64>,
+}
+
 ///
 /// `rename_page` 用の tool 引数
 ///
@@ -15,7 +15,7 @@ use rmcp::service::RequestContext;
 use rmcp::RoleServer;
 
 use crate::mcp::server::LuwikiMcpServer;
-use crate::mcp::tools::WritePageToolArgs;
+use crate::mcp::tools::UpdatePageToolArgs;
 
 ///
 /// `update_page` を実行する
@@ -30,7 +30,7 @@ use crate::mcp::tools::WritePageToolArgs;
 ///
 pub(crate) async fn execute(
     server: &LuwikiMcpServer,
-    Parameters(args): Parameters<WritePageToolArgs>,
+    Parameters(args): Parameters<UpdatePageToolArgs>,
     context: RequestContext<RoleServer>,
 ) -> Result<CallToolResult, McpProtocolError> {
     let auth = server.auth_from_context(&context)?;
@@ -47,6 +47,7 @@ pub(crate) async fn execute(
             address,
             &args.path,
             &args.content,
+            args.base_revision,
         ))
     })?;
 
@@ -0,0 +1,388 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 管理者向け監査ログ検索APIの実装をまとめたモジュール
+//!
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use actix_web::http::{StatusCode, header};
+use actix_web::{HttpRequest, HttpResponse, web};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use super::require_admin;
+use crate::audit::model::AuditRecord;
+use crate::audit::query::{
+    AuditQuery,
+    count_audit_records,
+    parse_query_time,
+    query_audit_page,
+};
+use crate::database::types::{BearerScope, TokenId, UserId};
+use crate::http_server::app_state::AppState;
+use crate::rest_api::{
+    CACHE_CONTROL_NO_STORE,
+    resp_error_json,
+    validate_page_path,
+};
+
+///
+/// 1回の応答で返す件数の既定値
+///
+const DEFAULT_LIMIT: usize = 50;
+
+///
+/// GET /api/admin/audit のクエリパラメータ
+///
+#[derive(Deserialize)]
+struct AuditQueryParams {
+    /// 操作主体のユーザ名
+    user: Option<String>,
+
+    /// Bearer トークンID
+    token: Option<String>,
+
+    /// 対象 path の prefix
+    path_prefix: Option<String>,
+
+    /// 操作種別
+    operation: Option<String>,
+
+    /// 検索範囲の開始時刻
+    since: Option<String>,
+
+    /// 検索範囲の終了時刻
+    until: Option<String>,
+
+    /// 操作結果分類
+    result: Option<String>,
+
+    /// 取得件数
+    limit: Option<String>,
+
+    /// 読み飛ばす件数
+    offset: Option<String>,
+
+    /// 一致件数の総数を返すか否か
+    with_total: Option<String>,
+}
+
+///
+/// GET /api/admin/audit[?user=..][&token=..][&path_prefix=..]
+/// [&operation=..][&since=..][&until=..][&result=..][&limit=..][&offset=..]
+/// [&with_total=..] の実体
+///
+/// # 概要
+/// 監査ログ(アクティブファイルおよびローテーション済みファイル)を
+/// 条件で絞り込み、記録時刻の昇順で取得する。
+///
+/// # 注記
+/// 監査ログの走査はブロッキングスレッドで行い、返却範囲の直後の一致
+/// レコードを見つけた時点で打ち切る。一致件数の総数は`with_total=true`
+/// の場合のみ全ファイルを走査して数える。
+///
+pub async fn get(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+) -> actix_web::Result<HttpResponse> {
+    /*
+     * 認可とクエリの取得
+     */
+    if let Err(resp) = require_admin(&req, BearerScope::Read) {
+        return Ok(resp);
+    }
+
+    let params =
+        match web::Query::<AuditQueryParams>::from_query(req.query_string()) {
+            Ok(params) => params.into_inner(),
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::BAD_REQUEST,
+                    "invalid query parameter",
+                ));
+            }
+        };
+
+    let mut query = match build_query(&params) {
+        Ok(query) => query,
+        Err(resp) => return Ok(resp),
+    };
+
+    let limit = match parse_count_param("limit", params.limit.as_deref()) {
+        Ok(Some(0)) | Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "invalid query parameter: limit",
+            ));
+        }
+        Ok(value) => value.unwrap_or(DEFAULT_LIMIT),
+    };
+
+    let offset = match parse_count_param("offset", params.offset.as_deref()) {
+        Ok(value) => value.unwrap_or(0),
+        Err(resp) => return Ok(resp),
+    };
+
+    let with_total =
+        match parse_bool_param("with_total", params.with_total.as_deref()) {
+            Ok(value) => value,
+            Err(resp) => return Ok(resp),
+        };
+
+    /*
+     * 出力先とユーザ名対応表の取得
+     */
+    let (audit_log_dir, user_names) = {
+        let state = match state.read() {
+            Ok(state) => state,
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "state lock failed",
+                ));
+            }
+        };
+
+        let Some(audit_log_dir) = state.audit_log_dir() else {
+            return Ok(resp_error_json(
+                StatusCode::NOT_IMPLEMENTED,
+                "audit log is not enabled",
+            ));
+        };
+
+        let users = match state.db().list_users() {
+            Ok(users) => users,
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "user query failed",
+                ));
+            }
+        };
+
+        let user_names: HashMap<UserId, String> = users
+            .into_iter()
+            .map(|user| (user.id(), user.username()))
+            .collect();
+
+        (audit_log_dir, user_names)
+    };
+
+    if let Some(user_name) = params.user.as_deref() {
+        let user_id = user_names
+            .iter()
+            .find(|(_, name)| name.as_str() == user_name)
+            .map(|(user_id, _)| user_id.clone());
+        let Some(user_id) = user_id else {
+            return Ok(resp_error_json(
+                StatusCode::NOT_FOUND,
+                "user not found",
+            ));
+        };
+        query.user_id = Some(user_id);
+    }
+
+    /*
+     * 監査ログの検索と切り出し
+     */
+    let result = tokio::task::spawn_blocking(move || {
+        let page = query_audit_page(&audit_log_dir, &query, offset, limit)?;
+        let total = if with_total {
+            Some(count_audit_records(&audit_log_dir, &query)?)
+        } else {
+            None
+        };
+
+        anyhow::Ok((page, total))
+    })
+    .await;
+
+    let (page, total) = match result {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) | Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "audit log query failed",
+            ));
+        }
+    };
+
+    let items: Vec<Value> = page
+        .records
+        .iter()
+        .map(|record| audit_record_json(record, &user_names))
+        .collect();
+
+    let body = json!({
+        "items": items,
+        "total": total,
+        "has_more": page.has_more,
+        "skipped_lines": page.skipped_lines,
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
+        .body(body.to_string()))
+}
+
+///
+/// クエリパラメータから検索条件を構築する
+///
+/// # 引数
+/// * `params` - クエリパラメータ
+///
+/// # 戻り値
+/// ユーザ条件を除く検索条件を返す。不正な値が含まれる場合は 400 応答を
+/// 返す。
+///
+fn build_query(params: &AuditQueryParams) -> Result<AuditQuery, HttpResponse> {
+    let now = Utc::now();
+    let mut query = AuditQuery::default();
+
+    if let Some(token) = params.token.as_deref() {
+        query.token_id = Some(TokenId::from_string(token).map_err(|_| {
+            resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "invalid query parameter: token",
+            )
+        })?);
+    }
+
+    if let Some(prefix) = params.path_prefix.as_deref() {
+        if let Err(message) = validate_page_path(prefix) {
+            return Err(resp_error_json(StatusCode::BAD_REQUEST, message));
+        }
+        query.path_prefix = Some(prefix.to_string());
+    }
+
+    if let Some(operation) = params.operation.as_deref() {
+        query.operation = Some(operation.parse().map_err(|_| {
+            resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "invalid query parameter: operation",
+            )
+        })?);
+    }
+
+    if let Some(result) = params.result.as_deref() {
+        query.result = Some(result.parse().map_err(|_| {
+            resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "invalid query parameter: result",
+            )
+        })?);
+    }
+
+    if let Some(since) = params.since.as_deref() {
+        query.since = Some(parse_query_time(since, now).map_err(|_| {
+            resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "invalid query parameter: since",
+            )
+        })?);
+    }
+
+    if let Some(until) = params.until.as_deref() {
+        query.until = Some(parse_query_time(until, now).map_err(|_| {
+            resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "invalid query parameter: until",
+            )
+        })?);
+    }
+
+    Ok(query)
+}
+
+///
+/// 件数指定のクエリパラメータを解析する
+///
+/// # 引数
+/// * `name` - パラメータ名
+/// * `raw` - パラメータ値
+///
+/// # 戻り値
+/// 指定がある場合は解析した件数を返す。不正な値の場合は 400 応答を返す。
+///
+fn parse_count_param(
+    name: &str,
+    raw: Option<&str>,
+) -> Result<Option<usize>, HttpResponse> {
+    match raw {
+        None => Ok(None),
+        Some(value) => value.parse::<usize>().map(Some).map_err(|_| {
+            resp_error_json(
+                StatusCode::BAD_REQUEST,
+                format!("invalid query parameter: {}", name),
+            )
+        }),
+    }
+}
+
+///
+/// 真偽値指定のクエリパラメータを解析する
+///
+/// # 引数
+/// * `name` - パラメータ名
+/// * `raw` - パラメータ値
+///
+/// # 戻り値
+/// 指定が無い場合は`false`を返す。不正な値の場合は 400 応答を返す。
+///
+fn parse_bool_param(
+    name: &str,
+    raw: Option<&str>,
+) -> Result<bool, HttpResponse> {
+    match raw {
+        None => Ok(false),
+        Some(value) => match value {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(resp_error_json(
+                StatusCode::BAD_REQUEST,
+                format!("invalid query parameter: {}", name),
+            )),
+        },
+    }
+}
+
+///
+/// 監査レコードの応答JSON表現を生成する
+///
+/// # 引数
+/// * `record` - 監査レコード
+/// * `user_names` - ユーザIDからユーザ名への対応表
+///
+/// # 戻り値
+/// 記録内容に操作主体のユーザ名を加えたJSON値を返す。
+///
+fn audit_record_json(
+    record: &AuditRecord,
+    user_names: &HashMap<UserId, String>,
+) -> Value {
+    let user_name = record
+        .user_id
+        .as_ref()
+        .and_then(|user_id| user_names.get(user_id));
+
+    json!({
+        "timestamp": record.timestamp.to_rfc3339(),
+        "operation": record.operation.as_str(),
+        "result": record.result.as_str(),
+        "user_id": record.user_id.as_ref().map(|user_id| user_id.to_string()),
+        "user_name": user_name,
+        "token_id": record.token_id.as_ref().map(|token_id| token_id.to_string()),
+        "address": record.address.map(|address| address.to_string()),
+        "target_path": record.target_path,
+        "revision": record.revision,
+        "summary": record.summary,
+    })
+}
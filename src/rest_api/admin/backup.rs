@@ -0,0 +1,252 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 管理者向けオンラインバックアップAPIの実装をまとめたモジュール
+//!
+
+use std::io::Read;
+use std::sync::{Arc, RwLock};
+
+use actix_web::http::{StatusCode, header};
+use actix_web::{HttpRequest, HttpResponse, web};
+use futures::{Stream, stream};
+use serde::Deserialize;
+
+use super::{record_admin_audit, require_admin};
+use crate::audit::model::{AuditOperation, AuditResult};
+use crate::database::types::BearerScope;
+use crate::export_import::{
+    TemporaryArchive,
+    backup_to_temporary_archive,
+    resolve_export_since,
+};
+use crate::http_server::app_state::AppState;
+use crate::rest_api::{
+    CACHE_CONTROL_NO_STORE, EXPORT_ID_HEADER, resp_error_json,
+};
+
+/// アーカイブ送出時の読み出し単位
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+///
+/// POST /api/admin/backup のリクエストボディ
+///
+#[derive(Default, Deserialize)]
+struct BackupRequest {
+    /// 差分 backup の起点(RFC3339 日時またはエクスポートID)
+    since: Option<String>,
+
+    /// ZIP パスワード
+    password: Option<String>,
+}
+
+///
+/// backup 用アーカイブ作成の失敗要因
+///
+enum BackupError {
+    /// 共有状態のロック取得失敗
+    StateLock,
+
+    /// 差分 backup の起点指定が不正(起点の指定値とエラー内容)
+    InvalidSince(String, String),
+
+    /// アーカイブの作成失敗
+    Build,
+}
+
+///
+/// POST /api/admin/backup の実体
+///
+/// # 概要
+/// 稼働中のDBから単一の読み取りトランザクションで backup 用アーカイブを
+/// 作成し、ZIP として送出する。
+///
+/// # 注記
+/// アーカイブには利用者情報(パスワードハッシュやTOTP秘密鍵を含む)が含まれ
+/// るため、Bearer認証時は`write`スコープを要求する。
+/// アーカイブの作成はブロッキングスレッドで行い、共有状態の読み取り
+/// ロックは作成完了時点で解放する。送出中はロックを保持しない。
+///
+pub async fn post(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    body: web::Bytes,
+) -> actix_web::Result<HttpResponse> {
+    /*
+     * 認可
+     */
+    let auth = match require_admin(&req, BearerScope::Write) {
+        Ok(auth) => auth,
+        Err(resp) => return Ok(resp),
+    };
+
+    /*
+     * 入力の解析と検証
+     */
+    let request = if body.is_empty() {
+        BackupRequest::default()
+    } else {
+        match serde_json::from_slice::<BackupRequest>(&body) {
+            Ok(request) => request,
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::BAD_REQUEST,
+                    "invalid request body",
+                ));
+            }
+        }
+    };
+
+    if request.password.as_deref().is_some_and(str::is_empty) {
+        return Ok(resp_error_json(
+            StatusCode::BAD_REQUEST,
+            "password is empty",
+        ));
+    }
+
+    /*
+     * アーカイブの作成
+     */
+    let shared = state.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let state = shared.read().map_err(|_| BackupError::StateLock)?;
+
+        let since = match request.since.as_deref() {
+            Some(value) => match resolve_export_since(state.db(), value) {
+                Ok(since) => Some(since),
+                Err(err) => {
+                    return Err(BackupError::InvalidSince(
+                        value.to_string(),
+                        err.to_string(),
+                    ));
+                }
+            },
+            None => None,
+        };
+
+        backup_to_temporary_archive(
+            state.db(),
+            since.as_ref(),
+            request.password.as_deref(),
+        )
+        .map_err(|_| BackupError::Build)
+    })
+    .await;
+
+    /*
+     * 結果の記録
+     */
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+
+    let backup = match result {
+        Ok(Ok(backup)) => backup,
+        Ok(Err(BackupError::StateLock)) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+        Ok(Err(BackupError::InvalidSince(value, message))) => {
+            record_admin_audit(
+                &state,
+                &req,
+                &auth,
+                AuditOperation::Backup,
+                AuditResult::InvalidInput,
+                format!("backup since={}", value),
+            );
+            return Ok(resp_error_json(StatusCode::BAD_REQUEST, message));
+        }
+        Ok(Err(BackupError::Build)) | Err(_) => {
+            record_admin_audit(
+                &state,
+                &req,
+                &auth,
+                AuditOperation::Backup,
+                AuditResult::InternalError,
+                "backup".to_string(),
+            );
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "backup failed",
+            ));
+        }
+    };
+
+    let export_id = backup
+        .manifest
+        .export_id
+        .as_ref()
+        .map(|export_id| export_id.to_string())
+        .unwrap_or_default();
+    record_admin_audit(
+        &state,
+        &req,
+        &auth,
+        AuditOperation::Backup,
+        AuditResult::Success,
+        format!(
+            "backup export_id={} incremental={} pages={} revisions={} assets={}",
+            export_id,
+            backup.manifest.is_incremental(),
+            backup.manifest.page_count,
+            backup.manifest.revision_count,
+            backup.manifest.asset_count,
+        ),
+    );
+    drop(state);
+
+    /*
+     * レスポンス生成
+     */
+    let size = backup.archive.size();
+    Ok(HttpResponse::Ok()
+        .content_type("application/zip")
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"luwiki-backup-{}.zip\"", export_id),
+        ))
+        .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
+        .insert_header((EXPORT_ID_HEADER, export_id))
+        .no_chunking(size)
+        .streaming(archive_stream(backup.archive)))
+}
+
+///
+/// 一時アーカイブ読み出しストリームの生成
+///
+/// # 引数
+/// * `archive` - 送出する一時アーカイブ
+///
+/// # 戻り値
+/// 一定サイズ毎にデータを返すストリームを返す。ストリームの破棄と同時に
+/// 一時アーカイブも削除される。
+///
+fn archive_stream(
+    archive: TemporaryArchive,
+) -> impl Stream<Item = Result<web::Bytes, std::io::Error>> {
+    stream::unfold(Some(archive), |archive| async move {
+        let mut archive = archive?;
+        let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+        match archive.read(&mut buf) {
+            Ok(0) => None,
+            Ok(len) => {
+                buf.truncate(len);
+                Some((Ok(web::Bytes::from(buf)), Some(archive)))
+            }
+            Err(err) => Some((Err(err), None)),
+        }
+    })
+}
@@ -0,0 +1,87 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 管理者向けAPIの実装をまとめたモジュール
+//!
+
+pub(crate) mod audit;
+pub(crate) mod backup;
+pub(crate) mod users;
+
+use actix_web::HttpRequest;
+use actix_web::HttpResponse;
+use actix_web::http::StatusCode;
+use chrono::Utc;
+
+use crate::audit::model::{AuditOperation, AuditRecord, AuditResult};
+use crate::database::types::{BearerScope, UserAttribute};
+use crate::http_server::app_state::AppState;
+use crate::rest_api::{AuthContext, require_request_scope, resp_error_json};
+
+///
+/// 管理者向けAPIの利用可否を検証する
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `required` - 要求スコープ
+///
+/// # 戻り値
+/// 必要スコープを満たし `Admin` 属性を持つ場合は認証文脈の複製を返す。
+/// `Admin` 属性を持たない場合は 403 応答を返す。
+///
+pub(crate) fn require_admin(
+    req: &HttpRequest,
+    required: BearerScope,
+) -> Result<AuthContext, HttpResponse> {
+    let auth = require_request_scope(req, required)?;
+
+    if !auth.user_attributes().contains(UserAttribute::Admin) {
+        return Err(resp_error_json(
+            StatusCode::FORBIDDEN,
+            "admin attribute required",
+        ));
+    }
+
+    Ok(auth)
+}
+
+///
+/// 管理操作の監査レコードを記録する
+///
+/// # 引数
+/// * `state` - 共有状態
+/// * `req` - HTTPリクエスト
+/// * `auth` - 操作主体の認証文脈
+/// * `operation` - 操作種別
+/// * `result` - 操作結果分類
+/// * `summary` - 補足要約
+///
+pub(crate) fn record_admin_audit(
+    state: &AppState,
+    req: &HttpRequest,
+    auth: &AuthContext,
+    operation: AuditOperation,
+    result: AuditResult,
+    summary: String,
+) {
+    let user_id = match state.db().get_user_id_by_name(auth.user_id()) {
+        Ok(Some(user_id)) => user_id,
+        Ok(None) | Err(_) => return,
+    };
+
+    state.record_audit(AuditRecord::new(
+        operation,
+        Some(user_id),
+        auth.token_id().cloned(),
+        req.peer_addr().map(|addr| addr.ip()),
+        None,
+        result,
+        Utc::now(),
+        Some(summary),
+        None,
+    ));
+}
@@ -0,0 +1,690 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 管理者向けユーザ管理APIの実装をまとめたモジュール
+//!
+
+use std::sync::{Arc, RwLock};
+
+use actix_web::http::{StatusCode, header};
+use actix_web::{HttpRequest, HttpResponse, web};
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use super::{record_admin_audit, require_admin};
+use crate::audit::model::{AuditOperation, AuditResult};
+use crate::auth::PasswordPolicy;
+use crate::database::types::{
+    BearerScope,
+    UserAttribute,
+    UserAttributeSet,
+    UserInfo,
+};
+use crate::http_server::app_state::AppState;
+use crate::rest_api::{CACHE_CONTROL_NO_STORE, resp_error_json};
+
+///
+/// POST /api/admin/users のリクエストボディ
+///
+#[derive(Deserialize)]
+struct CreateUserRequest {
+    /// 登録するユーザ名
+    username: String,
+
+    /// 初期パスワード(`NoBasicAuth` 属性を持つ場合は不要)
+    password: Option<String>,
+
+    /// 表示名
+    display_name: Option<String>,
+
+    /// 初期ユーザ属性
+    #[serde(default)]
+    attributes: Vec<String>,
+}
+
+///
+/// PATCH /api/admin/users/{user_name} のリクエストボディ
+///
+#[derive(Deserialize)]
+struct EditUserRequest {
+    /// 更新後の表示名
+    display_name: Option<String>,
+
+    /// 再設定するパスワード
+    password: Option<String>,
+
+    /// 更新後のユーザ属性(指定した集合で置き換える)
+    attributes: Option<Vec<String>>,
+
+    /// 無効化状態
+    disabled: Option<bool>,
+
+    /// `true` の場合はログイン失敗によるロックアウトを解除する
+    #[serde(default)]
+    unlock: bool,
+}
+
+///
+/// GET /api/admin/users の実体
+///
+/// # 概要
+/// 登録済みユーザの一覧を最終アクティビティ日時を含めて取得する。
+///
+pub async fn list(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+) -> actix_web::Result<HttpResponse> {
+    /*
+     * 認可と共有状態の取得
+     */
+    if let Err(resp) = require_admin(&req, BearerScope::Read) {
+        return Ok(resp);
+    }
+
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+
+    /*
+     * ユーザ一覧の取得
+     */
+    let mut users = match state.db().list_users() {
+        Ok(users) => users,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "user query failed",
+            ));
+        }
+    };
+    users.sort_by_key(|user| user.username());
+
+    let body = json!({
+        "users": users.iter().map(user_info_json).collect::<Vec<_>>(),
+    });
+
+    Ok(json_response(StatusCode::OK, body))
+}
+
+///
+/// GET /api/admin/users/{user_name} の実体
+///
+/// # 概要
+/// 指定ユーザの情報を取得する。
+///
+pub async fn get(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    /*
+     * 認可と共有状態の取得
+     */
+    if let Err(resp) = require_admin(&req, BearerScope::Read) {
+        return Ok(resp);
+    }
+
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+
+    /*
+     * 対象ユーザの取得
+     */
+    match state.db().get_user_info_by_name(&path.into_inner()) {
+        Ok(Some(user)) => {
+            Ok(json_response(StatusCode::OK, user_info_json(&user)))
+        }
+        Ok(None) => {
+            Ok(resp_error_json(StatusCode::NOT_FOUND, "user not found"))
+        }
+        Err(_) => Ok(resp_error_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "user query failed",
+        )),
+    }
+}
+
+///
+/// POST /api/admin/users の実体
+///
+/// # 概要
+/// ユーザを新規作成する。
+///
+pub async fn post(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    body: web::Bytes,
+) -> actix_web::Result<HttpResponse> {
+    /*
+     * 認可と共有状態の取得
+     */
+    let auth = match require_admin(&req, BearerScope::Create) {
+        Ok(auth) => auth,
+        Err(resp) => return Ok(resp),
+    };
+
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+
+    /*
+     * 入力の解析と検証
+     */
+    let request = match serde_json::from_slice::<CreateUserRequest>(&body) {
+        Ok(request) => request,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "invalid request body",
+            ));
+        }
+    };
+    let username = request.username.trim().to_string();
+    let (password, attributes) = match validate_create_request(
+        &request,
+        state.auth_config().password_policy(),
+    ) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            record_admin_audit(
+                &state,
+                &req,
+                &auth,
+                AuditOperation::UserCreate,
+                AuditResult::InvalidInput,
+                format!("user={} {}", username, message),
+            );
+            return Ok(resp_error_json(StatusCode::BAD_REQUEST, message));
+        }
+    };
+
+    /*
+     * 既存ユーザの確認
+     */
+    match state.db().get_user_id_by_name(&username) {
+        Ok(None) => {}
+        Ok(Some(_)) => {
+            record_admin_audit(
+                &state,
+                &req,
+                &auth,
+                AuditOperation::UserCreate,
+                AuditResult::Conflict,
+                format!("user={}", username),
+            );
+            return Ok(resp_error_json(
+                StatusCode::CONFLICT,
+                "user already exists",
+            ));
+        }
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "user query failed",
+            ));
+        }
+    }
+
+    /*
+     * ユーザ登録と既定ページ初期化
+     */
+    let created = state
+        .db()
+        .add_user_with_attributes(
+            &username,
+            password.as_deref(),
+            request.display_name.clone(),
+            attributes,
+        )
+        .and_then(|_| state.db().ensure_default_root(&username))
+        .and_then(|_| state.db().get_user_info_by_name(&username));
+    let user = match created {
+        Ok(Some(user)) => user,
+        Ok(None) | Err(_) => {
+            record_admin_audit(
+                &state,
+                &req,
+                &auth,
+                AuditOperation::UserCreate,
+                AuditResult::InternalError,
+                format!("user={}", username),
+            );
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "user create failed",
+            ));
+        }
+    };
+
+    record_admin_audit(
+        &state,
+        &req,
+        &auth,
+        AuditOperation::UserCreate,
+        AuditResult::Success,
+        format!(
+            "user={} attributes={}",
+            username,
+            format_attributes(&user.attributes()),
+        ),
+    );
+
+    Ok(json_response(StatusCode::CREATED, user_info_json(&user)))
+}
+
+///
+/// PATCH /api/admin/users/{user_name} の実体
+///
+/// # 概要
+/// 指定ユーザの表示名・パスワード・属性・無効化状態を更新する。
+///
+/// # 注記
+/// 自分自身の無効化と、自分自身からの `Admin` 属性の除去は拒否する。
+///
+pub async fn patch(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    path: web::Path<String>,
+    body: web::Bytes,
+) -> actix_web::Result<HttpResponse> {
+    /*
+     * 認可と共有状態の取得
+     */
+    let auth = match require_admin(&req, BearerScope::Update) {
+        Ok(auth) => auth,
+        Err(resp) => return Ok(resp),
+    };
+
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+
+    /*
+     * 対象ユーザの解決
+     */
+    let username = path.into_inner();
+    let current = match state.db().get_user_info_by_name(&username) {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            record_admin_audit(
+                &state,
+                &req,
+                &auth,
+                AuditOperation::UserUpdate,
+                AuditResult::NotFound,
+                format!("user={}", username),
+            );
+            return Ok(resp_error_json(StatusCode::NOT_FOUND, "user not found"));
+        }
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "user query failed",
+            ));
+        }
+    };
+
+    /*
+     * 入力の解析と検証
+     */
+    let request = match serde_json::from_slice::<EditUserRequest>(&body) {
+        Ok(request) => request,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "invalid request body",
+            ));
+        }
+    };
+    let attributes = match validate_edit_request(
+        &request,
+        &current,
+        state.auth_config().password_policy(),
+    ) {
+        Ok(attributes) => attributes,
+        Err(message) => {
+            record_admin_audit(
+                &state,
+                &req,
+                &auth,
+                AuditOperation::UserUpdate,
+                AuditResult::InvalidInput,
+                format!("user={} {}", username, message),
+            );
+            return Ok(resp_error_json(StatusCode::BAD_REQUEST, message));
+        }
+    };
+
+    if username == auth.user_id() {
+        let removes_admin = attributes
+            .as_ref()
+            .is_some_and(|attrs| !attrs.contains(UserAttribute::Admin));
+
+        if request.disabled == Some(true) || removes_admin {
+            record_admin_audit(
+                &state,
+                &req,
+                &auth,
+                AuditOperation::UserUpdate,
+                AuditResult::Conflict,
+                format!("user={} self lockout", username),
+            );
+            return Ok(resp_error_json(
+                StatusCode::CONFLICT,
+                "cannot disable or demote yourself",
+            ));
+        }
+    }
+
+    /*
+     * 更新の反映
+     */
+    let result = state.db().edit_user(
+        &username,
+        request.display_name.as_deref(),
+        request.password.as_deref(),
+        attributes,
+        request.disabled,
+        request.unlock,
+    );
+
+    if result.is_err() {
+        record_admin_audit(
+            &state,
+            &req,
+            &auth,
+            AuditOperation::UserUpdate,
+            AuditResult::InternalError,
+            format!("user={}", username),
+        );
+        return Ok(resp_error_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "user update failed",
+        ));
+    }
+
+    /*
+     * パスワード再設定・無効化時は二要素認証済みセッションを破棄する
+     */
+    if request.password.is_some() || request.disabled == Some(true) {
+        state.mfa_sessions().revoke_user(&username);
+    }
+
+    let user = match state.db().get_user_info_by_name(&username) {
+        Ok(Some(user)) => user,
+        Ok(None) | Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "user query failed",
+            ));
+        }
+    };
+
+    record_admin_audit(
+        &state,
+        &req,
+        &auth,
+        AuditOperation::UserUpdate,
+        AuditResult::Success,
+        format_update_summary(&username, &request, &user),
+    );
+
+    Ok(json_response(StatusCode::OK, user_info_json(&user)))
+}
+
+///
+/// 作成リクエストの検証
+///
+/// # 引数
+/// * `request` - 作成リクエスト
+/// * `policy` - パスワードの強度要件
+///
+/// # 戻り値
+/// 登録に用いるパスワードと属性集合を返す。検証に失敗した場合はエラー
+/// メッセージを返す。
+///
+fn validate_create_request(
+    request: &CreateUserRequest,
+    policy: &PasswordPolicy,
+) -> Result<(Option<String>, UserAttributeSet), String> {
+    if request.username.trim().is_empty() {
+        return Err("username must not be empty".to_string());
+    }
+
+    let attributes = parse_attributes(&request.attributes)?;
+
+    /*
+     * `NoBasicAuth` 属性を持たないユーザはパスワードが必須
+     */
+    if attributes.contains(UserAttribute::NoBasicAuth) {
+        return Ok((None, attributes));
+    }
+
+    match request.password.as_deref() {
+        Some(password) if !password.is_empty() => {
+            policy.validate(password).map_err(|err| err.to_string())?;
+            Ok((Some(password.to_string()), attributes))
+        }
+        _ => Err("password must be specified".to_string()),
+    }
+}
+
+///
+/// 編集リクエストの検証
+///
+/// # 引数
+/// * `request` - 編集リクエスト
+/// * `current` - 現在のユーザ情報
+/// * `policy` - パスワードの強度要件
+///
+/// # 戻り値
+/// 属性の置き換えが指定されている場合は検証済みの属性集合を返す。
+/// 検証に失敗した場合はエラーメッセージを返す。
+///
+fn validate_edit_request(
+    request: &EditUserRequest,
+    current: &UserInfo,
+    policy: &PasswordPolicy,
+) -> Result<Option<UserAttributeSet>, String> {
+    if request.display_name.is_none()
+        && request.password.is_none()
+        && request.attributes.is_none()
+        && request.disabled.is_none()
+        && !request.unlock
+    {
+        return Err("no update specified".to_string());
+    }
+
+    if let Some(password) = request.password.as_deref() {
+        if password.is_empty() {
+            return Err("password must not be empty".to_string());
+        }
+        policy.validate(password).map_err(|err| err.to_string())?;
+    }
+
+    let attributes = match request.attributes.as_ref() {
+        Some(raw) => Some(parse_attributes(raw)?),
+        None => None,
+    };
+
+    /*
+     * `NoBasicAuth` を外す場合はパスワード再設定を必須とする
+     */
+    let removes_no_basic = current
+        .attributes()
+        .contains(UserAttribute::NoBasicAuth)
+        && attributes
+            .as_ref()
+            .is_some_and(|attrs| !attrs.contains(UserAttribute::NoBasicAuth));
+
+    if removes_no_basic && request.password.is_none() {
+        return Err(
+            "password must be specified when removing NoBasicAuth".to_string()
+        );
+    }
+
+    Ok(attributes)
+}
+
+///
+/// 属性名一覧の解析
+///
+/// # 引数
+/// * `raw_attributes` - 属性名の一覧
+///
+/// # 戻り値
+/// 解析済みの属性集合を返す。
+///
+fn parse_attributes(
+    raw_attributes: &[String],
+) -> Result<UserAttributeSet, String> {
+    let mut attributes = UserAttributeSet::new();
+
+    for raw in raw_attributes {
+        let attribute = UserAttribute::try_from(raw.trim())
+            .map_err(|err| err.to_string())?;
+        attributes.insert(attribute);
+    }
+
+    Ok(attributes)
+}
+
+///
+/// 更新操作の監査要約を生成する
+///
+/// # 引数
+/// * `username` - 対象ユーザ名
+/// * `request` - 編集リクエスト
+/// * `user` - 更新後のユーザ情報
+///
+/// # 戻り値
+/// 変更項目を列挙した要約文字列を返す(パスワードは値を含めない)。
+///
+fn format_update_summary(
+    username: &str,
+    request: &EditUserRequest,
+    user: &UserInfo,
+) -> String {
+    let mut summary = format!("user={}", username);
+
+    if request.display_name.is_some() {
+        summary.push_str(" display_name");
+    }
+
+    if request.password.is_some() {
+        summary.push_str(" password_reset");
+    }
+
+    if request.attributes.is_some() {
+        summary.push_str(&format!(
+            " attributes={}",
+            format_attributes(&user.attributes()),
+        ));
+    }
+
+    if let Some(disabled) = request.disabled {
+        summary.push_str(&format!(" disabled={}", disabled));
+    }
+
+    if request.unlock {
+        summary.push_str(" unlock");
+    }
+
+    summary
+}
+
+///
+/// ユーザ情報のJSON表現を生成する
+///
+/// # 引数
+/// * `user` - ユーザ情報
+///
+/// # 戻り値
+/// レスポンス用のJSON値を返す。
+///
+fn user_info_json(user: &UserInfo) -> Value {
+    json!({
+        "id": user.id().to_string(),
+        "username": user.username(),
+        "display_name": user.display_name(),
+        "attributes": user
+            .attributes()
+            .iter()
+            .map(|attribute| attribute.as_str())
+            .collect::<Vec<_>>(),
+        "mfa_enrolled": user.is_mfa_enrolled(),
+        "disabled": user.is_disabled(),
+        "disabled_at": user.disabled_at().map(format_timestamp),
+        "last_activity": user.last_activity().map(format_timestamp),
+        "failed_logins": user.failed_logins(),
+        "locked_until": user
+            .locked_until()
+            .filter(|_| user.is_locked(Local::now()))
+            .map(format_timestamp),
+        "timestamp": format_timestamp(user.timestamp()),
+    })
+}
+
+///
+/// 属性集合の表示文字列を生成する
+///
+/// # 引数
+/// * `attributes` - 属性集合
+///
+/// # 戻り値
+/// カンマ区切りの属性表示文字列を返す。
+///
+fn format_attributes(attributes: &UserAttributeSet) -> String {
+    attributes
+        .iter()
+        .map(|attribute| attribute.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+///
+/// タイムスタンプをISO8601(タイムゾーン無し)へ整形する
+///
+fn format_timestamp(timestamp: DateTime<Local>) -> String {
+    timestamp.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+///
+/// JSONレスポンスの生成
+///
+fn json_response(status: StatusCode, body: Value) -> HttpResponse {
+    HttpResponse::build(status)
+        .content_type("application/json")
+        .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
+        .body(body.to_string())
+}
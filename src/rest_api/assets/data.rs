@@ -8,13 +8,24 @@
 //! アセットデータ取得APIの実装をまとめたモジュール
 //!
 
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
 use std::sync::{Arc, RwLock};
 
 use actix_web::http::{StatusCode, header};
 use actix_web::{HttpRequest, HttpResponse, web};
+use futures::{Stream, stream};
+use serde::Deserialize;
 
 use super::super::resp_error_json;
-use crate::database::types::{AssetId, BearerScope};
+use crate::asset_image;
+use crate::database::types::{
+    AssetHash,
+    AssetId,
+    AssetInfo,
+    AssetRevisionInfo,
+    BearerScope,
+};
 use crate::http_server::app_state::AppState;
 use crate::rest_api::{
     build_etag,
@@ -24,11 +35,21 @@ use crate::rest_api::{
     CACHE_CONTROL_REVALIDATE_PRIVATE,
 };
 
+/// ストリーム送信時に一度に読み出すサイズ(バイト)
+const STREAM_CHUNK_SIZE: u64 = 64 * 1024;
+
+#[derive(Deserialize)]
+struct DataQuery {
+    rev: Option<String>,
+    w: Option<String>,
+}
+
 ///
-/// GET /api/assets/{asset_id}/data の実体
+/// GET /api/assets/{asset_id}/data[?rev={revision}][&w={width}] の実体
 ///
 /// # 概要
-/// アセットの本体データを取得する。
+/// アセットの本体データを取得する。リビジョン指定時は指定リビジョンの
+/// データを返し、幅指定時は画像の縮小版を返す。
 ///
 /// # 引数
 /// * `state` - 共有状態
@@ -46,6 +67,45 @@ pub async fn get(
         return Ok(resp);
     }
 
+    /*
+     * クエリ取得と検証
+     */
+    let query = match web::Query::<DataQuery>::from_query(req.query_string()) {
+        Ok(query) => query,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "invalid query parameter: rev",
+            ));
+        }
+    };
+
+    let revision = match query.rev.as_deref() {
+        Some(raw) => match raw.parse::<u64>() {
+            Ok(value) => Some(value),
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::BAD_REQUEST,
+                    "invalid query parameter: rev",
+                ));
+            }
+        },
+        None => None,
+    };
+
+    let width = match query.w.as_deref() {
+        Some(raw) => match raw.parse::<u32>() {
+            Ok(value) if value > 0 => Some(value),
+            _ => {
+                return Ok(resp_error_json(
+                    StatusCode::BAD_REQUEST,
+                    "invalid query parameter: w",
+                ));
+            }
+        },
+        None => None,
+    };
+
     /*
      * アセットID解析
      */
@@ -54,6 +114,13 @@ pub async fn get(
         Err(resp) => return Ok(resp),
     };
 
+    /*
+     * 縮小版の取得
+     */
+    if let Some(width) = width {
+        return Ok(get_variant(&req, state, &asset_id, revision, width).await);
+    }
+
     /*
      * 共有状態取得
      */
@@ -70,16 +137,221 @@ pub async fn get(
     /*
      * アセット情報取得
      */
-    let asset_info = match state.db().get_asset_info_by_id(&asset_id) {
+    let asset_info = match lookup_asset_info(&state, &asset_id) {
+        Ok(info) => info,
+        Err(resp) => return Ok(resp),
+    };
+    let content_disposition = attachment_disposition(&asset_info);
+
+    /*
+     * 対象リビジョンの解決
+     */
+    let revision_info = match resolve_revision_info(&state, &asset_info, revision)
+    {
+        Ok(info) => info,
+        Err(resp) => return Ok(resp),
+    };
+
+    let hash = match revision_info.hash() {
+        Some(hash) => hash,
+        None => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "asset read failed",
+            ));
+        }
+    };
+
+    /*
+     * 条件付きGETの判定(現在のリビジョンのみ)
+     */
+    let etag = match (revision, asset_info.instance_id()) {
+        (None, Some(instance_id)) => Some(build_etag(instance_id.to_string())),
+        _ => None,
+    };
+
+    if let Some(etag) = &etag
+        && if_none_match_matches(&req, etag)
+    {
+        return Ok(HttpResponse::NotModified()
+            .insert_header((
+                header::CACHE_CONTROL,
+                CACHE_CONTROL_REVALIDATE_PRIVATE,
+            ))
+            .insert_header((header::ETAG, etag.clone()))
+            .finish());
+    }
+
+    /*
+     * 実体ファイルのオープン
+     */
+    let mut file = match fs::File::open(state.db().asset_blob_path(&hash)) {
+        Ok(file) => file,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "asset read failed",
+            ));
+        }
+    };
+    let size = match file.metadata() {
+        Ok(meta) => meta.len(),
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "asset read failed",
+            ));
+        }
+    };
+
+    /*
+     * 範囲指定の評価
+     */
+    let range = match parse_range_request(&req, size, etag.as_deref()) {
+        RangeRequest::Full => None,
+        RangeRequest::Partial(start, end) => Some((start, end)),
+        RangeRequest::Unsatisfiable => {
+            let mut resp = resp_error_json(
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                "range not satisfiable",
+            );
+            if let Ok(value) =
+                header::HeaderValue::from_str(&format!("bytes */{}", size))
+            {
+                resp.headers_mut().insert(header::CONTENT_RANGE, value);
+            }
+            return Ok(resp);
+        }
+    };
+
+    /*
+     * レスポンス生成
+     */
+    let mut builder = match range {
+        Some(_) => HttpResponse::PartialContent(),
+        None => HttpResponse::Ok(),
+    };
+    builder
+        .content_type(revision_info.mime())
+        .insert_header((header::CONTENT_DISPOSITION, content_disposition))
+        .insert_header((header::ACCEPT_RANGES, "bytes"));
+
+    match etag {
+        Some(etag) => {
+            builder
+                .insert_header((
+                    header::CACHE_CONTROL,
+                    CACHE_CONTROL_REVALIDATE_PRIVATE,
+                ))
+                .insert_header((header::ETAG, etag));
+        }
+        None => {
+            builder.insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE));
+        }
+    }
+
+    let length = match range {
+        Some((start, end)) => {
+            if file.seek(SeekFrom::Start(start)).is_err() {
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "asset read failed",
+                ));
+            }
+            builder.insert_header((
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, size),
+            ));
+            end - start + 1
+        }
+        None => size,
+    };
+
+    Ok(builder
+        .no_chunking(length)
+        .streaming(file_stream(file, length)))
+}
+
+///
+/// 縮小版取得の実体
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+/// * `asset_id` - アセットID
+/// * `revision` - 対象リビジョン(`None`の場合は現在のリビジョン)
+/// * `width` - 要求された幅
+///
+/// # 戻り値
+/// 縮小版(縮小の必要が無い場合は元画像)のレスポンスを返す。
+///
+/// # 注記
+/// 共有状態の読み取りロックはアセット情報の解決後に解放し、縮小版の生成
+/// 中は保持しない。
+///
+async fn get_variant(
+    req: &HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    asset_id: &AssetId,
+    revision: Option<u64>,
+    width: u32,
+) -> HttpResponse {
+    /*
+     * アセット情報と縮小版要求の解決
+     */
+    let (variant, content_disposition) = {
+        let state = match state.read() {
+            Ok(state) => state,
+            Err(_) => {
+                return resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "state lock failed",
+                );
+            }
+        };
+
+        let asset_info = match lookup_asset_info(&state, asset_id) {
+            Ok(info) => info,
+            Err(resp) => return resp,
+        };
+
+        match resolve_variant(req, &state, &asset_info, revision, width) {
+            Ok(variant) => (variant, attachment_disposition(&asset_info)),
+            Err(resp) => return resp,
+        }
+    };
+
+    /*
+     * 縮小版の生成とレスポンス生成
+     */
+    variant_response(state, variant, content_disposition).await
+}
+
+///
+/// アセット情報の取得
+///
+/// # 引数
+/// * `state` - 共有状態
+/// * `asset_id` - アセットID
+///
+/// # 戻り値
+/// 削除されていないアセットの情報を返す。存在しない場合や削除済みの場合
+/// はエラーレスポンスを返す。
+///
+fn lookup_asset_info(
+    state: &AppState,
+    asset_id: &AssetId,
+) -> Result<AssetInfo, HttpResponse> {
+    let asset_info = match state.db().get_asset_info_by_id(asset_id) {
         Ok(Some(info)) => info,
         Ok(None) => {
-            return Ok(resp_error_json(
+            return Err(resp_error_json(
                 StatusCode::NOT_FOUND,
                 "asset not found",
             ));
         }
         Err(_) => {
-            return Ok(resp_error_json(
+            return Err(resp_error_json(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "asset lookup failed",
             ));
@@ -87,16 +359,96 @@ pub async fn get(
     };
 
     if asset_info.deleted() {
-        return Ok(resp_error_json(StatusCode::GONE, "asset deleted"));
+        return Err(resp_error_json(StatusCode::GONE, "asset deleted"));
+    }
+
+    Ok(asset_info)
+}
+
+///
+/// ダウンロード用`Content-Disposition`の生成
+///
+/// # 引数
+/// * `asset_info` - 対象アセットの情報
+///
+fn attachment_disposition(asset_info: &AssetInfo) -> header::ContentDisposition {
+    header::ContentDisposition {
+        disposition: header::DispositionType::Attachment,
+        parameters: vec![header::DispositionParam::Filename(
+            asset_info.file_name().to_string(),
+        )],
     }
+}
 
+///
+/// 縮小版要求の解決結果
+///
+struct VariantRequest {
+    /// 元画像の内容ハッシュ値
+    hash: AssetHash,
+
+    /// 元画像のMIME種別
+    mime: String,
+
+    /// 候補値へ丸めた幅
+    width: u32,
+
+    /// 付与するETag(付与しない場合は`None`)
+    etag: Option<String>,
+}
+
+///
+/// 縮小版取得の失敗要因
+///
+enum VariantError {
+    /// 共有状態のロック取得失敗
+    StateLock,
+
+    /// 元画像の読み込み失敗
+    Read,
+
+    /// 縮小版の生成失敗
+    Resize(anyhow::Error),
+}
+
+///
+/// 縮小版要求の解決
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+/// * `asset_info` - 対象アセットの情報
+/// * `revision` - 対象リビジョン(`None`の場合は現在のリビジョン)
+/// * `width` - 要求された幅
+///
+/// # 戻り値
+/// 縮小版の生成に必要な情報を返す。条件付きGETが一致した場合やエラーの
+/// 場合は、そのまま返すレスポンスを返す。
+///
+fn resolve_variant(
+    req: &HttpRequest,
+    state: &AppState,
+    asset_info: &AssetInfo,
+    revision: Option<u64>,
+    width: u32,
+) -> Result<VariantRequest, HttpResponse> {
     /*
-     * アセットデータ取得
+     * 対象リビジョンの解決
      */
-    let data = match state.db().read_asset_data(&asset_id) {
-        Ok(data) => data,
-        Err(_) => {
-            return Ok(resp_error_json(
+    let revision_info = resolve_revision_info(state, asset_info, revision)?;
+
+    let mime = revision_info.mime();
+    if !asset_image::is_resizable_mime(&mime) {
+        return Err(resp_error_json(
+            StatusCode::BAD_REQUEST,
+            "asset is not a resizable image",
+        ));
+    }
+
+    let hash = match revision_info.hash() {
+        Some(hash) => hash,
+        None => {
+            return Err(resp_error_json(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "asset read failed",
             ));
@@ -104,46 +456,283 @@ pub async fn get(
     };
 
     /*
-     * レスポンス生成
+     * 条件付きGETの判定(現在のリビジョンのみ)
      */
-    let content_disposition = header::ContentDisposition {
-        disposition: header::DispositionType::Attachment,
-        parameters: vec![header::DispositionParam::Filename(
-            asset_info.file_name().to_string(),
-        )],
+    let width = asset_image::snap_variant_width(width);
+    let etag = match (revision, asset_info.instance_id()) {
+        (None, Some(instance_id)) => {
+            Some(build_etag(format!("{}-w{}", instance_id, width)))
+        }
+        _ => None,
     };
 
-    if let Some(instance_id) = asset_info.instance_id() {
-        let etag = build_etag(instance_id.to_string());
-        if if_none_match_matches(&req, &etag) {
-            return Ok(HttpResponse::NotModified()
+    if let Some(etag) = &etag
+        && if_none_match_matches(req, etag)
+    {
+        return Err(HttpResponse::NotModified()
+            .insert_header((
+                header::CACHE_CONTROL,
+                CACHE_CONTROL_REVALIDATE_PRIVATE,
+            ))
+            .insert_header((header::ETAG, etag.clone()))
+            .finish());
+    }
+
+    Ok(VariantRequest { hash, mime, width, etag })
+}
+
+///
+/// 縮小版レスポンスの生成
+///
+/// # 引数
+/// * `state` - 共有状態
+/// * `variant` - 縮小版要求の解決結果
+/// * `content_disposition` - 付与する`Content-Disposition`
+///
+/// # 戻り値
+/// 縮小版(縮小の必要が無い場合は元画像)のレスポンスを返す。
+///
+/// # 注記
+/// 画像のデコードと縮小はブロッキングスレッドで行う。呼び出し側は共有
+/// 状態の読み取りロックを解放してから呼び出すこと。
+///
+async fn variant_response(
+    state: web::Data<Arc<RwLock<AppState>>>,
+    variant: VariantRequest,
+    content_disposition: header::ContentDisposition,
+) -> HttpResponse {
+    let VariantRequest { hash, mime, width, etag } = variant;
+
+    /*
+     * 縮小版の取得(縮小不要の場合は元画像)
+     */
+    let result = tokio::task::spawn_blocking(move || {
+        let state = state.read().map_err(|_| VariantError::StateLock)?;
+
+        match state.db().load_asset_variant(&hash, &mime, width) {
+            Ok(Some((data, format))) => Ok((data, format.mime().to_string())),
+            Ok(None) => fs::read(state.db().asset_blob_path(&hash))
+                .map(|data| (data, mime))
+                .map_err(|_| VariantError::Read),
+            Err(err) => Err(VariantError::Resize(err)),
+        }
+    })
+    .await;
+
+    let (data, content_type) = match result {
+        Ok(Ok(variant)) => variant,
+        Ok(Err(VariantError::StateLock)) => {
+            return resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            );
+        }
+        Ok(Err(VariantError::Read)) | Err(_) => {
+            return resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "asset read failed",
+            );
+        }
+        Ok(Err(VariantError::Resize(err))) => {
+            log::warn!("asset resize failed: {:?}", err);
+            return resp_error_json(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "asset resize failed",
+            );
+        }
+    };
+
+    /*
+     * レスポンス生成
+     */
+    let mut builder = HttpResponse::Ok();
+    builder
+        .content_type(content_type)
+        .insert_header((header::CONTENT_DISPOSITION, content_disposition));
+
+    match etag {
+        Some(etag) => {
+            builder
                 .insert_header((
                     header::CACHE_CONTROL,
                     CACHE_CONTROL_REVALIDATE_PRIVATE,
                 ))
-                .insert_header((header::ETAG, etag))
-                .finish());
+                .insert_header((header::ETAG, etag));
         }
+        None => {
+            builder.insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE));
+        }
+    }
 
-        return Ok(HttpResponse::Ok()
-            .content_type(asset_info.mime())
-            .insert_header((header::CONTENT_DISPOSITION, content_disposition))
-            .insert_header((
-                header::CACHE_CONTROL,
-                CACHE_CONTROL_REVALIDATE_PRIVATE,
-            ))
-            .insert_header((header::ETAG, etag))
-            .body(data));
+    builder.body(data)
+}
+
+///
+/// 対象リビジョン情報の解決
+///
+/// # 引数
+/// * `state` - 共有状態
+/// * `asset_info` - 対象アセットの情報
+/// * `revision` - 対象リビジョン(`None`の場合は現在のリビジョン)
+///
+/// # 戻り値
+/// 対象リビジョンの情報を返す。存在しない場合は404レスポンスを返す。
+///
+fn resolve_revision_info(
+    state: &AppState,
+    asset_info: &AssetInfo,
+    revision: Option<u64>,
+) -> Result<AssetRevisionInfo, HttpResponse> {
+    let revision = match revision {
+        Some(revision) if revision != asset_info.revision() => revision,
+        _ => return Ok(asset_info.current_revision()),
+    };
+
+    let revisions = match state.db().list_asset_revisions(&asset_info.id()) {
+        Ok(revisions) => revisions,
+        Err(_) => {
+            return Err(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "asset lookup failed",
+            ));
+        }
+    };
+
+    revisions
+        .into_iter()
+        .find(|info| info.revision() == revision)
+        .ok_or_else(|| {
+            resp_error_json(StatusCode::NOT_FOUND, "revision not found")
+        })
+}
+
+///
+/// 範囲指定の評価結果
+///
+enum RangeRequest {
+    /// 全体を返す
+    Full,
+
+    /// 指定範囲(先頭, 末尾)を返す(いずれも含む)
+    Partial(u64, u64),
+
+    /// 指定範囲を満たせない
+    Unsatisfiable,
+}
+
+///
+/// Range/If-Rangeヘッダの評価
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `size` - データ全体のサイズ(バイト)
+/// * `etag` - 現在のETag(付与しない場合は`None`)
+///
+/// # 戻り値
+/// 評価結果を返す。
+///
+/// # 注記
+/// 単一範囲のみをサポートし、複数範囲や解析できない指定は無視して全体を
+/// 返す。`If-Range`はETagの強い比較のみを行い、一致しない場合(日付指定
+/// を含む)は全体を返す。
+///
+fn parse_range_request(
+    req: &HttpRequest,
+    size: u64,
+    etag: Option<&str>,
+) -> RangeRequest {
+    let raw = match req
+        .headers()
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(raw) => raw.trim(),
+        None => return RangeRequest::Full,
+    };
+
+    /*
+     * If-Rangeの照合
+     */
+    if let Some(if_range) = req.headers().get(header::IF_RANGE) {
+        let matched = match (if_range.to_str(), etag) {
+            (Ok(value), Some(etag)) => value.trim() == etag,
+            _ => false,
+        };
+        if !matched {
+            return RangeRequest::Full;
+        }
     }
 
-    Ok(HttpResponse::Ok()
-        .content_type(asset_info.mime())
-        .insert_header((header::CONTENT_DISPOSITION, content_disposition))
-        .insert_header((
-            header::CACHE_CONTROL,
-            CACHE_CONTROL_NO_STORE,
-        ))
-        .body(data))
+    /*
+     * 範囲指定の解析
+     */
+    let spec = match raw.strip_prefix("bytes=") {
+        Some(spec) if !spec.contains(',') => spec.trim(),
+        _ => return RangeRequest::Full,
+    };
+
+    let (first, last) = match spec.split_once('-') {
+        Some(pair) => pair,
+        None => return RangeRequest::Full,
+    };
+
+    match (first.trim(), last.trim()) {
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => RangeRequest::Unsatisfiable,
+            Ok(_) if size == 0 => RangeRequest::Unsatisfiable,
+            Ok(len) => RangeRequest::Partial(size.saturating_sub(len), size - 1),
+            Err(_) => RangeRequest::Full,
+        },
+
+        (start, "") => match start.parse::<u64>() {
+            Ok(start) if start >= size => RangeRequest::Unsatisfiable,
+            Ok(start) => RangeRequest::Partial(start, size - 1),
+            Err(_) => RangeRequest::Full,
+        },
+
+        (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if start <= end => {
+                if start >= size {
+                    RangeRequest::Unsatisfiable
+                } else {
+                    RangeRequest::Partial(start, end.min(size - 1))
+                }
+            }
+            _ => RangeRequest::Full,
+        },
+    }
+}
+
+///
+/// ファイル読み出しストリームの生成
+///
+/// # 引数
+/// * `file` - 読み出し位置へシーク済みのファイル
+/// * `length` - 読み出すバイト数
+///
+/// # 戻り値
+/// 一定サイズ毎にデータを返すストリームを返す。データ全体をメモリに展開
+/// しない。
+///
+fn file_stream(
+    file: fs::File,
+    length: u64,
+) -> impl Stream<Item = Result<web::Bytes, std::io::Error>> {
+    stream::unfold((file, length), |(mut file, remaining)| async move {
+        if remaining == 0 {
+            return None;
+        }
+
+        let mut buf = vec![0u8; remaining.min(STREAM_CHUNK_SIZE) as usize];
+        match file.read(&mut buf) {
+            Ok(0) => None,
+            Ok(len) => {
+                buf.truncate(len);
+                Some((Ok(web::Bytes::from(buf)), (file, remaining - len as u64)))
+            }
+            Err(err) => Some((Err(err), (file, 0))),
+        }
+    })
 }
 
 ///
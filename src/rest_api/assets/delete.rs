@@ -14,6 +14,7 @@ use actix_web::http::StatusCode;
 use actix_web::{HttpMessage, HttpRequest, HttpResponse, web};
 
 use super::super::resp_error_json;
+use super::reindex_asset_fts;
 use crate::database::DbError;
 use crate::database::types::{AssetId, BearerScope};
 use crate::http_server::app_state::AppState;
@@ -184,6 +185,13 @@ pub async fn delete(
         }
     }
 
+    /*
+     * FTSの更新
+     */
+    if let Err(resp) = reindex_asset_fts(&state, &asset_id) {
+        return Ok(resp);
+    }
+
     /*
      * レスポンス生成
      */
@@ -212,7 +220,7 @@ fn parse_asset_id(raw: String) -> Result<AssetId, HttpResponse> {
 ///
 /// ロック解除トークンの解析
 ///
-fn parse_lock_token(
+pub(super) fn parse_lock_token(
     req: &HttpRequest,
 ) -> Result<crate::database::types::LockToken, HttpResponse> {
     /*
@@ -112,8 +112,13 @@ pub async fn get(
         "file_name": asset_info.file_name(),
         "mime_type": asset_info.mime(),
         "size": asset_info.size(),
+        "revision": asset_info.revision(),
+        "sha256": asset_info.hash().map(|hash| hash.to_hex()),
+        "width": asset_info.dimensions().map(|(width, _)| width),
+        "height": asset_info.dimensions().map(|(_, height)| height),
         "timestamp": timestamp,
         "username": user_name,
+        "shared": asset_info.is_shared(),
     });
 
     Ok(HttpResponse::Ok()
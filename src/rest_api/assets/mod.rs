@@ -11,17 +11,26 @@
 pub(crate) mod data;
 pub(crate) mod delete;
 pub(crate) mod meta;
+pub(crate) mod refs;
+pub(crate) mod revision;
+pub(crate) mod upload;
 
+use std::fs;
+use std::io::Write;
+use std::path::Path;
 use std::sync::{Arc, RwLock};
 
 use actix_web::http::{header, StatusCode};
 use actix_web::{HttpMessage, HttpRequest, HttpResponse, web};
+use chrono::SecondsFormat;
+use futures::StreamExt;
 use serde::Deserialize;
 use serde_json::json;
 
 use super::resp_error_json;
 use crate::database::DbError;
-use crate::database::types::{BearerScope, LockToken};
+use crate::database::types::{AssetId, BearerScope, LockToken, PageId};
+use crate::fts;
 use crate::http_server::app_state::AppState;
 use crate::rest_api::{
     AuthContext,
@@ -36,18 +45,38 @@ const LOCK_AUTH_HEADER: &str = "X-Lock-Authentication";
 struct AssetQuery {
     path: Option<String>,
     file: Option<String>,
+    name: Option<String>,
+    mime: Option<String>,
+    user: Option<String>,
+    scope: Option<String>,
 }
 
 ///
-/// POST /api/assets?path={page_path}&file={file_name} の実体
+/// アセット一覧の絞り込み範囲
+///
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum AssetScope {
+    /// 全アセット
+    All,
+
+    /// 共有アセットのみ
+    Shared,
+
+    /// ページ所属アセットのみ
+    Page,
+}
+
+///
+/// POST /api/assets?[path={page_path}&]file={file_name} の実体
 ///
 /// # 概要
-/// アセットをアップロードする。
+/// アセットをアップロードする。`path`を省略した場合はページに所属しない
+/// 共有アセットとして登録する。
 ///
 /// # 引数
 /// * `req` - HTTPリクエスト
 /// * `state` - 共有状態
-/// * `body` - アセットデータ
+/// * `payload` - アセットデータ
 ///
 /// # 戻り値
 /// actix-webのレスポンスオブジェクト
@@ -55,7 +84,7 @@ struct AssetQuery {
 pub async fn post(
     req: HttpRequest,
     state: web::Data<Arc<RwLock<AppState>>>,
-    body: web::Bytes,
+    payload: web::Payload,
 ) -> actix_web::Result<HttpResponse> {
     if let Err(resp) = require_request_scope(&req, BearerScope::Write) {
         return Ok(resp);
@@ -74,16 +103,10 @@ pub async fn post(
         }
     };
 
-    let path = match query.path.as_deref() {
-        Some(path) => path,
-        None => {
-            return Ok(resp_error_json(
-                StatusCode::BAD_REQUEST,
-                "invalid query parameter: path",
-            ));
-        }
-    };
-    if let Err(message) = crate::rest_api::pages::validate_page_path(&path) {
+    let path = query.path.as_deref();
+    if let Some(path) = path
+        && let Err(message) = crate::rest_api::pages::validate_page_path(path)
+    {
         return Ok(resp_error_json(StatusCode::BAD_REQUEST, message));
     }
 
@@ -135,9 +158,57 @@ pub async fn post(
     /*
      * 共有状態取得
      */
-    let state = match state.read() {
+    let shared = state;
+    let (page_id, staged_path, limit) = {
+        let state = match shared.read() {
+            Ok(state) => state,
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "state lock failed",
+                ));
+            }
+        };
+
+        /*
+         * 所属ページの解決(共有アセットの場合は省略)
+         */
+        let page_id = match path {
+            Some(path) => {
+                match resolve_upload_page(&req, &state, path, &auth_user) {
+                    Ok(page_id) => Some(page_id),
+                    Err(resp) => return Ok(resp),
+                }
+            }
+            None => None,
+        };
+
+        /*
+         * アセットデータの受信(受信中は共有状態のロックを解放する)
+         */
+        let staged_path = match state.db().new_asset_staging_path() {
+            Ok(path) => path,
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "asset create failed",
+                ));
+            }
+        };
+        let limit = state.asset_limit_size();
+        (page_id, staged_path, limit)
+    };
+
+    if let Err(resp) =
+        receive_asset_payload(&req, payload, &staged_path, limit).await
+    {
+        return Ok(resp);
+    }
+
+    let state = match shared.read() {
         Ok(state) => state,
         Err(_) => {
+            let _ = fs::remove_file(&staged_path);
             return Ok(resp_error_json(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "state lock failed",
@@ -145,16 +216,113 @@ pub async fn post(
         }
     };
 
+    /*
+     * アセット作成
+     */
+    let result = match &page_id {
+        Some(page_id) => state.db().create_asset_from_file(
+            page_id,
+            file_name,
+            &mime,
+            &auth_user,
+            &staged_path,
+        ),
+        None => state.db().create_shared_asset_from_file(
+            file_name,
+            &mime,
+            &auth_user,
+            &staged_path,
+        ),
+    };
+    let asset_id =
+        match result {
+            Ok(asset_id) => asset_id,
+            Err(err) => {
+                if let Some(DbError::AssetAlreadyExists) =
+                    err.downcast_ref::<DbError>()
+                {
+                    return Ok(resp_error_json(
+                        StatusCode::CONFLICT,
+                        "asset already exists",
+                    ));
+                }
+                if let Some(DbError::PageNotFound) =
+                    err.downcast_ref::<DbError>()
+                {
+                    return Ok(resp_error_json(
+                        StatusCode::NOT_FOUND,
+                        "page not found",
+                    ));
+                }
+                if let Some(DbError::UserNotFound) =
+                    err.downcast_ref::<DbError>()
+                {
+                    return Ok(resp_error_json(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "user not found",
+                    ));
+                }
+
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "asset create failed",
+                ));
+            }
+        };
+
+    /*
+     * FTSの更新
+     */
+    if let Err(resp) = reindex_asset_fts(&state, &asset_id) {
+        return Ok(resp);
+    }
+
+    /*
+     * レスポンス生成
+     */
+    let body = json!({
+        "id": asset_id.to_string(),
+    });
+    let location = format!("/api/assets/{}/data", asset_id);
+
+    Ok(HttpResponse::Created()
+        .content_type("application/json")
+        .insert_header((header::LOCATION, location))
+        .insert_header((header::ETAG, asset_id.to_string()))
+        .body(body.to_string()))
+}
+
+///
+/// アップロード先ページの解決
+///
+/// # 概要
+/// ページパスからページIDを解決し、ページロックを検証する。
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+/// * `path` - ページパス
+/// * `auth_user` - 認証ユーザ名
+///
+/// # 戻り値
+/// 解決したページIDを返す。失敗した場合はエラーレスポンスを返す。
+///
+fn resolve_upload_page(
+    req: &HttpRequest,
+    state: &AppState,
+    path: &str,
+    auth_user: &str,
+) -> Result<PageId, HttpResponse> {
     /*
      * ページIDの解決
      */
-    let page_id = match state.db().get_page_id_by_path(&path) {
+    let page_id = match state.db().get_page_id_by_path(path) {
         Ok(Some(page_id)) => page_id,
         Ok(None) => {
-            return Ok(resp_error_json(StatusCode::NOT_FOUND, "page not found"));
+            return Err(resp_error_json(StatusCode::NOT_FOUND, "page not found"));
         }
         Err(_) => {
-            return Ok(resp_error_json(
+            return Err(resp_error_json(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "page lookup failed",
             ));
@@ -164,10 +332,10 @@ pub async fn post(
     let page_index = match state.db().get_page_index_by_id(&page_id) {
         Ok(Some(index)) => index,
         Ok(None) => {
-            return Ok(resp_error_json(StatusCode::NOT_FOUND, "page not found"));
+            return Err(resp_error_json(StatusCode::NOT_FOUND, "page not found"));
         }
         Err(_) => {
-            return Ok(resp_error_json(
+            return Err(resp_error_json(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "page lookup failed",
             ));
@@ -175,7 +343,7 @@ pub async fn post(
     };
 
     if page_index.deleted() {
-        return Ok(resp_error_json(StatusCode::GONE, "page deleted"));
+        return Err(resp_error_json(StatusCode::GONE, "page deleted"));
     }
 
     /*
@@ -184,7 +352,7 @@ pub async fn post(
     let lock_info = match state.db().get_page_lock_info(&page_id) {
         Ok(info) => info,
         Err(_) => {
-            return Ok(resp_error_json(
+            return Err(resp_error_json(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "lock lookup failed",
             ));
@@ -193,31 +361,28 @@ pub async fn post(
 
     if let Some(lock_info) = lock_info {
         if !req.headers().contains_key(LOCK_AUTH_HEADER) {
-            return Ok(resp_error_json(StatusCode::LOCKED, "page locked"));
+            return Err(resp_error_json(StatusCode::LOCKED, "page locked"));
         }
 
-        let token = match parse_lock_token(&req) {
-            Ok(token) => token,
-            Err(resp) => return Ok(resp),
-        };
+        let token = parse_lock_token(req)?;
 
         if lock_info.token() != token {
-            return Ok(resp_error_json(
+            return Err(resp_error_json(
                 StatusCode::FORBIDDEN,
                 "lock token invalid",
             ));
         }
 
-        let user_id = match state.db().get_user_id_by_name(&auth_user) {
+        let user_id = match state.db().get_user_id_by_name(auth_user) {
             Ok(Some(user_id)) => user_id,
             Ok(None) => {
-                return Ok(resp_error_json(
+                return Err(resp_error_json(
                     StatusCode::INTERNAL_SERVER_ERROR,
                     "user not found",
                 ));
             }
             Err(_) => {
-                return Ok(resp_error_json(
+                return Err(resp_error_json(
                     StatusCode::INTERNAL_SERVER_ERROR,
                     "user lookup failed",
                 ));
@@ -225,78 +390,19 @@ pub async fn post(
         };
 
         if lock_info.user() != user_id {
-            return Ok(resp_error_json(StatusCode::FORBIDDEN, "lock forbidden"));
+            return Err(resp_error_json(StatusCode::FORBIDDEN, "lock forbidden"));
         }
     }
 
-    /*
-     * アセット作成
-     */
-    let asset_id =
-        match state
-            .db()
-            .create_asset(
-                &page_id,
-                &file_name,
-                &mime,
-                &auth_user,
-                body.as_ref(),
-            )
-        {
-            Ok(asset_id) => asset_id,
-            Err(err) => {
-                if let Some(DbError::AssetAlreadyExists) =
-                    err.downcast_ref::<DbError>()
-                {
-                    return Ok(resp_error_json(
-                        StatusCode::CONFLICT,
-                        "asset already exists",
-                    ));
-                }
-                if let Some(DbError::PageNotFound) =
-                    err.downcast_ref::<DbError>()
-                {
-                    return Ok(resp_error_json(
-                        StatusCode::NOT_FOUND,
-                        "page not found",
-                    ));
-                }
-                if let Some(DbError::UserNotFound) =
-                    err.downcast_ref::<DbError>()
-                {
-                    return Ok(resp_error_json(
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        "user not found",
-                    ));
-                }
-
-                return Ok(resp_error_json(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "asset create failed",
-                ));
-            }
-        };
-
-    /*
-     * レスポンス生成
-     */
-    let body = json!({
-        "id": asset_id.to_string(),
-    });
-    let location = format!("/api/assets/{}/data", asset_id);
-
-    Ok(HttpResponse::Created()
-        .content_type("application/json")
-        .insert_header((header::LOCATION, location))
-        .insert_header((header::ETAG, asset_id.to_string()))
-        .body(body.to_string()))
+    Ok(page_id)
 }
 
 ///
-/// GET /api/assets?path={page_path}&file={file_name} の実体
+/// GET /api/assets?[path={page_path}&]file={file_name} の実体
 ///
 /// # 概要
-/// アセットIDによる取得先へリダイレクトする。
+/// アセットIDによる取得先へリダイレクトする。`path`を省略した場合は共有
+/// アセットを対象とする。`file`を省略した場合はアセット一覧を返す。
 ///
 /// # 引数
 /// * `req` - HTTPリクエスト
@@ -326,32 +432,21 @@ pub async fn get(
         }
     };
 
-    let path = match query.path.as_deref() {
-        Some(path) => path,
-        None => {
-            return Ok(resp_error_json(
-                StatusCode::BAD_REQUEST,
-                "invalid query parameter: path",
-            ));
-        }
-    };
-    if let Err(message) = crate::rest_api::pages::validate_page_path(&path) {
-        return Ok(resp_error_json(StatusCode::BAD_REQUEST, message));
-    }
-
     let file_name = match query.file.as_deref() {
         Some(file_name) => file_name,
-        None => {
-            return Ok(resp_error_json(
-                StatusCode::BAD_REQUEST,
-                "invalid query parameter: file",
-            ));
-        }
+        None => return Ok(list(&state, &query)),
     };
     if let Err(message) = super::validate_asset_file_name(&file_name) {
         return Ok(resp_error_json(StatusCode::BAD_REQUEST, message));
     }
 
+    let path = query.path.as_deref();
+    if let Some(path) = path
+        && let Err(message) = crate::rest_api::pages::validate_page_path(path)
+    {
+        return Ok(resp_error_json(StatusCode::BAD_REQUEST, message));
+    }
+
     /*
      * 共有状態取得
      */
@@ -365,16 +460,92 @@ pub async fn get(
         }
     };
 
+    /*
+     * アセットIDの解決
+     */
+    let asset_id = match path {
+        Some(path) => match resolve_page_asset_id(&state, path, file_name) {
+            Ok(asset_id) => asset_id,
+            Err(resp) => return Ok(resp),
+        },
+        None => match state.db().get_shared_asset_id_by_name(file_name) {
+            Ok(Some(asset_id)) => asset_id,
+            Ok(None) => {
+                return Ok(resp_error_json(
+                    StatusCode::NOT_FOUND,
+                    "asset not found",
+                ));
+            }
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "asset lookup failed",
+                ));
+            }
+        },
+    };
+
+    let asset_info = match state.db().get_asset_info_by_id(&asset_id) {
+        Ok(Some(info)) => info,
+        Ok(None) => {
+            return Ok(resp_error_json(
+                StatusCode::NOT_FOUND,
+                "asset not found",
+            ));
+        }
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "asset lookup failed",
+            ));
+        }
+    };
+
+    if asset_info.deleted() {
+        return Ok(resp_error_json(StatusCode::GONE, "asset deleted"));
+    }
+
+    /*
+     * レスポンス生成
+     */
+    let body = json!({
+        "id": asset_id.to_string(),
+    });
+    let location = format!("/api/assets/{}/data", asset_id);
+
+    Ok(HttpResponse::Found()
+        .content_type("application/json")
+        .insert_header((header::LOCATION, location))
+        .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
+        .body(body.to_string()))
+}
+
+///
+/// ページ所属アセットのアセットID解決
+///
+/// # 引数
+/// * `state` - 共有状態
+/// * `path` - 所属ページのパス
+/// * `file_name` - ファイル名
+///
+/// # 戻り値
+/// 解決したアセットIDを返す。失敗した場合はエラーレスポンスを返す。
+///
+fn resolve_page_asset_id(
+    state: &AppState,
+    path: &str,
+    file_name: &str,
+) -> Result<AssetId, HttpResponse> {
     /*
      * ページIDの解決
      */
-    let page_id = match state.db().get_page_id_by_path(&path) {
+    let page_id = match state.db().get_page_id_by_path(path) {
         Ok(Some(page_id)) => page_id,
         Ok(None) => {
-            return Ok(resp_error_json(StatusCode::NOT_FOUND, "page not found"));
+            return Err(resp_error_json(StatusCode::NOT_FOUND, "page not found"));
         }
         Err(_) => {
-            return Ok(resp_error_json(
+            return Err(resp_error_json(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "page lookup failed",
             ));
@@ -384,10 +555,10 @@ pub async fn get(
     let page_index = match state.db().get_page_index_by_id(&page_id) {
         Ok(Some(index)) => index,
         Ok(None) => {
-            return Ok(resp_error_json(StatusCode::NOT_FOUND, "page not found"));
+            return Err(resp_error_json(StatusCode::NOT_FOUND, "page not found"));
         }
         Err(_) => {
-            return Ok(resp_error_json(
+            return Err(resp_error_json(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "page lookup failed",
             ));
@@ -395,83 +566,299 @@ pub async fn get(
     };
 
     if page_index.deleted() {
-        return Ok(resp_error_json(StatusCode::GONE, "page deleted"));
+        return Err(resp_error_json(StatusCode::GONE, "page deleted"));
     }
 
     /*
      * アセットIDの解決
      */
-    let asset_id = match state
-        .db()
-        .get_asset_id_by_page_file(&page_id, &file_name)
-    {
-        Ok(Some(asset_id)) => asset_id,
+    match state.db().get_asset_id_by_page_file(&page_id, file_name) {
+        Ok(Some(asset_id)) => Ok(asset_id),
         Ok(None) => {
-            match state
-                .db()
-                .has_deleted_asset_by_page_file(&page_id, &file_name)
+            match state.db().has_deleted_asset_by_page_file(&page_id, file_name)
             {
                 Ok(true) => {
-                    return Ok(resp_error_json(
-                        StatusCode::GONE,
-                        "asset deleted",
-                    ));
-                }
-                Ok(false) => {}
-                Err(_) => {
-                    return Ok(resp_error_json(
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        "asset lookup failed",
-                    ));
+                    Err(resp_error_json(StatusCode::GONE, "asset deleted"))
                 }
+                Ok(false) => Err(resp_error_json(
+                    StatusCode::NOT_FOUND,
+                    "asset not found",
+                )),
+                Err(_) => Err(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "asset lookup failed",
+                )),
             }
+        }
+        Err(_) => Err(resp_error_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "asset lookup failed",
+        )),
+    }
+}
 
-            return Ok(resp_error_json(
-                StatusCode::NOT_FOUND,
-                "asset not found",
-            ));
+///
+/// GET /api/assets?[name={keyword}][&mime={mime_type}][&user={user_name}]
+/// [&scope={all|shared|page}] の実体
+///
+/// # 概要
+/// 削除されていないアセットを条件で絞り込んで一覧を返す。
+///
+/// # 引数
+/// * `state` - 共有状態
+/// * `query` - 検索条件
+///
+/// # 戻り値
+/// actix-webのレスポンスオブジェクト
+///
+/// # 注記
+/// 各条件の扱いは以下の通り。
+///  - `name`: ファイル名の部分一致(大文字小文字を区別しない)
+///  - `mime`: MIME種別の完全一致(`image/*`のように末尾を`/*`とした場合は
+///    前方一致)
+///  - `user`: 登録ユーザ名の完全一致
+///  - `scope`: 共有アセット(`shared`)、ページ所属アセット(`page`)、
+///    全て(`all`、既定値)
+///
+fn list(
+    state: &web::Data<Arc<RwLock<AppState>>>,
+    query: &AssetQuery,
+) -> HttpResponse {
+    /*
+     * 検索条件の解釈
+     */
+    let scope = match query.scope.as_deref() {
+        None | Some("all") => AssetScope::All,
+        Some("shared") => AssetScope::Shared,
+        Some("page") => AssetScope::Page,
+        Some(_) => {
+            return resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "invalid query parameter: scope",
+            );
         }
+    };
+    let name = query.name.as_deref().map(str::to_lowercase);
+
+    /*
+     * 共有状態取得
+     */
+    let state = match state.read() {
+        Ok(state) => state,
         Err(_) => {
-            return Ok(resp_error_json(
+            return resp_error_json(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "asset lookup failed",
-            ));
+                "state lock failed",
+            );
         }
     };
 
-    let asset_info = match state.db().get_asset_info_by_id(&asset_id) {
-        Ok(Some(info)) => info,
-        Ok(None) => {
-            return Ok(resp_error_json(
-                StatusCode::NOT_FOUND,
-                "asset not found",
-            ));
-        }
+    /*
+     * 一覧の取得と絞り込み
+     */
+    let mut assets = match state.db().list_assets() {
+        Ok(assets) => assets,
         Err(_) => {
-            return Ok(resp_error_json(
+            return resp_error_json(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "asset lookup failed",
-            ));
+            );
         }
     };
 
-    if asset_info.deleted() {
-        return Ok(resp_error_json(StatusCode::GONE, "asset deleted"));
-    }
+    assets.retain(|asset| {
+        if asset.deleted() || asset.is_zombie() {
+            return false;
+        }
+
+        let scope_matched = match scope {
+            AssetScope::All => true,
+            AssetScope::Shared => asset.is_shared(),
+            AssetScope::Page => !asset.is_shared(),
+        };
+        let name_matched = name.as_deref().is_none_or(|name| {
+            asset.file_name().to_lowercase().contains(name)
+        });
+        let mime_matched = query
+            .mime
+            .as_deref()
+            .is_none_or(|mime| mime_matches(mime, &asset.mime()));
+        let user_matched = query
+            .user
+            .as_deref()
+            .is_none_or(|user| asset.user_name() == user);
+
+        scope_matched && name_matched && mime_matched && user_matched
+    });
+    assets.sort_by(|a, b| {
+        a.file_name()
+            .cmp(&b.file_name())
+            .then_with(|| a.page_path().cmp(&b.page_path()))
+    });
 
     /*
      * レスポンス生成
      */
-    let body = json!({
-        "id": asset_id.to_string(),
-    });
-    let location = format!("/api/assets/{}/data", asset_id);
-
-    Ok(HttpResponse::Found()
+    let items: Vec<_> = assets
+        .iter()
+        .map(|asset| {
+            json!({
+                "id": asset.id().to_string(),
+                "file_name": asset.file_name(),
+                "mime_type": asset.mime(),
+                "size": asset.size(),
+                "timestamp": asset
+                    .timestamp()
+                    .to_rfc3339_opts(SecondsFormat::Secs, true),
+                "username": asset.user_name(),
+                "shared": asset.is_shared(),
+                "page_path": asset.page_path(),
+                "references": asset.references(),
+            })
+        })
+        .collect();
+
+    HttpResponse::Ok()
         .content_type("application/json")
-        .insert_header((header::LOCATION, location))
         .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
-        .body(body.to_string()))
+        .body(json!(items).to_string())
+}
+
+///
+/// MIME種別の条件判定
+///
+/// # 引数
+/// * `pattern` - 検索条件(`image/*`形式の場合は前方一致)
+/// * `mime` - 判定対象のMIME種別
+///
+/// # 戻り値
+/// 条件に一致する場合は`true`を返す。
+///
+fn mime_matches(pattern: &str, mime: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) if prefix.ends_with('/') => mime.starts_with(prefix),
+        _ => pattern.eq_ignore_ascii_case(mime),
+    }
+}
+
+///
+/// アップロードデータの受信
+///
+/// # 概要
+/// リクエストボディを逐次一時ファイルへ書き出す。データ全体をメモリに
+/// 展開しない。
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `payload` - リクエストボディ
+/// * `path` - 書き出し先のパス
+/// * `limit` - 受信可能な最大サイズ(バイト)
+///
+/// # 戻り値
+/// 受信したバイト数を返す。失敗した場合は書き出し途中のファイルを削除
+/// し、エラーレスポンスを返す。
+///
+pub(crate) async fn receive_asset_payload(
+    req: &HttpRequest,
+    mut payload: web::Payload,
+    path: &Path,
+    limit: u64,
+) -> Result<u64, HttpResponse> {
+    /*
+     * 宣言サイズによる事前判定
+     */
+    let declared = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if declared.is_some_and(|size| size > limit) {
+        return Err(resp_error_json(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "asset too large",
+        ));
+    }
+
+    let mut file = match fs::File::create(path) {
+        Ok(file) => file,
+        Err(_) => {
+            return Err(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "asset write failed",
+            ));
+        }
+    };
+
+    /*
+     * 逐次書き出し
+     */
+    let mut size = 0u64;
+    while let Some(chunk) = payload.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(_) => {
+                let _ = fs::remove_file(path);
+                return Err(resp_error_json(
+                    StatusCode::BAD_REQUEST,
+                    "payload read failed",
+                ));
+            }
+        };
+
+        size += chunk.len() as u64;
+        if size > limit {
+            let _ = fs::remove_file(path);
+            return Err(resp_error_json(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "asset too large",
+            ));
+        }
+
+        if file.write_all(&chunk).is_err() {
+            let _ = fs::remove_file(path);
+            return Err(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "asset write failed",
+            ));
+        }
+    }
+
+    if file.sync_data().is_err() {
+        let _ = fs::remove_file(path);
+        return Err(resp_error_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "asset write failed",
+        ));
+    }
+
+    Ok(size)
+}
+
+///
+/// アセット単位でFTSインデックスを更新する
+///
+/// # 引数
+/// * `state` - 共有状態
+/// * `asset_id` - 対象アセットID
+///
+/// # 戻り値
+/// 更新に成功した場合は`Ok(())`を返す。失敗した場合はエラーレスポンスを
+/// 返す。
+///
+pub(crate) fn reindex_asset_fts(
+    state: &AppState,
+    asset_id: &AssetId,
+) -> Result<(), HttpResponse> {
+    if let Err(err) = fts::reindex_asset(state.fts_config(), state.db(), asset_id)
+    {
+        log::error!("fts update failed: {:?}", err);
+        return Err(resp_error_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "fts update failed",
+        ));
+    }
+
+    Ok(())
 }
 
 ///
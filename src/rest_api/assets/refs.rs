@@ -0,0 +1,113 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! アセット参照元取得APIの実装
+//!
+
+use std::sync::{Arc, RwLock};
+
+use actix_web::http::{StatusCode, header};
+use actix_web::{HttpRequest, HttpResponse, web};
+use serde_json::json;
+
+use super::super::resp_error_json;
+use crate::database::DbError;
+use crate::database::types::{AssetId, BearerScope};
+use crate::http_server::app_state::AppState;
+use crate::rest_api::{CACHE_CONTROL_NO_STORE, require_request_scope};
+
+///
+/// GET /api/assets/{asset_id}/refs の実体
+///
+/// # 概要
+/// アセットを埋め込んでいるページの一覧を取得する。
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+/// * `path` - アセットID
+///
+/// # 戻り値
+/// actix-webのレスポンスオブジェクト
+///
+/// # 注記
+/// 削除済みアセットも対象とする(パージ可否の確認に用いるため)。
+///
+pub async fn get(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    if let Err(resp) = require_request_scope(&req, BearerScope::Read) {
+        return Ok(resp);
+    }
+
+    /*
+     * アセットID解析
+     */
+    let asset_id = match AssetId::from_string(&path.into_inner()) {
+        Ok(asset_id) => asset_id,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::NOT_FOUND,
+                "asset not found",
+            ));
+        }
+    };
+
+    /*
+     * 共有状態取得
+     */
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+
+    /*
+     * 参照元ページの取得
+     */
+    let referrers = match state.db().list_asset_referrers(&asset_id) {
+        Ok(referrers) => referrers,
+        Err(err) => {
+            if let Some(DbError::AssetNotFound) = err.downcast_ref::<DbError>()
+            {
+                return Ok(resp_error_json(
+                    StatusCode::NOT_FOUND,
+                    "asset not found",
+                ));
+            }
+
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "asset lookup failed",
+            ));
+        }
+    };
+
+    /*
+     * レスポンス生成
+     */
+    let items: Vec<_> = referrers
+        .into_iter()
+        .map(|(page_id, path)| {
+            json!({
+                "page_id": page_id.to_string(),
+                "path": path,
+            })
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
+        .body(json!(items).to_string()))
+}
@@ -0,0 +1,567 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! アセットリビジョン操作APIの実装をまとめたモジュール
+//!
+
+use std::fs;
+use std::sync::{Arc, RwLock};
+
+use actix_web::http::{StatusCode, header};
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, web};
+use chrono::SecondsFormat;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::super::resp_error_json;
+use super::delete::parse_lock_token;
+use super::{receive_asset_payload, reindex_asset_fts};
+use crate::database::DbError;
+use crate::database::types::{AssetId, AssetInfo, BearerScope, PageId};
+use crate::http_server::app_state::AppState;
+use crate::rest_api::{AuthContext, CACHE_CONTROL_NO_STORE, require_request_scope};
+
+/// ロック認証ヘッダの名称
+const LOCK_AUTH_HEADER: &str = "X-Lock-Authentication";
+
+/// 孤立した user_id を表示する際の代替ユーザ名
+const UNKNOWN_USERNAME: &str = "unknown";
+
+#[derive(Deserialize)]
+struct RevisionQuery {
+    rollback_to: Option<String>,
+}
+
+///
+/// PUT /api/assets/{asset_id}/data の実体
+///
+/// # 概要
+/// アセットの内容を差し替え、新しいリビジョンを登録する。
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+/// * `path` - アセットID
+/// * `payload` - アセットデータ
+///
+/// # 戻り値
+/// actix-webのレスポンスオブジェクト
+///
+pub async fn put(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    path: web::Path<String>,
+    payload: web::Payload,
+) -> actix_web::Result<HttpResponse> {
+    if let Err(resp) = require_request_scope(&req, BearerScope::Write) {
+        return Ok(resp);
+    }
+
+    /*
+     * アセットID解析
+     */
+    let asset_id = match parse_asset_id(path.into_inner()) {
+        Ok(asset_id) => asset_id,
+        Err(resp) => return Ok(resp),
+    };
+
+    /*
+     * Content-Typeの取得
+     */
+    let content_type = match req.headers().get(header::CONTENT_TYPE) {
+        Some(value) => value.to_str().unwrap_or(""),
+        None => "",
+    };
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_string();
+    let mime = if mime.is_empty() {
+        "application/octet-stream".to_string()
+    } else {
+        mime
+    };
+
+    /*
+     * 認証ユーザ取得
+     */
+    let auth_user = match req.extensions().get::<AuthContext>() {
+        Some(context) => context.user_id().to_string(),
+        None => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "auth context missing",
+            ));
+        }
+    };
+
+    /*
+     * 共有状態取得
+     */
+    let shared = state;
+    let (staged_path, limit) = {
+        let state = match shared.read() {
+            Ok(state) => state,
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "state lock failed",
+                ));
+            }
+        };
+
+        /*
+         * アセット情報取得とロック検証
+         */
+        let asset_info = match lookup_asset_info(&state, &asset_id) {
+            Ok(info) => info,
+            Err(resp) => return Ok(resp),
+        };
+
+        if let Err(resp) =
+            check_page_lock(&req, &state, asset_info.page_id(), &auth_user)
+        {
+            return Ok(resp);
+        }
+
+        /*
+         * アセットデータの受信(受信中は共有状態のロックを解放する)
+         */
+        let staged_path = match state.db().new_asset_staging_path() {
+            Ok(path) => path,
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "asset update failed",
+                ));
+            }
+        };
+        let limit = state.asset_limit_size();
+        (staged_path, limit)
+    };
+
+    if let Err(resp) =
+        receive_asset_payload(&req, payload, &staged_path, limit).await
+    {
+        return Ok(resp);
+    }
+
+    let state = match shared.read() {
+        Ok(state) => state,
+        Err(_) => {
+            let _ = fs::remove_file(&staged_path);
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+
+    /*
+     * リビジョン登録
+     */
+    let revision = match state.db().replace_asset_data_from_file(
+        &asset_id,
+        &mime,
+        &auth_user,
+        &staged_path,
+    ) {
+        Ok(revision) => revision,
+        Err(err) => return Ok(resp_db_error(err, "asset update failed")),
+    };
+
+    /*
+     * FTSの更新
+     */
+    if let Err(resp) = reindex_asset_fts(&state, &asset_id) {
+        return Ok(resp);
+    }
+
+    /*
+     * レスポンス生成
+     */
+    let body = json!({
+        "id": asset_id.to_string(),
+        "revision": revision,
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
+        .body(body.to_string()))
+}
+
+///
+/// GET /api/assets/{asset_id}/revisions の実体
+///
+/// # 概要
+/// アセットのリビジョン一覧を取得する。
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+/// * `path` - アセットID
+///
+/// # 戻り値
+/// actix-webのレスポンスオブジェクト
+///
+pub async fn list(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    if let Err(resp) = require_request_scope(&req, BearerScope::Read) {
+        return Ok(resp);
+    }
+
+    /*
+     * アセットID解析
+     */
+    let asset_id = match parse_asset_id(path.into_inner()) {
+        Ok(asset_id) => asset_id,
+        Err(resp) => return Ok(resp),
+    };
+
+    /*
+     * 共有状態取得
+     */
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+
+    /*
+     * アセット情報取得
+     */
+    if let Err(resp) = lookup_asset_info(&state, &asset_id) {
+        return Ok(resp);
+    }
+
+    /*
+     * リビジョン一覧取得
+     */
+    let revisions = match state.db().list_asset_revisions(&asset_id) {
+        Ok(revisions) => revisions,
+        Err(err) => return Ok(resp_db_error(err, "asset lookup failed")),
+    };
+
+    /*
+     * レスポンス生成
+     */
+    let mut entries = Vec::new();
+    for revision in revisions.iter().rev() {
+        let user_name = match state.db().get_user_name_by_id(&revision.user())
+        {
+            Ok(Some(name)) => name,
+            Ok(None) => UNKNOWN_USERNAME.to_string(),
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "user lookup failed",
+                ));
+            }
+        };
+
+        entries.push(json!({
+            "revision": revision.revision(),
+            "mime_type": revision.mime(),
+            "size": revision.size(),
+            "sha256": revision.hash().map(|hash| hash.to_hex()),
+            "width": revision.dimensions().map(|(width, _)| width),
+            "height": revision.dimensions().map(|(_, height)| height),
+            "timestamp": revision
+                .timestamp()
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+            "username": user_name,
+        }));
+    }
+
+    let body = json!({
+        "revisions": entries,
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
+        .body(body.to_string()))
+}
+
+///
+/// POST /api/assets/{asset_id}/revision?rollback_to={rev} の実体
+///
+/// # 概要
+/// アセットを指定リビジョンへロールバックする。
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+/// * `path` - アセットID
+///
+/// # 戻り値
+/// actix-webのレスポンスオブジェクト
+///
+pub async fn post(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    if let Err(resp) = require_request_scope(&req, BearerScope::Write) {
+        return Ok(resp);
+    }
+
+    /*
+     * クエリ取得と検証
+     */
+    let rollback_to = match web::Query::<RevisionQuery>::from_query(
+        req.query_string(),
+    ) {
+        Ok(query) => match query.rollback_to.as_deref().map(str::parse::<u64>)
+        {
+            Some(Ok(value)) => value,
+            _ => {
+                return Ok(resp_error_json(
+                    StatusCode::BAD_REQUEST,
+                    "invalid query parameter: rollback_to",
+                ));
+            }
+        },
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "invalid query parameter: rollback_to",
+            ));
+        }
+    };
+
+    /*
+     * アセットID解析
+     */
+    let asset_id = match parse_asset_id(path.into_inner()) {
+        Ok(asset_id) => asset_id,
+        Err(resp) => return Ok(resp),
+    };
+
+    /*
+     * 認証ユーザ取得
+     */
+    let auth_user = match req.extensions().get::<AuthContext>() {
+        Some(context) => context.user_id().to_string(),
+        None => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "auth context missing",
+            ));
+        }
+    };
+
+    /*
+     * 共有状態取得
+     */
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+
+    /*
+     * アセット情報取得とロック検証
+     */
+    let asset_info = match lookup_asset_info(&state, &asset_id) {
+        Ok(info) => info,
+        Err(resp) => return Ok(resp),
+    };
+
+    if let Err(resp) =
+        check_page_lock(&req, &state, asset_info.page_id(), &auth_user)
+    {
+        return Ok(resp);
+    }
+
+    /*
+     * ロールバック実行
+     */
+    if let Err(err) = state.db().rollback_asset(&asset_id, rollback_to) {
+        if let Some(DbError::InvalidRevision) = err.downcast_ref::<DbError>() {
+            return Ok(resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "invalid query parameter: rollback_to",
+            ));
+        }
+        return Ok(resp_db_error(err, "revision update failed"));
+    }
+
+    /*
+     * FTSの更新
+     */
+    if let Err(resp) = reindex_asset_fts(&state, &asset_id) {
+        return Ok(resp);
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+///
+/// アセット情報の取得
+///
+/// # 引数
+/// * `state` - 共有状態
+/// * `asset_id` - 対象アセットID
+///
+/// # 戻り値
+/// 削除されていないアセットの情報を返す。
+///
+pub(super) fn lookup_asset_info(
+    state: &AppState,
+    asset_id: &AssetId,
+) -> Result<AssetInfo, HttpResponse> {
+    let asset_info = match state.db().get_asset_info_by_id(asset_id) {
+        Ok(Some(info)) => info,
+        Ok(None) => {
+            return Err(resp_error_json(
+                StatusCode::NOT_FOUND,
+                "asset not found",
+            ));
+        }
+        Err(_) => {
+            return Err(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "asset lookup failed",
+            ));
+        }
+    };
+
+    if asset_info.deleted() || asset_info.is_zombie() {
+        return Err(resp_error_json(StatusCode::GONE, "asset deleted"));
+    }
+
+    Ok(asset_info)
+}
+
+///
+/// 所属ページのロック検証
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+/// * `page_id` - 対象ページのID(`None`の場合は検証しない)
+/// * `auth_user` - 認証ユーザ名
+///
+/// # 戻り値
+/// 操作可能な場合は`Ok(())`を返す。
+///
+pub(super) fn check_page_lock(
+    req: &HttpRequest,
+    state: &AppState,
+    page_id: Option<PageId>,
+    auth_user: &str,
+) -> Result<(), HttpResponse> {
+    let page_id = match page_id {
+        Some(page_id) => page_id,
+        None => return Ok(()),
+    };
+
+    let lock_info = match state.db().get_page_lock_info(&page_id) {
+        Ok(Some(info)) => info,
+        Ok(None) => return Ok(()),
+        Err(_) => {
+            return Err(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "lock lookup failed",
+            ));
+        }
+    };
+
+    if !req.headers().contains_key(LOCK_AUTH_HEADER) {
+        return Err(resp_error_json(StatusCode::LOCKED, "page locked"));
+    }
+
+    let token = parse_lock_token(req)?;
+    if lock_info.token() != token {
+        return Err(resp_error_json(
+            StatusCode::FORBIDDEN,
+            "lock token invalid",
+        ));
+    }
+
+    let user_id = match state.db().get_user_id_by_name(auth_user) {
+        Ok(Some(user_id)) => user_id,
+        Ok(None) => {
+            return Err(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "user not found",
+            ));
+        }
+        Err(_) => {
+            return Err(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "user lookup failed",
+            ));
+        }
+    };
+
+    if lock_info.user() != user_id {
+        return Err(resp_error_json(StatusCode::FORBIDDEN, "lock forbidden"));
+    }
+
+    Ok(())
+}
+
+///
+/// DBエラーのレスポンス変換
+///
+/// # 引数
+/// * `err` - DB操作のエラー
+/// * `message` - 想定外エラー時のメッセージ
+///
+/// # 戻り値
+/// エラーレスポンスを返す。
+///
+pub(super) fn resp_db_error(err: anyhow::Error, message: &str) -> HttpResponse {
+    match err.downcast_ref::<DbError>() {
+        Some(DbError::AssetNotFound) => {
+            resp_error_json(StatusCode::NOT_FOUND, "asset not found")
+        }
+        Some(DbError::AssetDeleted) => {
+            resp_error_json(StatusCode::GONE, "asset deleted")
+        }
+        Some(DbError::UserNotFound) => resp_error_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "user not found",
+        ),
+        _ => resp_error_json(StatusCode::INTERNAL_SERVER_ERROR, message),
+    }
+}
+
+///
+/// アセットIDの解析
+///
+/// # 引数
+/// * `raw` - アセットID文字列
+///
+/// # 戻り値
+/// 変換に成功したアセットIDを返す。
+///
+pub(super) fn parse_asset_id(raw: String) -> Result<AssetId, HttpResponse> {
+    match AssetId::from_string(&raw) {
+        Ok(asset_id) => Ok(asset_id),
+        Err(_) => Err(resp_error_json(
+            StatusCode::NOT_FOUND,
+            "asset not found",
+        )),
+    }
+}
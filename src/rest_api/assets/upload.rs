@@ -0,0 +1,782 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 分割(再開可能)アップロードAPIの実装をまとめたモジュール
+//!
+
+use std::fs;
+use std::sync::{Arc, RwLock};
+
+use actix_web::http::{StatusCode, header};
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, web};
+use chrono::SecondsFormat;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::super::resp_error_json;
+use super::{receive_asset_payload, reindex_asset_fts};
+use super::revision::{check_page_lock, lookup_asset_info, parse_asset_id};
+use crate::database::DbError;
+use crate::database::AssetUploadResult;
+use crate::database::types::{
+    AssetUploadInfo,
+    AssetUploadTarget,
+    BearerScope,
+    PageId,
+    UploadId,
+};
+use crate::http_server::app_state::AppState;
+use crate::rest_api::{AuthContext, CACHE_CONTROL_NO_STORE, require_request_scope};
+
+/// 受信済みバイト数を示すヘッダの名称
+const UPLOAD_OFFSET_HEADER: &str = "Upload-Offset";
+
+#[derive(Deserialize)]
+struct CreateUploadRequest {
+    path: Option<String>,
+    page_id: Option<String>,
+    file_name: Option<String>,
+    asset_id: Option<String>,
+    mime_type: Option<String>,
+    size: u64,
+}
+
+///
+/// POST /api/assets/uploads の実体
+///
+/// # 概要
+/// 分割アップロードのセッションを作成する。
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+/// * `body` - セッション作成要求(JSON)
+///
+/// # 戻り値
+/// actix-webのレスポンスオブジェクト
+///
+pub async fn post(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    body: web::Bytes,
+) -> actix_web::Result<HttpResponse> {
+    if let Err(resp) = require_request_scope(&req, BearerScope::Write) {
+        return Ok(resp);
+    }
+
+    /*
+     * 入力の解析
+     */
+    let request = match serde_json::from_slice::<CreateUploadRequest>(&body) {
+        Ok(request) => request,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "invalid request body",
+            ));
+        }
+    };
+
+    let mime = match request.mime_type.as_deref().map(str::trim) {
+        Some(mime) if !mime.is_empty() => mime.to_string(),
+        _ => "application/octet-stream".to_string(),
+    };
+
+    let auth_user = match resolve_auth_user(&req) {
+        Ok(user) => user,
+        Err(resp) => return Ok(resp),
+    };
+
+    /*
+     * 共有状態取得
+     */
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+
+    if request.size > state.asset_limit_size() {
+        return Ok(resp_error_json(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "asset too large",
+        ));
+    }
+
+    /*
+     * 登録先の解決とロック検証
+     */
+    let target = match resolve_target(&state, &request) {
+        Ok(target) => target,
+        Err(resp) => return Ok(resp),
+    };
+
+    if let Err(resp) = check_target_lock(&req, &state, &target, &auth_user) {
+        return Ok(resp);
+    }
+
+    /*
+     * セッション作成
+     */
+    let upload_id = match state.db().create_asset_upload(
+        &auth_user,
+        target,
+        &mime,
+        request.size,
+    ) {
+        Ok(upload_id) => upload_id,
+        Err(err) => return Ok(resp_upload_error(err, "upload create failed")),
+    };
+
+    /*
+     * レスポンス生成
+     */
+    let body = json!({
+        "upload_id": upload_id.to_string(),
+        "offset": 0,
+        "size": request.size,
+    });
+    let location = format!("/api/assets/uploads/{}", upload_id);
+
+    Ok(HttpResponse::Created()
+        .content_type("application/json")
+        .insert_header((header::LOCATION, location))
+        .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
+        .insert_header((UPLOAD_OFFSET_HEADER, "0"))
+        .body(body.to_string()))
+}
+
+///
+/// GET /api/assets/uploads/{upload_id} の実体
+///
+/// # 概要
+/// 分割アップロードの受信状況を取得する。
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+/// * `path` - セッションID
+///
+/// # 戻り値
+/// actix-webのレスポンスオブジェクト
+///
+pub async fn get(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    if let Err(resp) = require_request_scope(&req, BearerScope::Write) {
+        return Ok(resp);
+    }
+
+    let upload_id = match parse_upload_id(path.into_inner()) {
+        Ok(upload_id) => upload_id,
+        Err(resp) => return Ok(resp),
+    };
+
+    let auth_user = match resolve_auth_user(&req) {
+        Ok(user) => user,
+        Err(resp) => return Ok(resp),
+    };
+
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+
+    /*
+     * セッション情報の取得
+     */
+    let (info, offset) =
+        match state.db().get_asset_upload(&upload_id, &auth_user) {
+            Ok(result) => result,
+            Err(err) => {
+                return Ok(resp_upload_error(err, "upload lookup failed"));
+            }
+        };
+
+    let body = upload_info_json(&upload_id, &info, offset);
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
+        .insert_header((UPLOAD_OFFSET_HEADER, offset.to_string()))
+        .body(body.to_string()))
+}
+
+///
+/// PATCH /api/assets/uploads/{upload_id} の実体
+///
+/// # 概要
+/// 分割アップロードにデータを追記する。追記位置はリクエストヘッダ
+/// `Upload-Offset`で指定する。
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+/// * `path` - セッションID
+/// * `payload` - 追記するデータ
+///
+/// # 戻り値
+/// actix-webのレスポンスオブジェクト
+///
+pub async fn patch(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    path: web::Path<String>,
+    payload: web::Payload,
+) -> actix_web::Result<HttpResponse> {
+    if let Err(resp) = require_request_scope(&req, BearerScope::Write) {
+        return Ok(resp);
+    }
+
+    let upload_id = match parse_upload_id(path.into_inner()) {
+        Ok(upload_id) => upload_id,
+        Err(resp) => return Ok(resp),
+    };
+
+    /*
+     * 追記位置の取得
+     */
+    let offset = match req
+        .headers()
+        .get(UPLOAD_OFFSET_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+    {
+        Some(offset) => offset,
+        None => {
+            return Ok(resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "invalid header: Upload-Offset",
+            ));
+        }
+    };
+
+    let auth_user = match resolve_auth_user(&req) {
+        Ok(user) => user,
+        Err(resp) => return Ok(resp),
+    };
+
+    /*
+     * セッションの検証と受信先の準備
+     */
+    let shared = state;
+    let (info, current, staged_path) = {
+        let state = match shared.read() {
+            Ok(state) => state,
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "state lock failed",
+                ));
+            }
+        };
+
+        let (info, current) =
+            match state.db().get_asset_upload(&upload_id, &auth_user) {
+                Ok(result) => result,
+                Err(err) => {
+                    return Ok(resp_upload_error(err, "upload lookup failed"));
+                }
+            };
+
+        if current != offset {
+            return Ok(resp_offset_mismatch(current));
+        }
+
+        let staged_path = match state.db().new_asset_staging_path() {
+            Ok(path) => path,
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "upload append failed",
+                ));
+            }
+        };
+        (info, current, staged_path)
+    };
+
+    /*
+     * データの受信(受信中は共有状態のロックを解放する)
+     */
+    let limit = info.size().saturating_sub(current);
+    if let Err(resp) =
+        receive_asset_payload(&req, payload, &staged_path, limit).await
+    {
+        return Ok(resp);
+    }
+
+    let state = match shared.read() {
+        Ok(state) => state,
+        Err(_) => {
+            let _ = fs::remove_file(&staged_path);
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+
+    /*
+     * 追記
+     */
+    let new_offset = match state.db().append_asset_upload(
+        &upload_id,
+        &auth_user,
+        offset,
+        &staged_path,
+    ) {
+        Ok(offset) => offset,
+        Err(err) => return Ok(resp_upload_error(err, "upload append failed")),
+    };
+
+    Ok(HttpResponse::NoContent()
+        .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
+        .insert_header((UPLOAD_OFFSET_HEADER, new_offset.to_string()))
+        .finish())
+}
+
+///
+/// POST /api/assets/uploads/{upload_id}/complete の実体
+///
+/// # 概要
+/// 受信を完了した分割アップロードをアセットとして登録する。
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+/// * `path` - セッションID
+///
+/// # 戻り値
+/// actix-webのレスポンスオブジェクト
+///
+pub async fn complete(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    if let Err(resp) = require_request_scope(&req, BearerScope::Write) {
+        return Ok(resp);
+    }
+
+    let upload_id = match parse_upload_id(path.into_inner()) {
+        Ok(upload_id) => upload_id,
+        Err(resp) => return Ok(resp),
+    };
+
+    let auth_user = match resolve_auth_user(&req) {
+        Ok(user) => user,
+        Err(resp) => return Ok(resp),
+    };
+
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+
+    /*
+     * 登録先のロック検証
+     */
+    let (info, _) = match state.db().get_asset_upload(&upload_id, &auth_user) {
+        Ok(result) => result,
+        Err(err) => return Ok(resp_upload_error(err, "upload lookup failed")),
+    };
+
+    if let Err(resp) =
+        check_target_lock(&req, &state, info.target(), &auth_user)
+    {
+        return Ok(resp);
+    }
+
+    /*
+     * アセット登録
+     */
+    let result = match state.db().complete_asset_upload(&upload_id, &auth_user)
+    {
+        Ok(result) => result,
+        Err(err) => {
+            return Ok(resp_upload_error(err, "upload complete failed"));
+        }
+    };
+
+    /*
+     * FTSの更新
+     */
+    let asset_id = match &result {
+        AssetUploadResult::Created(asset_id) => asset_id,
+        AssetUploadResult::Replaced { asset_id, .. } => asset_id,
+    };
+    if let Err(resp) = reindex_asset_fts(&state, asset_id) {
+        return Ok(resp);
+    }
+
+    /*
+     * レスポンス生成
+     */
+    match result {
+        AssetUploadResult::Created(asset_id) => {
+            let body = json!({
+                "id": asset_id.to_string(),
+            });
+            let location = format!("/api/assets/{}/data", asset_id);
+
+            Ok(HttpResponse::Created()
+                .content_type("application/json")
+                .insert_header((header::LOCATION, location))
+                .insert_header((header::ETAG, asset_id.to_string()))
+                .body(body.to_string()))
+        }
+
+        AssetUploadResult::Replaced { asset_id, revision } => {
+            let body = json!({
+                "id": asset_id.to_string(),
+                "revision": revision,
+            });
+
+            Ok(HttpResponse::Ok()
+                .content_type("application/json")
+                .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
+                .body(body.to_string()))
+        }
+    }
+}
+
+///
+/// DELETE /api/assets/uploads/{upload_id} の実体
+///
+/// # 概要
+/// 分割アップロードを中止し、受信済みのデータを破棄する。
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+/// * `path` - セッションID
+///
+/// # 戻り値
+/// actix-webのレスポンスオブジェクト
+///
+pub async fn delete(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    if let Err(resp) = require_request_scope(&req, BearerScope::Write) {
+        return Ok(resp);
+    }
+
+    let upload_id = match parse_upload_id(path.into_inner()) {
+        Ok(upload_id) => upload_id,
+        Err(resp) => return Ok(resp),
+    };
+
+    let auth_user = match resolve_auth_user(&req) {
+        Ok(user) => user,
+        Err(resp) => return Ok(resp),
+    };
+
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+
+    if let Err(err) = state.db().abort_asset_upload(&upload_id, &auth_user) {
+        return Ok(resp_upload_error(err, "upload abort failed"));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+///
+/// 登録先の解決
+///
+/// # 引数
+/// * `state` - 共有状態
+/// * `request` - セッション作成要求
+///
+/// # 戻り値
+/// 解決した登録先を返す。
+///
+fn resolve_target(
+    state: &AppState,
+    request: &CreateUploadRequest,
+) -> Result<AssetUploadTarget, HttpResponse> {
+    /*
+     * 既存アセットの差し替え
+     */
+    if let Some(asset_id) = &request.asset_id {
+        if request.path.is_some()
+            || request.page_id.is_some()
+            || request.file_name.is_some()
+        {
+            return Err(resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "asset_id cannot be combined with page target",
+            ));
+        }
+
+        let asset_id = parse_asset_id(asset_id.clone())?;
+        lookup_asset_info(state, &asset_id)?;
+        return Ok(AssetUploadTarget::Replace { asset_id });
+    }
+
+    /*
+     * 新規アセットの登録
+     */
+    let file_name = match request.file_name.as_deref() {
+        Some(file_name) => file_name,
+        None => {
+            return Err(resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "file_name is required",
+            ));
+        }
+    };
+    if let Err(message) = crate::rest_api::validate_asset_file_name(file_name)
+    {
+        return Err(resp_error_json(StatusCode::BAD_REQUEST, message));
+    }
+
+    let page_id = match (&request.page_id, &request.path) {
+        (Some(page_id), None) => match PageId::from_string(page_id) {
+            Ok(page_id) => page_id,
+            Err(_) => {
+                return Err(resp_error_json(
+                    StatusCode::NOT_FOUND,
+                    "page not found",
+                ));
+            }
+        },
+
+        (None, Some(path)) => {
+            if let Err(message) = crate::rest_api::validate_page_path(path) {
+                return Err(resp_error_json(StatusCode::BAD_REQUEST, message));
+            }
+
+            match state.db().get_page_id_by_path(path) {
+                Ok(Some(page_id)) => page_id,
+                Ok(None) => {
+                    return Err(resp_error_json(
+                        StatusCode::NOT_FOUND,
+                        "page not found",
+                    ));
+                }
+                Err(_) => {
+                    return Err(resp_error_json(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "page lookup failed",
+                    ));
+                }
+            }
+        }
+
+        _ => {
+            return Err(resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "either page_id or path is required",
+            ));
+        }
+    };
+
+    Ok(AssetUploadTarget::NewAsset {
+        page_id,
+        file_name: file_name.to_string(),
+    })
+}
+
+///
+/// 登録先ページのロック検証
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+/// * `target` - 登録先
+/// * `auth_user` - 認証ユーザ名
+///
+/// # 戻り値
+/// 操作可能な場合は`Ok(())`を返す。
+///
+fn check_target_lock(
+    req: &HttpRequest,
+    state: &AppState,
+    target: &AssetUploadTarget,
+    auth_user: &str,
+) -> Result<(), HttpResponse> {
+    let page_id = match target {
+        AssetUploadTarget::NewAsset { page_id, .. } => Some(page_id.clone()),
+        AssetUploadTarget::Replace { asset_id } => {
+            lookup_asset_info(state, asset_id)?.page_id()
+        }
+    };
+
+    check_page_lock(req, state, page_id, auth_user)
+}
+
+///
+/// セッション情報のJSON変換
+///
+/// # 引数
+/// * `upload_id` - セッションID
+/// * `info` - セッション情報
+/// * `offset` - 受信済みバイト数
+///
+/// # 戻り値
+/// レスポンス用のJSON値を返す。
+///
+fn upload_info_json(
+    upload_id: &UploadId,
+    info: &AssetUploadInfo,
+    offset: u64,
+) -> serde_json::Value {
+    let mut body = json!({
+        "upload_id": upload_id.to_string(),
+        "offset": offset,
+        "size": info.size(),
+        "mime_type": info.mime(),
+        "updated": info
+            .updated()
+            .to_rfc3339_opts(SecondsFormat::Secs, true),
+    });
+
+    match info.target() {
+        AssetUploadTarget::NewAsset { page_id, file_name } => {
+            body["page_id"] = json!(page_id.to_string());
+            body["file_name"] = json!(file_name);
+        }
+        AssetUploadTarget::Replace { asset_id } => {
+            body["asset_id"] = json!(asset_id.to_string());
+        }
+    }
+
+    body
+}
+
+///
+/// 認証ユーザ名の取得
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+///
+/// # 戻り値
+/// 認証ユーザ名を返す。
+///
+fn resolve_auth_user(req: &HttpRequest) -> Result<String, HttpResponse> {
+    match req.extensions().get::<AuthContext>() {
+        Some(context) => Ok(context.user_id().to_string()),
+        None => Err(resp_error_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "auth context missing",
+        )),
+    }
+}
+
+///
+/// 受信位置不一致のレスポンス生成
+///
+/// # 引数
+/// * `offset` - サーバ側の受信済みバイト数
+///
+/// # 戻り値
+/// 現在の受信位置を`Upload-Offset`ヘッダに設定した409レスポンスを返す。
+///
+fn resp_offset_mismatch(offset: u64) -> HttpResponse {
+    let mut resp =
+        resp_error_json(StatusCode::CONFLICT, "upload offset mismatch");
+    if let Ok(value) = header::HeaderValue::from_str(&offset.to_string()) {
+        resp.headers_mut().insert(
+            header::HeaderName::from_static("upload-offset"),
+            value,
+        );
+    }
+    resp
+}
+
+///
+/// DBエラーのレスポンス変換
+///
+/// # 引数
+/// * `err` - DB操作のエラー
+/// * `message` - 想定外エラー時のメッセージ
+///
+/// # 戻り値
+/// エラーレスポンスを返す。
+///
+fn resp_upload_error(err: anyhow::Error, message: &str) -> HttpResponse {
+    match err.downcast_ref::<DbError>() {
+        Some(DbError::UploadNotFound) => {
+            resp_error_json(StatusCode::NOT_FOUND, "upload not found")
+        }
+        Some(DbError::UploadOffsetMismatch { offset }) => {
+            resp_offset_mismatch(*offset)
+        }
+        Some(DbError::UploadTooLarge) => resp_error_json(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "upload exceeds declared size",
+        ),
+        Some(DbError::UploadIncomplete) => {
+            resp_error_json(StatusCode::CONFLICT, "upload incomplete")
+        }
+        Some(DbError::PageNotFound) => {
+            resp_error_json(StatusCode::NOT_FOUND, "page not found")
+        }
+        Some(DbError::PageDeleted) => {
+            resp_error_json(StatusCode::GONE, "page deleted")
+        }
+        Some(DbError::AssetNotFound) => {
+            resp_error_json(StatusCode::NOT_FOUND, "asset not found")
+        }
+        Some(DbError::AssetDeleted) => {
+            resp_error_json(StatusCode::GONE, "asset deleted")
+        }
+        Some(DbError::AssetAlreadyExists) => {
+            resp_error_json(StatusCode::CONFLICT, "asset already exists")
+        }
+        Some(DbError::UserNotFound) => resp_error_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "user not found",
+        ),
+        _ => resp_error_json(StatusCode::INTERNAL_SERVER_ERROR, message),
+    }
+}
+
+///
+/// セッションIDの解析
+///
+/// # 引数
+/// * `raw` - セッションID文字列
+///
+/// # 戻り値
+/// 変換に成功したセッションIDを返す。
+///
+fn parse_upload_id(raw: String) -> Result<UploadId, HttpResponse> {
+    match UploadId::from_string(&raw) {
+        Ok(upload_id) => Ok(upload_id),
+        Err(_) => Err(resp_error_json(
+            StatusCode::NOT_FOUND,
+            "upload not found",
+        )),
+    }
+}
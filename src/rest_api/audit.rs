@@ -0,0 +1,486 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! REST API 操作の監査ログ記録をまとめたモジュール
+//!
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{Path, ResourceDef, ServiceRequest, ServiceResponse, Url};
+use actix_web::http::{Method, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpMessage, HttpRequest, web};
+use chrono::Utc;
+
+use crate::audit::model::{AuditOperation, AuditRecord, AuditResult};
+use crate::database::types::{AssetId, LockToken, PageId};
+use crate::http_server::app_state::AppState;
+use crate::rest_api::AuthContext;
+
+/// ページ作成リソースの登録名
+pub(crate) const RESOURCE_PAGES: &str = "pages";
+
+/// ページソースリソースの登録名
+pub(crate) const RESOURCE_PAGE_SOURCE: &str = "page_source";
+
+/// ページパスリソースの登録名
+pub(crate) const RESOURCE_PAGE_PATH: &str = "page_path";
+
+/// ページリビジョンリソースの登録名
+pub(crate) const RESOURCE_PAGE_REVISION: &str = "page_revision";
+
+/// ページ付随アセットリソースの登録名
+pub(crate) const RESOURCE_PAGE_ASSET: &str = "page_asset";
+
+/// ページロックリソースの登録名
+pub(crate) const RESOURCE_PAGE_LOCK: &str = "page_lock";
+
+/// 共同編集リソースの登録名
+pub(crate) const RESOURCE_PAGE_COLLAB: &str = "page_collab";
+
+/// ページリソースの登録名
+pub(crate) const RESOURCE_PAGE: &str = "page";
+
+/// アセット作成リソースの登録名
+pub(crate) const RESOURCE_ASSETS: &str = "assets";
+
+/// 分割アップロード完了リソースの登録名
+pub(crate) const RESOURCE_ASSET_UPLOAD_COMPLETE: &str =
+    "asset_upload_complete";
+
+/// アセットデータリソースの登録名
+pub(crate) const RESOURCE_ASSET_DATA: &str = "asset_data";
+
+/// アセットリビジョンリソースの登録名
+pub(crate) const RESOURCE_ASSET_REVISION: &str = "asset_revision";
+
+/// アセットリソースの登録名
+pub(crate) const RESOURCE_ASSET: &str = "asset";
+
+/// ロックリソースの登録名
+pub(crate) const RESOURCE_LOCK: &str = "lock";
+
+///
+/// REST API 操作の監査対象
+///
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RestAuditTarget {
+    /// 正規化済み対象 path
+    pub(crate) path: Option<String>,
+
+    /// 補足要約
+    pub(crate) summary: Option<String>,
+
+    /// 対象 revision
+    pub(crate) revision: Option<u64>,
+}
+
+impl RestAuditTarget {
+    ///
+    /// ページIDからの監査対象の生成
+    ///
+    /// # 引数
+    /// * `state` - 共有状態
+    /// * `raw_page_id` - パスパラメータのページID
+    ///
+    /// # 戻り値
+    /// ページのパス(削除済みの場合は削除時のパス)を対象とした監査対象を
+    /// 返す。ページが解決できない場合は対象 path を持たない。
+    ///
+    /// # 注記
+    /// 削除やリネームで操作後にパスが変わるため、操作前に生成すること。
+    ///
+    pub(crate) fn page(
+        state: &web::Data<Arc<RwLock<AppState>>>,
+        raw_page_id: &str,
+    ) -> Self {
+        let path = PageId::from_string(raw_page_id).ok().and_then(|page_id| {
+            let state = state.read().ok()?;
+            let index = state.db().get_page_index_by_id(&page_id).ok()??;
+            index
+                .current_path()
+                .or(index.last_deleted_path())
+                .map(str::to_string)
+        });
+
+        Self {
+            path,
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// アセットIDからの監査対象の生成
+    ///
+    /// # 引数
+    /// * `state` - 共有状態
+    /// * `raw_asset_id` - パスパラメータのアセットID
+    ///
+    /// # 戻り値
+    /// 所属ページのパスを対象とし、ファイル名を補足要約とした監査対象を
+    /// 返す。
+    ///
+    pub(crate) fn asset(
+        state: &web::Data<Arc<RwLock<AppState>>>,
+        raw_asset_id: &str,
+    ) -> Self {
+        let Ok(asset_id) = AssetId::from_string(raw_asset_id) else {
+            return Self::default();
+        };
+        let Some(info) = state
+            .read()
+            .ok()
+            .and_then(|state| state.db().get_asset_info_by_id(&asset_id).ok()?)
+        else {
+            return Self::default();
+        };
+
+        let mut target = match info.page_id() {
+            Some(page_id) => Self::page(state, &page_id.to_string()),
+            None => Self::default(),
+        };
+        target.summary = Some(format!("asset {}", info.file_name()));
+        target
+    }
+
+    ///
+    /// ロック解除トークンからの監査対象の生成
+    ///
+    /// # 引数
+    /// * `state` - 共有状態
+    /// * `raw_token` - パスパラメータのロック解除トークン
+    ///
+    /// # 戻り値
+    /// ロック対象ページのパスを対象とし、トークンを補足要約とした監査対象を
+    /// 返す。
+    ///
+    /// # 注記
+    /// ロック解除後はページを解決できないため、操作前に生成すること。
+    ///
+    pub(crate) fn lock(
+        state: &web::Data<Arc<RwLock<AppState>>>,
+        raw_token: &str,
+    ) -> Self {
+        let Ok(token) = LockToken::from_string(raw_token) else {
+            return Self::default();
+        };
+        let path = state.read().ok().and_then(|state| {
+            state
+                .db()
+                .list_locks()
+                .ok()?
+                .into_iter()
+                .find(|lock| lock.token() == token)
+                .map(|lock| lock.page_path())
+        });
+
+        Self {
+            path,
+            summary: Some(format!("lock {}", token)),
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// 補足要約の設定
+    ///
+    pub(crate) fn with_summary(mut self, summary: String) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+
+    ///
+    /// 対象 revision の設定
+    ///
+    pub(crate) fn with_revision(mut self, revision: Option<u64>) -> Self {
+        self.revision = revision;
+        self
+    }
+}
+
+///
+/// REST API 操作の監査記録
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `next` - 後続サービス
+///
+/// # 戻り値
+/// 後続サービスのレスポンスをそのまま返す。
+///
+/// # 注記
+/// 監査対象の操作は`audit_request()`で判定する。監査対象の解決は
+/// 削除やリネームで操作後にパスが変わるため、後続サービスの呼び出し前に
+/// 行う。
+///
+pub(crate) async fn record_rest_operation<B>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<B>, Error>
+where
+    B: MessageBody + 'static,
+{
+    let Some(state) = req.app_data::<web::Data<Arc<RwLock<AppState>>>>().cloned()
+    else {
+        return next.call(req).await;
+    };
+    let Some((operation, target)) = audit_request(&req, &state) else {
+        return next.call(req).await;
+    };
+
+    let res = next.call(req).await?;
+    record_rest_audit(&state, res.request(), operation, target, res.status());
+
+    Ok(res)
+}
+
+///
+/// 監査対象の操作と対象の判定
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+///
+/// # 戻り値
+/// 監査対象の操作であれば操作種別と監査対象を返す。監査対象外の場合は
+/// `None`を返す。
+///
+/// # 注記
+/// 操作種別は`create_api_scope()`でリソースに付与した登録名とHTTPメソッド
+/// から決定する。
+///
+fn audit_request(
+    req: &ServiceRequest,
+    state: &web::Data<Arc<RwLock<AppState>>>,
+) -> Option<(AuditOperation, RestAuditTarget)> {
+    /*
+     * ルートとパスパラメータの解決
+     */
+    let name = req.match_name()?;
+    let pattern = req.match_pattern()?;
+    let mut params = Path::new(Url::new(req.uri().clone()));
+    if !ResourceDef::new(pattern.as_str()).capture_match_info(&mut params) {
+        return None;
+    }
+    let param = |name: &str| params.get(name).unwrap_or_default();
+    let query = web::Query::<HashMap<String, String>>::from_query(
+        req.query_string(),
+    )
+    .map(web::Query::into_inner)
+    .unwrap_or_default();
+
+    /*
+     * 操作種別と監査対象の決定
+     */
+    let method = req.method();
+    let result = match name {
+        RESOURCE_PAGES if method == Method::POST => (
+            AuditOperation::Create,
+            RestAuditTarget {
+                path: query.get("path").cloned(),
+                ..Default::default()
+            },
+        ),
+        RESOURCE_PAGE_SOURCE if method == Method::PUT => (
+            AuditOperation::Update,
+            RestAuditTarget::page(state, param("page_id")),
+        ),
+        RESOURCE_PAGE_PATH if method == Method::POST => {
+            let target = RestAuditTarget::page(state, param("page_id"));
+            match query.get("restore_to") {
+                Some(restore_to) => (
+                    AuditOperation::Undelete,
+                    target.with_summary(format!("restore to {}", restore_to)),
+                ),
+                None => (
+                    AuditOperation::Rename,
+                    target.with_summary(format!(
+                        "rename to {}",
+                        query.get("rename_to").map(String::as_str).unwrap_or_default()
+                    )),
+                ),
+            }
+        }
+        RESOURCE_PAGE_REVISION if method == Method::POST => {
+            let (operation, revision) = match query.get("keep_from") {
+                Some(keep_from) => (AuditOperation::Compact, Some(keep_from)),
+                None => (AuditOperation::Rollback, query.get("rollback_to")),
+            };
+            (
+                operation,
+                RestAuditTarget::page(state, param("page_id")).with_revision(
+                    revision.and_then(|revision| revision.parse().ok()),
+                ),
+            )
+        }
+        RESOURCE_PAGE_ASSET if method == Method::POST => (
+            AuditOperation::AssetUpload,
+            RestAuditTarget::page(state, param("page_id"))
+                .with_summary(format!("asset {}", param("file_name"))),
+        ),
+        RESOURCE_PAGE_LOCK if method == Method::POST => (
+            AuditOperation::LockAcquire,
+            RestAuditTarget::page(state, param("page_id")),
+        ),
+        RESOURCE_PAGE_LOCK if method == Method::DELETE => (
+            AuditOperation::LockRelease,
+            RestAuditTarget::page(state, param("page_id")),
+        ),
+        RESOURCE_PAGE_COLLAB if method == Method::GET => (
+            AuditOperation::CollabJoin,
+            RestAuditTarget::page(state, param("page_id")),
+        ),
+        RESOURCE_PAGE if method == Method::DELETE => (
+            AuditOperation::Delete,
+            RestAuditTarget::page(state, param("page_id")),
+        ),
+        RESOURCE_ASSETS if method == Method::POST => (
+            AuditOperation::AssetUpload,
+            RestAuditTarget {
+                path: query.get("path").cloned(),
+                summary: query
+                    .get("name")
+                    .or(query.get("file"))
+                    .map(|name| format!("asset {}", name)),
+                ..Default::default()
+            },
+        ),
+        RESOURCE_ASSET_UPLOAD_COMPLETE if method == Method::POST => (
+            AuditOperation::AssetUpload,
+            RestAuditTarget::default()
+                .with_summary(format!("upload session {}", param("upload_id"))),
+        ),
+        RESOURCE_ASSET_DATA if method == Method::PUT => (
+            AuditOperation::AssetUpload,
+            RestAuditTarget::asset(state, param("asset_id")),
+        ),
+        RESOURCE_ASSET_REVISION if method == Method::POST => (
+            AuditOperation::Rollback,
+            RestAuditTarget::asset(state, param("asset_id")).with_revision(
+                query
+                    .get("rollback_to")
+                    .and_then(|revision| revision.parse().ok()),
+            ),
+        ),
+        RESOURCE_ASSET if method == Method::DELETE => (
+            AuditOperation::AssetDelete,
+            RestAuditTarget::asset(state, param("asset_id")),
+        ),
+        RESOURCE_LOCK if method == Method::DELETE => (
+            AuditOperation::LockBreak,
+            RestAuditTarget::lock(state, param("token")),
+        ),
+        _ => return None,
+    };
+
+    Some(result)
+}
+
+///
+/// REST API 操作の監査レコードを記録する
+///
+/// # 引数
+/// * `state` - 共有状態
+/// * `req` - HTTPリクエスト
+/// * `operation` - 操作種別
+/// * `target` - 監査対象
+/// * `status` - 操作の応答ステータス
+///
+/// # 注記
+/// 操作結果は応答のステータスコードから分類する。認証文脈を持たない
+/// リクエストは記録しない。
+///
+fn record_rest_audit(
+    state: &web::Data<Arc<RwLock<AppState>>>,
+    req: &HttpRequest,
+    operation: AuditOperation,
+    target: RestAuditTarget,
+    status: StatusCode,
+) {
+    let Some(auth) = req.extensions().get::<AuthContext>().cloned() else {
+        return;
+    };
+    let Ok(state) = state.read() else {
+        return;
+    };
+    let user_id = state.db().get_user_id_by_name(auth.user_id()).ok().flatten();
+
+    state.record_audit(AuditRecord::new(
+        operation,
+        user_id,
+        auth.token_id().cloned(),
+        req.peer_addr().map(|addr| addr.ip()),
+        target.path,
+        audit_result_from_status(status),
+        Utc::now(),
+        target.summary,
+        target.revision,
+    ));
+}
+
+///
+/// 応答ステータスからの結果分類
+///
+/// # 引数
+/// * `status` - 応答のステータスコード
+///
+/// # 戻り値
+/// 対応する監査ログ向け結果分類を返す。
+///
+fn audit_result_from_status(status: StatusCode) -> AuditResult {
+    match status {
+        status if status.is_success() => AuditResult::Success,
+        StatusCode::SWITCHING_PROTOCOLS => AuditResult::Success,
+        StatusCode::UNAUTHORIZED => AuditResult::AuthenticationFailed,
+        StatusCode::FORBIDDEN => AuditResult::ScopeDenied,
+        StatusCode::NOT_FOUND | StatusCode::GONE => AuditResult::NotFound,
+        StatusCode::CONFLICT
+        | StatusCode::LOCKED
+        | StatusCode::PRECONDITION_FAILED => AuditResult::Conflict,
+        StatusCode::NOT_IMPLEMENTED => AuditResult::Unsupported,
+        status if status.is_client_error() => AuditResult::InvalidInput,
+        _ => AuditResult::InternalError,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///
+    /// 応答ステータスが監査ログの結果分類へ対応付けられることを確認する。
+    ///
+    #[test]
+    fn audit_result_from_status_classifies_responses() {
+        assert_eq!(
+            audit_result_from_status(StatusCode::NO_CONTENT),
+            AuditResult::Success
+        );
+        assert_eq!(
+            audit_result_from_status(StatusCode::SWITCHING_PROTOCOLS),
+            AuditResult::Success
+        );
+        assert_eq!(
+            audit_result_from_status(StatusCode::LOCKED),
+            AuditResult::Conflict
+        );
+        assert_eq!(
+            audit_result_from_status(StatusCode::GONE),
+            AuditResult::NotFound
+        );
+        assert_eq!(
+            audit_result_from_status(StatusCode::PAYLOAD_TOO_LARGE),
+            AuditResult::InvalidInput
+        );
+        assert_eq!(
+            audit_result_from_status(StatusCode::INTERNAL_SERVER_ERROR),
+            AuditResult::InternalError
+        );
+    }
+}
@@ -9,10 +9,12 @@
 //!
 
 use std::future::{Ready, ready};
+use std::net::IpAddr;
 use std::sync::{Arc, RwLock};
 use std::{fmt, fmt::Display};
 
 use actix_web::body::MessageBody;
+use actix_web::cookie::{Cookie, SameSite};
 use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
 use actix_web::error::ErrorInternalServerError;
 use actix_web::http::{StatusCode, header};
@@ -20,10 +22,11 @@ use actix_web::middleware::Next;
 use actix_web::{Error, FromRequest, HttpMessage, HttpRequest, HttpResponse, ResponseError, web};
 use actix_web_httpauth::extractors::basic::BasicAuth;
 use actix_web_httpauth::headers::www_authenticate::basic::Basic;
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Duration, Local, Utc};
 use log::warn;
 use serde_json::json;
 
+use crate::audit::model::{AuditOperation, AuditRecord, AuditResult};
 use crate::auth::{AuthContext, AuthUser, authenticate_bearer_token};
 use crate::database::types::{
     BearerScope,
@@ -39,6 +42,41 @@ use super::{BASIC_AUTH_REALM, CACHE_CONTROL_NO_STORE};
 /// Bearer期限通知ヘッダ名
 pub(crate) const X_BEARER_EXPIRE_HEADER: &str = "X-Bearer-Expire";
 
+/// 二要素認証済みセッションを保持する Cookie 名
+pub(crate) const MFA_SESSION_COOKIE: &str = "luwiki_mfa";
+
+/// Basic認証パスワード欄で二要素認証コードを区切る文字
+const MFA_CODE_SEPARATOR: char = ':';
+
+/// ログイン成功を監査ログへ記録する最短間隔(分)
+///
+/// Basic認証はリクエスト毎に照合が行われるため、前回のアクティビティから
+/// この間隔が空いた場合のみをログインとして記録する。
+const LOGIN_AUDIT_INTERVAL_MINUTES: i64 = 30;
+
+///
+/// 二要素認証済みセッション Cookie 発行情報
+///
+#[derive(Clone, Debug)]
+struct MfaSessionCookieValue {
+    session_id: String,
+}
+
+///
+/// Basic認証資格情報の照合結果
+///
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum BasicCredentialResult {
+    /// 照合失敗
+    Rejected,
+
+    /// 照合成功
+    Accepted,
+
+    /// 二要素認証コードの照合により照合成功し、セッションを新規発行した
+    AcceptedWithMfaSession(String),
+}
+
 ///
 /// Bearer有効期限ヘッダ引き継ぎ情報
 ///
@@ -75,6 +113,7 @@ enum ParsedAuthorization {
 struct AuthErrorResponse {
     status: StatusCode,
     reason: &'static str,
+    retry_after: Option<i64>,
 }
 
 #[allow(dead_code)]
@@ -136,6 +175,7 @@ impl AuthErrorResponse {
         Self {
             status: StatusCode::BAD_REQUEST,
             reason: "bad request",
+            retry_after: None,
         }
     }
 
@@ -149,6 +189,7 @@ impl AuthErrorResponse {
         Self {
             status: StatusCode::UNAUTHORIZED,
             reason: "unauthorized",
+            retry_after: None,
         }
     }
 
@@ -162,6 +203,24 @@ impl AuthErrorResponse {
         Self {
             status: StatusCode::FORBIDDEN,
             reason: "forbidden",
+            retry_after: None,
+        }
+    }
+
+    ///
+    /// 429 Too Many Requests を生成する
+    ///
+    /// # 引数
+    /// * `retry_after` - 再試行までの待機秒数
+    ///
+    /// # 戻り値
+    /// ロックアウト中を示す 429 応答を返す。
+    ///
+    fn too_many_requests(retry_after: i64) -> Self {
+        Self {
+            status: StatusCode::TOO_MANY_REQUESTS,
+            reason: "too many failed login attempts",
+            retry_after: Some(retry_after),
         }
     }
 }
@@ -279,6 +338,10 @@ impl ResponseError for AuthErrorResponse {
             ));
         }
 
+        if let Some(retry_after) = self.retry_after {
+            builder.insert_header((header::RETRY_AFTER, retry_after));
+        }
+
         builder
             .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
             .content_type("application/json")
@@ -369,6 +432,8 @@ async fn validate_basic_auth(
     };
 
     let username = credentials.user_id().to_string();
+    let peer_ip = req.peer_addr().map(|addr| addr.ip());
+    let now = Local::now();
 
     let state = match data.read() {
         Ok(state) => state,
@@ -376,15 +441,98 @@ async fn validate_basic_auth(
             return Err((ErrorInternalServerError("state lock failed"), req));
         }
     };
-    let ok = match state.db().verify_user(&username, &password) {
-        Ok(ok) => ok,
+
+    /*
+     * ロックアウト状態の確認
+     */
+    if let Some(ip) = peer_ip
+        && let Some(until) = state.login_failures().locked_until(ip, now)
+    {
+        warn!(
+            "basic auth rejected: reason=address_locked user={} addr={}",
+            username, ip
+        );
+        record_login_audit(
+            &state,
+            &username,
+            peer_ip,
+            AuditResult::AuthenticationFailed,
+            "address_locked",
+        );
+        let retry_after = (until - now).num_seconds().max(1);
+        return Err((
+            AuthErrorResponse::too_many_requests(retry_after).into(),
+            req,
+        ));
+    }
+
+    let user_locked = match state.db().get_user_info_by_name(&username) {
+        Ok(user_info) => user_info.is_some_and(|info| info.is_locked(now)),
         Err(_) => return Err((ErrorInternalServerError("auth failed"), req)),
     };
 
-    if !ok {
+    if user_locked {
+        warn!(
+            "basic auth rejected: reason=user_locked user={} addr={}",
+            username,
+            format_peer_ip(peer_ip)
+        );
+        record_peer_login_failure(&state, peer_ip, now);
+        record_login_audit(
+            &state,
+            &username,
+            peer_ip,
+            AuditResult::AuthenticationFailed,
+            "user_locked",
+        );
         return Err((AuthErrorResponse::unauthorized().into(), req));
     }
 
+    /*
+     * 資格情報の照合
+     */
+    let session_id = req
+        .cookie(MFA_SESSION_COOKIE)
+        .map(|cookie| cookie.value().to_string());
+    let result = match verify_basic_credentials(
+        &state,
+        &username,
+        &password,
+        session_id.as_deref(),
+        now,
+    ) {
+        Ok(result) => result,
+        Err(_) => return Err((ErrorInternalServerError("auth failed"), req)),
+    };
+
+    match result {
+        BasicCredentialResult::Rejected => {
+            if record_login_failure(&state, &username, peer_ip, now).is_err() {
+                return Err((ErrorInternalServerError("auth failed"), req));
+            }
+            record_login_audit(
+                &state,
+                &username,
+                peer_ip,
+                AuditResult::AuthenticationFailed,
+                "invalid_credentials",
+            );
+            return Err((AuthErrorResponse::unauthorized().into(), req));
+        }
+        BasicCredentialResult::Accepted => {}
+        BasicCredentialResult::AcceptedWithMfaSession(session_id) => {
+            req.extensions_mut()
+                .insert(MfaSessionCookieValue { session_id });
+        }
+    }
+
+    /*
+     * ログイン失敗記録の消去
+     */
+    if state.db().clear_user_login_failures(&username).is_err() {
+        return Err((ErrorInternalServerError("auth failed"), req));
+    }
+
     let user_info = match state.db().get_user_info_by_name(&username) {
         Ok(Some(user_info)) => user_info,
         Ok(None) => {
@@ -395,6 +543,27 @@ async fn validate_basic_auth(
         }
     };
 
+    if state.db().record_user_activity(&username, now).is_err() {
+        return Err((ErrorInternalServerError("auth failed"), req));
+    }
+
+    /*
+     * 一定時間操作の無かったユーザの認証成功をログインとして記録する
+     */
+    let login_interval = Duration::minutes(LOGIN_AUDIT_INTERVAL_MINUTES);
+    if user_info
+        .last_activity()
+        .is_none_or(|last_activity| now - last_activity >= login_interval)
+    {
+        record_login_audit(
+            &state,
+            &username,
+            peer_ip,
+            AuditResult::Success,
+            "basic",
+        );
+    }
+
     req.extensions_mut().insert(AuthContext::new_with_attributes(
         AuthUser::new(username),
         BearerScopeSet::all(),
@@ -407,6 +576,226 @@ async fn validate_basic_auth(
     Ok(req)
 }
 
+///
+/// Basic認証のログイン失敗の記録
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `username` - ユーザ名
+/// * `peer_ip` - 接続元アドレス
+/// * `now` - 失敗日時
+///
+/// # 戻り値
+/// 処理に成功した場合は`Ok(())`を返す。
+///
+/// # 注記
+/// ユーザ単位・接続元アドレス単位の双方で失敗回数を数え、閾値に達した場合は
+/// ロックアウトする。存在しないユーザ名の場合は接続元アドレス単位のみ数える。
+///
+fn record_login_failure(
+    state: &AppState,
+    username: &str,
+    peer_ip: Option<IpAddr>,
+    now: DateTime<Local>,
+) -> anyhow::Result<()> {
+    let locked_until = state.db().record_user_login_failure(
+        username,
+        now,
+        state.auth_config().user_lockout(),
+    )?;
+
+    warn!(
+        "basic auth failed: user={} addr={}",
+        username,
+        format_peer_ip(peer_ip)
+    );
+    if let Some(until) = locked_until {
+        warn!(
+            "user locked out: user={} until={}",
+            username,
+            until.format("%Y-%m-%dT%H:%M:%S")
+        );
+    }
+
+    record_peer_login_failure(state, peer_ip, now);
+    Ok(())
+}
+
+///
+/// ログイン操作の監査レコードの記録
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `username` - 認証を試みたユーザ名
+/// * `peer_ip` - 接続元アドレス
+/// * `result` - 認証結果
+/// * `reason` - 補足要約として記録する理由
+///
+/// # 注記
+/// 未登録のユーザ名による失敗ではユーザIDを記録せず、試行されたユーザ名を
+/// 補足要約に残す。
+///
+fn record_login_audit(
+    state: &AppState,
+    username: &str,
+    peer_ip: Option<IpAddr>,
+    result: AuditResult,
+    reason: &str,
+) {
+    let user_id = state.db().get_user_id_by_name(username).ok().flatten();
+
+    state.record_audit(AuditRecord::new(
+        AuditOperation::Login,
+        user_id,
+        None,
+        peer_ip,
+        None,
+        result,
+        Utc::now(),
+        Some(format!("user={} reason={}", username, reason)),
+        None,
+    ));
+}
+
+///
+/// 接続元アドレス単位のログイン失敗の記録
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `peer_ip` - 接続元アドレス
+/// * `now` - 失敗日時
+///
+fn record_peer_login_failure(
+    state: &AppState,
+    peer_ip: Option<IpAddr>,
+    now: DateTime<Local>,
+) {
+    let Some(ip) = peer_ip else {
+        return;
+    };
+
+    let locked_until = state.login_failures().record_failure(
+        ip,
+        now,
+        state.auth_config().ip_lockout(),
+    );
+    if let Some(until) = locked_until {
+        warn!(
+            "address locked out: addr={} until={}",
+            ip,
+            until.format("%Y-%m-%dT%H:%M:%S")
+        );
+    }
+}
+
+///
+/// 接続元アドレスのログ出力用文字列を返す
+///
+/// # 引数
+/// * `peer_ip` - 接続元アドレス
+///
+/// # 戻り値
+/// 接続元アドレスが不明な場合は `-` を返す。
+///
+fn format_peer_ip(peer_ip: Option<IpAddr>) -> String {
+    peer_ip
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+///
+/// Basic認証資格情報の照合
+///
+/// # 概要
+/// 二要素認証を要求しないユーザはパスワードのみで照合する。要求するユーザは
+/// 有効な二要素認証済みセッションを提示している場合にパスワードのみで照合し、
+/// そうでない場合はパスワード欄を末尾の `:` で分割し、前半をパスワード、
+/// 後半を TOTP コードまたはリカバリコードとして照合する。
+///
+/// # 引数
+/// * `state` - アプリケーション状態
+/// * `username` - ユーザ名
+/// * `password` - Basic認証のパスワード欄
+/// * `session_id` - Cookie で提示された二要素認証済みセッションID
+/// * `now` - 照合基準時刻
+///
+/// # 戻り値
+/// 照合結果を返す。
+///
+/// # 注記
+/// ブラウザはコード付きの資格情報をキャッシュして再送するため、有効な
+/// セッションを提示している場合はパスワード欄にコードが付いたままでも
+/// (コードを照合せずに)受理する。
+///
+fn verify_basic_credentials(
+    state: &AppState,
+    username: &str,
+    password: &str,
+    session_id: Option<&str>,
+    now: DateTime<Local>,
+) -> anyhow::Result<BasicCredentialResult> {
+    /*
+     * 二要素認証要否の判定
+     */
+    let user_info = match state.db().get_user_info_by_name(username)? {
+        Some(user_info) => user_info,
+        None => return Ok(BasicCredentialResult::Rejected),
+    };
+
+    if !user_info.requires_mfa() {
+        return Ok(if state.db().verify_user(username, password)? {
+            BasicCredentialResult::Accepted
+        } else {
+            BasicCredentialResult::Rejected
+        });
+    }
+
+    /*
+     * 二要素認証済みセッションの確認
+     */
+    let enrolled_at = match user_info.mfa() {
+        Some(mfa) => mfa.enrolled_at(),
+        None => {
+            state.mfa_sessions().revoke_user(username);
+            return Ok(BasicCredentialResult::Rejected);
+        }
+    };
+    let has_session = session_id.is_some_and(|session_id| {
+        state
+            .mfa_sessions()
+            .is_valid(session_id, username, enrolled_at, now)
+    });
+
+    if has_session && state.db().verify_user(username, password)? {
+        return Ok(BasicCredentialResult::Accepted);
+    }
+
+    /*
+     * パスワードと二要素認証コードの分割・照合
+     */
+    let (password, code) = match password.rsplit_once(MFA_CODE_SEPARATOR) {
+        Some(pair) => pair,
+        None => return Ok(BasicCredentialResult::Rejected),
+    };
+
+    if !state.db().verify_user(username, password)? {
+        return Ok(BasicCredentialResult::Rejected);
+    }
+
+    if has_session {
+        return Ok(BasicCredentialResult::Accepted);
+    }
+
+    if !state.db().verify_user_mfa_code(username, code, now)? {
+        warn!("basic auth failed: reason=mfa_code_mismatch user={}", username);
+        return Ok(BasicCredentialResult::Rejected);
+    }
+
+    Ok(BasicCredentialResult::AcceptedWithMfaSession(
+        state.mfa_sessions().issue(username, now),
+    ))
+}
+
 ///
 /// Bearer認証の検証入口
 ///
@@ -501,6 +890,51 @@ where
     Ok(res)
 }
 
+///
+/// 二要素認証済みセッション Cookie の付与
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `next` - 後続サービス
+///
+/// # 戻り値
+/// 二要素認証コードの照合でセッションを発行した場合のみ Set-Cookie ヘッダを
+/// 付与したレスポンスを返す。
+///
+/// # 注記
+/// TLS で待ち受けている場合は Cookie に Secure 属性を付与する。
+///
+pub(crate) async fn append_mfa_session_cookie<B>(
+    req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<B>, Error>
+where
+    B: MessageBody + 'static,
+{
+    let mut res = next.call(req).await?;
+
+    let session_id = res
+        .request()
+        .extensions()
+        .get::<MfaSessionCookieValue>()
+        .map(|value| value.session_id.clone());
+
+    if let Some(session_id) = session_id {
+        let secure = res.request().app_config().secure();
+        let cookie = Cookie::build(MFA_SESSION_COOKIE, session_id)
+            .path("/")
+            .http_only(true)
+            .secure(secure)
+            .same_site(SameSite::Strict)
+            .finish();
+        res.response_mut()
+            .add_cookie(&cookie)
+            .map_err(|_| ErrorInternalServerError("auth failed"))?;
+    }
+
+    Ok(res)
+}
+
 ///
 /// Authorizationヘッダの解析
 ///
@@ -0,0 +1,237 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! ロック一覧および強制解除APIの実装をまとめたモジュール
+//!
+
+use std::sync::{Arc, RwLock};
+
+use actix_web::http::{StatusCode, header};
+use actix_web::{HttpRequest, HttpResponse, web};
+use serde_json::{Value, json};
+
+use crate::database::LockListEntry;
+use crate::database::types::{BearerScope, LockToken, UserAttribute};
+use crate::http_server::app_state::AppState;
+use crate::rest_api::admin::require_admin;
+use crate::rest_api::{
+    CACHE_CONTROL_NO_STORE,
+    require_request_scope,
+    resp_error_json,
+};
+
+///
+/// GET /api/locks の実体
+///
+/// # 概要
+/// 取得中のページロックの一覧を取得する。
+///
+/// # 注記
+/// ロック解除トークンは`Admin`属性を持つ利用者にのみ返す。Bearerトークンに
+/// path prefix 制約が設定されている場合は、許可範囲外のページのロックを
+/// 含めない。
+///
+pub async fn get(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+) -> actix_web::Result<HttpResponse> {
+    /*
+     * 認可と共有状態の取得
+     */
+    let auth = match require_request_scope(&req, BearerScope::Read) {
+        Ok(auth) => auth,
+        Err(resp) => return Ok(resp),
+    };
+    let with_token = auth.user_attributes().contains(UserAttribute::Admin);
+
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+
+    /*
+     * ロック一覧の取得
+     */
+    let mut locks = match state.db().list_locks() {
+        Ok(locks) => locks,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "lock query failed",
+            ));
+        }
+    };
+    locks.retain(|lock| auth.path_prefixes().allows_path(&lock.page_path()));
+    locks.sort_by_key(|lock| lock.page_path());
+
+    let body = json!({
+        "items": locks
+            .iter()
+            .map(|lock| lock_entry_json(lock, with_token))
+            .collect::<Vec<_>>(),
+    });
+
+    Ok(json_response(StatusCode::OK, body))
+}
+
+///
+/// DELETE /api/locks/{token} の実体
+///
+/// # 概要
+/// ページロックの強制解除
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+/// * `path` - ロック解除トークン
+///
+/// # 戻り値
+/// actix-webのレスポンスオブジェクト
+///
+/// # 注記
+/// ロックを保持する共同編集セッションが存在する場合は、未保存の編集を
+/// リビジョンとして保存し、参加者へ通知してから切断する。それ以外の
+/// 保持者へは、解除したトークンを用いた次回の操作で強制解除を通知する。
+/// ドラフトページは削除しない。Bearerトークンの path prefix 制約の範囲外の
+/// ページのロックは存在しないものとして扱う。
+///
+pub async fn delete(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    let auth = match require_admin(&req, BearerScope::Delete) {
+        Ok(auth) => auth,
+        Err(resp) => return Ok(resp),
+    };
+
+    /*
+     * ロック解除トークン解析
+     */
+    let token = match LockToken::from_string(&path.into_inner()) {
+        Ok(token) => token,
+        Err(_) => {
+            return Ok(resp_error_json(StatusCode::NOT_FOUND, "lock not found"));
+        }
+    };
+
+    /*
+     * 共有状態取得
+     */
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+
+    /*
+     * ロック情報取得
+     */
+    let lock = match state.db().list_locks() {
+        Ok(locks) => locks.into_iter().find(|lock| lock.token() == token),
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "lock query failed",
+            ));
+        }
+    };
+    let Some(lock) = lock else {
+        return Ok(resp_error_json(StatusCode::NOT_FOUND, "lock not found"));
+    };
+
+    if !auth.path_prefixes().allows_path(&lock.page_path()) {
+        return Ok(resp_error_json(StatusCode::NOT_FOUND, "lock not found"));
+    }
+
+    /*
+     * 共同編集セッションの保存と終了
+     */
+    let saved_revision = match state.collab_hub() {
+        Some(hub) => match hub.break_lock(&state, &lock.page_id(), &token) {
+            Ok(revision) => revision,
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "collab checkpoint failed",
+                ));
+            }
+        },
+        None => None,
+    };
+
+    /*
+     * ロック解除(ドラフトページは保持者の復帰のため残す)
+     */
+    match state.db().break_lock(&token) {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return Ok(resp_error_json(StatusCode::NOT_FOUND, "lock not found"));
+        }
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "lock delete failed",
+            ));
+        }
+    }
+
+    /*
+     * レスポンス生成
+     */
+    let body = json!({
+        "token": token.to_string(),
+        "page_id": lock.page_id().to_string(),
+        "path": lock.page_path(),
+        "username": lock.user_name(),
+        "saved_revision": saved_revision,
+    });
+
+    Ok(json_response(StatusCode::OK, body))
+}
+
+///
+/// ロック一覧エントリのJSON表現
+///
+/// # 引数
+/// * `lock` - ロック一覧エントリ
+/// * `with_token` - ロック解除トークンを含める場合は`true`
+///
+fn lock_entry_json(lock: &LockListEntry, with_token: bool) -> Value {
+    let mut value = json!({
+        "page_id": lock.page_id().to_string(),
+        "path": lock.page_path(),
+        "username": lock.user_name(),
+        "acquired": lock.acquired().map(|acquired| acquired.to_rfc3339()),
+        "expire": lock.expire().to_rfc3339(),
+    });
+
+    if with_token {
+        value["token"] = Value::String(lock.token().to_string());
+    }
+
+    value
+}
+
+///
+/// キャッシュ不可のJSON応答を生成する
+///
+fn json_response(status: StatusCode, body: Value) -> HttpResponse {
+    HttpResponse::build(status)
+        .content_type("application/json")
+        .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
+        .body(body.to_string())
+}
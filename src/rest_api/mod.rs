@@ -8,9 +8,12 @@
 //! REST APIの実装を集約するモジュール
 //!
 
+mod admin;
 mod assets;
+mod audit;
 mod auth;
 mod hello;
+mod locks;
 mod pages;
 mod users;
 
@@ -33,6 +36,9 @@ const FORBIDDEN_FILE_NAME_CHARS: &[char] = &['/', '\\'];
 /// キャッシュを禁止させる場合のCache-Controlヘッダ値
 pub(crate) const CACHE_CONTROL_NO_STORE: &str = "no-store";
 
+/// バックアップのエクスポートIDを通知するレスポンスヘッダ名
+pub(crate) const EXPORT_ID_HEADER: &str = "X-Luwiki-Export-Id";
+
 /// Basic認証チャレンジで使用するrealm名
 pub(crate) const BASIC_AUTH_REALM: &str = "LuWiki REST API";
 
@@ -192,64 +198,161 @@ pub(crate) fn create_api_scope(
      */
     web::scope("/api")
         .app_data(Config::default().realm(BASIC_AUTH_REALM))
+        .wrap(middleware::from_fn(audit::record_rest_operation))
         .wrap(actix_web_httpauth::middleware::HttpAuthentication::with_fn(
             auth::validate_authorization,
         ))
         .wrap(middleware::from_fn(auth::append_bearer_expire_header))
+        .wrap(middleware::from_fn(auth::append_mfa_session_cookie))
         /*
          * 共通・ページ系エンドポイント
          */
         .route("/hello", web::get().to(hello::get))
-        .route("/pages", web::post().to(pages::post))
-        .route("/pages", web::get().to(pages::list::get))
+        .service(
+            web::resource("/pages")
+                .name(audit::RESOURCE_PAGES)
+                .route(web::post().to(pages::post))
+                .route(web::get().to(pages::list::get)),
+        )
         .route("/pages/deleted", web::get().to(pages::deleted::get))
         .route("/pages/id", web::get().to(pages::id::get))
         .route("/pages/search", web::get().to(pages::search::get))
         .route("/pages/short", web::get().to(pages::short::get_by_path))
         .route("/pages/template", web::get().to(pages::template::get))
-        .route("/pages/{page_id}/source", web::get().to(pages::source::get))
-        .route("/pages/{page_id}/source", web::put().to(pages::source::put))
+        .service(
+            web::resource("/pages/{page_id}/source")
+                .name(audit::RESOURCE_PAGE_SOURCE)
+                .route(web::get().to(pages::source::get))
+                .route(web::put().to(pages::source::put)),
+        )
         .route("/pages/{page_id}/meta", web::get().to(pages::meta::get))
         .route("/pages/{page_id}/parent", web::get().to(pages::parent::get))
         .route("/pages/{page_id}/short", web::get().to(pages::short::get))
-        .route("/pages/{page_id}/path", web::get().to(pages::path::get))
-        .route("/pages/{page_id}/path", web::post().to(pages::path::post))
-        .route(
-            "/pages/{page_id}/revision",
-            web::post().to(pages::revision::post),
+        .service(
+            web::resource("/pages/{page_id}/path")
+                .name(audit::RESOURCE_PAGE_PATH)
+                .route(web::get().to(pages::path::get))
+                .route(web::post().to(pages::path::post)),
+        )
+        .service(
+            web::resource("/pages/{page_id}/revision")
+                .name(audit::RESOURCE_PAGE_REVISION)
+                .route(web::post().to(pages::revision::post)),
         )
         .route("/pages/{page_id}/assets", web::get().to(pages::assets::get))
         .service(
             web::resource("/pages/{page_id}/assets/{file_name}")
+                .name(audit::RESOURCE_PAGE_ASSET)
                 .app_data(web::PayloadConfig::new(payload_limit))
                 .route(web::post().to(pages::assets::post))
                 .route(web::get().to(pages::assets::redirect)),
         )
-        .route("/pages/{page_id}/lock", web::post().to(pages::lock::post))
-        .route("/pages/{page_id}/lock", web::put().to(pages::lock::put))
-        .route("/pages/{page_id}/lock", web::get().to(pages::lock::get))
-        .route(
-            "/pages/{page_id}/lock",
-            web::delete().to(pages::lock::delete),
+        .service(
+            web::resource("/pages/{page_id}/lock")
+                .name(audit::RESOURCE_PAGE_LOCK)
+                .route(web::post().to(pages::lock::post))
+                .route(web::put().to(pages::lock::put))
+                .route(web::get().to(pages::lock::get))
+                .route(web::delete().to(pages::lock::delete)),
+        )
+        .service(
+            web::resource("/pages/{page_id}/collab")
+                .name(audit::RESOURCE_PAGE_COLLAB)
+                .route(web::get().to(pages::collab::get)),
+        )
+        .service(
+            web::resource("/pages/{page_id}")
+                .name(audit::RESOURCE_PAGE)
+                .route(web::delete().to(pages::delete::delete)),
         )
-        .route("/pages/{page_id}", web::delete().to(pages::delete::delete))
         /*
          * アセット系エンドポイント
          */
         .service(
             web::resource("/assets")
+                .name(audit::RESOURCE_ASSETS)
                 .app_data(web::PayloadConfig::new(payload_limit))
                 .route(web::post().to(assets::post))
                 .route(web::get().to(assets::get)),
         )
-        .route("/assets/{asset_id}/data", web::get().to(assets::data::get))
+        .route("/assets/uploads", web::post().to(assets::upload::post))
+        .route(
+            "/assets/uploads/{upload_id}",
+            web::get().to(assets::upload::get),
+        )
+        .route(
+            "/assets/uploads/{upload_id}",
+            web::patch().to(assets::upload::patch),
+        )
+        .route(
+            "/assets/uploads/{upload_id}",
+            web::delete().to(assets::upload::delete),
+        )
+        .service(
+            web::resource("/assets/uploads/{upload_id}/complete")
+                .name(audit::RESOURCE_ASSET_UPLOAD_COMPLETE)
+                .route(web::post().to(assets::upload::complete)),
+        )
+        .service(
+            web::resource("/assets/{asset_id}/data")
+                .name(audit::RESOURCE_ASSET_DATA)
+                .app_data(web::PayloadConfig::new(payload_limit))
+                .route(web::get().to(assets::data::get))
+                .route(web::put().to(assets::revision::put)),
+        )
         .route("/assets/{asset_id}/meta", web::get().to(assets::meta::get))
+        .route("/assets/{asset_id}/refs", web::get().to(assets::refs::get))
         .route(
-            "/assets/{asset_id}",
-            web::delete().to(assets::delete::delete),
+            "/assets/{asset_id}/revisions",
+            web::get().to(assets::revision::list),
+        )
+        .service(
+            web::resource("/assets/{asset_id}/revision")
+                .name(audit::RESOURCE_ASSET_REVISION)
+                .route(web::post().to(assets::revision::post)),
+        )
+        .service(
+            web::resource("/assets/{asset_id}")
+                .name(audit::RESOURCE_ASSET)
+                .route(web::delete().to(assets::delete::delete)),
+        )
+        /*
+         * ロック系エンドポイント
+         */
+        .route("/locks", web::get().to(locks::get))
+        .service(
+            web::resource("/locks/{token}")
+                .name(audit::RESOURCE_LOCK)
+                .route(web::delete().to(locks::delete)),
         )
         /*
          * ユーザ系エンドポイント
          */
         .route("/users/me", web::get().to(users::me::get))
+        .route("/users/me/tokens", web::get().to(users::tokens::get))
+        .route("/users/me/tokens", web::post().to(users::tokens::post))
+        .route(
+            "/users/me/tokens/{token_id}",
+            web::patch().to(users::tokens::patch),
+        )
+        .route(
+            "/users/me/tokens/{token_id}",
+            web::delete().to(users::tokens::delete),
+        )
+        .route(
+            "/users/me/tokens/{token_id}/rotate",
+            web::post().to(users::tokens::rotate),
+        )
+        /*
+         * 管理者向けエンドポイント
+         */
+        .route("/admin/users", web::get().to(admin::users::list))
+        .route("/admin/users", web::post().to(admin::users::post))
+        .route("/admin/users/{user_name}", web::get().to(admin::users::get))
+        .route(
+            "/admin/users/{user_name}",
+            web::patch().to(admin::users::patch),
+        )
+        .route("/admin/backup", web::post().to(admin::backup::post))
+        .route("/admin/audit", web::get().to(admin::audit::get))
 }
@@ -8,6 +8,7 @@
 //! ページアセット関連APIの実装をまとめたモジュール
 //!
 
+use std::fs;
 use std::sync::{Arc, RwLock};
 
 use actix_web::http::{header, StatusCode};
@@ -20,6 +21,7 @@ use crate::database::DbError;
 use crate::database::types::{BearerScope, LockToken, PageId, UserId};
 use crate::http_server::app_state::AppState;
 use crate::rest_api::AuthContext;
+use crate::rest_api::assets::{receive_asset_payload, reindex_asset_fts};
 use crate::rest_api::{CACHE_CONTROL_NO_STORE, require_request_scope};
 /// ロック認証ヘッダの名称
 const LOCK_AUTH_HEADER: &str = "X-Lock-Authentication";
@@ -181,7 +183,7 @@ fn resolve_asset_username(
 /// * `state` - 共有状態
 /// * `path` - ページID
 /// * `file_name` - ファイル名
-/// * `body` - アセットデータ
+/// * `payload` - アセットデータ
 ///
 /// # 戻り値
 /// actix-webのレスポンスオブジェクト
@@ -190,7 +192,7 @@ pub async fn post(
     req: HttpRequest,
     state: web::Data<Arc<RwLock<AppState>>>,
     path: web::Path<(String, String)>,
-    body: web::Bytes,
+    payload: web::Payload,
 ) -> actix_web::Result<HttpResponse> {
     if let Err(resp) = require_request_scope(&req, BearerScope::Write) {
         return Ok(resp);
@@ -245,99 +247,134 @@ pub async fn post(
     /*
      * 共有状態取得
      */
-    let state = match state.read() {
-        Ok(state) => state,
-        Err(_) => {
-            return Ok(resp_error_json(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "state lock failed",
-            ));
-        }
-    };
-
-    /*
-     * ページ情報取得
-     */
-    let page_index = match state.db().get_page_index_by_id(&page_id) {
-        Ok(Some(index)) => index,
-        Ok(None) => {
-            return Ok(resp_error_json(StatusCode::NOT_FOUND, "page not found"));
-        }
-        Err(_) => {
-            return Ok(resp_error_json(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "page lookup failed",
-            ));
-        }
-    };
-
-    if page_index.deleted() {
-        return Ok(resp_error_json(StatusCode::GONE, "page deleted"));
-    }
+    let shared = state;
+    let (staged_path, limit) = {
+        let state = match shared.read() {
+            Ok(state) => state,
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "state lock failed",
+                ));
+            }
+        };
 
-    /*
-     * ロック検証
-     */
-    let lock_info = match state.db().get_page_lock_info(&page_id) {
-        Ok(info) => info,
-        Err(_) => {
-            return Ok(resp_error_json(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "lock lookup failed",
-            ));
-        }
-    };
+        /*
+         * ページ情報取得
+         */
+        let page_index = match state.db().get_page_index_by_id(&page_id) {
+            Ok(Some(index)) => index,
+            Ok(None) => {
+                return Ok(resp_error_json(StatusCode::NOT_FOUND, "page not found"));
+            }
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "page lookup failed",
+                ));
+            }
+        };
 
-    if let Some(lock_info) = lock_info {
-        if !req.headers().contains_key(LOCK_AUTH_HEADER) {
-            return Ok(resp_error_json(StatusCode::LOCKED, "page locked"));
+        if page_index.deleted() {
+            return Ok(resp_error_json(StatusCode::GONE, "page deleted"));
         }
 
-        let token = match parse_lock_token(&req) {
-            Ok(token) => token,
-            Err(resp) => return Ok(resp),
+        /*
+         * ロック検証
+         */
+        let lock_info = match state.db().get_page_lock_info(&page_id) {
+            Ok(info) => info,
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "lock lookup failed",
+                ));
+            }
         };
 
-        if lock_info.token() != token {
-            return Ok(resp_error_json(
-                StatusCode::FORBIDDEN,
-                "lock token invalid",
-            ));
-        }
+        if let Some(lock_info) = lock_info {
+            if !req.headers().contains_key(LOCK_AUTH_HEADER) {
+                return Ok(resp_error_json(StatusCode::LOCKED, "page locked"));
+            }
 
-        let user_id = match state.db().get_user_id_by_name(&auth_user) {
-            Ok(Some(user_id)) => user_id,
-            Ok(None) => {
+            let token = match parse_lock_token(&req) {
+                Ok(token) => token,
+                Err(resp) => return Ok(resp),
+            };
+
+            if lock_info.token() != token {
                 return Ok(resp_error_json(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "user not found",
+                    StatusCode::FORBIDDEN,
+                    "lock token invalid",
                 ));
             }
+
+            let user_id = match state.db().get_user_id_by_name(&auth_user) {
+                Ok(Some(user_id)) => user_id,
+                Ok(None) => {
+                    return Ok(resp_error_json(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "user not found",
+                    ));
+                }
+                Err(_) => {
+                    return Ok(resp_error_json(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "user lookup failed",
+                    ));
+                }
+            };
+
+            if lock_info.user() != user_id {
+                return Ok(resp_error_json(StatusCode::FORBIDDEN, "lock forbidden"));
+            }
+        }
+
+        /*
+         * アセットデータの受信(受信中は共有状態のロックを解放する)
+         */
+        let staged_path = match state.db().new_asset_staging_path() {
+            Ok(path) => path,
             Err(_) => {
                 return Ok(resp_error_json(
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    "user lookup failed",
+                    "asset create failed",
                 ));
             }
         };
+        let limit = state.asset_limit_size();
+        (staged_path, limit)
+    };
 
-        if lock_info.user() != user_id {
-            return Ok(resp_error_json(StatusCode::FORBIDDEN, "lock forbidden"));
-        }
+    if let Err(resp) =
+        receive_asset_payload(&req, payload, &staged_path, limit).await
+    {
+        return Ok(resp);
     }
 
+    let state = match shared.read() {
+        Ok(state) => state,
+        Err(_) => {
+            let _ = fs::remove_file(&staged_path);
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+
     /*
      * アセット作成
      */
     let asset_id =
         match state
             .db()
-            .create_asset(
+            .create_asset_from_file(
                 &page_id,
                 &file_name,
                 &mime,
                 &auth_user,
-                body.as_ref(),
+                &staged_path,
             )
         {
             Ok(asset_id) => asset_id,
@@ -374,6 +411,13 @@ pub async fn post(
             }
         };
 
+    /*
+     * FTSの更新
+     */
+    if let Err(resp) = reindex_asset_fts(&state, &asset_id) {
+        return Ok(resp);
+    }
+
     /*
      * レスポンス生成
      */
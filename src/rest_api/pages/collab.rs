@@ -0,0 +1,285 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! ページ共同編集用WebSocket APIの実装をまとめたモジュール
+//!
+
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use actix_web::http::StatusCode;
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, web};
+use actix_ws::{AggregatedMessage, CloseCode, MessageStream, Session};
+use log::warn;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+use super::super::resp_error_json;
+use crate::collab::{CollabEvent, CollabHub};
+use crate::database::DbError;
+use crate::database::types::{BearerScope, PageId};
+use crate::http_server::app_state::AppState;
+use crate::rest_api::{AuthContext, require_request_scope};
+
+/// 受信メッセージの最大サイズ(分割フレームの結合後を含む)
+const MAX_FRAME_SIZE: usize = 4 * 1024 * 1024;
+
+/// Pingの送出間隔
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 無通信とみなして切断するまでの時間
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(90);
+
+///
+/// GET /api/pages/{page_id}/collab の実体
+///
+/// # 概要
+/// 共同編集セッションへの参加(WebSocketへのアップグレード)
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+/// * `path` - ページID
+/// * `payload` - アップグレード後の受信ストリーム
+///
+/// # 戻り値
+/// actix-webのレスポンスオブジェクト
+///
+pub async fn get(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    path: web::Path<String>,
+    payload: web::Payload,
+) -> actix_web::Result<HttpResponse> {
+    if let Err(resp) = require_request_scope(&req, BearerScope::Write) {
+        return Ok(resp);
+    }
+
+    /*
+     * ページID解析
+     */
+    let page_id = match PageId::from_string(&path.into_inner()) {
+        Ok(page_id) => page_id,
+        Err(_) => {
+            return Ok(resp_error_json(StatusCode::NOT_FOUND, "page not found"));
+        }
+    };
+
+    /*
+     * 認証ユーザ取得
+     */
+    let auth_user = match req.extensions().get::<AuthContext>() {
+        Some(context) => context.user_id().to_string(),
+        None => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "auth context missing",
+            ));
+        }
+    };
+
+    /*
+     * ハンドシェイク
+     */
+    let (response, session, stream) = match actix_ws::handle(&req, payload) {
+        Ok(parts) => parts,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "websocket handshake required",
+            ));
+        }
+    };
+
+    /*
+     * セッションへの参加
+     */
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let (hub, client_id) = {
+        let state = match state.read() {
+            Ok(state) => state,
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "state lock failed",
+                ));
+            }
+        };
+
+        let hub = match state.collab_hub() {
+            Some(hub) => hub,
+            None => {
+                return Ok(resp_error_json(
+                    StatusCode::NOT_FOUND,
+                    "collaborative editing disabled",
+                ));
+            }
+        };
+
+        match state.db().get_page_index_by_id(&page_id) {
+            Ok(Some(index)) if index.deleted() => {
+                return Ok(resp_error_json(StatusCode::GONE, "page deleted"));
+            }
+            Ok(Some(index)) if index.is_draft() => {
+                return Ok(resp_error_json(
+                    StatusCode::CONFLICT,
+                    "page already locked",
+                ));
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return Ok(resp_error_json(
+                    StatusCode::NOT_FOUND,
+                    "page not found",
+                ));
+            }
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "page lookup failed",
+                ));
+            }
+        }
+
+        match hub.join(&state, &page_id, &auth_user, sender) {
+            Ok(client_id) => (hub, client_id),
+            Err(err) => {
+                if let Some(DbError::PageLocked) = err.downcast_ref::<DbError>()
+                {
+                    return Ok(resp_error_json(
+                        StatusCode::CONFLICT,
+                        "page already locked",
+                    ));
+                }
+
+                return Ok(resp_error_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "collab join failed",
+                ));
+            }
+        }
+    };
+
+    /*
+     * 送受信処理の起動
+     */
+    actix_web::rt::spawn(session_loop(
+        state.clone(),
+        hub,
+        page_id,
+        client_id,
+        session,
+        stream,
+        receiver,
+    ));
+
+    Ok(response)
+}
+
+///
+/// 接続ごとの送受信ループ
+///
+/// # 引数
+/// * `state` - 共有状態
+/// * `hub` - 共同編集セッションの管理
+/// * `page_id` - 対象ページ
+/// * `client_id` - 接続ID
+/// * `session` - WebSocketの送信側
+/// * `stream` - WebSocketの受信側
+/// * `receiver` - 自接続へのイベント受信側
+///
+/// # 注記
+/// クライアントからのCloseには同じ理由のCloseを返して終了する。
+/// `HEARTBEAT_INTERVAL`ごとにPingを送出し、`CLIENT_TIMEOUT`の間何も受信
+/// しなかった接続は切断する。接続の終了時にセッションから離脱する。
+///
+async fn session_loop(
+    state: web::Data<Arc<RwLock<AppState>>>,
+    hub: Arc<CollabHub>,
+    page_id: PageId,
+    client_id: u64,
+    mut session: Session,
+    stream: MessageStream,
+    mut receiver: UnboundedReceiver<CollabEvent>,
+) {
+    let mut stream = stream
+        .max_frame_size(MAX_FRAME_SIZE)
+        .aggregate_continuations()
+        .max_continuation_size(MAX_FRAME_SIZE);
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    let mut last_seen = Instant::now();
+
+    let reason = loop {
+        tokio::select! {
+            /*
+             * クライアントからの受信
+             */
+            message = stream.recv() => {
+                let message = match message {
+                    Some(Ok(message)) => message,
+                    Some(Err(_)) => break Some(CloseCode::Protocol.into()),
+                    None => break None,
+                };
+                last_seen = Instant::now();
+
+                match message {
+                    AggregatedMessage::Text(text) => {
+                        if hub.handle_text(&page_id, client_id, &text).is_err() {
+                            break Some(CloseCode::Policy.into());
+                        }
+                    }
+                    AggregatedMessage::Ping(data) => {
+                        if session.pong(&data).await.is_err() {
+                            break None;
+                        }
+                    }
+                    AggregatedMessage::Close(reason) => break reason,
+                    AggregatedMessage::Binary(_) | AggregatedMessage::Pong(_) => {}
+                }
+            }
+
+            /*
+             * セッションからのイベント送出
+             */
+            event = receiver.recv() => match event {
+                Some(CollabEvent::Text(text)) => {
+                    if session.text(text).await.is_err() {
+                        break None;
+                    }
+                }
+                Some(CollabEvent::Close) | None => {
+                    break Some(CloseCode::Normal.into());
+                }
+            },
+
+            /*
+             * 死活監視
+             */
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() > CLIENT_TIMEOUT {
+                    break Some(CloseCode::Away.into());
+                }
+                if session.ping(b"").await.is_err() {
+                    break None;
+                }
+            }
+        }
+    };
+
+    /*
+     * セッションからの離脱
+     */
+    match state.read() {
+        Ok(state) => {
+            if let Err(err) = hub.leave(&state, &page_id, client_id) {
+                warn!("collab leave failed: {:?}", err);
+            }
+        }
+        Err(_) => warn!("collab leave skipped: state lock failed"),
+    }
+
+    let _ = session.close(reason).await;
+}
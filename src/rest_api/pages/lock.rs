@@ -244,6 +244,12 @@ pub async fn put(
                     "lock forbidden",
                 ));
             }
+            if let Some(DbError::LockBroken) = err.downcast_ref::<DbError>() {
+                return Ok(resp_error_json(
+                    StatusCode::CONFLICT,
+                    "lock broken by administrator",
+                ));
+            }
 
             return Ok(resp_error_json(
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -474,6 +480,12 @@ pub async fn delete(
                     "lock forbidden",
                 ));
             }
+            if let Some(DbError::LockBroken) = err.downcast_ref::<DbError>() {
+                return Ok(resp_error_json(
+                    StatusCode::CONFLICT,
+                    "lock broken by administrator",
+                ));
+            }
 
             return Ok(resp_error_json(
                 StatusCode::INTERNAL_SERVER_ERROR,
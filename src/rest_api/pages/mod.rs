@@ -9,6 +9,7 @@
 //!
 
 pub(crate) mod assets;
+pub(crate) mod collab;
 pub(crate) mod delete;
 pub(crate) mod deleted;
 pub(crate) mod id;
@@ -18,11 +18,16 @@ use serde::Deserialize;
 use serde_json::json;
 
 use super::super::resp_error_json;
-use crate::database::types::{BearerScope, PageId};
+use crate::database::types::{AssetId, BearerScope, PageId};
 use crate::fts::{self, FtsSearchTarget};
 use crate::http_server::app_state::AppState;
 use crate::rest_api::require_request_scope;
 
+///
+/// 検索結果の集約キー(ページID, リビジョン, アセットID)
+///
+type SearchResultKey = (PageId, u64, Option<AssetId>);
+
 #[derive(Deserialize)]
 struct SearchQuery {
     expr: String,
@@ -111,7 +116,7 @@ pub async fn get(
     /*
      * 検索の実行と結果の集約
      */
-    let mut merged: HashMap<(PageId, u64), fts::FtsSearchResult> =
+    let mut merged: HashMap<SearchResultKey, fts::FtsSearchResult> =
         HashMap::new();
     for target in targets {
         let results = match fts::search_index(
@@ -167,6 +172,8 @@ pub async fn get(
                 "path": path,
                 "deleted": deleted,
                 "text": result.snippet(),
+                "asset_id": result.asset_id().map(|id| id.to_string()),
+                "file_name": result.file_name(),
             }),
         ));
     }
@@ -205,6 +212,7 @@ fn parse_target_param(
     let mut body = false;
     let mut code = false;
     let mut front_matter = false;
+    let mut assets = false;
     for item in raw.split(',') {
         let item = item.trim();
         if item.is_empty() {
@@ -219,6 +227,7 @@ fn parse_target_param(
             "body" => body = true,
             "code" => code = true,
             "front_matter" => front_matter = true,
+            "assets" => assets = true,
             _ => {
                 return Err(resp_error_json(
                     StatusCode::BAD_REQUEST,
@@ -228,7 +237,7 @@ fn parse_target_param(
         }
     }
 
-    if !headings && !body && !code && !front_matter {
+    if !headings && !body && !code && !front_matter && !assets {
         return Err(resp_error_json(
             StatusCode::BAD_REQUEST,
             "invalid query parameter: target",
@@ -248,6 +257,9 @@ fn parse_target_param(
     if front_matter {
         targets.push(FtsSearchTarget::FrontMatter);
     }
+    if assets {
+        targets.push(FtsSearchTarget::Assets);
+    }
 
     Ok(targets)
 }
@@ -290,11 +302,11 @@ fn parse_bool_param(
 /// なし
 ///
 fn merge_results(
-    merged: &mut HashMap<(PageId, u64), fts::FtsSearchResult>,
+    merged: &mut HashMap<SearchResultKey, fts::FtsSearchResult>,
     results: Vec<fts::FtsSearchResult>,
 ) {
     for result in results {
-        let key = (result.page_id(), result.revision());
+        let key = (result.page_id(), result.revision(), result.asset_id());
         let replace = match merged.get(&key) {
             Some(existing) => result.score() > existing.score(),
             None => true,
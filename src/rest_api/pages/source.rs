@@ -24,6 +24,11 @@ use crate::markdown_source::front_matter::{
     FrontMatterError,
     FrontMatterValidationError,
 };
+use crate::markdown_source::merge::{
+    MergeConflict,
+    MergeOutcome,
+    merge_three_way,
+};
 use crate::rest_api::AuthContext;
 use crate::rest_api::{
     build_etag,
@@ -43,6 +48,7 @@ struct GetSourceQuery {
 #[derive(Deserialize)]
 struct PutSourceQuery {
     amend: Option<String>,
+    base_rev: Option<String>,
 }
 
 ///
@@ -126,6 +132,36 @@ fn resp_front_matter_error(
     }
 }
 
+///
+/// マージ競合を REST API 向けレスポンスへ変換する
+///
+/// # 引数
+/// * `base_rev` - 保存要求の基準リビジョン
+/// * `latest_rev` - 最新リビジョン
+/// * `conflicts` - 競合箇所の一覧
+///
+/// # 戻り値
+/// 変換済みレスポンスを返す。
+///
+fn resp_merge_conflict(
+    base_rev: u64,
+    latest_rev: u64,
+    conflicts: &[MergeConflict],
+) -> HttpResponse {
+    let body = json!({
+        "error": "merge conflict",
+        "kind": "merge_conflict",
+        "base_revision": base_rev,
+        "latest_revision": latest_rev,
+        "conflicts": conflicts,
+    });
+
+    HttpResponse::Conflict()
+        .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
+        .content_type("application/json")
+        .body(body.to_string())
+}
+
 ///
 /// GET /api/pages/{page_id}/source の実体
 ///
@@ -302,7 +338,8 @@ pub async fn get(
 /// エラー時はJSON形式で返却する。
 /// 処理の流れはクエリ検証、Content-Type検証、ボディ解析、
 /// 認証ユーザ取得、状態取得、
-/// ロック検証、ページ更新の順。
+/// ロック検証、基準リビジョンが古い場合のマージ、ページ更新の順。
+/// マージを行った場合はステータス200でマージ結果を返す。
 ///
 pub async fn put(
     req: HttpRequest,
@@ -341,6 +378,30 @@ pub async fn put(
         None => false,
     };
 
+    let base_rev = match query.base_rev.as_deref() {
+        Some(raw) => match raw.parse::<u64>() {
+            Ok(revision) if revision > 0 => Some(revision),
+            _ => {
+                return Ok(resp_error_json(
+                    StatusCode::BAD_REQUEST,
+                    "invalid query parameter: base_rev",
+                ));
+            }
+        },
+        None => None,
+    };
+
+    /*
+     * amend と base_rev は同時に指定できない(マージ結果で最新リビジョンを
+     * 上書きすると、取り込んだ他者の変更履歴が失われるため)
+     */
+    if amend && base_rev.is_some() {
+        return Ok(resp_error_json(
+            StatusCode::BAD_REQUEST,
+            "invalid query parameter: amend",
+        ));
+    }
+
     /*
      * Content-Typeヘッダ検証
      */
@@ -466,6 +527,16 @@ pub async fn put(
         };
 
         if lock_info.token() != token {
+            /*
+             * 強制解除されたロックの保持者への通知
+             */
+            if let Ok(true) = state.db().is_broken_lock(&token) {
+                return Ok(resp_error_json(
+                    StatusCode::CONFLICT,
+                    "lock broken by administrator",
+                ));
+            }
+
             return Ok(resp_error_json(
                 StatusCode::FORBIDDEN,
                 "lock token invalid",
@@ -496,11 +567,69 @@ pub async fn put(
     }
 
     /*
-     * ページ更新
+     * 基準リビジョンが古い場合の3-wayマージ
+     */
+    let latest_rev = page_index.latest();
+    let (source, merged_from) = match base_rev {
+        Some(base_rev) if base_rev > latest_rev => {
+            return Ok(resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "invalid query parameter: base_rev",
+            ));
+        }
+        Some(base_rev) if base_rev < latest_rev => {
+            let base = match state.db().get_page_source(&page_id, base_rev) {
+                Ok(Some(base)) => base.source(),
+                Ok(None) => {
+                    return Ok(resp_error_json(
+                        StatusCode::BAD_REQUEST,
+                        "invalid query parameter: base_rev",
+                    ));
+                }
+                Err(_) => {
+                    return Ok(resp_error_json(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "page source lookup failed",
+                    ));
+                }
+            };
+            let latest =
+                match state.db().get_page_source(&page_id, latest_rev) {
+                    Ok(Some(latest)) => latest.source(),
+                    Ok(None) | Err(_) => {
+                        return Ok(resp_error_json(
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            "page source lookup failed",
+                        ));
+                    }
+                };
+
+            match merge_three_way(&base, &latest, &source) {
+                MergeOutcome::Merged(merged) => (merged, Some(base_rev)),
+                MergeOutcome::Conflict(conflicts) => {
+                    return Ok(resp_merge_conflict(
+                        base_rev,
+                        latest_rev,
+                        &conflicts,
+                    ));
+                }
+            }
+        }
+        _ => (source, None),
+    };
+
+    /*
+     * ページ更新(base_rev 指定時は確認した最新リビジョンから進んでいないことを
+     * 書き込みトランザクション内で検証する)
      */
     let source_for_error = source.clone();
-    let update_result =
-        state.db().put_page(&page_id, &auth_user, source, amend);
+    let update_result = if base_rev.is_some() {
+        state
+            .db()
+            .put_page_if_latest(&page_id, &auth_user, source, latest_rev)
+    } else {
+        state.db().put_page(&page_id, &auth_user, source, amend)
+    };
     match update_result {
         Ok(()) => {}
         Err(err) => {
@@ -528,6 +657,14 @@ pub async fn put(
                     "page not found",
                 ));
             }
+            if let Some(crate::database::DbError::RevisionConflict) =
+                err.downcast_ref::<crate::database::DbError>()
+            {
+                return Ok(resp_error_json(
+                    StatusCode::CONFLICT,
+                    "page updated concurrently",
+                ));
+            }
             if let Some(crate::database::DbError::UserNotFound) =
                 err.downcast_ref::<crate::database::DbError>()
             {
@@ -594,7 +731,30 @@ pub async fn put(
     /*
      * レスポンス生成
      */
-    Ok(HttpResponse::NoContent().finish())
+    match merged_from {
+        Some(base_rev) => {
+            let revision = match state.db().get_page_index_by_id(&page_id) {
+                Ok(Some(index)) => index.latest(),
+                Ok(None) | Err(_) => {
+                    return Ok(resp_error_json(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "page lookup failed",
+                    ));
+                }
+            };
+            let body = json!({
+                "merged": true,
+                "base_revision": base_rev,
+                "revision": revision,
+            });
+
+            Ok(HttpResponse::Ok()
+                .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
+                .content_type("application/json")
+                .body(body.to_string()))
+        }
+        None => Ok(HttpResponse::NoContent().finish()),
+    }
 }
 
 ///
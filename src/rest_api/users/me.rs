@@ -94,6 +94,7 @@ pub async fn get(
             .iter()
             .map(|attribute| attribute.as_str())
             .collect::<Vec<_>>(),
+        "mfa_enrolled": user_info.is_mfa_enrolled(),
         "timestamp": timestamp_iso,
     });
 
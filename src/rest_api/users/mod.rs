@@ -9,3 +9,4 @@
 //!
 
 pub(crate) mod me;
+pub(crate) mod tokens;
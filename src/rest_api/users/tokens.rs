@@ -0,0 +1,930 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+//!
+//! 自分自身の Bearerトークン管理APIの実装をまとめたモジュール
+//!
+
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
+
+use actix_web::http::{header, StatusCode};
+use actix_web::{HttpRequest, HttpResponse, web};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Deserializer};
+use serde_json::{Value, json};
+
+use crate::audit::model::{AuditOperation, AuditRecord, AuditResult};
+use crate::cmd_args::{
+    DEFAULT_TOKEN_CREATE_TTL,
+    parse_path_prefixes,
+    parse_token_ttl,
+    validate_token_name,
+};
+use crate::database::types::{
+    BearerScope,
+    BearerScopeSet,
+    BearerTokenInfo,
+    PathPrefixSet,
+    TokenId,
+    UserAttribute,
+    UserId,
+};
+use crate::http_server::app_state::AppState;
+use crate::rest_api::auth::auth_context_from_request;
+use crate::rest_api::{AuthContext, CACHE_CONTROL_NO_STORE, resp_error_json};
+
+///
+/// POST /api/users/me/tokens のリクエストボディ
+///
+#[derive(Deserialize)]
+struct CreateTokenRequest {
+    /// 任意のトークン名
+    name: Option<String>,
+
+    /// 付与スコープ
+    scopes: Vec<String>,
+
+    /// TTL (`30d`, `12h`, `90m` 形式)
+    ttl: Option<String>,
+
+    /// path prefix 制約
+    #[serde(default)]
+    path_prefixes: Vec<String>,
+}
+
+///
+/// PATCH /api/users/me/tokens/{token_id} のリクエストボディ
+///
+#[derive(Deserialize)]
+struct EditTokenRequest {
+    /// 更新後の任意名(`null` で名前を取り除く)
+    #[serde(default, deserialize_with = "deserialize_present")]
+    name: Option<Option<String>>,
+
+    /// 更新後の path prefix 制約(空配列で全領域を許可する)
+    path_prefixes: Option<Vec<String>>,
+}
+
+///
+/// POST /api/users/me/tokens/{token_id}/rotate のリクエストボディ
+///
+#[derive(Default, Deserialize)]
+struct RotateTokenRequest {
+    /// 旧トークンを受け付ける猶予期間 (`30d`, `12h`, `90m` 形式)
+    grace: Option<String>,
+}
+
+///
+/// 操作主体の情報
+///
+struct TokenOwner {
+    /// 操作主体のユーザID
+    user_id: UserId,
+
+    /// 操作主体の属性を含む認証文脈
+    auth: AuthContext,
+
+    /// 入力元アドレス
+    address: Option<IpAddr>,
+}
+
+///
+/// GET /api/users/me/tokens の実体
+///
+/// # 概要
+/// 認証済みユーザ自身の Bearerトークン一覧を取得する。
+///
+pub async fn get(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+) -> actix_web::Result<HttpResponse> {
+    /*
+     * 共有状態と操作主体の取得
+     */
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+    let owner = match resolve_owner(&req, &state) {
+        Ok(owner) => owner,
+        Err(resp) => return Ok(resp),
+    };
+
+    /*
+     * 自分のトークンのみを抽出する
+     */
+    let now = Local::now();
+    let mut tokens = match state.db().filter_bearer_tokens(
+        Some(&owner.user_id),
+        false,
+        false,
+        now,
+    ) {
+        Ok(tokens) => tokens,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "token query failed",
+            ));
+        }
+    };
+    tokens.sort_by_key(|info| info.created_at());
+
+    let body = json!({
+        "tokens": tokens
+            .iter()
+            .map(|info| token_info_json(info, now))
+            .collect::<Vec<_>>(),
+    });
+
+    Ok(json_response(StatusCode::OK, body))
+}
+
+///
+/// POST /api/users/me/tokens の実体
+///
+/// # 概要
+/// 認証済みユーザ自身を発行対象とする Bearerトークンを発行する。
+///
+/// # 注記
+/// トークン平文はこの応答でのみ返す。
+///
+pub async fn post(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    body: web::Bytes,
+) -> actix_web::Result<HttpResponse> {
+    /*
+     * 共有状態と操作主体の取得
+     */
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+    let owner = match resolve_owner(&req, &state) {
+        Ok(owner) => owner,
+        Err(resp) => return Ok(resp),
+    };
+
+    /*
+     * 入力の解析と検証
+     */
+    let request = match serde_json::from_slice::<CreateTokenRequest>(&body) {
+        Ok(request) => request,
+        Err(_) => {
+            record_token_audit(
+                &state,
+                &owner,
+                AuditOperation::TokenCreate,
+                AuditResult::InvalidInput,
+                None,
+            );
+            return Ok(resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "invalid request body",
+            ));
+        }
+    };
+    let (name, scopes, ttl, path_prefixes) =
+        match validate_create_request(&request) {
+            Ok(parsed) => parsed,
+            Err(message) => {
+                record_token_audit(
+                    &state,
+                    &owner,
+                    AuditOperation::TokenCreate,
+                    AuditResult::InvalidInput,
+                    Some(message.clone()),
+                );
+                return Ok(resp_error_json(StatusCode::BAD_REQUEST, message));
+            }
+        };
+
+    /*
+     * 自身の属性を超えるスコープの発行を拒否する
+     */
+    if !scopes_within_attributes(&owner.auth, &scopes) {
+        record_token_audit(
+            &state,
+            &owner,
+            AuditOperation::TokenCreate,
+            AuditResult::ReadOnlyDenied,
+            Some(format!("scopes={}", format_scopes(&scopes))),
+        );
+        return Ok(resp_error_json(
+            StatusCode::FORBIDDEN,
+            "scope exceeds user attributes",
+        ));
+    }
+
+    /*
+     * トークン発行
+     */
+    let (plaintext, info) = match state.db().create_bearer_token(
+        owner.auth.user_id(),
+        scopes,
+        path_prefixes,
+        ttl,
+        name,
+    ) {
+        Ok(created) => created,
+        Err(_) => {
+            record_token_audit(
+                &state,
+                &owner,
+                AuditOperation::TokenCreate,
+                AuditResult::InternalError,
+                None,
+            );
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "token create failed",
+            ));
+        }
+    };
+
+    record_token_audit(
+        &state,
+        &owner,
+        AuditOperation::TokenCreate,
+        AuditResult::Success,
+        Some(format!(
+            "token_id={} scopes={}",
+            info.token_id(),
+            format_scopes(&info.scopes()),
+        )),
+    );
+
+    let mut body = token_info_json(&info, Local::now());
+    body["token"] = Value::String(plaintext.expose().to_string());
+
+    Ok(json_response(StatusCode::CREATED, body))
+}
+
+///
+/// PATCH /api/users/me/tokens/{token_id} の実体
+///
+/// # 概要
+/// 認証済みユーザ自身の Bearerトークンの任意名と path prefix 制約を
+/// 更新する。
+///
+pub async fn patch(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    path: web::Path<String>,
+    body: web::Bytes,
+) -> actix_web::Result<HttpResponse> {
+    /*
+     * 共有状態と操作主体の取得
+     */
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+    let owner = match resolve_owner(&req, &state) {
+        Ok(owner) => owner,
+        Err(resp) => return Ok(resp),
+    };
+
+    /*
+     * 対象トークンの解決
+     */
+    let info = match resolve_owned_token(&state, &owner, &path.into_inner()) {
+        Ok(info) => info,
+        Err(resp) => {
+            record_token_audit(
+                &state,
+                &owner,
+                AuditOperation::TokenUpdate,
+                AuditResult::NotFound,
+                None,
+            );
+            return Ok(resp);
+        }
+    };
+
+    /*
+     * 入力の解析と検証
+     */
+    let request = match serde_json::from_slice::<EditTokenRequest>(&body) {
+        Ok(request) => request,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::BAD_REQUEST,
+                "invalid request body",
+            ));
+        }
+    };
+    let (name, path_prefixes) = match validate_edit_request(&request) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            record_token_audit(
+                &state,
+                &owner,
+                AuditOperation::TokenUpdate,
+                AuditResult::InvalidInput,
+                Some(format!("token_id={} {}", info.token_id(), message)),
+            );
+            return Ok(resp_error_json(StatusCode::BAD_REQUEST, message));
+        }
+    };
+
+    if info.revoked() {
+        record_token_audit(
+            &state,
+            &owner,
+            AuditOperation::TokenUpdate,
+            AuditResult::Conflict,
+            Some(format!("token_id={} revoked", info.token_id())),
+        );
+        return Ok(resp_error_json(StatusCode::CONFLICT, "token revoked"));
+    }
+
+    /*
+     * 更新の反映
+     */
+    let updated = match state.db().edit_bearer_token(
+        &info.token_id(),
+        name,
+        path_prefixes,
+    ) {
+        Ok(updated) => updated,
+        Err(_) => {
+            record_token_audit(
+                &state,
+                &owner,
+                AuditOperation::TokenUpdate,
+                AuditResult::InternalError,
+                Some(format!("token_id={}", info.token_id())),
+            );
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "token update failed",
+            ));
+        }
+    };
+
+    record_token_audit(
+        &state,
+        &owner,
+        AuditOperation::TokenUpdate,
+        AuditResult::Success,
+        Some(format!(
+            "token_id={} path_prefixes={}",
+            updated.token_id(),
+            updated.path_prefixes().iter().collect::<Vec<_>>().join(","),
+        )),
+    );
+
+    Ok(json_response(
+        StatusCode::OK,
+        token_info_json(&updated, Local::now()),
+    ))
+}
+
+///
+/// DELETE /api/users/me/tokens/{token_id} の実体
+///
+/// # 概要
+/// 認証済みユーザ自身の Bearerトークンを失効する。
+///
+/// # 注記
+/// 失効済みトークンに対しても成功を返す。
+///
+pub async fn delete(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    /*
+     * 共有状態と操作主体の取得
+     */
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+    let owner = match resolve_owner(&req, &state) {
+        Ok(owner) => owner,
+        Err(resp) => return Ok(resp),
+    };
+
+    /*
+     * 対象トークンの解決と失効
+     */
+    let info = match resolve_owned_token(&state, &owner, &path.into_inner()) {
+        Ok(info) => info,
+        Err(resp) => {
+            record_token_audit(
+                &state,
+                &owner,
+                AuditOperation::TokenRevoke,
+                AuditResult::NotFound,
+                None,
+            );
+            return Ok(resp);
+        }
+    };
+
+    if state.db().revoke_bearer_token_by_id(&info.token_id()).is_err() {
+        record_token_audit(
+            &state,
+            &owner,
+            AuditOperation::TokenRevoke,
+            AuditResult::InternalError,
+            Some(format!("token_id={}", info.token_id())),
+        );
+        return Ok(resp_error_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "token revoke failed",
+        ));
+    }
+
+    record_token_audit(
+        &state,
+        &owner,
+        AuditOperation::TokenRevoke,
+        AuditResult::Success,
+        Some(format!("token_id={}", info.token_id())),
+    );
+
+    Ok(HttpResponse::NoContent()
+        .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
+        .finish())
+}
+
+///
+/// POST /api/users/me/tokens/{token_id}/rotate の実体
+///
+/// # 概要
+/// 認証済みユーザ自身の Bearerトークンへ新しい平文を発行する。
+///
+/// # 注記
+/// トークンIDは変更せず、旧平文は猶予期限まで引き続き受け付ける。
+/// 新しい平文はこの応答でのみ返す。
+///
+pub async fn rotate(
+    req: HttpRequest,
+    state: web::Data<Arc<RwLock<AppState>>>,
+    path: web::Path<String>,
+    body: web::Bytes,
+) -> actix_web::Result<HttpResponse> {
+    /*
+     * 共有状態と操作主体の取得
+     */
+    let state = match state.read() {
+        Ok(state) => state,
+        Err(_) => {
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "state lock failed",
+            ));
+        }
+    };
+    let owner = match resolve_owner(&req, &state) {
+        Ok(owner) => owner,
+        Err(resp) => return Ok(resp),
+    };
+
+    /*
+     * 対象トークンの解決
+     */
+    let info = match resolve_owned_token(&state, &owner, &path.into_inner()) {
+        Ok(info) => info,
+        Err(resp) => {
+            record_token_audit(
+                &state,
+                &owner,
+                AuditOperation::TokenRotate,
+                AuditResult::NotFound,
+                None,
+            );
+            return Ok(resp);
+        }
+    };
+
+    /*
+     * 入力の解析と検証(ボディ省略時は既定の猶予期間を用いる)
+     */
+    let request = if body.is_empty() {
+        RotateTokenRequest::default()
+    } else {
+        match serde_json::from_slice::<RotateTokenRequest>(&body) {
+            Ok(request) => request,
+            Err(_) => {
+                return Ok(resp_error_json(
+                    StatusCode::BAD_REQUEST,
+                    "invalid request body",
+                ));
+            }
+        }
+    };
+    let grace = match request.grace.as_deref() {
+        Some(raw) => match parse_token_ttl(raw) {
+            Ok(grace) => grace,
+            Err(err) => {
+                let message = format!("invalid grace: {}", err);
+                record_token_audit(
+                    &state,
+                    &owner,
+                    AuditOperation::TokenRotate,
+                    AuditResult::InvalidInput,
+                    Some(format!("token_id={} {}", info.token_id(), message)),
+                );
+                return Ok(resp_error_json(StatusCode::BAD_REQUEST, message));
+            }
+        },
+        None => state.auth_config().token_rotation_grace(),
+    };
+
+    let now = Local::now();
+    if info.revoked() || info.expire_at() <= now {
+        let reason = if info.revoked() { "revoked" } else { "expired" };
+        record_token_audit(
+            &state,
+            &owner,
+            AuditOperation::TokenRotate,
+            AuditResult::Conflict,
+            Some(format!("token_id={} {}", info.token_id(), reason)),
+        );
+        return Ok(resp_error_json(
+            StatusCode::CONFLICT,
+            format!("token {}", reason),
+        ));
+    }
+
+    /*
+     * ローテーションの実行
+     */
+    let result = match state.db().rotate_bearer_token(
+        &info.token_id(),
+        grace,
+        now,
+    ) {
+        Ok(result) => result,
+        Err(_) => {
+            record_token_audit(
+                &state,
+                &owner,
+                AuditOperation::TokenRotate,
+                AuditResult::InternalError,
+                Some(format!("token_id={}", info.token_id())),
+            );
+            return Ok(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "token rotate failed",
+            ));
+        }
+    };
+
+    record_token_audit(
+        &state,
+        &owner,
+        AuditOperation::TokenRotate,
+        AuditResult::Success,
+        Some(result.audit_summary()),
+    );
+
+    let mut body = token_info_json(&result.token_info(), Local::now());
+    body["token"] = Value::String(result.plaintext().expose().to_string());
+    body["grace_until"] = match result.grace_until() {
+        Some(grace_until) => Value::String(format_timestamp(grace_until)),
+        None => Value::Null,
+    };
+
+    Ok(json_response(StatusCode::OK, body))
+}
+
+///
+/// 操作主体の解決
+///
+/// # 引数
+/// * `req` - HTTPリクエスト
+/// * `state` - 共有状態
+///
+/// # 戻り値
+/// 操作主体の情報を返す。Bearer認証によるリクエストの場合は 403 応答を
+/// 返す。
+///
+/// # 注記
+/// トークンから別トークンを連鎖的に発行して path prefix 制約やスコープを
+/// 回避できないよう、トークン管理は Basic認証でのみ受け付ける。
+///
+fn resolve_owner(
+    req: &HttpRequest,
+    state: &AppState,
+) -> Result<TokenOwner, HttpResponse> {
+    let auth = auth_context_from_request(req)?;
+
+    if auth.token_id().is_some() {
+        return Err(resp_error_json(
+            StatusCode::FORBIDDEN,
+            "token management requires basic authentication",
+        ));
+    }
+
+    let user_id = match state.db().get_user_id_by_name(auth.user_id()) {
+        Ok(Some(user_id)) => user_id,
+        Ok(None) => {
+            return Err(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "user not found",
+            ));
+        }
+        Err(_) => {
+            return Err(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "user query failed",
+            ));
+        }
+    };
+
+    Ok(TokenOwner {
+        user_id,
+        auth,
+        address: req.peer_addr().map(|addr| addr.ip()),
+    })
+}
+
+///
+/// 操作主体が所有するトークンの解決
+///
+/// # 引数
+/// * `state` - 共有状態
+/// * `owner` - 操作主体
+/// * `raw_token_id` - パスで指定されたトークンID
+///
+/// # 戻り値
+/// 操作主体が所有するトークンの管理情報を返す。存在しない場合や他ユーザの
+/// トークンの場合は 404 応答を返す。
+///
+fn resolve_owned_token(
+    state: &AppState,
+    owner: &TokenOwner,
+    raw_token_id: &str,
+) -> Result<BearerTokenInfo, HttpResponse> {
+    let not_found =
+        || resp_error_json(StatusCode::NOT_FOUND, "token not found");
+
+    let token_id = TokenId::from_string(raw_token_id).map_err(|_| not_found())?;
+    let info = match state.db().get_bearer_token_info_by_id(&token_id) {
+        Ok(Some(info)) => info,
+        Ok(None) => return Err(not_found()),
+        Err(_) => {
+            return Err(resp_error_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "token query failed",
+            ));
+        }
+    };
+
+    if info.user_id() != owner.user_id {
+        return Err(not_found());
+    }
+
+    Ok(info)
+}
+
+///
+/// 発行リクエストの検証
+///
+/// # 引数
+/// * `request` - 発行リクエスト
+///
+/// # 戻り値
+/// 検証済みの任意名、スコープ集合、TTL、path prefix 制約集合を返す。
+/// 検証に失敗した場合はエラーメッセージを返す。
+///
+fn validate_create_request(
+    request: &CreateTokenRequest,
+) -> Result<
+    (Option<String>, BearerScopeSet, chrono::Duration, PathPrefixSet),
+    String,
+> {
+    let name = normalize_name(request.name.as_deref())?;
+
+    let mut scopes = BearerScopeSet::new();
+    for raw_scope in &request.scopes {
+        let scope = BearerScope::try_from(raw_scope.trim())
+            .map_err(|err| err.to_string())?;
+        scopes.insert(scope);
+    }
+    if scopes.is_empty() {
+        return Err("scope must not be empty".to_string());
+    }
+
+    let ttl = parse_token_ttl(
+        request.ttl.as_deref().unwrap_or(DEFAULT_TOKEN_CREATE_TTL),
+    )
+    .map_err(|err| err.to_string())?;
+    let path_prefixes = parse_path_prefixes(&request.path_prefixes)
+        .map_err(|err| err.to_string())?;
+
+    Ok((name, scopes, ttl, path_prefixes))
+}
+
+///
+/// 編集リクエストの検証
+///
+/// # 引数
+/// * `request` - 編集リクエスト
+///
+/// # 戻り値
+/// 検証済みの任意名と path prefix 制約集合を返す(未指定項目は `None`)。
+/// 検証に失敗した場合はエラーメッセージを返す。
+///
+fn validate_edit_request(
+    request: &EditTokenRequest,
+) -> Result<(Option<Option<String>>, Option<PathPrefixSet>), String> {
+    if request.name.is_none() && request.path_prefixes.is_none() {
+        return Err("no update specified".to_string());
+    }
+
+    let name = match request.name.as_ref() {
+        Some(name) => Some(normalize_name(name.as_deref())?),
+        None => None,
+    };
+    let path_prefixes = match request.path_prefixes.as_ref() {
+        Some(raw) => {
+            Some(parse_path_prefixes(raw).map_err(|err| err.to_string())?)
+        }
+        None => None,
+    };
+
+    Ok((name, path_prefixes))
+}
+
+///
+/// トークン名の正規化と検証
+///
+/// # 引数
+/// * `name` - 入力されたトークン名
+///
+/// # 戻り値
+/// 前後空白を除去した検証済みのトークン名を返す。
+///
+fn normalize_name(name: Option<&str>) -> Result<Option<String>, String> {
+    match name.map(str::trim) {
+        Some(name) => {
+            validate_token_name(name).map_err(|err| err.to_string())?;
+            Ok(Some(name.to_string()))
+        }
+        None => Ok(None),
+    }
+}
+
+///
+/// スコープ集合がユーザ属性の範囲内かを判定する
+///
+/// # 引数
+/// * `auth` - 操作主体の認証文脈
+/// * `scopes` - 発行しようとしているスコープ集合
+///
+/// # 戻り値
+/// 発行可能な場合は `true` を返す。
+///
+/// # 注記
+/// `ReadOnly` 属性を持つユーザは `read` 以外のスコープを発行できない。
+///
+fn scopes_within_attributes(
+    auth: &AuthContext,
+    scopes: &BearerScopeSet,
+) -> bool {
+    if auth.user_attributes().contains(UserAttribute::ReadOnly) {
+        return scopes.iter().all(|scope| *scope == BearerScope::Read);
+    }
+
+    true
+}
+
+///
+/// トークン管理操作の監査レコードを記録する
+///
+/// # 引数
+/// * `state` - 共有状態
+/// * `owner` - 操作主体
+/// * `operation` - 操作種別
+/// * `result` - 操作結果分類
+/// * `summary` - 補足要約
+///
+fn record_token_audit(
+    state: &AppState,
+    owner: &TokenOwner,
+    operation: AuditOperation,
+    result: AuditResult,
+    summary: Option<String>,
+) {
+    state.record_audit(AuditRecord::new(
+        operation,
+        Some(owner.user_id.clone()),
+        None,
+        owner.address,
+        None,
+        result,
+        Utc::now(),
+        summary,
+        None,
+    ));
+}
+
+///
+/// トークン管理情報のJSON表現を生成する
+///
+/// # 引数
+/// * `info` - トークン管理情報
+/// * `now` - 期限切れ判定の基準時刻
+///
+/// # 戻り値
+/// レスポンス用のJSON値を返す。
+///
+fn token_info_json(info: &BearerTokenInfo, now: DateTime<Local>) -> Value {
+    json!({
+        "token_id": info.token_id().to_string(),
+        "name": info.name(),
+        "scopes": info
+            .scopes()
+            .iter()
+            .map(|scope| scope.as_str())
+            .collect::<Vec<_>>(),
+        "path_prefixes": info.path_prefixes().iter().collect::<Vec<_>>(),
+        "created_at": format_timestamp(info.created_at()),
+        "updated_at": format_timestamp(info.updated_at()),
+        "expire_at": format_timestamp(info.expire_at()),
+        "rotated_at": info.rotated_at().map(format_timestamp),
+        "revoked": info.revoked(),
+        "expired": info.expire_at() <= now,
+    })
+}
+
+///
+/// スコープ集合の表示文字列を生成する
+///
+/// # 引数
+/// * `scopes` - スコープ集合
+///
+/// # 戻り値
+/// カンマ区切りのスコープ表示文字列を返す。
+///
+fn format_scopes(scopes: &BearerScopeSet) -> String {
+    scopes
+        .iter()
+        .map(|scope| scope.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+///
+/// タイムスタンプをISO8601(タイムゾーン無し)へ整形する
+///
+fn format_timestamp(timestamp: DateTime<Local>) -> String {
+    timestamp.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+///
+/// JSONレスポンスの生成
+///
+fn json_response(status: StatusCode, body: Value) -> HttpResponse {
+    HttpResponse::build(status)
+        .content_type("application/json")
+        .insert_header((header::CACHE_CONTROL, CACHE_CONTROL_NO_STORE))
+        .body(body.to_string())
+}
+
+///
+/// 値が存在する場合に `Some` で包むデシリアライザ
+///
+/// # 注記
+/// フィールド省略(変更しない)と `null` 指定(値を取り除く)を区別するため
+/// に用いる。
+///
+fn deserialize_present<'de, D>(
+    deserializer: D,
+) -> Result<Option<Option<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer).map(Some)
+}
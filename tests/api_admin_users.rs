@@ -0,0 +1,367 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+mod common;
+
+use std::fs;
+
+use reqwest::header::CONTENT_TYPE;
+use serde_json::{Value, json};
+
+use common::*;
+
+/// 管理者属性を持つテスト用ユーザ名
+const ADMIN_USERNAME: &str = "admin_user";
+
+/// 管理APIで作成するテスト用ユーザ名
+const MANAGED_USERNAME: &str = "managed_user";
+
+/// 管理APIで再設定するパスワード
+const RESET_PASSWORD: &str = "resetpass456";
+
+#[test]
+///
+/// Admin属性: 管理APIでユーザの作成・一覧・無効化・再有効化ができることを
+/// 確認
+///
+/// # 注記
+/// 無効化中は Basic認証・Bearer認証の双方が拒否され、再有効化後に
+/// 再び認証できることを検証する。
+///
+fn admin_can_create_disable_and_enable_user() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    run_add_user_with_credentials_and_attributes(
+        &db_path,
+        &assets_dir,
+        ADMIN_USERNAME,
+        TEST_PASSWORD,
+        &["admin"],
+    );
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let url = format!("{}/admin/users", api_base_url);
+    let me_url = format!("{}/users/me", api_base_url);
+
+    /*
+     * ユーザ作成
+     */
+    let response = client
+        .post(&url)
+        .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+        .header(CONTENT_TYPE, "application/json")
+        .body(
+            json!({
+                "username": MANAGED_USERNAME,
+                "password": TEST_PASSWORD,
+                "display_name": "Managed",
+                "attributes": ["ReadOnly"],
+            })
+            .to_string(),
+        )
+        .send()
+        .expect("post admin users failed");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let body = response.text().expect("read body failed");
+    let created: Value =
+        serde_json::from_str(&body).expect("parse create response failed");
+    assert_eq!(created["username"], MANAGED_USERNAME);
+    assert_eq!(created["attributes"], json!(["ReadOnly"]));
+    assert_eq!(created["disabled"], false);
+
+    let response = client
+        .post(&url)
+        .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+        .header(CONTENT_TYPE, "application/json")
+        .body(
+            json!({ "username": MANAGED_USERNAME, "password": "x" })
+                .to_string(),
+        )
+        .send()
+        .expect("post duplicated user failed");
+    assert_eq!(response.status().as_u16(), 409);
+
+    /*
+     * 作成したユーザで認証できる
+     */
+    let response = client
+        .get(&me_url)
+        .basic_auth(MANAGED_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get users/me failed");
+    assert_eq!(response.status().as_u16(), 200);
+
+    /*
+     * 一覧に最終アクティビティ日時が含まれる
+     */
+    let response = client
+        .get(&url)
+        .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get admin users failed");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body = response.text().expect("read body failed");
+    let listed: Value =
+        serde_json::from_str(&body).expect("parse list response failed");
+    let users = listed["users"].as_array().expect("users missing");
+    assert_eq!(users.len(), 3);
+    let managed = users
+        .iter()
+        .find(|user| user["username"] == MANAGED_USERNAME)
+        .expect("managed user missing");
+    assert!(managed["last_activity"].is_string());
+
+    /*
+     * 無効化すると Basic・Bearer の双方が拒否される
+     */
+    let response = client
+        .post(format!("{}/users/me/tokens", api_base_url))
+        .basic_auth(MANAGED_USERNAME, Some(TEST_PASSWORD))
+        .header(CONTENT_TYPE, "application/json")
+        .body(json!({ "scopes": ["read"] }).to_string())
+        .send()
+        .expect("post users/me/tokens failed");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let body = response.text().expect("read body failed");
+    let issued: Value =
+        serde_json::from_str(&body).expect("parse token response failed");
+    let token = issued["token"].as_str().expect("token missing").to_string();
+
+    let response = client
+        .patch(format!("{}/{}", url, MANAGED_USERNAME))
+        .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+        .header(CONTENT_TYPE, "application/json")
+        .body(json!({ "disabled": true }).to_string())
+        .send()
+        .expect("patch disable failed");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body = response.text().expect("read body failed");
+    let disabled: Value =
+        serde_json::from_str(&body).expect("parse patch response failed");
+    assert_eq!(disabled["disabled"], true);
+    assert!(disabled["disabled_at"].is_string());
+
+    let response = client
+        .get(&me_url)
+        .basic_auth(MANAGED_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get users/me with basic failed");
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = client
+        .get(&me_url)
+        .bearer_auth(&token)
+        .send()
+        .expect("get users/me with bearer failed");
+    assert_eq!(response.status().as_u16(), 401);
+
+    /*
+     * 再有効化とパスワード再設定
+     */
+    let response = client
+        .patch(format!("{}/{}", url, MANAGED_USERNAME))
+        .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+        .header(CONTENT_TYPE, "application/json")
+        .body(
+            json!({ "disabled": false, "password": RESET_PASSWORD })
+                .to_string(),
+        )
+        .send()
+        .expect("patch enable failed");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = client
+        .get(&me_url)
+        .basic_auth(MANAGED_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get users/me with old password failed");
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = client
+        .get(&me_url)
+        .basic_auth(MANAGED_USERNAME, Some(RESET_PASSWORD))
+        .send()
+        .expect("get users/me with new password failed");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = client
+        .get(&me_url)
+        .bearer_auth(&token)
+        .send()
+        .expect("get users/me with bearer failed");
+    assert_eq!(response.status().as_u16(), 200);
+
+    /*
+     * 自分自身は無効化できない
+     */
+    let response = client
+        .patch(format!("{}/{}", url, ADMIN_USERNAME))
+        .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+        .header(CONTENT_TYPE, "application/json")
+        .body(json!({ "disabled": true }).to_string())
+        .send()
+        .expect("patch self disable failed");
+    assert_eq!(response.status().as_u16(), 409);
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// Admin属性: 属性を持たないユーザが管理APIを利用できないことを確認
+///
+/// # 注記
+/// 一覧取得・作成のいずれも 403 になることを検証する。
+///
+fn admin_api_rejects_non_admin_user() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let url = format!("{}/admin/users", api_base_url);
+
+    let response = client
+        .get(&url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get admin users failed");
+    assert_eq!(response.status().as_u16(), 403);
+
+    let response = client
+        .post(&url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header(CONTENT_TYPE, "application/json")
+        .body(
+            json!({ "username": MANAGED_USERNAME, "password": "x" })
+                .to_string(),
+        )
+        .send()
+        .expect("post admin users failed");
+    assert_eq!(response.status().as_u16(), 403);
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// Admin属性: 管理APIで監査ログを検索できることを確認
+///
+/// # 注記
+/// 管理APIでのユーザ作成が監査ログへ記録され、操作種別とユーザで
+/// 絞り込めること、ページングの `limit`/`offset` が反映されること、
+/// 属性を持たないユーザは 403 になることを検証する。
+///
+fn admin_can_query_audit_log() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    run_add_user_with_credentials_and_attributes(
+        &db_path,
+        &assets_dir,
+        ADMIN_USERNAME,
+        TEST_PASSWORD,
+        &["admin"],
+    );
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let users_url = format!("{}/admin/users", api_base_url);
+    let audit_url = format!("{}/admin/audit", api_base_url);
+
+    /*
+     * 監査対象となるユーザ作成を2回行う
+     */
+    for user_name in [MANAGED_USERNAME, "managed_user2"] {
+        let response = client
+            .post(&users_url)
+            .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+            .header(CONTENT_TYPE, "application/json")
+            .body(
+                json!({ "username": user_name, "password": TEST_PASSWORD })
+                    .to_string(),
+            )
+            .send()
+            .expect("post admin users failed");
+        assert_eq!(response.status().as_u16(), 201);
+    }
+
+    /*
+     * 操作種別とユーザによる絞り込み
+     */
+    let response = client
+        .get(&audit_url)
+        .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+        .query(&[
+            ("operation", "user_create"),
+            ("user", ADMIN_USERNAME),
+            ("limit", "1"),
+            ("with_total", "true"),
+        ])
+        .send()
+        .expect("get admin audit failed");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body: Value = serde_json::from_str(
+        &response.text().expect("read body failed"),
+    )
+    .expect("parse audit response failed");
+    assert_eq!(body["total"], 2);
+    assert_eq!(body["has_more"], true);
+    let items = body["items"].as_array().expect("items missing");
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["operation"], "user_create");
+    assert_eq!(items[0]["user_name"], ADMIN_USERNAME);
+    assert_eq!(items[0]["result"], "success");
+
+    let response = client
+        .get(&audit_url)
+        .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+        .query(&[
+            ("operation", "user_create"),
+            ("user", ADMIN_USERNAME),
+            ("offset", "1"),
+        ])
+        .send()
+        .expect("get admin audit failed");
+    let body: Value = serde_json::from_str(
+        &response.text().expect("read body failed"),
+    )
+    .expect("parse audit response failed");
+    assert!(body["total"].is_null());
+    assert_eq!(body["has_more"], false);
+    assert_eq!(body["items"].as_array().map(Vec::len), Some(1));
+
+    /*
+     * 不正な条件と非管理者
+     */
+    let response = client
+        .get(&audit_url)
+        .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+        .query(&[("operation", "unknown")])
+        .send()
+        .expect("get admin audit failed");
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = client
+        .get(&audit_url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get admin audit failed");
+    assert_eq!(response.status().as_u16(), 403);
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
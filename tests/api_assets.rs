@@ -327,6 +327,663 @@ fn get_assets_returns_redirect_and_data() {
     fs::remove_dir_all(base_dir).expect("cleanup failed");
 }
 
+#[test]
+///
+/// PUT: /api/assets/{asset_id}/data でアセットを差し替え、過去リビジョンの
+/// 取得とロールバックができることを確認する。
+///
+/// # 概要
+/// 差し替え後のリビジョン一覧と`rev`指定取得を検証し、ロールバック後に
+/// 元の内容へ戻ることを確認する。
+///
+/// # 戻り値
+/// なし
+///
+fn put_assets_data_creates_revision_and_rollback() {
+    /*
+     * テスト環境の準備
+     */
+    let (base_dir, db_path, assets_dir, config_path) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir, &config_path);
+    let server = ServerGuard::start(port, &db_path, &assets_dir, &config_path);
+
+    let hello_url = format!("http://127.0.0.1:{}/api/hello", port);
+    wait_for_server(&hello_url, server.stderr_path());
+
+    /*
+     * ページとアセットの作成
+     */
+    let api_url = format!("http://127.0.0.1:{}/api", port);
+    let page_id = create_page(&api_url, "/assets-revision", "body");
+    let page_path = get_page_path(&api_url, &page_id);
+
+    let asset_id = upload_asset_by_path(
+        &api_url,
+        &page_path,
+        "note.txt",
+        "text/plain",
+        b"first",
+    );
+
+    /*
+     * 差し替えの実行
+     */
+    let client = build_client();
+    let data_url = format!("{}/assets/{}/data", api_url, asset_id);
+    let response = client
+        .put(&data_url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header("Content-Type", "text/markdown")
+        .body(b"second".to_vec())
+        .send()
+        .expect("put asset data failed");
+
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.text().expect("read put body failed");
+    let value: Value = serde_json::from_str(&body).expect("parse put body failed");
+    assert_eq!(value["revision"].as_u64().expect("missing revision"), 2);
+
+    /*
+     * 現在と過去リビジョンの取得
+     */
+    let response = client
+        .get(&data_url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get asset data failed");
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.bytes().expect("read data failed").as_ref(),
+        b"second"
+    );
+
+    let response = client
+        .get(&data_url)
+        .query(&[("rev", "1")])
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get asset revision failed");
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response
+            .headers()
+            .get("Content-Type")
+            .expect("missing content-type")
+            .to_str()
+            .expect("content-type to_str failed"),
+        "text/plain"
+    );
+    assert_eq!(
+        response.bytes().expect("read data failed").as_ref(),
+        b"first"
+    );
+
+    let response = client
+        .get(&data_url)
+        .query(&[("rev", "9")])
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get unknown asset revision failed");
+    assert_eq!(response.status().as_u16(), 404);
+
+    let response = client
+        .get(&format!("{}/assets/{}/revisions", api_url, asset_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("list asset revisions failed");
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.text().expect("read revisions body failed");
+    let value: Value =
+        serde_json::from_str(&body).expect("parse revisions body failed");
+    let revisions: Vec<u64> = value["revisions"]
+        .as_array()
+        .expect("missing revisions")
+        .iter()
+        .map(|entry| entry["revision"].as_u64().expect("missing revision"))
+        .collect();
+    assert_eq!(revisions, vec![2, 1]);
+
+    /*
+     * ロールバックの実行
+     */
+    let revision_url = format!("{}/assets/{}/revision", api_url, asset_id);
+    let response = client
+        .post(&revision_url)
+        .query(&[("rollback_to", "5")])
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("rollback asset failed");
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = client
+        .post(&revision_url)
+        .query(&[("rollback_to", "1")])
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("rollback asset failed");
+    assert_eq!(response.status().as_u16(), 204);
+
+    let response = client
+        .get(&data_url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get asset data failed");
+    assert_eq!(
+        response.bytes().expect("read data failed").as_ref(),
+        b"first"
+    );
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// GET: /api/assets/{asset_id}/data?w={width} で画像の縮小版を取得できる
+/// ことを確認する。
+///
+/// # 概要
+/// メタ情報に寸法が含まれること、縮小版が候補幅へ切り上げて生成される
+/// こと、画像以外のアセットでは400となることを検証する。
+///
+/// # 戻り値
+/// なし
+///
+fn get_assets_data_returns_resized_variant() {
+    /*
+     * テスト環境の準備
+     */
+    let (base_dir, db_path, assets_dir, config_path) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir, &config_path);
+    let server = ServerGuard::start(port, &db_path, &assets_dir, &config_path);
+
+    let hello_url = format!("http://127.0.0.1:{}/api/hello", port);
+    wait_for_server(&hello_url, server.stderr_path());
+
+    /*
+     * ページとアセットの作成
+     */
+    let api_url = format!("http://127.0.0.1:{}/api", port);
+    let page_id = create_page(&api_url, "/assets-variant", "body");
+    let page_path = get_page_path(&api_url, &page_id);
+
+    let image = image::DynamicImage::ImageRgb8(image::RgbImage::new(800, 400));
+    let mut png = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .expect("encode png failed");
+
+    let asset_id =
+        upload_asset_by_path(&api_url, &page_path, "photo.png", "image/png", &png);
+    let text_id = upload_asset_by_path(
+        &api_url,
+        &page_path,
+        "note.txt",
+        "text/plain",
+        b"text",
+    );
+
+    /*
+     * メタ情報の寸法の確認
+     */
+    let client = build_client();
+    let response = client
+        .get(&format!("{}/assets/{}/meta", api_url, asset_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get asset meta failed");
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.text().expect("read meta body failed");
+    let value: Value = serde_json::from_str(&body).expect("parse meta body failed");
+    assert_eq!(value["width"].as_u64(), Some(800));
+    assert_eq!(value["height"].as_u64(), Some(400));
+
+    /*
+     * 縮小版の取得
+     */
+    let data_url = format!("{}/assets/{}/data", api_url, asset_id);
+    let response = client
+        .get(&data_url)
+        .query(&[("w", "300")])
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get asset variant failed");
+    assert_eq!(response.status().as_u16(), 200);
+    let bytes = response.bytes().expect("read variant failed");
+    let variant = image::load_from_memory(&bytes).expect("decode variant failed");
+    assert_eq!((variant.width(), variant.height()), (320, 160));
+
+    /*
+     * 元画像より大きい幅では元データが返される
+     */
+    let response = client
+        .get(&data_url)
+        .query(&[("w", "1000")])
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get asset variant failed");
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.bytes().expect("read data failed").as_ref(),
+        png.as_slice()
+    );
+
+    /*
+     * 不正な指定の確認
+     */
+    let response = client
+        .get(&data_url)
+        .query(&[("w", "0")])
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get asset variant failed");
+    assert_eq!(response.status().as_u16(), 400);
+
+    let response = client
+        .get(&format!("{}/assets/{}/data", api_url, text_id))
+        .query(&[("w", "320")])
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get text variant failed");
+    assert_eq!(response.status().as_u16(), 400);
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// GET: /api/assets/{asset_id}/data で Range/If-Range が処理されることを
+/// 確認する。
+///
+/// # 概要
+/// 単一範囲の部分取得、範囲外指定、ETag 不一致時の全体取得を検証する。
+///
+/// # 戻り値
+/// なし
+///
+fn get_assets_data_supports_range_requests() {
+    /*
+     * テスト環境の準備
+     */
+    let (base_dir, db_path, assets_dir, config_path) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir, &config_path);
+    let server = ServerGuard::start(port, &db_path, &assets_dir, &config_path);
+
+    let hello_url = format!("http://127.0.0.1:{}/api/hello", port);
+    wait_for_server(&hello_url, server.stderr_path());
+
+    let api_url = format!("http://127.0.0.1:{}/api", port);
+    let page_id = create_page(&api_url, "/assets-range", "body");
+    let page_path = get_page_path(&api_url, &page_id);
+    let asset_id = upload_asset_by_path(
+        &api_url,
+        &page_path,
+        "log.txt",
+        "text/plain",
+        b"0123456789",
+    );
+
+    /*
+     * 全体取得時の ETag と Accept-Ranges
+     */
+    let client = build_client();
+    let data_url = format!("{}/assets/{}/data", api_url, asset_id);
+    let response = client
+        .get(&data_url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get asset data failed");
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response
+            .headers()
+            .get("Accept-Ranges")
+            .expect("missing accept-ranges")
+            .to_str()
+            .expect("accept-ranges to_str failed"),
+        "bytes"
+    );
+    let etag = response
+        .headers()
+        .get("ETag")
+        .expect("missing etag")
+        .to_str()
+        .expect("etag to_str failed")
+        .to_string();
+
+    /*
+     * 部分取得
+     */
+    let response = client
+        .get(&data_url)
+        .header("Range", "bytes=2-5")
+        .header("If-Range", etag.as_str())
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get asset range failed");
+    assert_eq!(response.status().as_u16(), 206);
+    assert_eq!(
+        response
+            .headers()
+            .get("Content-Range")
+            .expect("missing content-range")
+            .to_str()
+            .expect("content-range to_str failed"),
+        "bytes 2-5/10"
+    );
+    assert_eq!(response.bytes().expect("read range failed").as_ref(), b"2345");
+
+    let response = client
+        .get(&data_url)
+        .header("Range", "bytes=-3")
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get asset suffix range failed");
+    assert_eq!(response.status().as_u16(), 206);
+    assert_eq!(response.bytes().expect("read range failed").as_ref(), b"789");
+
+    /*
+     * 範囲外指定と If-Range 不一致
+     */
+    let response = client
+        .get(&data_url)
+        .header("Range", "bytes=10-")
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get asset range failed");
+    assert_eq!(response.status().as_u16(), 416);
+    assert_eq!(
+        response
+            .headers()
+            .get("Content-Range")
+            .expect("missing content-range")
+            .to_str()
+            .expect("content-range to_str failed"),
+        "bytes */10"
+    );
+
+    let response = client
+        .get(&data_url)
+        .header("Range", "bytes=2-5")
+        .header("If-Range", "\"stale\"")
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get asset range failed");
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.bytes().expect("read data failed").as_ref(),
+        b"0123456789"
+    );
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// POST: /api/assets/uploads による分割アップロードでアセットを登録できる
+/// ことを確認する。
+///
+/// # 概要
+/// セッション作成、分割送信、受信位置の不一致、中断後の再開、完了を
+/// 検証する。
+///
+/// # 戻り値
+/// なし
+///
+fn post_assets_uploads_resumes_and_completes() {
+    /*
+     * テスト環境の準備
+     */
+    let (base_dir, db_path, assets_dir, config_path) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir, &config_path);
+    let server = ServerGuard::start(port, &db_path, &assets_dir, &config_path);
+
+    let hello_url = format!("http://127.0.0.1:{}/api/hello", port);
+    wait_for_server(&hello_url, server.stderr_path());
+
+    let api_url = format!("http://127.0.0.1:{}/api", port);
+    let page_id = create_page(&api_url, "/assets-upload", "body");
+
+    /*
+     * セッション作成
+     */
+    let client = build_client();
+    let response = client
+        .post(&format!("{}/assets/uploads", api_url))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header("Content-Type", "application/json")
+        .body(
+            serde_json::json!({
+                "page_id": page_id,
+                "file_name": "record.bin",
+                "mime_type": "application/octet-stream",
+                "size": 12,
+            })
+            .to_string(),
+        )
+        .send()
+        .expect("create upload failed");
+    assert_eq!(response.status().as_u16(), 201);
+    let body = response.text().expect("read upload body failed");
+    let value: Value = serde_json::from_str(&body).expect("parse upload body failed");
+    let upload_id = value["upload_id"]
+        .as_str()
+        .expect("missing upload_id")
+        .to_string();
+    let upload_url = format!("{}/assets/uploads/{}", api_url, upload_id);
+
+    /*
+     * 分割送信と受信位置の不一致
+     */
+    let response = client
+        .patch(&upload_url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header("Upload-Offset", "0")
+        .body(b"abcdef".to_vec())
+        .send()
+        .expect("patch upload failed");
+    assert_eq!(response.status().as_u16(), 204);
+
+    let response = client
+        .patch(&upload_url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header("Upload-Offset", "0")
+        .body(b"abcdef".to_vec())
+        .send()
+        .expect("patch upload failed");
+    assert_eq!(response.status().as_u16(), 409);
+    assert_eq!(
+        response
+            .headers()
+            .get("Upload-Offset")
+            .expect("missing upload-offset")
+            .to_str()
+            .expect("upload-offset to_str failed"),
+        "6"
+    );
+
+    let response = client
+        .post(&format!("{}/complete", upload_url))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("complete upload failed");
+    assert_eq!(response.status().as_u16(), 409);
+
+    /*
+     * 受信位置の問い合わせと再開
+     */
+    let response = client
+        .get(&upload_url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get upload failed");
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.text().expect("read upload body failed");
+    let value: Value = serde_json::from_str(&body).expect("parse upload body failed");
+    assert_eq!(value["offset"].as_u64(), Some(6));
+
+    let response = client
+        .patch(&upload_url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header("Upload-Offset", "6")
+        .body(b"ghijkl".to_vec())
+        .send()
+        .expect("patch upload failed");
+    assert_eq!(response.status().as_u16(), 204);
+
+    /*
+     * 完了とアセットの確認
+     */
+    let response = client
+        .post(&format!("{}/complete", upload_url))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("complete upload failed");
+    assert_eq!(response.status().as_u16(), 201);
+    let body = response.text().expect("read complete body failed");
+    let value: Value =
+        serde_json::from_str(&body).expect("parse complete body failed");
+    let asset_id = value["id"].as_str().expect("missing id").to_string();
+
+    let response = client
+        .get(&format!("{}/assets/{}/data", api_url, asset_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get asset data failed");
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.bytes().expect("read data failed").as_ref(),
+        b"abcdefghijkl"
+    );
+
+    let response = client
+        .get(&upload_url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get upload failed");
+    assert_eq!(response.status().as_u16(), 404);
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// 共有アセットの登録・一覧・参照元取得ができることを確認する。
+///
+/// # 概要
+/// ページを指定せずにアセットを登録し、ファイル名での取得、一覧の
+/// 絞り込みおよび参照元ページの取得を検証する。
+///
+/// # 戻り値
+/// なし
+///
+fn shared_assets_support_list_and_refs() {
+    /*
+     * テスト環境の準備
+     */
+    let (base_dir, db_path, assets_dir, config_path) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir, &config_path);
+    let server = ServerGuard::start(port, &db_path, &assets_dir, &config_path);
+
+    let hello_url = format!("http://127.0.0.1:{}/api/hello", port);
+    wait_for_server(&hello_url, server.stderr_path());
+
+    /*
+     * 共有アセットとページアセットの作成
+     */
+    let api_url = format!("http://127.0.0.1:{}/api", port);
+    let client = build_client();
+    let response = client
+        .post(&format!("{}/assets", api_url))
+        .query(&[("file", "logo.png")])
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header("Content-Type", "image/png")
+        .body(b"shared-logo".to_vec())
+        .send()
+        .expect("create shared asset failed");
+    assert_eq!(response.status().as_u16(), 201);
+    let body = response.text().expect("read create body failed");
+    let value: Value = serde_json::from_str(&body).expect("parse create body failed");
+    let shared_id = value["id"].as_str().expect("missing asset id").to_string();
+
+    let page_id = create_page(&api_url, "/assets-shared", "body");
+    let page_path = get_page_path(&api_url, &page_id);
+    upload_asset_by_path(&api_url, &page_path, "notes.txt", "text/plain", b"notes");
+
+    /*
+     * ファイル名のみでの取得
+     */
+    let response = build_no_redirect_client()
+        .get(&format!("{}/assets?file=logo.png", api_url))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get shared asset redirect failed");
+    assert_eq!(response.status().as_u16(), 302);
+    assert_eq!(
+        response
+            .headers()
+            .get("Location")
+            .expect("missing location")
+            .to_str()
+            .expect("location to_str failed"),
+        format!("/api/assets/{}/data", shared_id)
+    );
+
+    /*
+     * 一覧の絞り込み
+     */
+    let response = client
+        .get(&format!("{}/assets", api_url))
+        .query(&[("scope", "shared"), ("mime", "image/*")])
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("list assets failed");
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.text().expect("read list body failed");
+    let value: Value = serde_json::from_str(&body).expect("parse list body failed");
+    let items = value.as_array().expect("list must be array");
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["id"].as_str(), Some(shared_id.as_str()));
+    assert_eq!(items[0]["shared"].as_bool(), Some(true));
+    assert_eq!(items[0]["references"].as_u64(), Some(0));
+
+    let response = client
+        .get(&format!("{}/assets", api_url))
+        .query(&[("scope", "invalid")])
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("list assets failed");
+    assert_eq!(response.status().as_u16(), 400);
+
+    /*
+     * 参照元ページの取得
+     */
+    let referrer_id = create_page(&api_url, "/assets-referrer", "![logo](asset::logo.png)");
+    let response = client
+        .get(&format!("{}/assets/{}/refs", api_url, shared_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get asset refs failed");
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.text().expect("read refs body failed");
+    let value: Value = serde_json::from_str(&body).expect("parse refs body failed");
+    let items = value.as_array().expect("refs must be array");
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["page_id"].as_str(), Some(referrer_id.as_str()));
+    assert_eq!(items[0]["path"].as_str(), Some("/assets-referrer"));
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
 ///
 /// テスト用一時ディレクトリの準備
 ///
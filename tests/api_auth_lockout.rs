@@ -0,0 +1,334 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use reqwest::header::{CONTENT_TYPE, RETRY_AFTER};
+use serde_json::{Value, json};
+
+use common::*;
+
+/// 管理者属性を持つテスト用ユーザ名
+const ADMIN_USERNAME: &str = "admin_user";
+
+/// 誤ったパスワード
+const WRONG_PASSWORD: &str = "wrong-password";
+
+/// 既定のユーザ単位ロックアウト閾値
+const USER_LOCKOUT_THRESHOLD: usize = 5;
+
+/// 既定の接続元アドレス単位ロックアウト閾値
+const IP_LOCKOUT_THRESHOLD: usize = 20;
+
+///
+/// CLI を実行する
+///
+/// # 引数
+/// * `db_path` - DBパス
+/// * `assets_dir` - アセットディレクトリ
+/// * `args` - サブコマンド以降の引数
+///
+/// # 戻り値
+/// 実行結果を返す。
+///
+fn run_cli(db_path: &Path, assets_dir: &Path, args: &[&str]) -> Output {
+    let base_dir = db_path.parent().expect("db_path parent missing");
+
+    Command::new(test_binary_path())
+        .env("XDG_CONFIG_HOME", base_dir)
+        .env("XDG_DATA_HOME", base_dir)
+        .arg("--db-path")
+        .arg(db_path)
+        .arg("--assets-path")
+        .arg(assets_dir)
+        .arg("--fts-index")
+        .arg(fts_index_path(db_path))
+        .args(args)
+        .output()
+        .expect("run cli failed")
+}
+
+///
+/// `user info` の LOCKOUT 欄を取得する
+///
+/// # 引数
+/// * `db_path` - DBパス
+/// * `assets_dir` - アセットディレクトリ
+///
+/// # 戻り値
+/// LOCKOUT 欄の表示値を返す。
+///
+fn lockout_status(db_path: &Path, assets_dir: &Path) -> String {
+    let output = run_cli(db_path, assets_dir, &["user", "info", TEST_USERNAME]);
+    assert!(output.status.success());
+
+    String::from_utf8(output.stdout)
+        .expect("stdout decode failed")
+        .lines()
+        .find_map(|line| line.strip_prefix("LOCKOUT:"))
+        .map(|value| value.trim().to_string())
+        .expect("LOCKOUT missing")
+}
+
+#[test]
+///
+/// Basic認証: 連続失敗でユーザがロックアウトされ、`user unlock` で解除
+/// できることを確認
+///
+/// # 注記
+/// ロックアウト中は正しいパスワードでも 401 となり、`user info` に状態が
+/// 表示されることを検証する。
+///
+fn basic_auth_locks_user_after_repeated_failures() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let url = format!("{}/users/me", api_base_url);
+
+    /*
+     * 閾値回数の失敗でロックアウトされる
+     */
+    for _ in 0..USER_LOCKOUT_THRESHOLD {
+        let response = client
+            .get(&url)
+            .basic_auth(TEST_USERNAME, Some(WRONG_PASSWORD))
+            .send()
+            .expect("get users/me with wrong password failed");
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    let response = client
+        .get(&url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get users/me while locked failed");
+    assert_eq!(response.status().as_u16(), 401);
+
+    drop(server);
+
+    /*
+     * 状態の表示と解除
+     */
+    assert_eq!(
+        lockout_status(&db_path, &assets_dir),
+        format!("locked (failed logins: {})", USER_LOCKOUT_THRESHOLD),
+    );
+
+    let output = run_cli(&db_path, &assets_dir, &["user", "unlock", TEST_USERNAME]);
+    assert!(output.status.success());
+    assert_eq!(lockout_status(&db_path, &assets_dir), "none");
+
+    /*
+     * 解除後は認証できる
+     */
+    let port = reserve_port();
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+
+    let response = client
+        .get(format!("{}/users/me", api_base_url))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get users/me after unlock failed");
+    assert_eq!(response.status().as_u16(), 200);
+
+    drop(server);
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// Basic認証: 接続元アドレス単位の連続失敗で 429 が返ることを確認
+///
+/// # 注記
+/// 存在しないユーザ名による失敗も数えられ、ロックアウト中は正しい資格情報
+/// でも `Retry-After` 付きの 429 になることを検証する。
+///
+fn basic_auth_locks_address_after_repeated_failures() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let url = format!("{}/users/me", api_base_url);
+
+    for index in 0..IP_LOCKOUT_THRESHOLD {
+        let response = client
+            .get(&url)
+            .basic_auth(format!("missing_user{}", index), Some(WRONG_PASSWORD))
+            .send()
+            .expect("get users/me with missing user failed");
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    let response = client
+        .get(&url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get users/me while address locked failed");
+    assert_eq!(response.status().as_u16(), 429);
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .expect("Retry-After missing")
+        .to_str()
+        .expect("Retry-After to_str failed")
+        .parse::<u64>()
+        .expect("Retry-After parse failed");
+    assert!(retry_after > 0);
+
+    drop(server);
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// Basic認証: 認証成功で接続元アドレス単位の失敗記録が消去されないことを
+/// 確認
+///
+/// # 注記
+/// 失敗の合間に有効なアカウントで認証しても、同じ接続元からの失敗が
+/// 閾値に達した時点でロックアウトされることを検証する。
+///
+fn basic_auth_success_does_not_reset_address_failures() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let url = format!("{}/users/me", api_base_url);
+
+    for index in 0..IP_LOCKOUT_THRESHOLD {
+        /*
+         * 閾値直前で有効なアカウントによる認証を挟む
+         */
+        if index == IP_LOCKOUT_THRESHOLD - 1 {
+            let response = client
+                .get(&url)
+                .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+                .send()
+                .expect("get users/me with valid user failed");
+            assert_eq!(response.status().as_u16(), 200);
+        }
+
+        let response = client
+            .get(&url)
+            .basic_auth(format!("missing_user{}", index), Some(WRONG_PASSWORD))
+            .send()
+            .expect("get users/me with missing user failed");
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    let response = client
+        .get(&url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get users/me while address locked failed");
+    assert_eq!(response.status().as_u16(), 429);
+
+    drop(server);
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// Admin属性: 設定ファイルで有効化したパスワード強度要件が管理APIで適用
+/// され、ロックアウトを解除できることを確認
+///
+fn admin_api_enforces_password_policy_and_unlocks_user() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    run_add_user_with_credentials_and_attributes(
+        &db_path,
+        &assets_dir,
+        ADMIN_USERNAME,
+        TEST_PASSWORD,
+        &["admin"],
+    );
+    let server = ServerGuard::start_with_config(
+        port,
+        &db_path,
+        &assets_dir,
+        &[],
+        "[auth]\npassword_min_length = 8\n",
+    );
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let url = format!("{}/admin/users", api_base_url);
+
+    /*
+     * 強度要件を満たさないパスワードは拒否される
+     */
+    let response = client
+        .post(&url)
+        .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+        .header(CONTENT_TYPE, "application/json")
+        .body(json!({ "username": "weak_user", "password": "short" }).to_string())
+        .send()
+        .expect("post weak password user failed");
+    assert_eq!(response.status().as_u16(), 400);
+
+    /*
+     * ロックアウト状態の参照と解除
+     */
+    for _ in 0..USER_LOCKOUT_THRESHOLD {
+        let response = client
+            .get(format!("{}/users/me", api_base_url))
+            .basic_auth(TEST_USERNAME, Some(WRONG_PASSWORD))
+            .send()
+            .expect("get users/me with wrong password failed");
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    let response = client
+        .get(format!("{}/{}", url, TEST_USERNAME))
+        .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get admin user failed");
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.text().expect("read body failed");
+    let user: Value = serde_json::from_str(&body).expect("parse user failed");
+    assert_eq!(user["failed_logins"], USER_LOCKOUT_THRESHOLD);
+    assert!(user["locked_until"].is_string());
+
+    let response = client
+        .patch(format!("{}/{}", url, TEST_USERNAME))
+        .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+        .header(CONTENT_TYPE, "application/json")
+        .body(json!({ "unlock": true }).to_string())
+        .send()
+        .expect("patch unlock failed");
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.text().expect("read body failed");
+    let user: Value = serde_json::from_str(&body).expect("parse user failed");
+    assert_eq!(user["failed_logins"], 0);
+    assert_eq!(user["locked_until"], Value::Null);
+
+    let response = client
+        .get(format!("{}/users/me", api_base_url))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get users/me after unlock failed");
+    assert_eq!(response.status().as_u16(), 200);
+
+    drop(server);
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
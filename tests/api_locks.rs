@@ -0,0 +1,412 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+mod common;
+
+use std::fs;
+
+use reqwest::blocking::{Client, Response};
+use serde_json::{Value, json};
+
+use common::*;
+
+/// 管理者属性を持つテスト用ユーザ名
+const ADMIN_USERNAME: &str = "admin_user";
+
+#[test]
+///
+/// ロック一覧の取得と管理者によるロックの強制解除ができることを確認
+///
+/// # 注記
+/// 1) 一般ユーザの一覧にはロック解除トークンが含まれないことを確認する
+/// 2) 管理者の一覧にはロック解除トークンが含まれることを確認する
+/// 3) 一般ユーザによる強制解除が拒否されることを確認する
+/// 4) 強制解除後はロックが存在せず、元の保持者によるロック延長が強制解除
+///    として拒否されることを確認する
+/// 5) 強制解除後も元の保持者が保存できることを確認する
+///
+fn admin_can_list_and_break_page_locks() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    run_add_user_with_credentials_and_attributes(
+        &db_path,
+        &assets_dir,
+        ADMIN_USERNAME,
+        TEST_PASSWORD,
+        &["admin"],
+    );
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let pages_url = format!("{}/pages", api_base_url);
+    let locks_url = format!("{}/locks", api_base_url);
+    let page_id = create_page(&client, &pages_url, "/locked", "locked\n");
+    let lock_url = format!("{}/{}/lock", pages_url, page_id);
+
+    let response = client
+        .post(&lock_url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("lock post failed");
+    assert_eq!(response.status().as_u16(), 204);
+    let holder_token = lock_token_from_header(&response);
+
+    /*
+     * 一般ユーザによる一覧取得
+     */
+    let items = get_locks(&client, &locks_url, TEST_USERNAME);
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["page_id"], page_id.as_str());
+    assert_eq!(items[0]["path"], "/locked");
+    assert_eq!(items[0]["username"], TEST_USERNAME);
+    assert!(items[0]["acquired"].as_str().is_some());
+    assert!(items[0]["expire"].as_str().is_some());
+    assert!(items[0].get("token").is_none());
+
+    /*
+     * 管理者による一覧取得
+     */
+    let items = get_locks(&client, &locks_url, ADMIN_USERNAME);
+    let token = items[0]["token"]
+        .as_str()
+        .expect("missing lock token")
+        .to_string();
+    let break_url = format!("{}/{}", locks_url, token);
+
+    /*
+     * 一般ユーザによる強制解除の拒否
+     */
+    let response = client
+        .delete(&break_url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("lock break failed");
+    assert_eq!(response.status().as_u16(), 403);
+
+    /*
+     * 管理者による強制解除
+     */
+    let response = client
+        .delete(&break_url)
+        .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("lock break failed");
+    assert_eq!(response.status().as_u16(), 200);
+    let value: Value =
+        serde_json::from_str(&response.text().expect("read body failed"))
+            .expect("parse lock break response failed");
+    assert_eq!(value["token"], token.as_str());
+    assert_eq!(value["path"], "/locked");
+    assert_eq!(value["username"], TEST_USERNAME);
+    assert!(value["saved_revision"].is_null());
+
+    let response = client
+        .get(&lock_url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("lock get failed");
+    assert_eq!(response.status().as_u16(), 404);
+    assert!(get_locks(&client, &locks_url, ADMIN_USERNAME).is_empty());
+
+    let response = client
+        .delete(&break_url)
+        .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("lock break failed");
+    assert_eq!(response.status().as_u16(), 404);
+
+    let response = client
+        .delete(format!("{}/not-a-token", locks_url))
+        .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("lock break failed");
+    assert_eq!(response.status().as_u16(), 404);
+
+    /*
+     * 元の保持者への強制解除の通知
+     */
+    let response = client
+        .put(&lock_url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header("X-Lock-Authentication", format!("token={}", holder_token))
+        .send()
+        .expect("lock put failed");
+    assert_eq!(response.status().as_u16(), 409);
+
+    /*
+     * 元の保持者による保存
+     */
+    let response = client
+        .put(format!("{}/{}/source", pages_url, page_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header("Content-Type", "text/markdown")
+        .body("edited\n")
+        .send()
+        .expect("source put failed");
+    assert_eq!(response.status().as_u16(), 204);
+
+    drop(server);
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// ドラフトページのロックを強制解除してもドラフトが残ることを確認
+///
+/// # 注記
+/// 1) ページ作成で取得したドラフトのロックを管理者が強制解除する
+/// 2) 元の保持者によるロック延長が強制解除として拒否されることを確認する
+/// 3) 元の保持者がドラフトへ保存でき、ページとして作成されることを確認する
+///
+fn admin_break_keeps_draft_page() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    run_add_user_with_credentials_and_attributes(
+        &db_path,
+        &assets_dir,
+        ADMIN_USERNAME,
+        TEST_PASSWORD,
+        &["admin"],
+    );
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let pages_url = format!("{}/pages", api_base_url);
+    let locks_url = format!("{}/locks", api_base_url);
+
+    /*
+     * ドラフトページの作成
+     */
+    let response = client
+        .post(&pages_url)
+        .query(&[("path", "/draft-broken")])
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("create page failed");
+    assert_eq!(response.status().as_u16(), 201);
+    let holder_token = lock_token_from_header(&response);
+    let value: Value =
+        serde_json::from_str(&response.text().expect("read body failed"))
+            .expect("parse create page response failed");
+    let page_id = value["id"].as_str().expect("missing page id").to_string();
+
+    /*
+     * 管理者による強制解除
+     */
+    let items = get_locks(&client, &locks_url, ADMIN_USERNAME);
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["token"], holder_token.as_str());
+    let response = client
+        .delete(format!("{}/{}", locks_url, holder_token))
+        .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("lock break failed");
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(get_locks(&client, &locks_url, ADMIN_USERNAME).is_empty());
+
+    /*
+     * 元の保持者への強制解除の通知
+     */
+    let response = client
+        .put(format!("{}/{}/lock", pages_url, page_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header("X-Lock-Authentication", format!("token={}", holder_token))
+        .send()
+        .expect("lock put failed");
+    assert_eq!(response.status().as_u16(), 409);
+
+    /*
+     * 元の保持者による保存
+     */
+    let response = client
+        .put(format!("{}/{}/source", pages_url, page_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header("Content-Type", "text/markdown")
+        .header("X-Lock-Authentication", format!("token={}", holder_token))
+        .body("recovered\n")
+        .send()
+        .expect("source put failed");
+    assert_eq!(response.status().as_u16(), 204);
+
+    let response = client
+        .get(format!("{}/{}/source", pages_url, page_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("source get failed");
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.text().expect("read body failed"), "recovered\n");
+
+    drop(server);
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// path prefix 制約付きトークンによるロック一覧の取得を確認
+///
+/// # 注記
+/// 1) 制約の範囲内と範囲外のページをそれぞれロックする
+/// 2) `/docs` に制限したトークンの一覧には範囲内のロックのみが含まれること
+///    を確認する
+///
+fn path_restricted_token_lists_only_allowed_locks() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let pages_url = format!("{}/pages", api_base_url);
+    let locks_url = format!("{}/locks", api_base_url);
+
+    /*
+     * 範囲内と範囲外のページのロック
+     */
+    for path in ["/docs/locked", "/private/locked"] {
+        let page_id = create_page(&client, &pages_url, path, "locked\n");
+        let response = client
+            .post(format!("{}/{}/lock", pages_url, page_id))
+            .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+            .send()
+            .expect("lock post failed");
+        assert_eq!(response.status().as_u16(), 204);
+    }
+    assert_eq!(get_locks(&client, &locks_url, TEST_USERNAME).len(), 2);
+
+    /*
+     * path prefix 制約付きトークンの発行
+     */
+    let response = client
+        .post(format!("{}/users/me/tokens", api_base_url))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header("Content-Type", "application/json")
+        .body(
+            json!({
+                "scopes": ["read"],
+                "path_prefixes": ["/docs"],
+            })
+            .to_string(),
+        )
+        .send()
+        .expect("post tokens failed");
+    assert_eq!(response.status().as_u16(), 201);
+    let value: Value =
+        serde_json::from_str(&response.text().expect("read body failed"))
+            .expect("parse token response failed");
+    let token = value["token"].as_str().expect("missing token").to_string();
+
+    /*
+     * 制約付きトークンによる一覧取得
+     */
+    let response = client
+        .get(&locks_url)
+        .bearer_auth(&token)
+        .send()
+        .expect("locks get failed");
+    assert_eq!(response.status().as_u16(), 200);
+    let value: Value =
+        serde_json::from_str(&response.text().expect("read body failed"))
+            .expect("parse locks response failed");
+    let items = value["items"].as_array().expect("missing items");
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["path"], "/docs/locked");
+
+    drop(server);
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// ロック応答ヘッダからのロック解除トークンの取得
+///
+/// # 引数
+/// * `response` - ロックを取得したリクエストの応答
+///
+/// # 戻り値
+/// ロック解除トークン
+///
+fn lock_token_from_header(response: &Response) -> String {
+    response
+        .headers()
+        .get("X-Page-Lock")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            value
+                .split_whitespace()
+                .find_map(|part| part.strip_prefix("token="))
+                .map(str::to_string)
+        })
+        .expect("missing lock token")
+}
+
+///
+/// ロック一覧を取得する。
+///
+/// # 引数
+/// * `client` - HTTPクライアント
+/// * `locks_url` - ロック一覧APIのURL
+/// * `username` - 認証に用いるユーザ名
+///
+/// # 戻り値
+/// ロック一覧の要素
+///
+fn get_locks(client: &Client, locks_url: &str, username: &str) -> Vec<Value> {
+    let response = client
+        .get(locks_url)
+        .basic_auth(username, Some(TEST_PASSWORD))
+        .send()
+        .expect("locks get failed");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let value: Value =
+        serde_json::from_str(&response.text().expect("read body failed"))
+            .expect("parse locks response failed");
+    value["items"].as_array().expect("missing items").clone()
+}
+
+///
+/// テスト用ページを作成する。
+///
+/// # 引数
+/// * `client` - HTTPクライアント
+/// * `pages_url` - ページAPIのURL
+/// * `path` - ページパス
+/// * `body` - ページ本文
+///
+/// # 戻り値
+/// 作成したページID
+///
+fn create_page(client: &Client, pages_url: &str, path: &str, body: &str) -> String {
+    let response = client
+        .post(pages_url)
+        .query(&[("path", path)])
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("create page failed");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let lock_token = lock_token_from_header(&response);
+    let value: Value =
+        serde_json::from_str(&response.text().expect("read body failed"))
+            .expect("parse create page response failed");
+    let page_id = value["id"].as_str().expect("missing page id").to_string();
+
+    let response = client
+        .put(format!("{}/{}/source", pages_url, page_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header("Content-Type", "text/markdown")
+        .header("X-Lock-Authentication", format!("token={}", lock_token))
+        .body(body.to_string())
+        .send()
+        .expect("update page failed");
+    assert_eq!(response.status().as_u16(), 204);
+
+    page_id
+}
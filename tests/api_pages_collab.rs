@@ -0,0 +1,530 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+mod common;
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+use data_encoding::BASE64;
+use reqwest::blocking::Client;
+use serde_json::{Value, json};
+
+use common::*;
+
+/// 管理者属性を持つテスト用ユーザ名
+const ADMIN_USERNAME: &str = "admin_user";
+
+#[test]
+///
+/// 共同編集セッションで並行編集が統合され、最後の参加者の離脱時に
+/// リビジョンとして保存されることを確認する。
+///
+/// # 注記
+/// 1) 2つのクライアントが同じページのセッションへ参加する
+/// 2) セッション中は排他ロックと通常の保存が拒否されることを確認する
+/// 3) 同じリビジョンを基準にした編集が変換されて統合されることを確認する
+/// 4) 全員の離脱後にページソースが更新されロックが解除されることを確認する
+///
+fn collab_session_merges_edits_and_checkpoints() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    let server = ServerGuard::start_with_run_args(
+        port,
+        &db_path,
+        &assets_dir,
+        &["--collab"],
+    );
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let pages_url = format!("{}/pages", api_base_url);
+    let page_id = create_page(&client, &pages_url, "/meeting", "agenda\n");
+
+    /*
+     * セッションへの参加
+     */
+    let mut alice = WsClient::connect(port, &page_id);
+    let init = alice.recv_json();
+    assert_eq!(init["type"], "init");
+    assert_eq!(init["revision"], 0);
+    assert_eq!(init["document"], "agenda\n");
+
+    let mut bob = WsClient::connect(port, &page_id);
+    let init = bob.recv_json();
+    assert_eq!(init["participants"].as_array().unwrap().len(), 2);
+    let presence = alice.recv_json();
+    assert_eq!(presence["type"], "presence");
+    assert_eq!(presence["participants"][1]["user"], TEST_USERNAME);
+
+    /*
+     * 排他ロックとの併用の拒否
+     */
+    let response = client
+        .post(format!("{}/{}/lock", pages_url, page_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("lock post failed");
+    assert_eq!(response.status().as_u16(), 409);
+
+    let response = client
+        .put(format!("{}/{}/source", pages_url, page_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header("Content-Type", "text/markdown")
+        .body("overwrite")
+        .send()
+        .expect("source put failed");
+    assert_eq!(response.status().as_u16(), 423);
+
+    /*
+     * 並行編集の統合
+     */
+    alice.send_json(&json!({
+        "type": "op",
+        "revision": 0,
+        "operation": ["# ", 7],
+    }));
+    assert_eq!(alice.recv_json(), json!({"type": "ack", "revision": 1}));
+
+    bob.send_json(&json!({
+        "type": "op",
+        "revision": 0,
+        "operation": [7, "- item\n"],
+    }));
+    let op = bob.recv_json();
+    assert_eq!(op["type"], "op");
+    assert_eq!(op["operation"], json!(["# ", 7]));
+    assert_eq!(bob.recv_json(), json!({"type": "ack", "revision": 2}));
+
+    let op = alice.recv_json();
+    assert_eq!(op["revision"], 2);
+    assert_eq!(op["operation"], json!([9, "- item\n"]));
+
+    /*
+     * 離脱時の保存
+     */
+    bob.close();
+    let presence = alice.recv_json();
+    assert_eq!(presence["participants"].as_array().unwrap().len(), 1);
+    alice.close();
+
+    let source_url = format!("{}/{}/source", pages_url, page_id);
+    let mut source = String::new();
+    for _ in 0..50 {
+        source = client
+            .get(&source_url)
+            .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+            .send()
+            .expect("source get failed")
+            .text()
+            .expect("source body failed");
+        if source != "agenda\n" {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    assert_eq!(source, "# agenda\n- item\n");
+
+    let response = client
+        .get(format!("{}/{}/lock", pages_url, page_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("lock get failed");
+    assert_eq!(response.status().as_u16(), 404);
+
+    /*
+     * 排他ロック中のページへの参加の拒否
+     */
+    let response = client
+        .post(format!("{}/{}/lock", pages_url, page_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("lock post failed");
+    assert_eq!(response.status().as_u16(), 204);
+    assert!(WsClient::handshake(port, &page_id).starts_with("HTTP/1.1 409"));
+
+    drop(server);
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// 管理者がロックを強制解除すると共同編集セッションの未保存の編集が
+/// リビジョンとして保存され、参加者へ通知されることを確認する。
+///
+fn admin_lock_break_checkpoints_collab_session() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    run_add_user_with_credentials_and_attributes(
+        &db_path,
+        &assets_dir,
+        ADMIN_USERNAME,
+        TEST_PASSWORD,
+        &["admin"],
+    );
+    let server = ServerGuard::start_with_run_args(
+        port,
+        &db_path,
+        &assets_dir,
+        &["--collab"],
+    );
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let pages_url = format!("{}/pages", api_base_url);
+    let locks_url = format!("{}/locks", api_base_url);
+    let page_id = create_page(&client, &pages_url, "/notes", "draft\n");
+
+    /*
+     * 未保存の編集を持つセッションの用意
+     */
+    let mut alice = WsClient::connect(port, &page_id);
+    assert_eq!(alice.recv_json()["type"], "init");
+    alice.send_json(&json!({
+        "type": "op",
+        "revision": 0,
+        "operation": [6, "more\n"],
+    }));
+    assert_eq!(alice.recv_json(), json!({"type": "ack", "revision": 1}));
+
+    /*
+     * 強制解除
+     */
+    let value: Value = serde_json::from_str(
+        &client
+            .get(&locks_url)
+            .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+            .send()
+            .expect("locks get failed")
+            .text()
+            .expect("locks body failed"),
+    )
+    .expect("parse locks response failed");
+    let token = value["items"][0]["token"]
+        .as_str()
+        .expect("missing lock token")
+        .to_string();
+
+    let response = client
+        .delete(format!("{}/{}", locks_url, token))
+        .basic_auth(ADMIN_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("lock break failed");
+    assert_eq!(response.status().as_u16(), 200);
+    let value: Value =
+        serde_json::from_str(&response.text().expect("read body failed"))
+            .expect("parse lock break response failed");
+    assert_eq!(value["saved_revision"], 2);
+
+    let message = alice.recv_json();
+    assert_eq!(message["type"], "checkpoint");
+    assert_eq!(message["page_revision"], value["saved_revision"]);
+    let message = alice.recv_json();
+    assert_eq!(message["type"], "error");
+    assert_eq!(message["reason"], "page lock broken by administrator");
+
+    let source = client
+        .get(format!("{}/{}/source", pages_url, page_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("source get failed")
+        .text()
+        .expect("source body failed");
+    assert_eq!(source, "draft\nmore\n");
+
+    drop(server);
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// 制御フレームと分割されたメッセージを処理できることを確認する。
+///
+/// # 注記
+/// 1) Pingに同じペイロードのPongが返されることを確認する
+/// 2) 分割して送信したテキストメッセージが結合して処理されることを確認する
+/// 3) 開始フレームの無い継続フレームで接続が閉じられることを確認する
+/// 4) クライアントからのCloseに同じ状態コードのCloseが返されることを確認
+///    する
+///
+fn collab_session_handles_control_and_fragmented_frames() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    let server = ServerGuard::start_with_run_args(
+        port,
+        &db_path,
+        &assets_dir,
+        &["--collab"],
+    );
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let pages_url = format!("{}/pages", api_base_url);
+    let page_id = create_page(&client, &pages_url, "/frames", "draft\n");
+
+    let mut alice = WsClient::connect(port, &page_id);
+    assert_eq!(alice.recv_json()["type"], "init");
+
+    /*
+     * Ping/Pong
+     */
+    alice.send_frame(0x9, b"hb");
+    assert_eq!(alice.recv_control_frame(), (0xa, b"hb".to_vec()));
+
+    /*
+     * 分割されたメッセージ
+     */
+    let message = json!({
+        "type": "op",
+        "revision": 0,
+        "operation": [6, "more\n"],
+    })
+    .to_string();
+    let (first, last) = message.as_bytes().split_at(message.len() / 2);
+    alice.send_fragment(false, 0x1, first);
+    alice.send_fragment(true, 0x0, last);
+    assert_eq!(alice.recv_json(), json!({"type": "ack", "revision": 1}));
+
+    /*
+     * 開始フレームの無い継続フレーム
+     */
+    let mut bob = WsClient::connect(port, &page_id);
+    assert_eq!(bob.recv_json()["type"], "init");
+    assert_eq!(alice.recv_json()["type"], "presence");
+    bob.send_fragment(true, 0x0, b"{}");
+    let (opcode, payload) = bob.recv_control_frame();
+    assert_eq!(opcode, 0x8);
+    assert_eq!(payload[..2], 1002u16.to_be_bytes());
+
+    /*
+     * Closeの応答
+     */
+    alice.send_frame(0x8, &1000u16.to_be_bytes());
+    let (opcode, payload) = alice.recv_control_frame();
+    assert_eq!(opcode, 0x8);
+    assert_eq!(payload[..2], 1000u16.to_be_bytes());
+
+    drop(server);
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// テスト用の最小限のWebSocketクライアント
+///
+struct WsClient {
+    reader: BufReader<TcpStream>,
+}
+
+impl WsClient {
+    ///
+    /// 共同編集エンドポイントへ接続する
+    ///
+    fn connect(port: u16, page_id: &str) -> Self {
+        let (status, reader) = Self::open(port, page_id);
+        assert!(status.starts_with("HTTP/1.1 101"), "unexpected: {}", status);
+        Self { reader }
+    }
+
+    ///
+    /// ハンドシェイクのみを行い応答のステータス行を返す
+    ///
+    fn handshake(port: u16, page_id: &str) -> String {
+        Self::open(port, page_id).0
+    }
+
+    ///
+    /// ハンドシェイクを行う
+    ///
+    fn open(port: u16, page_id: &str) -> (String, BufReader<TcpStream>) {
+        let mut stream =
+            TcpStream::connect(("127.0.0.1", port)).expect("connect failed");
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .expect("set timeout failed");
+        let credentials = BASE64
+            .encode(format!("{}:{}", TEST_USERNAME, TEST_PASSWORD).as_bytes());
+        write!(
+            stream,
+            "GET /api/pages/{}/collab HTTP/1.1\r\n\
+             Host: 127.0.0.1:{}\r\n\
+             Authorization: Basic {}\r\n\
+             Connection: Upgrade\r\n\
+             Upgrade: websocket\r\n\
+             Sec-WebSocket-Version: 13\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+            page_id, port, credentials,
+        )
+        .expect("write handshake failed");
+
+        let mut reader = BufReader::new(stream);
+        let mut status = String::new();
+        reader.read_line(&mut status).expect("read status failed");
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).expect("read header failed");
+            if line.trim_end().is_empty() {
+                break;
+            }
+        }
+
+        (status, reader)
+    }
+
+    ///
+    /// テキストフレームでJSONを送信する
+    ///
+    fn send_json(&mut self, value: &Value) {
+        self.send_frame(0x1, value.to_string().as_bytes());
+    }
+
+    ///
+    /// テキストフレームを受信してJSONとして返す
+    ///
+    fn recv_json(&mut self) -> Value {
+        loop {
+            let (opcode, payload) = self.recv_frame();
+            if opcode == 0x1 {
+                return serde_json::from_slice(&payload).expect("parse failed");
+            }
+        }
+    }
+
+    ///
+    /// 接続を閉じ、サーバからのCloseフレームを待つ
+    ///
+    fn close(mut self) {
+        self.send_frame(0x8, &[]);
+        while self.recv_frame().0 != 0x8 {}
+    }
+
+    ///
+    /// テキストフレーム以外のフレームを受信する
+    ///
+    fn recv_control_frame(&mut self) -> (u8, Vec<u8>) {
+        loop {
+            let frame = self.recv_frame();
+            if frame.0 != 0x1 {
+                return frame;
+            }
+        }
+    }
+
+    ///
+    /// マスク付きのフレームを送信する
+    ///
+    fn send_frame(&mut self, opcode: u8, payload: &[u8]) {
+        self.send_fragment(true, opcode, payload);
+    }
+
+    ///
+    /// FINビットを指定してマスク付きのフレームを送信する
+    ///
+    fn send_fragment(&mut self, fin: bool, opcode: u8, payload: &[u8]) {
+        let mask = [0x12u8, 0x34, 0x56, 0x78];
+        let mut frame = vec![if fin { 0x80 | opcode } else { opcode }];
+        match payload.len() {
+            len if len < 126 => frame.push(0x80 | len as u8),
+            len => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+        }
+        frame.extend_from_slice(&mask);
+        frame.extend(
+            payload
+                .iter()
+                .enumerate()
+                .map(|(index, byte)| byte ^ mask[index % 4]),
+        );
+        self.reader
+            .get_mut()
+            .write_all(&frame)
+            .expect("write frame failed");
+    }
+
+    ///
+    /// フレームを受信する
+    ///
+    fn recv_frame(&mut self) -> (u8, Vec<u8>) {
+        let mut header = [0u8; 2];
+        self.reader.read_exact(&mut header).expect("read frame failed");
+        let len = match header[1] & 0x7f {
+            126 => {
+                let mut buf = [0u8; 2];
+                self.reader.read_exact(&mut buf).expect("read len failed");
+                u16::from_be_bytes(buf) as usize
+            }
+            127 => {
+                let mut buf = [0u8; 8];
+                self.reader.read_exact(&mut buf).expect("read len failed");
+                u64::from_be_bytes(buf) as usize
+            }
+            len => len as usize,
+        };
+        let mut payload = vec![0u8; len];
+        self.reader
+            .read_exact(&mut payload)
+            .expect("read payload failed");
+
+        (header[0] & 0x0f, payload)
+    }
+}
+
+///
+/// テスト用ページを作成する。
+///
+/// # 引数
+/// * `client` - HTTPクライアント
+/// * `pages_url` - ページAPIのURL
+/// * `path` - ページパス
+/// * `body` - ページ本文
+///
+/// # 戻り値
+/// 作成したページID
+///
+fn create_page(client: &Client, pages_url: &str, path: &str, body: &str) -> String {
+    let response = client
+        .post(pages_url)
+        .query(&[("path", path)])
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("create page failed");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let lock_token = response
+        .headers()
+        .get("X-Page-Lock")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            value
+                .split_whitespace()
+                .find_map(|part| part.strip_prefix("token="))
+                .map(str::to_string)
+        })
+        .expect("missing lock token");
+    let value: Value =
+        serde_json::from_str(&response.text().expect("read body failed"))
+            .expect("parse create page response failed");
+    let page_id = value["id"].as_str().expect("missing page id").to_string();
+
+    let response = client
+        .put(format!("{}/{}/source", pages_url, page_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header("Content-Type", "text/markdown")
+        .header("X-Lock-Authentication", format!("token={}", lock_token))
+        .body(body.to_string())
+        .send()
+        .expect("update page failed");
+    assert_eq!(response.status().as_u16(), 204);
+
+    page_id
+}
@@ -201,6 +201,99 @@ fn restore_page_recursive_restores_children() {
     fs::remove_dir_all(base_dir).expect("cleanup failed");
 }
 
+#[test]
+///
+/// DELETE/POST: ページの削除・復帰とログイン失敗が監査ログへ記録されることを
+/// 確認する。
+///
+/// # 注記
+/// 1) テスト用ユーザを作成する
+/// 2) ページを作成して削除し、別パスへ復帰する
+/// 3) 誤ったパスワードでアクセスする
+/// 4) 監査ログの各レコードを検証する
+fn delete_and_restore_page_records_audit_log() {
+    let _guard = lock_test();
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    let _server = ServerGuard::start(port, &db_path, &assets_dir);
+
+    let base_url = resolve_pages_base_url(port);
+    let page_id = create_page(&base_url, "/audit-delete", "body");
+
+    let client = client_for_base_url(&base_url);
+    let response = client
+        .delete(&format!("{}/{}", base_url, page_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("delete page failed");
+    assert_eq!(response.status().as_u16(), 204);
+
+    let response = client
+        .post(&format!("{}/{}/path", base_url, page_id))
+        .query(&[("restore_to", "/audit-restored")])
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("restore page failed");
+    assert_eq!(response.status().as_u16(), 204);
+
+    let response = client
+        .get(&format!("{}/{}/meta", base_url, page_id))
+        .basic_auth(TEST_USERNAME, Some("wrong-password"))
+        .send()
+        .expect("get meta with wrong password failed");
+    assert_eq!(response.status().as_u16(), 401);
+
+    let audit_path = db_path
+        .parent()
+        .expect("db_path parent missing")
+        .join("audit")
+        .join("audit.current.jsonl");
+    let records: Vec<Value> = fs::read_to_string(audit_path)
+        .expect("read audit log failed")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("parse audit record failed"))
+        .collect();
+    let find_record = |operation: &str| {
+        records
+            .iter()
+            .find(|record| record["operation"] == operation)
+            .unwrap_or_else(|| panic!("{} record missing", operation))
+    };
+
+    let create = find_record("create");
+    assert_eq!(create["target_path"], "/audit-delete");
+    assert_eq!(create["result"], "success");
+
+    let delete = find_record("delete");
+    assert_eq!(delete["target_path"], "/audit-delete");
+    assert_eq!(delete["result"], "success");
+    assert!(delete["user_id"].is_string());
+
+    let undelete = find_record("undelete");
+    assert_eq!(undelete["target_path"], "/audit-delete");
+    assert_eq!(undelete["summary"], "restore to /audit-restored");
+
+    let login = find_record("login");
+    assert_eq!(login["result"], "success");
+
+    let login_failure = records
+        .iter()
+        .find(|record| {
+            record["operation"] == "login"
+                && record["result"] == "authentication_failed"
+        })
+        .expect("login failure record missing");
+    assert!(
+        login_failure["summary"]
+            .as_str()
+            .is_some_and(|summary| summary.contains("reason=invalid_credentials"))
+    );
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
 struct ServerGuard {
     child: Child,
 }
@@ -219,6 +312,8 @@ impl ServerGuard {
             .arg(assets_dir)
             .arg("--fts-index")
             .arg(fts_index)
+            .arg("--audit-log-dir")
+            .arg(base_dir.join("audit"))
             .arg("--log-tee")
             .arg("run")
             .arg(format!("127.0.0.1:{}", port))
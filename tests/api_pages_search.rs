@@ -323,6 +323,57 @@ fn search_target_combination_deduplicates_results() {
     fs::remove_dir_all(base_dir).expect("cleanup failed");
 }
 
+#[test]
+/// GET: assets 指定時にテキスト形式のアセットがヒットすることを確認する。
+///
+/// # 注記
+/// - アセットにのみ含まれるトークンで検索する。
+/// - 本文検索ではヒットしないことも確認する。
+fn search_target_assets_returns_asset_hits() {
+    /*
+     * テスト環境の準備
+     */
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_url, client) = wait_for_server_with_scheme(port, server.stderr_path());
+
+    /*
+     * ページとアセットの作成
+     */
+    let token = format!("assettoken{}", unique_suffix());
+    let page_id = create_page(&client, &api_url, "/search-asset", "本文のみ");
+    let response = client
+        .post(&format!("{}/pages/{}/assets/config.toml", api_url, page_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header("Content-Type", "text/plain")
+        .body(format!("listen = \"{}\"\n", token))
+        .send()
+        .expect("create asset failed");
+    assert_eq!(response.status().as_u16(), 201);
+    let value: Value =
+        serde_json::from_str(&response.text().expect("read asset body failed"))
+            .expect("parse create asset response failed");
+    let asset_id = value["id"].as_str().expect("missing asset id").to_string();
+
+    /*
+     * 検索と検証
+     */
+    let results = search_pages(&client, &api_url, &token, Some("assets"), None, None);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["page_id"].as_str(), Some(page_id.as_str()));
+    assert_eq!(results[0]["path"].as_str(), Some("/search-asset"));
+    assert_eq!(results[0]["asset_id"].as_str(), Some(asset_id.as_str()));
+    assert_eq!(results[0]["file_name"].as_str(), Some("config.toml"));
+
+    let results = search_pages(&client, &api_url, &token, Some("body"), None, None);
+    assert!(results.is_empty());
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
 #[test]
 /// GET: expr が不正な場合に 400 が返ることを確認する。
 ///
@@ -262,6 +262,114 @@ fn put_page_source_creates_new_revision() {
     fs::remove_dir_all(base_dir).expect("cleanup failed");
 }
 
+#[test]
+/// PUT: 古い base_rev 指定時に3-wayマージまたは競合応答となることを確認する。
+fn put_page_source_merges_stale_base_revision() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_base_url, client) = wait_for_server_with_scheme(port, server.stderr_path());
+
+    let base_url = format!("{}/pages", api_base_url);
+    let page_id = create_page(
+        &client,
+        &base_url,
+        "/merge-test",
+        "# title\nintro\n\n## a\nalpha\n",
+    );
+    let url = format!("{}/{}/source", base_url, page_id);
+    let put = |base_rev: &str, body: &str| {
+        client
+            .put(&url)
+            .query(&[("base_rev", base_rev)])
+            .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+            .header("Content-Type", "text/markdown")
+            .body(body.to_string())
+            .send()
+            .expect("put source failed")
+    };
+
+    /*
+     * 最新リビジョンを基準とした保存は通常更新
+     */
+    let response = put("1", "# title\nintro (edited)\n\n## a\nalpha\n");
+    assert_eq!(response.status().as_u16(), 204);
+
+    /*
+     * 重ならない変更のマージ
+     */
+    let response = put("1", "# title\nintro\n\n## a\nalpha\nbeta\n");
+    assert_eq!(response.status().as_u16(), 200);
+    let value: Value =
+        serde_json::from_str(&response.text().expect("read body failed"))
+            .expect("parse merge response failed");
+    assert_eq!(value["merged"], true);
+    assert_eq!(value["base_revision"], 1);
+    assert_eq!(value["revision"], 3);
+
+    let response = client
+        .get(&url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get latest after merge failed");
+    assert_eq!(
+        response.text().expect("read body failed"),
+        "# title\nintro (edited)\n\n## a\nalpha\nbeta\n"
+    );
+
+    /*
+     * 重なる変更の競合
+     */
+    let response = put("1", "# title\nintro (mine)\n\n## a\nalpha\n");
+    assert_eq!(response.status().as_u16(), 409);
+    let value: Value =
+        serde_json::from_str(&response.text().expect("read body failed"))
+            .expect("parse conflict response failed");
+    assert_eq!(value["kind"], "merge_conflict");
+    assert_eq!(value["latest_revision"], 3);
+    assert_eq!(value["conflicts"].as_array().map(Vec::len), Some(1));
+    assert_eq!(value["conflicts"][0]["base_line"], 2);
+    assert_eq!(value["conflicts"][0]["latest"], "intro (edited)\n");
+    assert_eq!(value["conflicts"][0]["submitted"], "intro (mine)\n");
+
+    /*
+     * 不正な base_rev
+     */
+    for base_rev in ["0", "4", "abc"] {
+        let response = put(base_rev, "body");
+        assert_eq!(response.status().as_u16(), 400);
+    }
+
+    /*
+     * amend と base_rev の同時指定
+     */
+    let response = client
+        .put(&url)
+        .query(&[("base_rev", "3"), ("amend", "true")])
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header("Content-Type", "text/markdown")
+        .body("# title\nintro (amended)\n")
+        .send()
+        .expect("put source with amend failed");
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(
+        read_error_reason(response),
+        "invalid query parameter: amend"
+    );
+
+    let response = client
+        .get(&url)
+        .query(&[("rev", "4")])
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get rev=4 failed");
+    assert_eq!(response.status().as_u16(), 404);
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
 #[test]
 /// PUT: front matter の YAML 構文不正時に詳細付き 400 を返すことを確認する。
 fn put_page_source_rejects_invalid_front_matter_syntax() {
@@ -0,0 +1,212 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use reqwest::header::{COOKIE, SET_COOKIE};
+use serde_json::Value;
+
+use common::*;
+
+/// 二要素認証を登録するテスト用ユーザ名
+const MFA_USERNAME: &str = "mfa_user";
+
+///
+/// CLI で二要素認証を登録し、リカバリコード群を取得する
+///
+/// # 引数
+/// * `db_path` - DBパス
+/// * `assets_dir` - アセットディレクトリ
+/// * `user_name` - 登録対象ユーザ名
+///
+/// # 戻り値
+/// 発行されたリカバリコード群
+///
+fn run_enroll_mfa(
+    db_path: &Path,
+    assets_dir: &Path,
+    user_name: &str,
+) -> Vec<String> {
+    let base_dir = db_path.parent().expect("db_path parent missing");
+    let output = Command::new(test_binary_path())
+        .env("XDG_CONFIG_HOME", base_dir)
+        .env("XDG_DATA_HOME", base_dir)
+        .arg("--db-path")
+        .arg(db_path)
+        .arg("--assets-path")
+        .arg(assets_dir)
+        .arg("--fts-index")
+        .arg(fts_index_path(db_path))
+        .arg("user")
+        .arg("mfa")
+        .arg("enroll")
+        .arg(user_name)
+        .stdin(Stdio::null())
+        .output()
+        .expect("spawn user mfa enroll failed");
+
+    assert!(
+        output.status.success(),
+        "user mfa enroll failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8(output.stdout)
+        .expect("user mfa enroll stdout decode failed");
+    assert!(stdout.contains(&format!(
+        "otpauth://totp/LuWiki:{}?secret=",
+        user_name
+    )));
+
+    stdout
+        .lines()
+        .skip_while(|line| line.trim() != "recovery codes:")
+        .skip(1)
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+#[test]
+///
+/// Basic認証: 二要素認証登録済みユーザがコード付きでのみ認証できることを確認
+///
+/// # 注記
+/// コード無しで 401、リカバリコード付きで 200 と Set-Cookie を得た後、
+/// 発行された Cookie を付与すればパスワードのみで認証できることを検証する。
+///
+fn basic_auth_requires_mfa_code_after_enroll() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    run_add_user_with_credentials(
+        &db_path,
+        &assets_dir,
+        MFA_USERNAME,
+        TEST_PASSWORD,
+    );
+    let recovery_codes = run_enroll_mfa(&db_path, &assets_dir, MFA_USERNAME);
+    assert_eq!(recovery_codes.len(), 10);
+
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let url = format!("{}/users/me", api_base_url);
+
+    /*
+     * コード無しの Basic認証は拒否される
+     */
+    let response = client
+        .get(&url)
+        .basic_auth(MFA_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get users/me without code failed");
+    assert_eq!(response.status().as_u16(), 401);
+
+    /*
+     * リカバリコード付きで認証するとセッション Cookie が発行される
+     */
+    let password = format!("{}:{}", TEST_PASSWORD, recovery_codes[0]);
+    let response = client
+        .get(&url)
+        .basic_auth(MFA_USERNAME, Some(&password))
+        .send()
+        .expect("get users/me with recovery code failed");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let set_cookie = response
+        .headers()
+        .get(SET_COOKIE)
+        .expect("missing set-cookie")
+        .to_str()
+        .expect("set-cookie to_str failed")
+        .to_string();
+    let cookie = set_cookie
+        .split(';')
+        .next()
+        .expect("cookie pair missing")
+        .to_string();
+    assert!(cookie.starts_with("luwiki_mfa="));
+    assert_eq!(
+        set_cookie.split(';').any(|attr| attr.trim() == "Secure"),
+        api_base_url.starts_with("https://")
+    );
+
+    let body = response.text().expect("read body failed");
+    let value: Value =
+        serde_json::from_str(&body).expect("parse users/me response failed");
+    assert_eq!(value["mfa_enrolled"], true);
+
+    /*
+     * 使用済みリカバリコードは再利用できない
+     */
+    let response = client
+        .get(&url)
+        .basic_auth(MFA_USERNAME, Some(&password))
+        .send()
+        .expect("get users/me with used code failed");
+    assert_eq!(response.status().as_u16(), 401);
+
+    /*
+     * セッション Cookie があればパスワードのみで認証できる
+     */
+    let response = client
+        .get(&url)
+        .basic_auth(MFA_USERNAME, Some(TEST_PASSWORD))
+        .header(COOKIE, &cookie)
+        .send()
+        .expect("get users/me with session cookie failed");
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response.headers().get(SET_COOKIE).is_none());
+
+    let response = client
+        .get(&url)
+        .basic_auth(MFA_USERNAME, Some("wrong-password"))
+        .header(COOKIE, &cookie)
+        .send()
+        .expect("get users/me with wrong password failed");
+    assert_eq!(response.status().as_u16(), 401);
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// Basic認証: `require_mfa` 属性を持つ未登録ユーザが拒否されることを確認
+///
+/// # 注記
+/// 属性のみ付与したユーザで、正しいパスワードでも 401 になることを検証する。
+///
+fn basic_auth_rejects_require_mfa_user_without_enrollment() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    run_add_user_with_credentials_and_attributes(
+        &db_path,
+        &assets_dir,
+        MFA_USERNAME,
+        TEST_PASSWORD,
+        &["require_mfa"],
+    );
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+
+    let response = client
+        .get(format!("{}/users/me", api_base_url))
+        .basic_auth(MFA_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get users/me failed");
+    assert_eq!(response.status().as_u16(), 401);
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
@@ -0,0 +1,382 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+mod common;
+
+use std::fs;
+
+use reqwest::header::CONTENT_TYPE;
+use serde_json::{Value, json};
+
+use common::*;
+
+/// 読み取り専用属性を持つテスト用ユーザ名
+const READ_ONLY_USERNAME: &str = "read_only_user";
+
+#[test]
+///
+/// Basic認証: 自分のトークンの発行・一覧・編集・失効ができることを確認
+///
+/// # 注記
+/// 発行時のみ平文が返り、一覧には平文が含まれないこと、編集結果が反映
+/// されること、失効後は発行したトークンで認証できないことを検証する。
+///
+fn self_service_token_lifecycle() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let url = format!("{}/users/me/tokens", api_base_url);
+
+    /*
+     * 発行
+     */
+    let response = client
+        .post(&url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header(CONTENT_TYPE, "application/json")
+        .body(json!({
+            "name": "ci",
+            "scopes": ["read", "write"],
+            "ttl": "1d",
+            "path_prefixes": ["/docs"],
+        })
+        .to_string())
+        .send()
+        .expect("post tokens failed");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let body = response.text().expect("read body failed");
+    let created: Value =
+        serde_json::from_str(&body).expect("parse create response failed");
+    let token = created["token"].as_str().expect("token missing").to_string();
+    let token_id = created["token_id"]
+        .as_str()
+        .expect("token_id missing")
+        .to_string();
+    assert_eq!(created["name"], "ci");
+    assert_eq!(created["scopes"], json!(["read", "write"]));
+    assert_eq!(created["path_prefixes"], json!(["/docs"]));
+
+    /*
+     * 発行したトークンで認証できる
+     */
+    let response = client
+        .get(format!("{}/users/me", api_base_url))
+        .bearer_auth(&token)
+        .send()
+        .expect("get users/me with token failed");
+    assert_eq!(response.status().as_u16(), 200);
+
+    /*
+     * 一覧には平文が含まれない
+     */
+    let response = client
+        .get(&url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get tokens failed");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body = response.text().expect("read body failed");
+    let listed: Value =
+        serde_json::from_str(&body).expect("parse list response failed");
+    let tokens = listed["tokens"].as_array().expect("tokens missing");
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0]["token_id"], token_id.as_str());
+    assert!(tokens[0].get("token").is_none());
+
+    /*
+     * 名前と path prefix の編集
+     */
+    let response = client
+        .patch(format!("{}/{}", url, token_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header(CONTENT_TYPE, "application/json")
+        .body(json!({
+            "name": null,
+            "path_prefixes": ["/notes", "/docs"],
+        })
+        .to_string())
+        .send()
+        .expect("patch token failed");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body = response.text().expect("read body failed");
+    let edited: Value =
+        serde_json::from_str(&body).expect("parse patch response failed");
+    assert_eq!(edited["name"], Value::Null);
+    assert_eq!(edited["path_prefixes"], json!(["/docs", "/notes"]));
+
+    /*
+     * 失効(冪等)
+     */
+    for _ in 0..2 {
+        let response = client
+            .delete(format!("{}/{}", url, token_id))
+            .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+            .send()
+            .expect("delete token failed");
+        assert_eq!(response.status().as_u16(), 204);
+    }
+
+    let response = client
+        .get(format!("{}/users/me", api_base_url))
+        .bearer_auth(&token)
+        .send()
+        .expect("get users/me with revoked token failed");
+    assert_eq!(response.status().as_u16(), 401);
+
+    /*
+     * 失効済みトークンは編集できない
+     */
+    let response = client
+        .patch(format!("{}/{}", url, token_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header(CONTENT_TYPE, "application/json")
+        .body(json!({ "name": "again" })
+        .to_string())
+        .send()
+        .expect("patch revoked token failed");
+    assert_eq!(response.status().as_u16(), 409);
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// Basic認証: 自分のトークンをローテーションできることを確認
+///
+/// # 注記
+/// トークンIDが維持されること、猶予期間中は新旧いずれの平文でも認証
+/// できること、失効後は旧平文も拒否されローテーションも 409 になる
+/// ことを検証する。
+///
+fn self_service_token_rotation() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let url = format!("{}/users/me/tokens", api_base_url);
+    let me_url = format!("{}/users/me", api_base_url);
+
+    let response = client
+        .post(&url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header(CONTENT_TYPE, "application/json")
+        .body(json!({ "scopes": ["read"] }).to_string())
+        .send()
+        .expect("post tokens failed");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let body = response.text().expect("read body failed");
+    let created: Value =
+        serde_json::from_str(&body).expect("parse create response failed");
+    let old_token = created["token"].as_str().expect("token missing").to_string();
+    let token_id = created["token_id"]
+        .as_str()
+        .expect("token_id missing")
+        .to_string();
+    assert_eq!(created["rotated_at"], Value::Null);
+
+    /*
+     * 不正な猶予期間の指定は拒否される
+     */
+    let rotate_url = format!("{}/{}/rotate", url, token_id);
+    let response = client
+        .post(&rotate_url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header(CONTENT_TYPE, "application/json")
+        .body(json!({ "grace": "1w" }).to_string())
+        .send()
+        .expect("post rotate with invalid grace failed");
+    assert_eq!(response.status().as_u16(), 400);
+
+    /*
+     * ローテーション
+     */
+    let response = client
+        .post(&rotate_url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .header(CONTENT_TYPE, "application/json")
+        .body(json!({ "grace": "1h" }).to_string())
+        .send()
+        .expect("post rotate failed");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body = response.text().expect("read body failed");
+    let rotated: Value =
+        serde_json::from_str(&body).expect("parse rotate response failed");
+    let new_token = rotated["token"].as_str().expect("token missing").to_string();
+    assert_eq!(rotated["token_id"], token_id.as_str());
+    assert_ne!(new_token, old_token);
+    assert!(rotated["rotated_at"].is_string());
+    assert!(rotated["grace_until"].is_string());
+
+    /*
+     * 猶予期間中は新旧いずれの平文でも認証できる
+     */
+    for token in [&old_token, &new_token] {
+        let response = client
+            .get(&me_url)
+            .bearer_auth(token)
+            .send()
+            .expect("get users/me with token failed");
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    /*
+     * 失効は旧平文にも反映され、以後はローテーションできない
+     */
+    let response = client
+        .delete(format!("{}/{}", url, token_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("delete token failed");
+    assert_eq!(response.status().as_u16(), 204);
+
+    for token in [&old_token, &new_token] {
+        let response = client
+            .get(&me_url)
+            .bearer_auth(token)
+            .send()
+            .expect("get users/me with revoked token failed");
+        assert_eq!(response.status().as_u16(), 401);
+    }
+
+    let response = client
+        .post(&rotate_url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("post rotate revoked token failed");
+    assert_eq!(response.status().as_u16(), 409);
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// Bearer認証: トークン管理APIが拒否されることを確認
+///
+/// # 注記
+/// トークンから別トークンを連鎖的に発行できないことを検証する。
+///
+fn token_management_rejects_bearer_auth() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    let token = run_create_token(&db_path, &assets_dir, "read,write");
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let url = format!("{}/users/me/tokens", api_base_url);
+
+    let response = client
+        .get(&url)
+        .bearer_auth(&token)
+        .send()
+        .expect("get tokens with bearer failed");
+    assert_eq!(response.status().as_u16(), 403);
+
+    let response = client
+        .post(&url)
+        .bearer_auth(&token)
+        .header(CONTENT_TYPE, "application/json")
+        .body(json!({ "scopes": ["read"] })
+        .to_string())
+        .send()
+        .expect("post tokens with bearer failed");
+    assert_eq!(response.status().as_u16(), 403);
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// Basic認証: 自身の属性を超えるスコープや他ユーザのトークンを操作できない
+/// ことを確認
+///
+/// # 注記
+/// 読み取り専用ユーザが `write` スコープを要求すると 403、他ユーザの
+/// トークンIDを指定すると 404 になることを検証する。
+///
+fn token_management_enforces_owner_limits() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    run_add_user_with_credentials_and_attributes(
+        &db_path,
+        &assets_dir,
+        READ_ONLY_USERNAME,
+        TEST_PASSWORD,
+        &["read_only"],
+    );
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_base_url, client) =
+        wait_for_server_with_scheme(port, server.stderr_path());
+    let url = format!("{}/users/me/tokens", api_base_url);
+
+    /*
+     * 読み取り専用ユーザは write スコープを発行できない
+     */
+    let response = client
+        .post(&url)
+        .basic_auth(READ_ONLY_USERNAME, Some(TEST_PASSWORD))
+        .header(CONTENT_TYPE, "application/json")
+        .body(json!({ "scopes": ["read", "write"] })
+        .to_string())
+        .send()
+        .expect("post write token failed");
+    assert_eq!(response.status().as_u16(), 403);
+
+    let response = client
+        .post(&url)
+        .basic_auth(READ_ONLY_USERNAME, Some(TEST_PASSWORD))
+        .header(CONTENT_TYPE, "application/json")
+        .body(json!({ "scopes": ["read"] })
+        .to_string())
+        .send()
+        .expect("post read token failed");
+    assert_eq!(response.status().as_u16(), 201);
+
+    let body = response.text().expect("read body failed");
+    let created: Value =
+        serde_json::from_str(&body).expect("parse create response failed");
+    let token_id = created["token_id"]
+        .as_str()
+        .expect("token_id missing")
+        .to_string();
+
+    /*
+     * 他ユーザのトークンは見えない
+     */
+    let response = client
+        .delete(format!("{}/{}", url, token_id))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("delete other user's token failed");
+    assert_eq!(response.status().as_u16(), 404);
+
+    let response = client
+        .get(&url)
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("get tokens failed");
+    let body = response.text().expect("read body failed");
+    let listed: Value =
+        serde_json::from_str(&body).expect("parse list response failed");
+    assert_eq!(listed["tokens"], json!([]));
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
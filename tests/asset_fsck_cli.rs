@@ -0,0 +1,94 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+mod common;
+
+use common::*;
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// 孤立した実体として配置するデータ
+const ORPHAN_DATA: &[u8] = b"orphan";
+
+/// `ORPHAN_DATA`のSHA-256
+const ORPHAN_HASH: &str =
+    "88f6811ab5d8fc6d3177f9b7609ae0fcebfda187e5046b62d38bb539e88b74d7";
+
+#[test]
+///
+/// asset fsck が参照されていない実体を検出し、--fix で削除することを確認
+/// する。
+///
+/// # 注記
+/// 1) テスト用ユーザを作成する
+/// 2) 不整合の無い状態で asset fsck が成功することを確認する
+/// 3) blob ディレクトリに孤立した実体を配置する
+/// 4) asset fsck がエラー終了し、不整合を表示することを確認する
+/// 5) asset fsck --fix で実体が削除され成功することを確認する
+fn asset_fsck_cli_detects_and_removes_unreferenced_blob() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+
+    run_add_user(&db_path, &assets_dir);
+
+    let output = run_asset_fsck(&db_path, &assets_dir, false);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("no issues found"));
+
+    let orphan_path = assets_dir
+        .join("blobs")
+        .join(&ORPHAN_HASH[0..2])
+        .join(&ORPHAN_HASH[2..4])
+        .join(ORPHAN_HASH);
+    fs::create_dir_all(orphan_path.parent().expect("parent missing"))
+        .expect("create blob dir failed");
+    fs::write(&orphan_path, ORPHAN_DATA).expect("write orphan blob failed");
+
+    let output = run_asset_fsck(&db_path, &assets_dir, false);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("unreferenced blob: {}", ORPHAN_HASH)));
+    assert!(orphan_path.exists());
+
+    let output = run_asset_fsck(&db_path, &assets_dir, true);
+    assert!(output.status.success());
+    assert!(!orphan_path.exists());
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// asset fsck を実行する。
+///
+/// # 引数
+/// * `db_path` - DBファイルのパス
+/// * `assets_dir` - アセットディレクトリのパス
+/// * `fix` - 修復を行う場合はtrue
+///
+/// # 戻り値
+/// 実行結果を返す。
+fn run_asset_fsck(db_path: &Path, assets_dir: &Path, fix: bool) -> Output {
+    let exe = test_binary_path();
+    let base_dir = db_path.parent().expect("db_path parent missing");
+    let mut command = Command::new(exe);
+    command
+        .env("XDG_CONFIG_HOME", base_dir)
+        .env("XDG_DATA_HOME", base_dir)
+        .arg("--db-path")
+        .arg(db_path)
+        .arg("--assets-path")
+        .arg(assets_dir)
+        .arg("--fts-index")
+        .arg(fts_index_path(db_path))
+        .arg("asset")
+        .arg("fsck");
+    if fix {
+        command.arg("--fix");
+    }
+
+    command.output().expect("asset fsck failed")
+}
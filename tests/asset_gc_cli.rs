@@ -0,0 +1,92 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+mod common;
+
+use common::*;
+
+use std::fs::{self, File};
+use std::path::Path;
+use std::process::{Command, Output};
+use std::time::{Duration, SystemTime};
+
+/// 中断された受信データとして配置するデータ
+const STRAY_DATA: &[u8] = b"interrupted upload";
+
+#[test]
+///
+/// asset gc --dry-run が中断された受信データを報告のみ行い、asset gc で
+/// 削除することを確認する。
+///
+/// # 注記
+/// 1) テスト用ユーザを作成する
+/// 2) 受信データ格納ディレクトリに古い一時ファイルを配置する
+/// 3) asset gc --dry-run が回収可能なサイズを表示し、ファイルを残すこと
+///    を確認する
+/// 4) asset gc でファイルが削除されることを確認する
+fn asset_gc_cli_reports_and_removes_stray_upload() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+
+    run_add_user(&db_path, &assets_dir);
+
+    let stray_path = assets_dir.join("uploads").join("interrupted.tmp");
+    fs::create_dir_all(stray_path.parent().expect("parent missing"))
+        .expect("create upload dir failed");
+    fs::write(&stray_path, STRAY_DATA).expect("write stray file failed");
+    File::options()
+        .write(true)
+        .open(&stray_path)
+        .expect("open stray file failed")
+        .set_modified(SystemTime::now() - Duration::from_secs(2 * 60 * 60))
+        .expect("set modified failed");
+
+    let output = run_asset_gc(&db_path, &assets_dir, true);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("stray file:"));
+    assert!(stdout.contains(&format!("{} bytes reclaimable", STRAY_DATA.len())));
+    assert!(stray_path.exists());
+
+    let output = run_asset_gc(&db_path, &assets_dir, false);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("{} bytes reclaimed", STRAY_DATA.len())));
+    assert!(!stray_path.exists());
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// asset gc を実行する。
+///
+/// # 引数
+/// * `db_path` - DBファイルのパス
+/// * `assets_dir` - アセットディレクトリのパス
+/// * `dry_run` - 表示のみを行う場合はtrue
+///
+/// # 戻り値
+/// 実行結果を返す。
+fn run_asset_gc(db_path: &Path, assets_dir: &Path, dry_run: bool) -> Output {
+    let exe = test_binary_path();
+    let base_dir = db_path.parent().expect("db_path parent missing");
+    let mut command = Command::new(exe);
+    command
+        .env("XDG_CONFIG_HOME", base_dir)
+        .env("XDG_DATA_HOME", base_dir)
+        .arg("--db-path")
+        .arg(db_path)
+        .arg("--assets-path")
+        .arg(assets_dir)
+        .arg("--fts-index")
+        .arg(fts_index_path(db_path))
+        .arg("asset")
+        .arg("gc");
+    if dry_run {
+        command.arg("--dry-run");
+    }
+
+    command.output().expect("asset gc failed")
+}
@@ -0,0 +1,217 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+mod common;
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, UdpSocket};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use serde_json::Value;
+
+use common::*;
+
+#[test]
+///
+/// config.toml の audit_output 設定に従い、CLI 操作の監査レコードが
+/// syslog と webhook へ転送されることを確認する。
+///
+/// # 注記
+/// 1) UDP の syslog 受信側と HTTP の webhook 受信側を起動する
+/// 2) ローカルファイル出力を有効にしたまま両転送先を設定して user add を
+///    実行し、3 つの出力先すべてにレコードが届くことを確認する
+/// 3) ローカルファイル出力を無効にすると監査ログファイルが作成されない
+///    ことを確認する
+///
+fn audit_output_forwards_cli_records_to_syslog_and_webhook() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let audit_dir = base_dir.join("audit");
+
+    /*
+     * 受信側の起動と設定ファイルの作成
+     */
+    let syslog = UdpSocket::bind("127.0.0.1:0").expect("bind syslog failed");
+    syslog
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .expect("set timeout failed");
+    let (webhook_url, collector) = spawn_webhook_collector(2);
+
+    let config_path = base_dir.join("config.toml");
+    let write_config = |file: bool| {
+        fs::write(
+            &config_path,
+            format!(
+                "[audit_output]\n\
+                 file = {}\n\
+                 \n\
+                 [[audit_output.syslog]]\n\
+                 address = \"{}\"\n\
+                 protocol = \"udp\"\n\
+                 facility = \"auth\"\n\
+                 hostname = \"wiki01\"\n\
+                 \n\
+                 [[audit_output.webhook]]\n\
+                 url = \"{}\"\n\
+                 batch_size = 10\n\
+                 flush_interval_secs = 1\n",
+                file,
+                syslog.local_addr().unwrap(),
+                webhook_url,
+            ),
+        )
+        .expect("write config failed");
+    };
+
+    /*
+     * ローカルファイルと外部転送先の併用
+     */
+    write_config(true);
+    run_add_user_with_config(
+        &db_path,
+        &assets_dir,
+        &audit_dir,
+        &config_path,
+        "forward_user1",
+    );
+
+    let mut buf = [0u8; 8192];
+    let size = syslog.recv(&mut buf).expect("recv syslog failed");
+    let message = String::from_utf8_lossy(&buf[..size]).to_string();
+    assert!(message.starts_with("<38>1 "), "unexpected: {}", message);
+    assert!(message.contains(" wiki01 luwiki "));
+    assert!(message.contains(" user_create - {"));
+    assert!(message.contains("user=forward_user1"));
+
+    let audit_log = fs::read_to_string(audit_dir.join("audit.current.jsonl"))
+        .expect("read audit log failed");
+    assert!(audit_log.contains("user=forward_user1"));
+
+    /*
+     * ローカルファイル出力の無効化
+     */
+    fs::remove_dir_all(&audit_dir).expect("remove audit dir failed");
+    write_config(false);
+    run_add_user_with_config(
+        &db_path,
+        &assets_dir,
+        &audit_dir,
+        &config_path,
+        "forward_user2",
+    );
+
+    let size = syslog.recv(&mut buf).expect("recv syslog failed");
+    let message = String::from_utf8_lossy(&buf[..size]).to_string();
+    assert!(message.contains("user=forward_user2"));
+    assert!(!audit_dir.join("audit.current.jsonl").exists());
+
+    /*
+     * webhook の受信内容の確認
+     */
+    let bodies = collector.join().expect("collector failed");
+    let summaries: Vec<String> = bodies
+        .iter()
+        .flat_map(|body| {
+            serde_json::from_str::<Vec<Value>>(body).expect("parse body failed")
+        })
+        .map(|record| {
+            assert_eq!(record["operation"], "user_create");
+            record["summary"].as_str().unwrap_or_default().to_string()
+        })
+        .collect();
+    assert_eq!(summaries, vec!["user=forward_user1", "user=forward_user2"]);
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// 指定回数の POST を受け付ける webhook 受信側を起動する
+///
+fn spawn_webhook_collector(count: usize) -> (String, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind failed");
+    let url = format!("http://{}/audit", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let mut bodies = Vec::new();
+        for _ in 0..count {
+            let (stream, _) = listener.accept().expect("accept failed");
+            let mut reader = BufReader::new(stream);
+            let mut length = 0usize;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).expect("read header failed");
+                let line = line.trim_end().to_ascii_lowercase();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some(value) = line.strip_prefix("content-length:") {
+                    length = value.trim().parse().expect("invalid length");
+                }
+            }
+            let mut body = vec![0u8; length];
+            reader.read_exact(&mut body).expect("read body failed");
+            bodies.push(String::from_utf8(body).expect("invalid body"));
+
+            let mut stream = reader.into_inner();
+            stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\
+                      Connection: close\r\n\r\n",
+                )
+                .expect("write response failed");
+        }
+        bodies
+    });
+
+    (url, handle)
+}
+
+///
+/// 設定ファイルを指定してテスト用ユーザを追加する
+///
+fn run_add_user_with_config(
+    db_path: &Path,
+    assets_dir: &Path,
+    audit_dir: &Path,
+    config_path: &Path,
+    user_name: &str,
+) {
+    let base_dir = db_path.parent().expect("db_path parent missing");
+
+    let mut child = Command::new(test_binary_path())
+        .env("XDG_CONFIG_HOME", base_dir)
+        .env("XDG_DATA_HOME", base_dir)
+        .arg("--config-path")
+        .arg(config_path)
+        .arg("--db-path")
+        .arg(db_path)
+        .arg("--assets-path")
+        .arg(assets_dir)
+        .arg("--fts-index")
+        .arg(fts_index_path(db_path))
+        .arg("--audit-log-dir")
+        .arg(audit_dir)
+        .arg("user")
+        .arg("add")
+        .arg(user_name)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("spawn add_user failed");
+
+    {
+        let stdin = child.stdin.as_mut().expect("stdin missing");
+        writeln!(stdin, "{}", TEST_PASSWORD).expect("write password failed");
+        writeln!(stdin, "{}", TEST_PASSWORD).expect("write confirm failed");
+    }
+
+    let output = child.wait_with_output().expect("wait add_user failed");
+    assert!(output.status.success(), "user add failed: {:?}", output);
+}
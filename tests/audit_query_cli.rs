@@ -0,0 +1,319 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use serde_json::{Value, json};
+
+use common::*;
+
+#[test]
+///
+/// audit query がローテーション済みファイルとアクティブファイルの双方を
+/// 検索できることを確認する。
+///
+/// # 注記
+/// 1) ローテーション済みファイルとアクティブファイルへ監査レコードを配置する
+/// 2) 操作種別と path prefix で絞り込み、双方のファイルから抽出されることを
+///    確認する
+/// 3) 期間と結果分類で絞り込めることを確認する
+/// 4) 未登録ユーザや不正な操作種別の指定がエラーになることを確認する
+///
+fn audit_query_cli_filters_rotated_and_active_logs() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    run_add_user(&db_path, &assets_dir);
+
+    let audit_dir = base_dir.join("audit");
+    fs::create_dir_all(&audit_dir).expect("create audit dir failed");
+    write_audit_log(
+        &audit_dir.join("audit-20260301T020000Z-000001.jsonl"),
+        &[
+            audit_record("delete", "/docs/a", "success", "2026-03-01T01:00:00Z"),
+            audit_record("rename", "/docs/b", "success", "2026-03-01T01:30:00Z"),
+        ],
+    );
+    write_audit_log(
+        &audit_dir.join("audit.current.jsonl"),
+        &[
+            audit_record("delete", "/docs", "not_found", "2026-03-02T01:00:00Z"),
+            audit_record("delete", "/other", "success", "2026-03-02T02:00:00Z"),
+        ],
+    );
+
+    /*
+     * 操作種別と path prefix による絞り込み
+     */
+    let output = run_audit_query(
+        &db_path,
+        &assets_dir,
+        &audit_dir,
+        &["--operation", "delete", "--path-prefix", "/docs", "--json"],
+    );
+    assert!(output.status.success(), "audit query failed: {:?}", output);
+    let records = parse_json_lines(&output);
+    let paths: Vec<_> = records
+        .iter()
+        .map(|record| record["target_path"].as_str().unwrap_or_default())
+        .collect();
+    assert_eq!(paths, vec!["/docs/a", "/docs"]);
+
+    /*
+     * 期間と結果分類による絞り込み
+     */
+    let output = run_audit_query(
+        &db_path,
+        &assets_dir,
+        &audit_dir,
+        &[
+            "--since",
+            "2026-03-01T01:15:00Z",
+            "--until",
+            "2026-03-02T01:30:00Z",
+            "--result",
+            "success",
+        ],
+    );
+    assert!(output.status.success(), "audit query failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2, "unexpected output: {}", stdout);
+    assert!(lines[0].starts_with("TIMESTAMP"));
+    assert!(lines[1].contains("rename"));
+    assert!(lines[1].contains("/docs/b"));
+
+    /*
+     * ユーザ条件 (レコードに記録されていないユーザ)
+     */
+    let output = run_audit_query(
+        &db_path,
+        &assets_dir,
+        &audit_dir,
+        &["--user", TEST_USERNAME, "--json"],
+    );
+    assert!(output.status.success(), "audit query failed: {:?}", output);
+    assert!(parse_json_lines(&output).is_empty());
+
+    /*
+     * 不正な指定
+     */
+    let output = run_audit_query(
+        &db_path,
+        &assets_dir,
+        &audit_dir,
+        &["--user", "missing_user"],
+    );
+    assert!(!output.status.success());
+
+    let output = run_audit_query(
+        &db_path,
+        &assets_dir,
+        &audit_dir,
+        &["--operation", "unknown"],
+    );
+    assert!(!output.status.success());
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// audit verify がハッシュチェーンの改ざんを検出することを確認する。
+///
+/// # 注記
+/// 1) 監査対象の CLI 操作で監査ログを出力し、検証が成功することを確認する
+/// 2) レコードを書き換えると検証が失敗し、問題箇所が出力されることを確認する
+/// 3) 途中のレコードを削除すると欠落として検出されることを確認する
+///
+fn audit_verify_cli_detects_tampered_log() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let audit_dir = base_dir.join("audit");
+    for user_name in ["verify_user1", "verify_user2", "verify_user3"] {
+        run_add_user_with_audit(&db_path, &assets_dir, &audit_dir, user_name);
+    }
+
+    /*
+     * 改ざんのない状態の検証
+     */
+    let output = run_audit_command(
+        &db_path,
+        &assets_dir,
+        &audit_dir,
+        &["verify"],
+    );
+    assert!(output.status.success(), "audit verify failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("checked 3 record(s) in 1 file(s)"));
+    assert!(stdout.contains("audit log hash chain is intact"));
+
+    /*
+     * レコードの書き換え
+     */
+    let active_path = audit_dir.join("audit.current.jsonl");
+    let original = fs::read_to_string(&active_path).expect("read log failed");
+    assert!(original.contains("user=verify_user2"));
+    fs::write(&active_path, original.replace("verify_user2", "forged_user"))
+        .expect("write log failed");
+    let output = run_audit_command(
+        &db_path,
+        &assets_dir,
+        &audit_dir,
+        &["verify"],
+    );
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("modified: "), "unexpected output: {}", stdout);
+
+    /*
+     * 途中のレコードの削除
+     */
+    let lines: Vec<_> = original.lines().collect();
+    fs::write(&active_path, format!("{}\n{}\n", lines[0], lines[2]))
+        .expect("write log failed");
+
+    let output = run_audit_command(
+        &db_path,
+        &assets_dir,
+        &audit_dir,
+        &["verify"],
+    );
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("gap: "), "unexpected output: {}", stdout);
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// 監査ログの出力先を指定してテスト用ユーザを追加する
+///
+fn run_add_user_with_audit(
+    db_path: &Path,
+    assets_dir: &Path,
+    audit_dir: &Path,
+    user_name: &str,
+) {
+    let base_dir = db_path.parent().expect("db_path parent missing");
+
+    let mut child = Command::new(test_binary_path())
+        .env("XDG_CONFIG_HOME", base_dir)
+        .env("XDG_DATA_HOME", base_dir)
+        .arg("--db-path")
+        .arg(db_path)
+        .arg("--assets-path")
+        .arg(assets_dir)
+        .arg("--fts-index")
+        .arg(fts_index_path(db_path))
+        .arg("--audit-log-dir")
+        .arg(audit_dir)
+        .arg("user")
+        .arg("add")
+        .arg(user_name)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn add_user failed");
+
+    {
+        let stdin = child.stdin.as_mut().expect("stdin missing");
+        writeln!(stdin, "{}", TEST_PASSWORD).expect("write password failed");
+        writeln!(stdin, "{}", TEST_PASSWORD).expect("write confirm failed");
+    }
+
+    let status = child.wait().expect("wait add_user failed");
+    assert!(status.success());
+}
+
+///
+/// テスト用の監査レコードを生成する
+///
+fn audit_record(
+    operation: &str,
+    path: &str,
+    result: &str,
+    timestamp: &str,
+) -> Value {
+    json!({
+        "operation": operation,
+        "user_id": null,
+        "token_id": null,
+        "address": null,
+        "target_path": path,
+        "result": result,
+        "timestamp": timestamp,
+        "summary": null,
+        "revision": null,
+    })
+}
+
+///
+/// 監査レコードを JSON Lines 形式で書き出す
+///
+fn write_audit_log(path: &Path, records: &[Value]) {
+    let mut text = String::new();
+    for record in records {
+        text.push_str(&record.to_string());
+        text.push('\n');
+    }
+    fs::write(path, text).expect("write audit log failed");
+}
+
+///
+/// JSON Lines 形式の標準出力を解析する
+///
+fn parse_json_lines(output: &Output) -> Vec<Value> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("parse json line failed"))
+        .collect()
+}
+
+///
+/// audit query を実行する
+///
+fn run_audit_query(
+    db_path: &Path,
+    assets_dir: &Path,
+    audit_dir: &Path,
+    args: &[&str],
+) -> Output {
+    let mut command_args = vec!["query"];
+    command_args.extend_from_slice(args);
+    run_audit_command(db_path, assets_dir, audit_dir, &command_args)
+}
+
+///
+/// audit サブコマンドを実行する
+///
+fn run_audit_command(
+    db_path: &Path,
+    assets_dir: &Path,
+    audit_dir: &Path,
+    args: &[&str],
+) -> Output {
+    let base_dir = db_path.parent().expect("db_path parent missing");
+
+    Command::new(test_binary_path())
+        .env("XDG_CONFIG_HOME", base_dir)
+        .env("XDG_DATA_HOME", base_dir)
+        .arg("--db-path")
+        .arg(db_path)
+        .arg("--assets-path")
+        .arg(assets_dir)
+        .arg("--fts-index")
+        .arg(fts_index_path(db_path))
+        .arg("--audit-log-dir")
+        .arg(audit_dir)
+        .arg("audit")
+        .args(args)
+        .output()
+        .expect("audit command failed")
+}
@@ -407,6 +407,53 @@ impl ServerGuard {
     ///
     #[allow(dead_code)]
     pub fn start(port: u16, db_path: &Path, assets_dir: &Path) -> Self {
+        Self::start_with_run_args(port, db_path, assets_dir, &[])
+    }
+
+    ///
+    /// runサブコマンドへ追加の引数を渡してAPIサーバを起動する
+    ///
+    /// # 引数
+    /// * `port` - 待受ポート
+    /// * `db_path` - DBパス
+    /// * `assets_dir` - アセットディレクトリ
+    /// * `run_args` - runサブコマンドへ追加する引数
+    ///
+    /// # 戻り値
+    /// ServerGuard
+    ///
+    #[allow(dead_code)]
+    pub fn start_with_run_args(
+        port: u16,
+        db_path: &Path,
+        assets_dir: &Path,
+        run_args: &[&str],
+    ) -> Self {
+        Self::start_with_config(port, db_path, assets_dir, run_args, "")
+    }
+
+    ///
+    /// 追加の設定ファイルを指定してAPIサーバを起動する
+    ///
+    /// # 引数
+    /// * `port` - 待受ポート
+    /// * `db_path` - DBパス
+    /// * `assets_dir` - アセットディレクトリ
+    /// * `run_args` - runサブコマンドへ追加する引数
+    /// * `extra_config` - `--config-path`で指定する設定ファイルの内容
+    ///   (空の場合は指定しない)
+    ///
+    /// # 戻り値
+    /// ServerGuard
+    ///
+    #[allow(dead_code)]
+    pub fn start_with_config(
+        port: u16,
+        db_path: &Path,
+        assets_dir: &Path,
+        run_args: &[&str],
+        extra_config: &str,
+    ) -> Self {
         /*
          * サーバ起動
          */
@@ -420,6 +467,13 @@ impl ServerGuard {
         fs::create_dir_all(&config_dir).expect("create config dir failed");
         let config_path = config_dir.join("config.toml");
         fs::write(&config_path, "[run]\nuse_tls = false\n").expect("write test config failed");
+        let mut config_args = Vec::new();
+        if !extra_config.is_empty() {
+            let extra_path = base_dir.join("server.config.toml");
+            fs::write(&extra_path, extra_config).expect("write extra config failed");
+            config_args.push("--config-path".into());
+            config_args.push(extra_path.into_os_string());
+        }
         let stdout_path = base_dir.join("server.stdout.log");
         let stdout = File::create(&stdout_path).expect("create server stdout failed");
         let stderr_path = base_dir.join("server.stderr.log");
@@ -433,7 +487,11 @@ impl ServerGuard {
             .arg(assets_dir)
             .arg("--fts-index")
             .arg(fts_index)
+            .arg("--audit-log-dir")
+            .arg(base_dir.join("audit"))
+            .args(&config_args)
             .arg("run")
+            .args(run_args)
             .arg(format!("127.0.0.1:{}", port))
             .stdin(Stdio::null())
             .stdout(Stdio::from(stdout))
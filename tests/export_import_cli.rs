@@ -11,14 +11,18 @@
 mod common;
 
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
 
 use reqwest::blocking::Client;
 use serde_json::Value;
 
 use common::*;
 
+/// 管理者属性を持つテスト用ユーザ名
+const ADMIN_USERNAME: &str = "admin_user";
+
 #[test]
 ///
 /// backup export/import の正常系を確認する。
@@ -261,6 +265,519 @@ fn backup_export_import_cli_preserves_read_only_user_attributes() {
     fs::remove_dir_all(dst_base_dir).expect("destination cleanup failed");
 }
 
+#[test]
+///
+/// 差分 backup export と連続 import を確認する。
+///
+/// # 注記
+/// 1) 元DBで完全 backup export を実行する
+/// 2) ページ追加後に `--since <export_id>` で差分 export を実行する
+/// 3) 新規DBへ完全アーカイブと差分アーカイブを指定順に import する
+/// 4) page list で両方のページが復元されることを確認する
+///
+fn incremental_backup_export_import_cli_applies_chain() {
+    /*
+     * 元データを準備し完全アーカイブを出力する
+     */
+    let (src_base_dir, src_db_path, src_assets_dir) = prepare_test_dirs();
+    run_add_user(&src_db_path, &src_assets_dir);
+    let markdown_path = src_base_dir.join("page.md");
+    fs::write(&markdown_path, "# incremental source\n")
+        .expect("write markdown failed");
+    run_page_add_as_user(
+        &src_db_path,
+        &src_assets_dir,
+        TEST_USERNAME,
+        &markdown_path,
+        "/incremental/first",
+    );
+
+    let full_path = src_base_dir.join("full.zip");
+    let export_output =
+        run_export(&src_db_path, &src_assets_dir, None, &full_path);
+    assert!(export_output.contains("incremental=false"));
+    let export_id = parse_export_id(&export_output);
+
+    /*
+     * 変更後に差分アーカイブを出力する
+     */
+    run_page_add_as_user(
+        &src_db_path,
+        &src_assets_dir,
+        TEST_USERNAME,
+        &markdown_path,
+        "/incremental/second",
+    );
+
+    let incremental_path = src_base_dir.join("incremental.zip");
+    let export_output = run_cli_command(
+        &src_db_path,
+        &src_assets_dir,
+        &[
+            "export",
+            "-y",
+            "--since",
+            &export_id,
+            &incremental_path.to_string_lossy(),
+        ],
+    );
+    assert!(export_output.contains("incremental=true"));
+    assert!(export_output.contains("pages=1"));
+
+    /*
+     * 新規DBへ連続 import する
+     */
+    let (dst_base_dir, dst_db_path, dst_assets_dir) = prepare_test_dirs();
+    let import_output = run_cli_command(
+        &dst_db_path,
+        &dst_assets_dir,
+        &[
+            "import",
+            "-y",
+            &full_path.to_string_lossy(),
+            &incremental_path.to_string_lossy(),
+        ],
+    );
+    assert!(import_output.contains("type=backup incremental=false"));
+    assert!(import_output.contains("type=backup incremental=true"));
+
+    let page_list = run_page_list(&dst_db_path, &dst_assets_dir);
+    assert!(page_list.contains("/incremental/first"));
+    assert!(page_list.contains("/incremental/second"));
+
+    fs::remove_dir_all(src_base_dir).expect("source cleanup failed");
+    fs::remove_dir_all(dst_base_dir).expect("destination cleanup failed");
+}
+
+#[test]
+///
+/// 稼働中サーバ経由の backup を確認する。
+///
+/// # 注記
+/// 1) サーバ稼働中に管理者ユーザで `backup --via-server` を実行する
+/// 2) 管理者以外のユーザは backup API を利用できないことを確認する
+/// 3) 管理者であっても `read` スコープの Bearer トークンでは backup API を
+///    利用できないことを確認する
+/// 4) 取得したアーカイブを新規DBへ import し復元結果を確認する
+///
+fn online_backup_via_server_cli_round_trip_works() {
+    /*
+     * 元データを準備しサーバを起動する
+     */
+    let (src_base_dir, src_db_path, src_assets_dir) = prepare_test_dirs();
+    let src_port = reserve_port();
+
+    run_add_user(&src_db_path, &src_assets_dir);
+    run_add_user_with_credentials_and_attributes(
+        &src_db_path,
+        &src_assets_dir,
+        ADMIN_USERNAME,
+        TEST_PASSWORD,
+        &["admin"],
+    );
+    let read_token = run_create_token_for_user(
+        &src_db_path,
+        &src_assets_dir,
+        "read",
+        ADMIN_USERNAME,
+    );
+    let src_server = ServerGuard::start(src_port, &src_db_path, &src_assets_dir);
+    let (src_api_url, client) =
+        wait_for_server_with_scheme(src_port, src_server.stderr_path());
+    let page_id = create_page(
+        &client,
+        &format!("{}/pages", src_api_url),
+        "/online/source",
+        "# online source\n",
+    );
+    upload_asset_by_page_id(
+        &client,
+        &src_api_url,
+        &page_id,
+        "online.txt",
+        "text/plain",
+        b"online-asset",
+    );
+
+    /*
+     * サーバ稼働中に backup を取得する
+     */
+    let archive_path = src_base_dir.join("online.zip");
+    let server_url = src_api_url.trim_end_matches("/api").to_string();
+    let backup_output = run_backup_via_server(
+        &src_db_path,
+        &src_assets_dir,
+        &server_url,
+        ADMIN_USERNAME,
+        &archive_path,
+    );
+    assert!(backup_output.contains("backup completed: via_server=true"));
+    assert!(parse_export_id(&backup_output) != "-");
+
+    let response = client
+        .post(format!("{}/admin/backup", src_api_url))
+        .basic_auth(TEST_USERNAME, Some(TEST_PASSWORD))
+        .send()
+        .expect("post admin backup failed");
+    assert_eq!(response.status().as_u16(), 403);
+
+    let response = client
+        .post(format!("{}/admin/backup", src_api_url))
+        .bearer_auth(&read_token)
+        .send()
+        .expect("post admin backup failed");
+    assert_eq!(response.status().as_u16(), 403);
+    drop(src_server);
+
+    /*
+     * 新規DBへ import する
+     */
+    let (dst_base_dir, dst_db_path, dst_assets_dir) = prepare_test_dirs();
+    let import_output = run_import(
+        &dst_db_path,
+        &dst_assets_dir,
+        None,
+        &archive_path,
+    );
+    assert!(import_output.contains("import completed: type=backup"));
+
+    let page_list = run_page_list(&dst_db_path, &dst_assets_dir);
+    assert!(page_list.contains("/online/source"));
+
+    let asset_list = run_asset_list(&dst_db_path, &dst_assets_dir);
+    assert!(asset_list.contains("online.txt"));
+
+    fs::remove_dir_all(src_base_dir).expect("source cleanup failed");
+    fs::remove_dir_all(dst_base_dir).expect("destination cleanup failed");
+}
+
+#[test]
+///
+/// 静的 HTML サイト export の出力内容を確認する。
+///
+/// # 注記
+/// 1) ページ間リンク、マクロ、アセット埋め込みを持つ元DBを作成する
+/// 2) CLI で `export --format html --subtree` を実行する
+/// 3) ページ、アセット、検索索引の出力と相対リンクを確認する
+///
+fn static_html_export_cli_renders_browsable_site() {
+    /*
+     * 元データを準備する
+     */
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&db_path, &assets_dir);
+    let server = ServerGuard::start(port, &db_path, &assets_dir);
+    let (api_url, client) = wait_for_server_with_scheme(port, server.stderr_path());
+    let pages_url = format!("{}/pages", api_url);
+    let page_id = create_page(
+        &client,
+        &pages_url,
+        "/site",
+        "# Site Top\n\n{{children}}\n\n![[asset:logo.png]]\n\n[[/outside]]\n",
+    );
+    create_page(
+        &client,
+        &pages_url,
+        "/site/guide",
+        "# Guide\n\n{{toc}}\n\n## Install Steps\n\n[top](..) [[/site]]\n",
+    );
+    create_page(&client, &pages_url, "/outside", "# Outside\n");
+    upload_asset_by_page_id(
+        &client,
+        &api_url,
+        &page_id,
+        "logo.png",
+        "image/png",
+        b"png-data",
+    );
+    drop(server);
+
+    /*
+     * html export を実行する
+     */
+    let site_dir = base_dir.join("site");
+    let output = run_cli_command(
+        &db_path,
+        &assets_dir,
+        &[
+            "export",
+            "--format",
+            "html",
+            "--subtree",
+            "/site",
+            site_dir.to_str().expect("site path"),
+        ],
+    );
+    assert!(output.contains(
+        "export completed: type=html dry_run=false pages=2 assets=1"
+    ));
+
+    /*
+     * 出力内容を確認する
+     */
+    let top = fs::read_to_string(site_dir.join("index.html"))
+        .expect("read top page failed");
+    assert!(top.contains("<title>Site Top</title>"));
+    assert!(top.contains("href=\"guide/index.html\""));
+    assert!(top.contains("src=\"_assets/"));
+    assert!(!top.contains("/outside"));
+
+    let guide = fs::read_to_string(site_dir.join("guide/index.html"))
+        .expect("read guide page failed");
+    assert!(guide.contains("<h2 id=\"install-steps\">"));
+    assert!(guide.contains("href=\"#install-steps\""));
+    assert!(guide.contains("href=\"../index.html\""));
+    assert!(guide.contains("href=\"../_site/style.css\""));
+
+    let asset_dirs: Vec<_> = fs::read_dir(site_dir.join("_assets"))
+        .expect("read asset dir failed")
+        .collect();
+    assert_eq!(asset_dirs.len(), 1);
+
+    let index = fs::read_to_string(site_dir.join("_site/search_index.js"))
+        .expect("read search index failed");
+    assert!(index.contains("\"path\":\"/site/guide\""));
+    assert!(index.contains("Install Steps"));
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+#[test]
+///
+/// Markdown ツリー export/import の往復を確認する。
+///
+/// # 注記
+/// 1) ページとアセットを持つ元DBを作成する
+/// 2) CLI で markdown export を実行し、出力ファイルを確認する
+/// 3) ファイルを編集して新規DBへ markdown import を実行する
+/// 4) 再実行時に変更の無いページとアセットが読み飛ばされることを確認する
+///
+fn markdown_tree_export_import_cli_round_trip_works() {
+    /*
+     * 元データを準備する
+     */
+    let (src_base_dir, src_db_path, src_assets_dir) = prepare_test_dirs();
+    let port = reserve_port();
+
+    run_add_user(&src_db_path, &src_assets_dir);
+    let server = ServerGuard::start(port, &src_db_path, &src_assets_dir);
+    let (api_url, client) = wait_for_server_with_scheme(port, server.stderr_path());
+    let pages_url = format!("{}/pages", api_url);
+    let page_id = create_page(
+        &client,
+        &pages_url,
+        "/notes",
+        "---\ntitle: Notes\n---\n# Notes\n",
+    );
+    create_page(&client, &pages_url, "/notes/guide", "# Guide\n");
+    upload_asset_by_page_id(
+        &client,
+        &api_url,
+        &page_id,
+        "logo.png",
+        "image/png",
+        b"png-data",
+    );
+    drop(server);
+
+    /*
+     * markdown export を実行する
+     */
+    let tree_dir = src_base_dir.join("tree");
+    let output = run_cli_command(
+        &src_db_path,
+        &src_assets_dir,
+        &[
+            "export",
+            "--format",
+            "markdown",
+            "--subtree",
+            "/notes",
+            tree_dir.to_str().expect("tree path"),
+        ],
+    );
+    assert!(output.contains(
+        "export completed: type=markdown dry_run=false pages=2 assets=1"
+    ));
+
+    let top = fs::read_to_string(tree_dir.join("_index.md"))
+        .expect("read top page failed");
+    assert_eq!(top, "---\ntitle: Notes\n---\n# Notes\n");
+    assert_eq!(
+        fs::read(tree_dir.join("_index.assets/logo.png"))
+            .expect("read asset failed"),
+        b"png-data"
+    );
+    assert!(tree_dir.join("guide.md").is_file());
+
+    /*
+     * 編集したツリーを新規DBへ取り込む
+     */
+    fs::write(tree_dir.join("guide.md"), "# Guide\n\nedited\n")
+        .expect("edit page failed");
+    fs::write(tree_dir.join("new.md"), "# New\n").expect("add page failed");
+
+    let (dst_base_dir, dst_db_path, dst_assets_dir) = prepare_test_dirs();
+    run_add_user(&dst_db_path, &dst_assets_dir);
+    let import_args = [
+        "import",
+        "--format",
+        "markdown",
+        "--user",
+        TEST_USERNAME,
+        "--migrate",
+        "/imported",
+        tree_dir.to_str().expect("tree path"),
+    ];
+    let output = run_cli_command(&dst_db_path, &dst_assets_dir, &import_args);
+    assert!(output.contains(
+        "import completed: type=markdown dry_run=false created=3 updated=0 unchanged=0 assets=1"
+    ));
+
+    let page_list = run_page_list(&dst_db_path, &dst_assets_dir);
+    assert!(page_list.contains("/imported/guide"));
+    assert!(page_list.contains("/imported/new"));
+
+    let asset_list = run_asset_list(&dst_db_path, &dst_assets_dir);
+    assert!(asset_list.contains("logo.png"));
+
+    /*
+     * 再実行では変更のあるページのみ更新する
+     */
+    fs::write(tree_dir.join("new.md"), "# New\n\nagain\n")
+        .expect("edit page failed");
+    let output = run_cli_command(&dst_db_path, &dst_assets_dir, &import_args);
+    assert!(output.contains(
+        "import completed: type=markdown dry_run=false created=0 updated=1 unchanged=2 assets=0"
+    ));
+
+    fs::remove_dir_all(src_base_dir).expect("source cleanup failed");
+    fs::remove_dir_all(dst_base_dir).expect("destination cleanup failed");
+}
+
+#[test]
+///
+/// 他の Wiki システムのデータの取り込みを確認する。
+///
+/// # 注記
+/// 1) MediaWiki の XML ダンプを作成し、編集者一覧を表示する
+/// 2) 未登録の編集者を `--user` へ対応付けて取り込む
+/// 3) DokuWiki のデータディレクトリをメディア付きで取り込む
+/// 4) page list / asset list で取り込み結果を確認する
+///
+fn foreign_wiki_import_cli_imports_history_and_media() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    run_add_user(&db_path, &assets_dir);
+
+    /*
+     * MediaWiki の XML ダンプを取り込む
+     */
+    let dump_path = base_dir.join("dump.xml");
+    fs::write(
+        &dump_path,
+        r#"<mediawiki xmlns="http://www.mediawiki.org/xml/export-0.11/">
+  <page>
+    <title>Main Page</title>
+    <ns>0</ns>
+    <revision>
+      <timestamp>2020-01-01T00:00:00Z</timestamp>
+      <contributor><ip>192.0.2.1</ip></contributor>
+      <text>first</text>
+    </revision>
+    <revision>
+      <timestamp>2020-01-02T00:00:00Z</timestamp>
+      <contributor><username>Bob</username></contributor>
+      <text>== Intro ==
+See [[Guide]].</text>
+    </revision>
+  </page>
+  <page>
+    <title>Guide</title>
+    <ns>0</ns>
+    <revision>
+      <timestamp>2020-01-03T00:00:00Z</timestamp>
+      <contributor><username>Bob</username></contributor>
+      <text>Back to [[Main Page]].</text>
+    </revision>
+  </page>
+</mediawiki>
+"#,
+    )
+    .expect("write dump failed");
+    let dump = dump_path.to_str().expect("dump path");
+
+    let output = run_cli_command(
+        &db_path,
+        &assets_dir,
+        &["import", "--format", "mediawiki", "--user-list", dump],
+    );
+    assert_eq!(output, "192.0.2.1\nBob\n");
+
+    let output = run_cli_command(
+        &db_path,
+        &assets_dir,
+        &[
+            "import",
+            "--format",
+            "mediawiki",
+            "--migrate",
+            "/mw",
+            "--user",
+            TEST_USERNAME,
+            dump,
+        ],
+    );
+    assert!(output.contains(
+        "import completed: type=mediawiki dry_run=false pages=2 revisions=3 assets=0"
+    ));
+
+    /*
+     * DokuWiki のデータディレクトリを取り込む
+     */
+    let data_dir = base_dir.join("dokuwiki");
+    fs::create_dir_all(data_dir.join("pages/docs")).expect("create pages failed");
+    fs::create_dir_all(data_dir.join("media/docs")).expect("create media failed");
+    fs::write(
+        data_dir.join("pages/docs/start.txt"),
+        "====== Docs ======\n{{docs:logo.png}} [[setup]]\n",
+    )
+    .expect("write page failed");
+    fs::write(data_dir.join("pages/docs/setup.txt"), "  * step\n")
+        .expect("write page failed");
+    fs::write(data_dir.join("media/docs/logo.png"), b"png-data")
+        .expect("write media failed");
+
+    let output = run_cli_command(
+        &db_path,
+        &assets_dir,
+        &[
+            "import",
+            "--format",
+            "dokuwiki",
+            "--migrate",
+            "/dw",
+            "--user",
+            TEST_USERNAME,
+            data_dir.to_str().expect("data path"),
+        ],
+    );
+    assert!(output.contains(
+        "import completed: type=dokuwiki dry_run=false pages=2 revisions=2 assets=1"
+    ));
+
+    let page_list = run_page_list(&db_path, &assets_dir);
+    assert!(page_list.contains("/mw/Main Page"));
+    assert!(page_list.contains("/mw/Guide"));
+    assert!(page_list.contains("/dw/docs/setup"));
+
+    let asset_list = run_asset_list(&db_path, &assets_dir);
+    assert!(asset_list.contains("logo.png"));
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
 ///
 /// ページを作成する。
 ///
@@ -558,6 +1075,82 @@ fn run_import(
     run_cli_command(db_path, assets_dir, &refs)
 }
 
+///
+/// export 完了出力からエクスポートIDを取り出す。
+///
+/// # 引数
+/// * `output` - export コマンドの標準出力
+///
+/// # 戻り値
+/// エクスポートIDを返す。
+///
+fn parse_export_id(output: &str) -> String {
+    output
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("export_id="))
+        .expect("export_id missing")
+        .to_string()
+}
+
+///
+/// サーバ経由の backup コマンドを実行する。
+///
+/// # 引数
+/// * `db_path` - DB パス
+/// * `assets_dir` - アセットディレクトリ
+/// * `server_url` - 接続先サーバURL
+/// * `user_name` - 認証ユーザ名
+/// * `archive_path` - 出力ZIPパス
+///
+/// # 戻り値
+/// 標準エラー出力文字列を返す。
+///
+fn run_backup_via_server(
+    db_path: &Path,
+    assets_dir: &Path,
+    server_url: &str,
+    user_name: &str,
+    archive_path: &Path,
+) -> String {
+    let exe = test_binary_path();
+    let base_dir = db_path.parent().expect("db_path parent missing");
+    let mut child = Command::new(exe)
+        .env("XDG_CONFIG_HOME", base_dir)
+        .env("XDG_DATA_HOME", base_dir)
+        .arg("--db-path")
+        .arg(db_path)
+        .arg("--assets-path")
+        .arg(assets_dir)
+        .arg("--fts-index")
+        .arg(fts_index_path(db_path))
+        .arg("backup")
+        .arg("--via-server")
+        .arg("--url")
+        .arg(server_url)
+        .arg("--user")
+        .arg(user_name)
+        .arg(archive_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("spawn backup failed");
+
+    {
+        let stdin = child.stdin.as_mut().expect("stdin missing");
+        writeln!(stdin, "{}", TEST_PASSWORD).expect("write password failed");
+    }
+
+    let output = child.wait_with_output().expect("wait backup failed");
+    assert!(
+        output.status.success(),
+        "backup failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8(output.stderr).expect("backup stderr decode failed")
+}
+
 ///
 /// 共通 CLI 実行処理。
 ///
@@ -0,0 +1,161 @@
+/*
+ * Light weight and small wiki system for local use
+ *
+ *  Copyright (C) 2025 Hiroshi KUWAGATA <kgt9221@gmail.com>
+ */
+
+mod common;
+
+use common::*;
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use git2::{Repository, Signature};
+
+#[test]
+///
+/// git-sync push でページ履歴がリポジトリへ複製され、リポジトリ側で
+/// 追加したコミットが git-sync pull で取り込まれることを確認する。
+///
+/// # 注記
+/// 1) テスト用ユーザとページを作成する
+/// 2) git-sync push でページファイルを含むコミットが作成されることを確認する
+/// 3) リポジトリ側でページを追加するコミットを作成する
+/// 4) git-sync pull でページが作成されることを確認する
+fn git_sync_cli_push_and_pull_round_trip() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    let repository = base_dir.join("wiki.git");
+
+    run_add_user(&db_path, &assets_dir);
+
+    let source_path = base_dir.join("docs.md");
+    fs::write(&source_path, "# docs\n").expect("write source failed");
+    let output = run_luwiki(
+        &db_path,
+        &assets_dir,
+        &[
+            "page",
+            "add",
+            "--user",
+            TEST_USERNAME,
+            source_path.to_str().expect("path must be utf-8"),
+            "/docs",
+        ],
+    );
+    assert!(output.status.success());
+
+    /*
+     * リポジトリへの複製
+     */
+    let output = run_git_sync(&db_path, &assets_dir, &repository, &["push"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("git-sync push completed: dry_run=false"));
+    assert!(!stdout.contains("commits=0"));
+
+    let repo = Repository::open_bare(&repository).expect("open repo failed");
+    let head = repo
+        .find_reference("refs/heads/main")
+        .expect("branch missing")
+        .peel_to_commit()
+        .expect("head commit missing");
+    assert_eq!(head.author().name(), Some(TEST_USERNAME));
+    assert!(head.tree().unwrap().get_path(Path::new("docs.md")).is_ok());
+
+    /*
+     * リポジトリ側でのページ追加
+     */
+    let blob = repo.blob(b"# from git\n").expect("write blob failed");
+    let mut builder = repo
+        .treebuilder(Some(&head.tree().unwrap()))
+        .expect("tree builder failed");
+    builder
+        .insert("from-git.md", blob, 0o100644)
+        .expect("insert failed");
+    let tree = repo
+        .find_tree(builder.write().expect("write tree failed"))
+        .unwrap();
+    let signature =
+        Signature::now(TEST_USERNAME, "test@example.invalid").unwrap();
+    repo.commit(
+        Some("refs/heads/main"),
+        &signature,
+        &signature,
+        "Add page from git",
+        &tree,
+        &[&head],
+    )
+    .expect("commit failed");
+
+    let output = run_git_sync(&db_path, &assets_dir, &repository, &["push"]);
+    assert!(!output.status.success());
+
+    let output = run_git_sync(&db_path, &assets_dir, &repository, &["pull"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("commits=1"));
+    assert!(stdout.contains("created=1"));
+
+    let output = run_luwiki(&db_path, &assets_dir, &["page", "list"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("/from-git"));
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
+///
+/// git-sync サブコマンドを実行する。
+///
+/// # 引数
+/// * `db_path` - DBファイルのパス
+/// * `assets_dir` - アセットディレクトリのパス
+/// * `repository` - 同期リポジトリのパス
+/// * `args` - サブコマンド以降の引数
+///
+/// # 戻り値
+/// 実行結果を返す。
+fn run_git_sync(
+    db_path: &Path,
+    assets_dir: &Path,
+    repository: &Path,
+    args: &[&str],
+) -> Output {
+    let repository = repository.to_str().expect("path must be utf-8");
+    let mut all_args = vec!["git-sync"];
+    all_args.extend_from_slice(args);
+    all_args.extend_from_slice(&["--repository", repository]);
+    run_luwiki(db_path, assets_dir, &all_args)
+}
+
+///
+/// luwiki を実行する。
+///
+/// # 引数
+/// * `db_path` - DBファイルのパス
+/// * `assets_dir` - アセットディレクトリのパス
+/// * `args` - グローバルオプション以降の引数
+///
+/// # 戻り値
+/// 実行結果を返す。
+fn run_luwiki(db_path: &Path, assets_dir: &Path, args: &[&str]) -> Output {
+    let exe = test_binary_path();
+    let base_dir = db_path.parent().expect("db_path parent missing");
+    Command::new(exe)
+        .env("XDG_CONFIG_HOME", base_dir)
+        .env("XDG_DATA_HOME", base_dir)
+        .arg("--db-path")
+        .arg(db_path)
+        .arg("--assets-path")
+        .arg(assets_dir)
+        .arg("--fts-index")
+        .arg(fts_index_path(db_path))
+        .args(args)
+        .output()
+        .expect("luwiki failed")
+}
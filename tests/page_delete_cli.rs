@@ -27,7 +27,7 @@ use serde_json;
 /// # 注記
 /// 1) テスト用ユーザを作成する
 /// 2) page add でページを作成する
-/// 3) page delete を実行する
+/// 3) page delete を実行し、監査ログへ記録されることを確認する
 /// 4) 再度 page delete を実行しエラーになることを確認する
 fn page_delete_cli_soft_delete_fails_on_second_try() {
     let (base_dir, db_path, assets_dir) = prepare_test_dirs();
@@ -46,6 +46,23 @@ fn page_delete_cli_soft_delete_fails_on_second_try() {
 
     run_page_delete(&db_path, &assets_dir, false, false, false, "/soft-delete");
 
+    let audit_log = fs::read_to_string(
+        db_path
+            .parent()
+            .expect("db_path parent missing")
+            .join("audit")
+            .join("audit.current.jsonl"),
+    )
+    .expect("read audit log failed");
+    let record: serde_json::Value = serde_json::from_str(
+        audit_log.lines().last().expect("audit record missing"),
+    )
+    .expect("parse audit record failed");
+    assert_eq!(record["operation"], "delete");
+    assert_eq!(record["target_path"], "/soft-delete");
+    assert_eq!(record["result"], "success");
+    assert!(record["user_id"].is_null());
+
     run_page_delete_expect_fail(&db_path, &assets_dir, false, false, false, "/soft-delete");
 
     fs::remove_dir_all(base_dir).expect("cleanup failed");
@@ -403,6 +420,8 @@ fn run_page_delete(
         .arg(assets_dir)
         .arg("--fts-index")
         .arg(fts_index_path(db_path))
+        .arg("--audit-log-dir")
+        .arg(base_dir.join("audit"))
         .arg("page")
         .arg("delete");
 
@@ -23,6 +23,7 @@ use common::{
     prepare_test_dirs,
     run_add_user,
     run_add_user_with_credentials,
+    run_add_user_with_credentials_and_attributes,
     test_binary_path,
     wait_for_server_with_scheme,
 };
@@ -832,6 +833,89 @@ fn token_revoke_rejects_mutually_exclusive_target_options() {
     fs::remove_dir_all(base_dir).expect("cleanup failed");
 }
 
+#[test]
+fn token_rotate_issues_new_token_and_records_audit_log() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    run_add_user(&db_path, &assets_dir);
+
+    let token_id = create_token_and_get_id(&db_path, &assets_dir, TEST_USERNAME);
+    let audit_dir = base_dir.join("audit");
+    let output = build_base_command(&db_path, &assets_dir)
+        .arg("--audit-log-dir")
+        .arg(&audit_dir)
+        .arg("token")
+        .arg("rotate")
+        .arg("--grace")
+        .arg("1h")
+        .arg(&token_id)
+        .output()
+        .expect("token rotate failed");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).expect("stdout decode failed");
+    assert_eq!(find_label_value(&stdout, "TOKEN ID:     "), token_id);
+    assert_cli_timestamp(find_label_value(&stdout, "    rotate: "));
+    assert_cli_timestamp(find_label_value(&stdout, "    grace:  "));
+    let token_value = stdout
+        .split("TOKEN VALUE:\n")
+        .nth(1)
+        .map(str::trim)
+        .expect("token value missing");
+    assert!(!token_value.is_empty());
+
+    /*
+     * トークンIDは維持され、ローテーション日時が記録される
+     */
+    let info_output = build_base_command(&db_path, &assets_dir)
+        .arg("token")
+        .arg("info")
+        .arg(&token_id)
+        .output()
+        .expect("token info failed");
+    assert!(info_output.status.success());
+    let stdout = String::from_utf8(info_output.stdout).expect("stdout decode failed");
+    assert_cli_timestamp(find_label_value(&stdout, "    rotate: "));
+    assert_eq!(list_token_rows(&db_path, &assets_dir, &[]).len(), 1);
+
+    /*
+     * 監査ログへの記録
+     */
+    let audit_log = fs::read_to_string(audit_dir.join("audit.current.jsonl"))
+    .expect("read audit log failed");
+    let record = audit_log
+        .lines()
+        .find(|line| line.contains("\"token_rotate\""))
+        .expect("token_rotate record missing");
+    assert!(record.contains(&format!("token_id={}", token_id)));
+    assert!(record.contains("previous="));
+    assert!(record.contains("current="));
+
+    /*
+     * 失効済みトークンはローテーションできない
+     */
+    let output = build_base_command(&db_path, &assets_dir)
+        .arg("token")
+        .arg("revoke")
+        .arg("--yes")
+        .arg(&token_id)
+        .output()
+        .expect("token revoke failed");
+    assert!(output.status.success());
+
+    let output = build_base_command(&db_path, &assets_dir)
+        .arg("token")
+        .arg("rotate")
+        .arg(&token_id)
+        .output()
+        .expect("token rotate failed");
+    assert_cli_error(
+        output,
+        &format!("error: token already revoked: {}", token_id),
+    );
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
 #[test]
 fn token_purge_by_token_id_removes_only_target_token() {
     let (base_dir, db_path, assets_dir) = prepare_test_dirs();
@@ -1465,6 +1549,46 @@ fn user_edit_removing_no_basic_auth_requires_password() {
     fs::remove_dir_all(base_dir).expect("cleanup failed");
 }
 
+#[test]
+fn user_edit_rejects_password_violating_policy() {
+    let (base_dir, db_path, assets_dir) = prepare_test_dirs();
+    run_add_user(&db_path, &assets_dir);
+
+    /*
+     * 強度要件は設定ファイルで有効化した場合のみ適用される
+     */
+    let config_path = base_dir.join("auth.config.toml");
+    fs::write(&config_path, "[auth]\npassword_min_length = 12\n")
+        .expect("write test config failed");
+    let config_arg = config_path.to_str().expect("path must be utf-8");
+
+    let output = run_user_edit_with_input(
+        &db_path,
+        &assets_dir,
+        &["--password", TEST_USERNAME],
+        Some("elevenchars\nelevenchars\n"),
+    );
+    assert!(output.status.success());
+
+    let output = build_base_command(&db_path, &assets_dir)
+        .args(["--config-path", config_arg, "user", "edit", "--password"])
+        .arg(TEST_USERNAME)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            use std::io::Write;
+            let stdin = child.stdin.as_mut().expect("stdin missing");
+            write!(stdin, "elevenchars\nelevenchars\n")?;
+            child.wait_with_output()
+        })
+        .expect("user edit failed");
+    assert_cli_error(output, "error: password must be at least 12 characters");
+
+    fs::remove_dir_all(base_dir).expect("cleanup failed");
+}
+
 #[test]
 fn user_info_rejects_missing_user() {
     let (base_dir, db_path, assets_dir) = prepare_test_dirs();